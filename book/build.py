@@ -148,6 +148,10 @@ list_of_functions(
                 "title": "Transcendental functions",
                 "modules": ["math::transcendental"],
             },
+            {
+                "title": "Special functions",
+                "modules": ["math::special_functions"],
+            },
             {
                 "title": "Trigonometry",
                 "modules": ["math::trigonometry"],
@@ -164,12 +168,23 @@ list_of_functions(
                 "title": "Number theory",
                 "modules": ["math::number_theory"],
             },
+            {
+                "title": "Combinatorics",
+                "modules": ["math::combinatorics"],
+            },
+            {
+                "title": "Percentages",
+                "modules": ["math::percentages"],
+            },
             {
                 "title": "Numerical methods",
                 "modules": [
                     "numerics::diff",
                     "numerics::solve",
                     "numerics::fixed_point",
+                    "numerics::integrate",
+                    "numerics::ode",
+                    "numerics::monte_carlo",
                 ],
             },
             {
@@ -200,6 +215,18 @@ list_of_functions(
     },
 )
 
+list_of_functions(
+    "dicts",
+    {
+        "title": "Dictionary-related functions",
+        "sections": [
+            {
+                "modules": ["core::dicts"],
+            },
+        ],
+    },
+)
+
 list_of_functions(
     "strings",
     {
@@ -219,7 +246,7 @@ list_of_functions(
         "introduction": "See [this page](./date-and-time.md) for a general introduction to date and time handling in Numbat.",
         "sections": [
             {
-                "modules": ["datetime::functions", "datetime::human"],
+                "modules": ["datetime::functions", "datetime::human", "datetime::durations"],
             },
         ],
     },
@@ -243,22 +270,106 @@ list_of_functions(
                 "title": "Quantities",
                 "modules": ["core::quantities"],
             },
+            {
+                "title": "Physical constants",
+                "modules": ["physics::constants"],
+            },
+            {
+                "title": "Kinematics",
+                "modules": ["physics::kinematics"],
+            },
+            {
+                "title": "Optics",
+                "modules": ["physics::optics"],
+            },
+            {
+                "title": "Thermodynamics",
+                "modules": ["physics::thermodynamics"],
+            },
+            {
+                "title": "Natural units",
+                "modules": ["physics::natural_units"],
+            },
             {
                 "title": "Chemical elements",
                 "modules": ["chemistry::elements"],
             },
+            {
+                "title": "Radioactive decay",
+                "modules": ["chemistry::isotopes"],
+            },
+            {
+                "title": "Astronomy",
+                "modules": ["extra::astronomy"],
+            },
+            {
+                "title": "Geodesy",
+                "modules": ["extra::geodesy"],
+            },
+            {
+                "title": "Finance",
+                "modules": ["extra::finance"],
+            },
+            {
+                "title": "Cooking",
+                "modules": ["extra::cooking"],
+            },
+            {
+                "title": "Sizes",
+                "modules": ["extra::sizes"],
+            },
+            {
+                "title": "Weather",
+                "modules": ["extra::weather"],
+            },
+            {
+                "title": "Files",
+                "modules": ["core::files"],
+            },
+            {
+                "title": "JSON",
+                "modules": ["core::json"],
+            },
+            {
+                "title": "Environment",
+                "modules": ["core::environment"],
+            },
+            {
+                "title": "Tables",
+                "modules": ["core::tables"],
+            },
+            {
+                "title": "Plotting",
+                "modules": ["plot::text"],
+            },
             {
                 "title": "Mixed unit conversion",
                 "modules": ["units::mixed"],
             },
+            {
+                "title": "Pace",
+                "modules": ["units::rates"],
+            },
+            {
+                "title": "Data size conversion",
+                "modules": ["units::data_size"],
+            },
             {
                 "title": "Temperature conversion",
                 "modules": ["physics::temperature_conversion"],
             },
+            {
+                "title": "Offset and level conversion",
+                "modules": ["physics::offset_conversion"],
+            },
             {
                 "title": "Color format conversion",
                 "modules": ["extra::color"],
             },
+            {
+                "title": "Photometry",
+                "modules": ["extra::photometry"],
+            },
         ],
     },
 )
@@ -0,0 +1,56 @@
+//! Access to the system clipboard for the `copy` and `paste` commands.
+
+pub use system::{copy, paste};
+
+#[cfg(feature = "clipboard")]
+mod system {
+    use std::cell::RefCell;
+
+    use anyhow::{Context, Result};
+
+    thread_local! {
+        // On Linux, the clipboard content is served by the process that owns it,
+        // so the clipboard needs to stay alive for the whole session.
+        static CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+    }
+
+    fn with_clipboard<T>(f: impl FnOnce(&mut arboard::Clipboard) -> Result<T>) -> Result<T> {
+        CLIPBOARD.with(|clipboard| {
+            let mut clipboard = clipboard.borrow_mut();
+            if clipboard.is_none() {
+                *clipboard =
+                    Some(arboard::Clipboard::new().context("Could not access the clipboard")?);
+            }
+            f(clipboard.as_mut().unwrap())
+        })
+    }
+
+    pub fn copy(text: &str) -> Result<()> {
+        with_clipboard(|clipboard| {
+            clipboard
+                .set_text(text)
+                .context("Could not copy to the clipboard")
+        })
+    }
+
+    pub fn paste() -> Result<String> {
+        with_clipboard(|clipboard| {
+            clipboard
+                .get_text()
+                .context("Could not read text from the clipboard")
+        })
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+mod system {
+    use anyhow::{bail, Result};
+
+    pub fn copy(_text: &str) -> Result<()> {
+        bail!("Numbat was built without clipboard support (feature 'clipboard')")
+    }
+
+    pub fn paste() -> Result<String> {
+        bail!("Numbat was built without clipboard support (feature 'clipboard')")
+    }
+}
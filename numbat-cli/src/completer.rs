@@ -3,6 +3,8 @@ use std::sync::{Arc, Mutex};
 use numbat::{unicode_input::UNICODE_INPUT, Context};
 use rustyline::completion::{extract_word, Completer, Pair};
 
+const CONVERSION_OPERATORS: [&str; 5] = ["|>", "->", "→", "➞", "to"];
+
 pub struct NumbatCompleter {
     pub context: Arc<Mutex<Context>>,
     pub modules: Vec<String>,
@@ -116,7 +118,7 @@ impl Completer for NumbatCompleter {
 
         // don't add an opening paren if we're completing after a reverse function call
         // or when completing conversion functions
-        let add_paren = !["|>", "->", "→", "➞", "to"]
+        let add_paren = !CONVERSION_OPERATORS
             .iter()
             .any(|&s| line[..pos].contains(s));
 
@@ -137,3 +139,28 @@ impl Completer for NumbatCompleter {
         ))
     }
 }
+
+/// Completions for the last word of a partial expression, e.g. `3 ft -> kilom`,
+/// for the shell completion of `numbat -e`. Shells replace the whole argument,
+/// so every candidate is the full expression (including an opening quote).
+pub fn complete_expression(context: &Context, expression: &str) -> Vec<String> {
+    let (quote, expression) = match expression.strip_prefix(['\'', '"']) {
+        Some(rest) => (&expression[..1], rest),
+        None => ("", expression),
+    };
+
+    let pos_word = expression
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_'))
+        .map(|(i, c)| i + c.len_utf8())
+        .unwrap_or(0);
+    let (head, word_part) = expression.split_at(pos_word);
+
+    let add_paren = !CONVERSION_OPERATORS.iter().any(|&s| head.contains(s));
+
+    context
+        .get_completions_for(word_part, add_paren)
+        .map(|candidate| format!("{quote}{head}{candidate}"))
+        .collect()
+}
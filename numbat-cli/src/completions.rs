@@ -0,0 +1,66 @@
+//! Completion scripts for shells. The options and subcommands are completed
+//! statically (generated by `clap_complete`). Expressions after `-e` are
+//! completed dynamically by calling `numbat complete-expression`, such that
+//! unit names (including prefixed ones like `kilometer`) come from the prelude
+//! and the user's init file.
+
+use std::io::Write;
+
+use clap_complete::generate;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+const BASH_DYNAMIC: &str = r#"
+_numbat_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    if [[ "$prev" == "-e" || "$prev" == "--expression" ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(numbat complete-expression -- "$cur" 2>/dev/null))
+        compopt -o nospace 2>/dev/null
+        return 0
+    fi
+    _numbat "$@"
+}
+
+complete -F _numbat_dynamic -o bashdefault -o default numbat
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_numbat_dynamic() {
+    if [[ "${words[CURRENT-1]}" == (-e|--expression) ]]; then
+        local -a candidates
+        candidates=(${(f)"$(numbat complete-expression -- "$PREFIX" 2>/dev/null)"})
+        compadd -Q -U -S '' -- $candidates
+    else
+        _numbat "$@"
+    fi
+}
+
+compdef _numbat_dynamic numbat
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c numbat -s e -l expression -r -f -a '(numbat complete-expression -- (commandline -ct) 2>/dev/null)'
+"#;
+
+/// Write the completion script for the given shell.
+pub fn write_completion_script(
+    shell: Shell,
+    command: &mut clap::Command,
+    output: &mut impl Write,
+) -> std::io::Result<()> {
+    let (generator, dynamic) = match shell {
+        Shell::Bash => (clap_complete::Shell::Bash, BASH_DYNAMIC),
+        Shell::Zsh => (clap_complete::Shell::Zsh, ZSH_DYNAMIC),
+        Shell::Fish => (clap_complete::Shell::Fish, FISH_DYNAMIC),
+    };
+
+    generate(generator, command, "numbat", output);
+    output.write_all(dynamic.as_bytes())
+}
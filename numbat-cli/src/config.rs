@@ -18,6 +18,33 @@ pub enum PrettyPrintMode {
     Auto,
 }
 
+/// What to do if a definition uses the name of an existing unit
+#[derive(Serialize, Deserialize, PartialEq, Eq, Default, Debug, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShadowingMode {
+    /// Reject the definition
+    #[default]
+    Error,
+
+    /// Shadow the unit, but print a warning
+    Warn,
+
+    /// Silently shadow the unit
+    Allow,
+}
+
+/// How very large and very small numbers are printed
+#[derive(Serialize, Deserialize, PartialEq, Eq, Default, Debug, Clone, Copy, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScientificNotationMode {
+    /// Like `1.602e-19`
+    #[default]
+    E,
+
+    /// Like `1.602×10⁻¹⁹`
+    Unicode,
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum ExchangeRateFetchingPolicy {
@@ -55,6 +82,16 @@ pub struct Config {
     pub prompt: String,
     pub pretty_print: PrettyPrintMode,
     pub color: ColorMode,
+    pub shadowing: ShadowingMode,
+    pub scientific_notation: ScientificNotationMode,
+    pub long_unit_names: bool,
+    pub spoken_output: bool,
+    pub strict_multiplication: bool,
+    pub compatibility_syntax: bool,
+    pub simplify_derived_units: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<usize>,
 
     #[serde(skip)]
     pub enter_repl: bool,
@@ -74,6 +111,14 @@ impl Default for Config {
             intro_banner: IntroBanner::default(),
             pretty_print: PrettyPrintMode::Auto,
             color: ColorMode::default(),
+            shadowing: ShadowingMode::default(),
+            scientific_notation: ScientificNotationMode::default(),
+            long_unit_names: false,
+            spoken_output: false,
+            strict_multiplication: false,
+            compatibility_syntax: false,
+            simplify_derived_units: true,
+            memory_limit: None,
             load_prelude: true,
             load_user_init: true,
             exchange_rates: Default::default(),
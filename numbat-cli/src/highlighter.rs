@@ -5,18 +5,79 @@ use rustyline::{highlight::Highlighter, CompletionType};
 
 use std::{
     borrow::Cow,
+    ops::Range,
     sync::{Arc, Mutex},
 };
 
 use crate::ansi_formatter::ansi_format;
 
+/// Byte ranges of the syntax errors in the input, along with their messages.
+/// Errors at the very end of the input are ignored, as they usually just mean
+/// that the input is not complete yet (like `2 +`).
+pub fn syntax_errors(context: &Context, line: &str) -> Vec<(Range<usize>, String)> {
+    // Commands that are not valid Numbat code
+    if matches!(line.trim(), "?" | ":stats") || line.starts_with("info ") {
+        return vec![];
+    }
+
+    // Check the expression of `:time <expression>`, with the command replaced by
+    // spaces such that the error positions still refer to the full line
+    let line = match line.strip_prefix(":time ") {
+        Some(expression) => {
+            Cow::Owned(format!("{:width$}{expression}", "", width = ":time ".len()))
+        }
+        None => Cow::Borrowed(line),
+    };
+
+    let input_end = line.trim_end().len();
+
+    context
+        .check_syntax(&line)
+        .into_iter()
+        .filter_map(|error| {
+            let start = error.span.start.byte as usize;
+            if start >= input_end {
+                return None;
+            }
+
+            let first_char = line.get(start..)?.chars().next()?;
+            let min_end = start + first_char.len_utf8();
+            let end = (error.span.end.byte as usize).clamp(min_end, input_end);
+            line.is_char_boundary(end)
+                .then(|| (start..end, error.to_string()))
+        })
+        .collect()
+}
+
 pub struct NumbatHighlighter {
     pub context: Arc<Mutex<Context>>,
 }
 
 impl Highlighter for NumbatHighlighter {
+    /// Underline syntax errors while the input is being typed
     fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
-        Cow::Borrowed(line)
+        let errors = syntax_errors(&self.context.lock().unwrap(), line);
+        if errors.is_empty() {
+            return Cow::Borrowed(line);
+        }
+
+        let mut highlighted = String::new();
+        let mut last_end = 0;
+        for (range, _) in errors {
+            if range.start < last_end {
+                continue;
+            }
+            highlighted.push_str(&line[last_end..range.start]);
+            highlighted.push_str(&line[range.clone()].red().underline().to_string());
+            last_end = range.end;
+        }
+        highlighted.push_str(&line[last_end..]);
+
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
     }
 
     fn highlight_prompt<'b, 's: 'b, 'p: 'b>(
@@ -28,7 +89,7 @@ impl Highlighter for NumbatHighlighter {
     }
 
     fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
-        Cow::Borrowed(hint)
+        Cow::Owned(hint.dimmed().to_string())
     }
 
     fn highlight_candidate<'c>(
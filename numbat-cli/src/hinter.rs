@@ -0,0 +1,40 @@
+use std::sync::{Arc, Mutex};
+
+use numbat::Context;
+use rustyline::hint::{Hint, Hinter};
+
+use crate::highlighter::syntax_errors;
+
+/// A hint that is only displayed, and not inserted when the cursor is moved
+/// to the right.
+pub struct ErrorHint(String);
+
+impl Hint for ErrorHint {
+    fn display(&self) -> &str {
+        &self.0
+    }
+
+    fn completion(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Shows the first syntax error of the input next to the cursor, as long as
+/// the cursor is at the end of the input.
+pub struct NumbatHinter {
+    pub context: Arc<Mutex<Context>>,
+}
+
+impl Hinter for NumbatHinter {
+    type Hint = ErrorHint;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &rustyline::Context<'_>) -> Option<ErrorHint> {
+        if pos < line.len() {
+            return None;
+        }
+
+        let errors = syntax_errors(&self.context.lock().unwrap(), line);
+        let (_, message) = errors.first()?;
+        Some(ErrorHint(format!("  ← {message}")))
+    }
+}
@@ -1,52 +1,115 @@
 mod ansi_formatter;
+mod clipboard;
 mod completer;
+mod completions;
 mod config;
 mod highlighter;
+mod hinter;
+mod packages;
+mod project;
+mod trust;
 
 use ansi_formatter::ansi_format;
 use colored::control::SHOULD_COLORIZE;
+use colored::Colorize;
 use completer::NumbatCompleter;
-use config::{ColorMode, Config, ExchangeRateFetchingPolicy, IntroBanner, PrettyPrintMode};
+use config::{
+    ColorMode, Config, ExchangeRateFetchingPolicy, IntroBanner, PrettyPrintMode,
+    ScientificNotationMode, ShadowingMode,
+};
 use highlighter::NumbatHighlighter;
+use hinter::NumbatHinter;
+use project::Project;
 
 use itertools::Itertools;
 use numbat::diagnostic::ErrorDiagnostic;
 use numbat::help::help_markup;
+use numbat::json::json_string;
 use numbat::markup as m;
 use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
 use numbat::pretty_print::PrettyPrint;
 use numbat::resolver::CodeSource;
+use numbat::value::Value;
 use numbat::{Context, NumbatError};
-use numbat::{InterpreterSettings, NameResolutionError};
+use numbat::{
+    InterpreterResult, InterpreterSettings, NameResolutionError, RuntimeError, ScientificNotation,
+    ShadowingPolicy, Statement,
+};
 
 use anyhow::{bail, Context as AnyhowContext, Result};
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use rustyline::config::Configurer;
 use rustyline::{
     error::ReadlineError, history::DefaultHistory, Completer, Editor, Helper, Hinter, Validator,
 };
 use rustyline::{EventHandler, Highlighter, KeyCode, KeyEvent, Modifiers};
 
+use std::collections::HashSet;
 use std::io::IsTerminal;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{fs, thread};
 
-#[derive(Debug, PartialEq, Eq)]
+/// How the interpreter stopped. The errors have distinct exit codes, such that
+/// scripts can tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExitStatus {
     Success,
-    Error,
+    /// Syntax errors and errors from resolving names and modules
+    ParseError,
+    TypeError,
+    RuntimeError,
+    /// A failed `assert` or `assert_eq`
+    AssertionFailed,
+}
+
+impl ExitStatus {
+    /// The exit code of the process. Exit code 1 is used for all other errors
+    /// (like a missing source file), and 2 for invalid command-line arguments.
+    fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::ParseError => 3,
+            ExitStatus::TypeError => 4,
+            ExitStatus::RuntimeError => 5,
+            ExitStatus::AssertionFailed => 6,
+        }
+    }
+}
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitStatus::Success => write!(f, "Interpreter stopped"),
+            ExitStatus::ParseError => write!(f, "Interpreter stopped due to a parse error"),
+            ExitStatus::TypeError => write!(f, "Interpreter stopped due to a type error"),
+            ExitStatus::RuntimeError => write!(f, "Interpreter stopped due to a runtime error"),
+            ExitStatus::AssertionFailed => {
+                write!(f, "Interpreter stopped due to a failed assertion")
+            }
+        }
+    }
 }
 
+impl std::error::Error for ExitStatus {}
+
 type ControlFlow = std::ops::ControlFlow<ExitStatus>;
 
 #[derive(Parser, Debug)]
 #[command(version, about, name("numbat"), max_term_width = 90)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to source file with Numbat code. If none is given, an interactive
     /// session is started.
     file: Option<PathBuf>,
 
+    /// Arguments for the script, which it can access via `args()`.
+    #[arg(requires = "file", trailing_var_arg = true)]
+    arguments: Vec<String>,
+
     /// Evaluate a single expression. Can be specified multiple times to evaluate several expressions in sequence.
     #[arg(
         short,
@@ -72,6 +135,18 @@ struct Args {
     #[arg(long, hide_short_help = true)]
     no_init: bool,
 
+    /// Do not allow functions like `read_csv` to access the file system.
+    #[arg(long, hide_short_help = true)]
+    no_file_access: bool,
+
+    /// Do not allow functions like `fetch_json` to access the network.
+    #[arg(long, hide_short_help = true)]
+    no_network_access: bool,
+
+    /// Do not warn about the use of deprecated units and functions.
+    #[arg(long, hide_short_help = true)]
+    no_deprecation_warnings: bool,
+
     /// Whether or not to pretty-print every input expression.
     #[arg(long, value_name = "WHEN")]
     pretty_print: Option<PrettyPrintMode>,
@@ -80,6 +155,44 @@ struct Args {
     #[arg(long, value_name = "WHEN")]
     color: Option<ColorMode>,
 
+    /// What to do if a definition uses the name of an existing unit.
+    #[arg(long, value_name = "POLICY", hide_short_help = true)]
+    shadowing: Option<ShadowingMode>,
+
+    /// Reject implicit multiplications with parentheses, like '2(3 + 4)'.
+    #[arg(long, hide_short_help = true)]
+    strict_multiplication: bool,
+
+    /// Accept common syntax from qalc and GNU units, like '5 ft 3 in' or '|x|'.
+    #[arg(long, hide_short_help = true)]
+    compatibility_syntax: bool,
+
+    /// Do not simplify results to derived units, like 'N' for 'kg·m/s²'.
+    #[arg(long, hide_short_help = true)]
+    no_derived_units: bool,
+
+    /// How very large and very small numbers are printed.
+    #[arg(long, value_name = "NOTATION", hide_short_help = true)]
+    scientific_notation: Option<ScientificNotationMode>,
+
+    /// Abort evaluations that allocate more than this many mebibytes for values in total.
+    #[arg(long, value_name = "MIB", hide_short_help = true)]
+    memory_limit: Option<usize>,
+
+    /// Write units in long form, like '3 kilometres per hour' instead of '3 km/h'.
+    #[arg(long, hide_short_help = true)]
+    long_unit_names: bool,
+
+    /// Write results as plain English, like 'three kilometres per hour', for
+    /// screen readers and voice assistants.
+    #[arg(long, hide_short_help = true)]
+    speak: bool,
+
+    /// Only print the value of each result, without its unit and without any
+    /// formatting, e.g. '3000' for '3 km -> m'. Output of 'print' is suppressed.
+    #[arg(short, long, visible_alias = "raw")]
+    quiet: bool,
+
     /// What kind of intro banner to show (if any).
     #[arg(long, value_name = "MODE")]
     intro_banner: Option<IntroBanner>,
@@ -88,9 +201,96 @@ struct Args {
     #[arg(long, hide_short_help = true)]
     generate_config: bool,
 
+    /// Read expressions line by line from standard input and print one result per
+    /// line. Use `--filter=ndjson` to emit one JSON object per line instead.
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "plain",
+        conflicts_with = "inspect_interactively"
+    )]
+    filter: Option<FilterFormat>,
+
+    /// Re-run the given file whenever it changes.
+    #[arg(
+        long,
+        requires = "file",
+        conflicts_with_all = ["inspect_interactively", "filter"]
+    )]
+    watch: bool,
+
     /// Turn on debug mode and print disassembler output (hidden, mainly for development)
     #[arg(long, short, hide = true)]
     debug: bool,
+
+    /// Print the compiled bytecode of every input (not including the prelude) to stderr.
+    #[arg(long, hide_short_help = true)]
+    debug_bytecode: bool,
+
+    /// Do not inline small functions at their call sites, such that every call
+    /// shows up in the bytecode listing.
+    #[arg(long, hide_short_help = true)]
+    no_inlining: bool,
+
+    /// Print the wall-clock time and the number of executed VM instructions of
+    /// every evaluation to stderr.
+    #[arg(long, hide_short_help = true)]
+    time: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Run the main file of the project that is described by the `numbat.toml`
+    /// file in the current folder (or any of its parent folders).
+    Run {
+        /// Run this file instead of the `main` file from the project manifest
+        file: Option<PathBuf>,
+    },
+
+    /// Download the module packages from the `[dependencies]` of the project
+    /// manifest and record their versions in `numbat.lock`.
+    Install,
+
+    /// Allow the manifest and the initialization file of the project to be used.
+    /// This needs to be repeated whenever one of the files changes.
+    Trust,
+
+    /// Check a Numbat program for errors without running it. Also reports unused
+    /// functions, variables and units, and branches that are never taken.
+    Check {
+        /// The program to check
+        file: PathBuf,
+    },
+
+    /// Print a JSON list of all units with their aliases, dimensions and
+    /// conversion factors to base units.
+    ExportUnits,
+
+    /// Convert a GNU units definitions file to Numbat code. Units that are already
+    /// defined in Numbat are skipped. Unsupported definitions are reported on stderr.
+    ImportGnuUnits {
+        /// The definitions file, e.g. `/usr/share/units/definitions.units`
+        file: PathBuf,
+    },
+
+    /// Print a completion script for the given shell. Expressions after `-e` are
+    /// completed with the names of units, functions and variables.
+    Completions { shell: completions::Shell },
+
+    /// Print completions for a partial expression, one per line. This is used
+    /// by the completion scripts.
+    #[command(hide = true)]
+    CompleteExpression { expression: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum FilterFormat {
+    /// One plain-text result per line
+    Plain,
+    /// One JSON object per line
+    Ndjson,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -100,9 +300,9 @@ enum ExecutionMode {
 }
 
 impl ExecutionMode {
-    fn exit_status_in_case_of_error(&self) -> ControlFlow {
+    fn exit_status_in_case_of_error(&self, status: ExitStatus) -> ControlFlow {
         if matches!(self, ExecutionMode::Normal) {
-            ControlFlow::Break(ExitStatus::Error)
+            ControlFlow::Break(status)
         } else {
             ControlFlow::Continue(())
         }
@@ -115,52 +315,212 @@ struct NumbatHelper {
     completer: NumbatCompleter,
     #[rustyline(Highlighter)]
     highlighter: NumbatHighlighter,
+    #[rustyline(Hinter)]
+    hinter: NumbatHinter,
 }
 
 struct Cli {
     config: Config,
+    project: Option<Project>,
     context: Arc<Mutex<Context>>,
     file: Option<PathBuf>,
     expression: Option<Vec<String>>,
+    filter: Option<FilterFormat>,
+    watch: bool,
+    quiet: bool,
+    debug_bytecode: bool,
+    time: bool,
+    export_units: bool,
+    trust: bool,
+
+    /// Whether the entries of the project manifest that load code or write
+    /// files may be used, see [`Project::needs_trust`]
+    project_trusted: bool,
+    import_gnu_units: Option<PathBuf>,
+    check: Option<PathBuf>,
+
+    /// A copy of the context right after loading the prelude, along with all
+    /// inputs since then that have successfully defined something or imported
+    /// a module. Used to rebuild the session when modules are reloaded.
+    session_start: Option<Context>,
+    session_inputs: Vec<(String, CodeSource)>,
+
+    /// The value of the last successful input, as shown to the user. Used by
+    /// the `copy` command.
+    last_result: Option<m::Markup>,
+
+    /// Wall-clock time of the most recent evaluation
+    last_evaluation_time: Duration,
 }
 
 impl Cli {
-    fn new(args: Args) -> Result<Self> {
+    fn load_user_config(args: &Args) -> Result<Config> {
         let user_config_path = Self::get_config_path().join("config.toml");
 
-        let mut config = if args.no_config {
-            Config::default()
+        if args.no_config {
+            Ok(Config::default())
         } else if let Ok(contents) = fs::read_to_string(&user_config_path) {
             toml::from_str(&contents).context(format!(
                 "Error while loading {}",
                 user_config_path.to_string_lossy()
-            ))?
+            ))
         } else {
-            Config::default()
+            Ok(Config::default())
+        }
+    }
+
+    fn new(args: Args) -> Result<Self> {
+        let mut config = Self::load_user_config(&args)?;
+
+        // A broken manifest should not prevent Numbat from starting, unless
+        // the project is run explicitly
+        let project = match Project::find(&std::env::current_dir()?) {
+            Ok(project) => project,
+            Err(e) if !matches!(args.command, Some(Command::Run { .. })) => {
+                eprintln!("Warning: {e:#}. Ignoring the project settings.");
+                None
+            }
+            Err(e) => return Err(e),
         };
 
+        if let Some(project) = &project {
+            project.manifest.settings.apply(&mut config);
+        }
+
         config.load_prelude &= !args.no_prelude;
         config.load_user_init &= !(args.no_prelude || args.no_init);
 
         config.intro_banner = args.intro_banner.unwrap_or(config.intro_banner);
         config.pretty_print = args.pretty_print.unwrap_or(config.pretty_print);
         config.color = args.color.unwrap_or(config.color);
+        config.shadowing = args.shadowing.unwrap_or(config.shadowing);
+        config.strict_multiplication |= args.strict_multiplication;
+        config.compatibility_syntax |= args.compatibility_syntax;
+        config.simplify_derived_units &= !args.no_derived_units;
+        config.scientific_notation = args
+            .scientific_notation
+            .unwrap_or(config.scientific_notation);
+        config.memory_limit = args.memory_limit.or(config.memory_limit);
+        config.long_unit_names |= args.long_unit_names;
+        config.spoken_output |= args.speak;
+
+        let export_units = matches!(args.command, Some(Command::ExportUnits));
+        let trust = matches!(args.command, Some(Command::Trust));
+        let mut import_gnu_units = None;
+        let mut check = None;
+
+        let mut file = args.file;
+        match args.command {
+            Some(Command::Run { file: run_file }) => {
+                let Some(project) = &project else {
+                    bail!(
+                        "Could not find a '{}' file in the current folder or any of its parents",
+                        project::MANIFEST_FILE_NAME
+                    );
+                };
+                file = Some(run_file.unwrap_or_else(|| project.main_file()));
+            }
+            Some(Command::ImportGnuUnits {
+                file: definitions_file,
+            }) => {
+                import_gnu_units = Some(definitions_file);
+            }
+            Some(Command::Check { file: checked_file }) => {
+                check = Some(checked_file);
+            }
+            Some(
+                Command::ExportUnits
+                | Command::Completions { .. }
+                | Command::CompleteExpression { .. }
+                | Command::Install
+                | Command::Trust,
+            )
+            | None => {}
+        }
 
-        config.enter_repl =
-            (args.file.is_none() && args.expression.is_none()) || args.inspect_interactively;
+        config.enter_repl = (file.is_none()
+            && args.expression.is_none()
+            && args.filter.is_none()
+            && !args.watch
+            && !export_units
+            && !trust
+            && import_gnu_units.is_none()
+            && check.is_none())
+            || args.inspect_interactively;
+
+        let project_trusted = match &project {
+            Some(project) if project.needs_trust() => {
+                let trusted = trust::is_trusted(
+                    &Self::get_data_path(),
+                    &project.manifest_path(),
+                    &project.manifest_source,
+                );
+                if !trusted && !trust {
+                    eprintln!(
+                        "Warning: the project manifest '{}' is not trusted. Its module paths, imports, dependencies, preferred units and files are ignored. Run 'numbat trust' to allow them.",
+                        project.manifest_path().to_string_lossy()
+                    );
+                }
+                trusted
+            }
+            _ => true,
+        };
+        let trusted_project = project.as_ref().filter(|_| project_trusted);
 
-        let mut fs_importer = FileSystemImporter::default();
+        let package_paths = match trusted_project {
+            Some(project) => Self::installed_package_paths(project),
+            None => vec![],
+        };
+
+        let mut user_importer = FileSystemImporter::default();
         for path in Self::get_modules_paths() {
-            fs_importer.add_path(path);
+            user_importer.add_path(path);
+        }
+
+        let mut project_importer = FileSystemImporter::default();
+        for path in trusted_project.into_iter().flat_map(Project::module_paths) {
+            project_importer.add_path(path);
+        }
+        for path in package_paths {
+            project_importer.add_path(path);
         }
 
+        // Modules of the user can replace builtin modules, while modules of a
+        // project or package can only add new ones
         let importer = ChainedImporter::new(
-            Box::new(fs_importer),
-            Box::<BuiltinModuleImporter>::default(),
+            Box::new(user_importer),
+            Box::new(ChainedImporter::new(
+                Box::<BuiltinModuleImporter>::default(),
+                Box::new(project_importer),
+            )),
         );
 
         let mut context = Context::new(importer);
         context.set_debug(args.debug);
+        context.set_function_inlining(!args.no_inlining);
+        context.set_file_system_access(!args.no_file_access);
+        context.set_network_access(!args.no_network_access);
+        context.set_deprecation_warnings(!args.no_deprecation_warnings);
+        context.set_shadowing_policy(match config.shadowing {
+            ShadowingMode::Error => ShadowingPolicy::Error,
+            ShadowingMode::Warn => ShadowingPolicy::Warn,
+            ShadowingMode::Allow => ShadowingPolicy::Allow,
+        });
+        context.set_strict_multiplication(config.strict_multiplication);
+        context.set_compatibility_syntax(config.compatibility_syntax);
+        context.set_derived_unit_simplification(config.simplify_derived_units);
+        context.set_memory_limit(
+            config
+                .memory_limit
+                .map(|mebibytes| mebibytes.saturating_mul(1024 * 1024)),
+        );
+        context.set_scientific_notation(match config.scientific_notation {
+            ScientificNotationMode::E => ScientificNotation::E,
+            ScientificNotationMode::Unicode => ScientificNotation::Unicode,
+        });
+        context.set_long_unit_names(config.long_unit_names || config.spoken_output);
+        context.set_environment_access(true);
+        context.set_script_arguments(args.arguments);
 
         context.set_terminal_width(
             terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize),
@@ -169,8 +529,23 @@ impl Cli {
         Ok(Self {
             context: Arc::new(Mutex::new(context)),
             config,
-            file: args.file,
+            project,
+            file,
             expression: args.expression,
+            filter: args.filter,
+            watch: args.watch,
+            quiet: args.quiet,
+            debug_bytecode: args.debug_bytecode,
+            time: args.time,
+            export_units,
+            trust,
+            project_trusted,
+            import_gnu_units,
+            check,
+            session_start: None,
+            session_inputs: vec![],
+            last_result: None,
+            last_evaluation_time: Duration::ZERO,
         })
     }
 
@@ -181,6 +556,10 @@ impl Cli {
             ColorMode::Auto => (), // Let colored itself decide whether coloring should occur or not
         }
 
+        if self.trust {
+            return self.trust_project();
+        }
+
         if self.config.load_prelude {
             let result = self.parse_and_evaluate(
                 "use prelude",
@@ -193,6 +572,10 @@ impl Cli {
             }
         }
 
+        if self.config.enter_repl {
+            self.session_start = Some(self.context.lock().unwrap().clone());
+        }
+
         if self.config.load_user_init {
             let user_init_path = Self::get_config_path().join("init.nbt");
 
@@ -209,6 +592,57 @@ impl Cli {
             }
         }
 
+        if let Some(project) = self.project.as_ref().filter(|_| self.project_trusted) {
+            let imports = project
+                .manifest
+                .imports
+                .iter()
+                .map(|module| format!("use {module}\n"))
+                .join("");
+            let preferred_units = project.manifest.settings.preferred_units_code()?;
+            let code = format!("{imports}{preferred_units}");
+            if !code.is_empty() {
+                let result = self.parse_and_evaluate(
+                    &code,
+                    CodeSource::File(project.manifest_path()),
+                    ExecutionMode::Normal,
+                    PrettyPrintMode::Never,
+                );
+                if result.is_break() {
+                    bail!("Interpreter error while loading the imports and settings from the project manifest")
+                }
+            }
+        }
+
+        // Initialization files are not needed for the tooling commands, and
+        // they are only executed if they have been trusted
+        let load_project_init = self.config.load_user_init
+            && !self.export_units
+            && self.import_gnu_units.is_none()
+            && self.check.is_none();
+        if let Some(init_path) = self.project_init_file().filter(|_| load_project_init) {
+            let init_code = fs::read_to_string(&init_path).context(format!(
+                "Could not load project initialization file '{}'",
+                init_path.to_string_lossy()
+            ))?;
+            if !trust::is_trusted(&Self::get_data_path(), &init_path, &init_code) {
+                eprintln!(
+                    "Warning: the project initialization file '{}' is not trusted and has not been executed. Run 'numbat trust' to allow it.",
+                    init_path.to_string_lossy()
+                );
+            } else {
+                let result = self.parse_and_evaluate(
+                    &init_code,
+                    CodeSource::File(init_path),
+                    ExecutionMode::Normal,
+                    PrettyPrintMode::Never,
+                );
+                if result.is_break() {
+                    bail!("Interpreter error in project initialization code")
+                }
+            }
+        }
+
         if self.config.load_prelude
             && self.config.exchange_rates.fetching_policy != ExchangeRateFetchingPolicy::Never
         {
@@ -216,6 +650,26 @@ impl Cli {
                 .lock()
                 .unwrap()
                 .load_currency_module_on_demand(true);
+            if let Some(session_start) = &mut self.session_start {
+                session_start.load_currency_module_on_demand(true);
+            }
+        }
+
+        if self.export_units {
+            print!("{}", self.context.lock().unwrap().export_units_json());
+            return Ok(());
+        }
+
+        if let Some(path) = &self.import_gnu_units {
+            return self.import_gnu_units(path);
+        }
+
+        if let Some(path) = &self.check {
+            return self.check_file(path);
+        }
+
+        if self.watch {
+            return self.watch_file();
         }
 
         let mut code_and_source = Vec::new();
@@ -246,9 +700,10 @@ impl Cli {
                 );
 
                 let result_status = match result {
-                    std::ops::ControlFlow::Continue(()) => Ok(()),
-                    std::ops::ControlFlow::Break(_) => {
-                        bail!("Interpreter stopped")
+                    std::ops::ControlFlow::Continue(())
+                    | std::ops::ControlFlow::Break(ExitStatus::Success) => Ok(()),
+                    std::ops::ControlFlow::Break(status) => {
+                        return Err(status.into());
                     }
                 };
 
@@ -256,6 +711,10 @@ impl Cli {
             }
         }
 
+        if let Some(format) = self.filter {
+            run_result = run_result.and(self.filter_stdin(format));
+        }
+
         if self.config.enter_repl {
             let mut currency_fetch_thread = if self.config.load_prelude
                 && self.config.exchange_rates.fetching_policy
@@ -295,6 +754,9 @@ impl Cli {
             highlighter: NumbatHighlighter {
                 context: self.context.clone(),
             },
+            hinter: NumbatHinter {
+                context: self.context.clone(),
+            },
         }));
         rl.bind_sequence(
             KeyEvent(KeyCode::Enter, Modifiers::ALT),
@@ -344,6 +806,21 @@ impl Cli {
             let readline = rl.readline(&self.config.prompt);
             match readline {
                 Ok(line) => {
+                    let line = if line.trim() == "paste" {
+                        match clipboard::paste() {
+                            Ok(text) => {
+                                println!("{}", text.trim());
+                                text
+                            }
+                            Err(e) => {
+                                eprintln!("{e:#}");
+                                continue;
+                            }
+                        }
+                    } else {
+                        line
+                    };
+
                     if !line.trim().is_empty() {
                         rl.add_history_entry(&line)?;
 
@@ -393,6 +870,15 @@ impl Cli {
                             "clear" => {
                                 rl.clear_screen()?;
                             }
+                            "reload" => {
+                                self.reload_modules();
+                            }
+                            "copy" | "copy value" => {
+                                self.copy_last_result(line.trim() == "copy value");
+                            }
+                            ":stats" => {
+                                self.print_statistics();
+                            }
                             "quit" | "exit" => {
                                 return Ok(());
                             }
@@ -414,8 +900,15 @@ impl Cli {
                                     println!("{}", ansi_format(&help, true));
                                     continue;
                                 }
+
+                                // The colon distinguishes the command from the `time` function
+                                let (input, time) = match line.trim().strip_prefix(":time ") {
+                                    Some(expression) => (expression, true),
+                                    None => (line.as_str(), self.time),
+                                };
+                                let previous_time = std::mem::replace(&mut self.time, time);
                                 let result = self.parse_and_evaluate(
-                                    &line,
+                                    input,
                                     CodeSource::Text,
                                     if interactive {
                                         ExecutionMode::Interactive
@@ -424,14 +917,15 @@ impl Cli {
                                     },
                                     self.config.pretty_print,
                                 );
+                                self.time = previous_time;
 
                                 match result {
                                     std::ops::ControlFlow::Continue(()) => {}
                                     std::ops::ControlFlow::Break(ExitStatus::Success) => {
                                         return Ok(());
                                     }
-                                    std::ops::ControlFlow::Break(ExitStatus::Error) => {
-                                        bail!("Interpreter stopped due to error")
+                                    std::ops::ControlFlow::Break(status) => {
+                                        return Err(status.into());
                                     }
                                 }
                             }
@@ -456,6 +950,35 @@ impl Cli {
         code_source: CodeSource,
         execution_mode: ExecutionMode,
         pretty_print_mode: PrettyPrintMode,
+    ) -> ControlFlow {
+        let print_timing = self.time && !matches!(code_source, CodeSource::Internal);
+
+        let result = self.evaluate_and_print(input, code_source, execution_mode, pretty_print_mode);
+
+        if print_timing {
+            let instructions = self.context.lock().unwrap().last_instruction_count();
+            eprintln!(
+                "{}",
+                format!(
+                    "  time: {:.2?}, {instructions} instructions",
+                    self.last_evaluation_time
+                )
+                .dimmed()
+            );
+            if execution_mode == ExecutionMode::Interactive {
+                eprintln!();
+            }
+        }
+
+        result
+    }
+
+    fn evaluate_and_print(
+        &mut self,
+        input: &str,
+        code_source: CodeSource,
+        execution_mode: ExecutionMode,
+        pretty_print_mode: PrettyPrintMode,
     ) -> ControlFlow {
         let to_be_printed: Arc<Mutex<Vec<m::Markup>>> = Arc::new(Mutex::new(vec![]));
         let to_be_printed_c = to_be_printed.clone();
@@ -463,13 +986,27 @@ impl Cli {
             print_fn: Box::new(move |s: &m::Markup| {
                 to_be_printed_c.lock().unwrap().push(s.clone());
             }),
+            ..Default::default()
         };
 
-        let result =
-            self.context
-                .lock()
-                .unwrap()
-                .interpret_with_settings(&mut settings, input, code_source);
+        let start = Instant::now();
+        let result = self.context.lock().unwrap().interpret_with_settings(
+            &mut settings,
+            input,
+            code_source.clone(),
+        );
+        self.last_evaluation_time = start.elapsed();
+
+        if self.debug_bytecode && !matches!(code_source, CodeSource::Internal) {
+            eprint!("{}", self.context.lock().unwrap().disassemble_last());
+        }
+
+        let warnings = self.context.lock().unwrap().take_warnings();
+        if !matches!(code_source, CodeSource::Internal) {
+            for warning in warnings {
+                self.print_diagnostic(warning);
+            }
+        }
 
         let interactive = execution_mode == ExecutionMode::Interactive;
 
@@ -481,6 +1018,17 @@ impl Cli {
 
         match result {
             Ok((statements, interpreter_result)) => {
+                if self.session_start.is_some() && defines_something(&statements) {
+                    self.session_inputs.push((input.to_owned(), code_source));
+                }
+
+                if self.quiet {
+                    if let InterpreterResult::Value(value) = &interpreter_result {
+                        println!("{}", raw_value(value));
+                    }
+                    return ControlFlow::Continue(());
+                }
+
                 if interactive || pretty_print {
                     println!();
                 }
@@ -493,22 +1041,36 @@ impl Cli {
                     }
                 }
 
+                let ctx = self.context.lock().unwrap();
+                let format = |markup: m::Markup| {
+                    let markup = ctx.with_unit_names(markup);
+                    let markup = if self.config.spoken_output {
+                        markup.with_spoken_numbers()
+                    } else {
+                        markup
+                    };
+                    markup.with_scientific_notation(ctx.scientific_notation())
+                };
+
                 let to_be_printed = to_be_printed.lock().unwrap();
                 for s in to_be_printed.iter() {
-                    println!("{}", ansi_format(s, interactive));
+                    println!("{}", ansi_format(&format(s.clone()), interactive));
                 }
                 if interactive && !to_be_printed.is_empty() {
                     println!();
                 }
 
-                let ctx = self.context.lock().unwrap();
+                if let InterpreterResult::Value(value) = &interpreter_result {
+                    self.last_result = Some(format(value.pretty_print()));
+                }
+
                 let registry = ctx.dimension_registry();
-                let result_markup = interpreter_result.to_markup(
+                let result_markup = format(interpreter_result.to_markup(
                     statements.last(),
                     registry,
                     interactive || pretty_print,
                     interactive || pretty_print,
-                );
+                ));
                 print!("{}", ansi_format(&result_markup, false));
 
                 if (interactive || pretty_print) && interpreter_result.is_value() {
@@ -519,23 +1081,255 @@ impl Cli {
             }
             Err(NumbatError::ResolverError(e)) => {
                 self.print_diagnostic(e.clone());
-                execution_mode.exit_status_in_case_of_error()
+                execution_mode.exit_status_in_case_of_error(ExitStatus::ParseError)
             }
             Err(NumbatError::NameResolutionError(
                 e @ (NameResolutionError::IdentifierClash { .. }
-                | NameResolutionError::ReservedIdentifier(_)),
+                | NameResolutionError::ConfusableIdentifierClash { .. }
+                | NameResolutionError::ReservedIdentifier(_)
+                | NameResolutionError::UnknownUnit(..)),
             )) => {
                 self.print_diagnostic(e);
-                execution_mode.exit_status_in_case_of_error()
+                execution_mode.exit_status_in_case_of_error(ExitStatus::ParseError)
             }
             Err(NumbatError::TypeCheckError(e)) => {
                 self.print_diagnostic(e);
-                execution_mode.exit_status_in_case_of_error()
+                execution_mode.exit_status_in_case_of_error(ExitStatus::TypeError)
             }
             Err(NumbatError::RuntimeError(e)) => {
+                let status = match e {
+                    RuntimeError::AssertFailed(..)
+                    | RuntimeError::AssertEq2Failed(..)
+                    | RuntimeError::AssertEq3Failed(..) => ExitStatus::AssertionFailed,
+                    _ => ExitStatus::RuntimeError,
+                };
                 self.print_diagnostic(e);
-                execution_mode.exit_status_in_case_of_error()
+                execution_mode.exit_status_in_case_of_error(status)
+            }
+        }
+    }
+
+    /// Show how long the last evaluation took and how much memory it used
+    fn print_statistics(&self) {
+        let ctx = self.context.lock().unwrap();
+        println!();
+        println!("  Last evaluation:");
+        println!("    time:          {:.2?}", self.last_evaluation_time);
+        println!("    instructions:  {}", ctx.last_instruction_count());
+        println!(
+            "    memory:        {}",
+            format_bytes(ctx.last_allocated_bytes())
+        );
+        if let Some(limit) = self.config.memory_limit {
+            println!("    memory limit:  {limit} MiB");
+        }
+        println!();
+    }
+
+    /// Copy the last result to the clipboard, either with its unit (`3 km/h`)
+    /// or just the number (`3`).
+    fn copy_last_result(&self, value_only: bool) {
+        let Some(result) = &self.last_result else {
+            println!("There is no result to copy yet.");
+            return;
+        };
+
+        let text = if value_only {
+            let parts = result
+                .0
+                .iter()
+                .filter(|part| part.1 != m::FormatType::Unit)
+                .cloned()
+                .collect();
+            m::Markup(parts).to_string()
+        } else {
+            result.to_string()
+        };
+        let text = text.trim();
+
+        match clipboard::copy(text) {
+            Ok(()) => println!("Copied '{text}' to the clipboard."),
+            Err(e) => eprintln!("{e:#}"),
+        }
+    }
+
+    /// Re-import all modules whose source files have changed. Definitions can
+    /// not be removed from a context, so we start over from the context right
+    /// after loading the prelude and replay the inputs of the session that
+    /// contain definitions or imports, with their output suppressed. Inputs
+    /// that only evaluate expressions are not run again. If anything fails,
+    /// the old state is kept.
+    fn reload_modules(&self) {
+        let Some(session_start) = &self.session_start else {
+            return;
+        };
+
+        let changed_modules = self.context.lock().unwrap().resolver().changed_modules();
+        if changed_modules.is_empty() {
+            println!("No modules have changed.");
+            return;
+        }
+
+        let mut context = session_start.clone();
+        let mut settings = InterpreterSettings {
+            print_fn: Box::new(|_: &m::Markup| {}),
+            ..Default::default()
+        };
+
+        for (code, code_source) in &self.session_inputs {
+            if let Err(e) =
+                context.interpret_with_settings(&mut settings, code, code_source.clone())
+            {
+                Self::print_numbat_error(&context, e);
+                println!("Could not reload modules, the previous state has been kept.");
+                return;
+            }
+        }
+
+        let still_changed = context.resolver().changed_modules();
+        *self.context.lock().unwrap() = context;
+
+        for module_path in changed_modules {
+            if still_changed.contains(&module_path) {
+                println!(
+                    "Module '{module_path}' is part of the prelude. Restart Numbat to reload it."
+                );
+            } else {
+                println!("Reloaded module '{module_path}'.");
+            }
+        }
+    }
+
+    /// Run the source file and re-run it whenever its modification time changes.
+    /// Every run starts from a copy of the context as it was after loading the
+    /// prelude and the user init file, such that those do not need to be
+    /// re-compiled.
+    fn watch_file(&mut self) -> Result<()> {
+        let Some(path) = self.file.clone() else {
+            bail!("--watch requires a source file");
+        };
+
+        let initial_context = self.context.lock().unwrap().clone();
+        let mut last_modified = None;
+
+        loop {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+            if modified.is_some() && modified != last_modified {
+                last_modified = modified;
+
+                *self.context.lock().unwrap() = initial_context.clone();
+
+                // Clear the screen and move the cursor to the top left corner
+                print!("\x1b[2J\x1b[H");
+
+                match fs::read_to_string(&path) {
+                    Ok(code) => {
+                        let _ = self.parse_and_evaluate(
+                            &code,
+                            CodeSource::File(path.clone()),
+                            ExecutionMode::Normal,
+                            self.config.pretty_print,
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "Could not load source file '{}': {e}",
+                            path.to_string_lossy()
+                        );
+                    }
+                }
+
+                println!();
+                println!(
+                    "{}",
+                    format!(
+                        "Watching '{}' for changes. Press Ctrl-C to quit.",
+                        path.to_string_lossy()
+                    )
+                    .dimmed()
+                );
             }
+
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+
+    /// Evaluate every line from stdin in the same context and write exactly one
+    /// line of output per line of input, so that the output can be pasted next
+    /// to the input (e.g. with `paste`). Errors are reported on stderr and do not
+    /// stop processing of the remaining lines.
+    fn filter_stdin(&mut self, format: FilterFormat) -> Result<()> {
+        use std::io::{BufRead, Write};
+
+        let stdin = std::io::stdin();
+        let mut stdout = std::io::stdout().lock();
+        let mut num_errors = 0;
+
+        for line in stdin.lock().lines() {
+            let line = line.context("Error while reading from standard input")?;
+
+            let mut settings = InterpreterSettings {
+                print_fn: Box::new(|s: &m::Markup| {
+                    eprintln!("{}", ansi_format(s, false));
+                }),
+                ..Default::default()
+            };
+
+            let result = if line.trim().is_empty() {
+                Ok(None)
+            } else {
+                self.context
+                    .lock()
+                    .unwrap()
+                    .interpret_with_settings(&mut settings, &line, CodeSource::Text)
+                    .map(|(_, interpreter_result)| match interpreter_result {
+                        InterpreterResult::Value(value) => Some(value.pretty_print().to_string()),
+                        InterpreterResult::Continue => None,
+                    })
+            };
+
+            let output = match (format, result) {
+                (FilterFormat::Plain, Ok(value)) => value.unwrap_or_default(),
+                (FilterFormat::Ndjson, Ok(value)) => format!(
+                    "{{\"input\":{},\"value\":{}}}",
+                    json_string(&line),
+                    match value {
+                        Some(value) => json_string(&value),
+                        None => "null".to_owned(),
+                    }
+                ),
+                (format, Err(e)) => {
+                    num_errors += 1;
+                    let message = e.to_string();
+                    Self::print_numbat_error(&self.context.lock().unwrap(), e);
+                    match format {
+                        FilterFormat::Plain => String::new(),
+                        FilterFormat::Ndjson => format!(
+                            "{{\"input\":{},\"error\":{}}}",
+                            json_string(&line),
+                            json_string(&message)
+                        ),
+                    }
+                }
+            };
+
+            writeln!(stdout, "{output}")?;
+        }
+
+        if num_errors > 0 {
+            bail!("{num_errors} line(s) could not be evaluated");
+        }
+
+        Ok(())
+    }
+
+    fn print_numbat_error(context: &Context, error: NumbatError) {
+        match error {
+            NumbatError::ResolverError(e) => context.print_diagnostic(e),
+            NumbatError::NameResolutionError(e) => context.print_diagnostic(e),
+            NumbatError::TypeCheckError(e) => context.print_diagnostic(e),
+            NumbatError::RuntimeError(e) => context.print_diagnostic(e),
         }
     }
 
@@ -574,13 +1368,278 @@ impl Cli {
         paths
     }
 
+    fn get_cache_path() -> PathBuf {
+        let cache_dir = dirs::cache_dir().unwrap_or_else(|| PathBuf::from("."));
+        cache_dir.join("numbat")
+    }
+
+    /// Module paths of the packages that have been installed via `numbat install`
+    fn installed_package_paths(project: &Project) -> Vec<PathBuf> {
+        match packages::installed_packages(project, &Self::get_cache_path()) {
+            Ok(installed) => {
+                for name in installed.missing {
+                    eprintln!(
+                        "Warning: package '{name}' is not installed. Run 'numbat install' to install the dependencies of the project."
+                    );
+                }
+                installed.module_paths
+            }
+            Err(e) => {
+                eprintln!("Warning: {e:#}");
+                vec![]
+            }
+        }
+    }
+
+    /// Print completions for a partial expression (`complete-expression`). This
+    /// runs whenever the user presses tab in the shell, so it only loads the
+    /// prelude and the user initialization file. Project settings, packages and
+    /// project initialization files are ignored.
+    fn complete_expression(args: &Args, expression: &str) -> Result<()> {
+        let config = Self::load_user_config(args)?;
+
+        let mut fs_importer = FileSystemImporter::default();
+        for path in Self::get_modules_paths() {
+            fs_importer.add_path(path);
+        }
+        let importer = ChainedImporter::new(
+            Box::new(fs_importer),
+            Box::<BuiltinModuleImporter>::default(),
+        );
+
+        let mut context = Context::new(importer);
+        context.set_file_system_access(false);
+        context.set_network_access(false);
+
+        if config.load_prelude && !args.no_prelude {
+            if context
+                .interpret("use prelude", CodeSource::Internal)
+                .is_err()
+            {
+                bail!("Interpreter error in Prelude code");
+            }
+
+            if config.load_user_init && !args.no_init {
+                let user_init_path = Self::get_config_path().join("init.nbt");
+                if let Ok(user_init_code) = fs::read_to_string(&user_init_path) {
+                    // Completions for the prelude are still useful if the
+                    // initialization file is broken
+                    let _ = context.interpret(&user_init_code, CodeSource::File(user_init_path));
+                }
+            }
+        }
+
+        for candidate in completer::complete_expression(&context, expression) {
+            println!("{candidate}");
+        }
+        Ok(())
+    }
+
+    fn install_packages() -> Result<()> {
+        let Some(project) = Project::find(&std::env::current_dir()?)? else {
+            bail!(
+                "Could not find a '{}' file in the current folder or any of its parents",
+                project::MANIFEST_FILE_NAME
+            );
+        };
+
+        let installed = packages::install_dependencies(&project, &Self::get_cache_path())
+            .context("Error while installing the dependencies of the project")?;
+        for (name, version) in installed {
+            println!("{name} {version}");
+        }
+
+        Ok(())
+    }
+
+    fn check_file(&self, path: &Path) -> Result<()> {
+        let code = fs::read_to_string(path).context(format!(
+            "Could not load source file '{}'",
+            path.to_string_lossy()
+        ))?;
+
+        let mut ctx = self.context.lock().unwrap();
+        match ctx.check(&code, CodeSource::File(path.to_owned())) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    ctx.print_diagnostic(warning);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                let status = match &e {
+                    NumbatError::ResolverError(_) | NumbatError::NameResolutionError(_) => {
+                        ExitStatus::ParseError
+                    }
+                    NumbatError::TypeCheckError(_) => ExitStatus::TypeError,
+                    NumbatError::RuntimeError(_) => ExitStatus::RuntimeError,
+                };
+                Self::print_numbat_error(&ctx, e);
+                Err(status.into())
+            }
+        }
+    }
+
+    fn import_gnu_units(&self, path: &Path) -> Result<()> {
+        let source = fs::read_to_string(path).context(format!(
+            "Could not load definitions file '{}'",
+            path.to_string_lossy()
+        ))?;
+
+        let ctx = self.context.lock().unwrap();
+        let known_names: HashSet<String> = ctx
+            .unit_names()
+            .iter()
+            .flatten()
+            .cloned()
+            .chain(ctx.variable_names())
+            .chain(ctx.dimension_names().iter().cloned())
+            .collect();
+
+        let conversion = numbat::gnu_units::convert(&source, |name| known_names.contains(name));
+
+        for warning in &conversion.warnings {
+            eprintln!(
+                "{}:{}: {}",
+                path.to_string_lossy(),
+                warning.line,
+                warning.message
+            );
+        }
+        print!("{}", conversion.code);
+
+        Ok(())
+    }
+
+    /// The folder in which project-local files are looked up: the project root,
+    /// or the current folder if there is no project.
+    fn project_folder(&self) -> Option<PathBuf> {
+        match &self.project {
+            Some(project) => Some(project.root.clone()),
+            None => std::env::current_dir().ok(),
+        }
+    }
+
+    /// The `init` file from the (trusted) project manifest, or `.numbat/init.nbt`
+    fn project_init_file(&self) -> Option<PathBuf> {
+        self.project
+            .as_ref()
+            .filter(|_| self.project_trusted)
+            .and_then(Project::init_file)
+            .or_else(|| self.local_init_file())
+    }
+
+    fn local_init_file(&self) -> Option<PathBuf> {
+        project::local_file(&self.project_folder()?, "init.nbt").filter(|path| path.is_file())
+    }
+
     fn get_history_path(&self) -> Result<PathBuf> {
-        let data_dir = dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("numbat");
+        let project_history = self
+            .project
+            .as_ref()
+            .filter(|_| self.project_trusted)
+            .and_then(Project::history_file)
+            .or_else(|| project::local_file(&self.project_folder()?, "history"));
+        if let Some(path) = project_history {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).ok();
+            }
+            return Ok(path);
+        }
+
+        let data_dir = Self::get_data_path();
         fs::create_dir_all(&data_dir).ok();
         Ok(data_dir.join("history"))
     }
+
+    fn get_data_path() -> PathBuf {
+        let data_dir = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        data_dir.join("numbat")
+    }
+
+    /// Allow the project manifest and the project initialization file to be
+    /// used (`numbat trust`)
+    fn trust_project(&self) -> Result<()> {
+        if let Some(project) = &self.project {
+            trust::trust(
+                &Self::get_data_path(),
+                &project.manifest_path(),
+                &project.manifest_source,
+            )?;
+            println!(
+                "The project manifest '{}' will be used from now on.",
+                project.manifest_path().to_string_lossy()
+            );
+        }
+
+        let init_path = self
+            .project
+            .as_ref()
+            .and_then(Project::init_file)
+            .or_else(|| self.local_init_file());
+        let Some(init_path) = init_path else {
+            if self.project.is_none() {
+                bail!("Could not find a project manifest or initialization file");
+            }
+            return Ok(());
+        };
+        let init_code = fs::read_to_string(&init_path).context(format!(
+            "Could not load project initialization file '{}'",
+            init_path.to_string_lossy()
+        ))?;
+
+        trust::trust(&Self::get_data_path(), &init_path, &init_code)?;
+        println!(
+            "The project initialization file '{}' will be executed from now on.",
+            init_path.to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+/// The value of a result as printed in quiet mode: numbers without units and
+/// with full precision, strings without quotes and lists with one element per
+/// line.
+fn raw_value(value: &Value) -> String {
+    match value {
+        Value::Quantity(q) => {
+            let number = q.unsafe_value().to_f64();
+            if number.fract() == 0.0 && number.abs() < 1e15 {
+                format!("{number}")
+            } else {
+                format!("{number:?}")
+            }
+        }
+        Value::String(s) => s.clone(),
+        Value::List(list) => list.iter().map(raw_value).join("\n"),
+        value => value.pretty_print().to_string(),
+    }
+}
+
+/// Whether an input changes the state of the context, i.e. contains anything
+/// but expressions and procedure calls. The items of imported modules show up
+/// as definitions.
+fn defines_something(statements: &[Statement]) -> bool {
+    statements
+        .iter()
+        .any(|s| !matches!(s, Statement::Expression(_) | Statement::ProcedureCall(..)))
+}
+
+/// Format a number of bytes like `12.3 KiB`.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
 }
 
 fn generate_config() -> Result<()> {
@@ -624,8 +1683,38 @@ fn main() {
         std::process::exit(0);
     }
 
+    if let Some(Command::Completions { shell }) = args.command {
+        let mut command = Args::command();
+        if let Err(e) =
+            completions::write_completion_script(shell, &mut command, &mut std::io::stdout())
+        {
+            eprintln!("{e:#}");
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    if let Some(Command::CompleteExpression { expression }) = &args.command {
+        if let Err(e) = Cli::complete_expression(&args, expression) {
+            eprintln!("{e:#}");
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    if let Some(Command::Install) = args.command {
+        if let Err(e) = Cli::install_packages() {
+            eprintln!("{e:#}");
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
     if let Err(e) = Cli::new(args).and_then(|mut cli| cli.run()) {
         eprintln!("{e:#}");
-        std::process::exit(1);
+        let code = e
+            .downcast_ref::<ExitStatus>()
+            .map_or(1, |status| status.code());
+        std::process::exit(code);
     }
 }
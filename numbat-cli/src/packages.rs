@@ -0,0 +1,521 @@
+//! Support for module packages from remote git repositories and archives.
+//!
+//! A dependency `company = { git = "…", tag = "v1.0" }` in `numbat.toml` is
+//! cloned into `<cache-path>/packages/<commit>/company`, such that the folder
+//! `<cache-path>/packages/<commit>` can be used as a module path and modules
+//! can be imported via `use company::…`. Dependencies of the form
+//! `company = { url = "….tar.gz", sha256 = "…" }` are downloaded and unpacked
+//! into `<cache-path>/packages/<sha256>/company` instead.
+//!
+//! Packages are only downloaded by `numbat install`, which records the exact
+//! commit (or the hash of the archive) in `numbat.lock` next to the manifest.
+//! Subsequent installs use the versions from the lockfile. All other commands
+//! only use packages that are already installed, and never access the network.
+//!
+//! Packages are first downloaded into a temporary folder, and only moved to
+//! their final location once they are complete and have been verified.
+
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{bail, Context as AnyhowContext, Result};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tar::EntryType;
+
+use crate::project::{is_plain_identifier, Dependency, Project};
+
+pub const LOCKFILE_NAME: &str = "numbat.lock";
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+struct Lockfile {
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct LockedPackage {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+}
+
+impl LockedPackage {
+    fn matches(&self, name: &str, source: &Source) -> bool {
+        self.name == name
+            && match source {
+                Source::Git { url, tag } => {
+                    self.git.as_deref() == Some(*url)
+                        && self.tag.as_deref() == *tag
+                        && self.commit.is_some()
+                }
+                Source::Archive { url, sha256 } => {
+                    self.url.as_deref() == Some(*url)
+                        && self.sha256.is_some()
+                        && (sha256.is_none() || self.sha256.as_deref() == *sha256)
+                }
+            }
+    }
+
+    /// Make sure that the entry can be used to build paths in the cache folder,
+    /// even if the lockfile has been tampered with.
+    fn validate(&self) -> Result<()> {
+        let name = &self.name;
+        if !is_plain_identifier(name) {
+            bail!("Invalid package name '{name}' in {LOCKFILE_NAME}");
+        }
+        match (&self.commit, &self.sha256) {
+            (Some(commit), None) if is_hex(commit, 40) => Ok(()),
+            (None, Some(sha256)) if is_hex(sha256, 64) => Ok(()),
+            _ => bail!("Invalid commit or SHA-256 hash for package '{name}' in {LOCKFILE_NAME}"),
+        }
+    }
+
+    /// The commit of git packages, or the hash of archives
+    fn version(&self) -> &str {
+        self.commit
+            .as_deref()
+            .or(self.sha256.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+enum Source<'a> {
+    Git {
+        url: &'a str,
+        tag: Option<&'a str>,
+    },
+    Archive {
+        url: &'a str,
+        sha256: Option<&'a str>,
+    },
+}
+
+impl<'a> Source<'a> {
+    fn of(name: &str, dependency: &'a Dependency) -> Result<Self> {
+        let source = match dependency {
+            Dependency {
+                git: Some(url),
+                tag,
+                url: None,
+                sha256: None,
+            } => Source::Git {
+                url,
+                tag: tag.as_deref(),
+            },
+            Dependency {
+                git: None,
+                tag: None,
+                url: Some(url),
+                sha256,
+            } => Source::Archive {
+                url,
+                sha256: sha256.as_deref(),
+            },
+            _ => bail!(
+                "Package '{name}' needs either a 'git' URL (and optionally a 'tag'), or a 'url' of an archive (and optionally its 'sha256' hash)"
+            ),
+        };
+
+        // The name is used as a folder name and as the first part of module paths
+        if !is_plain_identifier(name) {
+            bail!("Invalid package name '{name}'. Names may only contain letters, digits and underscores");
+        }
+
+        let url = match source {
+            Source::Git { url, .. } | Source::Archive { url, .. } => url,
+        };
+        if !url.starts_with("https://") {
+            bail!("The URL of package '{name}' needs to start with 'https://'");
+        }
+
+        if let Source::Archive {
+            sha256: Some(sha256),
+            ..
+        } = source
+        {
+            if !is_hex(sha256, 64) {
+                bail!("The 'sha256' hash of package '{name}' needs to consist of 64 hexadecimal digits");
+            }
+        }
+
+        Ok(source)
+    }
+}
+
+/// Download all dependencies of the project that are not installed yet, and
+/// update the lockfile. Returns the names and versions of all packages.
+pub fn install_dependencies(project: &Project, cache_path: &Path) -> Result<Vec<(String, String)>> {
+    let sources = project
+        .manifest
+        .dependencies
+        .iter()
+        .map(|(name, dependency)| Ok((name, Source::of(name, dependency)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let old_lockfile = read_lockfile(project)?;
+    let mut new_lockfile = Lockfile::default();
+
+    for (name, source) in sources {
+        let locked = old_lockfile
+            .package
+            .iter()
+            .find(|package| package.matches(name, &source));
+
+        let package = match source {
+            Source::Git { url, tag } => match locked {
+                Some(
+                    locked @ LockedPackage {
+                        commit: Some(commit),
+                        ..
+                    },
+                ) => {
+                    fetch_git_commit(name, url, commit, cache_path)?;
+                    locked.clone()
+                }
+                _ => clone_git(name, url, tag, cache_path)?,
+            },
+            Source::Archive { url, sha256 } => {
+                let expected_sha256 = sha256.or(locked.and_then(|p| p.sha256.as_deref()));
+                let sha256 = download_archive(name, url, expected_sha256, cache_path)?;
+                LockedPackage {
+                    name: name.clone(),
+                    git: None,
+                    tag: None,
+                    commit: None,
+                    url: Some(url.to_owned()),
+                    sha256: Some(sha256),
+                }
+            }
+        };
+
+        new_lockfile.package.push(package);
+    }
+
+    if new_lockfile.package != old_lockfile.package {
+        let lockfile_path = project.root.join(LOCKFILE_NAME);
+        let contents =
+            toml::to_string(&new_lockfile).context("Error while creating TOML from lockfile")?;
+        std::fs::write(&lockfile_path, contents).context(format!(
+            "Error while writing {}",
+            lockfile_path.to_string_lossy()
+        ))?;
+    }
+
+    Ok(new_lockfile
+        .package
+        .iter()
+        .map(|package| (package.name.clone(), package.version().to_owned()))
+        .collect())
+}
+
+pub struct InstalledPackages {
+    /// Module paths that need to be added to the importer
+    pub module_paths: Vec<PathBuf>,
+    /// Names of dependencies that have not been installed
+    pub missing: Vec<String>,
+}
+
+/// Find the packages of the project that have been installed with
+/// [`install_dependencies`]. This does not access the network.
+pub fn installed_packages(project: &Project, cache_path: &Path) -> Result<InstalledPackages> {
+    let mut installed = InstalledPackages {
+        module_paths: vec![],
+        missing: vec![],
+    };
+    if project.manifest.dependencies.is_empty() {
+        return Ok(installed);
+    }
+
+    let lockfile = read_lockfile(project)?;
+    for (name, dependency) in &project.manifest.dependencies {
+        let source = Source::of(name, dependency)?;
+        let root = lockfile
+            .package
+            .iter()
+            .find(|package| package.matches(name, &source))
+            .map(|package| package_root(cache_path, package.version()))
+            .filter(|root| root.join(name).is_dir());
+
+        match root {
+            Some(root) => installed.module_paths.push(root),
+            None => installed.missing.push(name.clone()),
+        }
+    }
+
+    Ok(installed)
+}
+
+fn read_lockfile(project: &Project) -> Result<Lockfile> {
+    let lockfile_path = project.root.join(LOCKFILE_NAME);
+    if !lockfile_path.exists() {
+        return Ok(Lockfile::default());
+    }
+
+    let contents = std::fs::read_to_string(&lockfile_path).context(format!(
+        "Error while reading {}",
+        lockfile_path.to_string_lossy()
+    ))?;
+    let lockfile: Lockfile = toml::from_str(&contents).context(format!(
+        "Error while loading {}",
+        lockfile_path.to_string_lossy()
+    ))?;
+    for package in &lockfile.package {
+        package.validate()?;
+    }
+
+    Ok(lockfile)
+}
+
+fn is_hex(text: &str, length: usize) -> bool {
+    text.len() == length && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn package_root(cache_path: &Path, version: &str) -> PathBuf {
+    cache_path.join("packages").join(version)
+}
+
+/// An empty temporary folder for downloading a package
+fn download_path(cache_path: &Path, name: &str) -> Result<PathBuf> {
+    let path = cache_path
+        .join("packages")
+        .join("download")
+        .join(format!("{name}-{}", std::process::id()));
+    if path.exists() {
+        std::fs::remove_dir_all(&path)?;
+    }
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    Ok(path)
+}
+
+/// Move a completely downloaded package to its final location
+fn move_into_place(download_path: &Path, target_path: &Path) -> Result<()> {
+    if target_path.exists() {
+        std::fs::remove_dir_all(download_path)?;
+    } else {
+        std::fs::create_dir_all(target_path.parent().unwrap())?;
+        std::fs::rename(download_path, target_path)?;
+    }
+    Ok(())
+}
+
+/// Run `download` on a fresh temporary folder, which is removed if it fails.
+fn with_download_path(
+    cache_path: &Path,
+    name: &str,
+    download: impl FnOnce(&Path) -> Result<()>,
+) -> Result<()> {
+    let download_path = download_path(cache_path, name)?;
+    let result = download(&download_path);
+    if download_path.exists() {
+        std::fs::remove_dir_all(&download_path).ok();
+    }
+    result
+}
+
+/// Clone a dependency that is not yet in the lockfile, and determine the commit.
+fn clone_git(name: &str, url: &str, tag: Option<&str>, cache_path: &Path) -> Result<LockedPackage> {
+    let mut commit = String::new();
+    with_download_path(cache_path, name, |download_path| {
+        let mut clone = Command::new("git");
+        clone.args(["clone", "--quiet", "--depth", "1"]);
+        if let Some(tag) = tag {
+            clone.arg(format!("--branch={tag}"));
+        }
+        clone.arg("--").arg(url).arg(download_path);
+        run(clone, &format!("clone package '{name}' from {url}"))?;
+
+        commit = head_commit(download_path)?;
+        move_into_place(download_path, &package_root(cache_path, &commit).join(name))
+    })?;
+
+    Ok(LockedPackage {
+        name: name.to_owned(),
+        git: Some(url.to_owned()),
+        tag: tag.map(str::to_owned),
+        commit: Some(commit),
+        url: None,
+        sha256: None,
+    })
+}
+
+/// Make sure that the exact commit from the lockfile is installed.
+fn fetch_git_commit(name: &str, url: &str, commit: &str, cache_path: &Path) -> Result<()> {
+    let target_path = package_root(cache_path, commit).join(name);
+    if target_path.exists() {
+        return Ok(());
+    }
+
+    with_download_path(cache_path, name, |download_path| {
+        std::fs::create_dir(download_path)?;
+
+        let mut init = Command::new("git");
+        init.arg("-C").arg(download_path).args(["init", "--quiet"]);
+        run(init, "initialize package folder")?;
+
+        let mut fetch = Command::new("git");
+        fetch
+            .arg("-C")
+            .arg(download_path)
+            .args(["fetch", "--quiet", "--depth", "1", "--"])
+            .arg(url)
+            .arg(commit);
+        run(fetch, &format!("fetch package '{name}' from {url}"))?;
+
+        let mut checkout = Command::new("git");
+        checkout.arg("-C").arg(download_path).args([
+            "checkout",
+            "--quiet",
+            "--detach",
+            "FETCH_HEAD",
+        ]);
+        run(checkout, &format!("check out package '{name}'"))?;
+
+        let head = head_commit(download_path)?;
+        if head != commit {
+            bail!("Package '{name}' is at commit {head}, but {LOCKFILE_NAME} requires {commit}");
+        }
+
+        move_into_place(download_path, &target_path)
+    })
+}
+
+/// Download and unpack a `.tar.gz` archive, and return its SHA-256 hash. If a
+/// hash is given, the archive needs to match it.
+fn download_archive(
+    name: &str,
+    url: &str,
+    expected_sha256: Option<&str>,
+    cache_path: &Path,
+) -> Result<String> {
+    if let Some(expected_sha256) = expected_sha256 {
+        if package_root(cache_path, expected_sha256)
+            .join(name)
+            .exists()
+        {
+            return Ok(expected_sha256.to_owned());
+        }
+    }
+
+    let archive = attohttpc::get(url)
+        .connect_timeout(Duration::from_secs(10))
+        .read_timeout(Duration::from_secs(60))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.bytes())
+        .context(format!("Could not download package '{name}' from {url}"))?;
+
+    let sha256 = sha256_hex(&archive);
+    if let Some(expected_sha256) = expected_sha256 {
+        if !expected_sha256.eq_ignore_ascii_case(&sha256) {
+            bail!("The archive of package '{name}' has the SHA-256 hash {sha256}, but {expected_sha256} is required");
+        }
+    }
+
+    with_download_path(cache_path, name, |download_path| {
+        let contents = extract_archive(&archive, download_path)?;
+        move_into_place(&contents, &package_root(cache_path, &sha256).join(name))
+    })?;
+
+    Ok(sha256)
+}
+
+/// Unpack a `.tar.gz` archive into a new `folder`, and return the folder with
+/// the contents of the package. Only regular files and folders are unpacked,
+/// archives with links or with paths outside of `folder` are rejected.
+fn extract_archive(archive: &[u8], folder: &Path) -> Result<PathBuf> {
+    let contents = folder.join("contents");
+    std::fs::create_dir_all(&contents)?;
+
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    for entry in tar.entries().context("Could not read package archive")? {
+        let mut entry = entry.context("Could not read package archive")?;
+        let path = entry.path()?.into_owned();
+
+        let mut target = contents.clone();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => target.push(part),
+                Component::CurDir => {}
+                _ => bail!(
+                    "The package archive contains an invalid path '{}'",
+                    path.to_string_lossy()
+                ),
+            }
+        }
+
+        match entry.header().entry_type() {
+            EntryType::Directory => std::fs::create_dir_all(&target)?,
+            EntryType::Regular | EntryType::Continuous => {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                entry.unpack(&target).context(format!(
+                    "Could not unpack '{}' from package archive",
+                    path.to_string_lossy()
+                ))?;
+            }
+            // Metadata, e.g. the commit ID in archives that are created by `git archive`
+            EntryType::XGlobalHeader | EntryType::XHeader => {}
+            _ => bail!(
+                "The package archive contains '{}', which is not a regular file or folder",
+                path.to_string_lossy()
+            ),
+        }
+    }
+
+    // Archives from code hosting sites usually contain a single top-level folder
+    let entries = std::fs::read_dir(&contents)?.collect::<std::io::Result<Vec<_>>>()?;
+    if let [entry] = &entries[..] {
+        if entry.file_type()?.is_dir() {
+            return Ok(entry.path());
+        }
+    }
+
+    Ok(contents)
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn head_commit(repository: &Path) -> Result<String> {
+    let mut rev_parse = Command::new("git");
+    rev_parse
+        .arg("-C")
+        .arg(repository)
+        .args(["rev-parse", "HEAD"]);
+    let output = run(rev_parse, "determine the checked out commit")?;
+
+    Ok(output.trim().to_owned())
+}
+
+fn run(mut command: Command, action: &str) -> Result<String> {
+    let output = command.output().context(format!(
+        "Could not run '{}' to {action}",
+        command.get_program().to_string_lossy()
+    ))?;
+
+    if !output.status.success() {
+        bail!(
+            "Could not {action}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as AnyhowContext, Result};
+use serde::Deserialize;
+
+use crate::config::{Config, ScientificNotationMode, ShadowingMode};
+
+pub const MANIFEST_FILE_NAME: &str = "numbat.toml";
+
+/// Folder for project-local files (`init.nbt` and `history`) that are not
+/// configured in a manifest
+pub const LOCAL_FOLDER_NAME: &str = ".numbat";
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct Manifest {
+    /// Minimum version of Numbat that is required to run this project
+    pub numbat_version: Option<String>,
+
+    /// The entry point for `numbat run`, relative to the project root
+    pub main: Option<PathBuf>,
+
+    /// Additional module search paths, relative to the project root
+    pub module_paths: Vec<PathBuf>,
+
+    /// Modules that are imported after the prelude (e.g. `"units::stoney"`)
+    pub imports: Vec<String>,
+
+    /// A script that is executed after the imports, relative to the project root
+    pub init: Option<PathBuf>,
+
+    /// The history file for interactive sessions, relative to the project root
+    pub history: Option<PathBuf>,
+
+    /// Remote module packages, see [`crate::packages`]
+    pub dependencies: BTreeMap<String, Dependency>,
+
+    /// Settings that override the user configuration
+    pub settings: Settings,
+}
+
+/// A subset of the fields of [`Config`] that can be set per project. Settings
+/// that are passed on the command line take precedence.
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct Settings {
+    pub shadowing: Option<ShadowingMode>,
+    pub scientific_notation: Option<ScientificNotationMode>,
+    pub long_unit_names: Option<bool>,
+    pub strict_multiplication: Option<bool>,
+    pub compatibility_syntax: Option<bool>,
+    pub simplify_derived_units: Option<bool>,
+    pub memory_limit: Option<usize>,
+
+    /// Preferred units per dimension (e.g. `Length = "ft"`), which can be
+    /// used to switch a project to another unit system
+    pub preferred_units: BTreeMap<String, String>,
+}
+
+impl Settings {
+    pub fn apply(&self, config: &mut Config) {
+        config.shadowing = self.shadowing.unwrap_or(config.shadowing);
+        config.scientific_notation = self
+            .scientific_notation
+            .unwrap_or(config.scientific_notation);
+        config.long_unit_names = self.long_unit_names.unwrap_or(config.long_unit_names);
+        config.strict_multiplication = self
+            .strict_multiplication
+            .unwrap_or(config.strict_multiplication);
+        config.compatibility_syntax = self
+            .compatibility_syntax
+            .unwrap_or(config.compatibility_syntax);
+        config.simplify_derived_units = self
+            .simplify_derived_units
+            .unwrap_or(config.simplify_derived_units);
+        config.memory_limit = self.memory_limit.or(config.memory_limit);
+    }
+
+    /// Numbat code that sets the preferred units
+    pub fn preferred_units_code(&self) -> Result<String> {
+        let mut code = String::new();
+        for (dimension, unit) in &self.preferred_units {
+            if !is_plain_identifier(dimension) || !is_plain_identifier(unit) {
+                bail!("Invalid preferred unit '{unit}' for dimension '{dimension}'");
+            }
+            code += &format!("set preferred_unit {dimension} = {unit}\n");
+        }
+        Ok(code)
+    }
+}
+
+/// A module package from a git repository or from a `.tar.gz` archive
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Dependency {
+    /// URL of a git repository (e.g. `https://example.com/units.git`)
+    pub git: Option<String>,
+
+    /// Tag or branch to check out. Uses the default branch if not specified.
+    pub tag: Option<String>,
+
+    /// URL of a `.tar.gz` archive (e.g. `https://example.com/units-1.0.tar.gz`)
+    pub url: Option<String>,
+
+    /// The SHA-256 hash of the archive. Recorded in the lockfile if not specified.
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Project {
+    pub root: PathBuf,
+    pub manifest: Manifest,
+
+    /// The contents of the manifest file, needed to check whether it has been trusted
+    pub manifest_source: String,
+}
+
+impl Project {
+    /// Look for a `numbat.toml` file in `start` or any of its parent folders.
+    pub fn find(start: &Path) -> Result<Option<Self>> {
+        for folder in start.ancestors() {
+            let manifest_path = folder.join(MANIFEST_FILE_NAME);
+            if manifest_path.is_file() {
+                let contents = std::fs::read_to_string(&manifest_path).context(format!(
+                    "Error while reading {}",
+                    manifest_path.to_string_lossy()
+                ))?;
+                let manifest: Manifest = toml::from_str(&contents).context(format!(
+                    "Error while loading {}",
+                    manifest_path.to_string_lossy()
+                ))?;
+
+                let project = Project {
+                    root: folder.to_path_buf(),
+                    manifest,
+                    manifest_source: contents,
+                };
+                project.check_numbat_version(env!("CARGO_PKG_VERSION"))?;
+
+                return Ok(Some(project));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        self.root.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Whether the manifest contains entries that load code or write files, and
+    /// therefore need to be trusted via `numbat trust` before they are used
+    pub fn needs_trust(&self) -> bool {
+        let manifest = &self.manifest;
+        !manifest.module_paths.is_empty()
+            || !manifest.imports.is_empty()
+            || !manifest.dependencies.is_empty()
+            || !manifest.settings.preferred_units.is_empty()
+            || manifest.init.is_some()
+            || manifest.history.is_some()
+    }
+
+    pub fn module_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.manifest
+            .module_paths
+            .iter()
+            .map(|path| self.root.join(path))
+    }
+
+    pub fn init_file(&self) -> Option<PathBuf> {
+        self.manifest.init.as_ref().map(|path| self.root.join(path))
+    }
+
+    pub fn history_file(&self) -> Option<PathBuf> {
+        self.manifest
+            .history
+            .as_ref()
+            .map(|path| self.root.join(path))
+    }
+
+    pub fn main_file(&self) -> PathBuf {
+        self.root.join(
+            self.manifest
+                .main
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("main.nbt")),
+        )
+    }
+
+    fn check_numbat_version(&self, current_version: &str) -> Result<()> {
+        let Some(required_version) = &self.manifest.numbat_version else {
+            return Ok(());
+        };
+
+        let required = parse_version(required_version).context(format!(
+            "Invalid 'numbat-version' in {}",
+            self.manifest_path().to_string_lossy()
+        ))?;
+        let current = parse_version(current_version)?;
+
+        if current < required {
+            bail!(
+                "This project requires Numbat {required_version} or newer, but this is Numbat {current_version}"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `name` consists of letters, digits and underscores only, and does not
+/// start with a digit
+pub fn is_plain_identifier(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Path of a file in the `.numbat` folder of `folder`, or `None` if there is
+/// no such folder.
+pub fn local_file(folder: &Path, name: &str) -> Option<PathBuf> {
+    let local_folder = folder.join(LOCAL_FOLDER_NAME);
+    local_folder.is_dir().then(|| local_folder.join(name))
+}
+
+/// Parse a version number like "1.13" or "1.13.0" into its (major, minor, patch) parts.
+fn parse_version(version: &str) -> Result<(u64, u64, u64)> {
+    let mut parts = version.trim().split('.').map(|part| {
+        part.parse::<u64>()
+            .context(format!("'{version}' is not a valid version number"))
+    });
+
+    let major = parts.next().unwrap_or(Ok(0))?;
+    let minor = parts.next().unwrap_or(Ok(0))?;
+    let patch = parts.next().unwrap_or(Ok(0))?;
+
+    if parts.next().is_some() {
+        bail!("'{version}' is not a valid version number");
+    }
+
+    Ok((major, minor, patch))
+}
@@ -0,0 +1,64 @@
+//! Project manifests and initialization files are only used once they have been
+//! trusted explicitly via `numbat trust`, since they could otherwise run arbitrary
+//! code from a freshly cloned repository.
+//!
+//! Trusted files are recorded in `<data-path>/trusted-files`, one line per file
+//! with the SHA-256 hash of its contents and its canonical path. A file needs to
+//! be trusted again whenever it changes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as AnyhowContext, Result};
+
+use crate::packages::sha256_hex;
+
+const TRUST_FILE_NAME: &str = "trusted-files";
+
+fn entry(path: &Path, contents: &str) -> Result<String> {
+    let canonical_path = path
+        .canonicalize()
+        .context(format!("Could not find '{}'", path.to_string_lossy()))?;
+    Ok(format!(
+        "{} {}",
+        sha256_hex(contents.as_bytes()),
+        canonical_path.to_string_lossy()
+    ))
+}
+
+fn trust_file(data_path: &Path) -> PathBuf {
+    data_path.join(TRUST_FILE_NAME)
+}
+
+/// Whether the file at `path` with the given contents has been trusted.
+pub fn is_trusted(data_path: &Path, path: &Path, contents: &str) -> bool {
+    let Ok(entry) = entry(path, contents) else {
+        return false;
+    };
+    fs::read_to_string(trust_file(data_path))
+        .is_ok_and(|contents| contents.lines().any(|line| line == entry))
+}
+
+/// Record that the file at `path` with the given contents may be used. Entries
+/// for older versions of the file are removed.
+pub fn trust(data_path: &Path, path: &Path, contents: &str) -> Result<()> {
+    let entry = entry(path, contents)?;
+    let (_, canonical_path) = entry.split_once(' ').expect("entry contains a space");
+
+    let trust_file = trust_file(data_path);
+    let contents = fs::read_to_string(&trust_file).unwrap_or_default();
+    let mut lines: Vec<&str> = contents
+        .lines()
+        .filter(|line| line.split_once(' ').map(|(_, path)| path) != Some(canonical_path))
+        .collect();
+    lines.push(&entry);
+
+    fs::create_dir_all(data_path).context(format!(
+        "Could not create the folder '{}'",
+        data_path.to_string_lossy()
+    ))?;
+    fs::write(&trust_file, lines.join("\n") + "\n").context(format!(
+        "Could not write '{}'",
+        trust_file.to_string_lossy()
+    ))
+}
@@ -3,7 +3,7 @@ use std::path::Path;
 use assert_cmd::Command;
 use predicates::boolean::PredicateBooleanExt;
 
-fn numbat() -> Command {
+fn numbat_command() -> Command {
     let module_path = Path::new(&std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
         .parent()
         .unwrap()
@@ -11,12 +11,26 @@ fn numbat() -> Command {
         .join("modules");
     std::env::set_var("NUMBAT_MODULES_PATH", module_path);
 
-    let mut cmd = Command::cargo_bin("numbat").unwrap();
+    Command::cargo_bin("numbat").unwrap()
+}
+
+fn numbat() -> Command {
+    let mut cmd = numbat_command();
     cmd.arg("--no-init");
     cmd.arg("--no-config");
     cmd
 }
 
+/// A command that loads initialization files, with separate folders for the
+/// user configuration and for the list of trusted project files
+fn numbat_with_init_files(home: &Path) -> Command {
+    let mut cmd = numbat_command();
+    cmd.env("XDG_CONFIG_HOME", home.join("config"));
+    cmd.env("XDG_DATA_HOME", home.join("data"));
+    cmd.arg("--no-config");
+    cmd
+}
+
 #[test]
 fn pass_expression_on_command_line() {
     numbat()
@@ -63,6 +77,83 @@ fn pass_expression_on_command_line() {
         .stdout(predicates::str::contains("Length / Time"));
 }
 
+#[test]
+fn exit_codes() {
+    numbat().arg("--expression").arg("2 +/ 3").assert().code(3);
+    numbat()
+        .arg("--expression")
+        .arg("2 meter + 3 second")
+        .assert()
+        .code(4);
+    numbat().arg("--expression").arg("1/0").assert().code(5);
+    numbat()
+        .arg("--expression")
+        .arg("assert_eq(1 m, 2 m)")
+        .assert()
+        .code(6);
+    numbat()
+        .arg("tests/examples/does_not_exist.nbt")
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn quiet_mode() {
+    numbat()
+        .arg("--quiet")
+        .arg("--expression")
+        .arg("3 km -> m")
+        .assert()
+        .success()
+        .stdout("3000\n");
+
+    numbat()
+        .arg("-q")
+        .arg("--expression")
+        .arg("print(\"hello\")")
+        .arg("--expression")
+        .arg("1.602e-19 C")
+        .assert()
+        .success()
+        .stdout("1.602e-19\n");
+
+    numbat()
+        .arg("--raw")
+        .arg("--expression")
+        .arg("[1 m, 2.5 m]")
+        .assert()
+        .success()
+        .stdout("1\n2.5\n");
+}
+
+#[test]
+fn timing() {
+    numbat()
+        .arg("--time")
+        .arg("--expression")
+        .arg("2 + 3")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("5"))
+        .stderr(
+            predicates::str::contains("time: ").and(predicates::str::contains(" instructions")),
+        );
+}
+
+#[test]
+fn memory_limit() {
+    numbat()
+        .arg("--memory-limit=1")
+        .arg("--expression")
+        .arg("fn twice(x: Scalar) -> Scalar = 2 x")
+        .arg("--expression")
+        .arg("len(map(twice, range(1, 100000)))")
+        .assert()
+        .failure()
+        .code(5)
+        .stderr(predicates::str::contains("memory limit"));
+}
+
 #[test]
 fn read_code_from_file() {
     numbat()
@@ -106,6 +197,17 @@ fn print_calls() {
         ));
 }
 
+#[test]
+fn script_arguments() {
+    numbat()
+        .arg("tests/examples/arguments.nbt")
+        .arg("5")
+        .arg("km")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("5, km\n"));
+}
+
 #[test]
 fn without_prelude() {
     numbat()
@@ -149,6 +251,16 @@ fn help_text() {
         ));
 }
 
+#[test]
+fn reload_without_changes() {
+    numbat()
+        .write_stdin("let x = 2\nreload\nx")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("No modules have changed."))
+        .stdout(predicates::str::contains("2"));
+}
+
 #[test]
 fn info_text() {
     numbat().write_stdin("info g0").assert().success().stdout(
@@ -169,3 +281,330 @@ fn info_text() {
                 .and(predicates::str::contains("Round to the nearest integer.")),
         );
 }
+
+#[test]
+fn filter_mode() {
+    numbat()
+        .arg("--filter")
+        .write_stdin("2 m + 3 m\nlet x = 2\n\nx^3\n30 km/h -> m/s\n")
+        .assert()
+        .success()
+        .stdout("5 m\n\n\n8\n8.33333 m/s\n");
+
+    numbat()
+        .arg("--filter")
+        .write_stdin("1 m + 1 s\n2 + 3\n")
+        .assert()
+        .failure()
+        .stdout("\n5\n")
+        .stderr(predicates::str::contains("while type checking"));
+
+    numbat()
+        .arg("--filter=ndjson")
+        .write_stdin("2 m + 3 m\nlet y = 1\n1/0\n")
+        .assert()
+        .failure()
+        .stdout(concat!(
+            "{\"input\":\"2 m + 3 m\",\"value\":\"5 m\"}\n",
+            "{\"input\":\"let y = 1\",\"value\":null}\n",
+            "{\"input\":\"1/0\",\"error\":\"Division by zero\"}\n",
+        ));
+}
+
+#[test]
+fn project_manifest() {
+    let home = Path::new(env!("CARGO_TARGET_TMPDIR")).join("project-manifest");
+    let _ = std::fs::remove_dir_all(&home);
+
+    // The manifest loads modules, so it is only used once it has been trusted
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/project")
+        .arg("--expression")
+        .arg("6 kg")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("6 kg"))
+        .stderr(predicates::str::contains(
+            "numbat.toml' is not trusted. Its module paths, imports, dependencies, preferred units and files are ignored.",
+        ));
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/project")
+        .arg("trust")
+        .assert()
+        .success();
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/project")
+        .arg("run")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("4 widget"))
+        .stderr(predicates::str::contains("not trusted").not());
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/project/scripts")
+        .arg("--expression")
+        .arg("9 kg -> widgets")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("3 widget"));
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/project/scripts")
+        .arg("--expression")
+        .arg("batch -> kg")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("15 kg"));
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/project")
+        .arg("--no-init")
+        .arg("--expression")
+        .arg("6 kg")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("2 widget"));
+
+    numbat()
+        .arg("run")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Could not find a 'numbat.toml' file",
+        ));
+}
+
+#[test]
+fn broken_project_manifest() {
+    numbat()
+        .current_dir("tests/examples/broken-project")
+        .arg("--expression")
+        .arg("2 + 3")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("5"))
+        .stderr(predicates::str::contains("Warning: Error while loading"));
+
+    numbat()
+        .current_dir("tests/examples/broken-project")
+        .arg("run")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Error while loading"));
+}
+
+#[test]
+fn packages_are_only_installed_explicitly() {
+    let home = Path::new(env!("CARGO_TARGET_TMPDIR")).join("packages");
+    let _ = std::fs::remove_dir_all(&home);
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/packages")
+        .arg("trust")
+        .assert()
+        .success();
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/packages")
+        .env("XDG_CACHE_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .arg("--expression")
+        .arg("2 + 3")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("5"))
+        .stderr(predicates::str::contains(
+            "package 'company' is not installed. Run 'numbat install'",
+        ));
+
+    numbat()
+        .current_dir("tests/examples/insecure-package")
+        .env("XDG_CACHE_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .arg("install")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "The URL of package 'company' needs to start with 'https://'",
+        ));
+    assert!(!Path::new("tests/examples/insecure-package/pwned").exists());
+}
+
+#[test]
+fn invalid_packages() {
+    numbat()
+        .current_dir("tests/examples/invalid-package-name")
+        .env("XDG_CACHE_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .arg("install")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Invalid package name '../company'",
+        ));
+
+    let home = Path::new(env!("CARGO_TARGET_TMPDIR")).join("tampered-lockfile");
+    let _ = std::fs::remove_dir_all(&home);
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/tampered-lockfile")
+        .arg("trust")
+        .assert()
+        .success();
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/tampered-lockfile")
+        .env("XDG_CACHE_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .arg("--expression")
+        .arg("2 + 3")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("5"))
+        .stderr(predicates::str::contains(
+            "Invalid commit or SHA-256 hash for package 'company' in numbat.lock",
+        ));
+}
+
+#[test]
+fn local_init_file() {
+    let home = Path::new(env!("CARGO_TARGET_TMPDIR")).join("local-init");
+    let _ = std::fs::remove_dir_all(&home);
+
+    // Initialization files are not needed for checks
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/local-init")
+        .arg("check")
+        .arg("../unused.nbt")
+        .assert()
+        .stderr(predicates::str::contains("not trusted").not());
+
+    // Initialization files are only executed once they have been trusted
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/local-init")
+        .arg("--expression")
+        .arg("answer")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "is not trusted and has not been executed. Run 'numbat trust' to allow it.",
+        ));
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/local-init")
+        .arg("trust")
+        .assert()
+        .success();
+
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/local-init")
+        .arg("--expression")
+        .arg("answer")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("42"));
+
+    // Trusted files are not executed with `--no-init`
+    numbat_with_init_files(&home)
+        .current_dir("tests/examples/local-init")
+        .arg("--no-init")
+        .arg("--expression")
+        .arg("answer")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn export_units() {
+    numbat()
+        .arg("export-units")
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("[\n"))
+        .stdout(predicates::str::contains(
+            "{\"name\":\"hour\",\"long_name\":\"Hour\",",
+        ))
+        .stdout(predicates::str::contains(
+            "\"dimension\":\"Time\",\"base_representation\":{\"second\":1},\"factor\":3600,",
+        ));
+}
+
+#[test]
+fn check() {
+    numbat()
+        .arg("check")
+        .arg("tests/examples/unused.nbt")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(predicates::str::contains(
+            "variable 'unused_length' is never used",
+        ))
+        .stderr(predicates::str::contains("halved").not());
+
+    numbat()
+        .arg("check")
+        .arg("tests/examples/parser_error.nbt")
+        .assert()
+        .failure()
+        .code(3);
+}
+
+#[test]
+fn import_gnu_units() {
+    numbat()
+        .arg("import-gnu-units")
+        .arg("tests/examples/definitions.units")
+        .assert()
+        .success()
+        .stdout("unit cubit = 18 inch\n\nunit rood = (1 / 4) acre\n\nunit shed = 1e-24 barn\n\n")
+        .stderr(predicates::str::contains(
+            "definitions.units:3: prefix definition 'kilo-' is not supported",
+        ))
+        .stderr(predicates::str::contains(
+            "definitions.units:8: nonlinear unit 'tempF' is not supported",
+        ));
+}
+
+#[test]
+fn shell_completions() {
+    numbat()
+        .arg("completions")
+        .arg("bash")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("--expression"))
+        .stdout(predicates::str::contains("numbat complete-expression"));
+
+    numbat()
+        .arg("complete-expression")
+        .arg("--")
+        .arg("3 ft -> centim")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("3 ft -> centimeter\n"));
+
+    numbat()
+        .arg("complete-expression")
+        .arg("--")
+        .arg("'2 kilo")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("'2 kilogram\n"));
+
+    // Project settings and initialization files are not loaded for completions
+    numbat()
+        .current_dir("tests/examples/project")
+        .arg("complete-expression")
+        .arg("--")
+        .arg("3 kg -> widg")
+        .assert()
+        .success()
+        .stdout("");
+
+    numbat()
+        .current_dir("tests/examples/broken-project")
+        .arg("complete-expression")
+        .arg("--")
+        .arg("3 ft -> centim")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("3 ft -> centimeter\n"))
+        .stderr("");
+}
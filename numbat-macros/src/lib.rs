@@ -0,0 +1,56 @@
+//! Compile-time checked quantity literals for Rust programs that embed Numbat.
+//!
+//! ```
+//! use numbat_macros::{const_quantity, quantity};
+//!
+//! let g0 = quantity!("9.80665 m/s^2");
+//! assert_eq!(g0.to_string(), "9.80665 m/s²");
+//!
+//! const SPEED_LIMIT: numbat::QuantityLiteral = const_quantity!("130 km/h");
+//! assert_eq!(SPEED_LIMIT.to_quantity().to_string(), "130 km/h");
+//! ```
+//!
+//! The literal is parsed and checked against the units of the Numbat prelude
+//! when the host program is compiled, so unit typos or dimension errors like
+//! `quantity!("2 m + 3 s")` are reported as compiler errors. Only numbers, units
+//! and arithmetic operators can be used. The result is stored as constant data
+//! in the host program; nothing is parsed or evaluated at runtime.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+fn literal_code(literal: &LitStr) -> syn::Result<proc_macro2::TokenStream> {
+    let code = numbat::macro_support::quantity_literal_code(&literal.value())
+        .map_err(|message| syn::Error::new(literal.span(), message))?;
+    Ok(code.parse().expect("valid Rust code for the literal"))
+}
+
+/// Create a [`numbat::QuantityLiteral`] from a Numbat expression like `"30 km/h"`.
+/// The result can be used in `const` and `static` items.
+#[proc_macro]
+pub fn const_quantity(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+
+    match literal_code(&literal) {
+        Ok(code) => code.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Create a [`numbat::Quantity`] from a Numbat expression like `"30 km/h"`.
+#[proc_macro]
+pub fn quantity(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+
+    match literal_code(&literal) {
+        Ok(code) => quote! {
+            {
+                const LITERAL: ::numbat::QuantityLiteral = #code;
+                LITERAL.to_quantity()
+            }
+        }
+        .into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
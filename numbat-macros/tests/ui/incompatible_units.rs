@@ -0,0 +1,7 @@
+use numbat_macros::const_quantity;
+
+const DISTANCE: numbat::QuantityLiteral = const_quantity!("2 m + 3 s");
+
+fn main() {
+    let _ = DISTANCE;
+}
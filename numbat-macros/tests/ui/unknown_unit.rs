@@ -0,0 +1,5 @@
+use numbat_macros::quantity;
+
+fn main() {
+    let _ = quantity!("3 meterz");
+}
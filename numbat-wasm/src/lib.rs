@@ -13,7 +13,7 @@ use numbat::module_importer::BuiltinModuleImporter;
 use numbat::pretty_print::PrettyPrint;
 use numbat::resolver::CodeSource;
 use numbat::{markup as m, NameResolutionError, NumbatError};
-use numbat::{Context, InterpreterSettings};
+use numbat::{Context, InterpreterSettings, ScientificNotation};
 
 use jquery_terminal_formatter::{JqueryTerminalFormatter, JqueryTerminalWriter};
 
@@ -74,6 +74,25 @@ impl Numbat {
             .unwrap();
     }
 
+    /// Print very large and very small numbers like `1.602×10⁻¹⁹` instead of `1.602e-19`
+    pub fn set_unicode_scientific_notation(&mut self, enable: bool) {
+        self.ctx.set_scientific_notation(if enable {
+            ScientificNotation::Unicode
+        } else {
+            ScientificNotation::E
+        });
+    }
+
+    /// Write units in long form, like `3 kilometres per hour` instead of `3 km/h`
+    pub fn set_long_unit_names(&mut self, enable: bool) {
+        self.ctx.set_long_unit_names(enable);
+    }
+
+    /// Abort evaluations that allocate more than approximately `limit` bytes
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.ctx.set_memory_limit(limit);
+    }
+
     fn format(&self, markup: &numbat::markup::Markup, indent: bool) -> String {
         let fmt: Box<dyn Formatter> = match self.format_type {
             FormatType::JqueryTerminal => Box::new(JqueryTerminalFormatter {}),
@@ -87,10 +106,15 @@ impl Numbat {
 
         let to_be_printed: Arc<Mutex<Vec<m::Markup>>> = Arc::new(Mutex::new(vec![]));
         let to_be_printed_c = to_be_printed.clone();
+        let notation = self.ctx.scientific_notation();
         let mut settings = InterpreterSettings {
             print_fn: Box::new(move |s: &m::Markup| {
-                to_be_printed_c.lock().unwrap().push(s.clone());
+                to_be_printed_c
+                    .lock()
+                    .unwrap()
+                    .push(s.clone().with_scientific_notation(notation));
             }),
+            ..Default::default()
         };
 
         let nl = &self.format(&numbat::markup::nl(), false);
@@ -119,16 +143,20 @@ impl Numbat {
                 // print(…) and type(…) results
                 let to_be_printed = to_be_printed.lock().unwrap();
                 for content in to_be_printed.iter() {
-                    output.push_str(&self.format(content, enable_indentation));
+                    let content = self.ctx.with_unit_names(content.clone());
+                    output.push_str(&self.format(&content, enable_indentation));
                     output.push_str(nl);
                 }
 
-                let result_markup = result.to_markup(
-                    statements.last(),
-                    &self.ctx.dimension_registry().clone(),
-                    true,
-                    true,
-                );
+                let result_markup = self
+                    .ctx
+                    .with_unit_names(result.to_markup(
+                        statements.last(),
+                        &self.ctx.dimension_registry().clone(),
+                        true,
+                        true,
+                    ))
+                    .with_scientific_notation(notation);
                 output.push_str(&self.format(&result_markup, enable_indentation));
 
                 InterpreterOutput {
@@ -139,7 +167,9 @@ impl Numbat {
             Err(NumbatError::ResolverError(e)) => self.print_diagnostic(&e),
             Err(NumbatError::NameResolutionError(
                 e @ (NameResolutionError::IdentifierClash { .. }
-                | NameResolutionError::ReservedIdentifier(_)),
+                | NameResolutionError::ConfusableIdentifierClash { .. }
+                | NameResolutionError::ReservedIdentifier(_)
+                | NameResolutionError::UnknownUnit(..)),
             )) => self.print_diagnostic(&e),
             Err(NumbatError::TypeCheckError(e)) => self.print_diagnostic(&e),
             Err(NumbatError::RuntimeError(e)) => self.print_diagnostic(&e),
@@ -17,7 +17,9 @@ async function fetch_exchange_rates() {
 }
 
 function create_numbat_instance() {
-    return Numbat.new(true, true, FormatType.JqueryTerminal);
+    let instance = Numbat.new(true, true, FormatType.JqueryTerminal);
+    instance.set_memory_limit(256 * 1024 * 1024);
+    return instance;
 }
 
 function updateUrlQuery(query) {
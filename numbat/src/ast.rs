@@ -2,7 +2,7 @@ use crate::markup as m;
 use crate::span::Span;
 use crate::{
     arithmetic::Exponent, decorator::Decorator, markup::Markup, number::Number, prefix::Prefix,
-    pretty_print::PrettyPrint, resolver::ModulePath,
+    prefix_parser::AcceptsPrefix, pretty_print::PrettyPrint, resolver::ModulePath, symbol::Symbol,
 };
 use itertools::Itertools;
 use num_traits::Signed;
@@ -30,6 +30,8 @@ pub enum BinaryOperator {
     NotEqual,
     LogicalAnd,
     LogicalOr,
+    /// `15 % of 80 kg`, a multiplication with a dimensionless left hand side
+    Of,
 }
 
 impl PrettyPrint for BinaryOperator {
@@ -51,6 +53,7 @@ impl PrettyPrint for BinaryOperator {
             NotEqual => m::space() + m::operator("≠") + m::space(),
             LogicalAnd => m::space() + m::operator("&&") + m::space(),
             LogicalOr => m::space() + m::operator("||") + m::space(),
+            Of => m::space() + m::keyword("of") + m::space(),
         }
     }
 }
@@ -68,8 +71,8 @@ pub enum StringPart {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Scalar(Span, Number),
-    Identifier(Span, String),
-    UnitIdentifier(Span, Prefix, String, String),
+    Identifier(Span, Symbol),
+    UnitIdentifier(Span, Prefix, Symbol, Symbol),
     TypedHole(Span),
     UnaryOperator {
         op: UnaryOperator,
@@ -85,15 +88,33 @@ pub enum Expression {
     FunctionCall(Span, Span, Box<Expression>, Vec<Expression>),
     Boolean(Span, bool),
     String(Span, Vec<StringPart>),
+    /// A date and time literal like `@2024-05-01T13:00Z`
+    DateTime(Span, crate::datetime::Zoned),
     Condition(Span, Box<Expression>, Box<Expression>, Box<Expression>),
     InstantiateStruct {
         full_span: Span,
         ident_span: Span,
-        name: String,
-        fields: Vec<(Span, String, Expression)>,
+        name: Symbol,
+        fields: Vec<(Span, Symbol, Expression)>,
+    },
+    AccessField(Span, Span, Box<Expression>, Symbol),
+    /// A functional struct update like `{ point with x: 3 m, origin.y: 0 m }`.
+    /// Every updated field is given by a (possibly nested) path of field names.
+    UpdateStruct {
+        full_span: Span,
+        expr: Box<Expression>,
+        fields: Vec<(Span, Vec<Symbol>, Expression)>,
     },
-    AccessField(Span, Span, Box<Expression>, String),
     List(Span, Vec<Expression>),
+    Dict(Span, Vec<(Expression, Expression)>),
+    /// A lazily evaluated range like `1 ..= 10` or `0 s .. 1 s step 0.1 s`
+    Range {
+        span_op: Span,
+        start: Box<Expression>,
+        end: Box<Expression>,
+        step: Option<Box<Expression>>,
+        inclusive: bool,
+    },
 }
 
 impl Expression {
@@ -125,9 +146,17 @@ impl Expression {
                 span_if.extend(&then_expr.full_span())
             }
             Expression::String(span, _) => *span,
+            Expression::DateTime(span, _) => *span,
             Expression::InstantiateStruct { full_span, .. } => *full_span,
             Expression::AccessField(full_span, _ident_span, _, _) => *full_span,
+            Expression::UpdateStruct { full_span, .. } => *full_span,
             Expression::List(span, _) => *span,
+            Expression::Dict(span, _) => *span,
+            Expression::Range {
+                start, end, step, ..
+            } => start
+                .full_span()
+                .extend(&step.as_ref().unwrap_or(end).full_span()),
             Expression::TypedHole(span) => *span,
         }
     }
@@ -217,9 +246,9 @@ macro_rules! struct_ {
         crate::ast::Expression::InstantiateStruct {
             full_span: Span::dummy(),
             ident_span: Span::dummy(),
-            name: stringify!($name).to_owned(),
+            name: stringify!($name).into(),
             fields: vec![
-                $((Span::dummy(), stringify!($field).to_owned(), $val)),*
+                $((Span::dummy(), stringify!($field).into(), $val)),*
             ]
         }
     }};
@@ -264,6 +293,9 @@ pub enum TypeAnnotation {
     DateTime(Span),
     Fn(Span, Vec<TypeAnnotation>, Box<TypeAnnotation>),
     List(Span, Box<TypeAnnotation>),
+    Dict(Span, Box<TypeAnnotation>),
+    /// A generic struct with its type arguments, like `Estimate<Length>`
+    Struct(Span, Symbol, Vec<TypeAnnotation>),
 }
 
 impl TypeAnnotation {
@@ -275,6 +307,8 @@ impl TypeAnnotation {
             TypeAnnotation::DateTime(span) => *span,
             TypeAnnotation::Fn(span, _, _) => *span,
             TypeAnnotation::List(span, _) => *span,
+            TypeAnnotation::Dict(span, _) => *span,
+            TypeAnnotation::Struct(span, _, _) => *span,
         }
     }
 }
@@ -307,6 +341,22 @@ impl PrettyPrint for TypeAnnotation {
                     + element_type.pretty_print()
                     + m::operator(">")
             }
+            TypeAnnotation::Dict(_, value_type) => {
+                m::type_identifier("Dict")
+                    + m::operator("<")
+                    + value_type.pretty_print()
+                    + m::operator(">")
+            }
+            TypeAnnotation::Struct(_, name, type_arguments) => {
+                m::type_identifier(name)
+                    + m::operator("<")
+                    + Itertools::intersperse(
+                        type_arguments.iter().map(|t| t.pretty_print()),
+                        m::operator(",") + m::space(),
+                    )
+                    .sum()
+                    + m::operator(">")
+            }
         }
     }
 }
@@ -315,7 +365,7 @@ impl PrettyPrint for TypeAnnotation {
 
 pub enum TypeExpression {
     Unity(Span),
-    TypeIdentifier(Span, String),
+    TypeIdentifier(Span, Symbol),
     Multiply(Span, Box<TypeExpression>, Box<TypeExpression>),
     Divide(Span, Box<TypeExpression>, Box<TypeExpression>),
     Power(
@@ -386,6 +436,9 @@ pub enum ProcedureKind {
     Assert,
     AssertEq,
     Type,
+    /// Attach a standard uncertainty (first argument) to constants (remaining
+    /// arguments). This is what the `@uncertainty(…)` decorator turns into.
+    DefineUncertainty,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -396,22 +449,34 @@ pub enum TypeParameterBound {
 #[derive(Debug, Clone, PartialEq)]
 pub struct DefineVariable {
     pub identifier_span: Span,
-    pub identifier: String,
+    pub identifier: Symbol,
     pub expr: Expression,
     pub type_annotation: Option<TypeAnnotation>,
     pub decorators: Vec<Decorator>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportKind {
+    /// `use foo::bar`: all names are imported into the global namespace
+    All,
+    /// `use foo::bar (a, b)`: only the given names are imported into the global
+    /// namespace. All other functions and variables are available under their
+    /// qualified name (`foo::bar::c`).
+    Selected(Vec<(Span, Symbol)>),
+    /// `use foo::bar as baz`: functions and variables are available as `baz::a`
+    Aliased(Span, Symbol),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     Expression(Expression),
     DefineVariable(DefineVariable),
     DefineFunction {
         function_name_span: Span,
-        function_name: String,
-        type_parameters: Vec<(Span, String, Option<TypeParameterBound>)>,
+        function_name: Symbol,
+        type_parameters: Vec<(Span, Symbol, Option<TypeParameterBound>)>,
         /// Parameters, optionally with type annotations.
-        parameters: Vec<(Span, String, Option<TypeAnnotation>)>,
+        parameters: Vec<(Span, Symbol, Option<TypeAnnotation>)>,
         /// Function body. If it is absent, the function is implemented via FFI
         body: Option<Expression>,
         /// Local variables
@@ -420,22 +485,37 @@ pub enum Statement {
         return_type_annotation: Option<TypeAnnotation>,
         decorators: Vec<Decorator>,
     },
-    DefineDimension(Span, String, Vec<TypeExpression>),
-    DefineBaseUnit(Span, String, Option<TypeExpression>, Vec<Decorator>),
+    DefineDimension(Span, Symbol, Vec<TypeExpression>),
+    DefineBaseUnit(Span, Symbol, Option<TypeExpression>, Vec<Decorator>),
     DefineDerivedUnit {
         identifier_span: Span,
-        identifier: String,
+        identifier: Symbol,
         expr: Expression,
         type_annotation_span: Option<Span>,
         type_annotation: Option<TypeAnnotation>,
         decorators: Vec<Decorator>,
     },
+    /// An additional name for an existing unit, like `unit alias mtr = meter`
+    DefineUnitAlias {
+        alias_span: Span,
+        alias: Symbol,
+        accepts_prefix: Option<AcceptsPrefix>,
+        unit_span: Span,
+        unit: Symbol,
+    },
+    /// A unit in which results of a given dimension are shown, like `set preferred_unit Speed = km/h`
+    SetPreferredUnit {
+        span: Span,
+        dimension: TypeExpression,
+        expr: Expression,
+    },
     ProcedureCall(Span, ProcedureKind, Vec<Expression>),
-    ModuleImport(Span, ModulePath),
+    ModuleImport(Span, ModulePath, ImportKind),
     DefineStruct {
         struct_name_span: Span,
-        struct_name: String,
-        fields: Vec<(Span, String, TypeAnnotation)>,
+        struct_name: Symbol,
+        type_parameters: Vec<(Span, Symbol, Option<TypeParameterBound>)>,
+        fields: Vec<(Span, Symbol, TypeAnnotation)>,
     },
 }
 
@@ -458,6 +538,14 @@ impl ReplaceSpans for TypeAnnotation {
             TypeAnnotation::List(_, et) => {
                 TypeAnnotation::List(Span::dummy(), Box::new(et.replace_spans()))
             }
+            TypeAnnotation::Dict(_, vt) => {
+                TypeAnnotation::Dict(Span::dummy(), Box::new(vt.replace_spans()))
+            }
+            TypeAnnotation::Struct(_, name, ta) => TypeAnnotation::Struct(
+                Span::dummy(),
+                name.clone(),
+                ta.iter().map(|t| t.replace_spans()).collect(),
+            ),
         }
     }
 }
@@ -544,6 +632,7 @@ impl ReplaceSpans for Expression {
                 args.iter().map(|a| a.replace_spans()).collect(),
             ),
             Expression::Boolean(_, val) => Expression::Boolean(Span::dummy(), *val),
+            Expression::DateTime(_, dt) => Expression::DateTime(Span::dummy(), dt.clone()),
             Expression::Condition(_, condition, then, else_) => Expression::Condition(
                 Span::dummy(),
                 Box::new(condition.replace_spans()),
@@ -569,10 +658,38 @@ impl ReplaceSpans for Expression {
                 Box::new(expr.replace_spans()),
                 attr.clone(),
             ),
+            Expression::UpdateStruct { expr, fields, .. } => Expression::UpdateStruct {
+                full_span: Span::dummy(),
+                expr: Box::new(expr.replace_spans()),
+                fields: fields
+                    .iter()
+                    .map(|(_, path, v)| (Span::dummy(), path.clone(), v.replace_spans()))
+                    .collect(),
+            },
             Expression::List(_, elements) => Expression::List(
                 Span::dummy(),
                 elements.iter().map(|e| e.replace_spans()).collect(),
             ),
+            Expression::Dict(_, entries) => Expression::Dict(
+                Span::dummy(),
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.replace_spans(), v.replace_spans()))
+                    .collect(),
+            ),
+            Expression::Range {
+                start,
+                end,
+                step,
+                inclusive,
+                ..
+            } => Expression::Range {
+                span_op: Span::dummy(),
+                start: Box::new(start.replace_spans()),
+                end: Box::new(end.replace_spans()),
+                step: step.as_ref().map(|s| Box::new(s.replace_spans())),
+                inclusive: *inclusive,
+            },
             Expression::TypedHole(_) => Expression::TypedHole(Span::dummy()),
         }
     }
@@ -659,21 +776,61 @@ impl ReplaceSpans for Statement {
                 type_annotation: type_annotation.as_ref().map(|t| t.replace_spans()),
                 decorators: decorators.clone(),
             },
+            Statement::DefineUnitAlias {
+                alias_span: _,
+                alias,
+                accepts_prefix,
+                unit_span: _,
+                unit,
+            } => Statement::DefineUnitAlias {
+                alias_span: Span::dummy(),
+                alias: alias.clone(),
+                accepts_prefix: *accepts_prefix,
+                unit_span: Span::dummy(),
+                unit: unit.clone(),
+            },
+            Statement::SetPreferredUnit {
+                span: _,
+                dimension,
+                expr,
+            } => Statement::SetPreferredUnit {
+                span: Span::dummy(),
+                dimension: dimension.replace_spans(),
+                expr: expr.replace_spans(),
+            },
             Statement::ProcedureCall(_, proc, args) => Statement::ProcedureCall(
                 Span::dummy(),
                 proc.clone(),
                 args.iter().map(|a| a.replace_spans()).collect(),
             ),
-            Statement::ModuleImport(_, module_path) => {
-                Statement::ModuleImport(Span::dummy(), module_path.clone())
-            }
+            Statement::ModuleImport(_, module_path, kind) => Statement::ModuleImport(
+                Span::dummy(),
+                module_path.clone(),
+                match kind {
+                    ImportKind::All => ImportKind::All,
+                    ImportKind::Selected(names) => ImportKind::Selected(
+                        names
+                            .iter()
+                            .map(|(_, name)| (Span::dummy(), name.clone()))
+                            .collect(),
+                    ),
+                    ImportKind::Aliased(_, alias) => {
+                        ImportKind::Aliased(Span::dummy(), alias.clone())
+                    }
+                },
+            ),
             Statement::DefineStruct {
                 struct_name,
+                type_parameters,
                 fields,
                 ..
             } => Statement::DefineStruct {
                 struct_name_span: Span::dummy(),
                 struct_name: struct_name.clone(),
+                type_parameters: type_parameters
+                    .iter()
+                    .map(|(_, name, bound)| (Span::dummy(), name.clone(), bound.clone()))
+                    .collect(),
                 fields: fields
                     .iter()
                     .map(|(_span, name, type_)| {
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use itertools::Itertools;
 
 use crate::ast::ProcedureKind;
 use crate::decorator::Decorator;
 use crate::dimension::DimensionRegistry;
+use crate::inlining::{substitute, InlineCandidate, MAX_INLINING_DEPTH};
 use crate::interpreter::{
     Interpreter, InterpreterResult, InterpreterSettings, Result, RuntimeError,
 };
@@ -12,13 +14,18 @@ use crate::name_resolution::LAST_RESULT_IDENTIFIERS;
 use crate::prefix::Prefix;
 use crate::prefix_parser::AcceptsPrefix;
 use crate::pretty_print::PrettyPrint;
+use crate::span::Span;
+use crate::symbol::Symbol;
+use crate::traversal::ForAllExpressions;
+use crate::typechecker::type_scheme::TypeScheme;
 use crate::typed_ast::{
     BinaryOperator, DefineVariable, Expression, Statement, StringPart, UnaryOperator,
 };
 use crate::unit::{CanonicalName, Unit};
+use crate::unit_lookup::UnitLookup;
 use crate::unit_registry::{UnitMetadata, UnitRegistry};
 use crate::value::{FunctionReference, Value};
-use crate::vm::{Constant, ExecutionContext, Op, Vm};
+use crate::vm::{BytecodePosition, Constant, ExecutionContext, InterruptFlag, Op, Vm};
 use crate::{decorator, ffi, Type};
 
 #[derive(Debug, Clone, Default)]
@@ -26,6 +33,7 @@ pub struct LocalMetadata {
     pub name: Option<String>,
     pub url: Option<String>,
     pub description: Option<String>,
+    pub source: Option<String>,
     pub aliases: Vec<String>,
 }
 
@@ -34,6 +42,9 @@ pub struct Local {
     identifier: String,
     depth: usize,
     pub metadata: LocalMetadata,
+    /// Name of the hidden global variable that holds the standard uncertainty
+    /// of this (global) variable, if it has one
+    uncertainty: Option<String>,
 }
 
 #[derive(Clone)]
@@ -45,10 +56,30 @@ pub struct BytecodeInterpreter {
     unit_name_to_constant_index: HashMap<String, u16>,
     /// List of functions
     functions: HashMap<String, bool>,
+    /// Functions that are small enough to be inlined at call sites
+    inline_candidates: HashMap<String, Arc<InlineCandidate>>,
+    /// Whether or not function calls should be inlined
+    inlining: bool,
+    /// Number of nested inlined calls that are currently being compiled
+    inlining_depth: usize,
+    /// Position in the bytecode before the most recent input
+    last_position: BytecodePosition,
+    /// Listing of the most recent input, if its bytecode has been discarded
+    /// because the evaluation failed
+    discarded_listing: Option<String>,
 }
 
 impl BytecodeInterpreter {
     fn compile_expression(&mut self, expr: &Expression) -> Result<()> {
+        // All instructions that are emitted for this expression (but not for
+        // its subexpressions) are associated with its span.
+        self.vm.push_span(expr.full_span());
+        let result = self.compile_expression_without_span(expr);
+        self.vm.pop_span();
+        result
+    }
+
+    fn compile_expression_without_span(&mut self, expr: &Expression) -> Result<()> {
         match expr {
             Expression::Scalar(_span, n, _type) => {
                 let index = self.vm.add_constant(Constant::Scalar(n.to_f64()));
@@ -116,7 +147,7 @@ impl BytecodeInterpreter {
                 let op = match operator {
                     BinaryOperator::Add => Op::Add,
                     BinaryOperator::Sub => Op::Subtract,
-                    BinaryOperator::Mul => Op::Multiply,
+                    BinaryOperator::Mul | BinaryOperator::Of => Op::Multiply,
                     BinaryOperator::Div => Op::Divide,
                     BinaryOperator::Power => Op::Power,
                     BinaryOperator::ConvertTo => Op::ConvertTo,
@@ -157,6 +188,17 @@ impl BytecodeInterpreter {
                 self.vm.add_op(op);
             }
             Expression::FunctionCall(_span, _full_span, name, args, _type) => {
+                if name == "uncertainty" && self.functions.get(name) == Some(&true) {
+                    return self.compile_uncertainty(&args[0]);
+                }
+
+                if let Some(body) = self.inlined_body(name, args) {
+                    self.inlining_depth += 1;
+                    let result = self.compile_expression(&body);
+                    self.inlining_depth -= 1;
+                    return result;
+                }
+
                 // Put all arguments on top of the stack
                 for arg in args {
                     self.compile_expression(arg)?;
@@ -202,6 +244,29 @@ impl BytecodeInterpreter {
 
                 self.vm.add_op1(Op::AccessStructField, idx as u16);
             }
+            Expression::UpdateStruct(_span, expr, fields, struct_type) => {
+                self.compile_expression(expr)?;
+
+                for (path, value) in fields {
+                    let mut field_type = struct_type.to_concrete_type();
+                    let mut indices = Vec::with_capacity(path.len());
+                    for field_name in path {
+                        let Type::Struct(ref struct_info) = field_type else {
+                            unreachable!(
+                                "Struct updates of non-struct types should be prevented by the type checker"
+                            );
+                        };
+                        let (idx, _, (_, type_)) = struct_info.fields.get_full(field_name).unwrap();
+                        indices.push(idx);
+                        field_type = type_.clone();
+                    }
+
+                    self.compile_expression(value)?;
+
+                    let path_idx = self.vm.add_field_path(indices);
+                    self.vm.add_op1(Op::UpdateStructField, path_idx);
+                }
+            }
             Expression::CallableCall(_span, callable, args, _type) => {
                 // Put all arguments on top of the stack
                 for arg in args {
@@ -217,6 +282,10 @@ impl BytecodeInterpreter {
                 let index = self.vm.add_constant(Constant::Boolean(*val));
                 self.vm.add_op1(Op::LoadConstant, index);
             }
+            Expression::DateTime(_, dt) => {
+                let index = self.vm.add_constant(Constant::DateTime(dt.clone()));
+                self.vm.add_op1(Op::LoadConstant, index);
+            }
             Expression::String(_, string_parts) => {
                 for part in string_parts {
                     match part {
@@ -268,6 +337,27 @@ impl BytecodeInterpreter {
 
                 self.vm.add_op1(Op::BuildList, elements.len() as u16);
             }
+            Expression::Dict(_, entries, _) => {
+                for (key, value) in entries {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+
+                self.vm.add_op1(Op::BuildDict, entries.len() as u16);
+            }
+            Expression::Range(_, start, end, step, inclusive, _) => {
+                self.compile_expression(start)?;
+                self.compile_expression(end)?;
+                if let Some(step) = step {
+                    self.compile_expression(step)?;
+                } else {
+                    // Ranges without a step are restricted to scalars
+                    let index = self.vm.add_constant(Constant::Scalar(1.0));
+                    self.vm.add_op1(Op::LoadConstant, index);
+                }
+
+                self.vm.add_op1(Op::BuildRange, u16::from(*inclusive));
+            }
             Expression::TypedHole(_, _) => {
                 unreachable!("Typed holes cause type inference errors")
             }
@@ -282,29 +372,133 @@ impl BytecodeInterpreter {
         let current_depth = self.current_depth();
 
         // For variables, we ignore the prefix info and only use the names
-        let aliases = crate::decorator::name_and_aliases(identifier, decorators)
-            .map(|(name, _)| name)
-            .cloned()
+        let aliases = crate::decorator::name_and_aliases(&Symbol::new(identifier), decorators)
+            .map(|(name, _)| name.to_string())
             .collect::<Vec<_>>();
         let metadata = LocalMetadata {
             name: crate::decorator::name(decorators),
             url: crate::decorator::url(decorators),
             description: crate::decorator::description(decorators),
+            source: crate::decorator::source(decorators),
             aliases: aliases.clone(),
         };
 
-        for alias_name in aliases {
+        for alias_name in &aliases {
             self.compile_expression(expr)?;
 
             self.locals[current_depth].push(Local {
                 identifier: alias_name.clone(),
                 depth: current_depth,
                 metadata: metadata.clone(),
+                uncertainty: None,
             });
         }
+
+        // Global variables that are computed from quantities with a known
+        // uncertainty (like `let x = 2 G`) get the propagated uncertainty
+        if current_depth == 0 && !self.uncertain_identifiers(expr).is_empty() {
+            self.compile_uncertainty(expr)?;
+            self.attach_uncertainty(&aliases);
+        }
         Ok(())
     }
 
+    /// The global variables with a known standard uncertainty that `expr`
+    /// refers to, along with the hidden variables that hold the uncertainties.
+    fn uncertain_identifiers(&self, expr: &Expression) -> Vec<(Span, String, TypeScheme, String)> {
+        let current_depth = self.current_depth();
+
+        let mut identifiers: Vec<(Span, String, TypeScheme, String)> = vec![];
+        expr.for_all_expressions(&mut |expr| {
+            let Expression::Identifier(span, identifier, type_) = expr else {
+                return;
+            };
+            let is_local = current_depth > 0
+                && self.locals[current_depth]
+                    .iter()
+                    .any(|l| &l.identifier == identifier);
+            if is_local || identifiers.iter().any(|(_, i, _, _)| i == identifier) {
+                return;
+            }
+            if let Some(uncertainty) = self.locals[0]
+                .iter()
+                .rfind(|l| &l.identifier == identifier)
+                .and_then(|l| l.uncertainty.clone())
+            {
+                identifiers.push((*span, identifier.clone(), type_.clone(), uncertainty));
+            }
+        });
+        identifiers
+    }
+
+    /// Compile `uncertainty(expr)`. The standard uncertainty of `expr` is
+    /// propagated to first order from the uncertainties of the global variables
+    /// it refers to, by evaluating `expr` with each of these variables shifted
+    /// up and down by its uncertainty (a central difference). Correlations
+    /// between different variables are not taken into account, and neither are
+    /// the bodies of functions that are called in `expr`.
+    fn compile_uncertainty(&mut self, expr: &Expression) -> Result<()> {
+        let identifiers = self.uncertain_identifiers(expr);
+
+        // The uncertainty of a single variable is known exactly
+        if let (Expression::Identifier(..), [(span, _, type_, uncertainty)]) =
+            (expr, identifiers.as_slice())
+        {
+            return self.compile_expression(&Expression::Identifier(
+                *span,
+                uncertainty.clone(),
+                type_.clone(),
+            ));
+        }
+
+        self.compile_expression(expr)?;
+        for (span, identifier, type_, uncertainty) in &identifiers {
+            let variable = Expression::Identifier(*span, identifier.clone(), type_.clone());
+            let shift = Expression::Identifier(*span, uncertainty.clone(), type_.clone());
+            for operator in [BinaryOperator::Add, BinaryOperator::Sub] {
+                let shifted_identifier = Expression::BinaryOperator(
+                    None,
+                    operator,
+                    Box::new(variable.clone()),
+                    Box::new(shift.clone()),
+                    type_.clone(),
+                );
+
+                let mut shifted = expr.clone();
+                substitute(
+                    &mut shifted,
+                    std::slice::from_ref(identifier),
+                    &[shifted_identifier],
+                );
+                self.compile_expression(&shifted)?;
+            }
+        }
+        self.vm
+            .add_op1(Op::CombineUncertainties, identifiers.len() as u16); // TODO: check overflow
+
+        Ok(())
+    }
+
+    /// Store the uncertainty on top of the stack in a hidden global variable,
+    /// and attach it to the given global variables.
+    fn attach_uncertainty(&mut self, names: &[String]) {
+        // This can not clash with the name of a user-defined variable
+        let hidden_name = format!("{} ±", names[0]);
+
+        self.locals[0].push(Local {
+            identifier: hidden_name.clone(),
+            depth: 0,
+            metadata: LocalMetadata::default(),
+            uncertainty: None,
+        });
+
+        for name in names {
+            if let Some(local) = self.locals[0].iter_mut().rfind(|l| &l.identifier == name) {
+                local.uncertainty = Some(hidden_name.clone());
+            }
+        }
+    }
+
     fn compile_statement(
         &mut self,
         stmt: &Statement,
@@ -339,6 +533,7 @@ impl BytecodeInterpreter {
                         identifier: parameter.1.clone(),
                         depth: current_depth,
                         metadata: LocalMetadata::default(),
+                        uncertainty: None,
                     });
                 }
                 for local_variables in local_variables {
@@ -354,6 +549,26 @@ impl BytecodeInterpreter {
                 self.vm.end_function();
 
                 self.functions.insert(name.clone(), false);
+
+                let parameter_names: Vec<String> = parameters.iter().map(|p| p.1.clone()).collect();
+                let candidate = InlineCandidate::new(
+                    name,
+                    &parameter_names,
+                    expr,
+                    local_variables,
+                    |f| self.function_index(f),
+                    |g| self.global_index(g),
+                    |u| self.unit_index(u),
+                );
+                match candidate {
+                    Some(candidate) => {
+                        self.inline_candidates
+                            .insert(name.clone(), Arc::new(candidate));
+                    }
+                    None => {
+                        self.inline_candidates.remove(name);
+                    }
+                }
             }
             Statement::DefineFunction(
                 name,
@@ -373,46 +588,56 @@ impl BytecodeInterpreter {
                     .add_foreign_function(name, parameters.len()..=parameters.len());
 
                 self.functions.insert(name.clone(), true);
+                self.inline_candidates.remove(name);
             }
-            Statement::DefineDimension(_name, _dexprs) => {
-                // Declaring a dimension is like introducing a new type. The information
-                // is only relevant for the type checker. Nothing happens at run time.
+            Statement::DefineDimension(name, _dexprs) => {
+                // Declaring a dimension is like introducing a new type. Apart from the
+                // name, which can be looked up at run time, the information is only
+                // relevant for the type checker.
+                if let Ok(base_representation) =
+                    dimension_registry.get_base_representation_for_name(name)
+                {
+                    self.vm.add_dimension_to_lookup(name, base_representation);
+                }
             }
             Statement::DefineBaseUnit(unit_name, decorators, annotation, type_) => {
-                let aliases = decorator::name_and_aliases(unit_name, decorators)
-                    .map(|(name, ap)| (name.clone(), ap))
+                let aliases = decorator::name_and_aliases(&Symbol::new(unit_name), decorators)
+                    .map(|(name, ap)| (name.to_string(), ap))
                     .collect();
 
+                let metadata = UnitMetadata {
+                    type_: type_.to_concrete_type(), // Base unit types can never be generic
+                    readable_type: annotation
+                        .as_ref()
+                        .map(|a| a.pretty_print())
+                        .unwrap_or(type_.to_readable_type(dimension_registry, false)),
+                    aliases,
+                    name: decorator::name(decorators),
+                    canonical_name: decorator::get_canonical_unit_name(unit_name, decorators),
+                    url: decorator::url(decorators),
+                    description: decorator::description(decorators),
+                    binary_prefixes: decorators.contains(&Decorator::BinaryPrefixes),
+                    metric_prefixes: decorators.contains(&Decorator::MetricPrefixes),
+                };
+
                 self.vm
                     .unit_registry
-                    .add_base_unit(
-                        unit_name,
-                        UnitMetadata {
-                            type_: type_.to_concrete_type(), // Base unit types can never be generic
-                            readable_type: annotation
-                                .as_ref()
-                                .map(|a| a.pretty_print())
-                                .unwrap_or(type_.to_readable_type(dimension_registry, false)),
-                            aliases,
-                            name: decorator::name(decorators),
-                            canonical_name: decorator::get_canonical_unit_name(
-                                unit_name, decorators,
-                            ),
-                            url: decorator::url(decorators),
-                            description: decorator::description(decorators),
-                            binary_prefixes: decorators.contains(&Decorator::BinaryPrefixes),
-                            metric_prefixes: decorators.contains(&Decorator::MetricPrefixes),
-                        },
-                    )
+                    .add_base_unit(unit_name, metadata.clone())
                     .map_err(RuntimeError::UnitRegistryError)?;
 
-                let constant_idx = self.vm.add_constant(Constant::Unit(Unit::new_base(
+                let unit = Unit::new_base(
                     unit_name,
                     crate::decorator::get_canonical_unit_name(unit_name.as_str(), &decorators[..]),
-                )));
-                for (name, _) in decorator::name_and_aliases(unit_name, decorators) {
+                );
+                self.vm.add_unit_to_lookup(unit_name, &unit, &metadata);
+                let constant_idx = self.vm.add_constant(Constant::Unit(unit));
+                if decorators.contains(&Decorator::Fractional) {
+                    self.vm.add_op1(Op::LoadConstant, constant_idx);
+                    self.vm.add_op(Op::AddFractionalUnit);
+                }
+                for (name, _) in decorator::name_and_aliases(&Symbol::new(unit_name), decorators) {
                     self.unit_name_to_constant_index
-                        .insert(name.into(), constant_idx);
+                        .insert(name.to_string(), constant_idx);
                 }
             }
             Statement::DefineDerivedUnit(
@@ -423,8 +648,8 @@ impl BytecodeInterpreter {
                 type_,
                 _readable_type,
             ) => {
-                let aliases = decorator::name_and_aliases(unit_name, decorators)
-                    .map(|(name, ap)| (name.clone(), ap))
+                let aliases = decorator::name_and_aliases(&Symbol::new(unit_name), decorators)
+                    .map(|(name, ap)| (name.to_string(), ap))
                     .collect();
 
                 let constant_idx = self.vm.add_constant(Constant::Unit(Unit::new_base(
@@ -463,12 +688,36 @@ impl BytecodeInterpreter {
                 self.vm
                     .add_op2(Op::SetUnitConstant, unit_information_idx, constant_idx);
 
+                if decorators.contains(&Decorator::SimplificationTarget) {
+                    self.vm.add_op1(Op::LoadConstant, constant_idx);
+                    self.vm.add_op(Op::AddSimplificationTarget);
+                }
+
+                if decorators.contains(&Decorator::Fractional) {
+                    self.vm.add_op1(Op::LoadConstant, constant_idx);
+                    self.vm.add_op(Op::AddFractionalUnit);
+                }
+
                 // TODO: code duplication with DeclareBaseUnit branch above
-                for (name, _) in decorator::name_and_aliases(unit_name, decorators) {
+                for (name, _) in decorator::name_and_aliases(&Symbol::new(unit_name), decorators) {
                     self.unit_name_to_constant_index
-                        .insert(name.into(), constant_idx);
+                        .insert(name.to_string(), constant_idx);
                 }
             }
+            Statement::DefineUnitAlias(alias, accepts_prefix, unit_name) => {
+                let constant_idx = *self
+                    .unit_name_to_constant_index
+                    .get(unit_name)
+                    .expect("unit should already exist");
+                self.unit_name_to_constant_index
+                    .insert(alias.clone(), constant_idx);
+                self.vm
+                    .add_unit_alias_to_lookup(alias, *accepts_prefix, unit_name);
+            }
+            Statement::SetPreferredUnit(_, expr) => {
+                self.compile_expression(expr)?;
+                self.vm.add_op(Op::SetPreferredUnit);
+            }
             Statement::ProcedureCall(ProcedureKind::Type, args) => {
                 assert_eq!(args.len(), 1);
                 let arg = &args[0];
@@ -479,6 +728,19 @@ impl BytecodeInterpreter {
                 );
                 self.vm.add_op1(Op::PrintString, idx);
             }
+            Statement::ProcedureCall(ProcedureKind::DefineUncertainty, args) => {
+                let (uncertainty, constants) = args.split_first().unwrap();
+                self.compile_expression(uncertainty)?;
+
+                let names = constants
+                    .iter()
+                    .map(|constant| match constant {
+                        Expression::Identifier(_, name, _) => name.clone(),
+                        _ => unreachable!("uncertainties are only defined for variables"),
+                    })
+                    .collect::<Vec<_>>();
+                self.attach_uncertainty(&names);
+            }
             Statement::ProcedureCall(kind, args) => {
                 // Put all arguments on top of the stack
                 for arg in args {
@@ -511,6 +773,7 @@ impl BytecodeInterpreter {
     fn run(&mut self, settings: &mut InterpreterSettings) -> Result<InterpreterResult> {
         let mut ctx = ExecutionContext {
             print_fn: &mut settings.print_fn,
+            trace_fn: settings.trace_fn.as_deref_mut(),
         };
 
         self.vm.disassemble();
@@ -518,9 +781,9 @@ impl BytecodeInterpreter {
         let result = self.vm.run(&mut ctx);
 
         let result = match result {
-            Ok(InterpreterResult::Value(Value::Quantity(q))) => {
-                Ok(InterpreterResult::Value(Value::Quantity(q.full_simplify())))
-            }
+            Ok(InterpreterResult::Value(Value::Quantity(q))) => Ok(InterpreterResult::Value(
+                Value::Quantity(self.vm.simplify(&q)),
+            )),
             r => r,
         };
 
@@ -533,6 +796,136 @@ impl BytecodeInterpreter {
         self.vm.set_debug(activate);
     }
 
+    pub(crate) fn instruction_count(&self) -> u64 {
+        self.vm.instruction_count()
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.vm.allocated_bytes()
+    }
+
+    pub(crate) fn reset_allocated_bytes(&mut self) {
+        self.vm.reset_allocated_bytes();
+    }
+
+    pub(crate) fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.vm.set_memory_limit(limit);
+    }
+
+    pub(crate) fn set_interrupt_flag(&mut self, flag: &InterruptFlag) {
+        self.vm.set_interrupt_flag(flag);
+    }
+
+    pub(crate) fn set_random_seed(&mut self, seed: u64) {
+        self.vm.set_random_seed(seed);
+    }
+
+    pub(crate) fn set_derived_unit_simplification(&mut self, enable: bool) {
+        self.vm.set_derived_unit_simplification(enable);
+    }
+
+    pub(crate) fn unit_lookup(&self) -> &UnitLookup {
+        self.vm.unit_lookup()
+    }
+
+    pub(crate) fn set_file_system_access(&mut self, allow: bool) {
+        self.vm.set_file_system_access(allow);
+    }
+
+    pub(crate) fn set_network_access(&mut self, allow: bool) {
+        self.vm.set_network_access(allow);
+    }
+
+    pub(crate) fn set_environment_access(&mut self, allow: bool) {
+        self.vm.set_environment_access(allow);
+    }
+
+    pub(crate) fn set_script_arguments(&mut self, arguments: Vec<String>) {
+        self.vm.set_script_arguments(arguments);
+    }
+
+    pub(crate) fn set_function_inlining(&mut self, enable: bool) {
+        self.inlining = enable;
+    }
+
+    /// Remember the current position in the bytecode, such that the code of
+    /// the next input (which might consist of several calls to
+    /// `interpret_statements`) can be listed via [`Self::disassemble_last`].
+    pub(crate) fn start_input(&mut self) {
+        self.last_position = self.vm.position();
+        self.discarded_listing = None;
+    }
+
+    /// Keep the listing of the most recent input after the state of the
+    /// interpreter has been reverted to `earlier`.
+    pub(crate) fn revert_to(&mut self, earlier: BytecodeInterpreter) {
+        let listing = self.disassemble_last();
+        *self = earlier;
+        self.discarded_listing = Some(listing);
+    }
+
+    pub(crate) fn disassemble_last(&self) -> String {
+        match &self.discarded_listing {
+            Some(listing) => listing.clone(),
+            None => self.vm.disassemble_since(self.last_position),
+        }
+    }
+
+    pub(crate) fn disassemble_function(&self, name: &str) -> Option<String> {
+        self.vm.disassemble_function(name)
+    }
+
+    /// Index of a (non-foreign) function in the VM
+    fn function_index(&self, name: &str) -> Option<u16> {
+        (self.functions.get(name) == Some(&false)).then(|| self.vm.get_function_idx(name))
+    }
+
+    /// Position of a global variable in the scope of the VM
+    fn global_index(&self, name: &str) -> Option<u16> {
+        self.locals[0]
+            .iter()
+            .rposition(|l| l.identifier == name)
+            .map(|position| position as u16)
+    }
+
+    fn unit_index(&self, unit_name: &str) -> u16 {
+        self.unit_name_to_constant_index[unit_name]
+    }
+
+    /// Returns the expression that a call to the function `name` can be
+    /// replaced with, if the function can be inlined.
+    fn inlined_body(&self, name: &str, args: &[Expression]) -> Option<Expression> {
+        if !self.inlining || self.inlining_depth >= MAX_INLINING_DEPTH {
+            return None;
+        }
+
+        // Parameters and local variables at the call site take precedence
+        // over the global variables that the body refers to
+        let current_depth = self.current_depth();
+        let global_index = |name: &str| {
+            let is_local = current_depth > 0
+                && self.locals[current_depth]
+                    .iter()
+                    .any(|l| l.identifier == name);
+            if is_local {
+                None
+            } else {
+                self.global_index(name)
+            }
+        };
+
+        let candidate = self.inline_candidates.get(name)?;
+        if !candidate.is_up_to_date(
+            |f| self.function_index(f),
+            global_index,
+            |u| self.unit_index(u),
+        ) {
+            return None;
+        }
+
+        candidate.instantiate(args)
+    }
+
     fn current_depth(&self) -> usize {
         self.locals.len() - 1
     }
@@ -550,6 +943,21 @@ impl BytecodeInterpreter {
     pub fn lookup_global(&self, name: &str) -> Option<&Local> {
         self.locals[0].iter().find(|l| l.identifier == name)
     }
+
+    /// The current value of a global variable, simplified like a result
+    pub(crate) fn global_value(&self, name: &str) -> Option<Value> {
+        let slot = self.locals[0].iter().rposition(|l| l.identifier == name)?;
+        match self.vm.global(slot)? {
+            Value::Quantity(q) => Some(Value::Quantity(self.vm.simplify(q))),
+            value => Some(value.clone()),
+        }
+    }
+
+    /// The standard uncertainty of a global variable, if it is known
+    pub(crate) fn global_uncertainty(&self, name: &str) -> Option<Value> {
+        let local = self.locals[0].iter().rfind(|l| l.identifier == name)?;
+        self.global_value(local.uncertainty.as_ref()?)
+    }
 }
 
 impl Interpreter for BytecodeInterpreter {
@@ -559,6 +967,11 @@ impl Interpreter for BytecodeInterpreter {
             locals: vec![vec![]],
             unit_name_to_constant_index: HashMap::new(),
             functions: HashMap::new(),
+            inline_candidates: HashMap::new(),
+            inlining: true,
+            inlining_depth: 0,
+            last_position: BytecodePosition::default(),
+            discarded_listing: None,
         }
     }
 
@@ -568,6 +981,8 @@ impl Interpreter for BytecodeInterpreter {
         statements: &[Statement],
         dimension_registry: &DimensionRegistry,
     ) -> Result<InterpreterResult> {
+        self.vm.set_record_spans(settings.trace_fn.is_some());
+
         for statement in statements {
             self.compile_statement(statement, dimension_registry)?;
         }
@@ -0,0 +1,513 @@
+//! Translation of syntax from other unit calculators, namely Qalculate! (`qalc`)
+//! and GNU `units(1)`, to Numbat code. This is enabled with
+//! [`crate::Context::set_compatibility_syntax`]. Every translation is reported
+//! as a [`Warning::CompatibilitySyntax`], so that users can learn the Numbat
+//! way of writing it.
+//!
+//! The following constructs are translated:
+//! - Sums of quantities in mixed units like `5 ft 3 in`, which would be a
+//!   product in Numbat, are written as `(5 ft + 3 in)`.
+//! - Absolute values like `|x - 3|` are written as `abs(x - 3)`.
+//! - Fractions in the GNU units notation like `1|3` are written as `(1/3)`.
+//! - Exponents in the GNU units notation like `cm3` are written as `cm^3`,
+//!   unless the identifier is a variable or function, like `t0` or `m2`.
+//!
+//! Conversions like `in->cm` or `5 ft to m` need no translation.
+
+use std::collections::HashSet;
+
+use crate::span::{SourceCodePositition, Span};
+use crate::tokenizer::{is_identifier_continue, is_identifier_start};
+use crate::warning::Warning;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Whitespace,
+    Number,
+    Identifier,
+    /// A single `|` (not part of `||` or `|>`)
+    Bar,
+    /// A translated, parenthesized group
+    Group,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct Lexeme {
+    kind: Kind,
+    text: String,
+    /// The text before translation
+    original: String,
+}
+
+impl Lexeme {
+    fn new(kind: Kind, text: String) -> Self {
+        Lexeme {
+            kind,
+            original: text.clone(),
+            text,
+        }
+    }
+}
+
+/// Split the input into lexemes. This does not need to be a full tokenizer, but
+/// it needs to keep strings and comments in one piece.
+fn lex(code: &str) -> Vec<Lexeme> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut lexemes = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        i += 1;
+
+        let kind = match c {
+            c if c.is_whitespace() => {
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                Kind::Whitespace
+            }
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                Kind::Other
+            }
+            '"' => {
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                Kind::Other
+            }
+            '0' if i < chars.len() && matches!(chars[i], 'x' | 'o' | 'b') => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Kind::Number
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && chars.get(i).is_some_and(char::is_ascii_digit)) =>
+            {
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit() || matches!(chars[i], '.' | '_'))
+                {
+                    i += 1;
+                }
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let mut j = i + 1;
+                    if j < chars.len() && matches!(chars[j], '+' | '-') {
+                        j += 1;
+                    }
+                    if j < chars.len() && chars[j].is_ascii_digit() {
+                        i = j;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                Kind::Number
+            }
+            c if is_identifier_start(c) => {
+                while i < chars.len() && is_identifier_continue(chars[i]) {
+                    i += 1;
+                }
+                Kind::Identifier
+            }
+            '|' if i < chars.len() && matches!(chars[i], '|' | '>') => {
+                i += 1;
+                Kind::Other
+            }
+            '|' => Kind::Bar,
+            '*' if i < chars.len() && chars[i] == '*' => {
+                i += 1;
+                Kind::Other
+            }
+            '-' if i < chars.len() && chars[i] == '>' => {
+                i += 1;
+                Kind::Other
+            }
+            _ => Kind::Other,
+        };
+
+        lexemes.push(Lexeme::new(kind, chars[start..i].iter().collect()));
+    }
+
+    lexemes
+}
+
+fn is_exponent(lexeme: Option<&Lexeme>) -> bool {
+    lexeme.is_some_and(|l| {
+        l.text == "^"
+            || l.text == "**"
+            || l.text
+                .starts_with(['²', '³', '¹', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹', '⁰', '⁻'])
+    })
+}
+
+/// Try to match a term like `5 ft` at position `i`. Returns the position after
+/// the term.
+fn match_term(lexemes: &[Lexeme], i: usize, is_unit: &impl Fn(&str) -> bool) -> Option<usize> {
+    if lexemes.get(i)?.kind != Kind::Number {
+        return None;
+    }
+    let mut j = i + 1;
+    if lexemes.get(j)?.kind == Kind::Whitespace {
+        j += 1;
+    }
+    let unit = lexemes.get(j)?;
+    if unit.kind != Kind::Identifier || !is_unit(&unit.text) || is_exponent(lexemes.get(j + 1)) {
+        return None;
+    }
+    Some(j + 1)
+}
+
+fn concat(lexemes: &[Lexeme], original: bool) -> String {
+    lexemes
+        .iter()
+        .map(|l| if original { &l.original } else { &l.text })
+        .map(String::as_str)
+        .collect()
+}
+
+/// Names that are defined in the code itself, like `m2` in `let m2 = …`, `t0`
+/// in `fn f(t0) = …` or `h2` in `… where h2 = …`.
+fn defined_names(lexemes: &[Lexeme]) -> HashSet<String> {
+    let tokens: Vec<&Lexeme> = lexemes
+        .iter()
+        .filter(|l| l.kind != Kind::Whitespace)
+        .collect();
+    let mut names = HashSet::new();
+    let mut expects_parameter_list = false;
+    let mut in_parameter_list = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|j| tokens[j].text.as_str());
+        if token.kind == Kind::Identifier
+            && ((in_parameter_list && matches!(previous, Some("(" | ",")))
+                || matches!(previous, Some("let" | "fn" | "where" | "and")))
+        {
+            names.insert(token.text.clone());
+        }
+
+        match token.text.as_str() {
+            "fn" => expects_parameter_list = true,
+            "(" if expects_parameter_list => {
+                expects_parameter_list = false;
+                in_parameter_list = true;
+            }
+            ")" => in_parameter_list = false,
+            _ => {}
+        }
+    }
+
+    names
+}
+
+/// Translate `units(1)` exponents like `cm3` to `cm^3`.
+fn translate_exponents(
+    lexemes: &mut [Lexeme],
+    is_unit: &impl Fn(&str) -> bool,
+    is_defined: &impl Fn(&str) -> bool,
+) {
+    let defined_names = defined_names(lexemes);
+    for lexeme in lexemes {
+        if lexeme.kind != Kind::Identifier
+            || is_unit(&lexeme.text)
+            || is_defined(&lexeme.text)
+            || defined_names.contains(&lexeme.text)
+        {
+            continue;
+        }
+        let base = lexeme.text.trim_end_matches(|c: char| c.is_ascii_digit());
+        if base.len() < lexeme.text.len() && is_unit(base) {
+            lexeme.text = format!("{base}^{}", &lexeme.text[base.len()..]);
+        }
+    }
+}
+
+/// Translate fractions like `1|3` and sums in mixed units like `5 ft 3 in`.
+fn translate_groups(lexemes: Vec<Lexeme>, is_unit: &impl Fn(&str) -> bool) -> Vec<Lexeme> {
+    let mut output = vec![];
+    let mut i = 0;
+
+    while i < lexemes.len() {
+        if lexemes[i].kind == Kind::Number
+            && lexemes.get(i + 1).is_some_and(|l| l.kind == Kind::Bar)
+            && lexemes.get(i + 2).is_some_and(|l| l.kind == Kind::Number)
+        {
+            output.push(Lexeme {
+                kind: Kind::Group,
+                text: format!("({}/{})", lexemes[i].text, lexemes[i + 2].text),
+                original: concat(&lexemes[i..i + 3], true),
+            });
+            i += 3;
+            continue;
+        }
+
+        let mut terms = vec![];
+        let mut end = i;
+        while let Some(term_end) = match_term(&lexemes, end, is_unit) {
+            terms.push(concat(&lexemes[end..term_end], false));
+            end = term_end;
+            if lexemes.get(end).is_some_and(|l| l.kind == Kind::Whitespace)
+                && match_term(&lexemes, end + 1, is_unit).is_some()
+            {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        if terms.len() >= 2 {
+            output.push(Lexeme {
+                kind: Kind::Group,
+                text: format!("({})", terms.join(" + ")),
+                original: concat(&lexemes[i..end], true),
+            });
+            i = end;
+        } else {
+            output.push(lexemes[i].clone());
+            i += 1;
+        }
+    }
+
+    output
+}
+
+/// Translate absolute values like `|x|` to `abs(x)`. A bar opens an absolute
+/// value if it appears where an operand is expected (or if no absolute value
+/// is open), and closes it otherwise.
+/// Returns `None` if the bars are unbalanced.
+fn translate_bars(lexemes: Vec<Lexeme>) -> Option<Vec<Lexeme>> {
+    let mut output: Vec<Lexeme> = vec![];
+    let mut open = vec![];
+
+    for lexeme in lexemes {
+        if lexeme.kind != Kind::Bar {
+            output.push(lexeme);
+            continue;
+        }
+
+        let previous = output.iter().rev().find(|l| l.kind != Kind::Whitespace);
+        let expects_operand = match previous {
+            None => true,
+            Some(l) => l.kind == Kind::Other && !matches!(l.text.as_str(), ")" | "]" | "}"),
+        };
+
+        if expects_operand || open.is_empty() {
+            open.push(output.len());
+            output.push(Lexeme {
+                kind: Kind::Other,
+                text: "abs(".into(),
+                original: lexeme.original,
+            });
+        } else {
+            let start = open.pop()?;
+            let inner = output.split_off(start);
+            output.push(Lexeme {
+                kind: Kind::Group,
+                text: format!("{})", concat(&inner, false)),
+                original: format!("{}{}", concat(&inner, true), lexeme.original),
+            });
+        }
+    }
+
+    open.is_empty().then_some(output)
+}
+
+/// The result of [`translate`]
+pub(crate) struct Translation {
+    pub code: String,
+    /// One warning per translated construct
+    pub warnings: Vec<Warning>,
+    /// The byte offsets of every lexeme in the translated and the original
+    /// code, along with the length of the original lexeme
+    offsets: Vec<(usize, usize, usize)>,
+}
+
+impl Translation {
+    /// Map a span in the translated code back to the original code. Spans
+    /// inside of a translated construct are mapped to its start.
+    pub fn original_span(&self, original_code: &str, span: Span) -> Span {
+        Span {
+            start: self.original_position(original_code, span.start),
+            end: self.original_position(original_code, span.end),
+            code_source_id: span.code_source_id,
+        }
+    }
+
+    fn original_position(
+        &self,
+        original_code: &str,
+        position: SourceCodePositition,
+    ) -> SourceCodePositition {
+        let byte = position.byte as usize;
+        let original_byte = match self
+            .offsets
+            .iter()
+            .rev()
+            .find(|(start, _, _)| *start <= byte)
+        {
+            Some((start, original_start, original_len)) => {
+                original_start + (byte - start).min(*original_len)
+            }
+            None => byte,
+        };
+
+        let mut original = SourceCodePositition::start();
+        for c in original_code[..original_byte.min(original_code.len())].chars() {
+            original.byte += c.len_utf8() as u32;
+            if c == '\n' {
+                original.line += 1;
+                original.position = 1;
+            } else {
+                original.position += 1;
+            }
+        }
+        original
+    }
+}
+
+/// Translate the given code to Numbat syntax. `is_unit` decides whether or not
+/// an identifier refers to a unit, and `is_defined` whether it refers to a
+/// variable or function that has been defined before.
+pub(crate) fn translate(
+    code: &str,
+    is_unit: impl Fn(&str) -> bool,
+    is_defined: impl Fn(&str) -> bool,
+) -> Translation {
+    let mut lexemes = lex(code);
+    translate_exponents(&mut lexemes, &is_unit, &is_defined);
+    let lexemes = translate_groups(lexemes, &is_unit);
+    let lexemes = translate_bars(lexemes.clone()).unwrap_or(lexemes);
+
+    let warnings = lexemes
+        .iter()
+        .filter(|l| l.text != l.original)
+        .map(|l| Warning::CompatibilitySyntax {
+            original: l.original.clone(),
+            replacement: l.text.clone(),
+        })
+        .collect();
+
+    let mut offsets = vec![];
+    let (mut start, mut original_start) = (0, 0);
+    for lexeme in &lexemes {
+        offsets.push((start, original_start, lexeme.original.len()));
+        start += lexeme.text.len();
+        original_start += lexeme.original.len();
+    }
+
+    Translation {
+        code: concat(&lexemes, false),
+        warnings,
+        offsets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate_code(code: &str) -> (String, Vec<String>) {
+        let translation = translate(
+            code,
+            |name| ["ft", "in", "cm", "m", "s", "h", "lb", "oz"].contains(&name),
+            |name| name == "t0",
+        );
+        let warnings = translation.warnings.iter().map(|w| w.to_string());
+        (translation.code, warnings.collect())
+    }
+
+    #[test]
+    fn mixed_units() {
+        assert_eq!(translate_code("5 ft 3 in").0, "(5 ft + 3 in)");
+        assert_eq!(translate_code("5ft 3in -> cm").0, "(5ft + 3in) -> cm");
+        assert_eq!(translate_code("2 * 1 lb 4 oz").0, "2 * (1 lb + 4 oz)");
+        assert_eq!(
+            translate_code("5 ft 3 in").1,
+            vec!["'5 ft 3 in' is interpreted as '(5 ft + 3 in)'"]
+        );
+
+        assert_eq!(translate_code("3 m 2 m²").0, "3 m 2 m²");
+        assert_eq!(translate_code("3 x 2 y").0, "3 x 2 y");
+        assert_eq!(translate_code("5 ft").0, "5 ft");
+    }
+
+    #[test]
+    fn absolute_values() {
+        assert_eq!(translate_code("|x - 3|").0, "abs(x - 3)");
+        assert_eq!(translate_code("2 |-5 m|").0, "2 abs(-5 m)");
+        assert_eq!(translate_code("|a| + |b|").0, "abs(a) + abs(b)");
+        assert_eq!(translate_code("|5 ft 3 in|").0, "abs((5 ft + 3 in))");
+        assert_eq!(translate_code("|x - 3|").1.len(), 1);
+
+        assert_eq!(translate_code("x || y").0, "x || y");
+        assert_eq!(translate_code("x |> f").0, "x |> f");
+        assert_eq!(translate_code("|x").0, "|x");
+    }
+
+    #[test]
+    fn gnu_units_syntax() {
+        assert_eq!(translate_code("1|3 ft").0, "(1/3) ft");
+        assert_eq!(translate_code("10 cm3").0, "10 cm^3");
+        assert_eq!(translate_code("x3").0, "x3");
+        assert_eq!(translate_code("in->cm").0, "in->cm");
+    }
+
+    #[test]
+    fn defined_names_are_not_exponents() {
+        assert_eq!(translate_code("t0 + 2 s").0, "t0 + 2 s");
+        assert_eq!(translate_code("let m2 = 3\nm2 + 1").0, "let m2 = 3\nm2 + 1");
+        assert_eq!(
+            translate_code("fn f(s1, x) = s1 × x\nf(2, 3)").0,
+            "fn f(s1, x) = s1 × x\nf(2, 3)"
+        );
+        assert_eq!(
+            translate_code("h2 × cm3 where h2 = 2").0,
+            "h2 × cm^3 where h2 = 2"
+        );
+        assert_eq!(translate_code("f(m2)").0, "f(m^2)");
+    }
+
+    #[test]
+    fn original_spans() {
+        let code = "|x| + y +/ 2";
+        let translation = translate(code, |_| false, |_| false);
+        assert_eq!(translation.code, "abs(x) + y +/ 2");
+
+        let position = |byte: u32| SourceCodePositition {
+            byte,
+            line: 1,
+            position: byte + 1,
+        };
+        let span = Span {
+            start: position(12),
+            end: position(13),
+            code_source_id: 0,
+        };
+        assert_eq!(
+            translation.original_span(code, span),
+            Span {
+                start: position(9),
+                end: position(10),
+                code_source_id: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn strings_and_comments() {
+        assert_eq!(
+            translate_code("\"|x|\" # 5 ft 3 in").0,
+            "\"|x|\" # 5 ft 3 in"
+        );
+        assert!(translate_code("\"|x|\"").1.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+//! Reading and writing of comma-separated values (RFC 4180)
+
+/// Split CSV data into records. Fields can be quoted, which allows them to
+/// contain commas, line breaks and (doubled) quotes. Empty lines are skipped.
+pub(crate) fn parse(input: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("unterminated quoted field".into());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records.retain(|record| record.len() > 1 || !record[0].is_empty());
+    Ok(records)
+}
+
+/// Quote a field if necessary
+pub(crate) fn escape_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_records() {
+        assert_eq!(
+            parse("a,b\n1,\"x, \"\"y\"\"\"\r\n\n2,\"multi\nline\"").unwrap(),
+            vec![
+                vec!["a", "b"],
+                vec!["1", "x, \"y\""],
+                vec!["2", "multi\nline"]
+            ]
+        );
+        assert_eq!(parse("").unwrap(), Vec::<Vec<String>>::new());
+        assert!(parse("a,\"b").is_err());
+    }
+
+    #[test]
+    fn escape_fields() {
+        assert_eq!(escape_field("plain"), "plain");
+        assert_eq!(escape_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
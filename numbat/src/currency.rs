@@ -1,4 +1,11 @@
 use std::sync::{Mutex, MutexGuard, OnceLock};
+#[cfg(feature = "fetch-exchangerates")]
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
 
 use numbat_exchange_rates::parse_exchange_rates;
 
@@ -51,7 +58,66 @@ impl ExchangeRatesCache {
         EXCHANGE_RATES.get().unwrap().lock().unwrap()
     }
 
+    /// Returns true if the exchange rates have already been loaded successfully.
+    /// In contrast to [`Self::fetch`], this never blocks.
+    pub fn is_available() -> bool {
+        EXCHANGE_RATES
+            .get()
+            .and_then(|rates| rates.try_lock().ok().map(|rates| rates.is_some()))
+            .unwrap_or(false)
+    }
+
+    /// Fetch the exchange rates in a background thread. The returned future
+    /// does not depend on a specific async runtime.
+    #[cfg(feature = "fetch-exchangerates")]
+    pub fn fetch_in_background() -> FetchExchangeRates {
+        let state = Arc::new(Mutex::new(FetchState::default()));
+
+        let state_c = state.clone();
+        std::thread::spawn(move || {
+            let available = Self::fetch().is_some();
+
+            let mut state = state_c.lock().unwrap();
+            state.result = Some(available);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        FetchExchangeRates { state }
+    }
+
     pub fn use_test_rates() {
         EXCHANGE_RATES.get_or_init(|| Mutex::new(Some(ExchangeRates::TestRates)));
     }
 }
+
+#[cfg(feature = "fetch-exchangerates")]
+#[derive(Default)]
+struct FetchState {
+    result: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// A future that resolves once the exchange rates have been fetched. The output
+/// is `true` if the exchange rates are available.
+#[cfg(feature = "fetch-exchangerates")]
+pub struct FetchExchangeRates {
+    state: Arc<Mutex<FetchState>>,
+}
+
+#[cfg(feature = "fetch-exchangerates")]
+impl Future for FetchExchangeRates {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<bool> {
+        let mut state = self.state.lock().unwrap();
+        match state.result {
+            Some(available) => Poll::Ready(available),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -1,10 +1,43 @@
-use jiff::{civil::DateTime, fmt::rfc2822, tz::TimeZone, Timestamp, Zoned};
+use crate::RuntimeError;
+#[cfg(feature = "datetime")]
+use jiff::{
+    civil::DateTime,
+    fmt::rfc2822,
+    tz::{Offset, TimeZone},
+    Timestamp,
+};
+#[cfg(feature = "datetime")]
+use num_traits::ToPrimitive;
+#[cfg(feature = "datetime")]
 use std::str::FromStr;
 
+#[cfg(feature = "datetime")]
+pub use jiff::Zoned;
+
+/// Without the `datetime` feature, there is no way to create a datetime value.
+/// The functions that would return one fail with a runtime error instead.
+#[cfg(not(feature = "datetime"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zoned(());
+
+#[cfg(not(feature = "datetime"))]
+fn no_datetime_values() -> ! {
+    unreachable!("datetime values can not be created without the 'datetime' feature")
+}
+
+#[cfg(not(feature = "datetime"))]
+impl std::fmt::Display for Zoned {
+    fn fmt(&self, _f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        no_datetime_values()
+    }
+}
+
+#[cfg(feature = "datetime")]
 pub fn get_local_timezone_or_utc() -> TimeZone {
     TimeZone::system()
 }
 
+#[cfg(feature = "datetime")]
 pub fn parse_datetime(input: &str) -> Result<Zoned, jiff::Error> {
     if let zoned @ Ok(_) = Zoned::from_str(input) {
         return zoned;
@@ -68,6 +101,70 @@ pub fn parse_datetime(input: &str) -> Result<Zoned, jiff::Error> {
     Timestamp::from_str(input).map(|ts| ts.to_zoned(get_local_timezone_or_utc()))
 }
 
+/// Parse the contents of a date literal like `@2024-05-01T13:00Z` (without
+/// the `@`). The time can be left out to refer to midnight. Date literals
+/// without an offset or time zone annotation refer to the local time zone.
+#[cfg(feature = "datetime")]
+pub fn parse_datetime_literal(input: &str) -> Result<Zoned, String> {
+    if input.ends_with(']') {
+        return Zoned::from_str(input).map_err(|e| e.to_string());
+    }
+
+    let civil = |input: &str| DateTime::from_str(input).map_err(|e| e.to_string());
+
+    if let Some(input) = input.strip_suffix(['Z', 'z']) {
+        return civil(input)?
+            .to_zoned(TimeZone::UTC)
+            .map_err(|e| e.to_string());
+    }
+
+    // An offset like `+02:00` can only appear after the time, since the date
+    // itself contains dashes
+    let offset_start = input
+        .find(['T', 't'])
+        .and_then(|t| input[t..].rfind(['+', '-']).map(|i| t + i));
+    if let Some(offset_start) = offset_start {
+        let (datetime, offset) = input.split_at(offset_start);
+        let offset = parse_offset(offset)
+            .ok_or_else(|| format!("invalid UTC offset '{offset}', expected e.g. '+02:00'"))?;
+        return civil(datetime)?
+            .to_zoned(TimeZone::fixed(offset))
+            .map_err(|e| e.to_string());
+    }
+
+    civil(input)?
+        .to_zoned(get_local_timezone_or_utc())
+        .map_err(|e| e.to_string())
+}
+
+/// Parse a UTC offset like `+02:00`, `-0530` or `+01`
+#[cfg(feature = "datetime")]
+fn parse_offset(input: &str) -> Option<Offset> {
+    let (sign, rest) = match input.chars().next()? {
+        '+' => (1, &input[1..]),
+        '-' => (-1, &input[1..]),
+        _ => return None,
+    };
+
+    let digits = rest.replacen(':', "", 1);
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.as_str(), "0"),
+        4 => (&digits[..2], &digits[2..]),
+        _ => return None,
+    };
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+
+    Offset::from_seconds(sign * (hours * 3600 + minutes * 60)).ok()
+}
+
+#[cfg(feature = "datetime")]
 pub fn to_string(dt: &Zoned) -> String {
     let tz = dt.time_zone();
 
@@ -93,3 +190,389 @@ pub fn to_string(dt: &Zoned) -> String {
         format!("{dt_str} {abbreviation_and_offset}{timezone_name}")
     }
 }
+
+#[cfg(feature = "datetime")]
+fn seconds_to_span(seconds: f64) -> Result<jiff::Span, RuntimeError> {
+    let seconds_i64 = seconds.to_i64().ok_or(RuntimeError::DurationOutOfRange)?;
+
+    Ok(jiff::Span::new()
+        .try_seconds(seconds_i64)
+        .map_err(|_| RuntimeError::DurationOutOfRange)?
+        .nanoseconds((seconds.fract() * 1_000_000_000f64).round() as i64))
+}
+
+#[cfg(feature = "datetime")]
+pub fn add_seconds(dt: &Zoned, seconds: f64) -> Result<Zoned, RuntimeError> {
+    dt.checked_add(seconds_to_span(seconds)?)
+        .map_err(|_| RuntimeError::DateTimeOutOfRange)
+}
+
+#[cfg(feature = "datetime")]
+pub fn sub_seconds(dt: &Zoned, seconds: f64) -> Result<Zoned, RuntimeError> {
+    dt.checked_sub(seconds_to_span(seconds)?)
+        .map_err(|_| RuntimeError::DateTimeOutOfRange)
+}
+
+/// The number of seconds from `rhs` to `lhs`
+#[cfg(feature = "datetime")]
+pub fn seconds_between(lhs: &Zoned, rhs: &Zoned) -> Result<f64, RuntimeError> {
+    lhs.since(rhs)
+        .map_err(|_| RuntimeError::DateTimeOutOfRange)?
+        .total(jiff::Unit::Second)
+        .map_err(|_| RuntimeError::DurationOutOfRange)
+}
+
+#[cfg(feature = "datetime")]
+pub fn with_time_zone(dt: &Zoned, tz_name: String) -> Result<Zoned, RuntimeError> {
+    let tz = TimeZone::get(&tz_name).map_err(|_| RuntimeError::UnknownTimezone(tz_name))?;
+
+    Ok(dt.with_time_zone(tz))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn parse_datetime(_input: &str) -> Result<Zoned, RuntimeError> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn parse_datetime_literal(_input: &str) -> Result<Zoned, String> {
+    Err("date literals are not available (requires the 'datetime' feature)".into())
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn to_string(_dt: &Zoned) -> String {
+    no_datetime_values()
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn add_seconds(_dt: &Zoned, _seconds: f64) -> Result<Zoned, RuntimeError> {
+    no_datetime_values()
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn sub_seconds(_dt: &Zoned, _seconds: f64) -> Result<Zoned, RuntimeError> {
+    no_datetime_values()
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn seconds_between(_lhs: &Zoned, _rhs: &Zoned) -> Result<f64, RuntimeError> {
+    no_datetime_values()
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn with_time_zone(_dt: &Zoned, _tz_name: String) -> Result<Zoned, RuntimeError> {
+    no_datetime_values()
+}
+
+/// Parse a duration into a number of seconds. This supports ISO 8601 durations
+/// like `P1DT2H30M` or `PT0.5S`, as well as compact forms like `1h30m` or
+/// `2d 12h`. Years and months are treated like the `year` and `month` units,
+/// i.e. they refer to the average length of a (tropical) year and month.
+pub fn parse_duration(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    let (sign, unsigned) = match input.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, input.strip_prefix('+').unwrap_or(input)),
+    };
+
+    let seconds = if let Some(iso) = unsigned.strip_prefix(['P', 'p']) {
+        parse_iso8601_duration(iso)
+    } else {
+        parse_compact_duration(unsigned)
+    }
+    .ok_or_else(|| input.to_string())?;
+
+    Ok(sign * seconds)
+}
+
+const SECONDS_PER_MINUTE: f64 = 60.0;
+const SECONDS_PER_HOUR: f64 = 3600.0;
+const SECONDS_PER_DAY: f64 = 86400.0;
+const SECONDS_PER_WEEK: f64 = 7.0 * SECONDS_PER_DAY;
+const SECONDS_PER_YEAR: f64 = 365.242_188_1 * SECONDS_PER_DAY;
+const SECONDS_PER_MONTH: f64 = SECONDS_PER_YEAR / 12.0;
+
+/// Split off a leading decimal number (with a `.` or `,` as the decimal separator)
+fn split_number(input: &str) -> Option<(f64, &str)> {
+    let end = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(input.len());
+    if end == 0 {
+        return None;
+    }
+    let number = input[..end].replace(',', ".").parse().ok()?;
+    Some((number, &input[end..]))
+}
+
+fn parse_iso8601_duration(input: &str) -> Option<f64> {
+    let (date_part, time_part) = match input.split_once(['T', 't']) {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return None,
+        None => (input, None),
+    };
+
+    // Designators need to appear in this order, and each of them at most once
+    let parse_components = |mut input: &str, designators: &[(char, f64)]| {
+        let mut seconds = 0.0;
+        let mut remaining = designators;
+        while !input.is_empty() {
+            let (number, rest) = split_number(input)?;
+            let designator = rest.chars().next()?.to_ascii_uppercase();
+            let position = remaining.iter().position(|(d, _)| *d == designator)?;
+            seconds += number * remaining[position].1;
+            remaining = &remaining[position + 1..];
+            input = &rest[1..];
+        }
+        Some(seconds)
+    };
+
+    let date = parse_components(
+        date_part,
+        &[
+            ('Y', SECONDS_PER_YEAR),
+            ('M', SECONDS_PER_MONTH),
+            ('W', SECONDS_PER_WEEK),
+            ('D', SECONDS_PER_DAY),
+        ],
+    )?;
+    let time = match time_part {
+        Some(time_part) => parse_components(
+            time_part,
+            &[
+                ('H', SECONDS_PER_HOUR),
+                ('M', SECONDS_PER_MINUTE),
+                ('S', 1.0),
+            ],
+        )?,
+        None if date_part.is_empty() => return None,
+        None => 0.0,
+    };
+
+    Some(date + time)
+}
+
+fn parse_compact_duration(mut input: &str) -> Option<f64> {
+    let mut seconds = 0.0;
+    let mut any_component = false;
+
+    while !input.is_empty() {
+        let (number, rest) = split_number(input)?;
+        let rest = rest.trim_start();
+        let unit_end = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_end] {
+            "y" | "yr" | "year" | "years" => SECONDS_PER_YEAR,
+            "mo" | "month" | "months" => SECONDS_PER_MONTH,
+            "w" | "wk" | "week" | "weeks" => SECONDS_PER_WEEK,
+            "d" | "day" | "days" => SECONDS_PER_DAY,
+            "h" | "hr" | "hour" | "hours" => SECONDS_PER_HOUR,
+            "m" | "min" | "minute" | "minutes" => SECONDS_PER_MINUTE,
+            "s" | "sec" | "second" | "seconds" => 1.0,
+            "ms" => 1e-3,
+            "us" | "µs" | "μs" => 1e-6,
+            "ns" => 1e-9,
+            _ => return None,
+        };
+        seconds += number * factor;
+        any_component = true;
+        input = rest[unit_end..].trim_start();
+    }
+
+    any_component.then_some(seconds)
+}
+
+/// Format a number of seconds as an ISO 8601 duration like `P1DT2H30M`. Only
+/// days, hours, minutes and seconds are used, since years and months do not
+/// have a fixed length.
+pub fn to_iso8601_duration(seconds: f64) -> Option<String> {
+    const NANOSECONDS_PER_SECOND: i128 = 1_000_000_000;
+
+    let nanoseconds = (seconds * 1e9).round();
+    if !nanoseconds.is_finite() || nanoseconds.abs() >= 1e30 {
+        return None;
+    }
+    let nanoseconds = nanoseconds as i128;
+
+    let mut output = String::new();
+    if nanoseconds < 0 {
+        output.push('-');
+    }
+    output.push('P');
+
+    let nanoseconds = nanoseconds.abs();
+    let total_seconds = nanoseconds / NANOSECONDS_PER_SECOND;
+    let fraction = nanoseconds % NANOSECONDS_PER_SECOND;
+    let days = total_seconds / 86400;
+    let hours = total_seconds % 86400 / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 60;
+
+    if days > 0 {
+        output += &format!("{days}D");
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || fraction > 0 || days == 0 {
+        output.push('T');
+        if hours > 0 {
+            output += &format!("{hours}H");
+        }
+        if minutes > 0 {
+            output += &format!("{minutes}M");
+        }
+        if seconds > 0 || fraction > 0 || (hours == 0 && minutes == 0) {
+            output += &seconds.to_string();
+            if fraction > 0 {
+                let fraction = format!("{fraction:09}");
+                output += ".";
+                output += fraction.trim_end_matches('0');
+            }
+            output.push('S');
+        }
+    }
+
+    Some(output)
+}
+
+/// Parse a clock time like `1:23:45`, `23:45` or `0:00:01.5` into a number of
+/// seconds. Hours (if present) are not limited to 24 and can have any number of
+/// digits. Minutes and seconds have to be less than 60 and need two digits,
+/// unless the minutes are the leading component.
+pub fn parse_clock_time(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    let (sign, unsigned) = match input.strip_prefix('-') {
+        Some(rest) => (-1.0, rest),
+        None => (1.0, input.strip_prefix('+').unwrap_or(input)),
+    };
+
+    let seconds = parse_unsigned_clock_time(unsigned).ok_or_else(|| input.to_string())?;
+
+    Ok(sign * seconds)
+}
+
+fn parse_digits(input: &str, allowed_lengths: std::ops::RangeInclusive<usize>) -> Option<f64> {
+    if allowed_lengths.contains(&input.len()) && input.chars().all(|c| c.is_ascii_digit()) {
+        input.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn parse_unsigned_clock_time(input: &str) -> Option<f64> {
+    // The leading component may have fewer digits, e.g. `5:30` or `1:02:03`
+    let (hours, minutes, seconds) = match input.split(':').collect::<Vec<_>>()[..] {
+        [minutes, seconds] => (0.0, parse_digits(minutes, 1..=2)?, seconds),
+        [hours, minutes, seconds] => (
+            parse_digits(hours, 1..=usize::MAX)?,
+            parse_digits(minutes, 2..=2)?,
+            seconds,
+        ),
+        _ => return None,
+    };
+
+    let seconds = match seconds.split_once(['.', ',']) {
+        Some((whole, fraction)) => {
+            parse_digits(whole, 2..=2)?
+                + parse_digits(fraction, 1..=9)? / 10f64.powi(fraction.len() as i32)
+        }
+        None => parse_digits(seconds, 2..=2)?,
+    };
+
+    (minutes < 60.0 && seconds < 60.0)
+        .then_some(hours * SECONDS_PER_HOUR + minutes * SECONDS_PER_MINUTE + seconds)
+}
+
+/// Format a number of seconds as a clock time like `01:02:05`. Milliseconds are
+/// only shown if the duration is not an integer number of seconds (after
+/// rounding to milliseconds), e.g. `00:00:01.500`.
+pub fn to_clock_time(seconds: f64) -> Option<String> {
+    let milliseconds = (seconds * 1e3).round();
+    if !milliseconds.is_finite() || milliseconds.abs() >= 1e30 {
+        return None;
+    }
+    let milliseconds = milliseconds as i128;
+
+    let sign = if milliseconds < 0 { "-" } else { "" };
+    let milliseconds = milliseconds.abs();
+    let total_seconds = milliseconds / 1000;
+    let fraction = milliseconds % 1000;
+    let hours = total_seconds / 3600;
+    let minutes = total_seconds % 3600 / 60;
+    let seconds = total_seconds % 60;
+
+    Some(if fraction > 0 {
+        format!("{sign}{hours:02}:{minutes:02}:{seconds:02}.{fraction:03}")
+    } else {
+        format!("{sign}{hours:02}:{minutes:02}:{seconds:02}")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("PT0S"), Ok(0.0));
+        assert_eq!(parse_duration("P1DT2H30M"), Ok(95400.0));
+        assert_eq!(parse_duration("PT1.5S"), Ok(1.5));
+        assert_eq!(parse_duration("PT0,5S"), Ok(0.5));
+        assert_eq!(parse_duration("P2W"), Ok(1_209_600.0));
+        assert_eq!(parse_duration("-PT5M"), Ok(-300.0));
+        assert_eq!(parse_duration("P1M"), Ok(SECONDS_PER_MONTH));
+        assert_eq!(parse_duration("PT1M"), Ok(60.0));
+
+        assert_eq!(parse_duration("1h30m"), Ok(5400.0));
+        assert_eq!(parse_duration("2d 12h"), Ok(216_000.0));
+        assert_eq!(parse_duration("1.5 s"), Ok(1.5));
+        assert_eq!(parse_duration("90min"), Ok(5400.0));
+        assert_eq!(parse_duration("250ms"), Ok(0.25));
+
+        for invalid in [
+            "", "P", "PT", "P1H", "PT1D", "P1D2D", "PT1S2M", "1x", "h", "1h30",
+        ] {
+            assert!(parse_duration(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn iso8601_durations() {
+        assert_eq!(to_iso8601_duration(0.0).unwrap(), "PT0S");
+        assert_eq!(to_iso8601_duration(95400.0).unwrap(), "P1DT2H30M");
+        assert_eq!(to_iso8601_duration(86400.0).unwrap(), "P1D");
+        assert_eq!(to_iso8601_duration(3600.5).unwrap(), "PT1H0.5S");
+        assert_eq!(to_iso8601_duration(-300.0).unwrap(), "-PT5M");
+        assert_eq!(to_iso8601_duration(1e-9).unwrap(), "PT0.000000001S");
+        assert_eq!(to_iso8601_duration(f64::NAN), None);
+    }
+
+    #[test]
+    fn clock_times() {
+        assert_eq!(parse_clock_time("1:23:45"), Ok(5025.0));
+        assert_eq!(parse_clock_time("01:23:45"), Ok(5025.0));
+        assert_eq!(parse_clock_time("23:45"), Ok(1425.0));
+        assert_eq!(parse_clock_time("5:30"), Ok(330.0));
+        assert_eq!(parse_clock_time("0:00:01.5"), Ok(1.5));
+        assert_eq!(parse_clock_time("100:00:00"), Ok(360_000.0));
+        assert_eq!(parse_clock_time("-0:05:00"), Ok(-300.0));
+
+        for invalid in [
+            "",
+            "1",
+            "1:2:3",
+            "1:60:00",
+            "1:00:60",
+            "1:00:00.",
+            "a:00:00",
+            "1:00:00:00",
+        ] {
+            assert!(parse_clock_time(invalid).is_err(), "{invalid}");
+        }
+
+        assert_eq!(to_clock_time(0.0).unwrap(), "00:00:00");
+        assert_eq!(to_clock_time(3725.0).unwrap(), "01:02:05");
+        assert_eq!(to_clock_time(1.5).unwrap(), "00:00:01.500");
+        assert_eq!(to_clock_time(360_000.0).unwrap(), "100:00:00");
+        assert_eq!(to_clock_time(-300.0).unwrap(), "-00:05:00");
+        assert_eq!(to_clock_time(f64::NAN), None);
+    }
+}
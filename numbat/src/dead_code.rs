@@ -0,0 +1,246 @@
+//! Find functions, variables and units that are defined but never used, and
+//! branches of `if … then … else …` expressions that can never be taken. This
+//! works on the program after modules have been inlined and identifiers have
+//! been resolved, such that units are found by their full name, regardless of
+//! the prefix or alias that has been used.
+
+use std::collections::HashSet;
+
+use crate::ast::{DefineVariable, Expression, ProcedureKind, Statement, StringPart};
+use crate::decorator::{self, Decorator};
+use crate::span::Span;
+use crate::symbol::Symbol;
+use crate::warning::Warning;
+
+struct Definition<'a> {
+    span: Span,
+    kind: &'static str,
+    name: &'a Symbol,
+    decorators: &'a [Decorator],
+}
+
+impl Definition<'_> {
+    /// Private items (marked with `@private`, or with a leading underscore by
+    /// convention) can only be used from within their own module.
+    fn is_private(&self) -> bool {
+        decorator::is_private(self.decorators) || self.name.starts_with('_')
+    }
+}
+
+#[derive(Default)]
+struct Usages<'a> {
+    names: HashSet<&'a str>,
+    unreachable_branches: Vec<Warning>,
+}
+
+impl<'a> Usages<'a> {
+    fn statement(&mut self, statement: &'a Statement) {
+        match statement {
+            Statement::Expression(expr) => self.expression(expr, &[]),
+            Statement::DefineVariable(DefineVariable { expr, .. }) => self.expression(expr, &[]),
+            Statement::DefineFunction {
+                function_name,
+                parameters,
+                body,
+                local_variables,
+                ..
+            } => {
+                // Parameters and local variables shadow global names
+                let local_names: Vec<&str> = parameters
+                    .iter()
+                    .map(|(_, name, _)| name.as_str())
+                    .chain(local_variables.iter().map(|def| def.identifier.as_str()))
+                    .collect();
+
+                // Recursive calls do not count as usages
+                let mut usages = Usages::default();
+                for def in local_variables {
+                    usages.expression(&def.expr, &local_names);
+                }
+                if let Some(body) = body {
+                    usages.expression(body, &local_names);
+                }
+                usages.names.remove(function_name.as_str());
+
+                self.names.extend(usages.names);
+                self.unreachable_branches
+                    .extend(usages.unreachable_branches);
+            }
+            Statement::DefineDerivedUnit { expr, .. } => self.expression(expr, &[]),
+            Statement::DefineUnitAlias { unit, .. } => {
+                self.names.insert(unit.as_str());
+            }
+            Statement::SetPreferredUnit { expr, .. } => self.expression(expr, &[]),
+            Statement::ProcedureCall(_, ProcedureKind::DefineUncertainty, args) => {
+                // Attaching an uncertainty to a constant is not a usage
+                self.expression(&args[0], &[]);
+            }
+            Statement::ProcedureCall(_, _, args) => {
+                for arg in args {
+                    self.expression(arg, &[]);
+                }
+            }
+            Statement::DefineDimension(..)
+            | Statement::DefineBaseUnit(..)
+            | Statement::ModuleImport(..)
+            | Statement::DefineStruct { .. } => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expression, local_names: &[&str]) {
+        match expr {
+            Expression::Identifier(_, name) => {
+                if !local_names.contains(&name.as_str()) {
+                    self.names.insert(name.as_str());
+                }
+            }
+            Expression::UnitIdentifier(_, _, _, full_name) => {
+                self.names.insert(full_name.as_str());
+            }
+            Expression::Scalar(..)
+            | Expression::TypedHole(_)
+            | Expression::Boolean(..)
+            | Expression::DateTime(..) => {}
+            Expression::UnaryOperator { expr, .. } => self.expression(expr, local_names),
+            Expression::BinaryOperator { lhs, rhs, .. } => {
+                self.expression(lhs, local_names);
+                self.expression(rhs, local_names);
+            }
+            Expression::FunctionCall(_, _, callee, args) => {
+                self.expression(callee, local_names);
+                for arg in args {
+                    self.expression(arg, local_names);
+                }
+            }
+            Expression::String(_, parts) => {
+                for part in parts {
+                    if let StringPart::Interpolation { expr, .. } = part {
+                        self.expression(expr, local_names);
+                    }
+                }
+            }
+            Expression::Condition(_, condition, then, else_) => {
+                if let Expression::Boolean(_, value) = condition.as_ref() {
+                    let unreachable = if *value { else_ } else { then };
+                    self.unreachable_branches.push(Warning::UnreachableBranch {
+                        span: unreachable.full_span(),
+                        condition: *value,
+                    });
+                }
+
+                self.expression(condition, local_names);
+                self.expression(then, local_names);
+                self.expression(else_, local_names);
+            }
+            Expression::InstantiateStruct { fields, .. } => {
+                for (_, _, expr) in fields {
+                    self.expression(expr, local_names);
+                }
+            }
+            Expression::AccessField(_, _, expr, _) => self.expression(expr, local_names),
+            Expression::UpdateStruct { expr, fields, .. } => {
+                self.expression(expr, local_names);
+                for (_, _, value) in fields {
+                    self.expression(value, local_names);
+                }
+            }
+            Expression::List(_, elements) => {
+                for element in elements {
+                    self.expression(element, local_names);
+                }
+            }
+            Expression::Dict(_, entries) => {
+                for (key, value) in entries {
+                    self.expression(key, local_names);
+                    self.expression(value, local_names);
+                }
+            }
+            Expression::Range {
+                start, end, step, ..
+            } => {
+                self.expression(start, local_names);
+                self.expression(end, local_names);
+                if let Some(step) = step {
+                    self.expression(step, local_names);
+                }
+            }
+        }
+    }
+}
+
+fn definitions(statements: &[Statement]) -> Vec<Definition<'_>> {
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::DefineVariable(DefineVariable {
+                identifier_span,
+                identifier,
+                decorators,
+                ..
+            }) => Some(Definition {
+                span: *identifier_span,
+                kind: "variable",
+                name: identifier,
+                decorators,
+            }),
+            Statement::DefineFunction {
+                function_name_span,
+                function_name,
+                decorators,
+                ..
+            } => Some(Definition {
+                span: *function_name_span,
+                kind: "function",
+                name: function_name,
+                decorators,
+            }),
+            Statement::DefineBaseUnit(span, identifier, _, decorators)
+            | Statement::DefineDerivedUnit {
+                identifier_span: span,
+                identifier,
+                decorators,
+                ..
+            } => Some(Definition {
+                span: *span,
+                kind: "unit",
+                name: identifier,
+                decorators,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Analyze a program and return warnings for unused definitions and unreachable
+/// branches. Public items of modules are meant to be used by other code, so they
+/// are only reported if `is_module_code` returns `false` for the ID of the code
+/// source in which they are defined.
+pub(crate) fn analyze(
+    statements: &[Statement],
+    is_module_code: impl Fn(usize) -> bool,
+) -> Vec<Warning> {
+    let mut usages = Usages::default();
+    for statement in statements {
+        usages.statement(statement);
+    }
+
+    let mut warnings = vec![];
+    for definition in definitions(statements) {
+        let is_used = decorator::name_and_aliases(definition.name, definition.decorators)
+            .any(|(name, _)| usages.names.contains(name.as_str()));
+
+        let is_reported =
+            definition.is_private() || !is_module_code(definition.span.code_source_id);
+
+        if !is_used && is_reported {
+            warnings.push(Warning::Unused {
+                span: definition.span,
+                kind: definition.kind,
+                name: definition.name.to_string(),
+            });
+        }
+    }
+    warnings.extend(usages.unreachable_branches);
+
+    warnings
+}
@@ -1,19 +1,32 @@
-use crate::{prefix_parser::AcceptsPrefix, unit::CanonicalName};
+use crate::{
+    prefix_parser::{AcceptsPrefix, ShadowingPolicy},
+    symbol::Symbol,
+    unit::CanonicalName,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Decorator {
     MetricPrefixes,
     BinaryPrefixes,
-    Aliases(Vec<(String, Option<AcceptsPrefix>)>),
+    Aliases(Vec<(Symbol, Option<AcceptsPrefix>)>),
     Url(String),
     Name(String),
     Description(String),
+    Source(String),
+    Private,
+    Relative,
+    Reciprocal,
+    Fractional,
+    NaturalConversion,
+    SimplificationTarget,
+    Deprecated(String),
+    Shadowing(ShadowingPolicy),
 }
 
 pub fn name_and_aliases<'a>(
-    name: &'a String,
+    name: &'a Symbol,
     decorators: &'a [Decorator],
-) -> Box<dyn Iterator<Item = (&'a String, AcceptsPrefix)> + 'a> {
+) -> Box<dyn Iterator<Item = (&'a Symbol, AcceptsPrefix)> + 'a> {
     let aliases = {
         let mut aliases_vec = vec![];
         for decorator in decorators {
@@ -74,6 +87,33 @@ pub fn url(decorators: &[Decorator]) -> Option<String> {
     None
 }
 
+pub fn source(decorators: &[Decorator]) -> Option<String> {
+    for decorator in decorators {
+        if let Decorator::Source(source) = decorator {
+            return Some(source.clone());
+        }
+    }
+    None
+}
+
+pub fn shadowing_policy(decorators: &[Decorator]) -> Option<ShadowingPolicy> {
+    for decorator in decorators {
+        if let Decorator::Shadowing(policy) = decorator {
+            return Some(*policy);
+        }
+    }
+    None
+}
+
+pub fn deprecation(decorators: &[Decorator]) -> Option<String> {
+    for decorator in decorators {
+        if let Decorator::Deprecated(hint) = decorator {
+            return Some(hint.clone());
+        }
+    }
+    None
+}
+
 pub fn description(decorators: &[Decorator]) -> Option<String> {
     let mut description = String::new();
     for decorator in decorators {
@@ -110,3 +150,7 @@ pub fn contains_aliases(decorators: &[Decorator]) -> bool {
 
     false
 }
+
+pub fn is_private(decorators: &[Decorator]) -> bool {
+    decorators.contains(&Decorator::Private)
+}
@@ -6,6 +6,7 @@ use crate::{
     pretty_print::PrettyPrint,
     resolver::ResolverError,
     typechecker::{IncompatibleDimensionsError, TypeCheckError},
+    warning::Warning,
     NameResolutionError,
 };
 
@@ -37,6 +38,12 @@ impl ErrorDiagnostic for ResolverError {
             ResolverError::ParseErrors(errors) => {
                 errors.iter().flat_map(|e| e.diagnostics()).collect()
             }
+            ResolverError::UnknownImportedName(span, _, _)
+            | ResolverError::PrivateItem(span, _, _) => vec![Diagnostic::error()
+                .with_message("while resolving imports in")
+                .with_labels(vec![span
+                    .diagnostic_label(LabelStyle::Primary)
+                    .with_message(self.to_string())])],
         }
     }
 }
@@ -63,11 +70,35 @@ impl ErrorDiagnostic for NameResolutionError {
                         .diagnostic_label(LabelStyle::Primary)
                         .with_message("identifier is already in use"),
                 ])],
+            NameResolutionError::ConfusableIdentifierClash {
+                conflict_span,
+                original_span,
+                difference,
+                ..
+            } => vec![Diagnostic::error()
+                .with_message("identifier clash in definition")
+                .with_labels(vec![
+                    original_span
+                        .diagnostic_label(LabelStyle::Secondary)
+                        .with_message("Previously defined here"),
+                    conflict_span
+                        .diagnostic_label(LabelStyle::Primary)
+                        .with_message("identifier is already in use"),
+                ])
+                .with_notes(vec![format!(
+                    "Both names look the same, and are treated as the same identifier, even though \
+                     they are written with different characters: {difference}"
+                )])],
             NameResolutionError::ReservedIdentifier(span) => vec![Diagnostic::error()
                 .with_message("reserved identifier may not be used")
                 .with_labels(vec![span
                     .diagnostic_label(LabelStyle::Primary)
                     .with_message("reserved identifier")])],
+            NameResolutionError::UnknownUnit(span, _) => vec![Diagnostic::error()
+                .with_message("while resolving a unit alias")
+                .with_labels(vec![span
+                    .diagnostic_label(LabelStyle::Primary)
+                    .with_message(self.to_string())])],
         }
     }
 }
@@ -125,7 +156,8 @@ impl ErrorDiagnostic for TypeCheckError {
                 d.with_labels(labels).with_notes(vec![inner_error])
             }
             TypeCheckError::NonScalarExponent(span, type_)
-            | TypeCheckError::NonScalarFactorialArgument(span, type_) => d
+            | TypeCheckError::NonScalarFactorialArgument(span, type_)
+            | TypeCheckError::NonScalarFraction(span, type_) => d
                 .with_labels(vec![span
                     .diagnostic_label(LabelStyle::Primary)
                     .with_message(format!("{type_}"))])
@@ -311,6 +343,18 @@ impl ErrorDiagnostic for TypeCheckError {
                 type_first,
                 span_subsequent,
                 type_subsequent,
+            )
+            | TypeCheckError::IncompatibleTypesInDict(
+                span_first,
+                type_first,
+                span_subsequent,
+                type_subsequent,
+            )
+            | TypeCheckError::IncompatibleTypesInRange(
+                span_first,
+                type_first,
+                span_subsequent,
+                type_subsequent,
             ) => d
                 .with_labels(vec![
                     span_first
@@ -329,14 +373,19 @@ impl ErrorDiagnostic for TypeCheckError {
                     format!("Use 'unit {unit_name}' for ad-hoc units."),
                     format!("Use 'unit {unit_name}: Scalar = …' for derived units."),
                 ]),
-            TypeCheckError::ForeignFunctionNeedsTypeAnnotations(span, _)
+            TypeCheckError::NoDimensionlessPreferredUnit(span)
+            | TypeCheckError::ForeignFunctionNeedsTypeAnnotations(span, _)
             | TypeCheckError::UnknownForeignFunction(span, _)
             | TypeCheckError::NonRationalExponent(span)
             | TypeCheckError::OverflowInConstExpr(span)
             | TypeCheckError::ExpectedDimensionType(span, _)
+            | TypeCheckError::WrongNumberOfTypeArguments(span, _, _, _)
             | TypeCheckError::ExpectedBool(span)
+            | TypeCheckError::NonStringDictKey(span, _)
+            | TypeCheckError::MissingStepInRange(span, _)
             | TypeCheckError::NoFunctionReferenceToGenericFunction(span)
             | TypeCheckError::OnlyFunctionsAndReferencesCanBeCalled(span)
+            | TypeCheckError::ImplicitMultiplicationWithFunction(span, _)
             | TypeCheckError::DerivedUnitDefinitionMustNotBeGeneric(span)
             | TypeCheckError::MultipleTypedHoles(span) => d.with_labels(vec![span
                 .diagnostic_label(LabelStyle::Primary)
@@ -495,6 +544,46 @@ impl ErrorDiagnostic for TypeCheckError {
     }
 }
 
+impl ErrorDiagnostic for Warning {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Warning::Deprecated { span, hint, .. } => vec![Diagnostic::warning()
+                .with_message(self.to_string())
+                .with_labels(vec![span
+                    .diagnostic_label(LabelStyle::Primary)
+                    .with_message(hint.clone())])],
+            Warning::Shadowing {
+                span,
+                original_span,
+                ..
+            } => vec![Diagnostic::warning()
+                .with_message(self.to_string())
+                .with_labels(vec![
+                    original_span
+                        .diagnostic_label(LabelStyle::Secondary)
+                        .with_message("Previously defined here"),
+                    span.diagnostic_label(LabelStyle::Primary)
+                        .with_message("this definition shadows the previous one"),
+                ])],
+            Warning::CompatibilitySyntax { .. } => vec![Diagnostic::warning()
+                .with_message(self.to_string())
+                .with_notes(vec![
+                    "This syntax from other unit calculators is translated because compatibility syntax is enabled".into(),
+                ])],
+            Warning::Unused { span, .. } => vec![Diagnostic::warning()
+                .with_message(self.to_string())
+                .with_labels(vec![span
+                    .diagnostic_label(LabelStyle::Primary)
+                    .with_message("defined here")])],
+            Warning::UnreachableBranch { span, .. } => vec![Diagnostic::warning()
+                .with_message(self.to_string())
+                .with_labels(vec![span
+                    .diagnostic_label(LabelStyle::Primary)
+                    .with_message("unreachable")])],
+        }
+    }
+}
+
 impl ErrorDiagnostic for RuntimeError {
     fn diagnostics(&self) -> Vec<Diagnostic> {
         let inner = format!("{self:#}");
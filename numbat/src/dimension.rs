@@ -21,10 +21,10 @@ impl DimensionRegistry {
                 if self
                     .introduced_type_parameters
                     .iter()
-                    .any(|(_, n, _)| n == name)
+                    .any(|(_, n, _)| n == name.as_str())
                 {
                     Ok(BaseRepresentation::from_factor(BaseRepresentationFactor(
-                        name.clone(),
+                        name.to_string(),
                         Exponent::from_integer(1),
                     )))
                 } else {
@@ -1,6 +1,10 @@
+#[cfg(feature = "datetime")]
 use jiff::Span;
+#[cfg(feature = "datetime")]
 use jiff::Timestamp;
+#[cfg(feature = "datetime")]
 use jiff::Zoned;
+#[cfg(feature = "datetime")]
 use num_traits::ToPrimitive;
 
 use super::macros::*;
@@ -12,12 +16,15 @@ use crate::value::FunctionReference;
 use crate::value::Value;
 use crate::RuntimeError;
 
+#[cfg(feature = "datetime")]
 use std::fmt::Write;
 
+#[cfg(feature = "datetime")]
 pub fn now(_args: Args) -> Result<Value> {
     return_datetime!(Zoned::now())
 }
 
+#[cfg(feature = "datetime")]
 pub fn datetime(mut args: Args) -> Result<Value> {
     let input = string_arg!(args);
 
@@ -27,6 +34,7 @@ pub fn datetime(mut args: Args) -> Result<Value> {
     return_datetime!(output)
 }
 
+#[cfg(feature = "datetime")]
 pub fn format_datetime(mut args: Args) -> Result<Value> {
     let format = string_arg!(args);
     let dt = datetime_arg!(args);
@@ -37,6 +45,7 @@ pub fn format_datetime(mut args: Args) -> Result<Value> {
     return_string!(output)
 }
 
+#[cfg(feature = "datetime")]
 pub fn get_local_timezone(_args: Args) -> Result<Value> {
     let local_tz = datetime::get_local_timezone_or_utc();
     let tz_name = local_tz.iana_name().unwrap_or("<unknown timezone>");
@@ -52,6 +61,7 @@ pub fn tz(mut args: Args) -> Result<Value> {
     )))
 }
 
+#[cfg(feature = "datetime")]
 pub fn unixtime(mut args: Args) -> Result<Value> {
     let input = datetime_arg!(args);
 
@@ -60,6 +70,7 @@ pub fn unixtime(mut args: Args) -> Result<Value> {
     return_scalar!(output as f64)
 }
 
+#[cfg(feature = "datetime")]
 pub fn from_unixtime(mut args: Args) -> Result<Value> {
     let timestamp = quantity_arg!(args).unsafe_value().to_f64() as i64;
 
@@ -70,6 +81,7 @@ pub fn from_unixtime(mut args: Args) -> Result<Value> {
     return_datetime!(dt)
 }
 
+#[cfg(feature = "datetime")]
 fn calendar_add(
     mut args: Args,
     unit_name: &str,
@@ -95,14 +107,96 @@ fn calendar_add(
     return_datetime!(output)
 }
 
+#[cfg(feature = "datetime")]
 pub fn _add_days(args: Args) -> Result<Value> {
     calendar_add(args, "day", |n| Span::new().try_days(n))
 }
 
+#[cfg(feature = "datetime")]
 pub fn _add_months(args: Args) -> Result<Value> {
     calendar_add(args, "month", |n| Span::new().try_months(n))
 }
 
+#[cfg(feature = "datetime")]
 pub fn _add_years(args: Args) -> Result<Value> {
     calendar_add(args, "year", |n| Span::new().try_years(n))
 }
+
+#[cfg(not(feature = "datetime"))]
+pub fn now(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn datetime(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn format_datetime(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+/// This can not fail, since the prelude calls it when defining `local`
+#[cfg(not(feature = "datetime"))]
+pub fn get_local_timezone(_args: Args) -> Result<Value> {
+    return_string!("<unknown timezone>")
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn unixtime(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn from_unixtime(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn _add_days(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn _add_months(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+#[cfg(not(feature = "datetime"))]
+pub fn _add_years(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("datetime".into()))
+}
+
+pub fn _parse_duration(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+
+    let seconds = datetime::parse_duration(&input).map_err(RuntimeError::DurationParsingError)?;
+
+    return_scalar!(seconds)
+}
+
+pub fn _iso8601_duration(mut args: Args) -> Result<Value> {
+    let seconds = scalar_arg!(args).to_f64();
+
+    let output = datetime::to_iso8601_duration(seconds).ok_or(RuntimeError::DurationOutOfRange)?;
+
+    return_string!(output)
+}
+
+pub fn _parse_clock_time(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+
+    let seconds =
+        datetime::parse_clock_time(&input).map_err(RuntimeError::ClockTimeParsingError)?;
+
+    return_scalar!(seconds)
+}
+
+pub fn _clock_time(mut args: Args) -> Result<Value> {
+    let seconds = scalar_arg!(args).to_f64();
+
+    let output = datetime::to_clock_time(seconds).ok_or(RuntimeError::DurationOutOfRange)?;
+
+    return_string!(output)
+}
@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+
+use super::macros::*;
+use super::{Args, Result};
+use crate::value::Value;
+use crate::RuntimeError;
+
+pub fn dict_get(mut args: Args) -> Result<Value> {
+    let key = string_arg!(args);
+    let mut dict = dict_arg!(args);
+
+    dict.swap_remove(&key).ok_or(RuntimeError::KeyNotFound(key))
+}
+
+pub fn dict_has_key(mut args: Args) -> Result<Value> {
+    let key = string_arg!(args);
+    let dict = dict_arg!(args);
+
+    return_boolean!(dict.contains_key(&key))
+}
+
+pub fn dict_insert(mut args: Args) -> Result<Value> {
+    let key = string_arg!(args);
+    let value = arg!(args);
+    let mut dict = dict_arg!(args);
+    dict.insert(key, value);
+
+    Ok(Value::Dict(dict))
+}
+
+pub fn dict_remove(mut args: Args) -> Result<Value> {
+    let key = string_arg!(args);
+    let mut dict = dict_arg!(args);
+    dict.shift_remove(&key);
+
+    Ok(Value::Dict(dict))
+}
+
+pub fn dict_keys(mut args: Args) -> Result<Value> {
+    let dict = dict_arg!(args);
+
+    let keys: VecDeque<_> = dict.into_keys().map(Value::String).collect();
+    Ok(keys.into())
+}
+
+pub fn dict_values(mut args: Args) -> Result<Value> {
+    let dict = dict_arg!(args);
+
+    let values: VecDeque<_> = dict.into_values().collect();
+    Ok(values.into())
+}
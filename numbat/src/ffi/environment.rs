@@ -0,0 +1,30 @@
+use std::collections::VecDeque;
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::value::Value;
+use crate::RuntimeError;
+
+pub fn env(mut args: Args) -> Result<Value> {
+    let name = string_arg!(args);
+    match std::env::var(&name) {
+        Ok(value) => return_string!(value),
+        Err(_) => Err(RuntimeError::EnvironmentVariableNotSet(name)),
+    }
+}
+
+pub fn env_or(mut args: Args) -> Result<Value> {
+    let name = string_arg!(args);
+    let default = string_arg!(args);
+    return_string!(std::env::var(name).unwrap_or(default))
+}
+
+pub fn args(arguments: &[String], _args: Args) -> Result<Value> {
+    let arguments = arguments
+        .iter()
+        .cloned()
+        .map(Value::String)
+        .collect::<VecDeque<_>>();
+    Ok(arguments.into())
+}
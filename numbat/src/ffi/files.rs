@@ -0,0 +1,289 @@
+use std::collections::VecDeque;
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::quantity::Quantity;
+use crate::unit::Unit;
+use crate::unit_lookup::UnitLookup;
+use crate::value::Value;
+use crate::RuntimeError;
+
+fn read_file(path: &str) -> Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| RuntimeError::CouldNotReadFile(path.to_string(), e.to_string()))
+}
+
+fn write_file(path: &str, contents: &str) -> Result<()> {
+    std::fs::write(path, contents)
+        .map_err(|e| RuntimeError::CouldNotWriteFile(path.to_string(), e.to_string()))
+}
+
+/// Split a column header like `power [W]` into the column name and the unit
+fn split_header(header: &str) -> (&str, Option<&str>) {
+    let header = header.trim();
+    if let Some((name, unit)) = header
+        .strip_suffix(']')
+        .and_then(|header| header.rsplit_once('['))
+    {
+        (name.trim(), Some(unit.trim()))
+    } else {
+        (header, None)
+    }
+}
+
+/// The quantity that numbers in a column are multiplied with. This is the
+/// value of the struct field, unless the header specifies a unit, which then
+/// needs to be compatible with the field.
+fn column_template(
+    units: &UnitLookup,
+    field: &str,
+    header_unit: Option<&str>,
+    template: &Value,
+) -> Result<Value> {
+    let (Some(unit), Value::Quantity(q)) = (header_unit, template) else {
+        return Ok(template.clone());
+    };
+
+    let header_quantity = units.parse_quantity(unit).map_err(|reason| {
+        RuntimeError::InvalidCsvData(format!(
+            "could not parse the unit '{unit}' of column '{field}': {reason}"
+        ))
+    })?;
+    let converted = header_quantity.convert_to(q.unit()).map_err(|_| {
+        RuntimeError::InvalidCsvData(format!(
+            "column '{field}' is given in '{unit}', which is not compatible with the unit of the field, '{}'",
+            q.unit()
+        ))
+    })?;
+
+    Ok(Value::Quantity(converted))
+}
+
+/// Parse a CSV entry into a value of the same type as `template`. Numbers are
+/// multiplied with the template quantity, which determines their unit.
+/// Empty entries in numeric columns are an error, unless `allow_missing` is
+/// set, in which case they are read as `NaN`.
+fn parse_entry(
+    template: &Value,
+    entry: &str,
+    column: &str,
+    row: usize,
+    allow_missing: bool,
+) -> Result<Value> {
+    let invalid = |kind: &str| {
+        RuntimeError::InvalidCsvData(format!(
+            "could not parse '{entry}' in column '{column}' (row {row}) as {kind}"
+        ))
+    };
+
+    match template {
+        Value::Quantity(q) => {
+            let entry = entry.trim();
+            let value = if entry.is_empty() {
+                if !allow_missing {
+                    return Err(RuntimeError::InvalidCsvData(format!(
+                        "missing entry in column '{column}' (row {row}). Use 'read_csv_with_missing' to read missing numbers as NaN"
+                    )));
+                }
+                f64::NAN
+            } else {
+                entry
+                    .replace('_', "")
+                    .parse::<f64>()
+                    .map_err(|_| invalid("a number"))?
+            };
+            Ok(Value::Quantity(Quantity::new_f64(
+                value * q.unsafe_value().to_f64(),
+                q.unit().clone(),
+            )))
+        }
+        Value::String(_) => Ok(Value::String(entry.to_string())),
+        Value::Boolean(_) => match entry.trim() {
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            _ => Err(invalid("a boolean")),
+        },
+        Value::DateTime(_) => crate::datetime::parse_datetime(entry.trim())
+            .map(Value::DateTime)
+            .map_err(|_| invalid("a date")),
+        _ => unreachable!("Field types are checked before reading the rows"),
+    }
+}
+
+pub fn read_csv(units: &UnitLookup, args: Args) -> Result<Value> {
+    read_csv_rows(units, args, false)
+}
+
+pub fn read_csv_with_missing(units: &UnitLookup, args: Args) -> Result<Value> {
+    read_csv_rows(units, args, true)
+}
+
+fn read_csv_rows(units: &UnitLookup, mut args: Args, allow_missing: bool) -> Result<Value> {
+    let path = string_arg!(args);
+    let Value::StructInstance(info, template) = arg!(args) else {
+        return Err(RuntimeError::InvalidCsvData(
+            "the second argument of 'read_csv' needs to be a struct instance".into(),
+        ));
+    };
+
+    let mut records = crate::csv::parse(&read_file(&path)?)
+        .map_err(RuntimeError::InvalidCsvData)?
+        .into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| RuntimeError::InvalidCsvData("the file is empty".into()))?;
+    let columns: Vec<_> = header.iter().map(|h| split_header(h)).collect();
+
+    let mut column_indices = vec![];
+    let mut column_templates = vec![];
+    for (field, template_value) in info.fields.keys().zip(&template) {
+        if !matches!(
+            template_value,
+            Value::Quantity(_) | Value::String(_) | Value::Boolean(_) | Value::DateTime(_)
+        ) {
+            return Err(RuntimeError::InvalidCsvData(format!(
+                "field '{field}' has an unsupported type. Only quantities, strings, booleans and dates can be read"
+            )));
+        }
+
+        let index = columns
+            .iter()
+            .position(|(name, _)| name == field)
+            .ok_or_else(|| RuntimeError::InvalidCsvData(format!("missing column '{field}'")))?;
+
+        column_indices.push(index);
+        column_templates.push(column_template(
+            units,
+            field,
+            columns[index].1,
+            template_value,
+        )?);
+    }
+
+    let rows = records
+        .enumerate()
+        .map(|(i, record)| {
+            // Row numbers start at 1 and the header is the first row
+            let row = i + 2;
+            let fields = column_indices
+                .iter()
+                .zip(info.fields.keys())
+                .zip(&column_templates)
+                .map(|((&index, field), template_value)| {
+                    let entry = record.get(index).ok_or_else(|| {
+                        RuntimeError::InvalidCsvData(format!(
+                            "row {row} has no entry for column '{field}'"
+                        ))
+                    })?;
+                    parse_entry(template_value, entry, field, row, allow_missing)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Value::StructInstance(info.clone(), fields))
+        })
+        .collect::<Result<VecDeque<_>>>()?;
+
+    Ok(rows.into())
+}
+
+fn csv_entry(value: &Value, unit: Option<&Unit>) -> Result<String> {
+    match value {
+        Value::Quantity(q) => {
+            // The type checker makes sure that all rows have the same dimensions
+            let value = match unit {
+                Some(unit) => q.convert_to(unit).unwrap().unsafe_value().to_f64(),
+                None => q.unsafe_value().to_f64(),
+            };
+            Ok(if value.is_nan() {
+                String::new()
+            } else {
+                value.to_string()
+            })
+        }
+        Value::String(s) => Ok(crate::csv::escape_field(s)),
+        Value::Boolean(b) => Ok(b.to_string()),
+        Value::DateTime(dt) => Ok(crate::csv::escape_field(&crate::datetime::to_string(dt))),
+        _ => Err(RuntimeError::UserError(
+            "write_csv: Only quantities, strings, booleans and dates can be written".into(),
+        )),
+    }
+}
+
+pub fn write_csv(mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    let rows = list_elements_arg!(args);
+
+    let mut lines = vec![];
+    if let Some(first) = rows.iter().next() {
+        let Value::StructInstance(info, first_values) = &first else {
+            return Err(RuntimeError::UserError(
+                "write_csv: The data needs to be a list of structs".into(),
+            ));
+        };
+
+        // Quantities are written in the units of the first row, which are
+        // given in the header
+        let units: Vec<Option<Unit>> = first_values
+            .iter()
+            .map(|value| match value {
+                Value::Quantity(q) if !q.unit().is_scalar() => Some(q.unit().clone()),
+                _ => None,
+            })
+            .collect();
+
+        let header = info
+            .fields
+            .keys()
+            .zip(&units)
+            .map(|(name, unit)| match unit {
+                Some(unit) => crate::csv::escape_field(&format!("{name} [{unit}]")),
+                None => crate::csv::escape_field(name),
+            })
+            .collect::<Vec<_>>();
+        lines.push(header.join(","));
+
+        for row in rows.iter() {
+            let values = row.unsafe_as_struct_fields();
+            let entries = values
+                .iter()
+                .zip(&units)
+                .map(|(value, unit)| csv_entry(value, unit.as_ref()))
+                .collect::<Result<Vec<_>>>()?;
+            lines.push(entries.join(","));
+        }
+    }
+
+    let mut contents = lines.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    write_file(&path, &contents)?;
+
+    return_scalar!(rows.len() as f64)
+}
+
+pub fn read_text(mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    return_string!(read_file(&path)?)
+}
+
+pub fn write_text(mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    let text = string_arg!(args);
+    write_file(&path, &text)?;
+    return_scalar!(text.len() as f64)
+}
+
+pub fn append_text(mut args: Args) -> Result<Value> {
+    use std::io::Write;
+
+    let path = string_arg!(args);
+    let text = string_arg!(args);
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| RuntimeError::CouldNotWriteFile(path.clone(), e.to_string()))?;
+    return_scalar!(text.len() as f64)
+}
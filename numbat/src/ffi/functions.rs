@@ -4,18 +4,26 @@ use std::sync::OnceLock;
 use super::{macros::*, Args};
 use crate::{quantity::Quantity, value::Value, RuntimeError};
 
-use super::{Callable, ForeignFunction, Result};
+use super::{Callable, Capability, ForeignFunction, Result};
 
 static FFI_FUNCTIONS: OnceLock<HashMap<String, ForeignFunction>> = OnceLock::new();
 
 pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
     use super::currency::*;
     use super::datetime::*;
+    use super::dicts::*;
+    use super::environment::*;
+    use super::files::*;
+    use super::json::*;
     use super::lists::*;
     use super::lookup::*;
     use super::math::*;
+    use super::number_theory::*;
     use super::plot::*;
+    use super::quantities::*;
+    use super::statistics::*;
     use super::strings::*;
+    use super::tables::*;
 
     FFI_FUNCTIONS.get_or_init(|| {
         let mut m = HashMap::new();
@@ -36,9 +44,65 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
             };
         }
 
+        macro_rules! insert_function_with_capability {
+            ($capability:expr, $callable:expr, $arity:expr) => {
+                m.insert(
+                    stringify!($callable).to_string(),
+                    ForeignFunction {
+                        name: stringify!($callable),
+                        arity: $arity,
+                        callable: Callable::FunctionWithCapability($capability, $callable),
+                    },
+                );
+            };
+        }
+
+        macro_rules! insert_function_with_capability_and_unit_lookup {
+            ($capability:expr, $callable:expr, $arity:expr) => {
+                m.insert(
+                    stringify!($callable).to_string(),
+                    ForeignFunction {
+                        name: stringify!($callable),
+                        arity: $arity,
+                        callable: Callable::FunctionWithCapabilityAndUnitLookup(
+                            $capability,
+                            $callable,
+                        ),
+                    },
+                );
+            };
+        }
+
+        macro_rules! insert_function_with_unit_lookup {
+            ($callable:expr, $arity:expr) => {
+                m.insert(
+                    stringify!($callable).to_string(),
+                    ForeignFunction {
+                        name: stringify!($callable),
+                        arity: $arity,
+                        callable: Callable::FunctionWithUnitLookup($callable),
+                    },
+                );
+            };
+        }
+
         // Core
         insert_function!(error, 1..=1);
         insert_function!(unit_of, 1..=1);
+        insert_function!(uncertainty, 1..=1);
+        insert_function_with_unit_lookup!(parse_quantity, 2..=2);
+        insert_function_with_unit_lookup!(parse_quantity_or, 2..=2);
+
+        // Reflection
+        insert_function!(unit_name, 1..=1);
+        insert_function_with_unit_lookup!(dimension_name, 1..=1);
+        insert_function_with_unit_lookup!(dimension_exponents, 1..=1);
+        insert_function_with_unit_lookup!(is_dimensionless, 1..=1);
+        insert_function_with_unit_lookup!(speak, 1..=1);
+        insert_function!(is_convertible, 2..=2);
+        insert_function!(try_convert, 2..=2);
+        insert_function_with_unit_lookup!(_natural_convert, 4..=4);
+        insert_function_with_unit_lookup!(_natural_energy_power, 4..=4);
 
         // Math
         insert_function!("mod", mod_, 2..=2);
@@ -67,11 +131,37 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         insert_function!(log10, 1..=1);
         insert_function!(log2, 1..=1);
         insert_function!(gamma, 1..=1);
+        insert_function!(ln_gamma, 1..=1);
+        insert_function!(erf, 1..=1);
+        insert_function!(erfc, 1..=1);
+        insert_function!(bessel_j, 2..=2);
+        insert_function!(bessel_y, 2..=2);
+        insert_function!(bessel_i, 2..=2);
+        insert_function!(bessel_k, 2..=2);
+        insert_function!(elliptic_k, 1..=1);
+        insert_function!(elliptic_e, 1..=1);
 
         insert_function!(is_nan, 1..=1);
         insert_function!(is_infinite, 1..=1);
 
-        insert_function!(random, 0..=0);
+        m.insert(
+            "random".to_string(),
+            ForeignFunction {
+                name: "random",
+                arity: 0..=0,
+                callable: Callable::FunctionWithRng(random),
+            },
+        );
+
+        // Number theory
+        insert_function!(gcd, 2..=2);
+        insert_function!(lcm, 2..=2);
+        insert_function!(modpow, 3..=3);
+        insert_function!(is_prime, 1..=1);
+        insert_function!(prime_factors, 1..=1);
+        insert_function!(binom, 2..=2);
+        insert_function!(perm, 2..=2);
+        insert_function!(factorial_exact, 1..=1);
 
         // Lists
         insert_function!(len, 1..=1);
@@ -79,6 +169,27 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         insert_function!(tail, 1..=1);
         insert_function!(cons, 2..=2);
         insert_function!(cons_end, 2..=2);
+        insert_function!(take, 2..=2);
+        insert_function!(drop, 2..=2);
+        insert_function!(unique, 1..=1);
+        insert_function!("_group_by_keys", group_by_keys, 2..=2);
+
+        // Dictionaries
+        insert_function!(dict_get, 2..=2);
+        insert_function!(dict_has_key, 2..=2);
+        insert_function!(dict_insert, 3..=3);
+        insert_function!(dict_remove, 2..=2);
+        insert_function!(dict_keys, 1..=1);
+        insert_function!(dict_values, 1..=1);
+
+        // Statistics
+        insert_function!(mean, 1..=1);
+        insert_function!(variance, 1..=1);
+        insert_function!(median, 1..=1);
+        insert_function!(quantile, 2..=2);
+        insert_function!(covariance, 2..=2);
+        insert_function!(correlation, 2..=2);
+        insert_function!(fit_polynomial, 4..=4);
 
         // Strings
         insert_function!(str_length, 1..=1);
@@ -87,6 +198,28 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         insert_function!(str_slice, 3..=3);
         insert_function!(chr, 1..=1);
         insert_function!(ord, 1..=1);
+        insert_function!(hex_float, 1..=1);
+        insert_function!(latex, 1..=1);
+        insert_function!(typst, 1..=1);
+        insert_function!(mathml, 1..=1);
+        insert_function!(str_find, 2..=2);
+        insert_function!(str_replace, 3..=3);
+        insert_function!(str_trim, 1..=1);
+        insert_function!(str_trim_start, 1..=1);
+        insert_function!(str_trim_end, 1..=1);
+        insert_function!(str_starts_with, 2..=2);
+        insert_function!(str_ends_with, 2..=2);
+        insert_function!(capitalize, 1..=1);
+        insert_function!(str_repeat, 2..=2);
+
+        // Regular expressions
+        insert_function!(regex_match, 2..=2);
+        insert_function!(regex_find, 2..=2);
+        insert_function!(regex_captures, 2..=2);
+        insert_function!(regex_replace, 3..=3);
+
+        // Tables
+        insert_function!("_format_table", format_table, 4..=4);
 
         // Date and time
         insert_function!(now, 0..=0);
@@ -100,15 +233,51 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         insert_function!(_add_days, 2..=2);
         insert_function!(_add_months, 2..=2);
         insert_function!(_add_years, 2..=2);
+        insert_function!(_parse_duration, 1..=1);
+        insert_function!(_iso8601_duration, 1..=1);
+        insert_function!(_parse_clock_time, 1..=1);
+        insert_function!(_clock_time, 1..=1);
 
         // Currency
         insert_function!(exchange_rate, 1..=1);
 
+        // File system
+        insert_function_with_capability_and_unit_lookup!(Capability::FileSystem, read_csv, 2..=2);
+        insert_function_with_capability_and_unit_lookup!(
+            Capability::FileSystem,
+            read_csv_with_missing,
+            2..=2
+        );
+        insert_function_with_capability!(Capability::FileSystem, write_csv, 2..=2);
+        insert_function_with_capability!(Capability::FileSystem, read_text, 1..=1);
+        insert_function_with_capability!(Capability::FileSystem, write_text, 2..=2);
+        insert_function_with_capability!(Capability::FileSystem, append_text, 2..=2);
+
+        // Environment
+        insert_function_with_capability!(Capability::Environment, env, 1..=1);
+        insert_function_with_capability!(Capability::Environment, env_or, 2..=2);
+        m.insert(
+            "args".to_string(),
+            ForeignFunction {
+                name: "args",
+                arity: 0..=0,
+                callable: Callable::FunctionWithScriptArguments(args),
+            },
+        );
+
+        // JSON
+        insert_function!(to_json, 1..=1);
+        insert_function!(parse_json, 2..=2);
+        insert_function_with_capability!(Capability::Network, fetch_json, 2..=2);
+
         // Database lookup
         insert_function!(_get_chemical_element_data_raw, 1..=1);
+        insert_function!(_molar_mass_raw, 1..=1);
 
         // Plotting
         insert_function!(show, 1..=1);
+        insert_function!(text_plot, 1..=1);
+        insert_function!(svg_plot, 1..=1);
 
         m
     })
@@ -118,6 +287,14 @@ fn error(mut args: Args) -> Result<Value> {
     Err(RuntimeError::UserError(arg!(args).unsafe_as_string()))
 }
 
+/// Calls of `uncertainty(…)` are compiled to a propagation of uncertainties, so
+/// this is only reached if the function is called via a function reference.
+fn uncertainty(_args: Args) -> Result<Value> {
+    Err(RuntimeError::UserError(
+        "The `uncertainty` function can only be called directly".into(),
+    ))
+}
+
 fn unit_of(mut args: Args) -> Result<Value> {
     let input_unit = quantity_arg!(args).unit().clone();
     return_quantity!(1.0, input_unit)
@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::json::{json_string, JsonValue};
+use crate::quantity::Quantity;
+use crate::unit_export::json_number;
+use crate::value::Value;
+use crate::RuntimeError;
+
+/// Quantities with a unit are encoded as `{"value": …, "unit": …}` objects,
+/// dimensionless quantities as plain numbers.
+fn value_to_json(value: &Value) -> String {
+    match value {
+        Value::Quantity(q) if q.unit().is_scalar() => json_number(q.unsafe_value().to_f64()),
+        Value::Quantity(q) => format!(
+            "{{\"value\": {}, \"unit\": {}}}",
+            json_number(q.unsafe_value().to_f64()),
+            json_string(&q.unit().to_string())
+        ),
+        Value::Boolean(b) => b.to_string(),
+        Value::String(s) => json_string(s),
+        Value::DateTime(dt) => json_string(&dt.to_string()),
+        Value::FunctionReference(r) => json_string(&r.to_string()),
+        Value::FormatSpecifiers(_) => unreachable!("Format specifiers are not values"),
+        Value::StructInstance(info, values) => format!(
+            "{{{}}}",
+            info.fields
+                .keys()
+                .zip(values)
+                .map(|(name, value)| format!("{}: {}", json_string(name), value_to_json(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::List(elements) => format!(
+            "[{}]",
+            elements
+                .iter()
+                .map(value_to_json)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Range(range) => format!(
+            "[{}]",
+            range
+                .iter()
+                .map(|element| value_to_json(&element))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Dict(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", json_string(key), value_to_json(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+pub fn to_json(mut args: Args) -> Result<Value> {
+    return_string!(value_to_json(&arg!(args)))
+}
+
+/// Convert parsed JSON data into a value with the same shape as `schema`.
+/// Numbers are multiplied with the corresponding schema quantity, a list
+/// schema has a single element that describes all elements of the array, and
+/// a dictionary schema has a single entry that describes all object members.
+fn value_from_json(json: &JsonValue, schema: &Value, path: &str) -> Result<Value> {
+    let invalid = RuntimeError::InvalidJsonData;
+    let mismatch = |expected: &str| {
+        invalid(format!(
+            "expected {expected} at '{path}', got {}",
+            json.kind()
+        ))
+    };
+
+    match (schema, json) {
+        (Value::Quantity(q), JsonValue::Number(_) | JsonValue::Null) => {
+            let value = match json {
+                JsonValue::Number(x) => *x,
+                _ => f64::NAN,
+            };
+            Ok(Value::Quantity(Quantity::new_f64(
+                value * q.unsafe_value().to_f64(),
+                q.unit().clone(),
+            )))
+        }
+        (Value::Quantity(_), _) => Err(mismatch("a number")),
+        (Value::String(_), JsonValue::String(s)) => Ok(Value::String(s.clone())),
+        (Value::String(_), _) => Err(mismatch("a string")),
+        (Value::Boolean(_), JsonValue::Boolean(b)) => Ok(Value::Boolean(*b)),
+        (Value::Boolean(_), _) => Err(mismatch("a boolean")),
+        (Value::DateTime(_), JsonValue::String(s)) => crate::datetime::parse_datetime(s)
+            .map(Value::DateTime)
+            .map_err(|_| invalid(format!("could not parse '{s}' at '{path}' as a date"))),
+        (Value::DateTime(_), _) => Err(mismatch("a date string")),
+        (Value::StructInstance(info, field_schemas), JsonValue::Object(_)) => {
+            let fields = info
+                .fields
+                .keys()
+                .zip(field_schemas)
+                .map(|(name, field_schema)| {
+                    let path = format!("{path}.{name}");
+                    let entry = json
+                        .get(name)
+                        .ok_or_else(|| invalid(format!("missing field '{path}'")))?;
+                    value_from_json(entry, field_schema, &path)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Value::StructInstance(info.clone(), fields))
+        }
+        (Value::StructInstance(..), _) => Err(mismatch("an object")),
+        (Value::List(element_schemas), JsonValue::Array(elements)) => {
+            let mut element_schemas = element_schemas.iter();
+            let (Some(element_schema), None) = (element_schemas.next(), element_schemas.next())
+            else {
+                return Err(invalid(format!(
+                    "the schema for '{path}' needs to be a list with a single element"
+                )));
+            };
+            let elements = elements
+                .iter()
+                .enumerate()
+                .map(|(i, element)| {
+                    value_from_json(element, element_schema, &format!("{path}[{i}]"))
+                })
+                .collect::<Result<VecDeque<_>>>()?;
+            Ok(elements.into())
+        }
+        (Value::List(_), _) => Err(mismatch("an array")),
+        // Only ranges with a single element are valid schemas
+        (Value::Range(range), _) => {
+            value_from_json(json, &Value::List(range.iter().take(2).collect()), path)
+        }
+        (Value::Dict(value_schemas), JsonValue::Object(members)) => {
+            let mut value_schemas = value_schemas.values();
+            let (Some(value_schema), None) = (value_schemas.next(), value_schemas.next()) else {
+                return Err(invalid(format!(
+                    "the schema for '{path}' needs to be a dictionary with a single entry"
+                )));
+            };
+            let entries = members
+                .iter()
+                .map(|(key, member)| {
+                    let value = value_from_json(member, value_schema, &format!("{path}.{key}"))?;
+                    Ok((key.clone(), value))
+                })
+                .collect::<Result<_>>()?;
+            Ok(Value::Dict(entries))
+        }
+        (Value::Dict(_), _) => Err(mismatch("an object")),
+        (Value::FunctionReference(_) | Value::FormatSpecifiers(_), _) => {
+            Err(invalid("functions can not be read from JSON data".into()))
+        }
+    }
+}
+
+fn value_from_json_string(input: &str, schema: &Value) -> Result<Value> {
+    let json = crate::json::parse(input).map_err(RuntimeError::InvalidJsonData)?;
+    value_from_json(&json, schema, "$")
+}
+
+pub fn parse_json(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let schema = arg!(args);
+    value_from_json_string(&input, &schema)
+}
+
+#[cfg(feature = "fetch-json")]
+fn fetch(url: &str) -> Result<String> {
+    use std::time::Duration;
+
+    attohttpc::get(url)
+        .connect_timeout(Duration::from_secs(10))
+        .read_timeout(Duration::from_secs(30))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|e| RuntimeError::CouldNotFetchUrl(url.to_string(), e.to_string()))
+}
+
+#[cfg(not(feature = "fetch-json"))]
+fn fetch(url: &str) -> Result<String> {
+    Err(RuntimeError::CouldNotFetchUrl(
+        url.to_string(),
+        "this version of Numbat has been built without support for HTTP requests".into(),
+    ))
+}
+
+pub fn fetch_json(mut args: Args) -> Result<Value> {
+    let url = string_arg!(args);
+    let schema = arg!(args);
+    value_from_json_string(&fetch(&url)?, &schema)
+}
@@ -1,32 +1,155 @@
+use std::collections::{HashMap, VecDeque};
+
 use super::macros::*;
 use super::{Args, Result};
 use crate::quantity::Quantity;
-use crate::value::Value;
+use crate::value::{ListElements, Value};
 use crate::RuntimeError;
 
 pub fn len(mut args: Args) -> Result<Value> {
-    let list = list_arg!(args);
+    let list = list_elements_arg!(args);
 
     return_scalar!(list.len() as f64)
 }
 
 pub fn head(mut args: Args) -> Result<Value> {
-    let list = list_arg!(args);
-
-    if let Some(first) = list.head() {
-        Ok(first)
-    } else {
-        Err(RuntimeError::EmptyList)
+    match list_elements_arg!(args) {
+        ListElements::List(list) => list.head(),
+        ListElements::Range(range) => range.head(),
     }
+    .ok_or(RuntimeError::EmptyList)
 }
 
 pub fn tail(mut args: Args) -> Result<Value> {
-    let mut list = list_arg!(args);
+    let mut list = list_elements_arg!(args);
+    match &mut list {
+        ListElements::List(list) => list.tail()?,
+        ListElements::Range(range) => range.tail()?,
+    }
 
-    list.tail()?;
     Ok(list.into())
 }
 
+pub fn take(mut args: Args) -> Result<Value> {
+    let n = count_arg(scalar_arg!(args).to_f64())?;
+    let list = list_elements_arg!(args);
+
+    match list {
+        ListElements::List(list) => {
+            Ok(list.iter().take(n).cloned().collect::<VecDeque<_>>().into())
+        }
+        ListElements::Range(mut range) => {
+            range.take(n);
+            Ok(Value::Range(range))
+        }
+    }
+}
+
+pub fn drop(mut args: Args) -> Result<Value> {
+    let n = count_arg(scalar_arg!(args).to_f64())?;
+    let mut list = list_elements_arg!(args);
+
+    match &mut list {
+        ListElements::List(list) => {
+            for _ in 0..n.min(list.len()) {
+                list.tail()?;
+            }
+        }
+        ListElements::Range(range) => range.drop(n),
+    }
+    Ok(list.into())
+}
+
+/// A key for finding values that are possibly equal in a hash map. Values
+/// that are equal have the same key, but values with the same key still need
+/// to be compared.
+#[derive(PartialEq, Eq, Hash)]
+enum EqualityKey {
+    /// The bits of the value in base units
+    Quantity(u64),
+    String(String),
+    Boolean(bool),
+    Elements(Vec<EqualityKey>),
+    Other,
+}
+
+fn equality_key(value: &Value) -> EqualityKey {
+    match value {
+        Value::Quantity(q) => {
+            let value = q.to_base_unit_representation().unsafe_value().to_f64();
+            // Normalize -0.0 (which is equal to 0.0)
+            EqualityKey::Quantity((value + 0.0).to_bits())
+        }
+        Value::String(s) => EqualityKey::String(s.clone()),
+        Value::Boolean(b) => EqualityKey::Boolean(*b),
+        Value::List(elements) => EqualityKey::Elements(elements.iter().map(equality_key).collect()),
+        Value::Range(range) => {
+            EqualityKey::Elements(range.iter().map(|e| equality_key(&e)).collect())
+        }
+        Value::StructInstance(_, fields) => {
+            EqualityKey::Elements(fields.iter().map(equality_key).collect())
+        }
+        _ => EqualityKey::Other,
+    }
+}
+
+pub fn unique(mut args: Args) -> Result<Value> {
+    let list = list_elements_arg!(args);
+
+    let mut result: VecDeque<Value> = VecDeque::new();
+    let mut indices: HashMap<EqualityKey, Vec<usize>> = HashMap::new();
+    for element in list.iter() {
+        let candidates = indices.entry(equality_key(&element)).or_default();
+        if !candidates.iter().any(|&i| result[i] == element) {
+            candidates.push(result.len());
+            result.push_back(element);
+        }
+    }
+
+    Ok(result.into())
+}
+
+pub fn group_by_keys(mut args: Args) -> Result<Value> {
+    let keys = list_elements_arg!(args);
+    let list = list_elements_arg!(args);
+
+    let mut groups: Vec<(Value, VecDeque<Value>)> = vec![];
+    let mut indices: HashMap<EqualityKey, Vec<usize>> = HashMap::new();
+    for (key, element) in keys.iter().zip(list.iter()) {
+        let candidates = indices.entry(equality_key(&key)).or_default();
+        match candidates.iter().find(|&&i| key == groups[i].0) {
+            Some(&i) => groups[i].1.push_back(element),
+            None => {
+                candidates.push(groups.len());
+                groups.push((key, VecDeque::from([element])));
+            }
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|(_, elements)| Value::from(elements))
+        .collect::<VecDeque<_>>()
+        .into())
+}
+
+/// The number of elements for `take` and `drop`
+fn count_arg(n: f64) -> Result<usize> {
+    if n < 0.0 || n.fract() != 0.0 {
+        return Err(RuntimeError::ExpectedNonNegativeInteger(n.to_string()));
+    }
+    Ok(n as usize)
+}
+
+/// The size of the list that `cons` or `cons_end` turn a range into, which is
+/// checked against the memory limit before the range is materialized.
+pub(crate) fn materialized_size(args: &Args) -> usize {
+    match &args[1] {
+        Value::Range(range) => range.len().saturating_mul(std::mem::size_of::<Value>()),
+        _ => 0,
+    }
+}
+
 pub fn cons(mut args: Args) -> Result<Value> {
     let element = arg!(args);
     let mut list = list_arg!(args);
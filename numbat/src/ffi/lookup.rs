@@ -1,11 +1,49 @@
+#[cfg(feature = "chemical-elements")]
 use super::macros::*;
 use super::Args;
 use super::Result;
+#[cfg(feature = "chemical-elements")]
 use crate::quantity::Quantity;
+#[cfg(feature = "chemical-elements")]
 use crate::typed_ast::DType;
 use crate::value::Value;
 use crate::RuntimeError;
 
+#[cfg(not(feature = "chemical-elements"))]
+pub fn _get_chemical_element_data_raw(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable(
+        "chemical-elements".into(),
+    ))
+}
+
+#[cfg(not(feature = "chemical-elements"))]
+pub fn _molar_mass_raw(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable(
+        "chemical-elements".into(),
+    ))
+}
+
+/// Electronegativity on the Pauling scale, indexed by atomic number minus one.
+/// The `mendeleev` crate does not provide this property. Values are missing for
+/// the noble gases He, Ne and Ar and for the superheavy elements.
+#[cfg(feature = "chemical-elements")]
+#[rustfmt::skip]
+const PAULING_ELECTRONEGATIVITY: [f64; 118] = [
+    2.20, f64::NAN, 0.98, 1.57, 2.04, 2.55, 3.04, 3.44, 3.98, f64::NAN,
+    0.93, 1.31, 1.61, 1.90, 2.19, 2.58, 3.16, f64::NAN,
+    0.82, 1.00, 1.36, 1.54, 1.63, 1.66, 1.55, 1.83, 1.88, 1.91, 1.90, 1.65, 1.81, 2.01, 2.18, 2.55,
+    2.96, 3.00,
+    0.82, 0.95, 1.22, 1.33, 1.6, 2.16, 1.9, 2.2, 2.28, 2.20, 1.93, 1.69, 1.78, 1.96, 2.05, 2.1,
+    2.66, 2.6,
+    0.79, 0.89, 1.10, 1.12, 1.13, 1.14, 1.13, 1.17, 1.2, 1.2, 1.1, 1.22, 1.23, 1.24, 1.25, 1.1,
+    1.27,
+    1.3, 1.5, 2.36, 1.9, 2.2, 2.20, 2.28, 2.54, 2.00, 1.62, 2.33, 2.02, 2.0, 2.2, 2.2,
+    0.7, 0.9, 1.1, 1.3, 1.5, 1.38, 1.36, 1.28, 1.13, 1.28, 1.3, 1.3, 1.3, 1.3, 1.3, 1.3, 1.3,
+    f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN,
+    f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN, f64::NAN,
+];
+
+#[cfg(feature = "chemical-elements")]
 pub fn _get_chemical_element_data_raw(mut args: Args) -> Result<Value> {
     use crate::span::{SourceCodePositition, Span};
     use crate::typed_ast::StructInfo;
@@ -38,6 +76,10 @@ pub fn _get_chemical_element_data_raw(mut args: Args) -> Result<Value> {
         fields.insert("group".to_string(), (unknown_span, type_scalar.clone()));
         fields.insert("group_name".to_string(), (unknown_span, Type::String));
         fields.insert("period".to_string(), (unknown_span, type_scalar.clone()));
+        fields.insert(
+            "atomic_mass_dalton".to_string(),
+            (unknown_span, type_scalar.clone()),
+        );
         fields.insert(
             "melting_point_kelvin".to_string(),
             (unknown_span, type_scalar.clone()),
@@ -62,10 +104,15 @@ pub fn _get_chemical_element_data_raw(mut args: Args) -> Result<Value> {
             "vaporization_heat_kilojoule_per_mole".to_string(),
             (unknown_span, type_scalar.clone()),
         );
+        fields.insert(
+            "electronegativity".to_string(),
+            (unknown_span, type_scalar.clone()),
+        );
 
         let info = StructInfo {
             name: "_ChemicalElementRaw".to_string(),
             definition_span: unknown_span,
+            type_parameters: vec![],
             fields,
         };
         Ok(Value::StructInstance(
@@ -86,6 +133,7 @@ pub fn _get_chemical_element_data_raw(mut args: Args) -> Result<Value> {
                         .unwrap_or("unknown".into()),
                 ),
                 Value::Quantity(Quantity::from_scalar(element.period() as f64)),
+                Value::Quantity(Quantity::from_scalar(f64::from(element.atomic_weight()))),
                 Value::Quantity(Quantity::from_scalar(
                     element
                         .melting_point()
@@ -122,9 +170,132 @@ pub fn _get_chemical_element_data_raw(mut args: Args) -> Result<Value> {
                         .map(|KiloJoulePerMole(e)| e)
                         .unwrap_or(f64::NAN),
                 )),
+                Value::Quantity(Quantity::from_scalar(
+                    PAULING_ELECTRONEGATIVITY[element.atomic_number() as usize - 1],
+                )),
             ],
         ))
     } else {
         Err(RuntimeError::ChemicalElementNotFound(pattern))
     }
 }
+
+/// Parser for chemical formulas like `H2O`, `Ca(OH)2` or `CuSO4·5H2O`. Returns
+/// the element symbols along with the number of atoms.
+#[cfg(feature = "chemical-elements")]
+struct FormulaParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+#[cfg(feature = "chemical-elements")]
+impl<'a> FormulaParser<'a> {
+    fn parse(formula: &'a str) -> std::result::Result<Vec<(String, u32)>, String> {
+        let mut parser = FormulaParser {
+            chars: formula.chars().peekable(),
+        };
+
+        // A formula consists of one or more parts, separated by dots, e.g. for hydrates
+        let mut atoms = vec![];
+        loop {
+            let coefficient = parser.count()?.unwrap_or(1);
+            let part = parser.group(None)?;
+            if part.is_empty() {
+                return Err(match parser.chars.peek() {
+                    Some(c) => format!("unexpected character '{c}'"),
+                    None => format!("'{formula}' is empty or incomplete"),
+                });
+            }
+            multiply_counts(&mut atoms, part, coefficient)?;
+
+            match parser.chars.next() {
+                Some('·' | '.' | '*') => continue,
+                None => break,
+                Some(c) => return Err(format!("unexpected character '{c}'")),
+            }
+        }
+
+        Ok(atoms)
+    }
+
+    fn count(&mut self) -> std::result::Result<Option<u32>, String> {
+        let mut count = None;
+        while let Some(d) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+            self.chars.next();
+            count = Some(
+                count
+                    .unwrap_or(0u32)
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(d))
+                    .ok_or(TOO_MANY_ATOMS)?,
+            );
+        }
+        Ok(count)
+    }
+
+    fn group(&mut self, closing: Option<char>) -> std::result::Result<Vec<(String, u32)>, String> {
+        let mut atoms = vec![];
+        loop {
+            match self.chars.peek().copied() {
+                Some(c) if c.is_ascii_uppercase() => {
+                    self.chars.next();
+                    let mut symbol = c.to_string();
+                    while let Some(c) = self.chars.next_if(|c| c.is_ascii_lowercase()) {
+                        symbol.push(c);
+                    }
+                    atoms.push((symbol, self.count()?.unwrap_or(1)));
+                }
+                Some(open @ ('(' | '[')) => {
+                    self.chars.next();
+                    let inner = self.group(Some(if open == '(' { ')' } else { ']' }))?;
+                    let count = self.count()?.unwrap_or(1);
+                    multiply_counts(&mut atoms, inner, count)?;
+                }
+                Some(c) if Some(c) == closing => {
+                    self.chars.next();
+                    return Ok(atoms);
+                }
+                Some(')' | ']') => return Err("unbalanced parentheses".into()),
+                _ if closing.is_some() => {
+                    return Err(format!("missing '{}'", closing.unwrap()));
+                }
+                _ => return Ok(atoms),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "chemical-elements")]
+const TOO_MANY_ATOMS: &str = "the number of atoms is too large";
+
+/// Add the atoms of a group, multiplied by the given count
+#[cfg(feature = "chemical-elements")]
+fn multiply_counts(
+    atoms: &mut Vec<(String, u32)>,
+    group: Vec<(String, u32)>,
+    count: u32,
+) -> std::result::Result<(), String> {
+    for (symbol, n) in group {
+        atoms.push((symbol, n.checked_mul(count).ok_or(TOO_MANY_ATOMS)?));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "chemical-elements")]
+pub fn _molar_mass_raw(mut args: Args) -> Result<Value> {
+    let formula = string_arg!(args);
+
+    let atoms = FormulaParser::parse(&formula).map_err(RuntimeError::InvalidChemicalFormula)?;
+
+    let mut molar_mass = 0.0;
+    for (symbol, count) in atoms {
+        let element = mendeleev::Element::list()
+            .iter()
+            .find(|e| e.symbol() == symbol)
+            .ok_or_else(|| {
+                RuntimeError::InvalidChemicalFormula(format!("unknown element '{symbol}'"))
+            })?;
+        molar_mass += f64::from(element.atomic_weight()) * f64::from(count);
+    }
+
+    return_scalar!(molar_mass)
+}
@@ -28,6 +28,20 @@ macro_rules! list_arg {
 }
 pub(crate) use list_arg;
 
+macro_rules! list_elements_arg {
+    ($args:ident) => {
+        arg!($args).unsafe_as_list_elements()
+    };
+}
+pub(crate) use list_elements_arg;
+
+macro_rules! dict_arg {
+    ($args:ident) => {
+        arg!($args).unsafe_as_dict()
+    };
+}
+pub(crate) use dict_arg;
+
 macro_rules! string_arg {
     ($args:ident) => {
         arg!($args).unsafe_as_string()
@@ -35,11 +49,13 @@ macro_rules! string_arg {
 }
 pub(crate) use string_arg;
 
+#[cfg(feature = "datetime")]
 macro_rules! datetime_arg {
     ($args:ident) => {
         arg!($args).unsafe_as_datetime()
     };
 }
+#[cfg(feature = "datetime")]
 pub(crate) use datetime_arg;
 
 macro_rules! return_scalar {
@@ -77,9 +93,11 @@ macro_rules! return_string {
 }
 pub(crate) use return_string;
 
+#[cfg(feature = "datetime")]
 macro_rules! return_datetime {
     ($value:expr) => {
         Ok(Value::DateTime($value))
     };
 }
+#[cfg(feature = "datetime")]
 pub(crate) use return_datetime;
@@ -3,7 +3,12 @@ use super::Args;
 use super::Result;
 
 use crate::quantity::Quantity;
+use crate::special_functions;
 use crate::value::Value;
+use crate::RuntimeError;
+
+use rand::rngs::StdRng;
+use rand::Rng;
 
 pub fn mod_(mut args: Args) -> Result<Value> {
     let x = quantity_arg!(args);
@@ -15,6 +20,11 @@ pub fn mod_(mut args: Args) -> Result<Value> {
     return_quantity!(x_value.rem_euclid(y_value), x.unit().clone())
 }
 
+pub fn abs(mut args: Args) -> Result<Value> {
+    let arg = quantity_arg!(args);
+    return_quantity!(arg.unsafe_value().to_f64().abs(), arg.unit().clone())
+}
+
 // Similar, but with signature 'Fn[(Scalar) -> Scalar]'
 macro_rules! simple_scalar_math_function {
     ($name:ident, $op:ident) => {
@@ -25,7 +35,6 @@ macro_rules! simple_scalar_math_function {
     };
 }
 
-simple_scalar_math_function!(abs, abs);
 simple_scalar_math_function!(round, round);
 simple_scalar_math_function!(floor, floor);
 simple_scalar_math_function!(ceil, ceil);
@@ -59,12 +68,45 @@ simple_scalar_math_function!(ln, ln);
 simple_scalar_math_function!(log10, log10);
 simple_scalar_math_function!(log2, log2);
 
-pub fn gamma(mut args: Args) -> Result<Value> {
-    let input = scalar_arg!(args).to_f64();
+// Similar, but for functions from the special_functions module
+macro_rules! special_scalar_function {
+    ($name:ident, $function:ident) => {
+        pub fn $name(mut args: Args) -> Result<Value> {
+            let input = scalar_arg!(args).to_f64();
+            return_scalar!(special_functions::$function(input))
+        }
+    };
+}
+
+special_scalar_function!(gamma, gamma);
+special_scalar_function!(ln_gamma, ln_gamma);
+special_scalar_function!(erf, error_function);
+special_scalar_function!(erfc, complementary_error_function);
+special_scalar_function!(elliptic_k, elliptic_k);
+special_scalar_function!(elliptic_e, elliptic_e);
+
+// Bessel functions with signature 'Fn[(Scalar, Scalar) -> Scalar]', where the
+// first argument is the (integer) order
+macro_rules! bessel_function {
+    ($name:ident) => {
+        pub fn $name(mut args: Args) -> Result<Value> {
+            let order = scalar_arg!(args).to_f64();
+            let x = scalar_arg!(args).to_f64();
+
+            if order.fract() != 0.0 || order.abs() > i32::MAX as f64 {
+                return Err(RuntimeError::NonIntegerBesselOrder);
+            }
 
-    return_scalar!(crate::gamma::gamma(input))
+            return_scalar!(special_functions::$name(order as i32, x))
+        }
+    };
 }
 
+bessel_function!(bessel_j);
+bessel_function!(bessel_y);
+bessel_function!(bessel_i);
+bessel_function!(bessel_k);
+
 pub fn is_nan(mut args: Args) -> Result<Value> {
     let arg = quantity_arg!(args);
 
@@ -77,6 +119,6 @@ pub fn is_infinite(mut args: Args) -> Result<Value> {
     return_boolean!(arg.unsafe_value().to_f64().is_infinite())
 }
 
-pub fn random(_args: Args) -> Result<Value> {
-    return_scalar!(rand::random::<f64>())
+pub fn random(rng: &mut StdRng, _args: Args) -> Result<Value> {
+    return_scalar!(rng.gen::<f64>())
 }
@@ -1,18 +1,27 @@
 mod currency;
 mod datetime;
+mod dicts;
+mod environment;
+mod files;
 mod functions;
+mod json;
 mod lists;
 mod lookup;
 mod macros;
 mod math;
+mod number_theory;
 mod plot;
 mod procedures;
+mod quantities;
+mod statistics;
 mod strings;
+mod tables;
 
 use std::collections::VecDeque;
 
 use crate::interpreter::RuntimeError;
 use crate::span::Span;
+use crate::unit_lookup::UnitLookup;
 use crate::value::Value;
 use crate::vm::ExecutionContext;
 
@@ -26,8 +35,26 @@ pub(crate) type Args = VecDeque<Value>;
 
 type BoxedFunction = Box<dyn Fn(Args) -> Result<Value> + Send + Sync>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Capability {
+    FileSystem,
+    Network,
+    Environment,
+}
+
 pub(crate) enum Callable {
     Function(BoxedFunction),
+    /// A function that needs access to the random number generator of the VM
+    FunctionWithRng(fn(&mut rand::rngs::StdRng, Args) -> Result<Value>),
+    /// A function that accesses the outside world. It can only be called if
+    /// the capability has been granted to the context.
+    FunctionWithCapability(Capability, fn(Args) -> Result<Value>),
+    /// A function with a capability that also needs to look up units
+    FunctionWithCapabilityAndUnitLookup(Capability, fn(&UnitLookup, Args) -> Result<Value>),
+    /// A function that needs access to the command-line arguments of a script
+    FunctionWithScriptArguments(fn(&[String], Args) -> Result<Value>),
+    /// A function that needs to look up units by their name
+    FunctionWithUnitLookup(fn(&UnitLookup, Args) -> Result<Value>),
     Procedure(fn(&mut ExecutionContext, Args, Vec<Span>) -> ControlFlow),
 }
 
@@ -37,5 +64,17 @@ pub(crate) struct ForeignFunction {
     pub(crate) callable: Callable,
 }
 
+/// The number of bytes that a call of the foreign function `name` with the given
+/// arguments allocates for its result, as far as this is known in advance. It is
+/// checked against the memory limit before the function is called, so that huge
+/// lists or strings are not built in the first place.
+pub(crate) fn expected_allocation(name: &str, args: &Args) -> usize {
+    match name {
+        "cons" | "cons_end" => lists::materialized_size(args),
+        "str_repeat" => strings::repeated_size(args),
+        _ => 0,
+    }
+}
+
 pub(crate) use functions::functions;
 pub(crate) use procedures::procedures;
@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+
+use crate::quantity::Quantity;
+use crate::value::Value;
+use crate::RuntimeError;
+
+/// The largest integer up to which all integers can be represented exactly as `f64`
+const MAX_EXACT_INTEGER: u64 = 1 << 53;
+
+fn integer_arg(value: f64) -> Result<i64> {
+    if value.fract() != 0.0 || value.abs() > MAX_EXACT_INTEGER as f64 {
+        return Err(RuntimeError::ExpectedExactInteger(value.to_string()));
+    }
+    Ok(value as i64)
+}
+
+fn non_negative_integer_arg(value: f64) -> Result<u64> {
+    let integer = integer_arg(value)?;
+    u64::try_from(integer).map_err(|_| RuntimeError::ExpectedNonNegativeInteger(value.to_string()))
+}
+
+fn exact_integer_result(value: u64) -> Result<Value> {
+    if value > MAX_EXACT_INTEGER {
+        return Err(RuntimeError::IntegerResultTooLarge);
+    }
+    return_scalar!(value as f64)
+}
+
+fn gcd_of(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn modpow_of(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result as u64
+}
+
+/// Deterministic Miller-Rabin test. Testing these bases is sufficient for all
+/// integers below 3.3 × 10^24, see <https://oeis.org/A014233>.
+fn is_prime_number(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if n < 2 {
+        return false;
+    }
+    if let Some(&base) = BASES.iter().find(|&&base| n % base == 0) {
+        return n == base;
+    }
+
+    let trailing_zeros = (n - 1).trailing_zeros();
+    let odd_part = (n - 1) >> trailing_zeros;
+
+    BASES.iter().all(|&base| {
+        let mut x = modpow_of(base, odd_part, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        for _ in 1..trailing_zeros {
+            x = modpow_of(x, 2, n);
+            if x == n - 1 {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Prime factors by trial division, in ascending order. We stop as soon as
+/// the remaining cofactor is prime, so large prime factors are cheap.
+fn prime_factors_of(mut n: u64) -> Vec<u64> {
+    let mut factors = vec![];
+    let mut divisor = 2;
+    while n > 1 && !is_prime_number(n) {
+        // n is composite, so it has a divisor that is at most √n
+        while n % divisor != 0 {
+            divisor += if divisor == 2 { 1 } else { 2 };
+        }
+        while n % divisor == 0 {
+            factors.push(divisor);
+            n /= divisor;
+        }
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Return exact results where they fit into the range of exactly representable
+/// integers and a floating point approximation otherwise.
+fn combinatorial_result(exact: Option<u128>, approximation: f64) -> Result<Value> {
+    match exact {
+        Some(value) if value <= MAX_EXACT_INTEGER as u128 => return_scalar!(value as f64),
+        _ if approximation.is_finite() => return_scalar!(approximation),
+        _ => Err(RuntimeError::FloatOverflow),
+    }
+}
+
+pub fn binom(mut args: Args) -> Result<Value> {
+    let n = non_negative_integer_arg(scalar_arg!(args).to_f64())?;
+    let k = non_negative_integer_arg(scalar_arg!(args).to_f64())?;
+
+    if k > n {
+        return_scalar!(0.0)
+    } else {
+        let k = k.min(n - k);
+
+        // Every intermediate result is itself a binomial coefficient, so the
+        // divisions are exact
+        let mut exact = Some(1u128);
+        let mut approximation = 1.0;
+        for i in 1..=k {
+            let factor = n - k + i;
+            exact = exact
+                .and_then(|e| e.checked_mul(factor as u128))
+                .map(|e| e / i as u128);
+            approximation *= factor as f64 / i as f64;
+            if approximation.is_infinite() {
+                break;
+            }
+        }
+
+        combinatorial_result(exact, approximation)
+    }
+}
+
+pub fn perm(mut args: Args) -> Result<Value> {
+    let n = non_negative_integer_arg(scalar_arg!(args).to_f64())?;
+    let k = non_negative_integer_arg(scalar_arg!(args).to_f64())?;
+
+    if k > n {
+        return_scalar!(0.0)
+    } else {
+        let mut exact = Some(1u128);
+        let mut approximation = 1.0;
+        for factor in (n - k + 1)..=n {
+            exact = exact.and_then(|e| e.checked_mul(factor as u128));
+            approximation *= factor as f64;
+            if approximation.is_infinite() {
+                break;
+            }
+        }
+
+        combinatorial_result(exact, approximation)
+    }
+}
+
+/// The largest argument of `factorial_exact`, whose result has 35,660 digits
+const MAX_EXACT_FACTORIAL_ARGUMENT: u64 = 10_000;
+
+/// Decimal digits of n!, computed with a big integer in base 10^9
+fn factorial_digits(n: u64) -> String {
+    const BASE: u64 = 1_000_000_000;
+
+    // Little-endian limbs
+    let mut limbs = vec![1u64];
+    for factor in 2..=n {
+        let mut carry = 0;
+        for limb in limbs.iter_mut() {
+            let product = *limb * factor + carry;
+            *limb = product % BASE;
+            carry = product / BASE;
+        }
+        while carry > 0 {
+            limbs.push(carry % BASE);
+            carry /= BASE;
+        }
+    }
+
+    let mut digits = limbs.last().unwrap().to_string();
+    for limb in limbs.iter().rev().skip(1) {
+        digits.push_str(&format!("{limb:09}"));
+    }
+    digits
+}
+
+pub fn factorial_exact(mut args: Args) -> Result<Value> {
+    let n = non_negative_integer_arg(scalar_arg!(args).to_f64())?;
+    if n > MAX_EXACT_FACTORIAL_ARGUMENT {
+        return Err(RuntimeError::IntegerResultTooLarge);
+    }
+
+    return_string!(factorial_digits(n))
+}
+
+pub fn gcd(mut args: Args) -> Result<Value> {
+    let a = integer_arg(scalar_arg!(args).to_f64())?;
+    let b = integer_arg(scalar_arg!(args).to_f64())?;
+
+    return_scalar!(gcd_of(a.unsigned_abs(), b.unsigned_abs()) as f64)
+}
+
+pub fn lcm(mut args: Args) -> Result<Value> {
+    let a = integer_arg(scalar_arg!(args).to_f64())?.unsigned_abs();
+    let b = integer_arg(scalar_arg!(args).to_f64())?.unsigned_abs();
+
+    if a == 0 || b == 0 {
+        return_scalar!(0.0)
+    } else {
+        let lcm = (a / gcd_of(a, b)).checked_mul(b);
+        exact_integer_result(lcm.ok_or(RuntimeError::IntegerResultTooLarge)?)
+    }
+}
+
+pub fn modpow(mut args: Args) -> Result<Value> {
+    let base = integer_arg(scalar_arg!(args).to_f64())?;
+    let exponent = non_negative_integer_arg(scalar_arg!(args).to_f64())?;
+    let modulus_value = scalar_arg!(args).to_f64();
+    let modulus = non_negative_integer_arg(modulus_value)?;
+
+    if modulus == 0 {
+        return Err(RuntimeError::ExpectedPositiveInteger(
+            modulus_value.to_string(),
+        ));
+    }
+
+    let base = base.rem_euclid(modulus as i64) as u64;
+    return_scalar!(modpow_of(base, exponent, modulus) as f64)
+}
+
+pub fn is_prime(mut args: Args) -> Result<Value> {
+    let n = integer_arg(scalar_arg!(args).to_f64())?;
+
+    return_boolean!(u64::try_from(n).is_ok_and(is_prime_number))
+}
+
+pub fn prime_factors(mut args: Args) -> Result<Value> {
+    let value = scalar_arg!(args).to_f64();
+    let n = non_negative_integer_arg(value)?;
+
+    if n == 0 {
+        return Err(RuntimeError::ExpectedPositiveInteger(value.to_string()));
+    }
+
+    let factors = prime_factors_of(n)
+        .into_iter()
+        .map(|factor| Value::Quantity(Quantity::from_scalar(factor as f64)))
+        .collect::<VecDeque<_>>();
+
+    Ok(factors.into())
+}
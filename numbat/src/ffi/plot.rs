@@ -1,3 +1,4 @@
+#[cfg(feature = "plotting")]
 use plotly::Plot;
 
 use super::macros::*;
@@ -6,7 +7,16 @@ use super::Result;
 use crate::value::Value;
 use crate::RuntimeError;
 
-fn line_plot(mut args: Args) -> Plot {
+struct LinePlotData {
+    x_label: String,
+    x_unit: String,
+    y_label: String,
+    y_unit: String,
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+}
+
+fn line_plot_data(mut args: Args) -> LinePlotData {
     let mut fields = arg!(args).unsafe_as_struct_fields();
     let ys = fields.pop().unwrap();
     let xs = fields.pop().unwrap();
@@ -15,6 +25,38 @@ fn line_plot(mut args: Args) -> Plot {
     let x_unit = fields.pop().unwrap().unsafe_as_string();
     let x_label = fields.pop().unwrap().unsafe_as_string();
 
+    let xs = xs
+        .unsafe_as_list_elements()
+        .iter()
+        .map(|e| e.unsafe_as_quantity().unsafe_value().to_f64())
+        .collect::<Vec<_>>();
+    let ys = ys
+        .unsafe_as_list_elements()
+        .iter()
+        .map(|e| e.unsafe_as_quantity().unsafe_value().to_f64())
+        .collect::<Vec<_>>();
+
+    LinePlotData {
+        x_label,
+        x_unit,
+        y_label,
+        y_unit,
+        xs,
+        ys,
+    }
+}
+
+#[cfg(feature = "plotting")]
+fn line_plot(args: Args) -> Plot {
+    let LinePlotData {
+        x_label,
+        x_unit,
+        y_label,
+        y_unit,
+        xs,
+        ys,
+    } = line_plot_data(args);
+
     let x_label = format!(
         "{x_label}{x_unit}",
         x_unit = if x_unit.is_empty() {
@@ -32,22 +74,10 @@ fn line_plot(mut args: Args) -> Plot {
         }
     );
 
-    let xs = xs
-        .unsafe_as_list()
-        .iter()
-        .cloned()
-        .map(|e| e.unsafe_as_quantity().unsafe_value().to_f64())
-        .collect::<Vec<_>>();
-    let ys = ys
-        .unsafe_as_list()
-        .iter()
-        .cloned()
-        .map(|e| e.unsafe_as_quantity().unsafe_value().to_f64())
-        .collect::<Vec<_>>();
-
     crate::plot::line_plot(xs, ys, &x_label, &y_label)
 }
 
+#[cfg(feature = "plotting")]
 fn bar_chart(mut args: Args) -> Plot {
     let mut fields = arg!(args).unsafe_as_struct_fields();
     let x_labels = fields.pop().unwrap();
@@ -56,15 +86,13 @@ fn bar_chart(mut args: Args) -> Plot {
     let value_label = fields.pop().unwrap().unsafe_as_string();
 
     let x_labels = x_labels
-        .unsafe_as_list()
+        .unsafe_as_list_elements()
         .iter()
-        .cloned()
         .map(|e| e.unsafe_as_string())
         .collect::<Vec<_>>();
     let values = values
-        .unsafe_as_list()
+        .unsafe_as_list_elements()
         .iter()
-        .cloned()
         .map(|e| e.unsafe_as_quantity().unsafe_value().to_f64())
         .collect::<Vec<_>>();
 
@@ -80,14 +108,14 @@ fn bar_chart(mut args: Args) -> Plot {
     crate::plot::bar_chart(values, x_labels, &value_label)
 }
 
-#[cfg(not(target_family = "wasm"))]
+#[cfg(all(feature = "plotting", not(target_family = "wasm")))]
 fn show_plot(plot: Plot) -> String {
     plot.show();
 
     "Plot will be opened in the browser".into()
 }
 
-#[cfg(target_family = "wasm")]
+#[cfg(all(feature = "plotting", target_family = "wasm"))]
 fn show_plot(_plot: Plot) -> String {
     // The way we could implement this would be to return plot.to_inline_html(..).
     // This would have to be retrieved on the JS side and then rendered using plotly.js.
@@ -95,6 +123,12 @@ fn show_plot(_plot: Plot) -> String {
     "Plotting is currently not supported on this platform.".into()
 }
 
+#[cfg(not(feature = "plotting"))]
+pub fn show(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("plotting".into()))
+}
+
+#[cfg(feature = "plotting")]
 pub fn show(args: Args) -> Result<Value> {
     // Dynamic dispatch hack since we don't have bounded polymorphism.
     // And no real support for generics in the FFI.
@@ -117,3 +151,50 @@ pub fn show(args: Args) -> Result<Value> {
 
     return_string!(show_plot(plot))
 }
+
+/// Check that the argument is a line plot. Bar charts are only supported by
+/// the browser-based backend.
+fn expect_line_plot(args: &Args) -> Result<()> {
+    match args.front().unwrap() {
+        Value::StructInstance(info, _) if info.name == "LinePlot" => Ok(()),
+        Value::StructInstance(info, _) => Err(RuntimeError::UserError(format!(
+            "Unsupported plot type: {}",
+            info.name
+        ))),
+        _ => Err(RuntimeError::UserError(
+            "Unsupported argument to plotting function.".into(),
+        )),
+    }
+}
+
+pub fn text_plot(args: Args) -> Result<Value> {
+    expect_line_plot(&args)?;
+    let plot = line_plot_data(args);
+
+    let rendered = crate::plot_rendering::text_line_plot(
+        &plot.xs,
+        &plot.ys,
+        &plot.x_label,
+        &plot.x_unit,
+        &plot.y_label,
+        &plot.y_unit,
+    )
+    .ok_or(RuntimeError::NothingToPlot)?;
+    return_string!(rendered)
+}
+
+pub fn svg_plot(args: Args) -> Result<Value> {
+    expect_line_plot(&args)?;
+    let plot = line_plot_data(args);
+
+    let rendered = crate::plot_rendering::svg_line_plot(
+        &plot.xs,
+        &plot.ys,
+        &plot.x_label,
+        &plot.x_unit,
+        &plot.y_label,
+        &plot.y_unit,
+    )
+    .ok_or(RuntimeError::NothingToPlot)?;
+    return_string!(rendered)
+}
@@ -0,0 +1,208 @@
+use indexmap::IndexMap;
+use num_traits::{ToPrimitive, Zero};
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::arithmetic::Exponent;
+use crate::pretty_print::PrettyPrint;
+use crate::quantity::Quantity;
+use crate::registry::{BaseRepresentation, BaseRepresentationFactor};
+use crate::unit_lookup::UnitLookup;
+use crate::value::Value;
+use crate::RuntimeError;
+
+/// Parse a quantity and make sure that it can be converted to the unit of the
+/// given reference quantity. The type checker only knows the dimension of the
+/// reference, so this has to be checked at runtime.
+fn parse_with_reference(units: &UnitLookup, input: &str, reference: &Quantity) -> Result<Quantity> {
+    let error = |reason: String| RuntimeError::CouldNotParseQuantity(input.into(), reason);
+
+    let quantity = units.parse_quantity(input).map_err(error)?;
+
+    if quantity.convert_to(reference.unit()).is_err() {
+        return Err(error(if reference.unit().is_scalar() {
+            "expected a number without a unit".into()
+        } else {
+            format!("expected a quantity in units of '{}'", reference.unit())
+        }));
+    }
+
+    Ok(quantity)
+}
+
+pub fn parse_quantity(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let reference = quantity_arg!(args);
+
+    Ok(Value::Quantity(parse_with_reference(
+        units, &input, &reference,
+    )?))
+}
+
+pub fn parse_quantity_or(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let default = quantity_arg!(args);
+
+    let quantity = parse_with_reference(units, &input, &default).unwrap_or(default);
+    Ok(Value::Quantity(quantity))
+}
+
+pub fn unit_name(mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+
+    return_string!(quantity.unit().to_string())
+}
+
+pub fn dimension_name(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+    let dimension = units.dimension_of(quantity.unit());
+
+    return_string!(units.dimension_name(&dimension))
+}
+
+pub fn dimension_exponents(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+
+    let exponents: IndexMap<_, _> = units
+        .dimension_of(quantity.unit())
+        .iter()
+        .map(|factor| {
+            let exponent = factor.1.to_f64().unwrap_or(f64::NAN);
+            (
+                factor.0.clone(),
+                Value::Quantity(Quantity::from_scalar(exponent)),
+            )
+        })
+        .collect();
+    Ok(Value::Dict(exponents))
+}
+
+pub fn is_dimensionless(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+
+    return_boolean!(units.dimension_of(quantity.unit()).iter().next().is_none())
+}
+
+pub fn speak(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args).full_simplify();
+    let markup = units
+        .with_long_unit_names(quantity.pretty_print())
+        .with_spoken_numbers();
+
+    return_string!(markup.to_string().trim())
+}
+
+pub fn is_convertible(mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+    let target = quantity_arg!(args);
+
+    return_boolean!(quantity.convert_to(target.unit()).is_ok())
+}
+
+pub fn try_convert(mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+    let target = quantity_arg!(args);
+
+    Ok(Value::List(
+        quantity
+            .convert_to(target.unit())
+            .ok()
+            .map(Value::Quantity)
+            .into_iter()
+            .collect(),
+    ))
+}
+
+/// With `c = ħ = 1`, a quantity with the dimension `Mass^a Length^b Time^c`
+/// is an energy to the power of `a - b - c`. Other base dimensions can not be
+/// expressed in terms of energies.
+fn natural_energy_exponent(dimension: &BaseRepresentation) -> Option<Exponent> {
+    let mut exponent = Exponent::zero();
+    for BaseRepresentationFactor(name, e) in dimension.iter() {
+        match name.as_str() {
+            "Mass" => exponent += *e,
+            "Length" | "Time" => exponent -= *e,
+            _ => return None,
+        }
+    }
+    Some(exponent)
+}
+
+/// Convert a quantity to the given unit by multiplying it with powers of `c`
+/// and `ħ`, if that is possible.
+fn convert_in_natural_units(
+    units: &UnitLookup,
+    quantity: Quantity,
+    target: &Quantity,
+    c: Quantity,
+    hbar: Quantity,
+) -> Result<Quantity> {
+    let dimension = units.dimension_of(quantity.unit());
+    let target_dimension = units.dimension_of(target.unit());
+    let difference = dimension.clone() / target_dimension.clone();
+
+    if natural_energy_exponent(&difference) != Some(Exponent::zero()) {
+        return Err(RuntimeError::NotConvertibleInNaturalUnits(
+            units.dimension_name(&dimension),
+            units.dimension_name(&target_dimension),
+        ));
+    }
+
+    // Find α and β such that `quantity × c^α × ħ^β` has the target dimension
+    let exponent_of = |name: &str| {
+        difference
+            .iter()
+            .find(|factor| factor.0 == name)
+            .map(|factor| factor.1)
+            .unwrap_or_else(Exponent::zero)
+    };
+    let beta = -exponent_of("Mass");
+    let alpha = exponent_of("Time") - beta;
+
+    let scalar = |e: Exponent| Quantity::from_scalar(e.to_f64().unwrap_or(f64::NAN));
+    let result = quantity
+        * c.power(scalar(alpha))
+            .map_err(RuntimeError::QuantityError)?
+        * hbar
+            .power(scalar(beta))
+            .map_err(RuntimeError::QuantityError)?;
+
+    result
+        .convert_to(target.unit())
+        .map_err(RuntimeError::QuantityError)
+}
+
+pub fn _natural_convert(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+    let target = quantity_arg!(args);
+    let c = quantity_arg!(args);
+    let hbar = quantity_arg!(args);
+
+    Ok(Value::Quantity(convert_in_natural_units(
+        units, quantity, &target, c, hbar,
+    )?))
+}
+
+pub fn _natural_energy_power(units: &UnitLookup, mut args: Args) -> Result<Value> {
+    let quantity = quantity_arg!(args);
+    let energy_unit = quantity_arg!(args);
+    let c = quantity_arg!(args);
+    let hbar = quantity_arg!(args);
+
+    let dimension = units.dimension_of(quantity.unit());
+    let Some(exponent) = natural_energy_exponent(&dimension) else {
+        return Err(RuntimeError::NotConvertibleInNaturalUnits(
+            units.dimension_name(&dimension),
+            "Energy".into(),
+        ));
+    };
+
+    let target = energy_unit
+        .power(Quantity::from_scalar(exponent.to_f64().unwrap_or(f64::NAN)))
+        .map_err(RuntimeError::QuantityError)?;
+    // Dimensionless quantities are shown without a `GeV^(0)`
+    let target = Quantity::from_unit(target.unit().canonicalized());
+
+    return_string!(convert_in_natural_units(units, quantity, &target, c, hbar)?.to_string())
+}
@@ -0,0 +1,231 @@
+use std::collections::VecDeque;
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+
+use crate::quantity::Quantity;
+use crate::unit::Unit;
+use crate::value::{ListElements, Value};
+use crate::RuntimeError;
+
+/// The unit of the first element of a list of quantities, to which all
+/// elements are converted. Returns `None` for an empty list.
+fn common_unit(list: &ListElements) -> Option<Unit> {
+    let first = list.iter().next()?.unsafe_as_quantity();
+    Some(first.unit().clone())
+}
+
+fn value_in(value: Value, unit: &Unit) -> f64 {
+    // The type checker makes sure that all elements have the same dimension
+    value
+        .unsafe_as_quantity()
+        .convert_to(unit)
+        .unwrap()
+        .unsafe_value()
+        .to_f64()
+}
+
+/// The values of all elements of a list of quantities in the given unit.
+/// Ranges are not materialized.
+fn values_in<'a>(list: &'a ListElements, unit: &'a Unit) -> impl Iterator<Item = f64> + 'a {
+    list.iter().map(|v| value_in(v, unit))
+}
+
+fn values_in_common_unit(list: &ListElements) -> Option<(Unit, Vec<f64>)> {
+    let unit = common_unit(list)?;
+    let values = values_in(list, &unit).collect();
+    Some((unit, values))
+}
+
+fn mean_of(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Population variance, computed with the two-pass algorithm
+fn variance_of(values: &[f64]) -> f64 {
+    let mean = mean_of(values);
+    values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+/// Linear interpolation between the closest ranks (method 7 in Hyndman and Fan,
+/// the default in R and NumPy). The values need to be sorted.
+fn quantile_of_sorted(sorted: &[f64], p: f64) -> f64 {
+    let h = (sorted.len() - 1) as f64 * p;
+    let lower = h.floor() as usize;
+    let upper = h.ceil() as usize;
+    sorted[lower] + (h - lower as f64) * (sorted[upper] - sorted[lower])
+}
+
+fn sorted(mut values: Vec<f64>) -> Vec<f64> {
+    values.sort_by(f64::total_cmp);
+    values
+}
+
+fn paired_values(
+    xs: &ListElements,
+    ys: &ListElements,
+) -> Result<Option<((Unit, Vec<f64>), (Unit, Vec<f64>))>> {
+    if xs.len() != ys.len() {
+        return Err(RuntimeError::ListLengthMismatch(xs.len(), ys.len()));
+    }
+    Ok(values_in_common_unit(xs).zip(values_in_common_unit(ys)))
+}
+
+fn covariance_of(xs: &[f64], ys: &[f64]) -> f64 {
+    let mean_x = mean_of(xs);
+    let mean_y = mean_of(ys);
+    xs.iter()
+        .zip(ys)
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>()
+        / xs.len() as f64
+}
+
+/// Solve the linear system `a · x = b` using Gaussian elimination with partial
+/// pivoting. The matrix needs to be non-singular.
+fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+            .unwrap();
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+        let pivot_b = b[col];
+        for (row, b_row) in a.iter_mut().zip(b.iter_mut()).skip(col + 1) {
+            let factor = row[col] / pivot_row[col];
+            for (entry, pivot_entry) in row.iter_mut().zip(&pivot_row).skip(col) {
+                *entry -= factor * pivot_entry;
+            }
+            *b_row -= factor * pivot_b;
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = ((row + 1)..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    x
+}
+
+/// Least-squares polynomial coefficients (constant term first), obtained
+/// from the normal equations.
+fn polynomial_fit_of(xs: &[f64], ys: &[f64], degree: usize) -> Vec<f64> {
+    let powers = |x: f64| (0..=degree).map(move |k| x.powi(k as i32));
+
+    let mut normal_matrix = vec![vec![0.0; degree + 1]; degree + 1];
+    let mut rhs = vec![0.0; degree + 1];
+    for (&x, &y) in xs.iter().zip(ys) {
+        for (i, x_i) in powers(x).enumerate() {
+            for (j, x_j) in powers(x).enumerate() {
+                normal_matrix[i][j] += x_i * x_j;
+            }
+            rhs[i] += x_i * y;
+        }
+    }
+
+    solve_linear_system(normal_matrix, rhs)
+}
+
+pub fn mean(mut args: Args) -> Result<Value> {
+    let list = list_elements_arg!(args);
+
+    let Some(unit) = common_unit(&list) else {
+        return return_scalar!(0.0);
+    };
+    let mean = match &list {
+        // The elements of a range are evenly spaced
+        ListElements::Range(range) => {
+            (value_in(range.head().unwrap(), &unit) + value_in(range.last().unwrap(), &unit)) / 2.0
+        }
+        ListElements::List(_) => values_in(&list, &unit).sum::<f64>() / list.len() as f64,
+    };
+    return_quantity!(mean, unit)
+}
+
+pub fn variance(mut args: Args) -> Result<Value> {
+    let list = list_elements_arg!(args);
+
+    let Some(unit) = common_unit(&list) else {
+        return return_scalar!(0.0);
+    };
+    // Two passes over the elements, to avoid materializing ranges
+    let n = list.len() as f64;
+    let mean = values_in(&list, &unit).sum::<f64>() / n;
+    let variance = values_in(&list, &unit)
+        .map(|x| (x - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    return_quantity!(variance, unit.clone() * unit)
+}
+
+pub fn median(mut args: Args) -> Result<Value> {
+    let list = list_elements_arg!(args);
+
+    let (unit, values) = values_in_common_unit(&list).ok_or(RuntimeError::EmptyList)?;
+    return_quantity!(quantile_of_sorted(&sorted(values), 0.5), unit)
+}
+
+pub fn quantile(mut args: Args) -> Result<Value> {
+    let list = list_elements_arg!(args);
+    let p = scalar_arg!(args).to_f64();
+
+    if !(0.0..=1.0).contains(&p) {
+        return Err(RuntimeError::QuantileOutOfRange);
+    }
+
+    let (unit, values) = values_in_common_unit(&list).ok_or(RuntimeError::EmptyList)?;
+    return_quantity!(quantile_of_sorted(&sorted(values), p), unit)
+}
+
+pub fn covariance(mut args: Args) -> Result<Value> {
+    let xs = list_elements_arg!(args);
+    let ys = list_elements_arg!(args);
+
+    let ((unit_x, xs), (unit_y, ys)) = paired_values(&xs, &ys)?.ok_or(RuntimeError::EmptyList)?;
+    return_quantity!(covariance_of(&xs, &ys), unit_x * unit_y)
+}
+
+pub fn correlation(mut args: Args) -> Result<Value> {
+    let xs = list_elements_arg!(args);
+    let ys = list_elements_arg!(args);
+
+    let ((_, xs), (_, ys)) = paired_values(&xs, &ys)?.ok_or(RuntimeError::EmptyList)?;
+    return_scalar!(covariance_of(&xs, &ys) / (variance_of(&xs) * variance_of(&ys)).sqrt())
+}
+
+pub fn fit_polynomial(mut args: Args) -> Result<Value> {
+    let xs = list_elements_arg!(args);
+    let ys = list_elements_arg!(args);
+    let degree = scalar_arg!(args).to_f64();
+    let x_unit = quantity_arg!(args);
+
+    if degree < 0.0 || degree.fract() != 0.0 {
+        return Err(RuntimeError::InvalidPolynomialDegree);
+    }
+    let degree = degree as usize;
+
+    let ((unit_x, xs), (unit_y, ys)) = paired_values(&xs, &ys)?.ok_or(RuntimeError::EmptyList)?;
+
+    let mut distinct_xs = sorted(xs.clone());
+    distinct_xs.dedup();
+    if distinct_xs.len() <= degree {
+        return Err(RuntimeError::NotEnoughDataPointsForFit(degree));
+    }
+
+    // The type checker makes sure that x_unit has the same dimension as xs
+    let scale = x_unit.convert_to(&unit_x).unwrap().unsafe_value().to_f64();
+    let xs: Vec<f64> = xs.iter().map(|x| x / scale).collect();
+
+    let coefficients = polynomial_fit_of(&xs, &ys, degree)
+        .into_iter()
+        .map(|c| Value::Quantity(Quantity::new_f64(c, unit_y.clone())))
+        .collect::<VecDeque<_>>();
+
+    Ok(coefficients.into())
+}
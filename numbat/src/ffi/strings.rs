@@ -1,6 +1,13 @@
+#[cfg(feature = "regex")]
+use std::collections::VecDeque;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
 use super::macros::*;
 use super::Args;
 use super::Result;
+use crate::pretty_print::{MathFormat, PrettyPrint};
 use crate::quantity::Quantity;
 use crate::value::Value;
 use crate::RuntimeError;
@@ -36,6 +43,31 @@ pub fn chr(mut args: Args) -> Result<Value> {
     return_string!(output)
 }
 
+pub fn hex_float(mut args: Args) -> Result<Value> {
+    return_string!(quantity_arg!(args).unsafe_value().to_hex_float())
+}
+
+fn to_math(mut args: Args, format: MathFormat) -> Result<Value> {
+    let value = match arg!(args) {
+        Value::Quantity(q) => Value::Quantity(q.full_simplify()),
+        value => value,
+    };
+
+    return_string!(value.to_math(format))
+}
+
+pub fn latex(args: Args) -> Result<Value> {
+    to_math(args, MathFormat::Latex)
+}
+
+pub fn typst(args: Args) -> Result<Value> {
+    to_math(args, MathFormat::Typst)
+}
+
+pub fn mathml(args: Args) -> Result<Value> {
+    to_math(args, MathFormat::MathMl)
+}
+
 pub fn ord(mut args: Args) -> Result<Value> {
     let input = string_arg!(args);
 
@@ -47,3 +79,170 @@ pub fn ord(mut args: Args) -> Result<Value> {
 
     return_scalar!(output as f64)
 }
+
+pub fn str_find(mut args: Args) -> Result<Value> {
+    let haystack = string_arg!(args);
+    let needle = string_arg!(args);
+
+    match haystack.find(needle.as_str()) {
+        Some(index) => return_scalar!(index as f64),
+        None => return_scalar!(-1.0),
+    }
+}
+
+pub fn str_replace(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let pattern = string_arg!(args);
+    let replacement = string_arg!(args);
+
+    if pattern.is_empty() {
+        return_string!(input)
+    } else {
+        return_string!(input.replace(pattern.as_str(), &replacement))
+    }
+}
+
+pub fn str_trim(mut args: Args) -> Result<Value> {
+    return_string!(string_arg!(args).trim())
+}
+
+pub fn str_trim_start(mut args: Args) -> Result<Value> {
+    return_string!(string_arg!(args).trim_start())
+}
+
+pub fn str_trim_end(mut args: Args) -> Result<Value> {
+    return_string!(string_arg!(args).trim_end())
+}
+
+pub fn str_starts_with(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let prefix = string_arg!(args);
+
+    return_boolean!(input.starts_with(prefix.as_str()))
+}
+
+pub fn str_ends_with(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let suffix = string_arg!(args);
+
+    return_boolean!(input.ends_with(suffix.as_str()))
+}
+
+pub fn capitalize(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+
+    let mut chars = input.chars();
+    let output = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+
+    return_string!(output)
+}
+
+/// The number of repetitions for `str_repeat`. Like `n` recursive calls that
+/// stop once `n` is no longer positive, non-integer counts are rounded up.
+fn repeat_count(n: f64) -> Result<usize> {
+    if n <= 0.0 {
+        Ok(0)
+    } else if n.ceil() <= 2f64.powi(53) {
+        Ok(n.ceil() as usize)
+    } else {
+        Err(RuntimeError::ExpectedExactInteger(n.to_string()))
+    }
+}
+
+/// The length of the result of `str_repeat`, which is checked against the memory
+/// limit before the string is built.
+pub(crate) fn repeated_size(args: &Args) -> usize {
+    match (&args[0], &args[1]) {
+        (Value::String(s), Value::Quantity(n)) => {
+            let count = repeat_count(n.unsafe_value().to_f64()).unwrap_or(usize::MAX);
+            s.len().saturating_mul(count)
+        }
+        _ => 0,
+    }
+}
+
+pub fn str_repeat(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let count = repeat_count(scalar_arg!(args).to_f64())?;
+
+    return_string!(input.repeat(count))
+}
+
+#[cfg(feature = "regex")]
+fn regex_arg(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| RuntimeError::InvalidRegex(e.to_string()))
+}
+
+#[cfg(feature = "regex")]
+fn string_list<'a>(strings: impl Iterator<Item = &'a str>) -> Value {
+    strings
+        .map(|s| Value::String(s.into()))
+        .collect::<VecDeque<_>>()
+        .into()
+}
+
+#[cfg(feature = "regex")]
+pub fn regex_match(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let regex = regex_arg(&string_arg!(args))?;
+
+    return_boolean!(regex.is_match(&input))
+}
+
+#[cfg(feature = "regex")]
+pub fn regex_find(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let regex = regex_arg(&string_arg!(args))?;
+
+    Ok(string_list(regex.find_iter(&input).map(|m| m.as_str())))
+}
+
+#[cfg(feature = "regex")]
+pub fn regex_captures(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let regex = regex_arg(&string_arg!(args))?;
+
+    // Groups that did not participate in the match are returned as empty strings,
+    // so that the position of a group in the list does not depend on the input
+    let groups = regex.captures(&input).map(|captures| {
+        captures
+            .iter()
+            .skip(1)
+            .map(|group| group.map(|m| m.as_str()).unwrap_or_default())
+            .collect::<Vec<_>>()
+    });
+
+    Ok(string_list(groups.unwrap_or_default().into_iter()))
+}
+
+#[cfg(feature = "regex")]
+pub fn regex_replace(mut args: Args) -> Result<Value> {
+    let input = string_arg!(args);
+    let regex = regex_arg(&string_arg!(args))?;
+    let replacement = string_arg!(args);
+
+    return_string!(regex.replace_all(&input, replacement.as_str()))
+}
+
+#[cfg(not(feature = "regex"))]
+pub fn regex_match(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("regex".into()))
+}
+
+#[cfg(not(feature = "regex"))]
+pub fn regex_find(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("regex".into()))
+}
+
+#[cfg(not(feature = "regex"))]
+pub fn regex_captures(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("regex".into()))
+}
+
+#[cfg(not(feature = "regex"))]
+pub fn regex_replace(_args: Args) -> Result<Value> {
+    Err(RuntimeError::FeatureNotAvailable("regex".into()))
+}
@@ -0,0 +1,102 @@
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::value::{FunctionReference, Value};
+use crate::RuntimeError;
+
+fn cell(value: Value) -> String {
+    match value {
+        Value::String(s) => s,
+        Value::DateTime(dt) => crate::datetime::to_string(&dt),
+        value => value.to_string(),
+    }
+}
+
+fn function_name(reference: FunctionReference) -> String {
+    match reference {
+        FunctionReference::Foreign(name) | FunctionReference::Normal(name) => name,
+        FunctionReference::TzConversion(tz) => tz,
+    }
+}
+
+/// An aligned table with box-drawing separators between the header and the
+/// rows, and between the columns. All columns are right-aligned.
+fn text_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| {
+            std::iter::once(&header[column])
+                .chain(rows.iter().map(|row| &row[column]))
+                .map(|s| s.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let format_row = |row: &[String]| {
+        row.iter()
+            .zip(&widths)
+            .map(|(entry, &width)| format!(" {entry:>width$} "))
+            .collect::<Vec<_>>()
+            .join("│")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![format_row(header)];
+    lines.push(
+        widths
+            .iter()
+            .map(|width| "─".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("┼"),
+    );
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines.join("\n")
+}
+
+fn markdown_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let format_row = |row: &[String]| {
+        let entries: Vec<_> = row.iter().map(|entry| entry.replace('|', "\\|")).collect();
+        format!("| {} |", entries.join(" | "))
+    };
+
+    let mut lines = vec![format_row(header)];
+    lines.push(format!("|{}", "---:|".repeat(header.len())));
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines.join("\n")
+}
+
+fn csv_table(header: &[String], rows: &[Vec<String>]) -> String {
+    std::iter::once(header)
+        .chain(rows.iter().map(|row| row.as_slice()))
+        .map(|row| {
+            row.iter()
+                .map(|entry| crate::csv::escape_field(entry))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn format_table(mut args: Args) -> Result<Value> {
+    let xs = list_elements_arg!(args);
+    let f = arg!(args).unsafe_as_function_reference();
+    let ys = list_elements_arg!(args);
+    let format = string_arg!(args);
+
+    let header = vec!["x".to_string(), format!("{}(x)", function_name(f))];
+    let rows: Vec<Vec<String>> = xs
+        .iter()
+        .zip(ys.iter())
+        .map(|(x, y)| vec![cell(x), cell(y)])
+        .collect();
+
+    let table = match format.as_str() {
+        "text" => text_table(&header, &rows),
+        "markdown" => markdown_table(&header, &rows),
+        "csv" => csv_table(&header, &rows),
+        _ => return Err(RuntimeError::UnknownTableFormat(format)),
+    };
+    return_string!(table)
+}
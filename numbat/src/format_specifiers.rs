@@ -0,0 +1,249 @@
+//! Format specifiers for quantities in string interpolations, like `{x:.3}`,
+//! `{x:e}` or `{x:>10}`. The syntax follows the one from Rust's `std::fmt`:
+//!
+//! ```text
+//! [[fill]align][sign]['0'][width]['.' precision][type]['value']
+//! ```
+//!
+//! Alignment and width apply to the full text, including the unit. The type
+//! can be `f` for fixed-point notation or `e`/`E` for scientific notation. A
+//! trailing `value` prints the number without its unit.
+
+use crate::interpreter::RuntimeError;
+use crate::number::Number;
+use crate::quantity::Quantity;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Notation {
+    Default,
+    Fixed,
+    Scientific,
+    ScientificUppercase,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FormatSpecifiers {
+    fill: char,
+    alignment: Option<Alignment>,
+    plus_sign: bool,
+    zero_padding: bool,
+    width: usize,
+    precision: Option<usize>,
+    notation: Notation,
+    value_only: bool,
+}
+
+fn alignment(c: char) -> Option<Alignment> {
+    match c {
+        '<' => Some(Alignment::Left),
+        '^' => Some(Alignment::Center),
+        '>' => Some(Alignment::Right),
+        _ => None,
+    }
+}
+
+fn parse_number(chars: &[char], position: &mut usize) -> Option<usize> {
+    let start = *position;
+    while chars.get(*position).is_some_and(|c| c.is_ascii_digit()) {
+        *position += 1;
+    }
+    chars[start..*position]
+        .iter()
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+impl FormatSpecifiers {
+    /// Parse specifiers like `:>10.3e`, including the leading colon
+    fn parse(specifiers: &str) -> Result<Self, RuntimeError> {
+        let specifiers = specifiers.strip_prefix(':').unwrap_or(specifiers);
+        let (specifiers, value_only) = match specifiers.strip_suffix("value") {
+            Some(rest) => (rest, true),
+            None => (specifiers, false),
+        };
+
+        let chars: Vec<char> = specifiers.chars().collect();
+        let mut position = 0;
+
+        let (fill, alignment) = match (chars.first(), chars.get(1).copied().and_then(alignment)) {
+            (Some(&fill), Some(align)) => {
+                position = 2;
+                (fill, Some(align))
+            }
+            (Some(&c), None) if alignment(c).is_some() => {
+                position = 1;
+                (' ', alignment(c))
+            }
+            _ => (' ', None),
+        };
+
+        let plus_sign = match chars.get(position) {
+            Some('+') => {
+                position += 1;
+                true
+            }
+            Some('-') => {
+                position += 1;
+                false
+            }
+            _ => false,
+        };
+
+        let zero_padding = chars.get(position) == Some(&'0');
+        if zero_padding {
+            position += 1;
+        }
+
+        let width = parse_number(&chars, &mut position).unwrap_or(0);
+
+        let precision = if chars.get(position) == Some(&'.') {
+            position += 1;
+            Some(parse_number(&chars, &mut position).ok_or_else(|| {
+                RuntimeError::InvalidFormatSpecifiers("Format specifier missing precision".into())
+            })?)
+        } else {
+            None
+        };
+
+        let notation = match chars.get(position) {
+            None => Notation::Default,
+            Some('f') => Notation::Fixed,
+            Some('e') => Notation::Scientific,
+            Some('E') => Notation::ScientificUppercase,
+            Some(c) => {
+                return Err(RuntimeError::InvalidTypeForFormatSpecifiers(format!(
+                    "Unknown format code '{c}' for type 'quantity'"
+                )))
+            }
+        };
+        if notation != Notation::Default {
+            position += 1;
+        }
+
+        if position != chars.len() {
+            return Err(RuntimeError::InvalidFormatSpecifiers(format!(
+                "Unexpected '{}' in format specifiers",
+                chars[position..].iter().collect::<String>()
+            )));
+        }
+
+        Ok(FormatSpecifiers {
+            fill,
+            alignment,
+            plus_sign,
+            zero_padding,
+            width,
+            precision,
+            notation,
+            value_only,
+        })
+    }
+
+    fn format_number(&self, value: f64) -> String {
+        let number = match (self.notation, self.precision) {
+            (Notation::Default, None) => Number::from_f64(value).pretty_print(),
+            (Notation::Default | Notation::Fixed, Some(precision)) => {
+                format!("{value:.precision$}")
+            }
+            (Notation::Fixed, None) => format!("{value:.6}"),
+            (Notation::Scientific, Some(precision)) => format!("{value:.precision$e}"),
+            (Notation::Scientific, None) => format!("{value:e}"),
+            (Notation::ScientificUppercase, Some(precision)) => format!("{value:.precision$E}"),
+            (Notation::ScientificUppercase, None) => format!("{value:E}"),
+        };
+
+        if self.plus_sign && !number.starts_with('-') {
+            format!("+{number}")
+        } else {
+            number
+        }
+    }
+
+    fn format(&self, quantity: &Quantity) -> String {
+        let mut number = self.format_number(quantity.unsafe_value().to_f64());
+        let unit = if self.value_only {
+            String::new()
+        } else {
+            quantity.unit().to_string()
+        };
+        let unit_suffix = if unit.is_empty() {
+            unit
+        } else {
+            format!(" {unit}")
+        };
+
+        let length = number.chars().count() + unit_suffix.chars().count();
+        let padding = self.width.saturating_sub(length);
+
+        if self.zero_padding && self.alignment.is_none() {
+            let sign_length = if number.starts_with(['+', '-']) { 1 } else { 0 };
+            number.insert_str(sign_length, &"0".repeat(padding));
+            return number + &unit_suffix;
+        }
+
+        let text = number + &unit_suffix;
+        let fill = |n: usize| self.fill.to_string().repeat(n);
+        match self.alignment.unwrap_or(Alignment::Right) {
+            Alignment::Left => text + &fill(padding),
+            Alignment::Right => fill(padding) + &text,
+            Alignment::Center => fill(padding / 2) + &text + &fill(padding - padding / 2),
+        }
+    }
+}
+
+pub(crate) fn format_quantity(
+    quantity: &Quantity,
+    specifiers: &str,
+) -> Result<String, RuntimeError> {
+    Ok(FormatSpecifiers::parse(specifiers)?.format(quantity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let specifiers = FormatSpecifiers::parse(":*^+012.3evalue").unwrap();
+        assert_eq!(
+            specifiers,
+            FormatSpecifiers {
+                fill: '*',
+                alignment: Some(Alignment::Center),
+                plus_sign: true,
+                zero_padding: true,
+                width: 12,
+                precision: Some(3),
+                notation: Notation::Scientific,
+                value_only: true,
+            }
+        );
+
+        assert!(FormatSpecifiers::parse(":.").is_err());
+        assert!(FormatSpecifiers::parse(":x").is_err());
+        assert!(FormatSpecifiers::parse(":.3f3").is_err());
+    }
+
+    #[test]
+    fn format_scalars() {
+        let format = |value: f64, specifiers: &str| {
+            format_quantity(&Quantity::from_scalar(value), specifiers).unwrap()
+        };
+
+        assert_eq!(format(1.23456, ":.2"), "1.23");
+        assert_eq!(format(1234.5, ":e"), "1.2345e3");
+        assert_eq!(format(1234.5, ":.2E"), "1.23E3");
+        assert_eq!(format(-4.0, ":06.1"), "-004.0");
+        assert_eq!(format(42.0, ":<5"), "42   ");
+        assert_eq!(format(42.0, ":-^6"), "--42--");
+        assert_eq!(format(42.0, ":+"), "+42");
+    }
+}
@@ -0,0 +1,314 @@
+//! Conversion of unit definitions in the format of GNU `units(1)` (`definitions.units`)
+//! to Numbat code.
+//!
+//! Only the basic constructs are supported: primitive units (`name !`), dimensionless
+//! primitive units (`name !dimensionless`) and derived units (`name definition`).
+//! Prefix definitions, nonlinear units (functions), piecewise linear units (tables)
+//! and directives like `!include` are reported as warnings and skipped.
+
+use std::collections::HashSet;
+
+use heck::ToUpperCamelCase;
+
+use crate::keywords::KEYWORDS;
+use crate::tokenizer::{is_identifier_continue, is_identifier_start};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Line number (1-based) of the definition in the source file
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Conversion {
+    /// The generated Numbat code
+    pub code: String,
+    /// Definitions that could not be converted
+    pub warnings: Vec<Warning>,
+}
+
+fn is_keyword(name: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|k| k.trim_end_matches([' ', '(']) == name)
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_identifier_start)
+        && chars.all(is_identifier_continue)
+        && !is_keyword(name)
+}
+
+/// Translate the right hand side of a GNU units definition to a Numbat expression.
+/// Returns the expression and the names of all units it refers to.
+fn convert_expression(definition: &str) -> Result<(String, Vec<String>), String> {
+    let chars: Vec<char> = definition.chars().collect();
+    let mut output = String::new();
+    let mut references = vec![];
+    let mut i = 0;
+
+    let scan_number = |i: &mut usize| -> String {
+        let start = *i;
+        while *i < chars.len() && (chars[*i].is_ascii_digit() || chars[*i] == '.') {
+            *i += 1;
+        }
+        if *i < chars.len() && (chars[*i] == 'e' || chars[*i] == 'E') {
+            let mut j = *i + 1;
+            if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                j += 1;
+            }
+            if j < chars.len() && chars[j].is_ascii_digit() {
+                *i = j;
+                while *i < chars.len() && chars[*i].is_ascii_digit() {
+                    *i += 1;
+                }
+            }
+        }
+        chars[start..*i].iter().collect()
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => {
+                output.push(' ');
+                i += 1;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let number = scan_number(&mut i);
+                if i < chars.len() && chars[i] == '|' {
+                    // `1|3` is a division of numbers with the highest precedence
+                    i += 1;
+                    if !(i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.')) {
+                        return Err("expected a number after '|'".into());
+                    }
+                    let denominator = scan_number(&mut i);
+                    output.push_str(&format!("({number} / {denominator})"));
+                } else {
+                    output.push_str(&number);
+                }
+            }
+            c if is_identifier_start(c) => {
+                let start = i;
+                while i < chars.len() && is_identifier_continue(chars[i]) {
+                    i += 1;
+                }
+                let identifier: String = chars[start..i].iter().collect();
+
+                // `cm3` means `cm^3`, unless the digit follows an underscore
+                let (name, exponent) = match identifier.char_indices().last() {
+                    Some((idx, d @ '2'..='9')) if !identifier[..idx].ends_with('_') => {
+                        (identifier[..idx].to_string(), Some(d))
+                    }
+                    _ => (identifier.clone(), None),
+                };
+
+                if name.ends_with(|c: char| c.is_ascii_digit()) {
+                    return Err(format!("unsupported identifier '{identifier}'"));
+                }
+
+                output.push_str(&name);
+                if let Some(exponent) = exponent {
+                    output.push('^');
+                    output.push(exponent);
+                }
+                if name != "per" {
+                    references.push(name);
+                }
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                output.push('^');
+                i += 2;
+            }
+            '*' | '/' | '^' | '+' | '-' | '(' | ')' => {
+                output.push(c);
+                i += 1;
+            }
+            c => {
+                return Err(format!("unsupported character '{c}'"));
+            }
+        }
+    }
+
+    let expression = output.split_whitespace().collect::<Vec<_>>().join(" ");
+    if expression.is_empty() {
+        return Err("empty definition".into());
+    }
+
+    Ok((expression, references))
+}
+
+/// Convert the contents of a GNU units definition file. Units for which `is_defined`
+/// returns `true` (e.g. because they are already part of the Numbat prelude) are not
+/// redefined, but they can still be referenced by other definitions.
+pub fn convert(source: &str, is_defined: impl Fn(&str) -> bool) -> Conversion {
+    let mut code = String::new();
+    let mut warnings = vec![];
+    let mut defined = HashSet::new();
+    let mut skipped = HashSet::new();
+
+    let mut warn = |line: usize, message: String| {
+        warnings.push(Warning { line, message });
+    };
+
+    // Join continuation lines (ending in a backslash) and remove comments
+    let mut logical_lines = vec![];
+    let mut current: Option<(usize, String)> = None;
+    for (number, line) in source.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let (start, mut text) = current.take().unwrap_or((number + 1, String::new()));
+        if let Some(line) = line.strip_suffix('\\') {
+            text.push_str(line);
+            text.push(' ');
+            current = Some((start, text));
+        } else {
+            text.push_str(line);
+            logical_lines.push((start, text));
+        }
+    }
+    logical_lines.extend(current);
+
+    for (line, text) in logical_lines {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        if text.starts_with('!') {
+            let directive = text.split_whitespace().next().unwrap_or(text);
+            warn(
+                line,
+                format!("unsupported directive '{directive}', ignored"),
+            );
+            continue;
+        }
+
+        let (name, definition) = match text.split_once(char::is_whitespace) {
+            Some((name, definition)) => (name, definition.trim()),
+            None => (text, ""),
+        };
+
+        if name.ends_with('-') {
+            warn(line, format!("prefix definition '{name}' is not supported"));
+            continue;
+        }
+        if let Some((function_name, _)) = name.split_once(['(', '[']) {
+            warn(
+                line,
+                format!("nonlinear unit '{function_name}' is not supported"),
+            );
+            skipped.insert(function_name.to_string());
+            continue;
+        }
+        if !is_valid_identifier(name) {
+            warn(line, format!("'{name}' is not a valid Numbat identifier"));
+            skipped.insert(name.to_string());
+            continue;
+        }
+        if is_defined(name) {
+            continue;
+        }
+        if defined.contains(name) {
+            warn(line, format!("'{name}' is defined multiple times, skipped"));
+            continue;
+        }
+
+        if definition == "!" {
+            let dimension = name.to_upper_camel_case();
+            if is_defined(&dimension) || defined.contains(&dimension) {
+                warn(
+                    line,
+                    format!("dimension '{dimension}' for primitive unit '{name}' already exists"),
+                );
+                skipped.insert(name.to_string());
+                continue;
+            }
+            code += &format!("dimension {dimension}\nunit {name}: {dimension}\n\n");
+            defined.insert(dimension);
+        } else if definition == "!dimensionless" {
+            code += &format!("unit {name} = 1\n\n");
+        } else {
+            match convert_expression(definition) {
+                Ok((expression, references)) => {
+                    if let Some(reference) = references.iter().find(|r| skipped.contains(*r)) {
+                        warn(
+                            line,
+                            format!("'{name}' depends on '{reference}', which has been skipped"),
+                        );
+                        skipped.insert(name.to_string());
+                        continue;
+                    }
+                    code += &format!("unit {name} = {expression}\n\n");
+                }
+                Err(reason) => {
+                    warn(line, format!("could not convert '{name}': {reason}"));
+                    skipped.insert(name.to_string());
+                    continue;
+                }
+            }
+        }
+
+        defined.insert(name.to_string());
+    }
+
+    Conversion { code, warnings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_definitions() {
+        let source = "
+# comment
+meter       !
+m           meter         # short name
+furlong     1|8 mile
+acre        10 chain2
+smoot       5 ft + 7 inch
+flow        ft**3 / \\
+              s
+";
+        let conversion = convert(source, |name| {
+            ["mile", "chain", "ft", "inch", "s"].contains(&name)
+        });
+
+        assert_eq!(
+            conversion.code,
+            "dimension Meter\nunit meter: Meter\n\n\
+             unit m = meter\n\n\
+             unit furlong = (1 / 8) mile\n\n\
+             unit acre = 10 chain^2\n\n\
+             unit smoot = 5 ft + 7 inch\n\n\
+             unit flow = ft^3 / s\n\n"
+        );
+        assert!(conversion.warnings.is_empty());
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        let source = "
+!include currency.units
+kilo-       1e3
+tempC(x)    units=[1;K] x K + stdtemp
+tempCdiff   tempC(1) - tempC(0)
+'           foot
+radian      !dimensionless
+second      !
+";
+        let conversion = convert(source, |name| name == "second");
+
+        assert_eq!(conversion.code, "unit radian = 1\n\n");
+        assert_eq!(
+            conversion
+                .warnings
+                .iter()
+                .map(|w| w.line)
+                .collect::<Vec<_>>(),
+            [2, 3, 4, 5, 6]
+        );
+    }
+}
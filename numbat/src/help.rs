@@ -15,6 +15,7 @@ fn evaluate_example(context: &mut Context, input: &str) -> m::Markup {
         print_fn: Box::new(move |s: &m::Markup| {
             statement_output_c.lock().unwrap().push(s.clone());
         }),
+        ..Default::default()
     };
 
     let (statements, interpreter_result) = context
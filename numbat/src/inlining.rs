@@ -0,0 +1,318 @@
+//! Inlining of small functions during bytecode compilation. A call like
+//! `sqr(x - 1)` is compiled as if `(x - 1)^2` had been written, which avoids
+//! the overhead of setting up a call frame. As the VM does not distinguish
+//! between physical dimensions, this also specializes generic functions to the
+//! arguments at the call site, and calls in the inlined body can be inlined in
+//! turn.
+//!
+//! Inlining must not change the behavior of a program. Arguments are therefore
+//! only substituted into the function body if they are trivial (literals and
+//! identifiers, which can not fail, and which can be evaluated any number of
+//! times), or if there is a single non-trivial argument that would be evaluated
+//! exactly once and before anything else in the body.
+
+use crate::traversal::ForAllExpressions;
+use crate::typed_ast::{DefineVariable, Expression, StringPart};
+
+/// Larger functions are not inlined, to keep the bytecode small
+const MAX_BODY_SIZE: usize = 16;
+
+/// Calls in inlined bodies are inlined as well, up to this depth
+pub(crate) const MAX_INLINING_DEPTH: usize = 8;
+
+#[derive(Debug)]
+pub(crate) struct InlineCandidate {
+    parameters: Vec<String>,
+    body: Expression,
+    /// How often each parameter is used in the body
+    parameter_uses: Vec<usize>,
+    /// Parameters that are evaluated before any operation in the body
+    leading_parameters: Vec<String>,
+    /// Functions, global variables and units that are referenced in the body,
+    /// along with their indices at the time of definition
+    called_functions: Vec<(String, Option<u16>)>,
+    globals: Vec<(String, u16)>,
+    units: Vec<(String, u16)>,
+}
+
+impl InlineCandidate {
+    /// Returns `None` if the function can not be inlined. Bodies of inlined
+    /// functions may only refer to their parameters and to global variables
+    /// (like in `fn speed_of_light() = c`), such that they can be compiled in
+    /// any scope. Recursive functions are never inlined.
+    ///
+    /// `function_index` returns `None` for foreign functions, `global_index`
+    /// returns `None` for names that are not global variables.
+    pub(crate) fn new(
+        name: &str,
+        parameters: &[String],
+        body: &Expression,
+        local_variables: &[DefineVariable],
+        function_index: impl Fn(&str) -> Option<u16>,
+        global_index: impl Fn(&str) -> Option<u16>,
+        unit_index: impl Fn(&str) -> u16,
+    ) -> Option<Self> {
+        if !local_variables.is_empty() {
+            return None;
+        }
+
+        let mut size = 0;
+        let mut is_inlinable = true;
+        let mut parameter_uses = vec![0; parameters.len()];
+        let mut called_functions = vec![];
+        let mut globals = vec![];
+        let mut units = vec![];
+        body.for_all_expressions(&mut |expr| {
+            size += 1;
+            match expr {
+                Expression::Identifier(_, identifier, _) => {
+                    match parameters.iter().position(|p| p == identifier) {
+                        Some(index) => parameter_uses[index] += 1,
+                        None => match global_index(identifier) {
+                            Some(index) => globals.push((identifier.clone(), index)),
+                            None => is_inlinable = false,
+                        },
+                    }
+                }
+                Expression::FunctionCall(_, _, callee, _, _) => {
+                    // The uncertainty of a parameter is zero, but it would not
+                    // be after substituting an argument like `G`
+                    if callee == name || callee == "uncertainty" {
+                        is_inlinable = false;
+                    } else {
+                        called_functions.push((callee.clone(), function_index(callee)));
+                    }
+                }
+                Expression::UnitIdentifier(_, _, unit_name, _, _) => {
+                    units.push((unit_name.clone(), unit_index(unit_name)));
+                }
+                Expression::TypedHole(..) => is_inlinable = false,
+                _ => {}
+            }
+        });
+
+        if !is_inlinable || size > MAX_BODY_SIZE {
+            return None;
+        }
+
+        let mut leading_identifiers = LeadingIdentifiers::default();
+        leading_identifiers.visit(body);
+
+        Some(InlineCandidate {
+            parameters: parameters.to_vec(),
+            body: body.clone(),
+            parameter_uses,
+            leading_parameters: leading_identifiers
+                .identifiers
+                .into_iter()
+                .cloned()
+                .collect(),
+            called_functions,
+            globals,
+            units,
+        })
+    }
+
+    /// A function that has been inlined needs to behave like the original
+    /// function, which refers to the functions, global variables and units
+    /// that were defined at the time of its definition. Returns `false` if any
+    /// of them has been redefined since then, or if `global_index` reports that
+    /// a global variable is shadowed at the call site.
+    pub(crate) fn is_up_to_date(
+        &self,
+        function_index: impl Fn(&str) -> Option<u16>,
+        global_index: impl Fn(&str) -> Option<u16>,
+        unit_index: impl Fn(&str) -> u16,
+    ) -> bool {
+        self.called_functions
+            .iter()
+            .all(|(name, index)| function_index(name) == *index)
+            && self
+                .globals
+                .iter()
+                .all(|(name, index)| global_index(name) == Some(*index))
+            && self
+                .units
+                .iter()
+                .all(|(name, index)| unit_index(name) == *index)
+    }
+
+    /// Returns the body of the function with the parameters replaced by the
+    /// given arguments, or `None` if this would change the behavior.
+    pub(crate) fn instantiate(&self, arguments: &[Expression]) -> Option<Expression> {
+        if arguments.len() != self.parameters.len() {
+            return None;
+        }
+
+        let mut non_trivial_arguments =
+            (0..arguments.len()).filter(|&i| !is_trivial(&arguments[i]));
+        if let Some(index) = non_trivial_arguments.next() {
+            let parameter = &self.parameters[index];
+            if non_trivial_arguments.next().is_some()
+                || self.parameter_uses[index] != 1
+                || !self.leading_parameters.contains(parameter)
+            {
+                return None;
+            }
+        }
+
+        let mut body = self.body.clone();
+        substitute(&mut body, &self.parameters, arguments);
+        Some(body)
+    }
+}
+
+/// Arguments that can not fail and that have no side effects
+fn is_trivial(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Scalar(..)
+            | Expression::Boolean(..)
+            | Expression::Identifier(..)
+            | Expression::UnitIdentifier(..)
+            | Expression::DateTime(..)
+    )
+}
+
+/// Collects the identifiers that are evaluated before the first operation
+/// (which could fail), in the order in which the code is generated.
+#[derive(Default)]
+struct LeadingIdentifiers<'a> {
+    identifiers: Vec<&'a String>,
+    operation_found: bool,
+}
+
+impl<'a> LeadingIdentifiers<'a> {
+    fn visit(&mut self, expr: &'a Expression) {
+        if self.operation_found {
+            return;
+        }
+
+        match expr {
+            Expression::Scalar(..)
+            | Expression::Boolean(..)
+            | Expression::UnitIdentifier(..)
+            | Expression::DateTime(..) => return,
+            Expression::Identifier(_, identifier, _) => {
+                self.identifiers.push(identifier);
+                return;
+            }
+            Expression::UnaryOperator(_, _, expr, _) => self.visit(expr),
+            Expression::BinaryOperator(_, _, lhs, rhs, _)
+            | Expression::BinaryOperatorForDate(_, _, lhs, rhs, _) => {
+                self.visit(lhs);
+                self.visit(rhs);
+            }
+            Expression::FunctionCall(_, _, _, args, _) => {
+                for arg in args {
+                    self.visit(arg);
+                }
+            }
+            Expression::CallableCall(_, callable, args, _) => {
+                for arg in args {
+                    self.visit(arg);
+                }
+                self.visit(callable);
+            }
+            Expression::Condition(_, condition, _, _) => self.visit(condition),
+            Expression::String(_, parts) => {
+                for part in parts {
+                    if let StringPart::Interpolation { expr, .. } = part {
+                        self.visit(expr);
+                    }
+                }
+            }
+            Expression::AccessField(_, _, expr, _, _, _)
+            | Expression::UpdateStruct(_, expr, _, _) => self.visit(expr),
+            Expression::List(_, elements, _) => {
+                for element in elements {
+                    self.visit(element);
+                }
+            }
+            Expression::Dict(_, entries, _) => {
+                for (key, value) in entries {
+                    self.visit(key);
+                    self.visit(value);
+                }
+            }
+            Expression::Range(_, start, end, step, _, _) => {
+                self.visit(start);
+                self.visit(end);
+                if let Some(step) = step {
+                    self.visit(step);
+                }
+            }
+            // The fields of struct instances are evaluated in a different order
+            Expression::InstantiateStruct(..) | Expression::TypedHole(..) => {}
+        }
+
+        self.operation_found = true;
+    }
+}
+
+/// Replace the given parameters in `expr` by the corresponding arguments
+pub(crate) fn substitute(expr: &mut Expression, parameters: &[String], arguments: &[Expression]) {
+    let substitute = |expr: &mut Expression| substitute(expr, parameters, arguments);
+
+    match expr {
+        Expression::Identifier(_, identifier, _) => {
+            if let Some(index) = parameters.iter().position(|p| p == identifier) {
+                *expr = arguments[index].clone();
+            }
+        }
+        Expression::Scalar(..)
+        | Expression::UnitIdentifier(..)
+        | Expression::Boolean(..)
+        | Expression::DateTime(..)
+        | Expression::TypedHole(..) => {}
+        Expression::UnaryOperator(_, _, expr, _) => substitute(expr),
+        Expression::BinaryOperator(_, _, lhs, rhs, _)
+        | Expression::BinaryOperatorForDate(_, _, lhs, rhs, _) => {
+            substitute(lhs);
+            substitute(rhs);
+        }
+        Expression::FunctionCall(_, _, _, args, _) => args.iter_mut().for_each(substitute),
+        Expression::CallableCall(_, callable, args, _) => {
+            substitute(callable);
+            args.iter_mut().for_each(substitute);
+        }
+        Expression::Condition(_, condition, then_expr, else_expr) => {
+            substitute(condition);
+            substitute(then_expr);
+            substitute(else_expr);
+        }
+        Expression::String(_, parts) => {
+            for part in parts {
+                if let StringPart::Interpolation { expr, .. } = part {
+                    substitute(expr);
+                }
+            }
+        }
+        Expression::InstantiateStruct(_, fields, _) => {
+            for (_, expr) in fields {
+                substitute(expr);
+            }
+        }
+        Expression::AccessField(_, _, expr, _, _, _) => substitute(expr),
+        Expression::UpdateStruct(_, expr, fields, _) => {
+            substitute(expr);
+            for (_, value) in fields {
+                substitute(value);
+            }
+        }
+        Expression::List(_, elements, _) => elements.iter_mut().for_each(substitute),
+        Expression::Dict(_, entries, _) => {
+            for (key, value) in entries {
+                substitute(key);
+                substitute(value);
+            }
+        }
+        Expression::Range(_, start, end, step, _, _) => {
+            substitute(start);
+            substitute(end);
+            if let Some(step) = step {
+                substitute(step);
+            }
+        }
+    }
+}
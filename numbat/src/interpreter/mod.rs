@@ -41,6 +41,10 @@ pub enum RuntimeError {
     UserError(String),
     #[error("Unrecognized datetime format: {0}")]
     DateParsingError(String),
+    #[error("Unrecognized duration format: '{0}'. Expected an ISO 8601 duration like 'P1DT2H30M' or a compact duration like '1h30m'")]
+    DurationParsingError(String),
+    #[error("Unrecognized clock time format: '{0}'. Expected a clock time like '1:23:45' or '23:45.5'")]
+    ClockTimeParsingError(String),
     #[error("Unknown timezone: {0}")]
     UnknownTimezone(String),
     #[error("Exceeded maximum size for time durations")]
@@ -58,8 +62,89 @@ pub enum RuntimeError {
     #[error("Chemical element not found: {0}")]
     ChemicalElementNotFound(String),
 
+    #[error("Invalid chemical formula: {0}")]
+    InvalidChemicalFormula(String),
+
     #[error("Empty list")]
     EmptyList,
+    #[error("Invalid range: {0}")]
+    InvalidRange(String),
+    #[error("Key not found in dictionary: \"{0}\"")]
+    KeyNotFound(String),
+    #[error("Expected lists of equal length, got lists with {0} and {1} elements")]
+    ListLengthMismatch(usize, usize),
+    #[error("Quantile must be between 0 and 1")]
+    QuantileOutOfRange,
+    #[error("Expected an integer with an absolute value of at most 2^53, got {0}")]
+    ExpectedExactInteger(String),
+    #[error("Expected a non-negative integer, got {0}")]
+    ExpectedNonNegativeInteger(String),
+    #[error("Expected a positive integer, got {0}")]
+    ExpectedPositiveInteger(String),
+    #[error("Result is too large to be represented as an exact integer")]
+    IntegerResultTooLarge,
+    #[error("Result is too large to be represented as a floating point number")]
+    FloatOverflow,
+    #[error("The order of Bessel functions must be an integer")]
+    NonIntegerBesselOrder,
+    #[error("Polynomial degree must be a non-negative integer")]
+    InvalidPolynomialDegree,
+    #[error("Not enough distinct data points to fit a polynomial of degree {0}")]
+    NotEnoughDataPointsForFit(usize),
+
+    #[error("There are no finite data points to plot")]
+    NothingToPlot,
+
+    #[error("Unknown table format '{0}'. Supported formats are 'text', 'markdown' and 'csv'")]
+    UnknownTableFormat(String),
+
+    #[error("'{0}' needs access to the file system, which has not been enabled")]
+    FileSystemAccessDisabled(String),
+
+    #[error("'{0}' needs network access, which has not been enabled")]
+    NetworkAccessDisabled(String),
+
+    #[error("'{0}' needs access to environment variables, which has not been enabled")]
+    EnvironmentAccessDisabled(String),
+
+    #[error("Environment variable '{0}' is not set")]
+    EnvironmentVariableNotSet(String),
+
+    #[error("Could not read file '{0}': {1}")]
+    CouldNotReadFile(String, String),
+
+    #[error("Could not write file '{0}': {1}")]
+    CouldNotWriteFile(String, String),
+
+    #[error("Invalid CSV data: {0}")]
+    InvalidCsvData(String),
+
+    #[error("Invalid JSON data: {0}")]
+    InvalidJsonData(String),
+
+    #[error("Could not fetch '{0}': {1}")]
+    CouldNotFetchUrl(String, String),
+
+    #[error("Invalid regular expression: {0}")]
+    InvalidRegex(String),
+
+    #[error("Could not parse '{0}' as a quantity: {1}")]
+    CouldNotParseQuantity(String, String),
+
+    #[error("Evaluation has been interrupted")]
+    Interrupted,
+
+    #[error("Evaluation exceeded the memory limit of {0} bytes")]
+    MemoryLimitExceeded(usize),
+
+    #[error("This build of Numbat does not include the '{0}' feature")]
+    FeatureNotAvailable(String),
+
+    #[error("Not a unit: '{0}'")]
+    NotAUnit(String),
+
+    #[error("Can not convert between '{0}' and '{1}' in natural units (c = ħ = 1)")]
+    NotConvertibleInNaturalUnits(String, String),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -136,8 +221,32 @@ pub type Result<T> = std::result::Result<T, RuntimeError>;
 
 pub type PrintFunction = dyn FnMut(&Markup) + Send;
 
+/// Information about the instruction that the VM is about to execute.
+#[derive(Debug)]
+pub struct TraceEvent<'a> {
+    /// Name of the function that is being executed (`<main>` for global code)
+    pub function_name: &'a str,
+    /// Offset of the instruction in the bytecode of that function
+    pub offset: usize,
+    /// Name of the instruction, e.g. `LoadConstant`
+    pub op: &'static str,
+    /// The value on top of the stack, if any
+    pub stack_top: Option<&'a Value>,
+    /// Span of the expression that this instruction has been compiled from
+    pub span: Option<Span>,
+}
+
+pub type TraceFunction = dyn FnMut(&TraceEvent<'_>) + Send;
+
 pub struct InterpreterSettings {
     pub print_fn: Box<PrintFunction>,
+
+    /// Called before every single instruction of the VM, if set. This is
+    /// meant for debugging and comes with a significant performance cost.
+    /// Spans are only available for code that has been compiled with a trace
+    /// function (or in debug mode), e.g. not for functions that were defined
+    /// in an earlier evaluation without one.
+    pub trace_fn: Option<Box<TraceFunction>>,
 }
 
 impl Default for InterpreterSettings {
@@ -146,6 +255,7 @@ impl Default for InterpreterSettings {
             print_fn: Box::new(move |s: &Markup| {
                 print!("{s}");
             }),
+            trace_fn: None,
         }
     }
 }
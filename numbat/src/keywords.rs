@@ -7,6 +7,7 @@ pub const KEYWORDS: &[&str] = &[
     "let ",
     "fn ",
     "where ",
+    "with ",
     "dimension ",
     "unit ",
     "use ",
@@ -37,7 +38,15 @@ pub const KEYWORDS: &[&str] = &[
     // decorators
     "metric_prefixes",
     "binary_prefixes",
+    "relative",
+    "reciprocal",
+    "fractional",
+    "natural_conversion",
+    "simplification_target",
+    "deprecated",
+    "shadowing",
     "aliases",
+    "alias",
     "name",
     "url",
 ];
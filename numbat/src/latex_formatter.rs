@@ -0,0 +1,327 @@
+//! A markup backend that writes LaTeX (math mode). Quantities are written with
+//! the `\qty`, `\num` and `\unit` macros of the `siunitx` package, such that
+//! results and expressions can be pasted into documents.
+
+use crate::markup::{FormatType, FormattedString, Formatter, Markup};
+use crate::unit::{unit_string_parts, UnitStringPart};
+
+pub struct LatexFormatter;
+
+/// SI prefixes, by their long name
+const PREFIXES: [(&str, &str); 24] = [
+    ("quecto", r"\quecto"),
+    ("ronto", r"\ronto"),
+    ("yocto", r"\yocto"),
+    ("zepto", r"\zepto"),
+    ("atto", r"\atto"),
+    ("femto", r"\femto"),
+    ("pico", r"\pico"),
+    ("nano", r"\nano"),
+    ("micro", r"\micro"),
+    ("milli", r"\milli"),
+    ("centi", r"\centi"),
+    ("deci", r"\deci"),
+    ("deca", r"\deca"),
+    ("hecto", r"\hecto"),
+    ("kilo", r"\kilo"),
+    ("mega", r"\mega"),
+    ("giga", r"\giga"),
+    ("tera", r"\tera"),
+    ("peta", r"\peta"),
+    ("exa", r"\exa"),
+    ("zetta", r"\zetta"),
+    ("yotta", r"\yotta"),
+    ("ronna", r"\ronna"),
+    ("quetta", r"\quetta"),
+];
+
+/// Units that have a macro in siunitx, by their long name
+const UNITS: [(&str, &str); 37] = [
+    ("metre", r"\metre"),
+    ("meter", r"\metre"),
+    ("second", r"\second"),
+    ("gram", r"\gram"),
+    ("ampere", r"\ampere"),
+    ("kelvin", r"\kelvin"),
+    ("mole", r"\mole"),
+    ("candela", r"\candela"),
+    ("radian", r"\radian"),
+    ("steradian", r"\steradian"),
+    ("hertz", r"\hertz"),
+    ("newton", r"\newton"),
+    ("pascal", r"\pascal"),
+    ("joule", r"\joule"),
+    ("watt", r"\watt"),
+    ("coulomb", r"\coulomb"),
+    ("volt", r"\volt"),
+    ("farad", r"\farad"),
+    ("ohm", r"\ohm"),
+    ("siemens", r"\siemens"),
+    ("weber", r"\weber"),
+    ("tesla", r"\tesla"),
+    ("henry", r"\henry"),
+    ("lumen", r"\lumen"),
+    ("lux", r"\lux"),
+    ("becquerel", r"\becquerel"),
+    ("gray", r"\gray"),
+    ("sievert", r"\sievert"),
+    ("katal", r"\katal"),
+    ("litre", r"\litre"),
+    ("liter", r"\litre"),
+    ("minute", r"\minute"),
+    ("hour", r"\hour"),
+    ("day", r"\day"),
+    ("tonne", r"\tonne"),
+    ("electronvolt", r"\electronvolt"),
+    ("degree", r"\degree"),
+];
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn text(content: &str) -> String {
+    format!(r"\text{{{}}}", escape(content))
+}
+
+/// The argument of a `\num` macro, or `None` if this is not a decimal number
+fn number(value: &str) -> Option<String> {
+    let value = value.replace('_', "");
+    value
+        .parse::<f64>()
+        .is_ok_and(f64::is_finite)
+        .then_some(value)
+}
+
+fn latex_number(value: &str) -> String {
+    match value {
+        "inf" => r"\infty".into(),
+        "-inf" => r"-\infty".into(),
+        _ => match number(value) {
+            Some(number) => format!(r"\num{{{number}}}"),
+            None => text(value),
+        },
+    }
+}
+
+/// Convert a unit to the argument of a siunitx `\unit` macro. Long unit names
+/// like `kilometre` (as used by the pretty printer) are written with the unit
+/// macros of siunitx (`\kilo\metre`). Everything else is written in the literal
+/// syntax of siunitx, e.g. `kg.m^{2}/s^{2}` for `kg·m²/s²`.
+pub fn siunitx_unit(unit: &str) -> String {
+    match unit {
+        "°" => return r"\degree".into(),
+        "′" => return r"\arcminute".into(),
+        "″" => return r"\arcsecond".into(),
+        "%" => return r"\percent".into(),
+        _ => {}
+    }
+
+    let (prefix, name) = PREFIXES
+        .iter()
+        .find_map(|(long, latex)| unit.strip_prefix(*long).map(|name| (*latex, name)))
+        .filter(|(_, name)| UNITS.iter().any(|(long, _)| *long == *name))
+        .unwrap_or(("", unit));
+    if let Some((_, latex)) = UNITS.iter().find(|(long, _)| *long == name) {
+        return format!("{prefix}{latex}");
+    }
+
+    let mut result = String::new();
+    for part in unit_string_parts(unit) {
+        match part {
+            UnitStringPart::Name(name) => {
+                for c in name.chars() {
+                    if matches!(c, '#' | '$' | '%' | '&' | '_') {
+                        result.push('\\');
+                    }
+                    result.push(c);
+                }
+            }
+            UnitStringPart::Times => result.push('.'),
+            UnitStringPart::Over => result.push('/'),
+            UnitStringPart::Exponent(exponent) => result.push_str(&format!("^{{{exponent}}}")),
+            UnitStringPart::OpenParen => result.push('('),
+            UnitStringPart::CloseParen => result.push(')'),
+        }
+    }
+    result
+}
+
+fn latex_operator(operator: &str) -> String {
+    match operator {
+        "*" | "×" => r"\times".into(),
+        "·" => r"\cdot".into(),
+        "÷" => r"\div".into(),
+        "->" | "→" | "➞" | "to" => r"\to".into(),
+        "==" => "=".into(),
+        "!=" | "≠" => r"\neq".into(),
+        "<=" | "≤" => r"\leq".into(),
+        ">=" | "≥" => r"\geq".into(),
+        "&&" => r"\land".into(),
+        "||" => r"\lor".into(),
+        "!" => r"\lnot".into(),
+        "{" => r"\{".into(),
+        "}" => r"\}".into(),
+        "%" => r"\%".into(),
+        "+" | "-" | "/" | "=" | "<" | ">" | "(" | ")" | "[" | "]" | "," | ":" | "^" => {
+            operator.into()
+        }
+        _ => text(operator),
+    }
+}
+
+fn latex_identifier(identifier: &str) -> String {
+    if identifier.chars().count() == 1 {
+        identifier.into()
+    } else {
+        format!(r"\mathrm{{{}}}", escape(identifier))
+    }
+}
+
+impl Formatter for LatexFormatter {
+    fn format_part(&self, FormattedString(_, format_type, s): &FormattedString) -> String {
+        match format_type {
+            FormatType::Whitespace if s.contains('\n') => r" \\ ".into(),
+            FormatType::Whitespace => " ".into(),
+            FormatType::Value => latex_number(s),
+            FormatType::Unit if s.is_empty() => String::new(),
+            FormatType::Unit => format!(r"\unit{{{}}}", siunitx_unit(s)),
+            FormatType::Identifier | FormatType::TypeIdentifier => latex_identifier(s),
+            FormatType::Operator => latex_operator(s),
+            FormatType::Emphasized
+            | FormatType::Dimmed
+            | FormatType::Text
+            | FormatType::String
+            | FormatType::Keyword
+            | FormatType::Decorator => {
+                if s.trim().is_empty() {
+                    " ".into()
+                } else {
+                    text(s)
+                }
+            }
+        }
+    }
+
+    /// Values that are directly followed by a unit are written as `\qty`, and
+    /// exponents are wrapped in braces.
+    fn format(&self, markup: &Markup, _indent: bool) -> String {
+        let mut output = String::new();
+        let mut parts = markup.0.iter();
+        let mut exponent_open = false;
+
+        while let Some(part) = parts.next() {
+            let formatted = match (part, number(&part.2)) {
+                (FormattedString(_, FormatType::Value, _), Some(value)) => {
+                    let mut lookahead = parts.clone();
+                    let next = match lookahead.next() {
+                        Some(FormattedString(_, FormatType::Whitespace, s)) if s == " " => {
+                            lookahead.next()
+                        }
+                        next => next,
+                    };
+
+                    match next {
+                        Some(FormattedString(_, FormatType::Unit, unit)) if !unit.is_empty() => {
+                            parts = lookahead;
+                            format!(r"\qty{{{value}}}{{{}}}", siunitx_unit(unit))
+                        }
+                        _ => self.format_part(part),
+                    }
+                }
+                _ => self.format_part(part),
+            };
+
+            if exponent_open && part.1 != FormatType::Whitespace {
+                output.push_str(&formatted);
+                output.push('}');
+                exponent_open = false;
+            } else if part.1 == FormatType::Operator && part.2 == "^" {
+                output.push_str("^{");
+                exponent_open = true;
+            } else {
+                output.push_str(&formatted);
+            }
+        }
+        if exponent_open {
+            output.push('}');
+        }
+
+        output.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup as m;
+
+    fn latex(markup: Markup) -> String {
+        LatexFormatter.format(&markup, false)
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(siunitx_unit("km/h"), "km/h");
+        assert_eq!(siunitx_unit("kg·m²/s²"), "kg.m^{2}/s^{2}");
+        assert_eq!(siunitx_unit("m·s⁻¹"), "m.s^{-1}");
+        assert_eq!(siunitx_unit("m^(1/2)"), "m^{1/2}");
+        assert_eq!(siunitx_unit("°"), r"\degree");
+        assert_eq!(siunitx_unit("kilometre"), r"\kilo\metre");
+        assert_eq!(siunitx_unit("second"), r"\second");
+        assert_eq!(siunitx_unit("foot"), "foot");
+        assert_eq!(siunitx_unit("$"), r"\$");
+    }
+
+    #[test]
+    fn quantities() {
+        assert_eq!(
+            latex(m::value("3") + m::space() + m::unit("km/h")),
+            r"\qty{3}{km/h}"
+        );
+        assert_eq!(latex(m::value("30") + m::unit("°")), r"\qty{30}{\degree}");
+        assert_eq!(
+            latex(m::value("1_234_567") + m::space() + m::unit("m")),
+            r"\qty{1234567}{m}"
+        );
+        assert_eq!(latex(m::value("1.602e-19")), r"\num{1.602e-19}");
+        assert_eq!(latex(m::value("inf")), r"\infty");
+    }
+
+    #[test]
+    fn expressions() {
+        let expression = m::identifier("v_0")
+            + m::space()
+            + m::operator("×")
+            + m::space()
+            + m::identifier("t")
+            + m::operator("^")
+            + m::value("2")
+            + m::space()
+            + m::operator("->")
+            + m::space()
+            + m::unit("kilometre");
+        assert_eq!(
+            latex(expression),
+            r"\mathrm{v\_0} \times t^{\num{2}} \to \unit{\kilo\metre}"
+        );
+
+        assert_eq!(
+            latex(m::keyword("if") + m::space() + m::identifier("x")),
+            r"\text{if} x"
+        );
+    }
+}
@@ -4,63 +4,91 @@ mod ast;
 pub mod buffered_writer;
 mod bytecode_interpreter;
 mod column_formatter;
+mod compat_syntax;
+mod csv;
 mod currency;
 mod datetime;
+mod dead_code;
 mod decorator;
 pub mod diagnostic;
 mod dimension;
 mod ffi;
-mod gamma;
+mod format_specifiers;
+pub mod gnu_units;
 pub mod help;
 #[cfg(feature = "html-formatter")]
 pub mod html_formatter;
+mod inlining;
 mod interpreter;
+pub mod json;
 pub mod keywords;
+pub mod latex_formatter;
 pub mod list;
+#[doc(hidden)]
+pub mod macro_support;
 pub mod markup;
 mod math;
+pub mod mathml_formatter;
 pub mod module_importer;
 mod name_resolution;
+mod normalization;
 mod number;
 mod parser;
+#[cfg(feature = "plotting")]
 mod plot;
+mod plot_rendering;
 mod prefix;
 mod prefix_parser;
 mod prefix_transformer;
 pub mod pretty_print;
 mod product;
 mod quantity;
+pub mod range;
 mod registry;
 pub mod resolver;
 mod span;
+mod special_functions;
 mod suggestion;
+mod symbol;
 mod tokenizer;
 mod traversal;
 mod type_variable;
 mod typechecker;
 mod typed_ast;
+pub mod typst_formatter;
 pub mod unicode_input;
 mod unit;
+mod unit_export;
+mod unit_lookup;
 mod unit_registry;
 pub mod value;
 mod vm;
+pub mod warning;
 
+use arithmetic::Exponent;
 use bytecode_interpreter::BytecodeInterpreter;
 use column_formatter::ColumnFormatter;
 use currency::ExchangeRatesCache;
+use decorator::Decorator;
+#[cfg(feature = "terminal")]
 use diagnostic::ErrorDiagnostic;
 use dimension::DimensionRegistry;
 use interpreter::Interpreter;
+use itertools::Itertools;
 use keywords::KEYWORDS;
 use markup as m;
 use markup::FormatType;
 use markup::Markup;
 use module_importer::{ModuleImporter, NullImporter};
+use normalization::{describe_difference, normalize_identifier};
+use number::Number;
+use prefix_parser::AcceptsPrefix;
 use prefix_transformer::Transformer;
 
 use resolver::CodeSource;
 use resolver::Resolver;
 use resolver::ResolverError;
+use symbol::{Interner, Symbol};
 use thiserror::Error;
 use typechecker::{TypeCheckError, TypeChecker};
 
@@ -68,14 +96,27 @@ pub use diagnostic::Diagnostic;
 pub use interpreter::InterpreterResult;
 pub use interpreter::InterpreterSettings;
 pub use interpreter::RuntimeError;
+pub use interpreter::TraceEvent;
+pub use macro_support::QuantityLiteral;
 pub use name_resolution::NameResolutionError;
+pub use number::ScientificNotation;
 pub use parser::ParseError;
+pub use prefix_parser::ShadowingPolicy;
+pub use quantity::Quantity;
+pub use quantity::QuantityError;
 pub use registry::BaseRepresentation;
 pub use registry::BaseRepresentationFactor;
+pub use span::Span;
 pub use typed_ast::Statement;
 pub use typed_ast::Type;
 use unit::BaseUnitAndFactor;
+pub use unit::Unit;
 use unit_registry::UnitMetadata;
+use vm::InterruptFlag;
+pub use vm::InterruptHandle;
+pub use warning::Warning;
+
+use std::sync::Arc;
 
 use crate::prefix_parser::PrefixParserResult;
 use crate::unicode_input::UNICODE_INPUT;
@@ -94,6 +135,63 @@ pub enum NumbatError {
 
 type Result<T> = std::result::Result<T, NumbatError>;
 
+pub type StatementObserver = dyn Fn(&Statement, &InterpreterResult) + Send + Sync;
+
+pub type OutputSink = dyn Fn(&Markup) + Send + Sync;
+
+/// Prefixes and aliases for units that are defined with [`Context::define_base_unit`]
+/// or [`Context::define_unit`].
+#[derive(Debug, Clone, Default)]
+pub struct UnitOptions {
+    /// Alternative names that accept long prefixes, like `meters` (`kilometers`)
+    pub aliases: Vec<String>,
+    /// Alternative names that accept short prefixes, like `m` (`km`)
+    pub short_aliases: Vec<String>,
+    /// Accept metric prefixes (`kilo`, `mega`, …)
+    pub metric_prefixes: bool,
+    /// Accept binary prefixes (`kibi`, `mebi`, …)
+    pub binary_prefixes: bool,
+}
+
+impl UnitOptions {
+    fn decorators(self, interner: &Interner) -> Vec<Decorator> {
+        let mut decorators = vec![];
+        if self.metric_prefixes {
+            decorators.push(Decorator::MetricPrefixes);
+        }
+        if self.binary_prefixes {
+            decorators.push(Decorator::BinaryPrefixes);
+        }
+
+        let aliases: Vec<_> = self
+            .aliases
+            .into_iter()
+            .map(|alias| (alias, Some(AcceptsPrefix::only_long())))
+            .chain(
+                self.short_aliases
+                    .into_iter()
+                    .map(|alias| (alias, Some(AcceptsPrefix::only_short()))),
+            )
+            .map(|(alias, accepts_prefix)| {
+                (
+                    interner.intern(&normalize_identifier(&alias)),
+                    accepts_prefix,
+                )
+            })
+            .collect();
+        if !aliases.is_empty() {
+            decorators.push(Decorator::Aliases(aliases));
+        }
+
+        decorators
+    }
+}
+
+/// The main entry point for evaluating Numbat code.
+///
+/// A `Context` is `Send + Sync`. To evaluate many independent inputs in
+/// parallel (e.g. in a web server), load the prelude into a single context and
+/// clone it for every input. This avoids compiling the prelude again.
 #[derive(Clone)]
 pub struct Context {
     prefix_transformer: Transformer,
@@ -101,7 +199,17 @@ pub struct Context {
     interpreter: BytecodeInterpreter,
     resolver: Resolver,
     load_currency_module_on_demand: bool,
+    deprecation_warnings: bool,
+    compatibility_syntax: bool,
+    compatibility_warnings: Vec<Warning>,
+    scientific_notation: ScientificNotation,
+    long_unit_names: bool,
     terminal_width: Option<usize>,
+    observer: Option<Arc<StatementObserver>>,
+    output_sink: Option<Arc<OutputSink>>,
+    interrupt_flag: InterruptFlag,
+    last_instruction_count: u64,
+    last_allocated_bytes: usize,
 }
 
 impl Context {
@@ -112,7 +220,17 @@ impl Context {
             interpreter: BytecodeInterpreter::new(),
             resolver: Resolver::new(module_importer),
             load_currency_module_on_demand: false,
+            deprecation_warnings: true,
+            compatibility_syntax: false,
+            compatibility_warnings: vec![],
+            scientific_notation: ScientificNotation::default(),
+            long_unit_names: false,
             terminal_width: None,
+            observer: None,
+            output_sink: None,
+            interrupt_flag: InterruptFlag::default(),
+            last_instruction_count: 0,
+            last_allocated_bytes: 0,
         }
     }
 
@@ -124,6 +242,303 @@ impl Context {
         self.interpreter.set_debug(activate);
     }
 
+    /// Enable or disable the inlining of small functions at their call sites.
+    /// This is enabled by default. Inlined calls do not show up in bytecode
+    /// listings and execution traces, so it can be useful to disable this for
+    /// debugging. It only affects code that is compiled afterwards.
+    pub fn set_function_inlining(&mut self, enable: bool) {
+        self.interpreter.set_function_inlining(enable);
+    }
+
+    /// Seed the random number generator that is used by `random()` and the
+    /// sampling functions built on top of it, to make simulations reproducible.
+    pub fn set_random_seed(&mut self, seed: u64) {
+        self.interpreter.set_random_seed(seed);
+    }
+
+    /// Allow or forbid functions like `read_csv` to access the file system.
+    /// File system access is disabled by default, so that untrusted code can
+    /// be evaluated safely.
+    pub fn set_file_system_access(&mut self, allow: bool) {
+        self.interpreter.set_file_system_access(allow);
+    }
+
+    /// Allow or forbid functions like `fetch_json` to access the network.
+    /// Network access is disabled by default.
+    pub fn set_network_access(&mut self, allow: bool) {
+        self.interpreter.set_network_access(allow);
+    }
+
+    /// Allow or forbid `env` to read environment variables. This is disabled
+    /// by default.
+    pub fn set_environment_access(&mut self, allow: bool) {
+        self.interpreter.set_environment_access(allow);
+    }
+
+    /// Enable or disable warnings for the use of units and functions that are
+    /// marked as `@deprecated(…)`. They are enabled by default.
+    pub fn set_deprecation_warnings(&mut self, enable: bool) {
+        self.deprecation_warnings = enable;
+    }
+
+    /// Return the warnings that were emitted by the last call to `interpret`,
+    /// e.g. for the use of deprecated units or for shadowed names. Warnings do
+    /// not prevent the code from being evaluated. Warnings that are not taken
+    /// are dropped by the next call to `interpret`.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        let mut warnings = std::mem::take(&mut self.compatibility_warnings);
+        warnings.extend(self.prefix_transformer.prefix_parser.take_warnings());
+        warnings.extend(self.typechecker.take_warnings());
+        if !self.deprecation_warnings {
+            warnings.retain(|warning| !matches!(warning, Warning::Deprecated { .. }));
+        }
+        warnings
+    }
+
+    /// Choose what happens if a definition uses a name that is already taken
+    /// by a unit (or one of its prefixed forms, like `am` for attometer). By
+    /// default, this is an error. The policy can also be chosen for single
+    /// definitions with the `@shadowing(error|warn|allow)` decorator.
+    pub fn set_shadowing_policy(&mut self, policy: ShadowingPolicy) {
+        self.prefix_transformer.shadowing_policy = policy;
+    }
+
+    /// Accept common syntax from Qalculate! and GNU `units(1)`, like `5 ft 3 in`
+    /// for `5 ft + 3 in` or `|x|` for `abs(x)`. Input is translated to Numbat
+    /// syntax before it is evaluated, and every translation is reported as a
+    /// warning. This is disabled by default.
+    pub fn set_compatibility_syntax(&mut self, enable: bool) {
+        self.compatibility_syntax = enable;
+    }
+
+    /// Reject implicit multiplications with parentheses, like `2(3 + 4)` or
+    /// `(a)(b)`, which are allowed by default. This does not apply to modules.
+    pub fn set_strict_multiplication(&mut self, strict: bool) {
+        self.resolver.strict_multiplication = strict;
+    }
+
+    /// Check code for syntax errors without evaluating it. The parser recovers
+    /// from errors, so this returns all errors that it found. This is cheap
+    /// enough to be called on every keystroke, e.g. to validate REPL input.
+    /// With [`Context::set_compatibility_syntax`], the code is translated
+    /// first, and the error positions refer to the original code.
+    pub fn check_syntax(&self, code: &str) -> Vec<ParseError> {
+        let strict_multiplication = self.resolver.strict_multiplication;
+
+        if self.compatibility_syntax {
+            let translation = self.translate_compatibility_syntax(code);
+            return match parser::parse_with_strict_multiplication(
+                &translation.code,
+                0,
+                strict_multiplication,
+                &Interner::default(),
+            ) {
+                Ok(_) => vec![],
+                Err((_, errors)) => errors
+                    .into_iter()
+                    .map(|error| ParseError {
+                        span: translation.original_span(code, error.span),
+                        ..error
+                    })
+                    .collect(),
+            };
+        }
+
+        // The syntax tree is thrown away, so its identifiers are not added to the
+        // interner of the context
+        match parser::parse_with_strict_multiplication(
+            code,
+            0,
+            strict_multiplication,
+            &Interner::default(),
+        ) {
+            Ok(_) => vec![],
+            Err((_, errors)) => errors,
+        }
+    }
+
+    /// Translate syntax from other unit calculators, see
+    /// [`Context::set_compatibility_syntax`]
+    fn translate_compatibility_syntax(&self, code: &str) -> compat_syntax::Translation {
+        let transformer = &self.prefix_transformer;
+        compat_syntax::translate(
+            code,
+            |name| {
+                matches!(
+                    transformer
+                        .prefix_parser
+                        .parse(&Symbol::new(&normalize_identifier(name))),
+                    PrefixParserResult::UnitIdentifier(..)
+                )
+            },
+            |name| {
+                let name = normalize_identifier(name);
+                let mut defined_names = transformer
+                    .variable_names
+                    .iter()
+                    .chain(&transformer.function_names);
+                defined_names.any(|n| *n == name)
+            },
+        )
+    }
+
+    /// Check code for errors without evaluating it, and look for problems that
+    /// do not prevent the code from running: unused functions, variables and
+    /// units ([`Warning::Unused`]), branches that are never taken
+    /// ([`Warning::UnreachableBranch`]) and the warnings that are also reported
+    /// during evaluation (deprecated items, shadowing). Public items of imported
+    /// modules are not reported as unused, as they are meant to be used by other
+    /// code. Nothing is defined in this context, only the source code is kept
+    /// to be able to print diagnostics.
+    pub fn check(&mut self, code: &str, code_source: CodeSource) -> Result<Vec<Warning>> {
+        // Modules are only imported for checking. They need to be imported again
+        // when the code is evaluated.
+        let resolver_old = self.resolver.clone();
+        let result = self.resolver.resolve(code, code_source);
+        self.resolver.reset_imports(&resolver_old);
+        let statements = result.map_err(NumbatError::ResolverError)?;
+
+        let mut prefix_transformer = self.prefix_transformer.clone();
+        let transformed_statements = prefix_transformer
+            .transform(statements)
+            .map_err(|e| NumbatError::NameResolutionError(self.explain_confusable_clash(e)))?;
+
+        let dead_code_warnings = dead_code::analyze(&transformed_statements, |id| {
+            matches!(self.resolver.get_code_source(id), CodeSource::Module(..))
+        });
+
+        let mut typechecker = self.typechecker.clone();
+        typechecker
+            .check(transformed_statements)
+            .map_err(NumbatError::TypeCheckError)?;
+
+        let mut warnings = prefix_transformer.prefix_parser.take_warnings();
+        warnings.extend(typechecker.take_warnings());
+        if !self.deprecation_warnings {
+            warnings.retain(|warning| !matches!(warning, Warning::Deprecated { .. }));
+        }
+        warnings.extend(dead_code_warnings);
+
+        Ok(warnings)
+    }
+
+    /// Choose how very large and very small numbers are printed, e.g. as
+    /// `1.602e-19` (the default) or as `1.602×10⁻¹⁹`. This applies to the
+    /// output of [`Context::interpret`]. Front ends that format results
+    /// themselves can use [`Markup::with_scientific_notation`].
+    pub fn set_scientific_notation(&mut self, notation: ScientificNotation) {
+        self.scientific_notation = notation;
+    }
+
+    pub fn scientific_notation(&self) -> ScientificNotation {
+        self.scientific_notation
+    }
+
+    /// Write units in long form, like `3 kilometres per hour` instead of
+    /// `3 km/h`. This applies to the output of [`Context::interpret`]. Front
+    /// ends that format results themselves can use [`Context::with_unit_names`].
+    pub fn set_long_unit_names(&mut self, enable: bool) {
+        self.long_unit_names = enable;
+    }
+
+    pub fn long_unit_names(&self) -> bool {
+        self.long_unit_names
+    }
+
+    /// Rewrite the units in the given markup to their long form, if long unit
+    /// names have been enabled with [`Context::set_long_unit_names`].
+    pub fn with_unit_names(&self, markup: Markup) -> Markup {
+        if self.long_unit_names {
+            self.interpreter.unit_lookup().with_long_unit_names(markup)
+        } else {
+            markup
+        }
+    }
+
+    /// Enable or disable the simplification of results to derived units that
+    /// are marked with `@simplification_target`, like `N` for `kg·m/s²` or
+    /// `W` for `J/s`. This is enabled by default.
+    pub fn set_derived_unit_simplification(&mut self, enable: bool) {
+        self.interpreter.set_derived_unit_simplification(enable);
+    }
+
+    /// Set the command-line arguments that are returned by `args()`
+    pub fn set_script_arguments(&mut self, arguments: Vec<String>) {
+        self.interpreter.set_script_arguments(arguments);
+    }
+
+    /// Returns a handle that can be used to abort a running evaluation from
+    /// another thread (e.g. when the user presses a "Stop" button). The
+    /// evaluation then fails with [`RuntimeError::Interrupted`]. If nothing is
+    /// running, the next evaluation is interrupted. Clones of this context need
+    /// to be interrupted via their own handles.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt_flag.handle()
+    }
+
+    /// Returns the number of VM instructions that have been executed for the
+    /// most recent input, including those of failed evaluations.
+    pub fn last_instruction_count(&self) -> u64 {
+        self.last_instruction_count
+    }
+
+    /// Returns the approximate number of bytes that have been allocated for
+    /// values (strings, lists, structs, …) while evaluating the most recent input.
+    /// This is the total of all allocations during the evaluation, not the size
+    /// of the values that are alive at the end of it.
+    pub fn last_allocated_bytes(&self) -> usize {
+        self.last_allocated_bytes
+    }
+
+    /// Abort evaluations that allocate more than approximately `limit` bytes for
+    /// values in total, with [`RuntimeError::MemoryLimitExceeded`]. This protects
+    /// embedders like web or server applications from inputs that build huge lists
+    /// or strings. Functions like `str_repeat` fail before building a result that
+    /// would exceed the limit. There is no limit by default.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.interpreter.set_memory_limit(limit);
+    }
+
+    /// Returns a human-readable listing of the bytecode (instructions, constants,
+    /// called functions) that has been compiled for the most recent input.
+    pub fn disassemble_last(&self) -> String {
+        self.interpreter.disassemble_last()
+    }
+
+    /// Returns a human-readable listing of the bytecode of the given function,
+    /// or `None` if there is no such function (or if it is a foreign function).
+    pub fn disassemble_function(&self, name: &str) -> Option<String> {
+        self.interpreter.disassemble_function(name)
+    }
+
+    /// Register a function that is called after each statement has been
+    /// executed, e.g. to log calculations or to mirror results into a UI.
+    /// Note that if a later statement of the same input fails, the effects
+    /// of the whole input are reverted, even though the observer has already
+    /// been called for the first statements.
+    pub fn set_observer(
+        &mut self,
+        observer: impl Fn(&Statement, &InterpreterResult) + Send + Sync + 'static,
+    ) {
+        self.observer = Some(Arc::new(observer));
+    }
+
+    pub fn clear_observer(&mut self) {
+        self.observer = None;
+    }
+
+    /// Send the output of `print` (and similar procedures) to the given function
+    /// instead of the standard output. This only affects [`Context::interpret`],
+    /// as [`Context::interpret_with_settings`] uses the print function from the
+    /// given settings.
+    pub fn set_output_sink(&mut self, sink: impl Fn(&Markup) + Send + Sync + 'static) {
+        self.output_sink = Some(Arc::new(sink));
+    }
+
+    pub fn clear_output_sink(&mut self) {
+        self.output_sink = None;
+    }
+
     pub fn load_currency_module_on_demand(&mut self, yes: bool) {
         self.load_currency_module_on_demand = yes;
     }
@@ -133,6 +548,21 @@ impl Context {
         let _unused = ExchangeRatesCache::fetch();
     }
 
+    /// Fetch the exchange rates in a background thread, such that the first use
+    /// of a currency unit does not block. The returned future can be awaited on
+    /// any async runtime and resolves to `true` if the rates are available.
+    #[cfg(feature = "fetch-exchangerates")]
+    pub fn prefetch_exchange_rates_async() -> impl std::future::Future<Output = bool> {
+        ExchangeRatesCache::fetch_in_background()
+    }
+
+    /// Returns true if the exchange rates have already been loaded. This never
+    /// blocks, so it can be used by interactive frontends to check whether
+    /// currency conversions are available yet.
+    pub fn exchange_rates_available() -> bool {
+        ExchangeRatesCache::is_available()
+    }
+
     pub fn set_exchange_rates(xml_content: &str) {
         ExchangeRatesCache::set_from_xml(xml_content);
     }
@@ -323,10 +753,14 @@ impl Context {
         if keyword.is_empty() {
             return m::text("Usage: info <unit or variable>");
         }
+        let keyword = normalize_identifier(keyword);
+        let keyword = keyword.as_ref();
         let reg = self.interpreter.get_unit_registry();
 
-        if let PrefixParserResult::UnitIdentifier(_span, prefix, _, full_name) =
-            self.prefix_transformer.prefix_parser.parse(keyword)
+        if let PrefixParserResult::UnitIdentifier(_span, prefix, _, full_name) = self
+            .prefix_transformer
+            .prefix_parser
+            .parse(&Symbol::new(keyword))
         {
             if let Some(md) = reg
                 .inner
@@ -451,8 +885,30 @@ impl Context {
                     + m::nl();
             }
 
-            if let Ok((_, results)) = self.interpret(keyword, CodeSource::Internal) {
-                help += m::nl() + results.to_markup(None, self.dimension_registry(), true, true);
+            if let Some(source) = &l.metadata.source {
+                help += m::text("Source: ") + m::text(source) + m::nl();
+            }
+
+            // Values are read from the interpreter instead of evaluating the
+            // keyword, which would overwrite the last result
+            if let Some(uncertainty) = self.interpreter.global_uncertainty(keyword) {
+                help += m::text("Standard uncertainty: ")
+                    + InterpreterResult::Value(uncertainty).to_markup(
+                        None,
+                        self.dimension_registry(),
+                        false,
+                        false,
+                    );
+            }
+
+            if let Some(value) = self.interpreter.global_value(keyword) {
+                help += m::nl()
+                    + InterpreterResult::Value(value).to_markup(
+                        None,
+                        self.dimension_registry(),
+                        true,
+                        true,
+                    );
             }
 
             return help;
@@ -488,12 +944,56 @@ impl Context {
                 }
             }
 
+            // Functions like `element` return a struct, so its fields are
+            // what the user is actually interested in
+            if let Some(ast::TypeAnnotation::TypeExpression(ast::TypeExpression::TypeIdentifier(
+                _,
+                name,
+            ))) = &fn_signature.return_type_annotation
+            {
+                if let Some(info) = self.typechecker.lookup_struct(name) {
+                    help += m::text("Returns:     ")
+                        + m::type_identifier(&info.name)
+                        + m::nl()
+                        + self.struct_fields_markup(info);
+                }
+            }
+
+            return help;
+        }
+
+        if let Some(info) = self.typechecker.lookup_struct(keyword) {
+            let mut help = m::text("Struct: ") + m::type_identifier(&info.name);
+            if !info.type_parameters.is_empty() {
+                help += m::operator("<")
+                    + Itertools::intersperse(
+                        info.type_parameters.iter().map(m::type_identifier),
+                        m::operator(",") + m::space(),
+                    )
+                    .sum()
+                    + m::operator(">");
+            }
+            help += m::nl() + self.struct_fields_markup(info);
+
             return help;
         }
 
         m::text("Not found")
     }
 
+    fn struct_fields_markup(&self, info: &typed_ast::StructInfo) -> Markup {
+        let mut markup = m::text("Fields:") + m::nl();
+        for (name, (_, type_)) in &info.fields {
+            markup += m::whitespace("    ")
+                + m::identifier(name)
+                + m::operator(":")
+                + m::space()
+                + type_.to_readable_type(self.typechecker.registry())
+                + m::nl();
+        }
+        markup
+    }
+
     pub fn list_modules(&self) -> impl Iterator<Item = String> {
         let modules = self.resolver.get_importer().list_modules();
         modules.into_iter().map(|m| m.0.join("::"))
@@ -529,16 +1029,279 @@ impl Context {
         })
     }
 
+    /// A JSON array with information about all units that are currently defined:
+    /// names and aliases (including the prefixes they accept), the physical
+    /// dimension, the representation in terms of base units and the conversion
+    /// factor to those base units.
+    pub fn export_units_json(&self) -> String {
+        let units = self.unit_representations().collect::<Vec<_>>();
+
+        unit_export::units_to_json(units.iter().map(|(name, (base_representation, metadata))| {
+            unit_export::UnitExport {
+                name,
+                base_representation,
+                metadata,
+                factor: self.interpreter.get_defining_unit(name).map(|unit| {
+                    Quantity::from_unit(unit.clone())
+                        .to_base_unit_representation()
+                        .unsafe_value()
+                        .to_f64()
+                }),
+            }
+        }))
+    }
+
     pub fn resolver(&self) -> &Resolver {
         &self.resolver
     }
 
+    /// Look up a unit expression like `km/h` or `kg m^2 / s^2`.
+    ///
+    /// Every unit that has been defined so far (including prefixed forms) can be
+    /// used. Only units, numbers and arithmetic operators are allowed, so this
+    /// never evaluates any code and leaves the context untouched.
+    pub fn unit(&self, unit: &str) -> Result<Unit> {
+        match self.interpreter.unit_lookup().parse_quantity(unit) {
+            Ok(q) if q.unsafe_value().to_f64() == 1.0 => Ok(q.unit().clone()),
+            _ => Err(NumbatError::RuntimeError(RuntimeError::NotAUnit(
+                unit.into(),
+            ))),
+        }
+    }
+
+    /// Create a quantity from a numerical value and a unit expression, e.g.
+    /// `ctx.quantity(30.0, "km/h")`. The result can be used with the arithmetic
+    /// operators on [`Quantity`] and converted with [`Quantity::convert_to`].
+    pub fn quantity(&self, value: f64, unit: &str) -> Result<Quantity> {
+        Ok(Quantity::new_f64(value, self.unit(unit)?))
+    }
+
+    /// Convert a numerical value from one unit to another, e.g.
+    /// `ctx.convert(100.0, "km/h", "mph")`.
+    pub fn convert(&self, value: f64, from: &str, to: &str) -> Result<f64> {
+        Ok(self.convert_many(&[value], from, to)?[0])
+    }
+
+    /// Convert a list of values from one unit to another. The unit expressions
+    /// are only looked up once, so this is much faster than calling
+    /// [`Context::convert`] for each value.
+    pub fn convert_many(&self, values: &[f64], from: &str, to: &str) -> Result<Vec<f64>> {
+        let from = self.unit(from)?;
+        let to = self.unit(to)?;
+
+        let factor = Quantity::new_f64(1.0, from)
+            .value_in(&to)
+            .map_err(|e| NumbatError::RuntimeError(RuntimeError::QuantityError(e)))?;
+
+        Ok(values.iter().map(|v| v * factor).collect())
+    }
+
+    /// Define a new base dimension, like `dimension Currency` in Numbat code.
+    pub fn define_dimension(&mut self, name: &str) -> Result<()> {
+        self.define(ast::Statement::DefineDimension(
+            Span::dummy(),
+            self.identifier(name),
+            vec![],
+        ))
+    }
+
+    /// Define a derived dimension as a product of powers of existing
+    /// dimensions, e.g. `ctx.define_derived_dimension("Speed", &[("Length", 1), ("Time", -1)])`.
+    pub fn define_derived_dimension(&mut self, name: &str, factors: &[(&str, i64)]) -> Result<()> {
+        let expression = factors
+            .iter()
+            .map(|&(dimension, exponent)| {
+                ast::TypeExpression::Power(
+                    None,
+                    Box::new(ast::TypeExpression::TypeIdentifier(
+                        Span::dummy(),
+                        self.identifier(dimension),
+                    )),
+                    Span::dummy(),
+                    Exponent::from_integer(exponent.into()),
+                )
+            })
+            .reduce(|lhs, rhs| {
+                ast::TypeExpression::Multiply(Span::dummy(), Box::new(lhs), Box::new(rhs))
+            })
+            .unwrap_or(ast::TypeExpression::Unity(Span::dummy()));
+
+        self.define(ast::Statement::DefineDimension(
+            Span::dummy(),
+            self.identifier(name),
+            vec![expression],
+        ))
+    }
+
+    /// Define a new base unit for the given dimension, like `unit dollar: Currency`
+    /// in Numbat code.
+    pub fn define_base_unit(
+        &mut self,
+        name: &str,
+        dimension: &str,
+        options: UnitOptions,
+    ) -> Result<()> {
+        self.define(ast::Statement::DefineBaseUnit(
+            Span::dummy(),
+            self.identifier(name),
+            Some(ast::TypeExpression::TypeIdentifier(
+                Span::dummy(),
+                self.identifier(dimension),
+            )),
+            options.decorators(&self.resolver.interner),
+        ))
+    }
+
+    /// Define a new unit in terms of an existing one, such that `1 name` equals
+    /// `factor unit`. For example, `ctx.define_unit("league", 3.0, "mile", UnitOptions::default())`.
+    pub fn define_unit(
+        &mut self,
+        name: &str,
+        factor: f64,
+        unit: &str,
+        options: UnitOptions,
+    ) -> Result<()> {
+        let expr = ast::Expression::BinaryOperator {
+            op: ast::BinaryOperator::Mul,
+            lhs: Box::new(ast::Expression::Scalar(
+                Span::dummy(),
+                Number::from_f64(factor),
+            )),
+            rhs: Box::new(ast::Expression::Identifier(
+                Span::dummy(),
+                self.identifier(unit),
+            )),
+            span_op: None,
+        };
+
+        self.define(ast::Statement::DefineDerivedUnit {
+            identifier_span: Span::dummy(),
+            identifier: self.identifier(name),
+            expr,
+            type_annotation_span: None,
+            type_annotation: None,
+            decorators: options.decorators(&self.resolver.interner),
+        })
+    }
+
+    /// An identifier for a definition that is built without parsing code
+    fn identifier(&self, name: &str) -> Symbol {
+        self.resolver.interner.intern(&normalize_identifier(name))
+    }
+
+    /// Run a single definition through all stages. The context is left
+    /// unchanged if any of them fails.
+    fn define(&mut self, statement: ast::Statement) -> Result<()> {
+        let prefix_transformer_old = self.prefix_transformer.clone();
+        let typechecker_old = self.typechecker.clone();
+        let interpreter_old = self.interpreter.clone();
+
+        let result = self.define_unchecked(statement);
+
+        if result.is_err() {
+            self.prefix_transformer = prefix_transformer_old;
+            self.typechecker = typechecker_old;
+            self.interpreter = interpreter_old;
+        }
+
+        result
+    }
+
+    fn define_unchecked(&mut self, statement: ast::Statement) -> Result<()> {
+        let transformed_statements = self
+            .prefix_transformer
+            .transform([statement])
+            .map_err(NumbatError::NameResolutionError)?;
+
+        let typed_statements = self
+            .typechecker
+            .check(transformed_statements)
+            .map_err(NumbatError::TypeCheckError)?;
+
+        let _ = self
+            .interpreter
+            .interpret_statements(
+                &mut InterpreterSettings::default(),
+                &typed_statements,
+                self.typechecker.registry(),
+            )
+            .map_err(NumbatError::RuntimeError)?;
+
+        Ok(())
+    }
+
+    /// Identifiers are normalized, so names that look the same are treated as
+    /// the same name, even if they are encoded differently (e.g. `µ` and `μ`).
+    /// If this leads to a clash, point out the difference between the two
+    /// spellings, as it is not visible in the error message otherwise.
+    fn explain_confusable_clash(&self, error: NameResolutionError) -> NameResolutionError {
+        if let NameResolutionError::IdentifierClash {
+            conflicting_identifier,
+            conflict_span,
+            original_span,
+            ..
+        } = &error
+        {
+            let source_text = |span: &Span| {
+                let file = self.resolver.files.get(span.code_source_id).ok()?;
+                file.source()
+                    .get(span.start.byte as usize..span.end.byte as usize)
+                    .map(str::to_owned)
+            };
+
+            if let (Some(new_spelling), Some(original_spelling)) =
+                (source_text(conflict_span), source_text(original_span))
+            {
+                if new_spelling != original_spelling
+                    && normalize_identifier(&new_spelling)
+                        == normalize_identifier(&original_spelling)
+                {
+                    return NameResolutionError::ConfusableIdentifierClash {
+                        conflicting_identifier: conflicting_identifier.clone(),
+                        conflict_span: *conflict_span,
+                        original_span: *original_span,
+                        difference: describe_difference(&original_spelling, &new_spelling),
+                    };
+                }
+            }
+        }
+
+        error
+    }
+
     pub fn interpret(
         &mut self,
         code: &str,
         code_source: CodeSource,
     ) -> Result<(Vec<typed_ast::Statement>, InterpreterResult)> {
-        self.interpret_with_settings(&mut InterpreterSettings::default(), code, code_source)
+        let notation = self.scientific_notation;
+        let unit_lookup = self
+            .long_unit_names
+            .then(|| self.interpreter.unit_lookup().clone());
+        let format = move |m: &Markup| {
+            let m = match &unit_lookup {
+                Some(unit_lookup) => unit_lookup.with_long_unit_names(m.clone()),
+                None => m.clone(),
+            };
+            m.with_scientific_notation(notation)
+        };
+        let mut settings = match &self.output_sink {
+            Some(sink) => {
+                let sink = sink.clone();
+                InterpreterSettings {
+                    print_fn: Box::new(move |m: &Markup| sink(&format(m))),
+                    ..Default::default()
+                }
+            }
+            None => InterpreterSettings {
+                print_fn: Box::new(move |m: &Markup| {
+                    print!("{}", format(m));
+                }),
+                ..Default::default()
+            },
+        };
+
+        self.interpret_with_settings(&mut settings, code, code_source)
     }
 
     pub fn interpret_with_settings(
@@ -547,6 +1310,20 @@ impl Context {
         code: &str,
         code_source: CodeSource,
     ) -> Result<(Vec<typed_ast::Statement>, InterpreterResult)> {
+        self.last_instruction_count = 0;
+        self.last_allocated_bytes = 0;
+        self.take_warnings();
+
+        let translated;
+        let code = if self.compatibility_syntax {
+            let translation = self.translate_compatibility_syntax(code);
+            self.compatibility_warnings.extend(translation.warnings);
+            translated = translation.code;
+            translated.as_str()
+        } else {
+            code
+        };
+
         let statements = self
             .resolver
             .resolve(code, code_source.clone())
@@ -557,7 +1334,7 @@ impl Context {
         let result = self
             .prefix_transformer
             .transform(statements)
-            .map_err(NumbatError::NameResolutionError);
+            .map_err(|e| NumbatError::NameResolutionError(self.explain_confusable_clash(e)));
 
         if result.is_err() {
             // Reset the state of the prefix transformer to what we had before. This is necessary
@@ -725,6 +1502,7 @@ impl Context {
                                 move |_: &m::Markup| { // ignore any print statements when loading this module asynchronously
                                 },
                             ),
+                            ..Default::default()
                         };
 
                         // We also call this from a thread at program startup, so if a user only starts
@@ -761,13 +1539,53 @@ impl Context {
 
         let typed_statements = result?;
 
+        self.interpreter.set_interrupt_flag(&self.interrupt_flag);
         let interpreter_old = self.interpreter.clone();
+        let instruction_count_before = self.interpreter.instruction_count();
+        self.interpreter.reset_allocated_bytes();
+        self.interpreter.start_input();
+
+        let result = if let Some(observer) = &self.observer {
+            // Run statements one by one, such that the observer can be called
+            // with the result of each of them.
+            let mut result = Ok(InterpreterResult::Continue);
+            for statement in &typed_statements {
+                match self.interpreter.interpret_statements(
+                    settings,
+                    std::slice::from_ref(statement),
+                    self.typechecker.registry(),
+                ) {
+                    Ok(statement_result) => {
+                        observer(statement, &statement_result);
+                        if statement_result.is_value() {
+                            result = Ok(statement_result);
+                        }
+                    }
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+            }
+            result
+        } else {
+            self.interpreter.interpret_statements(
+                settings,
+                &typed_statements,
+                self.typechecker.registry(),
+            )
+        };
 
-        let result = self.interpreter.interpret_statements(
-            settings,
-            &typed_statements,
-            self.typechecker.registry(),
-        );
+        self.last_instruction_count =
+            self.interpreter.instruction_count() - instruction_count_before;
+        self.last_allocated_bytes = self.interpreter.allocated_bytes();
+
+        if matches!(result, Err(RuntimeError::Interrupted)) {
+            // The interrupt has been handled. An interrupt that is requested
+            // before the code runs (e.g. while it is being type checked) is not
+            // lost, it stops the code as soon as it starts.
+            self.interrupt_flag.reset();
+        }
 
         if result.is_err() {
             // Similar to above: we need to reset the state of the typechecker and the prefix transformer
@@ -782,7 +1600,7 @@ impl Context {
             //
             self.prefix_transformer = prefix_transformer_old;
             self.typechecker = typechecker_old;
-            self.interpreter = interpreter_old;
+            self.interpreter.revert_to(interpreter_old);
         }
 
         let result = result.map_err(NumbatError::RuntimeError)?;
@@ -790,6 +1608,59 @@ impl Context {
         Ok((typed_statements, result))
     }
 
+    /// Evaluate a batch of independent inputs, e.g. all expressions of a single
+    /// request to a server. Every input is evaluated in a throw-away copy of this
+    /// context, such that the (already loaded) prelude is shared between all of
+    /// them, while definitions, `ans` and the compiled code of one input are not
+    /// visible to the other inputs or to later evaluations in this context.
+    /// Unless a fixed seed has been set with [`Context::set_random_seed`], every
+    /// input gets its own random numbers.
+    ///
+    /// Note that the whole context is cloned for every input. This copies the
+    /// tables of all compilation stages and the compiled code of the VM, so the
+    /// cost grows with the number of definitions in the context (about as much
+    /// as [`Context::interpret`] spends anyway to be able to roll back failed
+    /// inputs). Nothing is parsed or compiled again. For large batches, use
+    /// [`Context::interpret_many_parallel`], which distributes these copies over
+    /// all CPU cores.
+    ///
+    /// Returns one result per input, in the same order.
+    pub fn interpret_many(
+        &self,
+        inputs: &[&str],
+    ) -> Vec<Result<(Vec<typed_ast::Statement>, InterpreterResult)>> {
+        inputs
+            .iter()
+            .map(|code| self.clone().interpret(code, CodeSource::Text))
+            .collect()
+    }
+
+    /// Like [`Context::interpret_many`], but distributes the inputs over all
+    /// available CPU cores. The results are the same as with `interpret_many`,
+    /// but output of `print` calls is written in no particular order.
+    pub fn interpret_many_parallel(
+        &self,
+        inputs: &[&str],
+    ) -> Vec<Result<(Vec<typed_ast::Statement>, InterpreterResult)>> {
+        let num_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let chunk_size = inputs.len().div_ceil(num_threads).max(1);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = inputs
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.interpret_many(chunk)))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    #[cfg(feature = "terminal")]
     pub fn print_diagnostic(&self, error: impl ErrorDiagnostic) {
         use codespan_reporting::term::{
             self,
@@ -90,6 +90,12 @@ impl<T> NumbatList<T> {
         Ok(())
     }
 
+    /// The address and the capacity of the underlying allocation. Used to find
+    /// out whether an operation on the list has allocated new memory.
+    pub(crate) fn allocation(&self) -> (*const VecDeque<T>, usize) {
+        (Arc::as_ptr(&self.alloc), self.alloc.capacity())
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &T> {
         let (start, end) = self.view.map_or((0, self.alloc.len()), |view| view);
         self.alloc.iter().skip(start).take(end - start)
@@ -161,6 +167,15 @@ impl<T: Clone> NumbatList<T> {
     }
 }
 
+impl<T> FromIterator<T> for NumbatList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self {
+            alloc: Arc::new(iter.into_iter().collect()),
+            view: None,
+        }
+    }
+}
+
 impl From<NumbatList<Value>> for Value {
     fn from(list: NumbatList<Value>) -> Self {
         Value::List(list)
@@ -0,0 +1,131 @@
+//! Support code for the `quantity!` macro in the `numbat-macros` crate. This is
+//! not part of the public API.
+
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+
+use crate::arithmetic::Rational;
+use crate::module_importer::BuiltinModuleImporter;
+use crate::number::Number;
+use crate::prefix::Prefix;
+use crate::prefix_parser::AcceptsPrefix;
+use crate::resolver::CodeSource;
+use crate::unit::{CanonicalName, Unit, UnitFactor, UnitIdentifier, UnitKind};
+use crate::{Context, Quantity};
+
+/// A quantity that has been checked at compile time, in a form that can be
+/// stored in a `const`.
+#[derive(Debug, Clone, Copy)]
+pub struct QuantityLiteral {
+    pub value: f64,
+    pub unit: &'static [UnitFactorLiteral],
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct UnitFactorLiteral {
+    pub name: &'static str,
+    pub canonical_name: &'static str,
+    pub accepts_short_prefix: bool,
+    pub accepts_long_prefix: bool,
+    /// The conversion factor and the defining unit of derived units
+    pub definition: Option<(f64, &'static [UnitFactorLiteral])>,
+    /// Whether the prefix is a binary prefix, and its exponent
+    pub prefix: (bool, i32),
+    /// Numerator and denominator of the exponent
+    pub exponent: (i128, i128),
+}
+
+impl QuantityLiteral {
+    pub fn to_quantity(&self) -> Quantity {
+        Quantity::new(Number::from_f64(self.value), unit_from_literal(self.unit))
+    }
+}
+
+fn unit_from_literal(factors: &[UnitFactorLiteral]) -> Unit {
+    Unit::from_factors(factors.iter().map(|factor| UnitFactor {
+        unit_id: UnitIdentifier {
+            name: factor.name.into(),
+            canonical_name: CanonicalName::new(
+                factor.canonical_name,
+                AcceptsPrefix {
+                    short: factor.accepts_short_prefix,
+                    long: factor.accepts_long_prefix,
+                },
+            ),
+            kind: match factor.definition {
+                None => UnitKind::Base,
+                Some((conversion_factor, defining_unit)) => UnitKind::Derived(
+                    Number::from_f64(conversion_factor),
+                    unit_from_literal(defining_unit),
+                ),
+            },
+        },
+        prefix: match factor.prefix {
+            (false, exponent) => Prefix::Metric(exponent),
+            (true, exponent) => Prefix::Binary(exponent),
+        },
+        exponent: Rational::new(factor.exponent.0, factor.exponent.1),
+    }))
+}
+
+/// Parse a quantity literal like `9.81 m/s^2`, using the units of the prelude,
+/// and return Rust code for the corresponding [`QuantityLiteral`]. Only numbers,
+/// units and arithmetic operators are allowed in the literal.
+pub fn quantity_literal_code(literal: &str) -> Result<String, String> {
+    static CONTEXT: OnceLock<Mutex<Context>> = OnceLock::new();
+
+    let context = CONTEXT.get_or_init(|| {
+        let mut context = Context::new(BuiltinModuleImporter::default());
+        let _ = context
+            .interpret("use prelude", CodeSource::Internal)
+            .expect("the prelude can be loaded");
+        Mutex::new(context)
+    });
+
+    let context = context.lock().unwrap_or_else(|e| e.into_inner());
+    let quantity = context.interpreter.unit_lookup().parse_quantity(literal)?;
+    if !quantity.unsafe_value().to_f64().is_finite() {
+        return Err(format!("The value of '{literal}' is not finite"));
+    }
+
+    Ok(format!(
+        "::numbat::macro_support::QuantityLiteral {{ value: {:?}, unit: {} }}",
+        quantity.unsafe_value().to_f64(),
+        unit_literal_code(quantity.unit())
+    ))
+}
+
+fn unit_literal_code(unit: &Unit) -> String {
+    let mut code = "&[".to_string();
+    for factor in unit.iter() {
+        let definition = match &factor.unit_id.kind {
+            UnitKind::Base => "None".to_string(),
+            UnitKind::Derived(conversion_factor, defining_unit) => format!(
+                "Some(({:?}, {}))",
+                conversion_factor.to_f64(),
+                unit_literal_code(defining_unit)
+            ),
+        };
+        let prefix = match factor.prefix {
+            Prefix::Metric(exponent) => (false, exponent),
+            Prefix::Binary(exponent) => (true, exponent),
+        };
+
+        write!(
+            code,
+            "::numbat::macro_support::UnitFactorLiteral {{ \
+                name: {:?}, canonical_name: {:?}, \
+                accepts_short_prefix: {}, accepts_long_prefix: {}, \
+                definition: {definition}, prefix: {prefix:?}, exponent: ({}, {}) }},",
+            factor.unit_id.name,
+            factor.unit_id.canonical_name.name,
+            factor.unit_id.canonical_name.accepts_prefix.short,
+            factor.unit_id.canonical_name.accepts_prefix.long,
+            factor.exponent.numer(),
+            factor.exponent.denom(),
+        )
+        .unwrap();
+    }
+    code.push(']');
+    code
+}
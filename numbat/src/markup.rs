@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use crate::number::{to_unicode_scientific_notation, to_words, ScientificNotation};
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum FormatType {
     Whitespace,
@@ -32,6 +34,32 @@ impl Markup {
     pub fn from(f: FormattedString) -> Self {
         Self(vec![f])
     }
+
+    /// Rewrite all values in e-notation to the given notation
+    pub fn with_scientific_notation(mut self, notation: ScientificNotation) -> Self {
+        if notation == ScientificNotation::Unicode {
+            for FormattedString(_, format_type, text) in &mut self.0 {
+                if *format_type == FormatType::Value {
+                    if let Some(unicode) = to_unicode_scientific_notation(text) {
+                        *text = unicode;
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Rewrite all values in words, like "nine point eight one" for `9.81`
+    pub fn with_spoken_numbers(mut self) -> Self {
+        for FormattedString(_, format_type, text) in &mut self.0 {
+            if *format_type == FormatType::Value {
+                if let Some(words) = to_words(text) {
+                    *text = words;
+                }
+            }
+        }
+        self
+    }
 }
 
 impl Display for Markup {
@@ -0,0 +1,193 @@
+//! A markup backend that writes MathML, for rendering results and expressions
+//! on the web. The output is a single `<math>` element.
+
+use crate::markup::{FormatType, FormattedString, Formatter, Markup};
+use crate::unit::{unit_string_parts, UnitStringPart};
+
+pub struct MathMlFormatter;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn element(tag: &str, content: &str) -> String {
+    format!("<{tag}>{}</{tag}>", escape(content))
+}
+
+fn superscript(base: String, exponent: String) -> String {
+    format!("<msup>{base}{exponent}</msup>")
+}
+
+fn mathml_number(value: &str) -> String {
+    let value = value.replace('_', "");
+    match value.as_str() {
+        "inf" => return element("mi", "∞"),
+        "-inf" => return element("mo", "-") + &element("mi", "∞"),
+        _ => {}
+    }
+
+    if !value.parse::<f64>().is_ok_and(f64::is_finite) {
+        return element("mtext", &value);
+    }
+
+    match value.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let exponent = exponent.trim_start_matches('+');
+            format!(
+                "{}{}{}",
+                element("mn", mantissa),
+                element("mo", "×"),
+                superscript(element("mn", "10"), element("mn", exponent))
+            )
+        }
+        None => element("mn", &value),
+    }
+}
+
+/// Convert a unit like `kg·m²/s²` to a MathML `<mrow>`, with upright unit names
+pub fn mathml_unit(unit: &str) -> String {
+    let mut elements: Vec<String> = vec![];
+    for part in unit_string_parts(unit) {
+        match part {
+            UnitStringPart::Name(name) => {
+                elements.push(format!("<mi mathvariant=\"normal\">{}</mi>", escape(&name)))
+            }
+            UnitStringPart::Times => elements.push(element("mo", "·")),
+            UnitStringPart::Over => elements.push(element("mo", "/")),
+            UnitStringPart::Exponent(exponent) => {
+                let base = elements.pop().unwrap_or_default();
+                elements.push(superscript(base, element("mn", &exponent)));
+            }
+            UnitStringPart::OpenParen => elements.push(element("mo", "(")),
+            UnitStringPart::CloseParen => elements.push(element("mo", ")")),
+        }
+    }
+    format!("<mrow>{}</mrow>", elements.concat())
+}
+
+fn mathml_operator(operator: &str) -> String {
+    let operator = match operator {
+        "*" => "×",
+        "->" | "➞" | "to" => "→",
+        "==" => "=",
+        "!=" => "≠",
+        "<=" => "≤",
+        ">=" => "≥",
+        "&&" => "∧",
+        "||" => "∨",
+        "!" => "¬",
+        _ => operator,
+    };
+    element("mo", operator)
+}
+
+impl Formatter for MathMlFormatter {
+    fn format_part(&self, FormattedString(_, format_type, s): &FormattedString) -> String {
+        match format_type {
+            FormatType::Whitespace if s.contains('\n') => "<mspace linebreak=\"newline\"/>".into(),
+            FormatType::Whitespace => "".into(),
+            FormatType::Value => mathml_number(s),
+            FormatType::Unit => mathml_unit(s),
+            FormatType::Identifier | FormatType::TypeIdentifier => element("mi", s),
+            FormatType::Operator => mathml_operator(s),
+            FormatType::Emphasized
+            | FormatType::Dimmed
+            | FormatType::Text
+            | FormatType::String
+            | FormatType::Keyword
+            | FormatType::Decorator => {
+                if s.trim().is_empty() {
+                    "".into()
+                } else {
+                    element("mtext", s)
+                }
+            }
+        }
+    }
+
+    /// Values and units are separated by a thin space, and the operand after
+    /// a `^` becomes the superscript of the preceding element.
+    fn format(&self, markup: &Markup, _indent: bool) -> String {
+        let mut elements: Vec<String> = vec![];
+        let mut exponent_open = false;
+        let mut follows_value = false;
+
+        for part in &markup.0 {
+            let formatted = self.format_part(part);
+            if part.1 == FormatType::Unit && follows_value {
+                elements.push("<mspace width=\"0.1667em\"/>".into());
+            }
+            follows_value =
+                part.1 == FormatType::Value || (follows_value && part.1 == FormatType::Whitespace);
+
+            if formatted.is_empty() {
+                continue;
+            }
+
+            if exponent_open {
+                let base = elements.pop().unwrap_or_default();
+                elements.push(superscript(base, formatted));
+                exponent_open = false;
+            } else if part.1 == FormatType::Operator && part.2 == "^" {
+                exponent_open = true;
+            } else {
+                elements.push(formatted);
+            }
+        }
+
+        format!("<math>{}</math>", elements.concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup as m;
+
+    fn mathml(markup: Markup) -> String {
+        MathMlFormatter.format(&markup, false)
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(
+            mathml_unit("km/h"),
+            "<mrow><mi mathvariant=\"normal\">km</mi><mo>/</mo><mi mathvariant=\"normal\">h</mi></mrow>"
+        );
+        assert_eq!(
+            mathml_unit("m·s⁻¹"),
+            "<mrow><mi mathvariant=\"normal\">m</mi><mo>·</mo><msup><mi mathvariant=\"normal\">s</mi><mn>-1</mn></msup></mrow>"
+        );
+    }
+
+    #[test]
+    fn quantities() {
+        assert_eq!(
+            mathml(m::value("3") + m::space() + m::unit("m")),
+            "<math><mn>3</mn><mspace width=\"0.1667em\"/><mrow><mi mathvariant=\"normal\">m</mi></mrow></math>"
+        );
+        assert_eq!(
+            mathml(m::value("1.602e-19")),
+            "<math><mn>1.602</mn><mo>×</mo><msup><mn>10</mn><mn>-19</mn></msup></math>"
+        );
+        assert_eq!(mathml(m::value("inf")), "<math><mi>∞</mi></math>");
+    }
+
+    #[test]
+    fn expressions() {
+        let expression = m::identifier("x")
+            + m::operator("^")
+            + m::value("2")
+            + m::space()
+            + m::operator("<=")
+            + m::space()
+            + m::string("a & b");
+        assert_eq!(
+            mathml(expression),
+            "<math><msup><mi>x</mi><mn>2</mn></msup><mo>≤</mo><mtext>a &amp; b</mtext></math>"
+        );
+    }
+}
@@ -1,8 +1,6 @@
-use std::{
-    ffi::OsStr,
-    fs,
-    path::{Path, PathBuf},
-};
+use std::path::PathBuf;
+#[cfg(feature = "filesystem-importer")]
+use std::{ffi::OsStr, fs, path::Path};
 
 use rust_embed::RustEmbed;
 
@@ -26,17 +24,20 @@ impl ModuleImporter for NullImporter {
     }
 }
 
+#[cfg(feature = "filesystem-importer")]
 #[derive(Debug, Clone, Default)]
 pub struct FileSystemImporter {
     root_paths: Vec<PathBuf>,
 }
 
+#[cfg(feature = "filesystem-importer")]
 impl FileSystemImporter {
     pub fn add_path<P: AsRef<Path>>(&mut self, path: P) {
         self.root_paths.push(path.as_ref().to_owned());
     }
 }
 
+#[cfg(feature = "filesystem-importer")]
 impl ModuleImporter for FileSystemImporter {
     fn import(&self, module_path: &ModulePath) -> Option<(String, Option<PathBuf>)> {
         for path in &self.root_paths {
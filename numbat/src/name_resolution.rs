@@ -15,8 +15,19 @@ pub enum NameResolutionError {
         original_item_type: Option<String>,
     },
 
+    #[error("Identifier is already in use: '{conflicting_identifier}'. The existing definition looks the same, but is written with different characters ({difference}).")]
+    ConfusableIdentifierClash {
+        conflicting_identifier: String,
+        conflict_span: Span,
+        original_span: Span,
+        difference: String,
+    },
+
     #[error("Reserved identifier")]
     ReservedIdentifier(Span),
+
+    #[error("Not the name of a unit: '{1}'")]
+    UnknownUnit(Span, String),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -0,0 +1,86 @@
+//! Identifiers are normalized by the tokenizer, so that names which look the
+//! same are also treated as the same name. This applies Unicode normalization
+//! form C (which, for example, maps the Ångström sign `Å` (U+212B) to the
+//! letter `Å` (U+00C5) and the Ohm sign `Ω` (U+2126) to the Greek letter `Ω`),
+//! as well as a small list of known confusables that are not covered by NFC.
+
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Characters that are not canonically equivalent, but look the same in most
+/// fonts. The first character is replaced by the second one.
+const CONFUSABLES: &[(char, char)] = &[
+    ('\u{00B5}', '\u{03BC}'), // Micro sign → Greek small letter mu
+    ('\u{0127}', '\u{210F}'), // Latin small letter h with stroke → Planck constant over two pi
+];
+
+fn fold_confusable(c: char) -> char {
+    CONFUSABLES
+        .iter()
+        .find(|(from, _)| *from == c)
+        .map_or(c, |(_, to)| *to)
+}
+
+pub fn normalize_identifier(name: &str) -> Cow<'_, str> {
+    if name.is_ascii() {
+        return Cow::Borrowed(name);
+    }
+
+    let is_normalized = is_nfc_quick(name.chars()) == IsNormalized::Yes
+        && !name
+            .chars()
+            .any(|c| CONFUSABLES.iter().any(|(from, _)| *from == c));
+
+    if is_normalized {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(name.nfc().map(fold_confusable).collect())
+    }
+}
+
+/// A description of the first character in which two (differently encoded)
+/// spellings of a name differ, like `'µ' (U+00B5) and 'μ' (U+03BC)`.
+pub(crate) fn describe_difference(a: &str, b: &str) -> String {
+    let describe = |c: char| format!("'{c}' (U+{:04X})", c as u32);
+
+    let mut a_chars = a.chars();
+    let mut b_chars = b.chars();
+    loop {
+        match (a_chars.next(), b_chars.next()) {
+            (Some(ca), Some(cb)) if ca == cb => continue,
+            (Some(ca), Some(cb)) => return format!("{} and {}", describe(ca), describe(cb)),
+            (Some(c), None) | (None, Some(c)) => {
+                return format!("the additional character {}", describe(c))
+            }
+            (None, None) => return String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization() {
+        assert_eq!(normalize_identifier("meter"), "meter");
+        assert_eq!(normalize_identifier("λ_0"), "λ_0");
+
+        assert_eq!(normalize_identifier("\u{212B}"), "\u{00C5}");
+        assert_eq!(normalize_identifier("A\u{030A}"), "\u{00C5}");
+        assert_eq!(normalize_identifier("\u{2126}"), "\u{03A9}");
+        assert_eq!(normalize_identifier("\u{212A}"), "K");
+
+        assert_eq!(normalize_identifier("\u{00B5}0"), "\u{03BC}0");
+        assert_eq!(normalize_identifier("\u{0127}"), "\u{210F}");
+    }
+
+    #[test]
+    fn difference() {
+        assert_eq!(
+            describe_difference("\u{00B5}0", "\u{03BC}0"),
+            "'µ' (U+00B5) and 'μ' (U+03BC)"
+        );
+    }
+}
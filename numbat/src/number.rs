@@ -30,6 +30,35 @@ impl Number {
         self.0.trunc() == self.0
     }
 
+    /// Format as a C-style hexadecimal floating point literal like `0x1.8p+3`.
+    /// Unlike the decimal representation, this is exact.
+    pub fn to_hex_float(self) -> String {
+        let x = self.0;
+        if x.is_nan() {
+            return "NaN".into();
+        }
+
+        let sign = if x.is_sign_negative() { "-" } else { "" };
+        if x.is_infinite() {
+            return format!("{sign}inf");
+        }
+
+        let bits = x.abs().to_bits();
+        let biased_exponent = (bits >> 52) as i32;
+        let mantissa = bits & ((1 << 52) - 1);
+        let (leading_digit, exponent) = match (biased_exponent, mantissa) {
+            (0, 0) => (0, 0),
+            (0, _) => (0, -1022), // subnormal numbers
+            _ => (1, biased_exponent - 1023),
+        };
+
+        let fraction = format!("{mantissa:013x}");
+        let fraction = fraction.trim_end_matches('0');
+        let point = if fraction.is_empty() { "" } else { "." };
+
+        format!("{sign}0x{leading_digit}{point}{fraction}p{exponent:+}")
+    }
+
     /// Pretty prints with default options
     pub fn pretty_print(self) -> String {
         self.pretty_print_with_options(None)
@@ -102,6 +131,163 @@ impl Number {
     }
 }
 
+/// How numbers that are too large or too small for the ordinary decimal
+/// notation are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScientificNotation {
+    /// `1.602e-19`
+    #[default]
+    E,
+    /// `1.602×10⁻¹⁹`
+    Unicode,
+}
+
+/// Rewrite a number in e-notation like `1.602e-19` (as printed by
+/// [`Number::pretty_print`]) to `1.602×10⁻¹⁹`. Returns `None` for anything
+/// that is not a number in e-notation.
+pub fn to_unicode_scientific_notation(formatted: &str) -> Option<String> {
+    let (mantissa, exponent) = formatted.split_once('e')?;
+    mantissa.parse::<f64>().ok()?;
+    let exponent = exponent.parse::<i32>().ok()?;
+
+    let mantissa = mantissa.strip_suffix(".0").unwrap_or(mantissa);
+    let exponent: String = exponent
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '-' => '⁻',
+            digit => "⁰¹²³⁴⁵⁶⁷⁸⁹"
+                .chars()
+                .nth(digit.to_digit(10).unwrap() as usize)
+                .unwrap(),
+        })
+        .collect();
+
+    Some(format!("{mantissa}×10{exponent}"))
+}
+
+/// Write a number as printed by [`Number::pretty_print`] in words, like
+/// "nine point eight one" for `9.81` or "one point six times ten to the power
+/// of minus nineteen" for `1.6e-19`. Digits after the decimal point are read
+/// one by one. Returns `None` for anything that is not a number.
+pub fn to_words(formatted: &str) -> Option<String> {
+    let formatted = formatted.replace('_', "");
+
+    if let Some(magnitude) = formatted.strip_prefix('-') {
+        return Some(format!("minus {}", to_words(magnitude)?));
+    }
+
+    if formatted == "inf" {
+        return Some("infinity".into());
+    } else if formatted == "NaN" {
+        return Some("not a number".into());
+    }
+
+    if let Some((mantissa, exponent)) = formatted.split_once('e') {
+        let exponent = exponent.parse::<i32>().ok()?;
+        return Some(format!(
+            "{} times ten to the power of {}",
+            to_words(mantissa)?,
+            to_words(&exponent.to_string())?
+        ));
+    }
+
+    let formatted = formatted.strip_suffix(".0").unwrap_or(&formatted);
+    let (integer_part, fractional_part) = formatted.split_once('.').unwrap_or((formatted, ""));
+    if !fractional_part.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut words = integer_to_words(integer_part.parse().ok()?);
+    if !fractional_part.is_empty() {
+        words.push_str(" point");
+        for digit in fractional_part.chars() {
+            words.push(' ');
+            words.push_str(ONES[digit.to_digit(10).unwrap() as usize]);
+        }
+    }
+    Some(words)
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+
+fn integer_to_words(n: u64) -> String {
+    if n == 0 {
+        return ONES[0].into();
+    }
+
+    let below_thousand = |n: u64| {
+        let (hundreds, rest) = ((n / 100) as usize, (n % 100) as usize);
+        let mut words = vec![];
+        if hundreds > 0 {
+            words.push(format!("{} hundred", ONES[hundreds]));
+        }
+        if rest >= 20 && rest % 10 != 0 {
+            words.push(format!("{}-{}", TENS[rest / 10], ONES[rest % 10]));
+        } else if rest >= 20 {
+            words.push(TENS[rest / 10].into());
+        } else if rest > 0 {
+            words.push(ONES[rest].into());
+        }
+        words.join(" ")
+    };
+
+    let mut groups = vec![];
+    let mut n = n;
+    for scale in SCALES {
+        let group = n % 1000;
+        if group > 0 {
+            let words = below_thousand(group);
+            groups.push(if scale.is_empty() {
+                words
+            } else {
+                format!("{words} {scale}")
+            });
+        }
+        n /= 1000;
+        if n == 0 {
+            break;
+        }
+    }
+    groups.reverse();
+    groups.join(" ")
+}
+
 impl Display for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.to_f64().fmt(f)
@@ -190,6 +376,68 @@ fn test_pretty_print() {
     assert_eq!(Number::from_f64(0.0000001).pretty_print(), "1.0e-7");
 }
 
+#[test]
+fn test_unicode_scientific_notation() {
+    let unicode = |n: f64| to_unicode_scientific_notation(&Number::from_f64(n).pretty_print());
+
+    assert_eq!(unicode(1.602e-19).as_deref(), Some("1.602×10⁻¹⁹"));
+    assert_eq!(unicode(-1.234e50).as_deref(), Some("-1.234×10⁵⁰"));
+    assert_eq!(unicode(1e-7).as_deref(), Some("1×10⁻⁷"));
+    assert_eq!(unicode(1234567890000000.).as_deref(), Some("1.23457×10¹⁵"));
+
+    assert_eq!(unicode(1234.0), None);
+    assert_eq!(unicode(0.5), None);
+    assert_eq!(to_unicode_scientific_notation("true"), None);
+    assert_eq!(to_unicode_scientific_notation("nie"), None);
+}
+
+#[test]
+fn test_to_words() {
+    let words = |n: f64| to_words(&Number::from_f64(n).pretty_print());
+
+    assert_eq!(words(0.0).as_deref(), Some("zero"));
+    assert_eq!(words(7.0).as_deref(), Some("seven"));
+    assert_eq!(words(42.0).as_deref(), Some("forty-two"));
+    assert_eq!(words(-13.0).as_deref(), Some("minus thirteen"));
+    assert_eq!(words(9.81).as_deref(), Some("nine point eight one"));
+    assert_eq!(words(0.05).as_deref(), Some("zero point zero five"));
+    assert_eq!(words(100.00001).as_deref(), Some("one hundred"));
+    assert_eq!(
+        words(1_234_567.0).as_deref(),
+        Some("one million two hundred thirty-four thousand five hundred sixty-seven")
+    );
+    assert_eq!(words(2_000_010.0).as_deref(), Some("two million ten"));
+    assert_eq!(
+        words(1.602e-19).as_deref(),
+        Some("one point six zero two times ten to the power of minus nineteen")
+    );
+    assert_eq!(
+        words(3e50).as_deref(),
+        Some("three times ten to the power of fifty")
+    );
+    assert_eq!(words(f64::INFINITY).as_deref(), Some("infinity"));
+
+    assert_eq!(to_words("true"), None);
+    assert_eq!(to_words("0x2a"), None);
+}
+
+#[test]
+fn test_hex_float() {
+    assert_eq!(Number::from_f64(12.0).to_hex_float(), "0x1.8p+3");
+    assert_eq!(Number::from_f64(-0.25).to_hex_float(), "-0x1p-2");
+    assert_eq!(Number::from_f64(0.1).to_hex_float(), "0x1.999999999999ap-4");
+    assert_eq!(Number::from_f64(0.0).to_hex_float(), "0x0p+0");
+    assert_eq!(
+        Number::from_f64(f64::MAX).to_hex_float(),
+        "0x1.fffffffffffffp+1023"
+    );
+    assert_eq!(
+        Number::from_f64(f64::MIN_POSITIVE / 2.0).to_hex_float(),
+        "0x0.8p-1022"
+    );
+    assert_eq!(Number::from_f64(f64::NEG_INFINITY).to_hex_float(), "-inf");
+}
+
 #[test]
 fn test_abs() {
     assert_eq!(Number::from_f64(0.0).abs(), Number::from_f64(0.0));
@@ -2,21 +2,24 @@
 //!
 //! Grammar:
 //! ```txt
-//! statement       ::=   variable_decl | struct_decl | function_decl | dimension_decl | unit_decl | module_import | procedure_call | expression
+//! statement       ::=   variable_decl | struct_decl | function_decl | dimension_decl | unit_decl | unit_alias | preferred_unit | module_import | procedure_call | expression
 //!
-//! variable_decl   ::=   "let" identifier ( ":" type_annotation ) ? "=" expression
-//! struct_decl     ::=   "struct" identifier "{" ( identifier ":" type_annotation "," )* ( identifier ":" type_annotation "," ? ) ? "}"
+//! variable_decl   ::=   "let" ( ( identifier ( ":" type_annotation ) ? ) | struct_pattern ) "=" expression
+//! struct_pattern  ::=   identifier "{" ( identifier ( ":" identifier ) ? "," )* ( identifier ( ":" identifier ) ? "," ? ) ? "}"
+//! struct_decl     ::=   "struct" identifier ( fn_decl_generic ) ? "{" ( identifier ":" type_annotation "," )* ( identifier ":" type_annotation "," ? ) ? "}"
 //! function_decl   ::=   "fn" identifier ( fn_decl_generic ) ? fn_decl_param ( "->" type_annotation ) ? ( "=" expression ) ?
 //! fn_decl_generic ::=   "<" ( identifier "," ) * identifier ">"
 //! fn_decl_param   ::=   "(" ( identifier ( ":" type_annotation ) ? "," )* ( identifier ( ":" type_annotation ) ) ? ")"
 //! dimension_decl  ::=   "dimension" identifier ( "=" dimension_expr ) *
 //! unit_decl       ::=   decorator * "unit" ( ":" dimension_expr ) ? ( "=" expression ) ?
-//! module_import   ::=   "use" ident ( "::" ident) *
+//! unit_alias      ::=   ( "unit" "alias" identifier ( ":" ( "long" | "short" | "both" | "none" ) ) ? "=" identifier ) | ( "@alias(" identifier ( ":" ( "long" | "short" | "both" | "none" ) ) ? ")" "unit" identifier )
+//! preferred_unit  ::=   "set" "preferred_unit" dimension_expr "=" expression
+//! module_import   ::=   "use" ident ( "::" ident) * ( ( "(" ident ( "," ident ) * ")" ) | ( "as" ident ) ) ?
 //! procedure_call  ::=   ( "print" | "assert" | "assert_eq" | "type" ) "(" arguments? ")"
 //!
-//! decorator       ::=   "@" ( "metric_prefixes" | "binary_prefixes" | ( "aliases(" list_of_aliases ")" ) )
+//! decorator       ::=   "@" ( "metric_prefixes" | "binary_prefixes" | "private" | "relative" | "reciprocal" | "fractional" | "natural_conversion" | "simplification_target" | ( "aliases(" list_of_aliases ")" ) | ( "uncertainty(" expression ")" ) | ( "deprecated(" string ")" ) | ( "shadowing(" ( "error" | "warn" | "allow" ) ")" ) )
 //!
-//! type_annotation ::=   "Bool" | "String" | "List<" type ">" | dimension_expr
+//! type_annotation ::=   "Bool" | "String" | "List<" type ">" | "Dict<" type ">" | identifier "<" ( type "," ) * type ">" | dimension_expr
 //! dimension_expr  ::=   dim_factor
 //! dim_factor      ::=   dim_power ( (multiply | divide) dim_power ) *
 //! dim_power       ::=   dim_primary ( power dim_exponent | unicode_exponent ) ?
@@ -25,28 +28,36 @@
 //!
 //! expression      ::=   postfix_apply
 //! postfix_apply   ::=   condition ( "|>" identifier ) *
-//! condition       ::=   ( "if" conversion "then" condition "else" condition ) | conversion
+//! condition       ::=   ( "if" conversion "then" condition "else" condition ) | range
+//! range           ::=   conversion ( ( ".." | "..=" ) conversion ( "step" conversion ) ? ) ?
 //! conversion      ::=   logical_or ( ( "→" | "->" | "to" ) logical_or ) *
 //! logical_or      ::=   logical_and ( "||" logical_and ) *
 //! logical_and     ::=   logical_neg ( "&&" logical_neg ) *
 //! logical_neg     ::=   ( "!" logical_neg) | comparison
 //! comparison      ::=   term ( (">" | ">="| "≥" | "<" | "<=" | "≤" | "==" | "!=" | "≠" ) term ) *
 //! term            ::=   factor ( ( "+" | "-") factor ) *
-//! factor          ::=   unary ( ( "*" | "/") per_factor ) *
+//! factor          ::=   of_factor ( ( "*" | "/") of_factor ) *
+//! of_factor       ::=   per_factor ( "of" per_factor ) *
 //! per_factor      ::=   unary ( "per" unary ) *
 //! unary           ::=   ( ( minus | plus ) unary ) | ifactor
-//! ifactor         ::=   power ( " " power ) *
+//! ifactor         ::=   ( dms_literal | power ) ( " " power ) *
+//! dms_literal     ::=   ( number "°" ) ? ( number "′" ) ? ( number "″" ) ?
 //! power           ::=   factorial ( "^" "-" ? power ) ?
 //! factorial       ::=   unicode_power "!" *
-//! unicode_power   ::=   call ( "⁻" ? ( "¹" | "²" | "³" | "⁴" | "⁵" | "⁶" | "⁷" | "⁸" | "⁹" ) ) ?
+//! unicode_power   ::=   call ( "⁻" ? ( "⁰" | "¹" | "²" | "³" | "⁴" | "⁵" | "⁶" | "⁷" | "⁸" | "⁹" ) + ) ?
 //! call            ::=   primary ( ( "(" arguments? ")" ) | "." identifier ) *
 //! arguments       ::=   expression ( "," expression ) *
-//! primary         ::=   boolean | string | hex_number | oct_number | bin_number | number | identifier ( struct_expr ? ) | typed_hole | list_expr | "(" expression ")"
+//! primary         ::=   boolean | string | datetime | hex_number | hex_float | oct_number | bin_number | number | qualified_ident ( struct_expr ? ) | struct_update | typed_hole | list_expr | dict_expr | "(" expression ")"
+//! qualified_ident ::=   identifier ( "::" identifier ) *
 //! struct_expr     ::=   "{" ( identifier ":" type_annotation "," )* ( identifier ":" expression "," ? ) ? "}"
+//! struct_update   ::=   "{" expression "with" ( field_path ":" expression "," )* ( field_path ":" expression "," ? ) "}"
+//! field_path      ::=   identifier ( "." identifier ) *
 //! list_expr       ::=   "[]" | "[" expression ( "," expression ) * "]"
+//! dict_expr       ::=   "{}" | "{" expression ":" expression ( "," expression ":" expression ) * "," ? "}"
 //!
 //! number          ::=   [0-9][0-9_]*("." ([0-9][0-9_]*)?)?([eE][+-]?[0-9][0-9_]*)?
 //! hex_number      ::=   "0x" [0-9a-fA-F]*
+//! hex_float       ::=   "0x" [0-9a-fA-F]* ( "." [0-9a-fA-F]* ) ? [pP] [+-] ? integer
 //! oct_number      ::=   "0o" [0-7]*
 //! bin_number      ::=   "0b" [01]*
 //! integer         ::=   [0-9]([0-9_]*[0-9])?
@@ -60,18 +71,20 @@
 //! multiply        ::=   "*" | "×" | "·"
 //! divide          ::=   "/" | "÷"
 //! string          ::=   '"' [^"]* '"'
+//! datetime        ::=   "@" [0-9] [0-9a-zA-Z:.+-]* ( "[" [^\]]* "]" ) ?
 //! ```
 
 use crate::arithmetic::{Exponent, Rational};
 use crate::ast::{
-    BinaryOperator, DefineVariable, Expression, ProcedureKind, Statement, StringPart,
+    BinaryOperator, DefineVariable, Expression, ImportKind, ProcedureKind, Statement, StringPart,
     TypeAnnotation, TypeExpression, TypeParameterBound, UnaryOperator,
 };
 use crate::decorator::{self, Decorator};
 use crate::number::Number;
-use crate::prefix_parser::AcceptsPrefix;
+use crate::prefix_parser::{AcceptsPrefix, ShadowingPolicy};
 use crate::resolver::ModulePath;
 use crate::span::Span;
+use crate::symbol::{Interner, Symbol};
 use crate::tokenizer::{Token, TokenKind, TokenizerError, TokenizerErrorKind};
 
 use num_traits::{CheckedDiv, FromPrimitive, Zero};
@@ -102,6 +115,9 @@ pub enum ParseErrorKind {
     #[error("Expected '=' or ':' after identifier (and type annotation) in 'let' assignment")]
     ExpectedEqualOrColonAfterLetIdentifier,
 
+    #[error("Expected '=' after struct pattern in 'let' assignment")]
+    ExpectedEqualAfterStructPattern,
+
     #[error("Expected identifier after 'fn' keyword. Note that some reserved words can not be used as function names.")]
     ExpectedIdentifierAfterFn,
 
@@ -144,12 +160,33 @@ pub enum ParseErrorKind {
     #[error("Expected '=' or ':' after identifier in unit definition")]
     ExpectedColonOrEqualAfterUnitIdentifier,
 
+    #[error("Expected '=' after the alias name in a unit alias definition")]
+    ExpectedEqualInUnitAlias,
+
+    #[error("Expected the name of an existing unit in a unit alias definition")]
+    ExpectedUnitInUnitAlias,
+
+    #[error("Expected '=' after the dimension in a preferred unit declaration")]
+    ExpectedEqualInPreferredUnit,
+
     #[error("Expected ':' after a field name")]
     ExpectedColonAfterFieldName,
 
+    #[error("Expected 'with' (struct update) or ':' (dictionary entry)")]
+    ExpectedWithOrColonInBraces,
+
+    #[error("Expected ':' after a dictionary key")]
+    ExpectedColonAfterDictKey,
+
+    #[error("Expected ',' or '}}' in dictionary")]
+    ExpectedCommaOrRightCurlyInDict,
+
     #[error("Only functions can be called")]
     CanOnlyCallIdentifier,
 
+    #[error("Implicit multiplication with parentheses is disabled, use '*' or '×' instead")]
+    ImplicitMultiplicationWithParentheses,
+
     #[error("Division by zero in dimension exponent")]
     DivisionByZeroInDimensionExponent,
 
@@ -171,9 +208,18 @@ pub enum ParseErrorKind {
     #[error("Expected module name after double colon (::)")]
     ExpectedModuleNameAfterDoubleColon,
 
+    #[error("Expected ',' or ')' in list of imported names")]
+    ExpectedCommaOrRightParenInImportList,
+
+    #[error("Expected identifier after 'as' in module import")]
+    ExpectedIdentifierAfterAs,
+
     #[error("Overflow in number literal")]
     OverflowInNumberLiteral,
 
+    #[error("Invalid date literal: {0}")]
+    InvalidDateTimeLiteral(String),
+
     #[error("Expected dimension exponent")]
     ExpectedDimensionExponent,
 
@@ -186,6 +232,12 @@ pub enum ParseErrorKind {
     #[error("Decorators can only be used on unit, let or function definitions")]
     DecoratorUsedOnUnsuitableKind,
 
+    #[error("The @uncertainty decorator can only be used on let definitions")]
+    UncertaintyDecoratorUsedOnUnsuitableKind,
+
+    #[error("The @alias decorator can only be used on the name of an existing unit, like '@alias(mtr) unit meter'")]
+    AliasDecoratorUsedOnUnsuitableKind,
+
     #[error("Decorators on let definitions cannot have prefix information")]
     DecoratorsWithPrefixOnLetDefinition,
 
@@ -195,6 +247,9 @@ pub enum ParseErrorKind {
     #[error("Unknown alias annotation")]
     UnknownAliasAnnotation,
 
+    #[error("Expected 'error', 'warn' or 'allow' as shadowing policy")]
+    UnknownShadowingPolicy,
+
     #[error("Aliases cannot be used on functions.")]
     AliasUsedOnFunction,
 
@@ -219,6 +274,9 @@ pub enum ParseErrorKind {
     #[error("Expected {0} in list type")]
     ExpectedTokenInListType(&'static str),
 
+    #[error("Expected {0} in dictionary type")]
+    ExpectedTokenInDictType(&'static str),
+
     #[error("Expected '{{' after struct name")]
     ExpectedLeftCurlyAfterStructName,
 
@@ -264,16 +322,41 @@ static PROCEDURES: &[TokenKind] = &[
 struct Parser {
     current: usize,
     decorator_stack: Vec<Decorator>,
+    /// The expression of a pending `@uncertainty(…)` decorator, and the span
+    /// of the decorator name
+    uncertainty: Option<(Span, Expression)>,
+    /// A pending `@alias(…)` decorator: the span and name of the alias, and
+    /// the prefixes that it accepts
+    alias: Option<(Span, Symbol, Option<AcceptsPrefix>)>,
+    /// The field definitions of a `let` with a struct pattern
+    destructured_fields: Vec<Statement>,
+    /// Reject implicit multiplications with parentheses, like `2(3 + 4)`
+    strict_multiplication: bool,
+    /// Whether the end of a range is being parsed. `step` is not a keyword,
+    /// but it ends the implicit multiplication in `1 ..= 10 step 2`.
+    in_range_end: bool,
+    /// Identifiers in the AST are interned with this interner
+    interner: Interner,
 }
 
 impl Parser {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(interner: Interner) -> Self {
         Parser {
             current: 0,
             decorator_stack: vec![],
+            uncertainty: None,
+            alias: None,
+            destructured_fields: vec![],
+            strict_multiplication: false,
+            in_range_end: false,
+            interner,
         }
     }
 
+    fn intern(&self, name: &str) -> Symbol {
+        self.interner.intern(name)
+    }
+
     fn skip_empty_lines(&mut self, tokens: &[Token]) {
         while self.match_exact(tokens, TokenKind::Newline).is_some() {}
     }
@@ -291,8 +374,16 @@ impl Parser {
 
         while !self.is_at_end(tokens) {
             match self.statement(tokens) {
-                Ok(statement) => statements.push(statement),
+                Ok(statement) => {
+                    let uncertainty = self.uncertainty_definition(&statement);
+                    statements.push(statement);
+                    statements.extend(uncertainty);
+                    statements.append(&mut self.destructured_fields);
+                }
                 Err(e) => {
+                    self.uncertainty = None;
+                    self.alias = None;
+                    self.destructured_fields.clear();
                     errors.push(e);
                     self.recover_from_error(tokens);
                 }
@@ -309,7 +400,7 @@ impl Parser {
                 TokenKind::Equal => {
                     errors.push(ParseError {
                         kind: ParseErrorKind::TrailingEqualSign(
-                            self.last(tokens).unwrap().lexeme.to_owned(),
+                            self.last(tokens).unwrap().lexeme.to_string(),
                         ),
                         span: self.peek(tokens).span,
                     });
@@ -318,7 +409,7 @@ impl Parser {
                 _ => {
                     errors.push(ParseError {
                         kind: ParseErrorKind::TrailingCharacters(
-                            self.peek(tokens).lexeme.to_owned(),
+                            self.peek(tokens).lexeme.to_string(),
                         ),
                         span: self.peek(tokens).span,
                     });
@@ -334,6 +425,29 @@ impl Parser {
         }
     }
 
+    /// The standard uncertainty of a constant given via `@uncertainty(…)` is
+    /// attached to the constant (and all of its aliases) by a separate statement
+    /// that follows the definition.
+    fn uncertainty_definition(&mut self, statement: &Statement) -> Option<Statement> {
+        let (span, expr) = self.uncertainty.take()?;
+        let Statement::DefineVariable(define_variable) = statement else {
+            return None;
+        };
+
+        let mut args = vec![expr];
+        args.extend(
+            decorator::name_and_aliases(&define_variable.identifier, &define_variable.decorators)
+                .map(|(name, _)| {
+                    Expression::Identifier(define_variable.identifier_span, name.clone())
+                }),
+        );
+        Some(Statement::ProcedureCall(
+            span,
+            ProcedureKind::DefineUncertainty,
+            args,
+        ))
+    }
+
     /// Must be called after encountering an error.
     fn recover_from_error(&mut self, tokens: &[Token]) {
         // Skip all the tokens until we encounter a newline or EoF.
@@ -366,12 +480,12 @@ impl Parser {
     fn list_of_aliases(
         &mut self,
         tokens: &[Token],
-    ) -> Result<Vec<(String, Option<AcceptsPrefix>)>> {
+    ) -> Result<Vec<(Symbol, Option<AcceptsPrefix>)>> {
         if self.match_exact(tokens, TokenKind::RightParen).is_some() {
             return Ok(vec![]);
         }
 
-        let mut identifiers: Vec<(String, Option<AcceptsPrefix>)> =
+        let mut identifiers: Vec<(Symbol, Option<AcceptsPrefix>)> =
             vec![(self.identifier(tokens)?, self.accepts_prefix(tokens)?)];
         while self.match_exact(tokens, TokenKind::Comma).is_some() {
             identifiers.push((self.identifier(tokens)?, self.accepts_prefix(tokens)?));
@@ -399,10 +513,33 @@ impl Parser {
                 span: self.peek(tokens).span,
             });
         }
+        if self.uncertainty.is_some()
+            && !matches!(self.peek(tokens).kind, TokenKind::At | TokenKind::Let)
+        {
+            return Err(ParseError {
+                kind: ParseErrorKind::UncertaintyDecoratorUsedOnUnsuitableKind,
+                span: self.peek(tokens).span,
+            });
+        }
+        if self.alias.is_some()
+            && !matches!(self.peek(tokens).kind, TokenKind::At | TokenKind::Unit)
+        {
+            return Err(ParseError {
+                kind: ParseErrorKind::AliasDecoratorUsedOnUnsuitableKind,
+                span: self.peek(tokens).span,
+            });
+        }
 
         if self.match_exact(tokens, TokenKind::Let).is_some() {
-            self.parse_variable(tokens, true)
-                .map(Statement::DefineVariable)
+            if self.peek(tokens).kind == TokenKind::Identifier
+                && tokens.get(self.current + 1).map(|t| t.kind) == Some(TokenKind::LeftCurly)
+            {
+                self.parse_struct_pattern(tokens)
+                    .map(Statement::DefineVariable)
+            } else {
+                self.parse_variable(tokens, true)
+                    .map(Statement::DefineVariable)
+            }
         } else if self.match_exact(tokens, TokenKind::Fn).is_some() {
             self.parse_function_declaration(tokens)
         } else if self.match_exact(tokens, TokenKind::Dimension).is_some() {
@@ -417,6 +554,13 @@ impl Parser {
             self.parse_struct(tokens)
         } else if self.match_any(tokens, PROCEDURES).is_some() {
             self.parse_procedure(tokens)
+        } else if self.peek(tokens).kind == TokenKind::Identifier
+            && self.peek(tokens).lexeme == "set"
+            && tokens
+                .get(self.current + 1)
+                .is_some_and(|t| t.kind == TokenKind::Identifier && t.lexeme == "preferred_unit")
+        {
+            self.parse_preferred_unit(tokens)
         } else {
             Ok(Statement::Expression(self.expression(tokens)?))
         }
@@ -458,7 +602,7 @@ impl Parser {
 
                 Ok(DefineVariable {
                     identifier_span,
-                    identifier: identifier.lexeme.to_owned(),
+                    identifier: self.intern(&identifier.lexeme),
                     expr,
                     type_annotation,
                     decorators,
@@ -472,58 +616,162 @@ impl Parser {
         }
     }
 
-    fn parse_function_declaration(&mut self, tokens: &[Token]) -> Result<Statement> {
-        if let Some(fn_name) = self.match_exact(tokens, TokenKind::Identifier) {
-            let function_name_span = self.last(tokens).unwrap().span;
-            let mut type_parameters = vec![];
-            // Parsing the generic parameters if there are any
-            if self.match_exact(tokens, TokenKind::LessThan).is_some() {
-                while self.match_exact(tokens, TokenKind::GreaterThan).is_none() {
-                    if let Some(type_parameter_name) =
-                        self.match_exact(tokens, TokenKind::Identifier)
-                    {
-                        let bound = if self.match_exact(tokens, TokenKind::Colon).is_some() {
-                            match self.match_exact(tokens, TokenKind::Identifier) {
-                                Some(token) if token.lexeme == "Dim" => {
-                                    Some(TypeParameterBound::Dim)
-                                }
-                                Some(token) => {
-                                    return Err(ParseError {
-                                        kind: ParseErrorKind::UnknownBound(token.lexeme.to_owned()),
-                                        span: token.span,
-                                    });
-                                }
-                                None => {
-                                    return Err(ParseError {
-                                        kind:
-                                            ParseErrorKind::ExpectedBoundInTypeParameterDefinition,
-                                        span: self.peek(tokens).span,
-                                    });
-                                }
-                            }
-                        } else {
-                            None
-                        };
+    /// Parse a definition like `let Point { x, y: py } = p`. The struct is stored
+    /// in a hidden variable, which is what is returned here. The definitions of
+    /// the variables for the individual fields are stored in
+    /// `destructured_fields`.
+    fn parse_struct_pattern(&mut self, tokens: &[Token]) -> Result<DefineVariable> {
+        let struct_name = self.match_exact(tokens, TokenKind::Identifier).unwrap();
+        let struct_span = struct_name.span;
+        self.match_exact(tokens, TokenKind::LeftCurly).unwrap();
 
-                        let span = self.last(tokens).unwrap().span;
-                        type_parameters.push((span, type_parameter_name.lexeme.to_string(), bound));
+        if !self.decorator_stack.is_empty() || self.uncertainty.is_some() {
+            return Err(ParseError::new(
+                ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+                struct_span,
+            ));
+        }
 
-                        if self.match_exact(tokens, TokenKind::Comma).is_none()
-                            && self.peek(tokens).kind != TokenKind::GreaterThan
-                        {
-                            return Err(ParseError {
-                                kind: ParseErrorKind::ExpectedCommaOrRightAngleBracket,
-                                span: self.peek(tokens).span,
-                            });
+        self.skip_empty_lines(tokens);
+
+        let mut bindings = vec![];
+        while self.match_exact(tokens, TokenKind::RightCurly).is_none() {
+            self.skip_empty_lines(tokens);
+
+            let Some(field) = self.match_exact(tokens, TokenKind::Identifier) else {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedFieldNameInStruct,
+                    self.peek(tokens).span,
+                ));
+            };
+
+            let binding = if self.match_exact(tokens, TokenKind::Colon).is_some() {
+                self.skip_empty_lines(tokens);
+                let Some(binding) = self.match_exact(tokens, TokenKind::Identifier) else {
+                    return Err(ParseError::new(
+                        ParseErrorKind::ExpectedIdentifier,
+                        self.peek(tokens).span,
+                    ));
+                };
+                binding
+            } else {
+                field
+            };
+
+            self.skip_empty_lines(tokens);
+
+            let has_comma = self.match_exact(tokens, TokenKind::Comma).is_some();
+
+            self.skip_empty_lines(tokens);
+
+            if !has_comma && self.peek(tokens).kind != TokenKind::RightCurly {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedCommaOrRightCurlyInStructFieldList,
+                    self.peek(tokens).span,
+                ));
+            }
+
+            bindings.push((field, binding));
+        }
+
+        if self.match_exact(tokens, TokenKind::Equal).is_none() {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedEqualAfterStructPattern,
+                self.peek(tokens).span,
+            ));
+        }
+
+        self.skip_empty_lines(tokens);
+        let expr = self.expression(tokens)?;
+
+        let identifier = self.intern(&format!("__destructured_{}", struct_name.lexeme));
+
+        self.destructured_fields = bindings
+            .into_iter()
+            .map(|(field, binding)| {
+                Statement::DefineVariable(DefineVariable {
+                    identifier_span: binding.span,
+                    identifier: self.intern(&binding.lexeme),
+                    expr: Expression::AccessField(
+                        field.span,
+                        field.span,
+                        Box::new(Expression::Identifier(struct_span, identifier.clone())),
+                        self.intern(&field.lexeme),
+                    ),
+                    type_annotation: None,
+                    decorators: vec![],
+                })
+            })
+            .collect();
+
+        Ok(DefineVariable {
+            identifier_span: struct_span,
+            identifier,
+            expr,
+            type_annotation: Some(TypeAnnotation::TypeExpression(
+                TypeExpression::TypeIdentifier(struct_span, self.intern(&struct_name.lexeme)),
+            )),
+            decorators: vec![],
+        })
+    }
+
+    /// Parse the generic parameters of a function or struct definition, like
+    /// `<A, D: Dim>`, if there are any.
+    fn type_parameters(
+        &mut self,
+        tokens: &[Token],
+    ) -> Result<Vec<(Span, Symbol, Option<TypeParameterBound>)>> {
+        let mut type_parameters = vec![];
+        if self.match_exact(tokens, TokenKind::LessThan).is_some() {
+            while self.match_exact(tokens, TokenKind::GreaterThan).is_none() {
+                if let Some(type_parameter_name) = self.match_exact(tokens, TokenKind::Identifier) {
+                    let bound = if self.match_exact(tokens, TokenKind::Colon).is_some() {
+                        match self.match_exact(tokens, TokenKind::Identifier) {
+                            Some(token) if token.lexeme == "Dim" => Some(TypeParameterBound::Dim),
+                            Some(token) => {
+                                return Err(ParseError {
+                                    kind: ParseErrorKind::UnknownBound(token.lexeme.to_string()),
+                                    span: token.span,
+                                });
+                            }
+                            None => {
+                                return Err(ParseError {
+                                    kind: ParseErrorKind::ExpectedBoundInTypeParameterDefinition,
+                                    span: self.peek(tokens).span,
+                                });
+                            }
                         }
                     } else {
+                        None
+                    };
+
+                    let span = self.last(tokens).unwrap().span;
+                    type_parameters.push((span, self.intern(&type_parameter_name.lexeme), bound));
+
+                    if self.match_exact(tokens, TokenKind::Comma).is_none()
+                        && self.peek(tokens).kind != TokenKind::GreaterThan
+                    {
                         return Err(ParseError {
-                            kind: ParseErrorKind::ExpectedTypeParameterName,
+                            kind: ParseErrorKind::ExpectedCommaOrRightAngleBracket,
                             span: self.peek(tokens).span,
                         });
                     }
+                } else {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::ExpectedTypeParameterName,
+                        span: self.peek(tokens).span,
+                    });
                 }
             }
+        }
+
+        Ok(type_parameters)
+    }
+
+    fn parse_function_declaration(&mut self, tokens: &[Token]) -> Result<Statement> {
+        if let Some(fn_name) = self.match_exact(tokens, TokenKind::Identifier) {
+            let function_name_span = self.last(tokens).unwrap().span;
+            let type_parameters = self.type_parameters(tokens)?;
 
             if self.match_exact(tokens, TokenKind::LeftParen).is_none() {
                 return Err(ParseError {
@@ -545,7 +793,7 @@ impl Parser {
                         None
                     };
 
-                    parameters.push((span, param_name.lexeme.to_string(), param_type_dexpr));
+                    parameters.push((span, self.intern(&param_name.lexeme), param_type_dexpr));
 
                     parameter_span = parameter_span.extend(&self.last(tokens).unwrap().span);
 
@@ -631,7 +879,7 @@ impl Parser {
 
             Ok(Statement::DefineFunction {
                 function_name_span,
-                function_name: fn_name.lexeme.to_owned(),
+                function_name: self.intern(&fn_name.lexeme),
                 type_parameters,
                 parameters,
                 body,
@@ -667,13 +915,13 @@ impl Parser {
 
                 Ok(Statement::DefineDimension(
                     identifier.span,
-                    identifier.lexeme.to_owned(),
+                    self.intern(&identifier.lexeme),
                     dexprs,
                 ))
             } else {
                 Ok(Statement::DefineDimension(
                     identifier.span,
-                    identifier.lexeme.to_owned(),
+                    self.intern(&identifier.lexeme),
                     vec![],
                 ))
             }
@@ -687,9 +935,15 @@ impl Parser {
 
     fn parse_decorators(&mut self, tokens: &[Token]) -> Result<Statement> {
         if let Some(decorator) = self.match_exact(tokens, TokenKind::Identifier) {
-            let decorator = match decorator.lexeme {
+            let decorator = match &*decorator.lexeme {
                 "metric_prefixes" => Decorator::MetricPrefixes,
                 "binary_prefixes" => Decorator::BinaryPrefixes,
+                "private" => Decorator::Private,
+                "relative" => Decorator::Relative,
+                "reciprocal" => Decorator::Reciprocal,
+                "fractional" => Decorator::Fractional,
+                "natural_conversion" => Decorator::NaturalConversion,
+                "simplification_target" => Decorator::SimplificationTarget,
                 "aliases" => {
                     if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
                         let aliases = self.list_of_aliases(tokens)?;
@@ -701,7 +955,80 @@ impl Parser {
                         });
                     }
                 }
-                "url" | "name" | "description" => {
+                "shadowing" => {
+                    if self.match_exact(tokens, TokenKind::LeftParen).is_none() {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::ExpectedLeftParenAfterDecorator,
+                            span: self.peek(tokens).span,
+                        });
+                    }
+                    let policy = match &*self.peek(tokens).lexeme {
+                        "error" => ShadowingPolicy::Error,
+                        "warn" => ShadowingPolicy::Warn,
+                        "allow" => ShadowingPolicy::Allow,
+                        _ => {
+                            return Err(ParseError::new(
+                                ParseErrorKind::UnknownShadowingPolicy,
+                                self.peek(tokens).span,
+                            ));
+                        }
+                    };
+                    self.advance(tokens);
+                    if self.match_exact(tokens, TokenKind::RightParen).is_none() {
+                        return Err(ParseError::new(
+                            ParseErrorKind::MissingClosingParen,
+                            self.peek(tokens).span,
+                        ));
+                    }
+                    Decorator::Shadowing(policy)
+                }
+                "uncertainty" => {
+                    if self.match_exact(tokens, TokenKind::LeftParen).is_none() {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::ExpectedLeftParenAfterDecorator,
+                            span: self.peek(tokens).span,
+                        });
+                    }
+                    let expr = self.expression(tokens)?;
+                    if self.match_exact(tokens, TokenKind::RightParen).is_none() {
+                        return Err(ParseError::new(
+                            ParseErrorKind::MissingClosingParen,
+                            self.peek(tokens).span,
+                        ));
+                    }
+                    self.uncertainty = Some((decorator.span, expr));
+
+                    self.skip_empty_lines(tokens);
+                    return self.statement(tokens);
+                }
+                "alias" => {
+                    if self.match_exact(tokens, TokenKind::LeftParen).is_none() {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::ExpectedLeftParenAfterDecorator,
+                            span: self.peek(tokens).span,
+                        });
+                    }
+                    let alias = self.identifier(tokens)?;
+                    let alias_span = self.last(tokens).unwrap().span;
+                    let accepts_prefix = self.accepts_prefix(tokens)?;
+                    if self.match_exact(tokens, TokenKind::RightParen).is_none() {
+                        return Err(ParseError::new(
+                            ParseErrorKind::MissingClosingParen,
+                            self.peek(tokens).span,
+                        ));
+                    }
+                    if self.alias.is_some() {
+                        return Err(ParseError::new(
+                            ParseErrorKind::AliasDecoratorUsedOnUnsuitableKind,
+                            decorator.span,
+                        ));
+                    }
+                    self.alias = Some((alias_span, alias, accepts_prefix));
+
+                    self.skip_empty_lines(tokens);
+                    return self.statement(tokens);
+                }
+                "url" | "name" | "description" | "source" | "deprecated" => {
                     if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
                         if let Some(token) = self.match_exact(tokens, TokenKind::StringFixed) {
                             if self.match_exact(tokens, TokenKind::RightParen).is_none() {
@@ -711,12 +1038,14 @@ impl Parser {
                                 ));
                             }
 
-                            let content = strip_and_escape(token.lexeme);
+                            let content = strip_and_escape(&token.lexeme);
 
-                            match decorator.lexeme {
+                            match &*decorator.lexeme {
                                 "url" => Decorator::Url(content),
                                 "name" => Decorator::Name(content),
                                 "description" => Decorator::Description(content),
+                                "source" => Decorator::Source(content),
+                                "deprecated" => Decorator::Deprecated(content),
                                 _ => unreachable!(),
                             }
                         } else {
@@ -754,6 +1083,17 @@ impl Parser {
     }
 
     fn parse_unit_declaration(&mut self, tokens: &[Token]) -> Result<Statement> {
+        if let Some((alias_span, alias, accepts_prefix)) = self.alias.take() {
+            return self.parse_aliased_unit(tokens, alias_span, alias, accepts_prefix);
+        }
+
+        if self.peek(tokens).lexeme == "alias"
+            && tokens.get(self.current + 1).map(|t| t.kind) == Some(TokenKind::Identifier)
+        {
+            self.advance(tokens);
+            return self.parse_unit_alias(tokens);
+        }
+
         if let Some(identifier) = self.match_exact(tokens, TokenKind::Identifier) {
             let identifier_span = self.last(tokens).unwrap().span;
             let (type_annotation_span, dexpr) =
@@ -764,7 +1104,7 @@ impl Parser {
                     (None, None)
                 };
 
-            let unit_name = identifier.lexeme.to_owned();
+            let unit_name = self.intern(&identifier.lexeme);
 
             let mut decorators = vec![];
             std::mem::swap(&mut decorators, &mut self.decorator_stack);
@@ -808,15 +1148,111 @@ impl Parser {
         }
     }
 
+    fn parse_unit_alias(&mut self, tokens: &[Token]) -> Result<Statement> {
+        if !self.decorator_stack.is_empty() {
+            return Err(ParseError::new(
+                ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+                self.peek(tokens).span,
+            ));
+        }
+
+        let alias = self.identifier(tokens)?;
+        let alias_span = self.last(tokens).unwrap().span;
+        let accepts_prefix = self.accepts_prefix(tokens)?;
+
+        if self.match_exact(tokens, TokenKind::Equal).is_none() {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedEqualInUnitAlias,
+                self.peek(tokens).span,
+            ));
+        }
+
+        let Some(unit) = self.match_exact(tokens, TokenKind::Identifier) else {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedUnitInUnitAlias,
+                self.peek(tokens).span,
+            ));
+        };
+
+        Ok(Statement::DefineUnitAlias {
+            alias_span,
+            alias,
+            accepts_prefix,
+            unit_span: unit.span,
+            unit: self.intern(&unit.lexeme),
+        })
+    }
+
+    /// The decorator form of a unit alias: `@alias(mtr: short) unit meter`
+    fn parse_aliased_unit(
+        &mut self,
+        tokens: &[Token],
+        alias_span: Span,
+        alias: Symbol,
+        accepts_prefix: Option<AcceptsPrefix>,
+    ) -> Result<Statement> {
+        if !self.decorator_stack.is_empty() {
+            return Err(ParseError::new(
+                ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+                self.peek(tokens).span,
+            ));
+        }
+
+        let Some(unit) = self.match_exact(tokens, TokenKind::Identifier) else {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedUnitInUnitAlias,
+                self.peek(tokens).span,
+            ));
+        };
+        if !self.is_end_of_statement(tokens) {
+            return Err(ParseError::new(
+                ParseErrorKind::AliasDecoratorUsedOnUnsuitableKind,
+                self.peek(tokens).span,
+            ));
+        }
+
+        Ok(Statement::DefineUnitAlias {
+            alias_span,
+            alias,
+            accepts_prefix,
+            unit_span: unit.span,
+            unit: self.intern(&unit.lexeme),
+        })
+    }
+
+    fn parse_preferred_unit(&mut self, tokens: &[Token]) -> Result<Statement> {
+        let mut span = self.peek(tokens).span;
+        self.advance(tokens); // "set"
+        self.advance(tokens); // "preferred_unit"
+
+        let dimension = self.dimension_expression(tokens)?;
+
+        if self.match_exact(tokens, TokenKind::Equal).is_none() {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedEqualInPreferredUnit,
+                self.peek(tokens).span,
+            ));
+        }
+
+        let expr = self.expression(tokens)?;
+        span = span.extend(&expr.full_span());
+
+        Ok(Statement::SetPreferredUnit {
+            span,
+            dimension,
+            expr,
+        })
+    }
+
     fn parse_use(&mut self, tokens: &[Token]) -> Result<Statement> {
         let mut span = self.peek(tokens).span;
 
         if let Some(identifier) = self.match_exact(tokens, TokenKind::Identifier) {
-            let mut module_path = vec![identifier.lexeme.to_owned()];
+            let mut module_path = vec![identifier.lexeme.to_string()];
 
             while self.match_exact(tokens, TokenKind::DoubleColon).is_some() {
                 if let Some(identifier) = self.match_exact(tokens, TokenKind::Identifier) {
-                    module_path.push(identifier.lexeme.to_owned());
+                    module_path.push(identifier.lexeme.to_string());
                 } else {
                     return Err(ParseError {
                         kind: ParseErrorKind::ExpectedModuleNameAfterDoubleColon,
@@ -826,7 +1262,43 @@ impl Parser {
             }
             span = span.extend(&self.last(tokens).unwrap().span);
 
-            Ok(Statement::ModuleImport(span, ModulePath(module_path)))
+            let kind = if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
+                let mut names = vec![];
+                loop {
+                    let name = self.identifier(tokens)?;
+                    names.push((self.last(tokens).unwrap().span, name));
+
+                    if self.match_exact(tokens, TokenKind::RightParen).is_some() {
+                        break;
+                    } else if self.match_exact(tokens, TokenKind::Comma).is_none() {
+                        return Err(ParseError::new(
+                            ParseErrorKind::ExpectedCommaOrRightParenInImportList,
+                            self.peek(tokens).span,
+                        ));
+                    }
+                }
+                span = span.extend(&self.last(tokens).unwrap().span);
+
+                ImportKind::Selected(names)
+            } else if self.peek(tokens).kind == TokenKind::Identifier
+                && self.peek(tokens).lexeme == "as"
+            {
+                self.advance(tokens);
+
+                let Some(alias) = self.match_exact(tokens, TokenKind::Identifier) else {
+                    return Err(ParseError::new(
+                        ParseErrorKind::ExpectedIdentifierAfterAs,
+                        self.peek(tokens).span,
+                    ));
+                };
+                span = span.extend(&alias.span);
+
+                ImportKind::Aliased(alias.span, self.intern(&alias.lexeme))
+            } else {
+                ImportKind::All
+            };
+
+            Ok(Statement::ModuleImport(span, ModulePath(module_path), kind))
         } else {
             Err(ParseError {
                 kind: ParseErrorKind::ExpectedModulePathAfterUse,
@@ -839,6 +1311,8 @@ impl Parser {
         let name = self.identifier(tokens)?;
         let name_span = self.last(tokens).unwrap().span;
 
+        let type_parameters = self.type_parameters(tokens)?;
+
         if self.match_exact(tokens, TokenKind::LeftCurly).is_none() {
             return Err(ParseError {
                 kind: ParseErrorKind::ExpectedLeftCurlyAfterStructName,
@@ -884,12 +1358,13 @@ impl Parser {
                 });
             }
 
-            fields.push((field_name.span, field_name.lexeme.to_owned(), attr_type));
+            fields.push((field_name.span, self.intern(&field_name.lexeme), attr_type));
         }
 
         Ok(Statement::DefineStruct {
             struct_name_span: name_span,
             struct_name: name,
+            type_parameters,
             fields,
         })
     }
@@ -945,12 +1420,16 @@ impl Parser {
     }
 
     pub fn expression(&mut self, tokens: &[Token]) -> Result<Expression> {
-        self.postfix_apply(tokens)
+        // Nested expressions like `(2 step)` are not affected by an outer range
+        let in_range_end = std::mem::replace(&mut self.in_range_end, false);
+        let expr = self.postfix_apply(tokens);
+        self.in_range_end = in_range_end;
+        expr
     }
 
-    fn identifier(&mut self, tokens: &[Token]) -> Result<String> {
+    fn identifier(&mut self, tokens: &[Token]) -> Result<Symbol> {
         if let Some(identifier) = self.match_exact(tokens, TokenKind::Identifier) {
-            Ok(identifier.lexeme.to_owned())
+            Ok(self.intern(&identifier.lexeme))
         } else {
             Err(ParseError::new(
                 ParseErrorKind::ExpectedIdentifier,
@@ -1030,10 +1509,42 @@ impl Parser {
                 Box::new(else_expr),
             ))
         } else {
-            self.conversion(tokens)
+            self.range(tokens)
         }
     }
 
+    fn range(&mut self, tokens: &[Token]) -> Result<Expression> {
+        let start = self.conversion(tokens)?;
+
+        let Some(op) = self.match_any(tokens, &[TokenKind::DotDot, TokenKind::DotDotEqual]) else {
+            return Ok(start);
+        };
+        let span_op = op.span;
+        let inclusive = op.kind == TokenKind::DotDotEqual;
+
+        self.in_range_end = true;
+        let end = self.conversion(tokens);
+        self.in_range_end = false;
+        let end = end?;
+
+        let step = if self.peek(tokens).kind == TokenKind::Identifier
+            && self.peek(tokens).lexeme == "step"
+        {
+            self.advance(tokens);
+            Some(Box::new(self.conversion(tokens)?))
+        } else {
+            None
+        };
+
+        Ok(Expression::Range {
+            span_op,
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            inclusive,
+        })
+    }
+
     fn conversion(&mut self, tokens: &[Token]) -> Result<Expression> {
         self.parse_binop(
             tokens,
@@ -1125,22 +1636,57 @@ impl Parser {
                 TokenKind::Divide => BinaryOperator::Div,
                 _ => unreachable!(),
             },
-            |parser| parser.per_factor(tokens),
+            |parser| parser.of_factor(tokens),
         )
     }
 
-    fn per_factor(&mut self, tokens: &[Token]) -> Result<Expression> {
-        self.parse_binop(
-            tokens,
-            &[TokenKind::Per],
-            |_| BinaryOperator::Div,
-            |parser| parser.unary(tokens),
-        )
-    }
+    fn of_factor(&mut self, tokens: &[Token]) -> Result<Expression> {
+        let mut expr = self.per_factor(tokens)?;
+        while self.next_token_is_of_operator(tokens) {
+            let span_op = Some(self.peek(tokens).span);
+            self.advance(tokens);
+            let rhs = self.per_factor(tokens)?;
 
-    fn unary(&mut self, tokens: &[Token]) -> Result<Expression> {
-        if self.match_exact(tokens, TokenKind::Minus).is_some() {
-            let span = self.last(tokens).unwrap().span;
+            expr = Expression::BinaryOperator {
+                op: BinaryOperator::Of,
+                lhs: Box::new(expr),
+                rhs: Box::new(rhs),
+                span_op,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// `of` is not a keyword. It is only treated as the operator in `15 % of 80 kg`
+    /// if it is followed by an operand, such that `2 of` is a multiplication with
+    /// a variable `of`.
+    fn next_token_is_of_operator(&self, tokens: &[Token]) -> bool {
+        self.peek(tokens).kind == TokenKind::Identifier
+            && self.peek(tokens).lexeme == "of"
+            && tokens.get(self.current + 1).is_some_and(|token| {
+                matches!(
+                    token.kind,
+                    TokenKind::Number
+                        | TokenKind::Identifier
+                        | TokenKind::LeftParen
+                        | TokenKind::QuestionMark
+                        | TokenKind::Minus
+                )
+            })
+    }
+
+    fn per_factor(&mut self, tokens: &[Token]) -> Result<Expression> {
+        self.parse_binop(
+            tokens,
+            &[TokenKind::Per],
+            |_| BinaryOperator::Div,
+            |parser| parser.unary(tokens),
+        )
+    }
+
+    fn unary(&mut self, tokens: &[Token]) -> Result<Expression> {
+        if self.match_exact(tokens, TokenKind::Minus).is_some() {
+            let span = self.last(tokens).unwrap().span;
             let rhs = self.unary(tokens)?;
 
             Ok(Expression::UnaryOperator {
@@ -1158,9 +1704,20 @@ impl Parser {
     }
 
     fn ifactor(&mut self, tokens: &[Token]) -> Result<Expression> {
-        let mut expr = self.power(tokens)?;
+        let mut expr = if self.next_tokens_form_dms_literal(tokens) {
+            self.dms_literal(tokens)?
+        } else {
+            self.power(tokens)?
+        };
 
         while self.next_token_could_start_power_expression(tokens) {
+            if self.strict_multiplication && self.peek(tokens).kind == TokenKind::LeftParen {
+                return Err(ParseError::new(
+                    ParseErrorKind::ImplicitMultiplicationWithParentheses,
+                    self.peek(tokens).span,
+                ));
+            }
+
             let rhs = self.power(tokens)?;
             expr = Expression::BinaryOperator {
                 op: BinaryOperator::Mul,
@@ -1173,6 +1730,66 @@ impl Parser {
         Ok(expr)
     }
 
+    /// Parse an angle like `12° 34′ 56″` as the sum of its components. A
+    /// plain implicit multiplication would result in a product of angles.
+    fn dms_literal(&mut self, tokens: &[Token]) -> Result<Expression> {
+        let mut expr: Option<Expression> = None;
+        let mut previous_rank = None;
+
+        while let Some(rank) = self.dms_component_rank(tokens, self.current) {
+            if previous_rank.is_some_and(|previous| rank <= previous) {
+                break;
+            }
+            previous_rank = Some(rank);
+
+            let number = self.power(tokens)?;
+            let unit = self.power(tokens)?;
+            let component = Expression::BinaryOperator {
+                op: BinaryOperator::Mul,
+                lhs: Box::new(number),
+                rhs: Box::new(unit),
+                span_op: None,
+            };
+
+            expr = Some(match expr {
+                None => component,
+                Some(lhs) => Expression::BinaryOperator {
+                    op: BinaryOperator::Add,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(component),
+                    span_op: None,
+                },
+            });
+        }
+
+        Ok(expr.expect("dms_literal is only called if there is at least one component"))
+    }
+
+    /// If the tokens at `index` are a number followed by `°`, `′` or `″`,
+    /// return the position of the symbol in that list.
+    fn dms_component_rank(&self, tokens: &[Token], index: usize) -> Option<usize> {
+        let number = tokens.get(index)?;
+        let symbol = tokens.get(index + 1)?;
+        if number.kind != TokenKind::Number || symbol.kind != TokenKind::Identifier {
+            return None;
+        }
+        ["°", "′", "″"]
+            .iter()
+            .position(|&dms_symbol| symbol.lexeme == dms_symbol)
+    }
+
+    /// A DMS literal needs at least two components in descending order, so
+    /// that a single `30°` is still parsed as an ordinary multiplication.
+    fn next_tokens_form_dms_literal(&self, tokens: &[Token]) -> bool {
+        match (
+            self.dms_component_rank(tokens, self.current),
+            self.dms_component_rank(tokens, self.current + 2),
+        ) {
+            (Some(first), Some(second)) => first < second,
+            _ => false,
+        }
+    }
+
     fn power(&mut self, tokens: &[Token]) -> Result<Expression> {
         let mut expr = self.factorial(tokens)?;
 
@@ -1227,37 +1844,93 @@ impl Parser {
         Ok(expr)
     }
 
-    fn unicode_exponent_to_int(lexeme: &str) -> i32 {
-        match lexeme {
-            "⁻¹" => -1,
-            "⁻²" => -2,
-            "⁻³" => -3,
-            "⁻⁴" => -4,
-            "⁻⁵" => -5,
-            "⁻⁶" => -6,
-            "⁻⁷" => -7,
-            "⁻⁸" => -8,
-            "⁻⁹" => -9,
-            "¹" => 1,
-            "²" => 2,
-            "³" => 3,
-            "⁴" => 4,
-            "⁵" => 5,
-            "⁶" => 6,
-            "⁷" => 7,
-            "⁸" => 8,
-            "⁹" => 9,
-            _ => unreachable!(
-                "Tokenizer should not generate unicode exponent tokens for anything else"
-            ),
+    fn unicode_exponent_to_int(exponent: &Token) -> Result<i128> {
+        let (negative, digits) = match exponent.lexeme.strip_prefix('⁻') {
+            Some(digits) => (true, digits),
+            None => (false, &*exponent.lexeme),
+        };
+
+        let magnitude = digits.chars().try_fold(0i128, |acc, c| {
+            let digit = match c {
+                '⁰' => 0,
+                '¹' => 1,
+                '²' => 2,
+                '³' => 3,
+                '⁴' => 4,
+                '⁵' => 5,
+                '⁶' => 6,
+                '⁷' => 7,
+                '⁸' => 8,
+                '⁹' => 9,
+                _ => unreachable!(
+                    "Tokenizer should not generate unicode exponent tokens for anything else"
+                ),
+            };
+            acc.checked_mul(10)?.checked_add(digit)
+        });
+
+        match magnitude {
+            Some(magnitude) if negative => Ok(-magnitude),
+            Some(magnitude) => Ok(magnitude),
+            None => Err(ParseError::new(
+                ParseErrorKind::OverflowInNumberLiteral,
+                exponent.span,
+            )),
         }
     }
 
+    /// Convert a number literal like `1_000`, `1.5e3` or `1.602×10⁻¹⁹` to a float
+    fn decimal_literal_to_f64(lexeme: &str) -> f64 {
+        let lexeme = lexeme.replace('_', "");
+
+        let Some((mantissa, power_of_ten)) = lexeme.split_once(['×', '·']) else {
+            return lexeme.parse().unwrap();
+        };
+
+        let exponent = power_of_ten.strip_prefix("10").unwrap_or(power_of_ten);
+        let exponent: String = exponent
+            .strip_prefix('^')
+            .unwrap_or(exponent)
+            .chars()
+            .map(|c| match c {
+                '⁻' | '−' => '-',
+                _ => "⁰¹²³⁴⁵⁶⁷⁸⁹"
+                    .chars()
+                    .position(|digit| digit == c)
+                    .and_then(|digit| char::from_digit(digit as u32, 10))
+                    .unwrap_or(c),
+            })
+            .collect();
+
+        format!("{mantissa}e{exponent}").parse().unwrap()
+    }
+
+    /// Convert a hexadecimal floating point literal like `0x1.8p3` to a float.
+    /// Returns `None` if the binary exponent is out of range.
+    fn hex_float_to_f64(lexeme: &str) -> Option<f64> {
+        let lexeme = lexeme[2..].replace('_', "");
+        let (mantissa, exponent) = lexeme
+            .split_once(['p', 'P'])
+            .expect("Tokenizer only generates hex floats with an exponent");
+        let (integer_part, fractional_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        let digits = integer_part
+            .chars()
+            .chain(fractional_part.chars())
+            .fold(0.0, |acc, c| acc * 16.0 + c.to_digit(16).unwrap() as f64);
+        let exponent = exponent
+            .parse::<i32>()
+            .ok()?
+            .checked_sub(4 * fractional_part.len() as i32)?;
+
+        Some(digits * 2f64.powi(exponent))
+    }
+
     fn unicode_power(&mut self, tokens: &[Token]) -> Result<Expression> {
         let mut expr = self.call(tokens)?;
 
         if let Some(exponent) = self.match_exact(tokens, TokenKind::UnicodeExponent) {
-            let exp = Self::unicode_exponent_to_int(exponent.lexeme);
+            let exp = Self::unicode_exponent_to_int(exponent)?;
 
             expr = Expression::BinaryOperator {
                 op: BinaryOperator::Power,
@@ -1277,7 +1950,10 @@ impl Parser {
         let mut expr = self.primary(tokens)?;
 
         loop {
-            if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
+            if self.peek(tokens).kind == TokenKind::LeftParen
+                && self.next_parens_are_call(tokens, &expr)
+            {
+                self.advance(tokens);
                 let args = self.arguments(tokens)?;
                 expr = Expression::FunctionCall(
                     expr.full_span(),
@@ -1297,6 +1973,37 @@ impl Parser {
         }
     }
 
+    /// Decide whether a `(` after `callee` starts the arguments of a function
+    /// call (`f(x)`) or a factor of an implicit multiplication (`2(3 + 4)`,
+    /// `(a)(b)` or `3 m (1 + x)`). A call needs a name, a field, a typed hole or
+    /// another call in front of the parentheses, without any whitespace in between.
+    fn next_parens_are_call(&self, tokens: &[Token], callee: &Expression) -> bool {
+        let left_paren = self.peek(tokens);
+        let previous = self.last(tokens).unwrap();
+
+        // Empty parentheses can not be a factor
+        if tokens
+            .get(self.current + 1)
+            .is_some_and(|token| token.kind == TokenKind::RightParen)
+        {
+            return true;
+        }
+
+        let is_adjacent = previous.span.end.byte == left_paren.span.start.byte;
+        let is_parenthesized = previous.kind == TokenKind::RightParen
+            && !matches!(callee, Expression::FunctionCall(..));
+
+        is_adjacent
+            && !is_parenthesized
+            && matches!(
+                callee,
+                Expression::Identifier(..)
+                    | Expression::FunctionCall(..)
+                    | Expression::AccessField(..)
+                    | Expression::TypedHole(..)
+            )
+    }
+
     fn arguments(&mut self, tokens: &[Token]) -> Result<Vec<Expression>> {
         self.skip_empty_lines(tokens);
         if self.match_exact(tokens, TokenKind::RightParen).is_some() {
@@ -1345,10 +2052,9 @@ impl Parser {
         };
 
         if let Some(num) = self.match_exact(tokens, TokenKind::Number) {
-            let num_string = num.lexeme.replace('_', "");
             Ok(Expression::Scalar(
                 self.last(tokens).unwrap().span,
-                Number::from_f64(num_string.parse::<f64>().unwrap()),
+                Number::from_f64(Self::decimal_literal_to_f64(&num.lexeme)),
             ))
         } else if let Some(hex_int) = self.match_exact(tokens, TokenKind::IntegerWithBase(16)) {
             let span = self.last(tokens).unwrap().span;
@@ -1377,6 +2083,19 @@ impl Parser {
                         .or_else(|_| overflow_error(span))? as f64, // TODO: i128 limits our precision here
                 ),
             ))
+        } else if let Some(hex_float) = self.match_exact(tokens, TokenKind::HexFloat) {
+            let span = self.last(tokens).unwrap().span;
+            Ok(Expression::Scalar(
+                span,
+                Number::from_f64(Self::hex_float_to_f64(&hex_float.lexeme).ok_or_else(|| {
+                    ParseError::new(ParseErrorKind::OverflowInNumberLiteral, span)
+                })?),
+            ))
+        } else if let Some(token) = self.match_exact(tokens, TokenKind::DateTimeLiteral) {
+            let span = token.span;
+            let dt = crate::datetime::parse_datetime_literal(&token.lexeme[1..])
+                .map_err(|e| ParseError::new(ParseErrorKind::InvalidDateTimeLiteral(e), span))?;
+            Ok(Expression::DateTime(span, dt))
         } else if self.match_exact(tokens, TokenKind::NaN).is_some() {
             let span = self.last(tokens).unwrap().span;
             Ok(Expression::Scalar(span, Number::from_f64(f64::NAN)))
@@ -1413,7 +2132,17 @@ impl Parser {
             let span = self.last(tokens).unwrap().span;
             Ok(Expression::TypedHole(span))
         } else if let Some(identifier) = self.match_exact(tokens, TokenKind::Identifier) {
-            let span = self.last(tokens).unwrap().span;
+            let mut span = self.last(tokens).unwrap().span;
+
+            // Qualified names like `foo::bar` refer to items from modules that
+            // have been imported via `use … as foo`.
+            let mut name = identifier.lexeme.to_string();
+            while self.match_exact(tokens, TokenKind::DoubleColon).is_some() {
+                name.push_str("::");
+                name.push_str(&self.identifier(tokens)?);
+                span = span.extend(&self.last(tokens).unwrap().span);
+            }
+            let name = self.intern(&name);
 
             if self.match_exact(tokens, TokenKind::LeftCurly).is_some() {
                 self.skip_empty_lines(tokens);
@@ -1455,7 +2184,7 @@ impl Parser {
                         });
                     }
 
-                    fields.push((field_name.span, field_name.lexeme.to_owned(), expr));
+                    fields.push((field_name.span, self.intern(&field_name.lexeme), expr));
                 }
 
                 let full_span = span.extend(&self.last(tokens).unwrap().span);
@@ -1463,12 +2192,12 @@ impl Parser {
                 return Ok(Expression::InstantiateStruct {
                     full_span,
                     ident_span: span,
-                    name: identifier.lexeme.to_owned(),
+                    name,
                     fields,
                 });
             }
 
-            Ok(Expression::Identifier(span, identifier.lexeme.to_owned()))
+            Ok(Expression::Identifier(span, name))
         } else if let Some(inner) = self.match_any(tokens, &[TokenKind::True, TokenKind::False]) {
             Ok(Expression::Boolean(
                 inner.span,
@@ -1477,7 +2206,7 @@ impl Parser {
         } else if let Some(token) = self.match_exact(tokens, TokenKind::StringFixed) {
             Ok(Expression::String(
                 token.span,
-                vec![StringPart::Fixed(strip_and_escape(token.lexeme))],
+                vec![StringPart::Fixed(strip_and_escape(&token.lexeme))],
             ))
         } else if let Some(token) = self.match_exact(tokens, TokenKind::StringInterpolationStart) {
             let mut parts = Vec::new();
@@ -1499,7 +2228,7 @@ impl Parser {
                         self.interpolation(tokens, &mut parts, inner_token)?;
                     }
                     TokenKind::StringInterpolationEnd => {
-                        parts.push(StringPart::Fixed(strip_and_escape(inner_token.lexeme)));
+                        parts.push(StringPart::Fixed(strip_and_escape(&inner_token.lexeme)));
                         has_end = true;
                         break;
                     }
@@ -1529,6 +2258,8 @@ impl Parser {
             }
 
             Ok(inner)
+        } else if self.match_exact(tokens, TokenKind::LeftCurly).is_some() {
+            self.curly_braces(tokens)
         } else if matches!(
             self.peek(tokens).kind,
             TokenKind::ProcedurePrint | TokenKind::ProcedureAssertEq
@@ -1564,19 +2295,148 @@ impl Parser {
         }
     }
 
+    /// Parse a dictionary like `{"a": 1, "b": 2}` or a struct update like
+    /// `{ point with x: 3 m }`, after the opening brace
+    fn curly_braces(&mut self, tokens: &[Token]) -> Result<Expression> {
+        let span = self.last(tokens).unwrap().span;
+        self.skip_empty_lines(tokens);
+
+        if self.match_exact(tokens, TokenKind::RightCurly).is_some() {
+            let full_span = span.extend(&self.last(tokens).unwrap().span);
+            return Ok(Expression::Dict(full_span, vec![]));
+        }
+
+        let expr = self.expression(tokens)?;
+
+        self.skip_empty_lines(tokens);
+        if self.match_exact(tokens, TokenKind::With).is_some() {
+            self.struct_update(tokens, span, expr)
+        } else if self.match_exact(tokens, TokenKind::Colon).is_some() {
+            self.dict(tokens, span, expr)
+        } else {
+            Err(ParseError::new(
+                ParseErrorKind::ExpectedWithOrColonInBraces,
+                self.peek(tokens).span,
+            ))
+        }
+    }
+
+    /// Parse the entries of a dictionary, after the colon of the first entry
+    fn dict(&mut self, tokens: &[Token], span: Span, first_key: Expression) -> Result<Expression> {
+        let mut entries = vec![];
+        let mut key = first_key;
+        loop {
+            self.skip_empty_lines(tokens);
+            let value = self.expression(tokens)?;
+            entries.push((key, value));
+
+            self.skip_empty_lines(tokens);
+            let has_comma = self.match_exact(tokens, TokenKind::Comma).is_some();
+            self.skip_empty_lines(tokens);
+
+            if self.match_exact(tokens, TokenKind::RightCurly).is_some() {
+                break;
+            }
+            if !has_comma {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedCommaOrRightCurlyInDict,
+                    self.peek(tokens).span,
+                ));
+            }
+
+            key = self.expression(tokens)?;
+
+            self.skip_empty_lines(tokens);
+            if self.match_exact(tokens, TokenKind::Colon).is_none() {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedColonAfterDictKey,
+                    self.peek(tokens).span,
+                ));
+            }
+        }
+
+        let full_span = span.extend(&self.last(tokens).unwrap().span);
+        Ok(Expression::Dict(full_span, entries))
+    }
+
+    /// Parse the updated fields of a struct update, after the 'with' keyword
+    fn struct_update(
+        &mut self,
+        tokens: &[Token],
+        span: Span,
+        expr: Expression,
+    ) -> Result<Expression> {
+        self.skip_empty_lines(tokens);
+
+        let mut fields = vec![];
+        while fields.is_empty() || self.match_exact(tokens, TokenKind::RightCurly).is_none() {
+            self.skip_empty_lines(tokens);
+
+            let Some(field_name) = self.match_exact(tokens, TokenKind::Identifier) else {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedFieldNameInStruct,
+                    self.peek(tokens).span,
+                ));
+            };
+
+            let mut path_span = field_name.span;
+            let mut path = vec![self.intern(&field_name.lexeme)];
+            while self.match_exact(tokens, TokenKind::Period).is_some() {
+                path.push(self.identifier(tokens)?);
+                path_span = path_span.extend(&self.last(tokens).unwrap().span);
+            }
+
+            self.skip_empty_lines(tokens);
+
+            if self.match_exact(tokens, TokenKind::Colon).is_none() {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedColonAfterFieldName,
+                    self.peek(tokens).span,
+                ));
+            }
+
+            self.skip_empty_lines(tokens);
+
+            let value = self.expression(tokens)?;
+
+            self.skip_empty_lines(tokens);
+
+            let has_comma = self.match_exact(tokens, TokenKind::Comma).is_some();
+
+            self.skip_empty_lines(tokens);
+
+            if !has_comma && self.peek(tokens).kind != TokenKind::RightCurly {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedCommaOrRightCurlyInStructFieldList,
+                    self.peek(tokens).span,
+                ));
+            }
+
+            fields.push((path_span, path, value));
+        }
+
+        let full_span = span.extend(&self.last(tokens).unwrap().span);
+
+        Ok(Expression::UpdateStruct {
+            full_span,
+            expr: Box::new(expr),
+            fields,
+        })
+    }
+
     fn interpolation(
         &mut self,
         tokens: &[Token],
         parts: &mut Vec<StringPart>,
         token: &Token,
     ) -> Result<()> {
-        parts.push(StringPart::Fixed(strip_and_escape(token.lexeme)));
+        parts.push(StringPart::Fixed(strip_and_escape(&token.lexeme)));
 
         let expr = self.expression(tokens)?;
 
         let format_specifiers = self
             .match_exact(tokens, TokenKind::StringInterpolationSpecifiers)
-            .map(|token| token.lexeme.to_owned());
+            .map(|token| token.lexeme.to_string());
 
         parts.push(StringPart::Interpolation {
             span: expr.full_span(),
@@ -1592,6 +2452,13 @@ impl Parser {
     fn next_token_could_start_power_expression(&self, tokens: &[Token]) -> bool {
         // This function needs to be kept in sync with `primary` above.
 
+        if self.in_range_end && self.peek(tokens).lexeme == "step" {
+            return false;
+        }
+        if self.next_token_is_of_operator(tokens) {
+            return false;
+        }
+
         matches!(
             self.peek(tokens).kind,
             TokenKind::Number
@@ -1679,6 +2546,58 @@ impl Parser {
             let span = span.extend(&self.last(tokens).unwrap().span);
 
             Ok(TypeAnnotation::List(span, Box::new(element_type)))
+        } else if self.peek(tokens).kind == TokenKind::Identifier
+            && self.peek(tokens).lexeme == "Dict"
+            && tokens
+                .get(self.current + 1)
+                .is_some_and(|token| token.kind == TokenKind::LessThan)
+        {
+            // `Dict` is not a keyword, so that it can still be used as an identifier
+            let span = self
+                .match_exact(tokens, TokenKind::Identifier)
+                .unwrap()
+                .span;
+            self.match_exact(tokens, TokenKind::LessThan).unwrap();
+
+            let value_type = self.type_annotation(tokens)?;
+
+            if self.match_exact(tokens, TokenKind::GreaterThan).is_none() {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedTokenInDictType("'>'"),
+                    self.peek(tokens).span,
+                ));
+            }
+
+            let span = span.extend(&self.last(tokens).unwrap().span);
+
+            Ok(TypeAnnotation::Dict(span, Box::new(value_type)))
+        } else if self.peek(tokens).kind == TokenKind::Identifier
+            && tokens
+                .get(self.current + 1)
+                .is_some_and(|token| token.kind == TokenKind::LessThan)
+        {
+            let name = self.match_exact(tokens, TokenKind::Identifier).unwrap();
+            self.match_exact(tokens, TokenKind::LessThan).unwrap();
+
+            let mut type_arguments = vec![self.type_annotation(tokens)?];
+            while self.match_exact(tokens, TokenKind::Comma).is_some() {
+                type_arguments.push(self.type_annotation(tokens)?);
+            }
+
+            if self.match_exact(tokens, TokenKind::GreaterThan).is_none() {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedCommaOrRightAngleBracket,
+                    self.peek(tokens).span,
+                ));
+            }
+
+            let span = name.span.extend(&self.last(tokens).unwrap().span);
+
+            Ok(TypeAnnotation::Struct(
+                span,
+                self.intern(&name.lexeme),
+                type_arguments,
+            ))
         } else {
             Ok(TypeAnnotation::TypeExpression(
                 self.dimension_expression(tokens)?,
@@ -1722,13 +2641,13 @@ impl Parser {
             ))
         } else if let Some(exponent) = self.match_exact(tokens, TokenKind::UnicodeExponent) {
             let span_exponent = self.last(tokens).unwrap().span;
-            let exp = Self::unicode_exponent_to_int(exponent.lexeme);
+            let exp = Self::unicode_exponent_to_int(exponent)?;
 
             Ok(TypeExpression::Power(
                 None,
                 Box::new(expr),
                 span_exponent,
-                Exponent::from_integer(exp as i128),
+                Exponent::from_integer(exp),
             ))
         } else {
             Ok(expr)
@@ -1812,7 +2731,7 @@ impl Parser {
             let span = self.last(tokens).unwrap().span;
             Ok(TypeExpression::TypeIdentifier(
                 span,
-                token.lexeme.to_owned(),
+                self.intern(&token.lexeme),
             ))
         } else if let Some(number) = self.match_exact(tokens, TokenKind::Number) {
             let span = self.last(tokens).unwrap().span;
@@ -1913,8 +2832,37 @@ impl Parser {
     }
 }
 
+/// Remove the delimiters from the lexeme of a string (part), like the quotes in
+/// `"foo"`, or the closing brace and the opening quotes in `} bar"""`. A
+/// newline directly after the opening quotes of a multi-line string is dropped
+/// as well.
+fn strip_delimiters(s: &str) -> &str {
+    const TRIPLE_QUOTE: &str = "\"\"\"";
+
+    let start = if s.starts_with(TRIPLE_QUOTE) { 3 } else { 1 };
+    let end = if s.len() >= start + 3 && s.ends_with(TRIPLE_QUOTE) {
+        3
+    } else {
+        1
+    };
+    let content = &s[start..(s.len() - end)];
+
+    if start == 3 {
+        content
+            .strip_prefix('\n')
+            .or_else(|| content.strip_prefix("\r\n"))
+            .unwrap_or(content)
+    } else {
+        content
+    }
+}
+
 fn strip_and_escape(s: &str) -> String {
-    let trimmed = &s[1..(s.len() - 1)];
+    if let Some(raw) = s.strip_prefix('r') {
+        return strip_delimiters(raw).to_string();
+    }
+
+    let trimmed = strip_delimiters(s);
 
     let mut result = String::with_capacity(trimmed.len());
     let mut escaped = false;
@@ -1955,6 +2903,17 @@ fn strip_and_escape(s: &str) -> String {
 /// while stacking all the errors in a `Vec`. At the end, it returns the complete
 /// list of statements parsed + the list of errors accumulated.
 pub fn parse(input: &str, code_source_id: usize) -> ParseResult {
+    parse_with_strict_multiplication(input, code_source_id, false, &Interner::default())
+}
+
+/// Like [`parse`], but optionally reject implicit multiplications with
+/// parentheses, like `2(3 + 4)`, and intern identifiers with the given interner
+pub fn parse_with_strict_multiplication(
+    input: &str,
+    code_source_id: usize,
+    strict_multiplication: bool,
+    interner: &Interner,
+) -> ParseResult {
     use crate::tokenizer::tokenize;
 
     let tokens = tokenize(input, code_source_id)
@@ -1962,14 +2921,15 @@ pub fn parse(input: &str, code_source_id: usize) -> ParseResult {
             ParseError::new(ParseErrorKind::TokenizerError(kind), span)
         })
         .map_err(|e| (Vec::new(), vec![e]))?;
-    let mut parser = Parser::new();
+    let mut parser = Parser::new(interner.clone());
+    parser.strict_multiplication = strict_multiplication;
     parser.parse(&tokens)
 }
 
 #[cfg(test)]
 pub fn parse_dexpr(input: &str) -> TypeExpression {
     let tokens = crate::tokenizer::tokenize(input, 0).expect("No tokenizer errors in tests");
-    let mut parser = crate::parser::Parser::new();
+    let mut parser = crate::parser::Parser::new(Interner::default());
     let expr = parser
         .dimension_expression(&tokens)
         .expect("No parser errors in tests");
@@ -2181,6 +3141,58 @@ mod tests {
         parse_as_expression(&["1erg"], binop!(scalar!(1.0), Mul, identifier!("erg")));
     }
 
+    #[test]
+    fn times_ten_notation() {
+        parse_as_expression(
+            &[
+                "1.602×10^-19",
+                "1.602·10^-19",
+                "1.602×10⁻¹⁹",
+                "1.602·10^−19",
+            ],
+            scalar!(1.602e-19),
+        );
+        parse_as_expression(&["3×10^8", "3×10⁸", "3×10^+8"], scalar!(3e8));
+        parse_as_expression(&["6.022_140_76×10^23"], scalar!(6.022_140_76e23));
+
+        // The power of ten belongs to the number literal
+        parse_as_expression(&["1 / 2×10^3"], binop!(scalar!(1.0), Div, scalar!(2e3)));
+
+        // Everything else is an ordinary multiplication
+        parse_as_expression(
+            &["2 × 10^3"],
+            binop!(
+                scalar!(2.0),
+                Mul,
+                binop!(scalar!(10.0), Power, scalar!(3.0))
+            ),
+        );
+        parse_as_expression(
+            &["2×10^x"],
+            binop!(
+                scalar!(2.0),
+                Mul,
+                binop!(scalar!(10.0), Power, identifier!("x"))
+            ),
+        );
+        parse_as_expression(
+            &["2×100^3"],
+            binop!(
+                scalar!(2.0),
+                Mul,
+                binop!(scalar!(100.0), Power, scalar!(3.0))
+            ),
+        );
+        parse_as_expression(
+            &["1e3×10^3"],
+            binop!(
+                scalar!(1e3),
+                Mul,
+                binop!(scalar!(10.0), Power, scalar!(3.0))
+            ),
+        );
+    }
+
     #[test]
     fn hex_oct_bin() {
         parse_as_expression(&["0x6A", "0x6a", "0b1101010", "0o152"], scalar!(106.0));
@@ -2201,10 +3213,58 @@ mod tests {
         should_fail(&["0o12348"]);
         should_fail(&["0b10102"]);
 
-        // Until we support hexadecimal float notation
+        // Hexadecimal floats need a binary exponent, other bases do not support floats
         should_fail(&["0x1.2", "0b1.0", "0o1.0", "0x.1", "0b.0", "0o.1"]);
     }
 
+    #[test]
+    fn hex_float() {
+        parse_as_expression(&["0x1.8p3", "0x1.8P3", "0x1.8p+3", "0xCp0"], scalar!(12.0));
+        parse_as_expression(&["0x1p-2", "0x0.4p0"], scalar!(0.25));
+        parse_as_expression(&["0xA.8p0", "0xa.8p0"], scalar!(10.5));
+        parse_as_expression(&["0x1.000_001p0"], scalar!(1.0 + 2f64.powi(-24)));
+        parse_as_expression(&["0x1.fffffffffffffp1023"], scalar!(f64::MAX));
+
+        should_fail(&[
+            "0x1.8", "0x1.8p", "0x1.8p-", "0x1p1.5", "0x1.8p3x", "0x1._8p0",
+        ]);
+        should_fail(&["0x1p99999999999"]);
+    }
+
+    #[test]
+    #[cfg(feature = "datetime")]
+    fn datetime_literals() {
+        let utc =
+            |s: &str| Expression::DateTime(Span::dummy(), format!("{s}[UTC]").parse().unwrap());
+
+        parse_as_expression(
+            &["@2024-05-01T13:00Z", "@2024-05-01T13:00:00.000Z"],
+            utc("2024-05-01T13:00"),
+        );
+        parse_as_expression(&["@2024-05-01Z"], utc("2024-05-01T00:00"));
+        parse_as_expression(
+            &["@2024-05-01T13:00+02:00"],
+            Expression::DateTime(
+                Span::dummy(),
+                "2024-05-01T13:00+02:00[+02:00]".parse().unwrap(),
+            ),
+        );
+        parse_as_expression(
+            &["@2024-05-01T13:00[Europe/Berlin]"],
+            Expression::DateTime(
+                Span::dummy(),
+                "2024-05-01T13:00[Europe/Berlin]".parse().unwrap(),
+            ),
+        );
+
+        should_fail(&[
+            "@2024-13-01",
+            "@2024-05-01T25:00Z",
+            "@2024-05-01T13:00+2:00",
+            "@2024-05-01[Mars/Olympus]",
+        ]);
+    }
+
     #[test]
     fn nonfinite() {
         parse_as_expression(&["inf"], scalar!(f64::INFINITY));
@@ -2239,60 +3299,226 @@ mod tests {
     }
 
     #[test]
-    fn multiplication_and_division() {
+    fn multiplication_and_division() {
+        parse_as_expression(
+            &["1*2", "  1   *  2    ", "1 · 2", "1 × 2"],
+            binop!(scalar!(1.0), Mul, scalar!(2.0)),
+        );
+
+        parse_as_expression(
+            &["1/2", "1 per 2", "1÷2"],
+            binop!(scalar!(1.0), Div, scalar!(2.0)),
+        );
+
+        parse_as_expression(
+            &["1/2/3", "(1/2)/3", "1 per 2 per 3"],
+            binop!(binop!(scalar!(1.0), Div, scalar!(2.0)), Div, scalar!(3.0)),
+        );
+
+        should_fail(&["1*@", "1*", "1 per", "÷", "×"]);
+
+        // 'per' is higher-precedence than '/'
+        parse_as_expression(
+            &["1 / meter per second"],
+            binop!(
+                scalar!(1.0),
+                Div,
+                binop!(identifier!("meter"), Div, identifier!("second"))
+            ),
+        );
+    }
+
+    #[test]
+    fn of() {
+        parse_as_expression(
+            &["15 % of 80 kg"],
+            binop!(
+                binop!(scalar!(15.0), Mul, identifier!("%")),
+                Of,
+                binop!(scalar!(80.0), Mul, identifier!("kg"))
+            ),
+        );
+
+        // 'of' is lower-precedence than 'per', but higher than '*' and '+'
+        parse_as_expression(
+            &["2 * 10 % of 5 kg per day + 1"],
+            binop!(
+                binop!(
+                    scalar!(2.0),
+                    Mul,
+                    binop!(
+                        binop!(scalar!(10.0), Mul, identifier!("%")),
+                        Of,
+                        binop!(
+                            binop!(scalar!(5.0), Mul, identifier!("kg")),
+                            Div,
+                            identifier!("day")
+                        )
+                    )
+                ),
+                Add,
+                scalar!(1.0)
+            ),
+        );
+
+        // `of` is not a keyword
+        parse_as_expression(&["2 of"], binop!(scalar!(2.0), Mul, identifier!("of")));
+        parse_as_expression(&["of + 1"], binop!(identifier!("of"), Add, scalar!(1.0)));
+        parse_as_expression(
+            &["of of of"],
+            binop!(identifier!("of"), Of, identifier!("of")),
+        );
+    }
+
+    #[test]
+    fn addition_subtraction_multiplication_division_precedence() {
+        parse_as_expression(
+            &["5+3*2", "5+(3*2)"],
+            binop!(scalar!(5.0), Add, binop!(scalar!(3.0), Mul, scalar!(2.0))),
+        );
+
+        parse_as_expression(
+            &["5/3*2", "(5/3)*2"],
+            binop!(binop!(scalar!(5.0), Div, scalar!(3.0)), Mul, scalar!(2.0)),
+        );
+
+        should_fail(&["3+*4", "3*/4", "(3+4", "3+4)", "3+(", "()", "(3+)4"])
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        parse_as_expression(
+            &["1 2", "  1     2    "],
+            binop!(scalar!(1.0), Mul, scalar!(2.0)),
+        );
+
+        parse_as_expression(
+            &["2 meter"],
+            binop!(scalar!(2.0), Mul, identifier!("meter")),
+        );
+    }
+
+    #[test]
+    fn implicit_multiplication_with_parentheses() {
         parse_as_expression(
-            &["1*2", "  1   *  2    ", "1 · 2", "1 × 2"],
-            binop!(scalar!(1.0), Mul, scalar!(2.0)),
+            &["2(3+4)", "2 (3 + 4)", "2*(3+4)"],
+            binop!(scalar!(2.0), Mul, binop!(scalar!(3.0), Add, scalar!(4.0))),
         );
 
         parse_as_expression(
-            &["1/2", "1 per 2", "1÷2"],
-            binop!(scalar!(1.0), Div, scalar!(2.0)),
+            &["(a)(b)", "(a) (b)", "a (b)"],
+            binop!(identifier!("a"), Mul, identifier!("b")),
         );
 
         parse_as_expression(
-            &["1/2/3", "(1/2)/3", "1 per 2 per 3"],
-            binop!(binop!(scalar!(1.0), Div, scalar!(2.0)), Div, scalar!(3.0)),
+            &["3 m (1 + x)"],
+            binop!(
+                binop!(scalar!(3.0), Mul, identifier!("m")),
+                Mul,
+                binop!(scalar!(1.0), Add, identifier!("x"))
+            ),
         );
 
-        should_fail(&["1*@", "1*", "1 per", "÷", "×"]);
-
-        // 'per' is higher-precedence than '/'
+        // Implicit multiplication binds stronger than division, but weaker than powers
         parse_as_expression(
-            &["1 / meter per second"],
+            &["1/2(3)^2"],
             binop!(
                 scalar!(1.0),
                 Div,
-                binop!(identifier!("meter"), Div, identifier!("second"))
+                binop!(scalar!(2.0), Mul, binop!(scalar!(3.0), Power, scalar!(2.0)))
             ),
         );
-    }
 
-    #[test]
-    fn addition_subtraction_multiplication_division_precedence() {
+        // A name that is directly followed by parentheses is still a function call
         parse_as_expression(
-            &["5+3*2", "5+(3*2)"],
-            binop!(scalar!(5.0), Add, binop!(scalar!(3.0), Mul, scalar!(2.0))),
+            &["f(x)"],
+            Expression::FunctionCall(
+                Span::dummy(),
+                Span::dummy(),
+                Box::new(identifier!("f")),
+                vec![identifier!("x")],
+            ),
         );
-
         parse_as_expression(
-            &["5/3*2", "(5/3)*2"],
-            binop!(binop!(scalar!(5.0), Div, scalar!(3.0)), Mul, scalar!(2.0)),
+            &["2 f(x)"],
+            binop!(
+                scalar!(2.0),
+                Mul,
+                Expression::FunctionCall(
+                    Span::dummy(),
+                    Span::dummy(),
+                    Box::new(identifier!("f")),
+                    vec![identifier!("x")],
+                )
+            ),
         );
+    }
 
-        should_fail(&["3+*4", "3*/4", "(3+4", "3+4)", "3+(", "()", "(3+)4"])
+    #[test]
+    fn strict_multiplication() {
+        for input in ["2(3+4)", "(a)(b)", "3 m (1 + x)"] {
+            let Err((_, errors)) =
+                parse_with_strict_multiplication(input, 0, true, &Interner::default())
+            else {
+                panic!("Expected parse failure on {input:?}");
+            };
+            assert_eq!(
+                errors[0].kind,
+                ParseErrorKind::ImplicitMultiplicationWithParentheses
+            );
+        }
+
+        for input in ["2*(3+4)", "2 m", "f(x)", "(2 m) s", "f()()"] {
+            assert!(parse_with_strict_multiplication(input, 0, true, &Interner::default()).is_ok());
+        }
     }
 
     #[test]
-    fn implicit_multiplication() {
+    fn dms_literals() {
         parse_as_expression(
-            &["1 2", "  1     2    "],
-            binop!(scalar!(1.0), Mul, scalar!(2.0)),
+            &["12° 34′ 56″", "12°  34′  56″"],
+            binop!(
+                binop!(
+                    binop!(scalar!(12.0), Mul, identifier!("°")),
+                    Add,
+                    binop!(scalar!(34.0), Mul, identifier!("′"))
+                ),
+                Add,
+                binop!(scalar!(56.0), Mul, identifier!("″"))
+            ),
         );
 
         parse_as_expression(
-            &["2 meter"],
-            binop!(scalar!(2.0), Mul, identifier!("meter")),
+            &["12° 56″"],
+            binop!(
+                binop!(scalar!(12.0), Mul, identifier!("°")),
+                Add,
+                binop!(scalar!(56.0), Mul, identifier!("″"))
+            ),
+        );
+
+        parse_as_expression(
+            &["-12° 34′"],
+            negate!(binop!(
+                binop!(scalar!(12.0), Mul, identifier!("°")),
+                Add,
+                binop!(scalar!(34.0), Mul, identifier!("′"))
+            )),
+        );
+
+        // A single component and other orders are ordinary multiplications
+        parse_as_expression(&["30°"], binop!(scalar!(30.0), Mul, identifier!("°")));
+        parse_as_expression(
+            &["34′ 12°"],
+            binop!(
+                binop!(
+                    binop!(scalar!(34.0), Mul, identifier!("′")),
+                    Mul,
+                    scalar!(12.0)
+                ),
+                Mul,
+                identifier!("°")
+            ),
         );
     }
 
@@ -2373,7 +3599,13 @@ mod tests {
             ),
         );
 
-        should_fail(&["1²³", "2⁻", "2⁻3", "²", "²3"]);
+        parse_as_expression(&["2¹⁰", "2^10"], binop!(scalar!(2.0), Power, scalar!(10.0)));
+
+        parse_as_expression(&["2⁻¹²"], binop!(scalar!(2.0), Power, scalar!(-12.0)));
+
+        parse_as_expression(&["2⁰"], binop!(scalar!(2.0), Power, scalar!(0.0)));
+
+        should_fail(&["2⁻", "2⁻3", "²", "²3", "2²⁻¹"]);
     }
 
     #[test]
@@ -2579,6 +3811,136 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unit_alias() {
+        parse_as(
+            &["unit alias mtr = meter", "unit alias mtr=meter"],
+            Statement::DefineUnitAlias {
+                alias_span: Span::dummy(),
+                alias: "mtr".into(),
+                accepts_prefix: None,
+                unit_span: Span::dummy(),
+                unit: "meter".into(),
+            },
+        );
+
+        parse_as(
+            &["unit alias M: short = meter"],
+            Statement::DefineUnitAlias {
+                alias_span: Span::dummy(),
+                alias: "M".into(),
+                accepts_prefix: Some(AcceptsPrefix::only_short()),
+                unit_span: Span::dummy(),
+                unit: "meter".into(),
+            },
+        );
+
+        // A unit can still be called 'alias'
+        parse_as(
+            &["unit alias: Length"],
+            Statement::DefineBaseUnit(
+                Span::dummy(),
+                "alias".into(),
+                Some(TypeExpression::TypeIdentifier(
+                    Span::dummy(),
+                    "Length".into(),
+                )),
+                vec![],
+            ),
+        );
+
+        should_fail_with(
+            &["unit alias mtr meter", "unit alias mtr: short meter"],
+            ParseErrorKind::ExpectedEqualInUnitAlias,
+        );
+        should_fail_with(
+            &["unit alias mtr = 2 meter", "unit alias mtr ="],
+            ParseErrorKind::ExpectedUnitInUnitAlias,
+        );
+        should_fail_with(
+            &["@metric_prefixes\nunit alias mtr = meter"],
+            ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+        );
+
+        parse_as(
+            &[
+                "@alias(mtr: short) unit meter",
+                "@alias(mtr: short)\nunit meter",
+            ],
+            Statement::DefineUnitAlias {
+                alias_span: Span::dummy(),
+                alias: "mtr".into(),
+                accepts_prefix: Some(AcceptsPrefix::only_short()),
+                unit_span: Span::dummy(),
+                unit: "meter".into(),
+            },
+        );
+
+        should_fail_with(
+            &[
+                "@alias(mtr) unit meter = 2 m",
+                "@alias(mtr) unit meter: Length",
+                "@alias(mtr) let x = 2",
+                "@alias(mtr) @alias(M) unit meter",
+            ],
+            ParseErrorKind::AliasDecoratorUsedOnUnsuitableKind,
+        );
+        should_fail_with(
+            &["@alias(mtr) @metric_prefixes unit meter"],
+            ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+        );
+        should_fail_with(
+            &["@alias mtr unit meter"],
+            ParseErrorKind::ExpectedLeftParenAfterDecorator,
+        );
+    }
+
+    #[test]
+    fn preferred_unit() {
+        parse_as(
+            &[
+                "set preferred_unit Velocity = km / h",
+                "set preferred_unit Velocity=km/h",
+            ],
+            Statement::SetPreferredUnit {
+                span: Span::dummy(),
+                dimension: TypeExpression::TypeIdentifier(Span::dummy(), "Velocity".into()),
+                expr: binop!(identifier!("km"), Div, identifier!("h")),
+            },
+        );
+
+        parse_as(
+            &["set preferred_unit Length / Time = mph"],
+            Statement::SetPreferredUnit {
+                span: Span::dummy(),
+                dimension: TypeExpression::Divide(
+                    Span::dummy(),
+                    Box::new(TypeExpression::TypeIdentifier(
+                        Span::dummy(),
+                        "Length".into(),
+                    )),
+                    Box::new(TypeExpression::TypeIdentifier(Span::dummy(), "Time".into())),
+                ),
+                expr: identifier!("mph"),
+            },
+        );
+
+        // 'set' is not a keyword
+        parse_as_expression(
+            &["set preferred"],
+            binop!(identifier!("set"), Mul, identifier!("preferred")),
+        );
+
+        should_fail_with(
+            &["set preferred_unit Velocity km/h"],
+            ParseErrorKind::ExpectedEqualInPreferredUnit,
+        );
+        should_fail_with(
+            &["@metric_prefixes\nset preferred_unit Velocity = km/h"],
+            ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+        );
+    }
+
     #[test]
     fn function_definition() {
         parse_as(
@@ -2802,7 +4164,7 @@ mod tests {
                 body: Some(identifier!("y")),
                 local_variables: vec![DefineVariable {
                     identifier_span: Span::dummy(),
-                    identifier: String::from("y"),
+                    identifier: "y".into(),
                     expr: binop!(identifier!("x"), Mul, scalar!(2.0)),
                     type_annotation: None,
                     decorators: vec![],
@@ -2825,14 +4187,14 @@ mod tests {
                 local_variables: vec![
                     DefineVariable {
                         identifier_span: Span::dummy(),
-                        identifier: String::from("y"),
+                        identifier: "y".into(),
                         expr: binop!(identifier!("x"), Add, identifier!("x")),
                         type_annotation: None,
                         decorators: vec![],
                     },
                     DefineVariable {
                         identifier_span: Span::dummy(),
-                        identifier: String::from("z"),
+                        identifier: "z".into(),
                         expr: binop!(identifier!("y"), Add, identifier!("x")),
                         type_annotation: None,
                         decorators: vec![],
@@ -3284,6 +4646,42 @@ mod tests {
             ),
         );
 
+        parse_as_expression(
+            &["\"\"\"\nline 1\n\"line 2\"\n\"\"\""],
+            Expression::String(
+                Span::dummy(),
+                vec![StringPart::Fixed("line 1\n\"line 2\"\n".into())],
+            ),
+        );
+
+        parse_as_expression(
+            &["\"\"\"pi = {pi}\"\"\""],
+            Expression::String(
+                Span::dummy(),
+                vec![
+                    StringPart::Fixed("pi = ".into()),
+                    StringPart::Interpolation {
+                        span: Span::dummy(),
+                        expr: Box::new(identifier!("pi")),
+                        format_specifiers: None,
+                    },
+                ],
+            ),
+        );
+
+        parse_as_expression(
+            &[r#"r"C:\new\{x}""#],
+            Expression::String(Span::dummy(), vec![StringPart::Fixed(r"C:\new\{x}".into())]),
+        );
+
+        parse_as_expression(
+            &[r#"r"""a "quoted" \d+""""#],
+            Expression::String(
+                Span::dummy(),
+                vec![StringPart::Fixed(r#"a "quoted" \d+"#.into())],
+            ),
+        );
+
         should_fail_with(&["\"test {1"], ParseErrorKind::UnterminatedString);
         should_fail_with(
             &[
@@ -3301,28 +4699,63 @@ mod tests {
             &["struct Foo { foo: Scalar, bar: Scalar }"],
             Statement::DefineStruct {
                 struct_name_span: Span::dummy(),
-                struct_name: "Foo".to_owned(),
+                struct_name: "Foo".into(),
+                type_parameters: vec![],
                 fields: vec![
                     (
                         Span::dummy(),
-                        "foo".to_owned(),
+                        "foo".into(),
                         TypeAnnotation::TypeExpression(TypeExpression::TypeIdentifier(
                             Span::dummy(),
-                            "Scalar".to_owned(),
+                            "Scalar".into(),
                         )),
                     ),
                     (
                         Span::dummy(),
-                        "bar".to_owned(),
+                        "bar".into(),
                         TypeAnnotation::TypeExpression(TypeExpression::TypeIdentifier(
                             Span::dummy(),
-                            "Scalar".to_owned(),
+                            "Scalar".into(),
                         )),
                     ),
                 ],
             },
         );
 
+        parse_as(
+            &["struct Estimate<D: Dim> { mean: D }"],
+            Statement::DefineStruct {
+                struct_name_span: Span::dummy(),
+                struct_name: "Estimate".into(),
+                type_parameters: vec![(Span::dummy(), "D".into(), Some(TypeParameterBound::Dim))],
+                fields: vec![(
+                    Span::dummy(),
+                    "mean".into(),
+                    TypeAnnotation::TypeExpression(TypeExpression::TypeIdentifier(
+                        Span::dummy(),
+                        "D".into(),
+                    )),
+                )],
+            },
+        );
+
+        parse_as(
+            &["let x: Estimate<Length> = e"],
+            Statement::DefineVariable(DefineVariable {
+                identifier_span: Span::dummy(),
+                identifier: "x".into(),
+                expr: identifier!("e"),
+                type_annotation: Some(TypeAnnotation::Struct(
+                    Span::dummy(),
+                    "Estimate".into(),
+                    vec![TypeAnnotation::TypeExpression(
+                        TypeExpression::TypeIdentifier(Span::dummy(), "Length".into()),
+                    )],
+                )),
+                decorators: vec![],
+            }),
+        );
+
         parse_as_expression(
             &["Foo {foo: 1, bar: 2}"],
             struct_! {
@@ -3342,9 +4775,82 @@ mod tests {
                     foo: scalar!(1.0),
                     bar: scalar!(2.0)
                 }),
-                "foo".to_owned(),
+                "foo".into(),
             ),
         );
+
+        parse_as_expression(
+            &[
+                "{ foo with bar: 1, baz.qux: 2 }",
+                "{foo with bar: 1, baz.qux: 2,}",
+                "{\n  foo with\n  bar: 1,\n  baz.qux: 2\n}",
+            ],
+            Expression::UpdateStruct {
+                full_span: Span::dummy(),
+                expr: Box::new(identifier!("foo")),
+                fields: vec![
+                    (Span::dummy(), vec!["bar".into()], scalar!(1.0)),
+                    (
+                        Span::dummy(),
+                        vec!["baz".into(), "qux".into()],
+                        scalar!(2.0),
+                    ),
+                ],
+            },
+        );
+
+        should_fail_with(
+            &["{ foo bar }"],
+            ParseErrorKind::ExpectedWithOrColonInBraces,
+        );
+        should_fail_with(&["{ foo with }"], ParseErrorKind::ExpectedFieldNameInStruct);
+        should_fail_with(
+            &["{ foo with bar 1 }"],
+            ParseErrorKind::ExpectedColonAfterFieldName,
+        );
+
+        let statements = parse("let Foo { foo, bar: b } = f", 0)
+            .expect("parse error")
+            .replace_spans();
+        assert_eq!(statements.len(), 3);
+        let Statement::DefineVariable(DefineVariable {
+            identifier,
+            expr,
+            type_annotation: Some(_),
+            ..
+        }) = &statements[0]
+        else {
+            panic!("Expected a definition of the destructured struct");
+        };
+        assert_eq!(*expr, identifier!("f"));
+        for (statement, (binding, field)) in
+            statements[1..].iter().zip([("foo", "foo"), ("b", "bar")])
+        {
+            assert_eq!(
+                *statement,
+                Statement::DefineVariable(DefineVariable {
+                    identifier_span: Span::dummy(),
+                    identifier: binding.into(),
+                    expr: Expression::AccessField(
+                        Span::dummy(),
+                        Span::dummy(),
+                        Box::new(identifier!(identifier.clone())),
+                        field.into(),
+                    ),
+                    type_annotation: None,
+                    decorators: vec![],
+                })
+            );
+        }
+
+        should_fail_with(
+            &["let Foo { foo } f"],
+            ParseErrorKind::ExpectedEqualAfterStructPattern,
+        );
+        should_fail_with(
+            &["@aliases(x) let Foo { foo } = f"],
+            ParseErrorKind::DecoratorUsedOnUnsuitableKind,
+        );
     }
 
     #[test]
@@ -3387,6 +4893,103 @@ mod tests {
         should_fail_with(&["[1,\n2,\n,\n"], ParseErrorKind::ExpectedPrimary);
     }
 
+    #[test]
+    fn dicts() {
+        let string = |s: &str| Expression::String(Span::dummy(), vec![StringPart::Fixed(s.into())]);
+
+        parse_as_expression(&["{}", "{\n}"], Expression::Dict(Span::dummy(), vec![]));
+        parse_as_expression(
+            &["{\"a\": 1}", "{ \"a\": 1, }", "{\n  \"a\":\n 1\n}"],
+            Expression::Dict(Span::dummy(), vec![(string("a"), scalar!(1.0))]),
+        );
+        parse_as_expression(
+            &["{\"a\": 1, key: [2]}"],
+            Expression::Dict(
+                Span::dummy(),
+                vec![
+                    (string("a"), scalar!(1.0)),
+                    (identifier!("key"), list!(scalar!(2.0))),
+                ],
+            ),
+        );
+
+        should_fail_with(
+            &["{\"a\": 1 \"b\": 2}"],
+            ParseErrorKind::ExpectedCommaOrRightCurlyInDict,
+        );
+        should_fail_with(
+            &["{\"a\": 1, \"b\"}"],
+            ParseErrorKind::ExpectedColonAfterDictKey,
+        );
+        should_fail_with(&["{\"a\": }"], ParseErrorKind::ExpectedPrimary);
+    }
+
+    #[test]
+    fn ranges() {
+        let range = |start, end, step: Option<Expression>, inclusive| Expression::Range {
+            span_op: Span::dummy(),
+            start: Box::new(start),
+            end: Box::new(end),
+            step: step.map(Box::new),
+            inclusive,
+        };
+
+        parse_as_expression(
+            &["1..=10", "1 ..= 10"],
+            range(scalar!(1.0), scalar!(10.0), None, true),
+        );
+        parse_as_expression(
+            &["1..10", "1 .. 10"],
+            range(scalar!(1.0), scalar!(10.0), None, false),
+        );
+        parse_as_expression(
+            &["0.5..=x step 0.25"],
+            range(scalar!(0.5), identifier!("x"), Some(scalar!(0.25)), true),
+        );
+        parse_as_expression(
+            &["0 s ..= 10 s step 0.1 s"],
+            range(
+                binop!(scalar!(0.0), Mul, identifier!("s")),
+                binop!(scalar!(10.0), Mul, identifier!("s")),
+                Some(binop!(scalar!(0.1), Mul, identifier!("s"))),
+                true,
+            ),
+        );
+        parse_as_expression(
+            &["1 + 1 ..= 2 * 3"],
+            range(
+                binop!(scalar!(1.0), Add, scalar!(1.0)),
+                binop!(scalar!(2.0), Mul, scalar!(3.0)),
+                None,
+                true,
+            ),
+        );
+
+        // `step` is not a keyword
+        parse_as_expression(&["2 step"], binop!(scalar!(2.0), Mul, identifier!("step")));
+        parse_as_expression(
+            &["0 ..= step step step"],
+            range(
+                scalar!(0.0),
+                identifier!("step"),
+                Some(identifier!("step")),
+                true,
+            ),
+        );
+        parse_as_expression(
+            &["0 ..= (2 step) step 2 step"],
+            range(
+                scalar!(0.0),
+                binop!(scalar!(2.0), Mul, identifier!("step")),
+                Some(binop!(scalar!(2.0), Mul, identifier!("step"))),
+                true,
+            ),
+        );
+
+        should_fail_with(&["1 ..= "], ParseErrorKind::ExpectedPrimary);
+        should_fail_with(&["1 ..= 10 step"], ParseErrorKind::ExpectedPrimary);
+    }
+
     #[test]
     fn accumulate_errors() {
         // error on the last character of a line
@@ -0,0 +1,340 @@
+//! Plot renderers that do not need a browser: Unicode braille plots for the
+//! terminal and standalone SVG images.
+
+use crate::number::Number;
+
+const TEXT_PLOT_COLUMNS: usize = 60;
+const TEXT_PLOT_ROWS: usize = 15;
+
+const SVG_WIDTH: f64 = 640.0;
+const SVG_HEIGHT: f64 = 400.0;
+const SVG_MARGIN: f64 = 60.0;
+
+/// The ranges of the axes. Degenerate ranges are widened, so that a constant
+/// function or a single data point can still be shown.
+struct Bounds {
+    x_min: f64,
+    x_max: f64,
+    y_min: f64,
+    y_max: f64,
+}
+
+fn widened(min: f64, max: f64) -> (f64, f64) {
+    if min < max {
+        (min, max)
+    } else if min == 0.0 {
+        (-1.0, 1.0)
+    } else {
+        (min - 0.1 * min.abs(), max + 0.1 * max.abs())
+    }
+}
+
+impl Bounds {
+    fn new(xs: &[f64], ys: &[f64]) -> Option<Self> {
+        let finite_points = xs
+            .iter()
+            .zip(ys)
+            .filter(|(x, y)| x.is_finite() && y.is_finite());
+
+        let (mut x_min, mut x_max) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut y_min, mut y_max) = (f64::INFINITY, f64::NEG_INFINITY);
+        for (&x, &y) in finite_points {
+            x_min = x_min.min(x);
+            x_max = x_max.max(x);
+            y_min = y_min.min(y);
+            y_max = y_max.max(y);
+        }
+
+        if x_min > x_max {
+            return None;
+        }
+
+        let (x_min, x_max) = widened(x_min, x_max);
+        let (y_min, y_max) = widened(y_min, y_max);
+        Some(Bounds {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        })
+    }
+
+    /// Map a point to the unit square, with the origin at the bottom left
+    fn normalize(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.x_min) / (self.x_max - self.x_min),
+            (y - self.y_min) / (self.y_max - self.y_min),
+        )
+    }
+}
+
+fn axis_label(label: &str, unit: &str) -> String {
+    match (label.is_empty(), unit.is_empty()) {
+        (_, true) => label.to_string(),
+        (true, false) => format!("[{unit}]"),
+        (false, false) => format!("{label} [{unit}]"),
+    }
+}
+
+fn format_tick(value: f64) -> String {
+    Number::from_f64(value).pretty_print()
+}
+
+/// A canvas of braille characters, each of which holds 2 × 4 dots
+struct BrailleCanvas {
+    columns: usize,
+    rows: usize,
+    cells: Vec<u8>,
+}
+
+impl BrailleCanvas {
+    fn new(columns: usize, rows: usize) -> Self {
+        BrailleCanvas {
+            columns,
+            rows,
+            cells: vec![0; columns * rows],
+        }
+    }
+
+    fn width(&self) -> usize {
+        2 * self.columns
+    }
+
+    fn height(&self) -> usize {
+        4 * self.rows
+    }
+
+    /// Set the dot at (x, y), where y = 0 is the top row
+    fn set(&mut self, x: usize, y: usize) {
+        const DOT_BITS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+        if x < self.width() && y < self.height() {
+            self.cells[(y / 4) * self.columns + x / 2] |= DOT_BITS[y % 4][x % 2];
+        }
+    }
+
+    /// Draw a line between two dots with Bresenham's algorithm
+    fn line(&mut self, (x0, y0): (i64, i64), (x1, y1): (i64, i64)) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+
+        loop {
+            self.set(x as usize, y as usize);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let double_error = 2 * error;
+            if double_error >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if double_error <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn row(&self, row: usize) -> String {
+        self.cells[row * self.columns..(row + 1) * self.columns]
+            .iter()
+            .map(|&bits| char::from_u32(0x2800 + bits as u32).unwrap())
+            .collect()
+    }
+}
+
+/// Render a line plot with braille characters. Non-finite values interrupt the
+/// line.
+pub fn text_line_plot(
+    xs: &[f64],
+    ys: &[f64],
+    x_label: &str,
+    x_unit: &str,
+    y_label: &str,
+    y_unit: &str,
+) -> Option<String> {
+    let bounds = Bounds::new(xs, ys)?;
+
+    let mut canvas = BrailleCanvas::new(TEXT_PLOT_COLUMNS, TEXT_PLOT_ROWS);
+    let (width, height) = (canvas.width(), canvas.height());
+    let to_dot = |x: f64, y: f64| {
+        let (x, y) = bounds.normalize(x, y);
+        (
+            (x * (width - 1) as f64).round() as i64,
+            ((1.0 - y) * (height - 1) as f64).round() as i64,
+        )
+    };
+
+    let mut previous = None;
+    for (&x, &y) in xs.iter().zip(ys) {
+        if !(x.is_finite() && y.is_finite()) {
+            previous = None;
+            continue;
+        }
+        let dot = to_dot(x, y);
+        canvas.line(previous.unwrap_or(dot), dot);
+        previous = Some(dot);
+    }
+
+    let y_max = format_tick(bounds.y_max);
+    let y_min = format_tick(bounds.y_min);
+    let margin = y_max.chars().count().max(y_min.chars().count());
+
+    let mut output = String::new();
+    let y_axis_label = axis_label(y_label, y_unit);
+    if !y_axis_label.is_empty() {
+        output += &format!("{y_axis_label}\n");
+    }
+    for row in 0..canvas.rows {
+        let (tick, axis) = match row {
+            0 => (y_max.as_str(), '┤'),
+            _ if row == canvas.rows - 1 => (y_min.as_str(), '┤'),
+            _ => ("", '│'),
+        };
+        output += &format!("{tick:>margin$} {axis}{}\n", canvas.row(row));
+    }
+    output += &format!("{:margin$} └{}\n", "", "─".repeat(canvas.columns));
+
+    let x_min = format_tick(bounds.x_min);
+    let x_max = format_tick(bounds.x_max);
+    let gap = (canvas.columns + 1).saturating_sub(x_min.chars().count());
+    output += &format!("{:margin$} {x_min}{x_max:>gap$}", "");
+
+    let x_axis_label = axis_label(x_label, x_unit);
+    if !x_axis_label.is_empty() {
+        let width = margin + 2 + canvas.columns;
+        output += &format!("\n{x_axis_label:>width$}");
+    }
+
+    Some(output)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Render a line plot as a standalone SVG image
+pub fn svg_line_plot(
+    xs: &[f64],
+    ys: &[f64],
+    x_label: &str,
+    x_unit: &str,
+    y_label: &str,
+    y_unit: &str,
+) -> Option<String> {
+    let bounds = Bounds::new(xs, ys)?;
+
+    let (left, right) = (SVG_MARGIN, SVG_WIDTH - SVG_MARGIN / 2.0);
+    let (top, bottom) = (SVG_MARGIN / 2.0, SVG_HEIGHT - SVG_MARGIN);
+    let to_svg = |x: f64, y: f64| {
+        let (x, y) = bounds.normalize(x, y);
+        (left + x * (right - left), bottom - y * (bottom - top))
+    };
+
+    // Every run of finite points becomes its own polyline
+    let mut segments: Vec<Vec<String>> = vec![vec![]];
+    for (&x, &y) in xs.iter().zip(ys) {
+        if x.is_finite() && y.is_finite() {
+            let (x, y) = to_svg(x, y);
+            segments.last_mut().unwrap().push(format!("{x:.2},{y:.2}"));
+        } else if !segments.last().unwrap().is_empty() {
+            segments.push(vec![]);
+        }
+    }
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SVG_WIDTH}\" height=\"{SVG_HEIGHT}\" \
+         viewBox=\"0 0 {SVG_WIDTH} {SVG_HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">\n"
+    );
+    svg += &format!(
+        "  <path d=\"M {left} {top} V {bottom} H {right}\" fill=\"none\" stroke=\"black\"/>\n"
+    );
+    for segment in segments.iter().filter(|s| !s.is_empty()) {
+        svg += &format!(
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"#0077ff\" stroke-width=\"1.5\"/>\n",
+            segment.join(" ")
+        );
+    }
+
+    let tick = |value: f64| escape_xml(&format_tick(value));
+    svg += &format!(
+        "  <text x=\"{}\" y=\"{top}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+        left - 5.0,
+        tick(bounds.y_max)
+    );
+    svg += &format!(
+        "  <text x=\"{}\" y=\"{bottom}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+        left - 5.0,
+        tick(bounds.y_min)
+    );
+    svg += &format!(
+        "  <text x=\"{left}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+        bottom + 18.0,
+        tick(bounds.x_min)
+    );
+    svg += &format!(
+        "  <text x=\"{right}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+        bottom + 18.0,
+        tick(bounds.x_max)
+    );
+
+    let x_axis_label = axis_label(x_label, x_unit);
+    if !x_axis_label.is_empty() {
+        svg += &format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            (left + right) / 2.0,
+            bottom + 40.0,
+            escape_xml(&x_axis_label)
+        );
+    }
+    let y_axis_label = axis_label(y_label, y_unit);
+    if !y_axis_label.is_empty() {
+        let (x, y) = (left - 40.0, (top + bottom) / 2.0);
+        svg += &format!(
+            "  <text x=\"{x}\" y=\"{y}\" text-anchor=\"middle\" transform=\"rotate(-90 {x} {y})\">{}</text>\n",
+            escape_xml(&y_axis_label)
+        );
+    }
+
+    svg += "</svg>";
+    Some(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn braille_canvas() {
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.line((0, 0), (3, 3));
+        assert_eq!(canvas.row(0), "⠑⢄");
+    }
+
+    #[test]
+    fn text_plot_layout() {
+        let plot = text_line_plot(&[0.0, 1.0], &[0.0, 2.0], "time", "s", "", "m").unwrap();
+        let lines: Vec<_> = plot.lines().collect();
+
+        assert_eq!(lines[0], "[m]");
+        assert!(lines[1].starts_with("2 ┤"));
+        assert!(lines[TEXT_PLOT_ROWS].starts_with("0 ┤"));
+        assert!(lines[TEXT_PLOT_ROWS + 2].starts_with("  0"));
+        assert!(lines[TEXT_PLOT_ROWS + 2].ends_with('1'));
+        assert!(lines[TEXT_PLOT_ROWS + 3].ends_with("time [s]"));
+    }
+
+    #[test]
+    fn degenerate_inputs() {
+        assert!(text_line_plot(&[], &[], "", "", "", "").is_none());
+        assert!(text_line_plot(&[f64::NAN], &[1.0], "", "", "", "").is_none());
+        assert!(text_line_plot(&[1.0], &[1.0], "", "", "", "").is_some());
+        assert!(svg_line_plot(&[0.0, 1.0], &[5.0, 5.0], "", "", "a<b", "")
+            .unwrap()
+            .contains("a&lt;b"));
+    }
+}
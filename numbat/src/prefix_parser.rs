@@ -1,17 +1,19 @@
 use std::collections::HashMap;
 
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
 use crate::span::Span;
+use crate::symbol::Symbol;
+use crate::warning::Warning;
 use crate::{name_resolution::NameResolutionError, prefix::Prefix};
 
 static PREFIXES: OnceLock<Vec<(&'static str, &'static [&'static str], Prefix)>> = OnceLock::new();
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PrefixParserResult {
-    Identifier(String),
+    Identifier,
     /// Span, prefix, unit name in source (e.g. 'm'), full unit name (e.g. 'meter')
-    UnitIdentifier(Span, Prefix, String, String),
+    UnitIdentifier(Span, Prefix, Symbol, Symbol),
 }
 
 type Result<T> = std::result::Result<T, NameResolutionError>;
@@ -52,23 +54,43 @@ impl AcceptsPrefix {
     }
 }
 
-#[derive(Debug, Clone)]
+/// What happens if a new definition uses a name that already refers to a unit
+/// (including prefixed forms like `am` for attometer), or if a new unit would
+/// take over the name of an existing identifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShadowingPolicy {
+    /// Reject the definition with an identifier clash error
+    #[default]
+    Error,
+    /// Let the new definition shadow the existing one and emit a warning
+    Warn,
+    /// Let the new definition shadow the existing one
+    Allow,
+}
+
+#[derive(Debug)]
 struct UnitInfo {
     definition_span: Span,
     accepts_prefix: AcceptsPrefix,
     metric_prefixes: bool,
     binary_prefixes: bool,
-    full_name: String,
+    full_name: Symbol,
 }
 
 #[derive(Debug, Clone)]
 pub struct PrefixParser {
-    units: HashMap<String, UnitInfo>,
+    units: HashMap<Symbol, Arc<UnitInfo>>,
     // This is the exact same information as in the "units" hashmap, only faster to iterate over.
     // TODO: maybe use an external crate for this (e.g. indexmap?)
-    units_vec: Vec<(String, UnitInfo)>,
+    units_vec: Vec<(Symbol, Arc<UnitInfo>)>,
 
-    other_identifiers: HashMap<String, Span>,
+    other_identifiers: HashMap<Symbol, Span>,
+
+    /// Names that have been taken over by a definition that shadows an
+    /// existing unit or identifier. These take precedence over everything else.
+    shadowed: HashMap<Symbol, PrefixParserResult>,
+    shadowing_policy: ShadowingPolicy,
+    warnings: Vec<Warning>,
 
     reserved_identifiers: &'static [&'static str],
 }
@@ -79,10 +101,18 @@ impl PrefixParser {
             units: HashMap::new(),
             units_vec: Vec::new(),
             other_identifiers: HashMap::new(),
+            shadowed: HashMap::new(),
+            shadowing_policy: ShadowingPolicy::default(),
+            warnings: vec![],
             reserved_identifiers: &["_", "ans"],
         }
     }
 
+    /// Set the policy that is used for the following definitions
+    pub fn set_shadowing_policy(&mut self, policy: ShadowingPolicy) {
+        self.shadowing_policy = policy;
+    }
+
     fn prefixes() -> &'static [(&'static str, &'static [&'static str], Prefix)] {
         PREFIXES.get_or_init(|| {
             vec![
@@ -97,11 +127,9 @@ impl PrefixParser {
                 ("nano", &["n"], Prefix::Metric(-9)),
                 (
                     "micro",
-                    &[
-                        "µ", // Micro Sign (U+00B5)
-                        "μ", // Greek Small Letter Mu (U+03BC)
-                        "u",
-                    ],
+                    // The micro sign (U+00B5) is normalized to the Greek
+                    // letter mu (U+03BC) by the tokenizer
+                    &["μ", "u"],
                     Prefix::Metric(-6),
                 ),
                 ("milli", &["m"], Prefix::Metric(-3)),
@@ -149,95 +177,259 @@ impl PrefixParser {
         }
     }
 
+    /// Returns `true` if the name is already in use, but the policy allows
+    /// the new definition to shadow it.
     fn ensure_name_is_available(
-        &self,
-        name: &str,
+        &mut self,
+        name: &Symbol,
         conflict_span: Span,
         clash_with_other_identifiers: bool,
-    ) -> Result<()> {
-        if self.reserved_identifiers.contains(&name) {
+    ) -> Result<bool> {
+        if self.reserved_identifiers.contains(&name.as_str()) {
             return Err(NameResolutionError::ReservedIdentifier(conflict_span));
         }
 
-        if clash_with_other_identifiers {
-            if let Some(original_span) = self.other_identifiers.get(name) {
-                return Err(self.identifier_clash_error(name, conflict_span, *original_span));
-            }
-        }
+        let other_identifier = if clash_with_other_identifiers {
+            self.other_identifiers.get(name).copied()
+        } else {
+            None
+        };
+        let original_span = other_identifier.or_else(|| match self.parse(name) {
+            PrefixParserResult::Identifier => None,
+            PrefixParserResult::UnitIdentifier(original_span, _, _, _) => Some(original_span),
+        });
+
+        let Some(original_span) = original_span else {
+            return Ok(false);
+        };
 
-        match self.parse(name) {
-            PrefixParserResult::Identifier(_) => Ok(()),
-            PrefixParserResult::UnitIdentifier(original_span, _, _, _) => {
+        match self.shadowing_policy {
+            ShadowingPolicy::Error => {
                 Err(self.identifier_clash_error(name, conflict_span, original_span))
             }
+            ShadowingPolicy::Warn => {
+                self.warnings.push(Warning::Shadowing {
+                    span: conflict_span,
+                    name: name.into(),
+                    original_span,
+                });
+                Ok(true)
+            }
+            ShadowingPolicy::Allow => Ok(true),
         }
     }
 
     pub fn add_unit(
         &mut self,
-        unit_name: &str,
+        unit_name: &Symbol,
         accepts_prefix: AcceptsPrefix,
         metric: bool,
         binary: bool,
-        full_name: &str,
+        full_name: &Symbol,
         definition_span: Span,
     ) -> Result<()> {
-        self.ensure_name_is_available(unit_name, definition_span, true)?;
+        let mut shadowed_names = vec![];
+
+        if self.ensure_name_is_available(unit_name, definition_span, true)? {
+            shadowed_names.push((unit_name.clone(), Prefix::none()));
+        }
 
         for (prefix_long, prefixes_short, prefix) in Self::prefixes() {
             if !(prefix.is_metric() && metric || prefix.is_binary() && binary) {
                 continue;
             }
 
+            let mut names = vec![];
             if accepts_prefix.long {
-                self.ensure_name_is_available(
-                    &format!("{prefix_long}{unit_name}"),
-                    definition_span,
-                    true,
-                )?;
+                names.push(Symbol::from(format!("{prefix_long}{unit_name}")));
             }
             if accepts_prefix.short {
                 for prefix_short in *prefixes_short {
-                    self.ensure_name_is_available(
-                        &format!("{prefix_short}{unit_name}"),
-                        definition_span,
-                        true,
-                    )?;
+                    names.push(Symbol::from(format!("{prefix_short}{unit_name}")));
+                }
+            }
+
+            for name in names {
+                if self.ensure_name_is_available(&name, definition_span, true)? {
+                    shadowed_names.push((name, *prefix));
                 }
             }
         }
 
-        let unit_info = UnitInfo {
+        self.insert_unit(
+            unit_name,
+            accepts_prefix,
+            metric,
+            binary,
+            full_name,
+            definition_span,
+        );
+
+        for (name, prefix) in shadowed_names {
+            self.other_identifiers.remove(&name);
+            self.shadowed.insert(
+                name,
+                PrefixParserResult::UnitIdentifier(
+                    definition_span,
+                    prefix,
+                    unit_name.clone(),
+                    full_name.clone(),
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Add another name for an existing (unprefixed) unit. The alias accepts the
+    /// same kinds of prefixes as the original unit.
+    pub fn add_alias(
+        &mut self,
+        alias: &Symbol,
+        accepts_prefix: AcceptsPrefix,
+        unit_name: &Symbol,
+        definition_span: Span,
+        unit_span: Span,
+    ) -> Result<()> {
+        let Some(info) = self.unit_info(unit_name) else {
+            return Err(NameResolutionError::UnknownUnit(
+                unit_span,
+                unit_name.to_string(),
+            ));
+        };
+
+        self.add_unit(
+            alias,
+            accepts_prefix,
+            info.metric_prefixes,
+            info.binary_prefixes,
+            &info.full_name,
+            definition_span,
+        )
+    }
+
+    /// Like `add_alias`, but without checking for clashes with existing
+    /// identifiers. Aliases of unknown units are ignored.
+    pub fn add_alias_unchecked(
+        &mut self,
+        alias: &Symbol,
+        accepts_prefix: AcceptsPrefix,
+        unit_name: &Symbol,
+    ) {
+        if let Some(info) = self.unit_info(unit_name) {
+            self.add_unit_unchecked(
+                alias,
+                accepts_prefix,
+                info.metric_prefixes,
+                info.binary_prefixes,
+                &info.full_name,
+            );
+        }
+    }
+
+    /// Add a unit without checking for clashes with existing identifiers. This
+    /// is meant for units that have already been validated by another parser.
+    pub fn add_unit_unchecked(
+        &mut self,
+        unit_name: &Symbol,
+        accepts_prefix: AcceptsPrefix,
+        metric: bool,
+        binary: bool,
+        full_name: &Symbol,
+    ) {
+        self.insert_unit(
+            unit_name,
+            accepts_prefix,
+            metric,
+            binary,
+            full_name,
+            Span::dummy(),
+        );
+    }
+
+    fn insert_unit(
+        &mut self,
+        unit_name: &Symbol,
+        accepts_prefix: AcceptsPrefix,
+        metric: bool,
+        binary: bool,
+        full_name: &Symbol,
+        definition_span: Span,
+    ) {
+        // A unit that is defined again (which is only possible if shadowing is
+        // allowed) replaces the previous definition
+        self.remove_unit(unit_name);
+
+        let unit_info = Arc::new(UnitInfo {
             definition_span,
             accepts_prefix,
             metric_prefixes: metric,
             binary_prefixes: binary,
-            full_name: full_name.into(),
-        };
-        self.units.insert(unit_name.into(), unit_info.clone());
-        self.units_vec.push((unit_name.into(), unit_info));
+            full_name: full_name.clone(),
+        });
+        self.units.insert(unit_name.clone(), unit_info.clone());
+        self.units_vec.push((unit_name.clone(), unit_info));
+    }
 
-        Ok(())
+    fn unit_info(&self, unit_name: &Symbol) -> Option<Arc<UnitInfo>> {
+        self.units.get(unit_name).cloned()
+    }
+
+    /// Remove a unit name (but not the other aliases of the same unit), along
+    /// with all of its prefixed forms.
+    fn remove_unit(&mut self, unit_name: &Symbol) {
+        if self.units.remove(unit_name).is_some() {
+            self.units_vec.retain(|(name, _)| name != unit_name);
+            self.shadowed.retain(|_, result| {
+                !matches!(result, PrefixParserResult::UnitIdentifier(_, _, name, _) if name == unit_name)
+            });
+        }
     }
 
-    pub fn add_other_identifier(&mut self, identifier: &str, definition_span: Span) -> Result<()> {
-        self.ensure_name_is_available(identifier, definition_span, false)?;
+    pub fn add_other_identifier(
+        &mut self,
+        identifier: &Symbol,
+        definition_span: Span,
+    ) -> Result<()> {
+        if self.ensure_name_is_available(identifier, definition_span, false)? {
+            self.remove_unit(identifier);
+            self.shadowed
+                .insert(identifier.clone(), PrefixParserResult::Identifier);
+        }
 
         self.other_identifiers
-            .insert(identifier.into(), definition_span);
+            .insert(identifier.clone(), definition_span);
         Ok(())
     }
 
-    pub fn parse(&self, input: &str) -> PrefixParserResult {
+    /// Return the warnings about shadowed names that have been collected so
+    /// far, and reset the list.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    pub fn extend_warnings(&mut self, warnings: Vec<Warning>) {
+        self.warnings.extend(warnings);
+    }
+
+    /// Find out whether `input` refers to a (possibly prefixed) unit. This
+    /// does not allocate.
+    pub fn parse(&self, input: &Symbol) -> PrefixParserResult {
+        if let Some(result) = self.shadowed.get(input) {
+            return result.clone();
+        }
+
         if let Some(info) = self.units.get(input) {
             return PrefixParserResult::UnitIdentifier(
                 info.definition_span,
                 Prefix::none(),
-                input.into(),
+                input.clone(),
                 info.full_name.clone(),
             );
         }
 
+        let input = input.as_str();
+
         for (unit_name, info) in &self.units_vec {
             if !input.ends_with(unit_name.as_str()) {
                 continue;
@@ -250,12 +442,12 @@ impl PrefixParser {
                 if info.accepts_prefix.long
                     && (is_metric && info.metric_prefixes || is_binary && info.binary_prefixes)
                     && input.starts_with(prefix_long)
-                    && &input[prefix_long.len()..] == unit_name
+                    && &input[prefix_long.len()..] == unit_name.as_str()
                 {
                     return PrefixParserResult::UnitIdentifier(
                         info.definition_span,
                         *prefix,
-                        unit_name.to_string(),
+                        unit_name.clone(),
                         info.full_name.clone(),
                     );
                 }
@@ -263,20 +455,21 @@ impl PrefixParser {
                 if info.accepts_prefix.short
                     && (is_metric && info.metric_prefixes || is_binary && info.binary_prefixes)
                     && prefixes_short.iter().any(|prefix_short| {
-                        input.starts_with(prefix_short) && &input[prefix_short.len()..] == unit_name
+                        input.starts_with(prefix_short)
+                            && &input[prefix_short.len()..] == unit_name.as_str()
                     })
                 {
                     return PrefixParserResult::UnitIdentifier(
                         info.definition_span,
                         *prefix,
-                        unit_name.to_string(),
+                        unit_name.clone(),
                         info.full_name.clone(),
                     );
                 }
             }
         }
 
-        PrefixParserResult::Identifier(input.into())
+        PrefixParserResult::Identifier
     }
 }
 
@@ -284,270 +477,242 @@ impl PrefixParser {
 mod tests {
     use super::*;
 
+    fn unit_identifier(prefix: Prefix, unit_name: &str, full_name: &str) -> PrefixParserResult {
+        PrefixParserResult::UnitIdentifier(
+            Span::dummy(),
+            prefix,
+            Symbol::new(unit_name),
+            Symbol::new(full_name),
+        )
+    }
+
     #[test]
     fn basic() {
         let mut prefix_parser = PrefixParser::new();
         prefix_parser
             .add_unit(
-                "meter",
+                &"meter".into(),
                 AcceptsPrefix::only_long(),
                 true,
                 false,
-                "meter",
+                &"meter".into(),
                 Span::dummy(),
             )
             .unwrap();
         prefix_parser
             .add_unit(
-                "m",
+                &"m".into(),
                 AcceptsPrefix::only_short(),
                 true,
                 false,
-                "meter",
+                &"meter".into(),
                 Span::dummy(),
             )
             .unwrap();
 
         prefix_parser
             .add_unit(
-                "byte",
+                &"byte".into(),
                 AcceptsPrefix::only_long(),
                 true,
                 true,
-                "byte",
+                &"byte".into(),
                 Span::dummy(),
             )
             .unwrap();
         prefix_parser
             .add_unit(
-                "B",
+                &"B".into(),
                 AcceptsPrefix::only_short(),
                 true,
                 true,
-                "byte",
+                &"byte".into(),
                 Span::dummy(),
             )
             .unwrap();
 
         prefix_parser
             .add_unit(
-                "me",
+                &"me".into(),
                 AcceptsPrefix::only_short(),
                 false,
                 false,
-                "me",
+                &"me".into(),
                 Span::dummy(),
             )
             .unwrap();
 
         assert_eq!(
-            prefix_parser.parse("meter"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::none(),
-                "meter".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"meter".into()),
+            unit_identifier(Prefix::none(), "meter", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("m"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::none(),
-                "m".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"m".into()),
+            unit_identifier(Prefix::none(), "m", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("byte"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::none(),
-                "byte".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"byte".into()),
+            unit_identifier(Prefix::none(), "byte", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("B"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::none(),
-                "B".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"B".into()),
+            unit_identifier(Prefix::none(), "B", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("me"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::none(),
-                "me".into(),
-                "me".into()
-            )
+            prefix_parser.parse(&"me".into()),
+            unit_identifier(Prefix::none(), "me", "me")
         );
 
         assert_eq!(
-            prefix_parser.parse("kilometer"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::kilo(),
-                "meter".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"kilometer".into()),
+            unit_identifier(Prefix::kilo(), "meter", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("millimeter"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::milli(),
-                "meter".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"millimeter".into()),
+            unit_identifier(Prefix::milli(), "meter", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("kilobyte"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::kilo(),
-                "byte".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"kilobyte".into()),
+            unit_identifier(Prefix::kilo(), "byte", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("kibibyte"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::kibi(),
-                "byte".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"kibibyte".into()),
+            unit_identifier(Prefix::kibi(), "byte", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("mebibyte"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::mebi(),
-                "byte".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"mebibyte".into()),
+            unit_identifier(Prefix::mebi(), "byte", "byte")
         );
 
         assert_eq!(
-            prefix_parser.parse("km"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::kilo(),
-                "m".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"km".into()),
+            unit_identifier(Prefix::kilo(), "m", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("mm"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::milli(),
-                "m".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"mm".into()),
+            unit_identifier(Prefix::milli(), "m", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("µm"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::micro(),
-                "m".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"μm".into()),
+            unit_identifier(Prefix::micro(), "m", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("μm"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::micro(),
-                "m".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"um".into()),
+            unit_identifier(Prefix::micro(), "m", "meter")
         );
         assert_eq!(
-            prefix_parser.parse("um"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::micro(),
-                "m".into(),
-                "meter".into()
-            )
+            prefix_parser.parse(&"kB".into()),
+            unit_identifier(Prefix::kilo(), "B", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("kB"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::kilo(),
-                "B".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"MB".into()),
+            unit_identifier(Prefix::mega(), "B", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("MB"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::mega(),
-                "B".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"KiB".into()),
+            unit_identifier(Prefix::kibi(), "B", "byte")
         );
         assert_eq!(
-            prefix_parser.parse("KiB"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::kibi(),
-                "B".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"MiB".into()),
+            unit_identifier(Prefix::mebi(), "B", "byte")
+        );
+
+        assert_eq!(
+            prefix_parser.parse(&"kilom".into()),
+            PrefixParserResult::Identifier
         );
         assert_eq!(
-            prefix_parser.parse("MiB"),
-            PrefixParserResult::UnitIdentifier(
-                Span::dummy(),
-                Prefix::mebi(),
-                "B".into(),
-                "byte".into()
-            )
+            prefix_parser.parse(&"kilome".into()),
+            PrefixParserResult::Identifier
+        );
+        assert_eq!(
+            prefix_parser.parse(&"kme".into()),
+            PrefixParserResult::Identifier
         );
 
         assert_eq!(
-            prefix_parser.parse("kilom"),
-            PrefixParserResult::Identifier("kilom".into())
+            prefix_parser.parse(&"kilomete".into()),
+            PrefixParserResult::Identifier
         );
         assert_eq!(
-            prefix_parser.parse("kilome"),
-            PrefixParserResult::Identifier("kilome".into())
+            prefix_parser.parse(&"kilometerr".into()),
+            PrefixParserResult::Identifier
         );
+
         assert_eq!(
-            prefix_parser.parse("kme"),
-            PrefixParserResult::Identifier("kme".into())
+            prefix_parser.parse(&"foometer".into()),
+            PrefixParserResult::Identifier
         );
 
         assert_eq!(
-            prefix_parser.parse("kilomete"),
-            PrefixParserResult::Identifier("kilomete".into())
+            prefix_parser.parse(&"kibimeter".into()),
+            PrefixParserResult::Identifier
         );
         assert_eq!(
-            prefix_parser.parse("kilometerr"),
-            PrefixParserResult::Identifier("kilometerr".into())
+            prefix_parser.parse(&"Kim".into()),
+            PrefixParserResult::Identifier
+        );
+    }
+
+    #[test]
+    fn shadowing() {
+        let mut prefix_parser = PrefixParser::new();
+        prefix_parser
+            .add_unit(
+                &"m".into(),
+                AcceptsPrefix::only_short(),
+                true,
+                false,
+                &"meter".into(),
+                Span::dummy(),
+            )
+            .unwrap();
+
+        assert!(prefix_parser
+            .add_other_identifier(&"km".into(), Span::dummy())
+            .is_err());
+
+        prefix_parser.set_shadowing_policy(ShadowingPolicy::Warn);
+        prefix_parser
+            .add_other_identifier(&"km".into(), Span::dummy())
+            .unwrap();
+        assert_eq!(
+            prefix_parser.parse(&"km".into()),
+            PrefixParserResult::Identifier
         );
+        assert_eq!(prefix_parser.take_warnings().len(), 1);
 
+        // Other prefixed forms still refer to the unit
         assert_eq!(
-            prefix_parser.parse("foometer"),
-            PrefixParserResult::Identifier("foometer".into())
+            prefix_parser.parse(&"mm".into()),
+            unit_identifier(Prefix::milli(), "m", "meter")
         );
 
+        // Shadowing the unit itself also removes its prefixed forms
+        prefix_parser.set_shadowing_policy(ShadowingPolicy::Allow);
+        prefix_parser
+            .add_other_identifier(&"m".into(), Span::dummy())
+            .unwrap();
         assert_eq!(
-            prefix_parser.parse("kibimeter"),
-            PrefixParserResult::Identifier("kibimeter".into())
+            prefix_parser.parse(&"mm".into()),
+            PrefixParserResult::Identifier
         );
+        assert!(prefix_parser.take_warnings().is_empty());
+
+        // A new unit can take over the name of an identifier
+        prefix_parser
+            .add_unit(
+                &"km".into(),
+                AcceptsPrefix::none(),
+                false,
+                false,
+                &"kilometre".into(),
+                Span::dummy(),
+            )
+            .unwrap();
         assert_eq!(
-            prefix_parser.parse("Kim"),
-            PrefixParserResult::Identifier("Kim".into())
+            prefix_parser.parse(&"km".into()),
+            unit_identifier(Prefix::none(), "km", "kilometre")
         );
     }
 }
@@ -2,8 +2,9 @@ use crate::{
     ast::{DefineVariable, Expression, Statement, StringPart},
     decorator::{self, Decorator},
     name_resolution::NameResolutionError,
-    prefix_parser::{PrefixParser, PrefixParserResult},
+    prefix_parser::{AcceptsPrefix, PrefixParser, PrefixParserResult, ShadowingPolicy},
     span::Span,
+    symbol::Symbol,
 };
 
 type Result<T> = std::result::Result<T, NameResolutionError>;
@@ -16,6 +17,10 @@ pub(crate) struct Transformer {
     pub function_names: Vec<String>,
     pub unit_names: Vec<Vec<String>>,
     pub dimension_names: Vec<String>,
+
+    /// What to do if a definition clashes with an existing unit. This can be
+    /// overwritten for single definitions with the `@shadowing(…)` decorator.
+    pub shadowing_policy: ShadowingPolicy,
 }
 
 impl Transformer {
@@ -26,6 +31,7 @@ impl Transformer {
             function_names: vec![],
             unit_names: vec![],
             dimension_names: vec![],
+            shadowing_policy: ShadowingPolicy::default(),
         }
     }
 
@@ -73,6 +79,7 @@ impl Transformer {
                     .collect(),
             ),
             expr @ Expression::Boolean(_, _) => expr,
+            expr @ Expression::DateTime(_, _) => expr,
             Expression::Condition(span, condition, then, else_) => Expression::Condition(
                 span,
                 Box::new(self.transform_expression(*condition)),
@@ -117,6 +124,18 @@ impl Transformer {
                 Box::new(self.transform_expression(*expr)),
                 attr,
             ),
+            Expression::UpdateStruct {
+                full_span,
+                expr,
+                fields,
+            } => Expression::UpdateStruct {
+                full_span,
+                expr: Box::new(self.transform_expression(*expr)),
+                fields: fields
+                    .into_iter()
+                    .map(|(span, path, value)| (span, path, self.transform_expression(value)))
+                    .collect(),
+            },
             Expression::List(span, elements) => Expression::List(
                 span,
                 elements
@@ -124,20 +143,49 @@ impl Transformer {
                     .map(|e| self.transform_expression(e))
                     .collect(),
             ),
+            Expression::Dict(span, entries) => Expression::Dict(
+                span,
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (self.transform_expression(k), self.transform_expression(v)))
+                    .collect(),
+            ),
+            Expression::Range {
+                span_op,
+                start,
+                end,
+                step,
+                inclusive,
+            } => Expression::Range {
+                span_op,
+                start: Box::new(self.transform_expression(*start)),
+                end: Box::new(self.transform_expression(*end)),
+                step: step.map(|s| Box::new(self.transform_expression(*s))),
+                inclusive,
+            },
             hole @ Expression::TypedHole(_) => hole,
         }
     }
 
+    /// Set up the prefix parser for a definition with the given decorators
+    fn use_shadowing_policy_for(&mut self, decorators: &[Decorator]) {
+        self.prefix_parser.set_shadowing_policy(
+            decorator::shadowing_policy(decorators).unwrap_or(self.shadowing_policy),
+        );
+    }
+
     fn has_decorator(decorators: &[Decorator], decorator: Decorator) -> bool {
         decorators.iter().any(|d| d == &decorator)
     }
 
     pub(crate) fn register_name_and_aliases(
         &mut self,
-        name: &String,
+        name: &Symbol,
         decorators: &[Decorator],
         conflict_span: Span,
     ) -> Result<()> {
+        self.use_shadowing_policy_for(decorators);
+
         let mut unit_names = vec![];
         let metric_prefixes = Self::has_decorator(decorators, Decorator::MetricPrefixes);
         let binary_prefixes = Self::has_decorator(decorators, Decorator::BinaryPrefixes);
@@ -172,8 +220,9 @@ impl Transformer {
         } = define_variable;
 
         for (name, _) in decorator::name_and_aliases(&identifier, &decorators) {
-            self.variable_names.push(name.clone());
+            self.variable_names.push(name.to_string());
         }
+        self.use_shadowing_policy_for(&decorators);
         self.prefix_parser
             .add_other_identifier(&identifier, identifier_span)?;
         Ok(DefineVariable {
@@ -223,7 +272,8 @@ impl Transformer {
                 return_type_annotation,
                 decorators,
             } => {
-                self.function_names.push(function_name.clone());
+                self.function_names.push(function_name.to_string());
+                self.use_shadowing_policy_for(&decorators);
                 self.prefix_parser
                     .add_other_identifier(&function_name, function_name_span)?;
 
@@ -236,22 +286,31 @@ impl Transformer {
                 //   fn foo(t: Time) -> Time = t    # not okay: shadows 't' for ton
                 //
                 let mut fn_body_transformer = self.clone();
+                fn_body_transformer.shadowing_policy =
+                    decorator::shadowing_policy(&decorators).unwrap_or(self.shadowing_policy);
+                // Pending warnings are reported by the parent transformer
+                fn_body_transformer.prefix_parser.take_warnings();
                 for (param_span, param, _) in &parameters {
                     fn_body_transformer
                         .prefix_parser
                         .add_other_identifier(param, *param_span)?;
                 }
 
+                let local_variables = local_variables
+                    .into_iter()
+                    .map(|def| fn_body_transformer.transform_define_variable(def))
+                    .collect::<Result<_>>()?;
+                let body = body.map(|expr| fn_body_transformer.transform_expression(expr));
+                self.prefix_parser
+                    .extend_warnings(fn_body_transformer.prefix_parser.take_warnings());
+
                 Statement::DefineFunction {
                     function_name_span,
                     function_name,
                     type_parameters,
                     parameters,
-                    body: body.map(|expr| self.transform_expression(expr)),
-                    local_variables: local_variables
-                        .into_iter()
-                        .map(|def| self.transform_define_variable(def))
-                        .collect::<Result<_>>()?,
+                    body,
+                    local_variables,
                     return_type_annotation,
                     decorators,
                 }
@@ -259,14 +318,58 @@ impl Transformer {
             Statement::DefineStruct {
                 struct_name_span,
                 struct_name,
+                type_parameters,
                 fields,
             } => Statement::DefineStruct {
                 struct_name_span,
                 struct_name,
+                type_parameters,
                 fields,
             },
+            Statement::DefineUnitAlias {
+                alias_span,
+                alias,
+                accepts_prefix,
+                unit_span,
+                unit,
+            } => {
+                self.prefix_parser
+                    .set_shadowing_policy(self.shadowing_policy);
+                self.prefix_parser.add_alias(
+                    &alias,
+                    accepts_prefix.unwrap_or(AcceptsPrefix::only_long()),
+                    &unit,
+                    alias_span,
+                    unit_span,
+                )?;
+                if let Some(names) = self
+                    .unit_names
+                    .iter_mut()
+                    .find(|names| names.iter().any(|name| name == unit.as_str()))
+                {
+                    names.push(alias.to_string());
+                    names.sort();
+                }
+
+                Statement::DefineUnitAlias {
+                    alias_span,
+                    alias,
+                    accepts_prefix,
+                    unit_span,
+                    unit,
+                }
+            }
+            Statement::SetPreferredUnit {
+                span,
+                dimension,
+                expr,
+            } => Statement::SetPreferredUnit {
+                span,
+                dimension,
+                expr: self.transform_expression(expr),
+            },
             Statement::DefineDimension(name_span, name, dexprs) => {
-                self.dimension_names.push(name.clone());
+                self.dimension_names.push(name.to_string());
                 Statement::DefineDimension(name_span, name, dexprs)
             }
             Statement::ProcedureCall(span, procedure, args) => Statement::ProcedureCall(
@@ -276,7 +379,7 @@ impl Transformer {
                     .map(|arg| self.transform_expression(arg))
                     .collect(),
             ),
-            statement @ Statement::ModuleImport(_, _) => statement,
+            statement @ Statement::ModuleImport(_, _, _) => statement,
         })
     }
 
@@ -1,8 +1,44 @@
-use crate::markup::Markup;
+use crate::latex_formatter::LatexFormatter;
+use crate::markup::{Formatter, Markup};
+use crate::mathml_formatter::MathMlFormatter;
+use crate::typst_formatter::TypstFormatter;
+
+/// Math markup languages that pretty-printed values and expressions can be
+/// rendered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathFormat {
+    /// LaTeX (math mode), using `siunitx` for quantities and units
+    Latex,
+    /// Typst math
+    Typst,
+    /// A MathML `<math>` element
+    MathMl,
+}
+
+impl MathFormat {
+    pub fn format(self, markup: &Markup) -> String {
+        match self {
+            MathFormat::Latex => LatexFormatter.format(markup, false),
+            MathFormat::Typst => TypstFormatter.format(markup, false),
+            MathFormat::MathMl => MathMlFormatter.format(markup, false),
+        }
+    }
+}
 
 pub trait PrettyPrint {
     /// Pretty prints with default options.
     fn pretty_print(&self) -> Markup;
+
+    /// Renders the pretty-printed form in the given math markup language.
+    fn to_math(&self, format: MathFormat) -> String {
+        format.format(&self.pretty_print())
+    }
+
+    /// Renders the pretty-printed form as LaTeX (math mode), using `siunitx`
+    /// for quantities and units.
+    fn to_latex(&self) -> String {
+        self.to_math(MathFormat::Latex)
+    }
 }
 
 impl PrettyPrint for bool {
@@ -1,7 +1,7 @@
 use crate::arithmetic::{Exponent, Power, Rational};
 use crate::number::Number;
 use crate::pretty_print::PrettyPrint;
-use crate::unit::{is_multiple_of, Unit, UnitFactor};
+use crate::unit::{is_multiple_of, PreferredUnits, Unit, UnitFactor};
 
 use itertools::Itertools;
 use num_rational::Ratio;
@@ -25,6 +25,9 @@ pub struct Quantity {
     value: Number,
     unit: Unit,
     can_simplify: bool,
+    /// Whether the value is shown with fractions like `1 ½`, see
+    /// [`Quantity::with_fractional_display`]
+    fractional_display: bool,
 }
 
 impl Quantity {
@@ -33,6 +36,7 @@ impl Quantity {
             value,
             unit,
             can_simplify: true,
+            fractional_display: false,
         }
     }
 
@@ -41,6 +45,7 @@ impl Quantity {
             value: Number::from_f64(value),
             unit,
             can_simplify: true,
+            fractional_display: false,
         }
     }
 
@@ -49,6 +54,13 @@ impl Quantity {
         self
     }
 
+    /// Show the value with a vulgar fraction, like `1 ½` or `⅔`, if it is a
+    /// (non-integer) multiple of an eighth or a third
+    pub(crate) fn with_fractional_display(mut self) -> Self {
+        self.fractional_display = true;
+        self
+    }
+
     pub fn from_scalar(value: f64) -> Quantity {
         Quantity::new_f64(value, Unit::scalar())
     }
@@ -140,6 +152,37 @@ impl Quantity {
         }
     }
 
+    /// Like [`Quantity::full_simplify`], but quantities with a dimension that
+    /// has a preferred unit are converted to that unit instead. Otherwise, the
+    /// result is expressed in terms of one of the `derived_units` (like `N`
+    /// for `kg·m/s²`) if possible, see [`simplify_to_derived_unit`]. Results of
+    /// an explicit conversion are left alone.
+    pub fn full_simplify_with(
+        &self,
+        preferred_units: &PreferredUnits,
+        derived_units: &[Unit],
+    ) -> Self {
+        if !self.can_simplify {
+            return self.clone();
+        }
+
+        if let Some(preferred_unit) = preferred_units.get(&self.unit) {
+            if let Ok(q) = self.convert_to(preferred_unit) {
+                return q;
+            }
+        }
+
+        let simplified = self.full_simplify();
+
+        if let Some(derived_unit) = simplify_to_derived_unit(&simplified.unit, derived_units) {
+            if let Ok(q) = simplified.convert_to(&derived_unit) {
+                return q;
+            }
+        }
+
+        simplified
+    }
+
     pub fn full_simplify(&self) -> Self {
         if !self.can_simplify {
             return self.clone();
@@ -229,6 +272,11 @@ impl Quantity {
         Quantity::new(self.value * factor, simplified_unit)
     }
 
+    /// The numerical value of this quantity, expressed in the given unit.
+    pub fn value_in(&self, unit: &Unit) -> Result<f64> {
+        Ok(self.convert_to(unit)?.value.to_f64())
+    }
+
     pub fn as_scalar(&self) -> Result<Number> {
         Ok(self.convert_to(&Unit::scalar())?.value)
     }
@@ -320,6 +368,58 @@ impl std::ops::Neg for Quantity {
     }
 }
 
+/// Try to write `unit` in terms of one of the `derived_units`, either on its
+/// own (`kg·m/s²` → `N`) or multiplied with one of the factors of `unit`
+/// (`kg·m²/s³·K` → `W·K`). A candidate is only considered if it has fewer factors
+/// than `unit` and if the conversion does not change the numerical value, so
+/// `mA·h` is not turned into `C`. Candidates with fewer factors are preferred,
+/// then those that come first in `derived_units`.
+///
+/// Products that already contain one of the `derived_units` are left alone,
+/// since the unit was chosen deliberately: `N·m` is usually a torque and not
+/// an energy, and `V·A` is used for apparent power.
+fn simplify_to_derived_unit(unit: &Unit, derived_units: &[Unit]) -> Option<Unit> {
+    let unit = unit.canonicalized();
+    let num_factors = unit.iter().count();
+    if num_factors < 2 || derived_units.is_empty() {
+        return None;
+    }
+
+    let is_product = unit.iter().all(|f| f.exponent > Rational::zero());
+    let contains_derived_unit = unit.iter().any(|f| {
+        derived_units
+            .iter()
+            .any(|derived_unit| derived_unit.iter().any(|d| d.unit_id == f.unit_id))
+    });
+    if is_product && contains_derived_unit {
+        return None;
+    }
+
+    let (base_unit, factor) = unit.to_base_unit_representation();
+    let preserves_value = |candidate: &Unit| {
+        let (candidate_base_unit, candidate_factor) = candidate.to_base_unit_representation();
+        candidate_base_unit == base_unit
+            && (factor.to_f64() / candidate_factor.to_f64() - 1.0).abs() < 1e-12
+    };
+
+    if let Some(derived_unit) = derived_units.iter().find(|u| preserves_value(u)) {
+        return Some(derived_unit.clone());
+    }
+
+    if num_factors > 2 {
+        for derived_unit in derived_units {
+            for unit_factor in unit.iter() {
+                let candidate = derived_unit.clone() * Unit::from_factor(unit_factor.clone());
+                if preserves_value(&candidate) {
+                    return Some(candidate);
+                }
+            }
+        }
+    }
+
+    None
+}
+
 impl PartialEq for Quantity {
     fn eq(&self, other: &Self) -> bool {
         if let Ok(other_converted) = other.convert_to(self.unit()) {
@@ -351,7 +451,13 @@ impl Quantity {
     fn pretty_print_with_options(&self, options: Option<FmtFloatConfig>) -> crate::markup::Markup {
         use crate::markup;
 
-        let formatted_number = self.unsafe_value().pretty_print_with_options(options);
+        let fraction = if self.fractional_display && options.is_none() {
+            vulgar_fraction(self.unsafe_value().to_f64())
+        } else {
+            None
+        };
+        let formatted_number =
+            fraction.unwrap_or_else(|| self.unsafe_value().pretty_print_with_options(options));
 
         let unit_str = format!("{}", self.unit());
 
@@ -381,6 +487,38 @@ impl Quantity {
     }
 }
 
+/// Write a number like 1.5 as `1 ½`, if it is (very close to) a non-integer
+/// multiple of an eighth or a third
+fn vulgar_fraction(x: f64) -> Option<String> {
+    const FRACTIONS: [(f64, &str); 9] = [
+        (1.0 / 8.0, "⅛"),
+        (1.0 / 4.0, "¼"),
+        (1.0 / 3.0, "⅓"),
+        (3.0 / 8.0, "⅜"),
+        (1.0 / 2.0, "½"),
+        (5.0 / 8.0, "⅝"),
+        (2.0 / 3.0, "⅔"),
+        (3.0 / 4.0, "¾"),
+        (7.0 / 8.0, "⅞"),
+    ];
+
+    if !x.is_finite() || x.abs() >= 1e6 {
+        return None;
+    }
+
+    let whole = x.abs().trunc();
+    let (_, glyph) = FRACTIONS
+        .iter()
+        .find(|(fraction, _)| (x.abs() - whole - fraction).abs() < 1e-9)?;
+    let sign = if x < 0.0 { "-" } else { "" };
+
+    Some(if whole == 0.0 {
+        format!("{sign}{glyph}")
+    } else {
+        format!("{sign}{whole} {glyph}")
+    })
+}
+
 impl std::fmt::Display for Quantity {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use crate::markup::{Formatter, PlainTextFormatter};
@@ -594,6 +732,88 @@ mod tests {
         }
     }
 
+    #[test]
+    fn full_simplify_with_preferred_units() {
+        let mut preferred_units = PreferredUnits::default();
+        preferred_units.set(Unit::kph());
+
+        {
+            let q = Quantity::new_f64(10.0, Unit::meter() / Unit::second());
+            let simplified = q.full_simplify_with(&preferred_units, &[]);
+            assert_eq!(simplified.unit(), &Unit::kph());
+            assert_eq!(simplified, q);
+        }
+        {
+            let q = Quantity::new_f64(2.0, Unit::meter() * Unit::second() * Unit::meter());
+            let simplified = q.full_simplify_with(&preferred_units, &[]);
+            assert_eq!(simplified.unit(), &(Unit::meter().powi(2) * Unit::second()));
+        }
+        {
+            let q = Quantity::new_f64(10.0, Unit::meter() / Unit::second()).no_simplify();
+            let simplified = q.full_simplify_with(&preferred_units, &[]);
+            assert_eq!(simplified.unit(), &(Unit::meter() / Unit::second()));
+        }
+
+        preferred_units.set(Unit::mile() / Unit::hour());
+        {
+            let q = Quantity::new_f64(10.0, Unit::meter() / Unit::second());
+            let simplified = q.full_simplify_with(&preferred_units, &[]);
+            assert_eq!(simplified.unit(), &(Unit::mile() / Unit::hour()));
+        }
+    }
+
+    #[test]
+    fn full_simplify_with_derived_units() {
+        let preferred_units = PreferredUnits::default();
+        let derived_units = [Unit::newton(), Unit::joule(), Unit::watt()];
+        let simplify = |q: &Quantity| q.full_simplify_with(&preferred_units, &derived_units);
+
+        {
+            let q = Quantity::new_f64(
+                2.0,
+                Unit::kilogram() * Unit::meter() / Unit::second().powi(2),
+            );
+            assert_eq!(simplify(&q).unit(), &Unit::newton());
+            assert_eq!(simplify(&q), q);
+        }
+        {
+            let q = Quantity::new_f64(
+                2.0,
+                Unit::kilogram() * Unit::meter().powi(2) / Unit::second().powi(3),
+            );
+            assert_eq!(simplify(&q).unit(), &Unit::watt());
+        }
+        {
+            let q = Quantity::new_f64(2.0, Unit::joule() / Unit::second());
+            assert_eq!(simplify(&q).unit(), &Unit::watt());
+        }
+        {
+            let q = Quantity::new_f64(
+                2.0,
+                Unit::kilogram() * Unit::meter().powi(2) / Unit::second().powi(3) * Unit::kelvin(),
+            );
+            assert_eq!(simplify(&q).unit(), &(Unit::watt() * Unit::kelvin()));
+            assert_eq!(simplify(&q), q);
+        }
+        {
+            // Products of derived units are left alone
+            let q = Quantity::new_f64(2.0, Unit::newton() * Unit::meter());
+            assert_eq!(simplify(&q).unit(), q.unit());
+
+            let q = Quantity::new_f64(2.0, Unit::newton() * Unit::meter() * Unit::hour());
+            assert_eq!(simplify(&q).unit(), q.unit());
+        }
+        {
+            // This would change the numerical value
+            let q = Quantity::new_f64(2.0, Unit::gram() * Unit::meter() / Unit::second().powi(2));
+            assert_eq!(simplify(&q).unit(), q.unit());
+        }
+        {
+            let q = Quantity::new_f64(2.0, Unit::meter() / Unit::second());
+            assert_eq!(simplify(&q).unit(), q.unit());
+        }
+    }
+
     #[test]
     fn si_compliant_pretty_printing() {
         //  See: https://en.wikipedia.org/wiki/International_System_of_Units
@@ -0,0 +1,265 @@
+//! This module defines the lazily evaluated ranges behind expressions like
+//! `1 ..= 10` or `0 s .. 1 s step 0.1 s`. A range has the type of a list, but
+//! it only stores its bounds. Elements are computed on demand, so `len`,
+//! `head` and `tail` are `O(1)` and a range is never materialized unless it is
+//! used like an ordinary list (e.g. by `cons`).
+
+use crate::{quantity::Quantity, unit::Unit, value::Value, RuntimeError};
+
+/// Ranges with more elements can not be indexed exactly with `f64`s
+const MAX_RANGE_LENGTH: f64 = 9_007_199_254_740_992.0; // 2^53
+
+/// Longer ranges are displayed in abbreviated form
+const MAX_DISPLAYED_ELEMENTS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumbatRange {
+    start: f64,
+    step: f64,
+    /// The value of the last element, which is exactly the end of an
+    /// inclusive range if the end is hit (up to floating point errors)
+    last: f64,
+    unit: Unit,
+    /// The index of the first element that is left after calls to `tail`
+    offset: usize,
+    /// The total number of elements, including the ones skipped by `tail`
+    total: usize,
+}
+
+impl Eq for NumbatRange {}
+
+impl NumbatRange {
+    /// Create a new range. The end and the step need to have the same
+    /// dimension as the start, which is ensured by the type checker.
+    pub fn new(
+        start: Quantity,
+        end: Quantity,
+        step: Quantity,
+        inclusive: bool,
+    ) -> Result<Self, RuntimeError> {
+        let unit = start.unit().clone();
+        let in_unit = |q: Quantity| -> Result<f64, RuntimeError> {
+            Ok(q.convert_to(&unit)
+                .map_err(RuntimeError::QuantityError)?
+                .unsafe_value()
+                .to_f64())
+        };
+        let start = start.unsafe_value().to_f64();
+        let end = in_unit(end)?;
+        let step = in_unit(step)?;
+
+        if !(start.is_finite() && end.is_finite() && step.is_finite()) {
+            return Err(RuntimeError::InvalidRange(
+                "the bounds and the step need to be finite".into(),
+            ));
+        }
+        if step == 0.0 {
+            return Err(RuntimeError::InvalidRange(
+                "the step must not be zero".into(),
+            ));
+        }
+
+        // Allow for floating point errors, so that `0 ..= 1 step 0.1` ends at 1
+        let steps = (end - start) / step;
+        let nearest = steps.round();
+        let hits_end = (steps - nearest).abs() <= 1e-9 * nearest.abs().max(1.0);
+        let total = match (hits_end, inclusive) {
+            (true, true) => nearest + 1.0,
+            (true, false) => nearest,
+            (false, _) => steps.floor() + 1.0,
+        }
+        .max(0.0);
+
+        if total > MAX_RANGE_LENGTH {
+            return Err(RuntimeError::InvalidRange(format!(
+                "the range has too many elements ({total:e})"
+            )));
+        }
+        let total = total as usize;
+
+        let last = if hits_end && inclusive {
+            end
+        } else {
+            start + total.saturating_sub(1) as f64 * step
+        };
+
+        Ok(NumbatRange {
+            start,
+            step,
+            last,
+            unit,
+            offset: 0,
+            total,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.total - self.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn element(&self, index: usize) -> Value {
+        let value = if index + 1 == self.total {
+            self.last
+        } else {
+            self.start + index as f64 * self.step
+        };
+        Value::Quantity(Quantity::new_f64(value, self.unit.clone()))
+    }
+
+    pub fn head(&self) -> Option<Value> {
+        (!self.is_empty()).then(|| self.element(self.offset))
+    }
+
+    pub fn last(&self) -> Option<Value> {
+        (!self.is_empty()).then(|| self.element(self.total - 1))
+    }
+
+    /// Drop the first element of the range.
+    /// Return an error if the range is empty.
+    pub fn tail(&mut self) -> Result<(), RuntimeError> {
+        if self.is_empty() {
+            return Err(RuntimeError::EmptyList);
+        }
+        self.offset += 1;
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Value> + '_ {
+        (self.offset..self.total).map(|index| self.element(index))
+    }
+
+    /// Keep only the first `n` elements of the range.
+    pub fn take(&mut self, n: usize) {
+        if n < self.len() {
+            self.total = self.offset + n;
+            self.last = self.start + self.total.saturating_sub(1) as f64 * self.step;
+        }
+    }
+
+    /// Drop the first `n` elements of the range.
+    pub fn drop(&mut self, n: usize) {
+        self.offset += n.min(self.len());
+    }
+
+    /// The elements that are shown when the range is displayed. Long ranges
+    /// are abbreviated to their first elements and their last element, with
+    /// `None` standing in for the elements in between.
+    pub fn displayed_elements(&self) -> Vec<Option<Value>> {
+        if self.len() <= MAX_DISPLAYED_ELEMENTS {
+            return self.iter().map(Some).collect();
+        }
+        self.iter()
+            .take(MAX_DISPLAYED_ELEMENTS - 2)
+            .map(Some)
+            .chain([None, Some(self.element(self.total - 1))])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: f64, end: f64, step: f64, inclusive: bool) -> Vec<f64> {
+        NumbatRange::new(
+            Quantity::from_scalar(start),
+            Quantity::from_scalar(end),
+            Quantity::from_scalar(step),
+            inclusive,
+        )
+        .unwrap()
+        .iter()
+        .map(|v| v.unsafe_as_quantity().unsafe_value().to_f64())
+        .collect()
+    }
+
+    #[test]
+    fn elements() {
+        assert_eq!(range(1.0, 5.0, 1.0, true), [1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(range(1.0, 5.0, 1.0, false), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            range(0.0, 1.0, 0.3, false),
+            [0.0, 0.3, 0.6, 0.8999999999999999]
+        );
+        assert_eq!(range(5.0, 1.0, -2.0, true), [5.0, 3.0, 1.0]);
+        assert_eq!(range(1.0, 1.0, 1.0, true), [1.0]);
+        assert!(range(1.0, 1.0, 1.0, false).is_empty());
+        assert!(range(5.0, 1.0, 1.0, true).is_empty());
+
+        let tenths = range(0.0, 1.0, 0.1, true);
+        assert_eq!(tenths.len(), 11);
+        assert_eq!(tenths.last(), Some(&1.0));
+    }
+
+    #[test]
+    fn head_and_tail() {
+        let mut range = NumbatRange::new(
+            Quantity::from_scalar(1.0),
+            Quantity::from_scalar(1e15),
+            Quantity::from_scalar(1.0),
+            true,
+        )
+        .unwrap();
+        assert_eq!(range.len(), 1_000_000_000_000_000);
+
+        range.tail().unwrap();
+        assert_eq!(
+            range.head(),
+            Some(Value::Quantity(Quantity::from_scalar(2.0)))
+        );
+        assert_eq!(range.len(), 999_999_999_999_999);
+    }
+
+    #[test]
+    fn take_and_drop() {
+        let mut range = NumbatRange::new(
+            Quantity::from_scalar(0.0),
+            Quantity::from_scalar(1e12),
+            Quantity::from_scalar(5.0),
+            true,
+        )
+        .unwrap();
+
+        range.drop(2);
+        range.take(3);
+        let elements: Vec<f64> = range
+            .iter()
+            .map(|v| v.unsafe_as_quantity().unsafe_value().to_f64())
+            .collect();
+        assert_eq!(elements, [10.0, 15.0, 20.0]);
+
+        range.take(0);
+        assert!(range.is_empty());
+    }
+
+    #[test]
+    fn displayed_elements() {
+        let range = NumbatRange::new(
+            Quantity::from_scalar(1.0),
+            Quantity::from_scalar(1e12),
+            Quantity::from_scalar(1.0),
+            true,
+        )
+        .unwrap();
+
+        let displayed = range.displayed_elements();
+        assert_eq!(displayed.len(), MAX_DISPLAYED_ELEMENTS);
+        assert_eq!(displayed[MAX_DISPLAYED_ELEMENTS - 2], None);
+        assert_eq!(
+            displayed[MAX_DISPLAYED_ELEMENTS - 1],
+            Some(Value::Quantity(Quantity::from_scalar(1e12)))
+        );
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        let scalar = Quantity::from_scalar;
+        assert!(NumbatRange::new(scalar(0.0), scalar(1.0), scalar(0.0), true).is_err());
+        assert!(NumbatRange::new(scalar(0.0), scalar(f64::INFINITY), scalar(1.0), true).is_err());
+        assert!(NumbatRange::new(scalar(0.0), scalar(1e300), scalar(1.0), true).is_err());
+    }
+}
@@ -1,13 +1,23 @@
-use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+};
 
 use crate::{
-    ast::Statement, module_importer::ModuleImporter, parser::parse, span::Span, ParseError,
+    ast::{DefineVariable, Expression, ImportKind, Statement, StringPart},
+    decorator::{self, Decorator},
+    module_importer::ModuleImporter,
+    parser::parse_with_strict_multiplication,
+    span::Span,
+    symbol::{Interner, Symbol},
+    ParseError,
 };
 
 use codespan_reporting::files::SimpleFiles;
 use thiserror::Error;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ModulePath(pub Vec<String>);
 
 impl std::fmt::Display for ModulePath {
@@ -39,6 +49,12 @@ pub enum ResolverError {
 
     #[error("{}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"))]
     ParseErrors(Vec<ParseError>),
+
+    #[error("Module '{1}' does not define '{2}'.")]
+    UnknownImportedName(Span, ModulePath, String),
+
+    #[error("'{1}' is private to module '{2}'.")]
+    PrivateItem(Span, String, ModulePath),
 }
 
 type Result<T> = std::result::Result<T, ResolverError>;
@@ -46,11 +62,30 @@ type Result<T> = std::result::Result<T, ResolverError>;
 #[derive(Clone)]
 pub struct Resolver {
     importer: Arc<dyn ModuleImporter>,
-    pub files: SimpleFiles<String, String>,
+    /// The source code of all inputs and modules. The code is reference-counted,
+    /// such that cloning a context (e.g. a context with a loaded prelude, once for
+    /// every request) does not copy it.
+    pub files: SimpleFiles<String, Arc<str>>,
     text_code_source_count: usize,
     internal_code_source_count: usize,
     imported_modules: Vec<ModulePath>,
+    /// For modules that have been imported with `use … as …` or `use … (…)`,
+    /// this maps the original names of functions and variables to the names
+    /// under which they have been defined.
+    renamed_items: HashMap<ModulePath, HashMap<Symbol, Symbol>>,
+    /// Names that have been defined as aliases for items of a module that had
+    /// already been imported before.
+    alias_definitions: HashSet<Symbol>,
+    /// Maps the internal names of private module items to the module that
+    /// defines them. These names may not appear in any source code.
+    private_items: HashMap<Symbol, (Symbol, ModulePath)>,
     codesources: HashMap<usize, CodeSource>,
+    /// Reject implicit multiplications with parentheses (like `2(3 + 4)`) in
+    /// everything but modules
+    pub strict_multiplication: bool,
+    /// Interns the identifiers of all parsed code. It is shared by all clones of
+    /// the resolver, i.e. by all copies of a context.
+    pub(crate) interner: Interner,
 }
 
 impl Resolver {
@@ -61,7 +96,12 @@ impl Resolver {
             text_code_source_count: 0,
             internal_code_source_count: 0,
             imported_modules: vec![],
+            renamed_items: HashMap::new(),
+            alias_definitions: HashSet::new(),
+            private_items: HashMap::new(),
             codesources: HashMap::new(),
+            strict_multiplication: false,
+            interner: Interner::default(),
         }
     }
 
@@ -86,7 +126,7 @@ impl Resolver {
             ),
         };
 
-        let id = self.files.add(code_source_name, content.to_string());
+        let id = self.files.add(code_source_name, Arc::from(content));
         self.codesources.insert(id, code_source);
 
         id
@@ -97,7 +137,19 @@ impl Resolver {
     }
 
     fn parse(&self, code: &str, code_source_id: usize) -> Result<Vec<Statement>> {
-        parse(code, code_source_id).map_err(|e| ResolverError::ParseErrors(e.1))
+        let strict_multiplication = self.strict_multiplication
+            && !matches!(
+                self.codesources.get(&code_source_id),
+                Some(CodeSource::Module(..))
+            );
+
+        parse_with_strict_multiplication(
+            code,
+            code_source_id,
+            strict_multiplication,
+            &self.interner,
+        )
+        .map_err(|e| ResolverError::ParseErrors(e.1))
     }
 
     fn inlining_pass(&mut self, program: &[Statement]) -> Result<Vec<Statement>> {
@@ -105,32 +157,180 @@ impl Resolver {
 
         for statement in program {
             match statement {
-                Statement::ModuleImport(span, module_path) => {
+                Statement::ModuleImport(span, module_path, kind) => {
                     if !self.imported_modules.contains(module_path) {
-                        if let Some((code, filesystem_path)) = self.importer.import(module_path) {
-                            self.imported_modules.push(module_path.clone());
-                            let code_source_id = self.add_code_source(
-                                CodeSource::Module(module_path.clone(), filesystem_path),
-                                &code,
-                            );
-
-                            let imported_program = self.parse(&code, code_source_id)?;
-                            let inlined_program = self.inlining_pass(&imported_program)?;
-                            for statement in inlined_program {
-                                new_program.push(statement);
-                            }
-                        } else {
-                            return Err(ResolverError::UnknownModule(*span, module_path.clone()));
-                        }
+                        self.import_module(*span, module_path, kind, &mut new_program)?;
+                    } else if !matches!(kind, ImportKind::All)
+                        || self.renamed_items.contains_key(module_path)
+                    {
+                        self.define_aliases(*span, module_path, kind, &mut new_program)?;
                     }
                 }
-                statement => new_program.push(statement.clone()),
+                statement => {
+                    self.check_private_access(statement)?;
+                    new_program.push(statement.clone());
+                }
             }
         }
 
         Ok(new_program)
     }
 
+    /// Make sure that the (internal) names of private items of other modules
+    /// are not used to bypass their privacy.
+    fn check_private_access(&self, statement: &Statement) -> Result<()> {
+        if self.private_items.is_empty() {
+            return Ok(());
+        }
+
+        match find_identifier(statement, &|name| self.private_items.contains_key(name)) {
+            Some((span, name)) => {
+                let (original_name, module_path) = self.private_items[name].clone();
+                Err(ResolverError::PrivateItem(
+                    span,
+                    original_name.to_string(),
+                    module_path,
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn load_module(&mut self, span: Span, module_path: &ModulePath) -> Result<Vec<Statement>> {
+        if let Some((code, filesystem_path)) = self.importer.import(module_path) {
+            let code_source_id = self.add_code_source(
+                CodeSource::Module(module_path.clone(), filesystem_path),
+                &code,
+            );
+
+            self.parse(&code, code_source_id)
+        } else {
+            Err(ResolverError::UnknownModule(span, module_path.clone()))
+        }
+    }
+
+    fn import_module(
+        &mut self,
+        span: Span,
+        module_path: &ModulePath,
+        kind: &ImportKind,
+        new_program: &mut Vec<Statement>,
+    ) -> Result<()> {
+        let imported_program = self.load_module(span, module_path)?;
+        self.imported_modules.push(module_path.clone());
+
+        check_imported_names(&imported_program, module_path, kind)?;
+
+        // Foreign functions can not be renamed, as they are identified by their
+        // name. They are always imported into the global namespace.
+        let mut renaming: HashMap<Symbol, Symbol> = value_names(&imported_program, false)
+            .into_iter()
+            .filter_map(|name| {
+                qualified_name(&name, module_path, kind, &self.interner)
+                    .map(|qualified| (name, qualified))
+            })
+            .collect();
+
+        // Private items are moved out of the way, such that they can neither
+        // be accessed from the outside nor clash with names in the importing
+        // code. The leading underscore hides them from listings.
+        let private = private_names(&imported_program);
+        for name in &private {
+            let internal_name = self.interner.intern(&format!("_{module_path}::{name}"));
+            self.private_items
+                .insert(internal_name.clone(), (name.clone(), module_path.clone()));
+            renaming.insert(name.clone(), internal_name);
+        }
+
+        let renamer = Renamer {
+            renaming: &renaming,
+        };
+        for statement in &imported_program {
+            if let Statement::ModuleImport(..) = statement {
+                let inlined_program = self.inlining_pass(std::slice::from_ref(statement))?;
+                new_program.extend(inlined_program);
+            } else {
+                self.check_private_access(statement)?;
+                new_program.push(renamer.rename_statement(statement.clone()));
+            }
+        }
+
+        if let ImportKind::Aliased(..) = kind {
+            new_program.extend(alias_definitions(&imported_program, |name| {
+                if renaming.contains_key(name) || private.contains(name) {
+                    None
+                } else {
+                    qualified_name(name, module_path, kind, &self.interner)
+                        .map(|alias| (alias, name.clone()))
+                }
+            }));
+        }
+
+        if !renaming.is_empty() {
+            self.renamed_items.insert(module_path.clone(), renaming);
+        }
+
+        Ok(())
+    }
+
+    /// A module has already been imported, but the new import statement asks for
+    /// names that are not available yet. Add definitions for these names that
+    /// refer to the already existing items.
+    fn define_aliases(
+        &mut self,
+        span: Span,
+        module_path: &ModulePath,
+        kind: &ImportKind,
+        new_program: &mut Vec<Statement>,
+    ) -> Result<()> {
+        let imported_program = self.load_module(span, module_path)?;
+
+        check_imported_names(&imported_program, module_path, kind)?;
+
+        let renaming = self
+            .renamed_items
+            .get(module_path)
+            .cloned()
+            .unwrap_or_default();
+
+        let private = private_names(&imported_program);
+
+        let definitions = alias_definitions(&imported_program, |name| {
+            if private.contains(name) {
+                return None;
+            }
+
+            let existing_name = renaming.get(name).cloned().unwrap_or_else(|| name.clone());
+            let wanted_name = match kind {
+                ImportKind::All => name.clone(),
+                ImportKind::Selected(names) if names.iter().any(|(_, n)| n == name) => name.clone(),
+                ImportKind::Selected(_) => existing_name.clone(),
+                ImportKind::Aliased(_, alias) => self.interner.intern(&format!("{alias}::{name}")),
+            };
+
+            if wanted_name == existing_name || self.alias_definitions.contains(&wanted_name) {
+                None
+            } else {
+                Some((wanted_name, existing_name))
+            }
+        });
+
+        for definition in definitions {
+            match &definition {
+                Statement::DefineFunction { function_name, .. } => {
+                    self.alias_definitions.insert(function_name.clone());
+                }
+                Statement::DefineVariable(DefineVariable { identifier, .. }) => {
+                    self.alias_definitions.insert(identifier.clone());
+                }
+                _ => unreachable!(),
+            }
+            new_program.push(definition);
+        }
+
+        Ok(())
+    }
+
     pub fn resolve(&mut self, code: &str, code_source: CodeSource) -> Result<Vec<Statement>> {
         let code_source_id = self.add_code_source(code_source, code);
         let statements = self.parse(code, code_source_id)?;
@@ -138,9 +338,601 @@ impl Resolver {
         self.inlining_pass(&statements)
     }
 
+    /// Forget about all modules that have been imported since `earlier` was
+    /// cloned from this resolver, such that they are imported again by the
+    /// next `use` statement. Their source code is kept for diagnostics.
+    pub(crate) fn reset_imports(&mut self, earlier: &Resolver) {
+        self.imported_modules = earlier.imported_modules.clone();
+        self.renamed_items = earlier.renamed_items.clone();
+        self.alias_definitions = earlier.alias_definitions.clone();
+    }
+
     pub fn get_importer(&self) -> &dyn ModuleImporter {
         self.importer.as_ref()
     }
+
+    /// Returns all modules that have been loaded from a file which has been
+    /// modified (or removed) since.
+    pub fn changed_modules(&self) -> Vec<ModulePath> {
+        let mut changed: Vec<ModulePath> = vec![];
+
+        for (id, code_source) in &self.codesources {
+            let CodeSource::Module(module_path, Some(path)) = code_source else {
+                continue;
+            };
+            // Builtin modules are embedded into the binary and can not change
+            if changed.contains(module_path) || path.starts_with("<builtin>") {
+                continue;
+            }
+
+            let loaded_code = self.files.get(*id).map(|file| file.source().as_ref());
+            let current_code = std::fs::read_to_string(path).ok();
+            if current_code.as_deref() != loaded_code.ok() {
+                changed.push(module_path.clone());
+            }
+        }
+
+        changed.sort();
+        changed
+    }
+}
+
+/// Returns the names under which the functions and variables (including their
+/// aliases) of a module are defined.
+fn value_names(program: &[Statement], include_foreign_functions: bool) -> Vec<Symbol> {
+    let mut names = vec![];
+    for statement in program {
+        match statement {
+            Statement::DefineFunction {
+                function_name,
+                body,
+                ..
+            } if body.is_some() || include_foreign_functions => {
+                names.push(function_name.clone());
+            }
+            Statement::DefineVariable(DefineVariable {
+                identifier,
+                decorators,
+                ..
+            }) => {
+                for (name, _) in decorator::name_and_aliases(identifier, decorators) {
+                    names.push(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Returns the names (including aliases) of all items of a module that are
+/// marked as `@private`. Foreign functions are not included, as they can not
+/// be renamed.
+fn private_names(program: &[Statement]) -> HashSet<Symbol> {
+    let mut names = HashSet::new();
+    for statement in program {
+        match statement {
+            Statement::DefineFunction {
+                function_name,
+                body: Some(_),
+                decorators,
+                ..
+            } if decorator::is_private(decorators) => {
+                names.insert(function_name.clone());
+            }
+            Statement::DefineVariable(DefineVariable {
+                identifier: name,
+                decorators,
+                ..
+            })
+            | Statement::DefineBaseUnit(_, name, _, decorators)
+            | Statement::DefineDerivedUnit {
+                identifier: name,
+                decorators,
+                ..
+            } if decorator::is_private(decorators) => {
+                for (name, _) in decorator::name_and_aliases(name, decorators) {
+                    names.insert(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Make sure that all names in `use foo (a, b)` are actually defined in `foo`
+/// and not private.
+fn check_imported_names(
+    program: &[Statement],
+    module_path: &ModulePath,
+    kind: &ImportKind,
+) -> Result<()> {
+    let ImportKind::Selected(selected_names) = kind else {
+        return Ok(());
+    };
+
+    let mut defined_names = value_names(program, true);
+    for statement in program {
+        match statement {
+            Statement::DefineBaseUnit(_, name, _, decorators)
+            | Statement::DefineDerivedUnit {
+                identifier: name,
+                decorators,
+                ..
+            } => {
+                for (name, _) in decorator::name_and_aliases(name, decorators) {
+                    defined_names.push(name.clone());
+                }
+            }
+            Statement::DefineDimension(_, name, _)
+            | Statement::DefineUnitAlias { alias: name, .. }
+            | Statement::DefineStruct {
+                struct_name: name, ..
+            } => {
+                defined_names.push(name.clone());
+            }
+            _ => {}
+        }
+    }
+
+    let private = private_names(program);
+
+    for (span, name) in selected_names {
+        if !defined_names.contains(name) || private.contains(name) {
+            return Err(ResolverError::UnknownImportedName(
+                *span,
+                module_path.clone(),
+                name.to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The name under which an item of a module is available after an import
+/// statement of the given kind, or `None` if it is imported unchanged.
+fn qualified_name(
+    name: &Symbol,
+    module_path: &ModulePath,
+    kind: &ImportKind,
+    interner: &Interner,
+) -> Option<Symbol> {
+    match kind {
+        ImportKind::All => None,
+        ImportKind::Selected(names) => {
+            if names.iter().any(|(_, n)| n == name) {
+                None
+            } else {
+                Some(interner.intern(&format!("{module_path}::{name}")))
+            }
+        }
+        ImportKind::Aliased(_, alias) => Some(interner.intern(&format!("{alias}::{name}"))),
+    }
+}
+
+/// Create definitions that make the functions and variables of a module
+/// available under another name. `names` maps the original name of an item to
+/// the new name and the name under which the item is currently available.
+fn alias_definitions(
+    program: &[Statement],
+    names: impl Fn(&Symbol) -> Option<(Symbol, Symbol)>,
+) -> Vec<Statement> {
+    let mut definitions = vec![];
+
+    for statement in program {
+        match statement {
+            Statement::DefineFunction {
+                function_name_span,
+                function_name,
+                type_parameters,
+                parameters,
+                return_type_annotation,
+                decorators,
+                ..
+            } => {
+                let Some((new_name, existing_name)) = names(function_name) else {
+                    continue;
+                };
+
+                let span = *function_name_span;
+                let arguments = parameters
+                    .iter()
+                    .map(|(span, name, _)| Expression::Identifier(*span, name.clone()))
+                    .collect();
+
+                definitions.push(Statement::DefineFunction {
+                    function_name_span: span,
+                    function_name: new_name,
+                    type_parameters: type_parameters.clone(),
+                    parameters: parameters.clone(),
+                    body: Some(Expression::FunctionCall(
+                        span,
+                        span,
+                        Box::new(Expression::Identifier(span, existing_name)),
+                        arguments,
+                    )),
+                    local_variables: vec![],
+                    return_type_annotation: return_type_annotation.clone(),
+                    decorators: decorators.clone(),
+                });
+            }
+            Statement::DefineVariable(DefineVariable {
+                identifier_span,
+                identifier,
+                type_annotation,
+                decorators,
+                ..
+            }) => {
+                for (name, _) in decorator::name_and_aliases(identifier, decorators) {
+                    let Some((new_name, existing_name)) = names(name) else {
+                        continue;
+                    };
+
+                    definitions.push(Statement::DefineVariable(DefineVariable {
+                        identifier_span: *identifier_span,
+                        identifier: new_name,
+                        expr: Expression::Identifier(*identifier_span, existing_name),
+                        type_annotation: type_annotation.clone(),
+                        decorators: decorators
+                            .iter()
+                            .filter(|d| !matches!(d, Decorator::Aliases(_)))
+                            .cloned()
+                            .collect(),
+                    }));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    definitions
+}
+
+/// Returns the first identifier within a statement that matches the given predicate.
+fn find_identifier<'a>(
+    statement: &'a Statement,
+    predicate: &dyn Fn(&Symbol) -> bool,
+) -> Option<(Span, &'a Symbol)> {
+    match statement {
+        Statement::Expression(expr)
+        | Statement::DefineVariable(DefineVariable { expr, .. })
+        | Statement::DefineDerivedUnit { expr, .. }
+        | Statement::SetPreferredUnit { expr, .. } => find_identifier_in(expr, predicate),
+        Statement::DefineFunction {
+            body,
+            local_variables,
+            ..
+        } => body
+            .iter()
+            .chain(local_variables.iter().map(|def| &def.expr))
+            .find_map(|expr| find_identifier_in(expr, predicate)),
+        Statement::DefineUnitAlias {
+            unit_span, unit, ..
+        } if predicate(unit) => Some((*unit_span, unit)),
+        Statement::ProcedureCall(_, _, args) => args
+            .iter()
+            .find_map(|arg| find_identifier_in(arg, predicate)),
+        Statement::DefineUnitAlias { .. }
+        | Statement::DefineBaseUnit(..)
+        | Statement::DefineDimension(..)
+        | Statement::ModuleImport(..)
+        | Statement::DefineStruct { .. } => None,
+    }
+}
+
+fn find_identifier_in<'a>(
+    expr: &'a Expression,
+    predicate: &dyn Fn(&Symbol) -> bool,
+) -> Option<(Span, &'a Symbol)> {
+    let find = |expr: &'a Expression| find_identifier_in(expr, predicate);
+
+    match expr {
+        Expression::Identifier(span, name) if predicate(name) => Some((*span, name)),
+        Expression::Identifier(..)
+        | Expression::Scalar(..)
+        | Expression::UnitIdentifier(..)
+        | Expression::TypedHole(_)
+        | Expression::Boolean(..)
+        | Expression::DateTime(..) => None,
+        Expression::UnaryOperator { expr, .. } => find(expr),
+        Expression::BinaryOperator { lhs, rhs, .. } => find(lhs).or_else(|| find(rhs)),
+        Expression::FunctionCall(_, _, callee, args) => {
+            find(callee).or_else(|| args.iter().find_map(find))
+        }
+        Expression::String(_, parts) => parts.iter().find_map(|part| match part {
+            StringPart::Interpolation { expr, .. } => find(expr),
+            StringPart::Fixed(_) => None,
+        }),
+        Expression::Condition(_, condition, then, else_) => find(condition)
+            .or_else(|| find(then))
+            .or_else(|| find(else_)),
+        Expression::InstantiateStruct { fields, .. } => {
+            fields.iter().find_map(|(_, _, expr)| find(expr))
+        }
+        Expression::AccessField(_, _, expr, _) => find(expr),
+        Expression::UpdateStruct { expr, fields, .. } => {
+            find(expr).or_else(|| fields.iter().find_map(|(_, _, value)| find(value)))
+        }
+        Expression::List(_, elements) => elements.iter().find_map(find),
+        Expression::Dict(_, entries) => entries
+            .iter()
+            .find_map(|(key, value)| find(key).or_else(|| find(value))),
+        Expression::Range {
+            start, end, step, ..
+        } => find(start)
+            .or_else(|| find(end))
+            .or_else(|| step.as_deref().and_then(find)),
+    }
+}
+
+/// Renames functions, variables and (private) units of a module, as well as all
+/// references to them within that module.
+struct Renamer<'a> {
+    renaming: &'a HashMap<Symbol, Symbol>,
+}
+
+impl Renamer<'_> {
+    fn rename(&self, name: Symbol) -> Symbol {
+        self.renaming.get(&name).cloned().unwrap_or(name)
+    }
+
+    fn rename_aliases(&self, decorators: Vec<Decorator>) -> Vec<Decorator> {
+        decorators
+            .into_iter()
+            .map(|decorator| match decorator {
+                Decorator::Aliases(aliases) => Decorator::Aliases(
+                    aliases
+                        .into_iter()
+                        .map(|(alias, accepts_prefix)| (self.rename(alias), accepts_prefix))
+                        .collect(),
+                ),
+                decorator => decorator,
+            })
+            .collect()
+    }
+
+    fn rename_statement(&self, statement: Statement) -> Statement {
+        if self.renaming.is_empty() {
+            return statement;
+        }
+
+        match statement {
+            Statement::Expression(expr) => Statement::Expression(self.rename_expression(expr, &[])),
+            Statement::DefineVariable(DefineVariable {
+                identifier_span,
+                identifier,
+                expr,
+                type_annotation,
+                decorators,
+            }) => Statement::DefineVariable(DefineVariable {
+                identifier_span,
+                identifier: self.rename(identifier),
+                expr: self.rename_expression(expr, &[]),
+                type_annotation,
+                decorators: self.rename_aliases(decorators),
+            }),
+            Statement::DefineFunction {
+                function_name_span,
+                function_name,
+                type_parameters,
+                parameters,
+                body,
+                local_variables,
+                return_type_annotation,
+                decorators,
+            } => {
+                // Parameters and local variables shadow module-level names
+                let local_names: Vec<Symbol> = parameters
+                    .iter()
+                    .map(|(_, name, _)| name.clone())
+                    .chain(local_variables.iter().map(|def| def.identifier.clone()))
+                    .collect();
+
+                Statement::DefineFunction {
+                    function_name_span,
+                    function_name: self.rename(function_name),
+                    type_parameters,
+                    body: body.map(|expr| self.rename_expression(expr, &local_names)),
+                    local_variables: local_variables
+                        .into_iter()
+                        .map(|def| DefineVariable {
+                            expr: self.rename_expression(def.expr, &local_names),
+                            ..def
+                        })
+                        .collect(),
+                    parameters,
+                    return_type_annotation,
+                    decorators,
+                }
+            }
+            Statement::DefineDerivedUnit {
+                identifier_span,
+                identifier,
+                expr,
+                type_annotation_span,
+                type_annotation,
+                decorators,
+            } => Statement::DefineDerivedUnit {
+                identifier_span,
+                identifier: self.rename(identifier),
+                expr: self.rename_expression(expr, &[]),
+                type_annotation_span,
+                type_annotation,
+                decorators: self.rename_aliases(decorators),
+            },
+            Statement::DefineBaseUnit(span, identifier, dexpr, decorators) => {
+                Statement::DefineBaseUnit(
+                    span,
+                    self.rename(identifier),
+                    dexpr,
+                    self.rename_aliases(decorators),
+                )
+            }
+            Statement::DefineUnitAlias {
+                alias_span,
+                alias,
+                accepts_prefix,
+                unit_span,
+                unit,
+            } => Statement::DefineUnitAlias {
+                alias_span,
+                alias: self.rename(alias),
+                accepts_prefix,
+                unit_span,
+                unit: self.rename(unit),
+            },
+            Statement::SetPreferredUnit {
+                span,
+                dimension,
+                expr,
+            } => Statement::SetPreferredUnit {
+                span,
+                dimension,
+                expr: self.rename_expression(expr, &[]),
+            },
+            Statement::ProcedureCall(span, kind, args) => Statement::ProcedureCall(
+                span,
+                kind,
+                args.into_iter()
+                    .map(|arg| self.rename_expression(arg, &[]))
+                    .collect(),
+            ),
+            statement @ (Statement::DefineDimension(..)
+            | Statement::ModuleImport(..)
+            | Statement::DefineStruct { .. }) => statement,
+        }
+    }
+
+    fn rename_expression(&self, expr: Expression, local_names: &[Symbol]) -> Expression {
+        let rename = |expr: Box<Expression>| Box::new(self.rename_expression(*expr, local_names));
+
+        match expr {
+            Expression::Identifier(span, name) => {
+                if local_names.contains(&name) {
+                    Expression::Identifier(span, name)
+                } else {
+                    Expression::Identifier(span, self.rename(name))
+                }
+            }
+            expr @ (Expression::Scalar(..)
+            | Expression::UnitIdentifier(..)
+            | Expression::TypedHole(_)
+            | Expression::Boolean(..)
+            | Expression::DateTime(..)) => expr,
+            Expression::UnaryOperator { op, expr, span_op } => Expression::UnaryOperator {
+                op,
+                expr: rename(expr),
+                span_op,
+            },
+            Expression::BinaryOperator {
+                op,
+                lhs,
+                rhs,
+                span_op,
+            } => Expression::BinaryOperator {
+                op,
+                lhs: rename(lhs),
+                rhs: rename(rhs),
+                span_op,
+            },
+            Expression::FunctionCall(span, full_span, callee, args) => Expression::FunctionCall(
+                span,
+                full_span,
+                rename(callee),
+                args.into_iter()
+                    .map(|arg| self.rename_expression(arg, local_names))
+                    .collect(),
+            ),
+            Expression::String(span, parts) => Expression::String(
+                span,
+                parts
+                    .into_iter()
+                    .map(|part| match part {
+                        StringPart::Interpolation {
+                            span,
+                            expr,
+                            format_specifiers,
+                        } => StringPart::Interpolation {
+                            span,
+                            expr: rename(expr),
+                            format_specifiers,
+                        },
+                        part @ StringPart::Fixed(_) => part,
+                    })
+                    .collect(),
+            ),
+            Expression::Condition(span, condition, then, else_) => {
+                Expression::Condition(span, rename(condition), rename(then), rename(else_))
+            }
+            Expression::InstantiateStruct {
+                full_span,
+                ident_span,
+                name,
+                fields,
+            } => Expression::InstantiateStruct {
+                full_span,
+                ident_span,
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(|(span, field, expr)| {
+                        (span, field, self.rename_expression(expr, local_names))
+                    })
+                    .collect(),
+            },
+            Expression::AccessField(full_span, ident_span, expr, field) => {
+                Expression::AccessField(full_span, ident_span, rename(expr), field)
+            }
+            Expression::UpdateStruct {
+                full_span,
+                expr,
+                fields,
+            } => Expression::UpdateStruct {
+                full_span,
+                expr: rename(expr),
+                fields: fields
+                    .into_iter()
+                    .map(|(span, path, value)| {
+                        (span, path, self.rename_expression(value, local_names))
+                    })
+                    .collect(),
+            },
+            Expression::List(span, elements) => Expression::List(
+                span,
+                elements
+                    .into_iter()
+                    .map(|element| self.rename_expression(element, local_names))
+                    .collect(),
+            ),
+            Expression::Dict(span, entries) => Expression::Dict(
+                span,
+                entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (
+                            self.rename_expression(key, local_names),
+                            self.rename_expression(value, local_names),
+                        )
+                    })
+                    .collect(),
+            ),
+            Expression::Range {
+                span_op,
+                start,
+                end,
+                step,
+                inclusive,
+            } => Expression::Range {
+                span_op,
+                start: rename(start),
+                end: rename(end),
+                step: step.map(rename),
+                inclusive,
+            },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -166,6 +958,14 @@ mod tests {
                 // ----
                 ModulePath(p) if p == &["cycle_a"] => Some(("use cycle_b".into(), None)),
                 ModulePath(p) if p == &["cycle_b"] => Some(("use cycle_a".into(), None)),
+                // ----
+                ModulePath(p) if p == &["mod_d"] => {
+                    Some(("let x = 1\nfn f(x) = x + y\nlet y = x".into(), None))
+                }
+                ModulePath(p) if p == &["mod_e"] => Some((
+                    "@private\nlet h = 2\n@private\nfn g(x) = h * x\nlet y = g(1)".into(),
+                    None,
+                )),
                 _ => None,
             }
         }
@@ -282,4 +1082,114 @@ mod tests {
 
         assert_eq!(&program_inlined, &[]);
     }
+
+    fn defined_names(program: &[Statement]) -> Vec<&str> {
+        program
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::DefineVariable(DefineVariable { identifier, .. }) => {
+                    Some(identifier.as_str())
+                }
+                Statement::DefineFunction { function_name, .. } => Some(function_name.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolver_aliased_import() {
+        use crate::ast::{BinaryOperator, ReplaceSpans};
+
+        let program = "
+        use mod_d as d
+        d::f(d::y)
+        ";
+
+        let importer = TestImporter {};
+
+        let mut resolver = Resolver::new(importer);
+        let program_inlined = resolver
+            .resolve(program, CodeSource::Internal)
+            .unwrap()
+            .replace_spans();
+
+        assert_eq!(defined_names(&program_inlined), ["d::x", "d::f", "d::y"]);
+
+        // The parameter 'x' shadows the module-level 'x'
+        let Statement::DefineFunction { body, .. } = &program_inlined[1] else {
+            panic!("Expected function definition");
+        };
+        assert_eq!(
+            body,
+            &Some(Expression::BinaryOperator {
+                op: BinaryOperator::Add,
+                lhs: Box::new(Expression::Identifier(Span::dummy(), "x".into())),
+                rhs: Box::new(Expression::Identifier(Span::dummy(), "d::y".into())),
+                span_op: Some(Span::dummy()),
+            })
+        );
+
+        let Statement::DefineVariable(DefineVariable { expr, .. }) = &program_inlined[2] else {
+            panic!("Expected variable definition");
+        };
+        assert_eq!(expr, &Expression::Identifier(Span::dummy(), "d::x".into()));
+    }
+
+    #[test]
+    fn resolver_selective_import() {
+        let program = "
+        use mod_d (f)
+        f(1)
+        ";
+
+        let importer = TestImporter {};
+
+        let mut resolver = Resolver::new(importer);
+        let program_inlined = resolver.resolve(program, CodeSource::Internal).unwrap();
+
+        assert_eq!(
+            defined_names(&program_inlined),
+            ["mod_d::x", "f", "mod_d::y"]
+        );
+
+        let mut resolver = Resolver::new(TestImporter {});
+        assert!(matches!(
+            resolver.resolve("use mod_d (z)", CodeSource::Internal),
+            Err(ResolverError::UnknownImportedName(_, _, name)) if name == "z"
+        ));
+    }
+
+    #[test]
+    fn resolver_private_items() {
+        let importer = TestImporter {};
+
+        let mut resolver = Resolver::new(importer);
+        let program_inlined = resolver.resolve("use mod_e", CodeSource::Internal).unwrap();
+
+        assert_eq!(
+            defined_names(&program_inlined),
+            ["_mod_e::h", "_mod_e::g", "y"]
+        );
+
+        let Statement::DefineVariable(DefineVariable { expr, .. }) = &program_inlined[2] else {
+            panic!();
+        };
+        assert!(matches!(
+            expr,
+            Expression::FunctionCall(_, _, callee, _)
+                if matches!(callee.as_ref(), Expression::Identifier(_, name) if name == "_mod_e::g")
+        ));
+
+        let mut resolver = Resolver::new(TestImporter {});
+        assert!(matches!(
+            resolver.resolve("use mod_e (h)", CodeSource::Internal),
+            Err(ResolverError::UnknownImportedName(_, _, name)) if name == "h"
+        ));
+
+        let mut resolver = Resolver::new(TestImporter {});
+        assert!(matches!(
+            resolver.resolve("use mod_e\n_mod_e::g(2)", CodeSource::Internal),
+            Err(ResolverError::PrivateItem(_, name, _)) if name == "g"
+        ));
+    }
 }
@@ -0,0 +1,171 @@
+//! Special functions that are not part of Rust's standard library. Where the C
+//! math library provides an implementation, we use that one.
+
+use std::f64::consts::PI;
+use std::ffi::{c_double, c_int};
+
+extern "C" {
+    fn tgamma(n: c_double) -> c_double;
+    fn erf(x: c_double) -> c_double;
+    fn erfc(x: c_double) -> c_double;
+    #[cfg_attr(windows, link_name = "_jn")]
+    fn jn(n: c_int, x: c_double) -> c_double;
+    #[cfg_attr(windows, link_name = "_yn")]
+    fn yn(n: c_int, x: c_double) -> c_double;
+}
+
+// TODO: This will be part of the standard in the future [1] [2]
+// [1] https://github.com/rust-lang/rfcs/issues/864
+// [2] https://github.com/rust-lang/rust/pull/99747
+pub fn gamma(x: f64) -> f64 {
+    unsafe { tgamma(x) }
+}
+
+/// The natural logarithm of the absolute value of the gamma function. The C
+/// `lgamma` stores the sign in a global variable, so we use the reentrant
+/// version instead.
+pub fn ln_gamma(x: f64) -> f64 {
+    libm::lgamma_r(x).0
+}
+
+pub fn error_function(x: f64) -> f64 {
+    unsafe { erf(x) }
+}
+
+pub fn complementary_error_function(x: f64) -> f64 {
+    unsafe { erfc(x) }
+}
+
+/// Bessel function of the first kind, J_n(x)
+pub fn bessel_j(n: i32, x: f64) -> f64 {
+    unsafe { jn(n, x) }
+}
+
+/// Bessel function of the second kind, Y_n(x)
+pub fn bessel_y(n: i32, x: f64) -> f64 {
+    unsafe { yn(n, x) }
+}
+
+/// Modified Bessel function of the first kind, I_n(x), computed from its power
+/// series. All terms have the same sign, so there is no cancellation. The sum
+/// starts at the largest term and is computed relative to it, so that the
+/// number of terms stays small and overflow is detected early, even for large
+/// orders or arguments.
+pub fn bessel_i(n: i32, x: f64) -> f64 {
+    if x.is_nan() {
+        return f64::NAN;
+    }
+
+    let sign = if x < 0.0 && n % 2 != 0 { -1.0 } else { 1.0 };
+    let n = n.unsigned_abs() as f64;
+    let half_x = x.abs() / 2.0;
+
+    if half_x == 0.0 {
+        return if n == 0.0 { 1.0 } else { 0.0 };
+    }
+
+    // The term (x/2)^(2k+n) / (k! (k+n)!) is largest for k ≈ k_peak
+    let k_peak = ((n.hypot(2.0 * half_x) - n) / 2.0).floor();
+    let ln_peak =
+        (2.0 * k_peak + n) * half_x.ln() - ln_gamma(k_peak + 1.0) - ln_gamma(k_peak + n + 1.0);
+    if ln_peak > f64::MAX.ln() {
+        return sign * f64::INFINITY;
+    }
+
+    let mut sum = 1.0;
+
+    let mut term = 1.0;
+    let mut k = k_peak;
+    loop {
+        k += 1.0;
+        term *= half_x / k * half_x / (k + n);
+        sum += term;
+        if term <= f64::EPSILON * sum {
+            break;
+        }
+    }
+
+    let mut term = 1.0;
+    let mut k = k_peak;
+    while k > 0.0 {
+        term *= k / half_x * (k + n) / half_x;
+        k -= 1.0;
+        sum += term;
+        if term <= f64::EPSILON * sum {
+            break;
+        }
+    }
+
+    sign * (ln_peak + sum.ln()).exp()
+}
+
+/// Modified Bessel function of the second kind, K_n(x), computed from the
+/// integral representation K_n(x) = ∫_0^∞ exp(-x cosh t) cosh(n t) dt. The
+/// integrand decays doubly exponentially, so the trapezoidal rule converges
+/// very quickly.
+pub fn bessel_k(n: i32, x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return f64::INFINITY;
+    }
+
+    let n = n.unsigned_abs() as f64;
+    let step = 0.02;
+
+    let integrand = |t: f64| (-x * t.cosh() + n * t).exp() * (1.0 + (-2.0 * n * t).exp()) / 2.0;
+
+    let mut sum = integrand(0.0) / 2.0;
+    let mut t = step;
+    loop {
+        let value = integrand(t);
+        sum += value;
+        if value <= f64::EPSILON * sum && x * t.sinh() > n {
+            break;
+        }
+        t += step;
+    }
+    sum * step
+}
+
+/// Arithmetic-geometric mean iteration for the complete elliptic integrals of
+/// the first and second kind, K(m) and E(m), with parameter m = k².
+fn complete_elliptic_integrals(m: f64) -> (f64, f64) {
+    let mut a = 1.0;
+    let mut g = (1.0 - m).sqrt();
+    let mut c_squared_sum = m / 2.0;
+    let mut power_of_two = 0.5;
+
+    while (a - g).abs() > f64::EPSILON * a {
+        let c = (a - g) / 2.0;
+        power_of_two *= 2.0;
+        c_squared_sum += power_of_two * c * c;
+        (a, g) = ((a + g) / 2.0, (a * g).sqrt());
+    }
+
+    let k = PI / (2.0 * a);
+    (k, k * (1.0 - c_squared_sum))
+}
+
+/// The AGM iteration does not converge for m > 1, where the integrals are
+/// not real-valued.
+pub fn elliptic_k(m: f64) -> f64 {
+    if m.is_nan() || m > 1.0 {
+        return f64::NAN;
+    }
+    if m == 1.0 {
+        return f64::INFINITY;
+    }
+    complete_elliptic_integrals(m).0
+}
+
+pub fn elliptic_e(m: f64) -> f64 {
+    if m.is_nan() || m > 1.0 {
+        return f64::NAN;
+    }
+    if m == 1.0 {
+        return 1.0;
+    }
+    complete_elliptic_integrals(m).1
+}
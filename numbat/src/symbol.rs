@@ -0,0 +1,201 @@
+//! Interned strings for identifiers. The parser interns every identifier in the
+//! AST, and the tables of the compilation stages (prefix parser, type checker)
+//! are keyed by [`Symbol`]s instead of `String`s. Cloning an AST or one of these
+//! tables (which happens for every input, to be able to roll back failed
+//! definitions) does not allocate a new string per entry, and looking up a key
+//! neither hashes the whole name nor compares strings in the common case.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// An identifier. Cloning a symbol is cheap. The hash of the name is computed
+/// once, when the symbol is created. Symbols from the same [`Interner`] share
+/// their name, so they can be compared by pointer. Symbols from different
+/// interners (or from [`Symbol::new`]) are compared by their names.
+#[derive(Clone)]
+pub struct Symbol {
+    name: Arc<str>,
+    hash: u64,
+}
+
+fn hash_name(name: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Symbol {
+    /// A symbol that is not interned, e.g. for looking up a name in a table.
+    pub fn new(name: &str) -> Self {
+        Symbol {
+            name: name.into(),
+            hash: hash_name(name),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.name, &other.name) || (self.hash == other.hash && self.name == other.name)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash)
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::new(&name)
+    }
+}
+
+impl From<&Symbol> for String {
+    fn from(symbol: &Symbol) -> Self {
+        symbol.as_str().to_owned()
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().to_owned()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// The names of all symbols that have been interned. The set only ever grows.
+/// Clones of an interner share the same set, such that a single interner can be
+/// used by all compilation stages and by all copies of a context.
+#[derive(Clone, Default)]
+pub struct Interner {
+    names: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    pub fn intern(&self, name: &str) -> Symbol {
+        let mut names = self.names.lock().unwrap();
+        let name = match names.get(name) {
+            Some(name) => name.clone(),
+            None => {
+                let name: Arc<str> = name.into();
+                names.insert(name.clone());
+                name
+            }
+        };
+
+        Symbol {
+            hash: hash_name(&name),
+            name,
+        }
+    }
+}
+
+impl fmt::Debug for Interner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interner").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning() {
+        let interner = Interner::default();
+
+        let meter = interner.intern("meter");
+        let meter2 = interner.clone().intern("meter");
+        let second = interner.intern("second");
+
+        assert!(Arc::ptr_eq(&meter.name, &meter2.name));
+        assert_eq!(meter, meter2);
+        assert_ne!(meter, second);
+        assert_eq!(meter.as_str(), "meter");
+        assert_eq!(interner.names.lock().unwrap().len(), 2);
+
+        // Symbols from different interners are equal if they have the same name
+        assert_eq!(meter, Interner::default().intern("meter"));
+        assert_eq!(meter, Symbol::new("meter"));
+        assert_ne!(meter, Symbol::new("second"));
+    }
+
+    #[test]
+    fn lookup_by_symbol() {
+        let interner = Interner::default();
+
+        let mut table = std::collections::HashMap::new();
+        table.insert(interner.intern("meter"), 1);
+
+        assert_eq!(table.get(&interner.intern("meter")), Some(&1));
+        assert_eq!(table.get(&Symbol::new("meter")), Some(&1));
+        assert_eq!(table.get(&interner.intern("second")), None);
+    }
+}
@@ -1,5 +1,7 @@
+use crate::normalization::normalize_identifier;
 use crate::span::{SourceCodePositition, Span};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::OnceLock;
 use thiserror::Error;
@@ -27,6 +29,12 @@ pub enum TokenizerErrorKind {
         character: Option<char>,
     },
 
+    #[error("Expected binary exponent (like 'p0') in hexadecimal floating point literal")]
+    ExpectedBinaryExponent { character: Option<char> },
+
+    #[error("Unterminated time zone annotation in date literal")]
+    UnterminatedTimeZoneAnnotation,
+
     #[error("Unterminated string")]
     UnterminatedString,
 
@@ -72,6 +80,8 @@ pub enum TokenKind {
     UnicodeExponent,
     At,
     Ellipsis,
+    DotDot,
+    DotDotEqual,
     ExclamationMark,
     EqualEqual,
     NotEqual,
@@ -91,6 +101,7 @@ pub enum TokenKind {
     Fn, // 'fn'
     Where,
     And,
+    With,
     Dimension,
     Unit,
     Use,
@@ -126,6 +137,10 @@ pub enum TokenKind {
     // Variable-length tokens
     Number,
     IntegerWithBase(usize),
+    // A C-style hexadecimal floating point number like `0x1.8p3`
+    HexFloat,
+    // A date and time literal like `@2024-05-01T13:00Z`
+    DateTimeLiteral,
     Identifier,
 
     // A normal string without interpolation: `"hello world"`
@@ -147,12 +162,14 @@ pub enum TokenKind {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token<'a> {
     pub kind: TokenKind,
-    pub lexeme: &'a str,
+    /// The text of the token. Identifiers are normalized, so this can differ
+    /// from the text in the source code (see [`crate::normalization`]).
+    pub lexeme: Cow<'a, str>,
     pub span: Span,
 }
 
 fn is_exponent_char(c: char) -> bool {
-    matches!(c, '¹' | '²' | '³' | '⁴' | '⁵' | '⁶' | '⁷' | '⁸' | '⁹')
+    matches!(c, '⁰' | '¹' | '²' | '³' | '⁴' | '⁵' | '⁶' | '⁷' | '⁸' | '⁹')
 }
 
 fn is_numerical_fraction_char(c: char) -> bool {
@@ -193,10 +210,10 @@ fn is_subscript_char(c: char) -> bool {
     let c_u32 = c as u32;
 
     // See https://en.wikipedia.org/wiki/Unicode_subscripts_and_superscripts#Superscripts_and_subscripts_block
-    (0x2080..=0x209CF).contains(&c_u32)
+    (0x2080..=0x209C).contains(&c_u32)
 }
 
-fn is_identifier_start(c: char) -> bool {
+pub(crate) fn is_identifier_start(c: char) -> bool {
     unicode_ident::is_xid_start(c)
         || is_numerical_fraction_char(c)
         || is_currency_char(c)
@@ -207,7 +224,7 @@ fn is_identifier_start(c: char) -> bool {
         || c == '_'
 }
 
-fn is_identifier_continue(c: char) -> bool {
+pub(crate) fn is_identifier_continue(c: char) -> bool {
     (unicode_ident::is_xid_continue(c)
         || is_subscript_char(c)
         || is_currency_char(c)
@@ -240,6 +257,7 @@ struct Tokenizer {
 
     // Special fields / state for parsing string interpolations
     string_start: SourceCodePositition,
+    string_is_triple_quoted: bool,
     interpolation_start: SourceCodePositition,
     interpolation_state: InterpolationState,
 }
@@ -257,6 +275,7 @@ impl Tokenizer {
 
             code_source_id,
             string_start: SourceCodePositition::start(),
+            string_is_triple_quoted: false,
             interpolation_start: SourceCodePositition::start(),
             interpolation_state: InterpolationState::Outside,
         }
@@ -273,7 +292,7 @@ impl Tokenizer {
 
         tokens.push(Token {
             kind: TokenKind::Eof,
-            lexeme: "",
+            lexeme: Cow::Borrowed(""),
             span: self.current.single_character_span(self.code_source_id),
         });
 
@@ -328,7 +347,10 @@ impl Tokenizer {
             });
         }
 
-        if disallow_dot_after_stream && self.peek(input).map(|c| c == '.').unwrap_or(false) {
+        if disallow_dot_after_stream
+            && self.peek(input) == Some('.')
+            && self.peek2(input) != Some('.')
+        {
             return Err(TokenizerError {
                 kind: TokenizerErrorKind::UnexpectedCharacterInNumberLiteral(
                     self.peek(input).unwrap(),
@@ -340,6 +362,136 @@ impl Tokenizer {
         Ok(())
     }
 
+    /// Scan the rest of a date literal like `@2024-05-01T13:00:00+02:00`,
+    /// including an optional time zone annotation like `[Europe/Berlin]`.
+    /// The contents are only validated by the parser.
+    fn consume_datetime_literal(&mut self, input: &str) -> Result<()> {
+        while self
+            .peek(input)
+            .map(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | ':' | '.'))
+            .unwrap_or(false)
+        {
+            self.advance(input);
+        }
+
+        if self.match_char(input, '[') {
+            loop {
+                match self.peek(input) {
+                    Some(']') => {
+                        self.advance(input);
+                        break;
+                    }
+                    Some(c) if !c.is_whitespace() => {
+                        self.advance(input);
+                    }
+                    _ => {
+                        return Err(TokenizerError {
+                            kind: TokenizerErrorKind::UnterminatedTimeZoneAnnotation,
+                            span: self.current.single_character_span(self.code_source_id),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scan the fractional part and the binary exponent of a hexadecimal
+    /// floating point literal like `0x1.8p3`, after the integer part
+    fn consume_hex_float_suffix(&mut self, input: &str) -> Result<()> {
+        if self.match_char(input, '.') {
+            let mut last_char = None;
+            while self
+                .peek(input)
+                .map(|c| c.is_ascii_hexdigit() || c == '_')
+                .unwrap_or(false)
+            {
+                last_char = Some(self.advance(input));
+            }
+
+            if last_char == Some('_') {
+                return Err(TokenizerError {
+                    kind: TokenizerErrorKind::UnexpectedCharacterInNumberLiteral('_'),
+                    span: self.last.single_character_span(self.code_source_id),
+                });
+            }
+        }
+
+        if !(self.match_char(input, 'p') || self.match_char(input, 'P')) {
+            return Err(TokenizerError {
+                kind: TokenizerErrorKind::ExpectedBinaryExponent {
+                    character: self.peek(input),
+                },
+                span: self.current.single_character_span(self.code_source_id),
+            });
+        }
+
+        let _ = self.match_char(input, '+') || self.match_char(input, '-');
+        self.consume_stream_of_digits(input, true, true, true)?;
+
+        if let Some(c) = self
+            .peek(input)
+            .filter(|&c| is_identifier_continue(c) || c == '.')
+        {
+            return Err(TokenizerError {
+                kind: TokenizerErrorKind::UnexpectedCharacterInNumberLiteral(c),
+                span: self.current.single_character_span(self.code_source_id),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Scan a power of ten like `×10^-19` or `·10⁻¹⁹` that immediately follows
+    /// a number literal, so that values can be pasted from papers directly. The
+    /// power of ten is part of the literal, i.e. `1 / 2×10^3` is `1 / 2000`.
+    fn times_ten_notation(&mut self, input: &str) {
+        if self.lexeme(input).contains(['e', 'E']) {
+            return;
+        }
+
+        let rest = &input[self.current.byte as usize..];
+        let Some(exponent) = rest
+            .strip_prefix("×10")
+            .or_else(|| rest.strip_prefix("·10"))
+        else {
+            return;
+        };
+
+        let (prefix, digits) = if let Some(exponent) = exponent.strip_prefix('^') {
+            let unsigned = exponent.strip_prefix(['+', '-', '−']).unwrap_or(exponent);
+            (
+                1 + exponent.chars().count() - unsigned.chars().count(),
+                unsigned.chars().take_while(char::is_ascii_digit).count(),
+            )
+        } else {
+            let unsigned = exponent.strip_prefix('⁻').unwrap_or(exponent);
+            (
+                exponent.chars().count() - unsigned.chars().count(),
+                unsigned
+                    .chars()
+                    .take_while(|&c| is_exponent_char(c))
+                    .count(),
+            )
+        };
+
+        let after_exponent = exponent.chars().nth(prefix + digits);
+        if digits == 0 || matches!(after_exponent, Some('.' | '^')) {
+            return;
+        }
+
+        for _ in 0.."×10".chars().count() + prefix + digits {
+            self.advance(input);
+        }
+    }
+
+    fn consume_exponent_chars(&mut self, input: &str) {
+        while self.peek(input).map(is_exponent_char).unwrap_or(false) {
+            self.advance(input);
+        }
+    }
+
     fn scientific_notation(&mut self, input: &str) -> Result<()> {
         if self
             .peek2(input)
@@ -363,18 +515,85 @@ impl Tokenizer {
                     break;
                 }
                 Some('\\') if !escaped => true,
-                Some('"') | Some('{') if !escaped => {
+                Some('"')
+                    if !escaped
+                        && (!self.string_is_triple_quoted || self.at_triple_quote(input)) =>
+                {
+                    break;
+                }
+                Some('{') if !escaped => {
                     break;
                 }
                 Some(_) => false,
             };
 
+            self.advance_in_string(input);
+        }
+
+        Ok(())
+    }
+
+    /// Consume the rest of a raw string like `r"C:\path"`, which has no escape
+    /// sequences and no interpolations
+    fn consume_raw_string(&mut self, input: &str) -> Result<()> {
+        let triple_quoted = self.at_triple_quote(input);
+        self.advance(input);
+        if triple_quoted {
+            self.advance(input);
             self.advance(input);
         }
 
+        loop {
+            match self.peek(input) {
+                None => {
+                    return Err(TokenizerError {
+                        kind: TokenizerErrorKind::UnterminatedString,
+                        span: Span {
+                            start: self.token_start,
+                            end: self.current,
+                            code_source_id: self.code_source_id,
+                        },
+                    });
+                }
+                Some('"') if !triple_quoted || self.at_triple_quote(input) => {
+                    break;
+                }
+                Some(_) => self.advance_in_string(input),
+            }
+        }
+
+        self.match_string_end(input, triple_quoted);
         Ok(())
     }
 
+    fn at_triple_quote(&self, input: &str) -> bool {
+        input[self.current.byte as usize..].starts_with("\"\"\"")
+    }
+
+    /// Consume the closing quote(s) of a string, if present
+    fn match_string_end(&mut self, input: &str, triple_quoted: bool) -> bool {
+        if !triple_quoted {
+            return self.match_char(input, '"');
+        }
+
+        if self.at_triple_quote(input) {
+            for _ in 0..3 {
+                self.advance(input);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Like `advance`, but keeps track of lines in multi-line strings
+    fn advance_in_string(&mut self, input: &str) {
+        if self.advance(input) == '\n' {
+            self.current.line += 1;
+            self.current.position = 1;
+        }
+    }
+
     fn scan_single_token<'a>(&mut self, input: &'a str) -> Result<Option<Token<'a>>> {
         static KEYWORDS: OnceLock<HashMap<&'static str, TokenKind>> = OnceLock::new();
         let keywords = KEYWORDS.get_or_init(|| {
@@ -386,6 +605,7 @@ impl Tokenizer {
             m.insert("fn", TokenKind::Fn);
             m.insert("where", TokenKind::Where);
             m.insert("and", TokenKind::And);
+            m.insert("with", TokenKind::With);
             m.insert("dimension", TokenKind::Dimension);
             m.insert("unit", TokenKind::Unit);
             m.insert("use", TokenKind::Use);
@@ -493,33 +713,44 @@ impl Tokenizer {
                     self.advance(input);
                 }
 
-                // Numeric literal should not end with a `_` either.
-                if last_char == Some('_')
-                    || self
-                        .peek(input)
-                        .map(|c| is_identifier_continue(c) || c == '.')
-                        .unwrap_or(false)
-                {
-                    return tokenizer_error(
-                        &self.current,
-                        TokenizerErrorKind::ExpectedDigitInBase {
-                            base,
-                            character: self.peek(input),
-                        },
-                    );
-                }
+                let is_hex_float = base == 16
+                    && last_char != Some('_')
+                    && (matches!(self.peek(input), Some('p' | 'P'))
+                        || (self.peek(input) == Some('.')
+                            && self.peek2(input).is_some_and(|c| c.is_ascii_hexdigit())));
+                if is_hex_float {
+                    self.consume_hex_float_suffix(input)?;
+                    TokenKind::HexFloat
+                } else {
+                    // Numeric literal should not end with a `_` either.
+                    if last_char == Some('_')
+                        || self
+                            .peek(input)
+                            .map(|c| is_identifier_continue(c) || c == '.')
+                            .unwrap_or(false)
+                    {
+                        return tokenizer_error(
+                            &self.current,
+                            TokenizerErrorKind::ExpectedDigitInBase {
+                                base,
+                                character: self.peek(input),
+                            },
+                        );
+                    }
 
-                TokenKind::IntegerWithBase(base)
+                    TokenKind::IntegerWithBase(base)
+                }
             }
             c if c.is_ascii_digit() => {
                 self.consume_stream_of_digits(input, false, false, false)?;
 
-                // decimal part
-                if self.match_char(input, '.') {
+                // decimal part (but not the start of a range like `1..5`)
+                if self.peek2(input) != Some('.') && self.match_char(input, '.') {
                     self.consume_stream_of_digits(input, false, true, true)?;
                 }
 
                 self.scientific_notation(input)?;
+                self.times_ten_notation(input);
 
                 TokenKind::Number
             }
@@ -529,6 +760,13 @@ impl Tokenizer {
 
                 TokenKind::Ellipsis
             }
+            '.' if self.match_char(input, '.') => {
+                if self.match_char(input, '=') {
+                    TokenKind::DotDotEqual
+                } else {
+                    TokenKind::DotDot
+                }
+            }
             '.' if self.peek(input).map_or(false, is_identifier_start) => TokenKind::Period,
             '.' => {
                 self.consume_stream_of_digits(input, true, true, true)?;
@@ -553,6 +791,10 @@ impl Tokenizer {
             '⩵' => TokenKind::EqualEqual,
             '=' if self.match_char(input, '=') => TokenKind::EqualEqual,
             '=' => TokenKind::Equal,
+            '@' if self.peek(input).is_some_and(|c| c.is_ascii_digit()) => {
+                self.consume_datetime_literal(input)?;
+                TokenKind::DateTimeLiteral
+            }
             '@' => TokenKind::At,
             '→' | '➞' => TokenKind::Arrow,
             '-' if self.match_char(input, '>') => TokenKind::Arrow,
@@ -563,7 +805,7 @@ impl Tokenizer {
             '⁻' => {
                 let c = self.peek(input);
                 if c.map(is_exponent_char).unwrap_or(false) {
-                    self.advance(input);
+                    self.consume_exponent_chars(input);
                     TokenKind::UnicodeExponent
                 } else {
                     return tokenizer_error(
@@ -572,16 +814,24 @@ impl Tokenizer {
                     );
                 }
             }
-            '¹' | '²' | '³' | '⁴' | '⁵' | '⁶' | '⁷' | '⁸' | '⁹' => {
+            c if is_exponent_char(c) => {
+                self.consume_exponent_chars(input);
                 TokenKind::UnicodeExponent
             }
             '"' => match self.interpolation_state {
                 InterpolationState::Outside => {
                     self.string_start = self.token_start;
 
+                    self.string_is_triple_quoted =
+                        self.peek(input) == Some('"') && self.peek2(input) == Some('"');
+                    if self.string_is_triple_quoted {
+                        self.advance(input);
+                        self.advance(input);
+                    }
+
                     self.consume_string(input)?;
 
-                    if self.match_char(input, '"') {
+                    if self.match_string_end(input, self.string_is_triple_quoted) {
                         TokenKind::StringFixed
                     } else if self.match_char(input, '{') {
                         self.interpolation_state = InterpolationState::Inside;
@@ -644,7 +894,7 @@ impl Tokenizer {
             '}' if self.interpolation_state.is_inside() => {
                 self.consume_string(input)?;
 
-                if self.match_char(input, '"') {
+                if self.match_string_end(input, self.string_is_triple_quoted) {
                     self.interpolation_state = InterpolationState::Outside;
                     TokenKind::StringInterpolationEnd
                 } else if self.match_char(input, '{') {
@@ -668,6 +918,10 @@ impl Tokenizer {
                 });
             }
             '…' => TokenKind::Ellipsis,
+            'r' if !self.interpolation_state.is_inside() && self.peek(input) == Some('"') => {
+                self.consume_raw_string(input)?;
+                TokenKind::StringFixed
+            }
             c if is_identifier_start(c) => {
                 while self
                     .peek(input)
@@ -680,7 +934,7 @@ impl Tokenizer {
                 if self.peek(input).map(|c| c == '.').unwrap_or(false)
                     && self
                         .peek2(input)
-                        .map(|c| !is_identifier_start(c))
+                        .map(|c| !is_identifier_start(c) && c != '.')
                         .unwrap_or(true)
                 {
                     return tokenizer_error(
@@ -707,9 +961,15 @@ impl Tokenizer {
             }
         };
 
+        let lexeme = if kind == TokenKind::Identifier {
+            normalize_identifier(self.lexeme(input))
+        } else {
+            Cow::Borrowed(self.lexeme(input))
+        };
+
         let token = Some(Token {
             kind,
-            lexeme: self.lexeme(input),
+            lexeme,
             span: Span {
                 start: self.token_start,
                 end: self.current,
@@ -1168,6 +1428,56 @@ fn test_tokenize_string() {
     );
 }
 
+#[test]
+fn test_tokenize_multi_line_and_raw_strings() {
+    use TokenKind::*;
+
+    assert_eq!(
+        tokenize_reduced(r#""""a "b" c""""#).unwrap(),
+        [
+            (r#""""a "b" c""""#.to_string(), StringFixed, (1, 1)),
+            ("".to_string(), Eof, (1, 14))
+        ]
+    );
+
+    assert_eq!(
+        tokenize_reduced("\"\"\"\nfoo\nbar\"\"\"\nx").unwrap(),
+        [
+            ("\"\"\"\nfoo\nbar\"\"\"".to_string(), StringFixed, (1, 1)),
+            ("\n".to_string(), Newline, (3, 7)),
+            ("x".to_string(), Identifier, (4, 1)),
+            ("".to_string(), Eof, (4, 2))
+        ]
+    );
+
+    assert_eq!(
+        tokenize_reduced(r#""""x = {x}""""#).unwrap(),
+        [
+            (r#""""x = {"#.to_string(), StringInterpolationStart, (1, 1)),
+            ("x".to_string(), Identifier, (1, 9)),
+            (r#"}""""#.to_string(), StringInterpolationEnd, (1, 10)),
+            ("".to_string(), Eof, (1, 14))
+        ]
+    );
+
+    assert_eq!(
+        tokenize_reduced(r#"r"C:\path\{x}""#).unwrap(),
+        [
+            (r#"r"C:\path\{x}""#.to_string(), StringFixed, (1, 1)),
+            ("".to_string(), Eof, (1, 15))
+        ]
+    );
+
+    assert_eq!(
+        tokenize("r\"foo", 0).unwrap_err().kind,
+        TokenizerErrorKind::UnterminatedString
+    );
+    assert_eq!(
+        tokenize("\"\"\"foo\"", 0).unwrap_err().kind,
+        TokenizerErrorKind::UnterminatedString
+    );
+}
+
 #[test]
 fn test_logical_operators() {
     insta::assert_snapshot!(
@@ -1220,6 +1530,10 @@ fn test_is_subscript_char() {
     assert!(is_subscript_char('ₓ'));
     assert!(is_subscript_char('ₘ'));
     assert!(is_subscript_char('₎'));
+    assert!(is_subscript_char('ₜ'));
+
+    assert!(!is_subscript_char('€'));
+    assert!(!is_subscript_char('→'));
 }
 
 #[test]
@@ -1252,8 +1566,13 @@ fn test_field_access() {
     );
 
     insta::assert_snapshot!(
-    tokenize_reduced_pretty("instance..field").unwrap_err(),
-        @"Error at (1, 9): `Unexpected character in identifier: '.'`"
+    tokenize_reduced_pretty("instance..field").unwrap(),
+        @r###"
+    "instance", Identifier, (1, 1)
+    "..", DotDot, (1, 9)
+    "field", Identifier, (1, 11)
+    "", Eof, (1, 16)
+    "###
     );
 
     insta::assert_snapshot!(
@@ -1278,3 +1597,60 @@ fn test_lists() {
     "###
     );
 }
+
+#[test]
+fn test_ranges() {
+    insta::assert_snapshot!(
+        tokenize_reduced_pretty("1..=10 step 2").unwrap(),
+        @r###"
+    "1", Number, (1, 1)
+    "..=", DotDotEqual, (1, 2)
+    "10", Number, (1, 5)
+    "step", Identifier, (1, 8)
+    "2", Number, (1, 13)
+    "", Eof, (1, 14)
+    "###
+    );
+
+    insta::assert_snapshot!(
+        tokenize_reduced_pretty("0.5..x").unwrap(),
+        @r###"
+    "0.5", Number, (1, 1)
+    "..", DotDot, (1, 4)
+    "x", Identifier, (1, 6)
+    "", Eof, (1, 7)
+    "###
+    );
+}
+
+#[test]
+fn test_datetime_literals() {
+    insta::assert_snapshot!(
+        tokenize_reduced_pretty("@2024-05-01T13:00Z - 1 h").unwrap(),
+        @r###"
+    "@2024-05-01T13:00Z", DateTimeLiteral, (1, 1)
+    "-", Minus, (1, 20)
+    "1", Number, (1, 22)
+    "h", Identifier, (1, 24)
+    "", Eof, (1, 25)
+    "###
+    );
+
+    insta::assert_snapshot!(
+        tokenize_reduced_pretty("@2024-05-01T13:00[Europe/Berlin]").unwrap(),
+        @r###"
+    "@2024-05-01T13:00[Europe/Berlin]", DateTimeLiteral, (1, 1)
+    "", Eof, (1, 33)
+    "###
+    );
+
+    assert_eq!(
+        tokenize_reduced("@2024-05-01[Europe/Berlin").unwrap_err(),
+        "Error at (1, 26): `Unterminated time zone annotation in date literal`"
+    );
+
+    assert_eq!(
+        tokenize_reduced("@aliases").unwrap()[0],
+        ("@".to_string(), TokenKind::At, (1, 1))
+    );
+}
@@ -51,6 +51,7 @@ impl ForAllTypeSchemes for Expression {
                 else_.for_all_type_schemes(f);
             }
             Expression::String(_, _) => {}
+            Expression::DateTime(_, _) => {}
             Expression::InstantiateStruct(_, initializers, info) => {
                 for (_, expr) in initializers {
                     expr.for_all_type_schemes(f);
@@ -62,12 +63,34 @@ impl ForAllTypeSchemes for Expression {
                 f(struct_type);
                 f(field_type);
             }
+            Expression::UpdateStruct(_, expr, fields, struct_type) => {
+                expr.for_all_type_schemes(f);
+                for (_, value) in fields {
+                    value.for_all_type_schemes(f);
+                }
+                f(struct_type);
+            }
             Expression::List(_, elements, type_) => {
                 for element in elements {
                     element.for_all_type_schemes(f);
                 }
                 f(type_);
             }
+            Expression::Dict(_, entries, type_) => {
+                for (key, value) in entries {
+                    key.for_all_type_schemes(f);
+                    value.for_all_type_schemes(f);
+                }
+                f(type_);
+            }
+            Expression::Range(_, start, end, step, _, type_) => {
+                start.for_all_type_schemes(f);
+                end.for_all_type_schemes(f);
+                if let Some(step) = step {
+                    step.for_all_type_schemes(f);
+                }
+                f(type_);
+            }
             Expression::TypedHole(_, type_) => {
                 f(type_);
             }
@@ -94,6 +117,7 @@ impl ForAllTypeSchemes for Statement {
                 f(fn_type);
             }
             Statement::DefineDimension(_, _) => {}
+            Statement::DefineUnitAlias(_, _, _) => {}
             Statement::DefineBaseUnit(_, _, _annotation, type_) => {
                 f(type_);
             }
@@ -101,6 +125,7 @@ impl ForAllTypeSchemes for Statement {
                 expr.for_all_type_schemes(f);
                 f(type_);
             }
+            Statement::SetPreferredUnit(_, expr) => expr.for_all_type_schemes(f),
             Statement::ProcedureCall(_, args) => {
                 for arg in args {
                     arg.for_all_type_schemes(f);
@@ -132,7 +157,9 @@ impl ForAllExpressions for Statement {
             }
             Statement::DefineDimension(_, _) => {}
             Statement::DefineBaseUnit(_, _, _, _) => {}
+            Statement::DefineUnitAlias(_, _, _) => {}
             Statement::DefineDerivedUnit(_, expr, _, _, _, _) => expr.for_all_expressions(f),
+            Statement::SetPreferredUnit(_, expr) => expr.for_all_expressions(f),
             Statement::ProcedureCall(_, args) => {
                 for arg in args {
                     arg.for_all_expressions(f);
@@ -177,6 +204,7 @@ impl ForAllExpressions for Expression {
                 else_.for_all_expressions(f);
             }
             Expression::String(_, _) => {}
+            Expression::DateTime(_, _) => {}
             Expression::InstantiateStruct(_, initializers, _) => {
                 for (_, expr) in initializers {
                     expr.for_all_expressions(f);
@@ -185,11 +213,30 @@ impl ForAllExpressions for Expression {
             Expression::AccessField(_, _, expr, _, _, _) => {
                 expr.for_all_expressions(f);
             }
+            Expression::UpdateStruct(_, expr, fields, _) => {
+                expr.for_all_expressions(f);
+                for (_, value) in fields {
+                    value.for_all_expressions(f);
+                }
+            }
             Expression::List(_, elements, _) => {
                 for element in elements {
                     element.for_all_expressions(f);
                 }
             }
+            Expression::Dict(_, entries, _) => {
+                for (key, value) in entries {
+                    key.for_all_expressions(f);
+                    value.for_all_expressions(f);
+                }
+            }
+            Expression::Range(_, start, end, step, _, _) => {
+                start.for_all_expressions(f);
+                end.for_all_expressions(f);
+                if let Some(step) = step {
+                    step.for_all_expressions(f);
+                }
+            }
             Expression::TypedHole(_, _) => {}
         }
     }
@@ -37,7 +37,7 @@ pub fn evaluate_const_expr(expr: &typed_ast::Expression) -> Result<Exponent> {
                 typed_ast::BinaryOperator::Sub => Ok(lhs
                     .checked_sub(&rhs)
                     .ok_or_else(|| TypeCheckError::OverflowInConstExpr(expr.full_span()))?),
-                typed_ast::BinaryOperator::Mul => Ok(lhs
+                typed_ast::BinaryOperator::Mul | typed_ast::BinaryOperator::Of => Ok(lhs
                     .checked_mul(&rhs)
                     .ok_or_else(|| TypeCheckError::OverflowInConstExpr(expr.full_span()))?),
                 typed_ast::BinaryOperator::Div => {
@@ -104,6 +104,9 @@ pub fn evaluate_const_expr(expr: &typed_ast::Expression) -> Result<Exponent> {
         e @ typed_ast::Expression::String(_, _) => Err(
             TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "String"),
         ),
+        e @ typed_ast::Expression::DateTime(_, _) => Err(
+            TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "DateTime"),
+        ),
         e @ typed_ast::Expression::Condition(..) => Err(
             TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "Conditional"),
         ),
@@ -119,9 +122,18 @@ pub fn evaluate_const_expr(expr: &typed_ast::Expression) -> Result<Exponent> {
         e @ typed_ast::Expression::AccessField(_, _, _, _, _, _) => Err(
             TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "access field of struct"),
         ),
+        e @ typed_ast::Expression::UpdateStruct(_, _, _, _) => Err(
+            TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "update struct"),
+        ),
         e @ typed_ast::Expression::List(_, _, _) => Err(
             TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "lists"),
         ),
+        e @ typed_ast::Expression::Dict(_, _, _) => Err(
+            TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "dictionaries"),
+        ),
+        e @ typed_ast::Expression::Range(..) => Err(
+            TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "ranges"),
+        ),
         e @ typed_ast::Expression::TypedHole(_, _) => Err(
             TypeCheckError::UnsupportedConstEvalExpression(e.full_span(), "typed hole"),
         ),
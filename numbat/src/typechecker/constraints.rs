@@ -196,6 +196,11 @@ impl Constraint {
             {
                 TrivialResolution::Violated
             }
+            Constraint::Equal(Type::Struct(info1), Type::Struct(info2))
+                if info1.name != info2.name =>
+            {
+                TrivialResolution::Violated
+            }
             Constraint::Equal(_, _) => TrivialResolution::Unknown,
             Constraint::IsDType(t) if t.is_closed() => match t {
                 Type::Dimension(_) => TrivialResolution::Satisfied,
@@ -263,12 +268,28 @@ impl Constraint {
 
                 Some(Satisfied::with_new_constraints(new_constraints))
             }
-            Constraint::Equal(Type::List(s1), Type::List(t1)) => {
+            Constraint::Equal(Type::List(s1), Type::List(t1))
+            | Constraint::Equal(Type::Dict(s1), Type::Dict(t1)) => {
                 Some(Satisfied::with_new_constraints(vec![Constraint::Equal(
                     s1.as_ref().clone(),
                     t1.as_ref().clone(),
                 )]))
             }
+            Constraint::Equal(Type::Struct(info1), Type::Struct(info2))
+                if info1.name == info2.name =>
+            {
+                // Instances of generic structs are equal if all of their field types are
+                Some(Satisfied::with_new_constraints(
+                    info1
+                        .fields
+                        .iter()
+                        .filter_map(|(name, (_, t1))| {
+                            let (_, t2) = info2.fields.get(name)?;
+                            Some(Constraint::Equal(t1.clone(), t2.clone()))
+                        })
+                        .collect(),
+                ))
+            }
             Constraint::Equal(Type::TVar(tv), Type::Dimension(d))
             | Constraint::Equal(Type::Dimension(d), Type::TVar(tv)) => {
                 Some(Satisfied::with_new_constraints(vec![Constraint::Equal(
@@ -2,6 +2,7 @@ use crate::ast::{TypeAnnotation, TypeParameterBound};
 use crate::dimension::DimensionRegistry;
 use crate::pretty_print::PrettyPrint;
 use crate::span::Span;
+use crate::symbol::Symbol;
 use crate::type_variable::TypeVariable;
 use crate::typed_ast::pretty_print_function_signature;
 use crate::Type;
@@ -10,7 +11,7 @@ use super::map_stack::MapStack;
 use super::substitutions::{ApplySubstitution, Substitution, SubstitutionError};
 use super::type_scheme::TypeScheme;
 
-type Identifier = String;
+type Identifier = Symbol;
 
 #[derive(Clone, Debug)]
 pub struct FunctionSignature {
@@ -97,16 +98,16 @@ pub struct Environment {
 }
 
 impl Environment {
-    pub fn add(&mut self, i: Identifier, type_: Type, span: Span, is_unit: bool) {
+    pub fn add(&mut self, i: &Identifier, type_: Type, span: Span, is_unit: bool) {
         self.identifiers.insert(
-            i,
+            i.clone(),
             IdentifierKind::Normal(TypeScheme::Concrete(type_), span, is_unit),
         );
     }
 
-    pub fn add_scheme(&mut self, i: Identifier, scheme: TypeScheme, span: Span, is_unit: bool) {
+    pub fn add_scheme(&mut self, i: &Identifier, scheme: TypeScheme, span: Span, is_unit: bool) {
         self.identifiers
-            .insert(i, IdentifierKind::Normal(scheme, span, is_unit));
+            .insert(i.clone(), IdentifierKind::Normal(scheme, span, is_unit));
     }
 
     pub(crate) fn save(&mut self) {
@@ -119,20 +120,20 @@ impl Environment {
 
     pub(crate) fn add_function(
         &mut self,
-        v: String,
+        v: &Identifier,
         signature: FunctionSignature,
         metadata: FunctionMetadata,
     ) {
         self.identifiers
-            .insert(v, IdentifierKind::Function(signature, metadata));
+            .insert(v.clone(), IdentifierKind::Function(signature, metadata));
     }
 
-    pub fn add_predefined(&mut self, v: Identifier, type_: TypeScheme) {
+    pub fn add_predefined(&mut self, v: &Identifier, type_: TypeScheme) {
         self.identifiers
-            .insert(v, IdentifierKind::Predefined(type_));
+            .insert(v.clone(), IdentifierKind::Predefined(type_));
     }
 
-    pub(crate) fn get_identifier_type(&self, v: &str) -> Option<TypeScheme> {
+    pub(crate) fn get_identifier_type(&self, v: &Identifier) -> Option<TypeScheme> {
         self.find(v).map(|k| k.get_type())
     }
 
@@ -152,13 +153,13 @@ impl Environment {
             .map(|(id, kind)| (id, kind.get_type()))
     }
 
-    fn find(&self, name: &str) -> Option<&IdentifierKind> {
+    fn find(&self, name: &Identifier) -> Option<&IdentifierKind> {
         self.identifiers.get(name)
     }
 
     pub(crate) fn get_function_info(
         &self,
-        name: &str,
+        name: &Identifier,
     ) -> Option<(&FunctionSignature, &FunctionMetadata)> {
         match self.find(name) {
             Some(IdentifierKind::Function(signature, metadata)) => Some((signature, metadata)),
@@ -23,6 +23,11 @@ pub enum TypeCheckError {
     #[error("Argument of factorial needs to be dimensionless (got {1}).")]
     NonScalarFactorialArgument(Span, Type),
 
+    #[error(
+        "The left hand side of 'of' needs to be a dimensionless fraction, like 15 % (got {1})."
+    )]
+    NonScalarFraction(Span, Type),
+
     #[error("Unsupported expression in const-evaluation of exponent: {1}.")]
     UnsupportedConstEvalExpression(Span, &'static str),
 
@@ -68,6 +73,9 @@ pub enum TypeCheckError {
     #[error("Expected dimension type, got {1} instead")]
     ExpectedDimensionType(Span, Type),
 
+    #[error("'{1}' is a function and can not be multiplied. To call it, remove the space before the parentheses: '{1}(…)'")]
+    ImplicitMultiplicationWithFunction(Span, String),
+
     #[error("Expected boolean value")]
     ExpectedBool(Span),
 
@@ -107,9 +115,15 @@ pub enum TypeCheckError {
     #[error("Base units can not be dimensionless.")]
     NoDimensionlessBaseUnit(Span, String),
 
+    #[error("Preferred units can not be set for dimensionless quantities")]
+    NoDimensionlessPreferredUnit(Span),
+
     #[error("Unknown struct '{1}")]
     UnknownStruct(Span, String),
 
+    #[error("Struct '{1}' takes {2} type argument(s), but {3} were given")]
+    WrongNumberOfTypeArguments(Span, String, usize, usize),
+
     #[error("Field '{2}' does not exist in struct '{3}'")]
     UnknownFieldInStructInstantiation(Span, Span, String, String),
 
@@ -131,6 +145,18 @@ pub enum TypeCheckError {
     #[error("Incompatible types in list: expected '{1}', got '{3}' instead")]
     IncompatibleTypesInList(Span, Type, Span, Type),
 
+    #[error("Dictionary keys need to be strings, got '{1}' instead")]
+    NonStringDictKey(Span, Type),
+
+    #[error("Incompatible types in dictionary: expected '{1}', got '{3}' instead")]
+    IncompatibleTypesInDict(Span, Type, Span, Type),
+
+    #[error("Incompatible types in range: expected '{1}', got '{3}' instead")]
+    IncompatibleTypesInRange(Span, Type, Span, Type),
+
+    #[error("A range of quantities of type '{1}' needs an explicit 'step'")]
+    MissingStepInRange(Span, Type),
+
     #[error(transparent)]
     NameResolutionError(#[from] NameResolutionError),
 
@@ -12,7 +12,7 @@ pub mod qualified_type;
 mod substitutions;
 pub mod type_scheme;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 
 use crate::arithmetic::Exponent;
@@ -20,13 +20,18 @@ use crate::ast::{
     self, BinaryOperator, DefineVariable, ProcedureKind, StringPart, TypeAnnotation,
     TypeExpression, TypeParameterBound,
 };
+use crate::decorator::Decorator;
 use crate::dimension::DimensionRegistry;
 use crate::name_resolution::Namespace;
 use crate::name_resolution::LAST_RESULT_IDENTIFIERS;
+use crate::number::Number;
+use crate::prefix_parser::AcceptsPrefix;
 use crate::pretty_print::PrettyPrint;
 use crate::span::Span;
+use crate::symbol::Symbol;
 use crate::type_variable::TypeVariable;
 use crate::typed_ast::{self, DType, DTypeFactor, Expression, StructInfo, Type};
+use crate::warning::Warning;
 use crate::{decorator, ffi, suggestion};
 
 use const_evaluation::evaluate_const_expr;
@@ -60,6 +65,24 @@ pub struct TypeChecker {
 
     name_generator: NameGenerator,
     constraints: ConstraintSet,
+
+    /// Names of deprecated units, functions and variables, with a hint about
+    /// what to use instead
+    deprecations: HashMap<String, String>,
+    warnings: Vec<Warning>,
+
+    /// Units with a `@relative` decorator, like `percent`
+    relative_units: HashSet<String>,
+
+    /// The dimensions of units with a `@reciprocal` decorator, like `mpg`.
+    /// Quantities of the reciprocal dimension can be converted to these (and
+    /// back) with `->`, as in `8 L/100km -> mpg`.
+    reciprocal_dimensions: Vec<DType>,
+
+    /// The function with a `@natural_conversion` decorator. If there is one,
+    /// conversions like `1 GeV -> kg` between dimensions that only differ by
+    /// powers of `c` and `ħ` are turned into calls of this function.
+    natural_conversion: Option<Symbol>,
 }
 
 struct ElaborationDefinitionArgs<'a> {
@@ -103,11 +126,19 @@ impl TypeChecker {
     fn type_from_annotation(&self, annotation: &TypeAnnotation) -> Result<Type> {
         match annotation {
             TypeAnnotation::TypeExpression(dexpr) => {
-                if let TypeExpression::TypeIdentifier(_, name) = dexpr {
-                    if let Some(info) = self.structs.get(name) {
+                if let TypeExpression::TypeIdentifier(span, name) = dexpr {
+                    if let Some(info) = self.structs.get(name.as_str()) {
                         // if we see a struct name here, it's safe to assume it
                         // isn't accidentally clashing with a dimension, we
                         // check that earlier.
+                        if !info.type_parameters.is_empty() {
+                            return Err(TypeCheckError::WrongNumberOfTypeArguments(
+                                *span,
+                                name.to_string(),
+                                info.type_parameters.len(),
+                                0,
+                            ));
+                        }
                         return Ok(Type::Struct(Box::new(info.clone())));
                     }
                 }
@@ -149,10 +180,57 @@ impl TypeChecker {
             TypeAnnotation::List(_, element_type) => Ok(Type::List(Box::new(
                 self.type_from_annotation(element_type)?,
             ))),
+            TypeAnnotation::Dict(_, value_type) => {
+                Ok(Type::Dict(Box::new(self.type_from_annotation(value_type)?)))
+            }
+            TypeAnnotation::Struct(span, name, type_arguments) => {
+                let Some(info) = self.structs.get(name.as_str()) else {
+                    return Err(TypeCheckError::UnknownStruct(*span, name.to_string()));
+                };
+
+                if info.type_parameters.len() != type_arguments.len() {
+                    return Err(TypeCheckError::WrongNumberOfTypeArguments(
+                        *span,
+                        name.to_string(),
+                        info.type_parameters.len(),
+                        type_arguments.len(),
+                    ));
+                }
+
+                let mut substitution = Substitution::empty();
+                for (parameter, argument) in info.type_parameters.iter().zip(type_arguments) {
+                    let argument_type = self.type_from_annotation(argument)?;
+                    if !matches!(argument_type, Type::Dimension(_)) {
+                        return Err(TypeCheckError::ExpectedDimensionType(
+                            argument.full_span(),
+                            argument_type,
+                        ));
+                    }
+                    substitution
+                        .0
+                        .push((TypeVariable::new(parameter), argument_type));
+                }
+
+                Ok(Type::Struct(Box::new(
+                    self.substitute_type_parameters(info, &substitution)?,
+                )))
+            }
         }
     }
 
-    fn identifier_type(&self, span: Span, name: &str) -> Result<TypeScheme> {
+    /// Replace the type parameters of a generic struct according to the given substitution.
+    fn substitute_type_parameters(
+        &self,
+        info: &StructInfo,
+        substitution: &Substitution,
+    ) -> Result<StructInfo> {
+        let mut info = info.clone();
+        info.apply(substitution)
+            .map_err(|e| TypeCheckError::SubstitutionError(info.name.clone(), e))?;
+        Ok(info)
+    }
+
+    fn identifier_type(&self, span: Span, name: &Symbol) -> Result<TypeScheme> {
         self.env.get_identifier_type(name).ok_or_else(|| {
             let suggestion = suggestion::did_you_mean(
                 self.env
@@ -166,6 +244,108 @@ impl TypeChecker {
         })
     }
 
+    fn check_deprecation(&mut self, span: Span, name: &str) {
+        if let Some(hint) = self.deprecations.get(name) {
+            self.warnings.push(Warning::Deprecated {
+                span,
+                name: name.into(),
+                hint: hint.clone(),
+            });
+        }
+    }
+
+    /// Whether the expression is a number followed by a relative unit, like
+    /// `19 %` or `3 ‰`
+    fn is_relative_literal(&self, expression: &ast::Expression) -> bool {
+        match expression {
+            ast::Expression::BinaryOperator {
+                op: BinaryOperator::Mul,
+                lhs,
+                rhs,
+                ..
+            } => match (lhs.as_ref(), rhs.as_ref()) {
+                (
+                    ast::Expression::Scalar(..),
+                    ast::Expression::UnitIdentifier(_, prefix, _, full_name),
+                ) => prefix.is_none() && self.relative_units.contains(full_name.as_str()),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether a conversion between the two types is a conversion between
+    /// reciprocal dimensions, one of which belongs to a `@reciprocal` unit
+    fn are_reciprocal_dimensions(&self, lhs: &Type, rhs: &Type) -> bool {
+        let (Type::Dimension(lhs), Type::Dimension(rhs)) = (lhs, rhs) else {
+            return false;
+        };
+
+        !lhs.is_scalar()
+            && lhs == &rhs.inverse()
+            && (self.reciprocal_dimensions.contains(lhs)
+                || self.reciprocal_dimensions.contains(rhs))
+    }
+
+    /// Whether two (different) dimension types can be converted into each other
+    /// by multiplying with powers of `c` and `ħ`. With `c = ħ = 1`, a quantity
+    /// with the dimension `Mass^a × Length^b × Time^c` is an energy to the power
+    /// of `a - b - c`, so this is the case if both types are the same power of
+    /// an energy.
+    fn differ_by_natural_units(lhs: &Type, rhs: &Type) -> bool {
+        let (Type::Dimension(lhs), Type::Dimension(rhs)) = (lhs, rhs) else {
+            return false;
+        };
+        let difference = lhs.divide(rhs);
+        if difference.is_scalar() {
+            return false;
+        }
+
+        let mut energy_exponent = Exponent::zero();
+        for (factor, exponent) in &difference.factors {
+            match factor {
+                DTypeFactor::BaseDimension(name) if name == "Mass" => energy_exponent += *exponent,
+                DTypeFactor::BaseDimension(name) if name == "Length" || name == "Time" => {
+                    energy_exponent -= *exponent
+                }
+                _ => return false,
+            }
+        }
+        energy_exponent == Exponent::zero()
+    }
+
+    /// Remember (or forget) the names that are introduced by the given
+    /// definition as deprecated.
+    fn register_deprecations(&mut self, statement: &ast::Statement) {
+        let (name, decorators) = match statement {
+            ast::Statement::DefineBaseUnit(_, name, _, decorators)
+            | ast::Statement::DefineDerivedUnit {
+                identifier: name,
+                decorators,
+                ..
+            }
+            | ast::Statement::DefineFunction {
+                function_name: name,
+                decorators,
+                ..
+            }
+            | ast::Statement::DefineVariable(ast::DefineVariable {
+                identifier: name,
+                decorators,
+                ..
+            }) => (name, decorators),
+            _ => return,
+        };
+
+        let hint = decorator::deprecation(decorators);
+        for (name, _) in decorator::name_and_aliases(name, decorators) {
+            match &hint {
+                Some(hint) => self.deprecations.insert(name.to_string(), hint.clone()),
+                None => self.deprecations.remove(name.as_str()),
+            };
+        }
+    }
+
     fn get_proper_function_reference(
         &self,
         expr: &ast::Expression,
@@ -174,7 +354,7 @@ impl TypeChecker {
             ast::Expression::Identifier(_, name) => self
                 .env
                 .get_function_info(name)
-                .map(|(signature, _)| (name.clone(), signature)),
+                .map(|(signature, _)| (name.to_string(), signature)),
             _ => None,
         }
     }
@@ -288,6 +468,268 @@ impl TypeChecker {
         ))
     }
 
+    /// The type of the field `field_name` of a struct of type `type_`, together
+    /// with the span of the field definition (if known). If the struct type is
+    /// not known yet, a `HasField` constraint is added instead.
+    fn field_type(
+        &mut self,
+        type_: &Type,
+        field_name: &str,
+        ident_span: Span,
+        expr_span: Span,
+    ) -> Result<(Span, Type)> {
+        if type_.is_closed() {
+            let Type::Struct(ref struct_info) = type_ else {
+                return Err(TypeCheckError::FieldAccessOfNonStructType(
+                    ident_span,
+                    expr_span,
+                    field_name.to_string(),
+                    type_.clone(),
+                ));
+            };
+
+            let Some((field_span, field_type)) = struct_info.fields.get(field_name) else {
+                return Err(TypeCheckError::UnknownFieldAccess(
+                    ident_span,
+                    expr_span,
+                    field_name.to_string(),
+                    type_.clone(),
+                ));
+            };
+
+            Ok((*field_span, field_type.clone()))
+        } else {
+            let field_type = self.fresh_type_variable();
+
+            self.constraints
+                .add(Constraint::HasField(
+                    type_.clone(),
+                    field_name.to_string(),
+                    field_type.clone(),
+                ))
+                .ok();
+
+            Ok((ident_span, field_type))
+        }
+    }
+
+    /// `x + 19 %` is a relative change, `x × (1 + 19 %)`. This only applies to
+    /// quantities with a dimension, so that `2 + 5 %` is 2.05.
+    fn relative_change(
+        op: BinaryOperator,
+        span_op: Option<Span>,
+        lhs_checked: typed_ast::Expression,
+        rhs_checked: typed_ast::Expression,
+        lhs_type: Type,
+    ) -> typed_ast::Expression {
+        let scalar = TypeScheme::concrete(Type::scalar());
+        let one = Expression::Scalar(
+            rhs_checked.full_span(),
+            Number::from_f64(1.0),
+            scalar.clone(),
+        );
+        let factor =
+            Expression::BinaryOperator(span_op, op, Box::new(one), Box::new(rhs_checked), scalar);
+        Expression::BinaryOperator(
+            None,
+            BinaryOperator::Mul,
+            Box::new(lhs_checked),
+            Box::new(factor),
+            TypeScheme::concrete(lhs_type),
+        )
+    }
+
+    /// `8 L/100km -> mpg` becomes `1 / (8 L/100km) -> mpg`
+    fn elaborate_reciprocal_conversion(
+        &mut self,
+        lhs: &ast::Expression,
+        rhs: &ast::Expression,
+        span_op: Option<Span>,
+    ) -> Result<typed_ast::Expression> {
+        let reciprocal = ast::Expression::BinaryOperator {
+            op: BinaryOperator::Div,
+            lhs: Box::new(ast::Expression::Scalar(
+                lhs.full_span(),
+                Number::from_f64(1.0),
+            )),
+            rhs: Box::new(lhs.clone()),
+            span_op: None,
+        };
+        self.elaborate_expression(&ast::Expression::BinaryOperator {
+            op: BinaryOperator::ConvertTo,
+            lhs: Box::new(reciprocal),
+            rhs: Box::new(rhs.clone()),
+            span_op,
+        })
+    }
+
+    /// `1 GeV -> kg` becomes `natural_convert(1 GeV, kg)`
+    fn elaborate_natural_conversion(
+        &mut self,
+        lhs: &ast::Expression,
+        rhs: &ast::Expression,
+        span_op: Option<Span>,
+    ) -> Result<typed_ast::Expression> {
+        let function_name = self.natural_conversion.clone().unwrap();
+        let span = span_op.unwrap_or_else(|| rhs.full_span());
+        self.elaborate_expression(&ast::Expression::FunctionCall(
+            span,
+            lhs.full_span().extend(&rhs.full_span()),
+            Box::new(ast::Expression::Identifier(span, function_name)),
+            vec![lhs.clone(), rhs.clone()],
+        ))
+    }
+
+    fn elaborate_update_struct(
+        &mut self,
+        full_span: Span,
+        expr: &ast::Expression,
+        fields: &[(Span, Vec<Symbol>, ast::Expression)],
+    ) -> Result<typed_ast::Expression> {
+        let expr_checked = self.elaborate_expression(expr)?;
+        let type_ = expr_checked.get_type();
+
+        let mut fields_checked = vec![];
+        for (path_span, path, value) in fields {
+            let value_checked = self.elaborate_expression(value)?;
+
+            let mut field_span = *path_span;
+            let mut field_type = type_.clone();
+            for field_name in path {
+                (field_span, field_type) =
+                    self.field_type(&field_type, field_name, *path_span, expr.full_span())?;
+            }
+
+            let found_type = value_checked.get_type();
+            if self
+                .add_equal_constraint(&found_type, &field_type)
+                .is_trivially_violated()
+            {
+                return Err(TypeCheckError::IncompatibleTypesForStructField(
+                    field_span,
+                    field_type,
+                    value.full_span(),
+                    found_type,
+                ));
+            }
+
+            fields_checked.push((path.iter().map(Symbol::to_string).collect(), value_checked));
+        }
+
+        Ok(Expression::UpdateStruct(
+            full_span,
+            Box::new(expr_checked),
+            fields_checked,
+            TypeScheme::concrete(type_),
+        ))
+    }
+
+    fn elaborate_dict(
+        &mut self,
+        span: Span,
+        entries: &[(ast::Expression, ast::Expression)],
+    ) -> Result<typed_ast::Expression> {
+        let mut entries_checked = vec![];
+        for (key, value) in entries {
+            let key_checked = self.elaborate_expression(key)?;
+            let key_type = key_checked.get_type();
+            if self
+                .add_equal_constraint(&key_type, &Type::String)
+                .is_trivially_violated()
+            {
+                return Err(TypeCheckError::NonStringDictKey(
+                    key_checked.full_span(),
+                    key_type,
+                ));
+            }
+            entries_checked.push((key_checked, self.elaborate_expression(value)?));
+        }
+
+        let value_types: Vec<Type> = entries_checked.iter().map(|(_, v)| v.get_type()).collect();
+
+        let result_value_type = if value_types.is_empty() {
+            self.fresh_type_variable()
+        } else if value_types[0].is_closed() {
+            value_types[0].clone()
+        } else {
+            let type_ = self.fresh_type_variable();
+            self.add_equal_constraint(&value_types[0], &type_).ok();
+            type_
+        };
+
+        for ((_, subsequent_value), type_of_subsequent_value) in
+            entries_checked.iter().zip(value_types.iter()).skip(1)
+        {
+            if self
+                .add_equal_constraint(&result_value_type, type_of_subsequent_value)
+                .is_trivially_violated()
+            {
+                return Err(TypeCheckError::IncompatibleTypesInDict(
+                    entries_checked[0].1.full_span(),
+                    result_value_type.clone(),
+                    subsequent_value.full_span(),
+                    type_of_subsequent_value.clone(),
+                ));
+            }
+        }
+
+        Ok(typed_ast::Expression::Dict(
+            span,
+            entries_checked,
+            TypeScheme::concrete(result_value_type),
+        ))
+    }
+
+    fn elaborate_range(
+        &mut self,
+        span_op: Span,
+        start: &ast::Expression,
+        end: &ast::Expression,
+        step: Option<&ast::Expression>,
+        inclusive: bool,
+    ) -> Result<typed_ast::Expression> {
+        let start = self.elaborate_expression(start)?;
+        let end = self.elaborate_expression(end)?;
+        let step = step
+            .map(|step| self.elaborate_expression(step))
+            .transpose()?;
+
+        let element_type = start.get_type();
+        self.enforce_dtype(&element_type, start.full_span())?;
+
+        for bound in std::iter::once(&end).chain(step.as_ref()) {
+            if self
+                .add_equal_constraint(&element_type, &bound.get_type())
+                .is_trivially_violated()
+            {
+                return Err(TypeCheckError::IncompatibleTypesInRange(
+                    start.full_span(),
+                    element_type,
+                    bound.full_span(),
+                    bound.get_type(),
+                ));
+            }
+        }
+
+        // Without an explicit step, we count in steps of one
+        if step.is_none()
+            && self
+                .add_equal_constraint(&element_type, &Type::scalar())
+                .is_trivially_violated()
+        {
+            return Err(TypeCheckError::MissingStepInRange(span_op, element_type));
+        }
+
+        Ok(typed_ast::Expression::Range(
+            span_op,
+            Box::new(start),
+            Box::new(end),
+            step.map(Box::new),
+            inclusive,
+            TypeScheme::concrete(element_type),
+        ))
+    }
+
     fn elaborate_expression(&mut self, ast: &ast::Expression) -> Result<typed_ast::Expression> {
         Ok(match ast {
             ast::Expression::Scalar(span, n)
@@ -306,6 +748,7 @@ impl TypeChecker {
             }
             ast::Expression::Identifier(span, name) => {
                 let type_scheme = self.identifier_type(*span, name)?.clone();
+                self.check_deprecation(*span, name);
 
                 let ty = match type_scheme {
                     TypeScheme::Concrete(ty) => ty,
@@ -319,10 +762,11 @@ impl TypeChecker {
                     }
                 };
 
-                typed_ast::Expression::Identifier(*span, name.clone(), TypeScheme::concrete(ty))
+                typed_ast::Expression::Identifier(*span, name.to_string(), TypeScheme::concrete(ty))
             }
             ast::Expression::UnitIdentifier(span, prefix, name, full_name) => {
                 let type_scheme = self.identifier_type(*span, name)?.clone();
+                self.check_deprecation(*span, name);
 
                 let qt = type_scheme.instantiate(&mut self.name_generator);
 
@@ -333,8 +777,8 @@ impl TypeChecker {
                 typed_ast::Expression::UnitIdentifier(
                     *span,
                     *prefix,
-                    name.clone(),
-                    full_name.clone(),
+                    name.to_string(),
+                    full_name.to_string(),
                     TypeScheme::concrete(qt.inner),
                 )
             }
@@ -386,7 +830,33 @@ impl TypeChecker {
                 let lhs_type = lhs_checked.get_type();
                 let rhs_type = rhs_checked.get_type();
 
-                if rhs_type.is_fn_type() && op == &BinaryOperator::ConvertTo {
+                // `f (x)` is parsed as an implicit multiplication, since the
+                // parser does not know whether `f` is a function or a unit
+                if op == &BinaryOperator::Mul && span_op.is_none() && lhs_type.is_fn_type() {
+                    if let ast::Expression::Identifier(span, name) = lhs.as_ref() {
+                        return Err(TypeCheckError::ImplicitMultiplicationWithFunction(
+                            *span,
+                            name.to_string(),
+                        ));
+                    }
+                }
+
+                if matches!(op, BinaryOperator::Add | BinaryOperator::Sub)
+                    && self.is_relative_literal(rhs)
+                    && matches!(&lhs_type, Type::Dimension(d) if !d.is_scalar())
+                    && lhs_type.is_closed()
+                {
+                    Self::relative_change(*op, *span_op, lhs_checked, rhs_checked, lhs_type)
+                } else if op == &BinaryOperator::ConvertTo
+                    && self.are_reciprocal_dimensions(&lhs_type, &rhs_type)
+                {
+                    return self.elaborate_reciprocal_conversion(lhs, rhs, *span_op);
+                } else if op == &BinaryOperator::ConvertTo
+                    && self.natural_conversion.is_some()
+                    && Self::differ_by_natural_units(&lhs_type, &rhs_type)
+                {
+                    return self.elaborate_natural_conversion(lhs, rhs, *span_op);
+                } else if rhs_type.is_fn_type() && op == &BinaryOperator::ConvertTo {
                     let (parameter_types, return_type) = match rhs_type {
                         Type::Fn(p, r) => (p, r),
                         _ => unreachable!(),
@@ -451,6 +921,18 @@ impl TypeChecker {
                             Box::new(rhs_checked),
                             TypeScheme::concrete(Type::DateTime),
                         )
+                    } else if (*op == BinaryOperator::Equal || *op == BinaryOperator::NotEqual)
+                        && rhs_is_datetime
+                    {
+                        // Two points in time are equal if they refer to the same
+                        // instant, even if they are given in different time zones
+                        typed_ast::Expression::BinaryOperator(
+                            *span_op,
+                            *op,
+                            Box::new(lhs_checked),
+                            Box::new(rhs_checked),
+                            TypeScheme::concrete(Type::Boolean),
+                        )
                     } else {
                         return Err(TypeCheckError::IncompatibleTypesInOperator(
                             span_op.unwrap_or_else(|| {
@@ -509,6 +991,7 @@ impl TypeChecker {
                                         }
                                         typed_ast::BinaryOperator::LogicalAnd => "and".into(),
                                         typed_ast::BinaryOperator::LogicalOr => "or".into(),
+                                        typed_ast::BinaryOperator::Of => "'of'".into(),
                                     },
                                     span_expected: lhs.full_span(),
                                     expected_name: " left hand side",
@@ -736,11 +1219,32 @@ impl TypeChecker {
 
                             Type::Boolean
                         }
+                        typed_ast::BinaryOperator::Of => {
+                            if self
+                                .add_equal_constraint(&lhs_type, &Type::scalar())
+                                .is_trivially_violated()
+                            {
+                                return Err(TypeCheckError::NonScalarFraction(
+                                    lhs.full_span(),
+                                    lhs_type,
+                                ));
+                            }
+                            self.enforce_dtype(&rhs_type, rhs.full_span())?;
+
+                            rhs_type
+                        }
+                    };
+
+                    // `p of x` is an ordinary multiplication after type checking
+                    let op = if *op == BinaryOperator::Of {
+                        BinaryOperator::Mul
+                    } else {
+                        *op
                     };
 
                     typed_ast::Expression::BinaryOperator(
                         *span_op,
-                        *op,
+                        op,
                         Box::new(lhs_checked),
                         Box::new(rhs_checked),
                         TypeScheme::concrete(type_),
@@ -764,6 +1268,7 @@ impl TypeChecker {
                 if let Some((name, signature)) = self.get_proper_function_reference(callable) {
                     // TODO: there is probably a better way to get around borrowing issues here
                     let signature = signature.clone();
+                    self.check_deprecation(callable.full_span(), &name);
 
                     self.proper_function_call(
                         span,
@@ -854,6 +1359,9 @@ impl TypeChecker {
                 }
             }
             ast::Expression::Boolean(span, val) => typed_ast::Expression::Boolean(*span, *val),
+            ast::Expression::DateTime(span, dt) => {
+                typed_ast::Expression::DateTime(*span, dt.clone())
+            }
             ast::Expression::String(span, parts) => typed_ast::Expression::String(
                 *span,
                 parts
@@ -919,10 +1427,22 @@ impl TypeChecker {
                     .map(|(_, n, v)| Ok((n.to_string(), self.elaborate_expression(v)?)))
                     .collect::<Result<Vec<_>>>()?;
 
-                let Some(struct_info) = self.structs.get(name).cloned() else {
-                    return Err(TypeCheckError::UnknownStruct(*ident_span, name.clone()));
+                let Some(struct_info) = self.structs.get(name.as_str()).cloned() else {
+                    return Err(TypeCheckError::UnknownStruct(*ident_span, name.to_string()));
                 };
 
+                // Generic structs are instantiated with fresh type variables
+                // for their type parameters
+                let mut substitution = Substitution::empty();
+                for parameter in &struct_info.type_parameters {
+                    let type_variable = self.fresh_type_variable();
+                    self.add_dtype_constraint(&type_variable).ok();
+                    substitution
+                        .0
+                        .push((TypeVariable::new(parameter), type_variable));
+                }
+                let struct_info = self.substitute_type_parameters(&struct_info, &substitution)?;
+
                 let mut seen_fields = HashMap::new();
 
                 for ((field, expr), span) in
@@ -987,49 +1507,23 @@ impl TypeChecker {
 
                 let type_ = expr_checked.get_type();
 
-                let field_type = if type_.is_closed() {
-                    let Type::Struct(ref struct_info) = type_ else {
-                        return Err(TypeCheckError::FieldAccessOfNonStructType(
-                            *ident_span,
-                            expr.full_span(),
-                            field_name.to_string(),
-                            type_.clone(),
-                        ));
-                    };
-
-                    let Some((_, field_type)) = struct_info.fields.get(field_name) else {
-                        return Err(TypeCheckError::UnknownFieldAccess(
-                            *ident_span,
-                            expr.full_span(),
-                            field_name.to_string(),
-                            type_.clone(),
-                        ));
-                    };
-
-                    field_type.clone()
-                } else {
-                    let field_type = self.fresh_type_variable();
-
-                    self.constraints
-                        .add(Constraint::HasField(
-                            type_.clone(),
-                            field_name.clone(),
-                            field_type.clone(),
-                        ))
-                        .ok();
-
-                    field_type
-                };
+                let (_, field_type) =
+                    self.field_type(&type_, field_name, *ident_span, expr.full_span())?;
 
                 Expression::AccessField(
                     *ident_span,
                     *full_span,
                     Box::new(expr_checked),
-                    field_name.to_owned(),
+                    field_name.to_string(),
                     TypeScheme::concrete(type_),
                     TypeScheme::concrete(field_type),
                 )
             }
+            ast::Expression::UpdateStruct {
+                full_span,
+                expr,
+                fields,
+            } => self.elaborate_update_struct(*full_span, expr, fields)?,
             ast::Expression::List(span, elements) => {
                 let elements_checked = elements
                     .iter()
@@ -1073,6 +1567,14 @@ impl TypeChecker {
                     TypeScheme::concrete(result_element_type),
                 )
             }
+            ast::Expression::Dict(span, entries) => self.elaborate_dict(*span, entries)?,
+            ast::Expression::Range {
+                span_op,
+                start,
+                end,
+                step,
+                inclusive,
+            } => self.elaborate_range(*span_op, start, end, step.as_deref(), *inclusive)?,
             ast::Expression::TypedHole(span) => {
                 let type_ = self.fresh_type_variable();
                 typed_ast::Expression::TypedHole(*span, TypeScheme::concrete(type_))
@@ -1178,17 +1680,17 @@ impl TypeChecker {
 
         for (name, _) in decorator::name_and_aliases(identifier, decorators) {
             self.env
-                .add(name.clone(), type_deduced.clone(), *identifier_span, false);
+                .add(name, type_deduced.clone(), *identifier_span, false);
 
             self.value_namespace.add_identifier_allow_override(
-                name.clone(),
+                name.to_string(),
                 *identifier_span,
                 "constant".to_owned(),
             )?;
         }
 
         Ok(typed_ast::DefineVariable(
-            identifier.clone(),
+            identifier.to_string(),
             decorators.clone(),
             expr_checked,
             type_annotation.clone(),
@@ -1203,7 +1705,7 @@ impl TypeChecker {
                 let checked_expr = self.elaborate_expression(expr)?;
                 for &identifier in LAST_RESULT_IDENTIFIERS {
                     self.env.add_predefined(
-                        identifier.into(),
+                        &Symbol::new(identifier),
                         TypeScheme::concrete(checked_expr.get_type()),
                     );
                 }
@@ -1241,16 +1743,12 @@ impl TypeChecker {
                         .into()
                 };
                 for (name, _) in decorator::name_and_aliases(unit_name, decorators) {
-                    self.env.add(
-                        name.clone(),
-                        Type::Dimension(type_specified.clone()),
-                        *span,
-                        true,
-                    );
+                    self.env
+                        .add(name, Type::Dimension(type_specified.clone()), *span, true);
                 }
 
                 typed_ast::Statement::DefineBaseUnit(
-                    unit_name.clone(),
+                    unit_name.to_string(),
                     decorators.clone(),
                     type_annotation.clone().map(TypeAnnotation::TypeExpression),
                     TypeScheme::concrete(Type::Dimension(type_specified)),
@@ -1279,10 +1777,22 @@ impl TypeChecker {
 
                 for (name, _) in decorator::name_and_aliases(identifier, decorators) {
                     self.env
-                        .add(name.clone(), type_deduced.clone(), *identifier_span, true);
+                        .add(name, type_deduced.clone(), *identifier_span, true);
+                }
+                if decorators.contains(&Decorator::Relative) {
+                    self.relative_units.insert(identifier.to_string());
+                } else {
+                    self.relative_units.remove(identifier.as_str());
+                }
+                if let (true, Type::Dimension(dtype)) =
+                    (decorators.contains(&Decorator::Reciprocal), &type_deduced)
+                {
+                    if !self.reciprocal_dimensions.contains(dtype) {
+                        self.reciprocal_dimensions.push(dtype.clone());
+                    }
                 }
                 typed_ast::Statement::DefineDerivedUnit(
-                    identifier.clone(),
+                    identifier.to_string(),
                     expr_checked,
                     decorators.clone(),
                     type_annotation.clone(),
@@ -1290,6 +1800,53 @@ impl TypeChecker {
                     crate::markup::empty(),
                 )
             }
+            ast::Statement::DefineUnitAlias {
+                alias_span,
+                alias,
+                accepts_prefix,
+                unit_span,
+                unit,
+            } => {
+                let type_scheme = self.identifier_type(*unit_span, unit)?;
+                self.env.add_scheme(alias, type_scheme, *alias_span, true);
+
+                typed_ast::Statement::DefineUnitAlias(
+                    alias.to_string(),
+                    accepts_prefix.unwrap_or(AcceptsPrefix::only_long()),
+                    unit.to_string(),
+                )
+            }
+            ast::Statement::SetPreferredUnit {
+                span,
+                dimension,
+                expr,
+            } => {
+                let dtype: DType = self
+                    .registry
+                    .get_base_representation(dimension)
+                    .map_err(TypeCheckError::RegistryError)?
+                    .into();
+                if dtype.is_scalar() {
+                    return Err(TypeCheckError::NoDimensionlessPreferredUnit(
+                        dimension.full_span(),
+                    ));
+                }
+
+                let type_annotation = TypeAnnotation::TypeExpression(dimension.clone());
+                let (expr_checked, _) = self._elaborate_inner(ElaborationDefinitionArgs {
+                    identifier_span: *span,
+                    expr,
+                    type_annotation_span: Some(dimension.full_span()),
+                    type_annotation: Some(&type_annotation),
+                    operation: "preferred unit",
+                    expected_name: "specified dimension",
+                    actual_name: "   actual dimension",
+                    actual_name_for_fix: "right hand side expression",
+                    elaboration_kind: "preferred unit",
+                })?;
+
+                typed_ast::Statement::SetPreferredUnit(dimension.clone(), expr_checked)
+            }
             ast::Statement::DefineFunction {
                 function_name_span,
                 function_name,
@@ -1302,13 +1859,13 @@ impl TypeChecker {
             } => {
                 if body.is_none() {
                     self.value_namespace.add_identifier(
-                        function_name.clone(),
+                        function_name.to_string(),
                         *function_name_span,
                         "foreign function".to_owned(),
                     )?;
                 } else {
                     self.value_namespace.add_identifier_allow_override(
-                        function_name.clone(),
+                        function_name.to_string(),
                         *function_name_span,
                         "function".to_owned(),
                     )?;
@@ -1326,23 +1883,27 @@ impl TypeChecker {
                     if self.type_namespace.has_identifier(type_parameter) {
                         return Err(TypeCheckError::TypeParameterNameClash(
                             *span,
-                            type_parameter.clone(),
+                            type_parameter.to_string(),
                         ));
                     }
 
                     self.type_namespace
-                        .add_identifier(type_parameter.clone(), *span, "type parameter".to_owned())
+                        .add_identifier(
+                            type_parameter.to_string(),
+                            *span,
+                            "type parameter".to_owned(),
+                        )
                         .ok(); // TODO: is this call even correct?
 
                     self.registry.introduced_type_parameters.push((
                         *span,
-                        type_parameter.clone(),
+                        type_parameter.to_string(),
                         bound.clone(),
                     ));
 
                     match bound {
                         Some(TypeParameterBound::Dim) => {
-                            self.add_dtype_constraint(&Type::TPar(type_parameter.clone()))
+                            self.add_dtype_constraint(&Type::TPar(type_parameter.to_string()))
                                 .ok();
                         }
                         None => {}
@@ -1364,12 +1925,12 @@ impl TypeChecker {
                     if is_ffi_function && annotated_type.is_none() {
                         return Err(TypeCheckError::ForeignFunctionNeedsTypeAnnotations(
                             *parameter_span,
-                            parameter.clone(),
+                            parameter.to_string(),
                         ));
                     }
 
                     self.env.add_scheme(
-                        parameter.clone(),
+                        parameter,
                         TypeScheme::make_quantified(parameter_type.clone()),
                         *parameter_span,
                         false,
@@ -1396,7 +1957,9 @@ impl TypeChecker {
 
                 let parameters: Vec<_> = typed_parameters
                     .iter()
-                    .map(|(span, name, _, annotation)| (*span, name.clone(), (*annotation).clone()))
+                    .map(|(span, name, _, annotation)| {
+                        (*span, name.to_string(), (*annotation).clone())
+                    })
                     .collect();
                 let parameter_types = typed_parameters
                     .iter()
@@ -1407,11 +1970,14 @@ impl TypeChecker {
                     TypeScheme::Concrete(Type::Fn(parameter_types, Box::new(return_type.clone())));
 
                 self.env.add_function(
-                    function_name.clone(),
+                    function_name,
                     FunctionSignature {
-                        name: function_name.clone(),
+                        name: function_name.to_string(),
                         definition_span: *function_name_span,
-                        type_parameters: type_parameters.clone(),
+                        type_parameters: type_parameters
+                            .iter()
+                            .map(|(span, name, bound)| (*span, name.to_string(), bound.clone()))
+                            .collect(),
                         parameters,
                         return_type_annotation: return_type_annotation.clone(),
                         fn_type: fn_type.clone(),
@@ -1494,14 +2060,14 @@ impl TypeChecker {
                     if !ffi::functions().contains_key(function_name.as_str()) {
                         return Err(TypeCheckError::UnknownForeignFunction(
                             *function_name_span,
-                            function_name.clone(),
+                            function_name.to_string(),
                         ));
                     }
 
                     annotated_return_type.ok_or_else(|| {
                         TypeCheckError::ForeignFunctionNeedsTypeAnnotations(
                             *function_name_span,
-                            function_name.clone(),
+                            function_name.to_string(),
                         )
                     })?
                 };
@@ -1520,21 +2086,24 @@ impl TypeChecker {
                 self.type_namespace.restore();
                 self.env.restore();
                 self.env
-                    .add_function(function_name.clone(), signature.clone(), metadata.clone());
+                    .add_function(function_name, signature.clone(), metadata.clone());
+                if decorators.contains(&Decorator::NaturalConversion) {
+                    self.natural_conversion = Some(function_name.clone());
+                }
 
                 typed_ast::Statement::DefineFunction(
-                    function_name.clone(),
+                    function_name.to_string(),
                     decorators.clone(),
                     type_parameters
                         .iter()
-                        .map(|(_, name, bound)| (name.clone(), bound.clone()))
+                        .map(|(_, name, bound)| (name.to_string(), bound.clone()))
                         .collect(),
                     typed_parameters
                         .iter()
                         .map(|(span, name, _, ref type_annotation)| {
                             (
                                 *span,
-                                name.clone(),
+                                name.to_string(),
                                 (*type_annotation).clone(),
                                 crate::markup::empty(),
                             )
@@ -1549,7 +2118,7 @@ impl TypeChecker {
             }
             ast::Statement::DefineDimension(name_span, name, dexprs) => {
                 self.type_namespace.add_identifier(
-                    name.clone(),
+                    name.to_string(),
                     *name_span,
                     "dimension".to_owned(),
                 )?;
@@ -1572,7 +2141,7 @@ impl TypeChecker {
                         if alternative_base_representation != base_representation {
                             return Err(
                                 TypeCheckError::IncompatibleAlternativeDimensionExpression(
-                                    name.clone(),
+                                    name.to_string(),
                                     dexpr.full_span(),
                                     base_representation,
                                     alternative_expr.full_span(),
@@ -1586,7 +2155,7 @@ impl TypeChecker {
                         .add_base_dimension(name)
                         .map_err(TypeCheckError::RegistryError)?;
                 }
-                typed_ast::Statement::DefineDimension(name.clone(), dexprs.clone())
+                typed_ast::Statement::DefineDimension(name.to_string(), dexprs.clone())
             }
             ast::Statement::ProcedureCall(span, kind @ ProcedureKind::Type, args) => {
                 if args.len() != 1 {
@@ -1606,6 +2175,31 @@ impl TypeChecker {
 
                 typed_ast::Statement::ProcedureCall(kind.clone(), checked_args)
             }
+            ast::Statement::ProcedureCall(span, kind @ ProcedureKind::DefineUncertainty, args) => {
+                let checked_args = args
+                    .iter()
+                    .map(|e| self.elaborate_expression(e))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let uncertainty = &checked_args[0];
+                for constant in &checked_args[1..] {
+                    if self
+                        .add_equal_constraint(&constant.get_type(), &uncertainty.get_type())
+                        .is_trivially_violated()
+                    {
+                        return Err(TypeCheckError::IncompatibleTypesInAnnotation(
+                            "uncertainty decorator".into(),
+                            *span,
+                            constant.get_type(),
+                            constant.full_span(),
+                            uncertainty.get_type(),
+                            uncertainty.full_span(),
+                        ));
+                    }
+                }
+
+                typed_ast::Statement::ProcedureCall(kind.clone(), checked_args)
+            }
             ast::Statement::ProcedureCall(span, kind, args) => {
                 let procedure = ffi::procedures().get(kind).unwrap();
                 if !procedure.arity.contains(&args.len()) {
@@ -1668,27 +2262,48 @@ impl TypeChecker {
                             }
                         }
                     }
-                    ProcedureKind::Type => {
-                        unreachable!("type() calls have a special handling above")
+                    ProcedureKind::Type | ProcedureKind::DefineUncertainty => {
+                        unreachable!("{kind:?} has a special handling above")
                     }
                 }
 
                 typed_ast::Statement::ProcedureCall(kind.clone(), checked_args)
             }
-            ast::Statement::ModuleImport(_, _) => {
+            ast::Statement::ModuleImport(_, _, _) => {
                 unreachable!("Modules should have been inlined by now")
             }
             ast::Statement::DefineStruct {
                 struct_name_span,
                 struct_name,
+                type_parameters,
                 fields,
             } => {
                 self.type_namespace.add_identifier(
-                    struct_name.clone(),
+                    struct_name.to_string(),
                     *struct_name_span,
                     "struct".to_owned(),
                 )?;
 
+                for (span, type_parameter, bound) in type_parameters {
+                    if self.type_namespace.has_identifier(type_parameter) {
+                        return Err(TypeCheckError::TypeParameterNameClash(
+                            *span,
+                            type_parameter.to_string(),
+                        ));
+                    }
+
+                    // Fields of structs can only be generic over dimensions
+                    if bound.is_none() {
+                        return Err(TypeCheckError::MissingDimBound(*span));
+                    }
+
+                    self.registry.introduced_type_parameters.push((
+                        *span,
+                        type_parameter.to_string(),
+                        bound.clone(),
+                    ));
+                }
+
                 let mut seen_fields = HashMap::new();
 
                 for (span, field, _) in fields {
@@ -1705,16 +2320,20 @@ impl TypeChecker {
 
                 let struct_info = StructInfo {
                     definition_span: *struct_name_span,
-                    name: struct_name.clone(),
+                    name: struct_name.to_string(),
+                    type_parameters: type_parameters
+                        .iter()
+                        .map(|(_, name, _)| name.to_string())
+                        .collect(),
                     fields: fields
                         .iter()
                         .map(|(span, name, type_)| {
-                            Ok((name.clone(), (*span, self.type_from_annotation(type_)?)))
+                            Ok((name.to_string(), (*span, self.type_from_annotation(type_)?)))
                         })
                         .collect::<Result<_>>()?,
                 };
                 self.structs
-                    .insert(struct_name.clone(), struct_info.clone());
+                    .insert(struct_name.to_string(), struct_info.clone());
 
                 typed_ast::Statement::DefineStruct(struct_info)
             }
@@ -1824,8 +2443,7 @@ impl TypeChecker {
                     .iter_relevant_matches()
                     .filter(|(_, t)| t == &type_of_hole)
                     .take(10)
-                    .map(|(n, _)| n)
-                    .cloned()
+                    .map(|(n, _)| n.to_string())
                     .collect(),
             ));
         }
@@ -1841,16 +2459,26 @@ impl TypeChecker {
 
         for statement in statements.into_iter() {
             checked_statements.push(self.check_statement(&statement)?);
+            self.register_deprecations(&statement);
         }
 
         Ok(checked_statements)
     }
 
+    /// Return the warnings that have been collected so far, and reset the list.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
     pub(crate) fn registry(&self) -> &DimensionRegistry {
         &self.registry
     }
 
     pub fn lookup_function(&self, name: &str) -> Option<(&FunctionSignature, &FunctionMetadata)> {
-        self.env.get_function_info(name)
+        self.env.get_function_info(&Symbol::new(name))
+    }
+
+    pub fn lookup_struct(&self, name: &str) -> Option<&StructInfo> {
+        self.structs.get(name)
     }
 }
@@ -85,6 +85,7 @@ impl ApplySubstitution for Type {
                 Ok(())
             }
             Type::List(element_type) => element_type.apply(s),
+            Type::Dict(value_type) => value_type.apply(s),
         }
     }
 }
@@ -188,6 +189,7 @@ impl ApplySubstitution for Expression {
                 else_.apply(s)
             }
             Expression::String(_, _) => Ok(()),
+            Expression::DateTime(_, _) => Ok(()),
             Expression::InstantiateStruct(_, initializers, info) => {
                 for (_, expr) in initializers {
                     expr.apply(s)?;
@@ -199,12 +201,34 @@ impl ApplySubstitution for Expression {
                 struct_type.apply(s)?;
                 field_type.apply(s)
             }
+            Expression::UpdateStruct(_, instance, fields, struct_type) => {
+                instance.apply(s)?;
+                for (_, value) in fields {
+                    value.apply(s)?;
+                }
+                struct_type.apply(s)
+            }
             Expression::List(_, elements, element_type) => {
                 for element in elements {
                     element.apply(s)?;
                 }
                 element_type.apply(s)
             }
+            Expression::Dict(_, entries, value_type) => {
+                for (key, value) in entries {
+                    key.apply(s)?;
+                    value.apply(s)?;
+                }
+                value_type.apply(s)
+            }
+            Expression::Range(_, start, end, step, _, element_type) => {
+                start.apply(s)?;
+                end.apply(s)?;
+                if let Some(step) = step {
+                    step.apply(s)?;
+                }
+                element_type.apply(s)
+            }
             Expression::TypedHole(_, type_) => type_.apply(s),
         }
     }
@@ -218,6 +242,7 @@ impl ApplySubstitution for Statement {
                 e.apply(s)?;
                 type_.apply(s)
             }
+            Statement::SetPreferredUnit(_, e) => e.apply(s),
             Statement::DefineFunction(_, _, _, _, body, local_variables, fn_type, _, _) => {
                 for local_variable in local_variables {
                     local_variable.2.apply(s)?;
@@ -229,6 +254,7 @@ impl ApplySubstitution for Statement {
                 fn_type.apply(s)
             }
             Statement::DefineDimension(_, _) => Ok(()),
+            Statement::DefineUnitAlias(_, _, _) => Ok(()),
             Statement::DefineBaseUnit(_, _, _annotation, type_) => type_.apply(s),
             Statement::DefineDerivedUnit(_, e, _, _annotation, type_, _) => {
                 e.apply(s)?;
@@ -19,6 +19,10 @@ const TEST_PRELUDE: &str = "
     unit b: B
     unit c: C = a * b
 
+    @relative
+    @aliases(%: short)
+    unit percent = 0.01
+
     fn returns_a() -> A = a
     fn takes_a_returns_a(x: A) -> A = x
     fn takes_a_returns_b(x: A) -> B = b
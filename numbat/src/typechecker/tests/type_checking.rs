@@ -628,6 +628,40 @@ fn structs() {
         TypeCheckError::MissingFieldsInStructInstantiation(..)
     ));
 
+    assert_successful_typecheck(
+        "
+          struct Outer { inner: SomeStruct, c: C }
+
+          let s = SomeStruct { a: 1a, b: 1b }
+          let t = { s with a: 2a }
+          let o = { Outer { inner: s, c: 1c } with inner.b: 2b, c: 3c }
+
+          let SomeStruct { a: x, b: y } = o.inner
+          let check_a: A = x
+          let check_b: B = y
+          ",
+    );
+
+    assert!(matches!(
+        get_typecheck_error("{ SomeStruct {a: 1a, b: 1b} with a: 1b }"),
+        TypeCheckError::IncompatibleTypesForStructField(..)
+    ));
+
+    assert!(matches!(
+        get_typecheck_error("{ SomeStruct {a: 1a, b: 1b} with foo: 1 }"),
+        TypeCheckError::UnknownFieldAccess(_, _, field, _) if field == "foo"
+    ));
+
+    assert!(matches!(
+        get_typecheck_error("{ SomeStruct {a: 1a, b: 1b} with a.foo: 1 }"),
+        TypeCheckError::FieldAccessOfNonStructType(_, _, field, _) if field == "foo"
+    ));
+
+    assert!(matches!(
+        get_typecheck_error("let SomeStruct { foo } = SomeStruct {a: 1a, b: 1b}"),
+        TypeCheckError::UnknownFieldAccess(_, _, field, _) if field == "foo"
+    ));
+
     // Regression test for https://github.com/sharkdp/numbat/issues/459
     assert_successful_typecheck("id(SomeStruct { a: 1a, b: 1b }).a");
 
@@ -671,6 +705,86 @@ fn lists() {
     ));
 }
 
+#[test]
+fn dicts() {
+    assert_successful_typecheck("{}");
+    assert_successful_typecheck("{\"x\": 1 a, \"y\": 2 a}");
+    assert_successful_typecheck("{\"x\": [1 b], \"y\": []}");
+    assert_successful_typecheck("let d: Dict<A> = {\"x\": 1 a}");
+    assert_successful_typecheck("fn f<T>(key: String, value: T) -> Dict<T> = {key: value}");
+
+    assert!(matches!(
+        get_typecheck_error("{1: 1 a}"),
+        TypeCheckError::NonStringDictKey(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error("{\"x\": 1 a, \"y\": 1 b}"),
+        TypeCheckError::IncompatibleTypesInDict(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error("let d: Dict<B> = {\"x\": 1 a}"),
+        TypeCheckError::IncompatibleTypesInAnnotation(..)
+    ));
+}
+
+#[test]
+fn ranges() {
+    assert_successful_typecheck("1 ..= 10");
+    assert_successful_typecheck("let xs: List<Scalar> = 10 .. 0 step -2");
+    assert_successful_typecheck("let xs: List<A> = 0 a ..= 1 a step 0.1 a");
+    assert_successful_typecheck("fn f<D: Dim>(x: D) -> List<D> = x ..= 2 x step x");
+
+    assert!(matches!(
+        get_typecheck_error("1 a ..= 2 b step 1 a"),
+        TypeCheckError::IncompatibleTypesInRange(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error("1 a ..= 2 a step 1"),
+        TypeCheckError::IncompatibleTypesInRange(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error("1 a ..= 2 a"),
+        TypeCheckError::MissingStepInRange(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error("\"a\" .. \"z\""),
+        TypeCheckError::ExpectedDimensionType(..)
+    ));
+}
+
+#[test]
+fn relative_units() {
+    assert_successful_typecheck("let x: A = 2 a + 19 %");
+    assert_successful_typecheck("let x: A = 2 a - 3 percent");
+    assert_successful_typecheck("let x: Scalar = 5 % + 1 %");
+    assert_successful_typecheck("let x: Scalar = 2 + 5 %");
+
+    assert!(matches!(
+        get_typecheck_error("19 % + 2 a"),
+        TypeCheckError::IncompatibleDimensions(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error("let p = 19 %\n2 a + p"),
+        TypeCheckError::IncompatibleDimensions(..)
+    ));
+}
+
+#[test]
+fn fraction_of() {
+    assert_successful_typecheck("let x: A = 15 % of 2 a");
+    assert_successful_typecheck("let x: C = 0.5 of a * b");
+    assert_successful_typecheck("fn f<D: Dim>(p: Scalar, x: D) -> D = p of x");
+
+    assert!(matches!(
+        get_typecheck_error("2 b of 2 a"),
+        TypeCheckError::NonScalarFraction(_, t) if t == Type::Dimension(type_b())
+    ));
+    assert!(matches!(
+        get_typecheck_error("let x: B = 15 % of 2 a"),
+        TypeCheckError::IncompatibleDimensions(..)
+    ));
+}
+
 #[test]
 fn name_resolution() {
     assert!(matches!(
@@ -12,8 +12,13 @@ use crate::typechecker::qualified_type::QualifiedType;
 use crate::typechecker::type_scheme::TypeScheme;
 use crate::typechecker::TypeCheckError;
 use crate::{
-    decorator::Decorator, markup::Markup, number::Number, prefix::Prefix,
-    prefix_parser::AcceptsPrefix, pretty_print::PrettyPrint, span::Span,
+    decorator::Decorator,
+    markup::Markup,
+    number::Number,
+    prefix::Prefix,
+    prefix_parser::{AcceptsPrefix, ShadowingPolicy},
+    pretty_print::PrettyPrint,
+    span::Span,
 };
 use crate::{markup as m, BaseRepresentation, BaseRepresentationFactor};
 
@@ -271,6 +276,7 @@ impl From<BaseRepresentation> for DType {
 pub struct StructInfo {
     pub definition_span: Span,
     pub name: String,
+    pub type_parameters: Vec<String>,
     pub fields: IndexMap<String, (Span, Type)>,
 }
 
@@ -285,6 +291,7 @@ pub enum Type {
     Fn(Vec<Type>, Box<Type>),
     Struct(Box<StructInfo>),
     List(Box<Type>),
+    Dict(Box<Type>),
 }
 
 impl std::fmt::Display for Type {
@@ -318,6 +325,7 @@ impl std::fmt::Display for Type {
                 )
             }
             Type::List(element_type) => write!(f, "List<{element_type}>"),
+            Type::Dict(value_type) => write!(f, "Dict<{value_type}>"),
         }
     }
 }
@@ -356,6 +364,12 @@ impl PrettyPrint for Type {
                     + element_type.pretty_print()
                     + m::operator(">")
             }
+            Type::Dict(value_type) => {
+                m::type_identifier("Dict")
+                    + m::operator("<")
+                    + value_type.pretty_print()
+                    + m::operator(">")
+            }
         }
     }
 }
@@ -409,6 +423,7 @@ impl Type {
                 vars
             }
             Type::List(element_type) => element_type.type_variables(including_type_parameters),
+            Type::Dict(value_type) => value_type.type_variables(including_type_parameters),
         }
     }
 
@@ -435,10 +450,17 @@ impl Type {
                     .collect(),
                 Box::new(return_type.instantiate(type_variables)),
             ),
-            t @ Type::Struct(_) => t.clone(),
+            Type::Struct(info) => {
+                let mut info = info.as_ref().clone();
+                for (_, field_type) in info.fields.values_mut() {
+                    *field_type = field_type.instantiate(type_variables);
+                }
+                Type::Struct(Box::new(info))
+            }
             Type::List(element_type) => {
                 Type::List(Box::new(element_type.instantiate(type_variables)))
             }
+            Type::Dict(value_type) => Type::Dict(Box::new(value_type.instantiate(type_variables))),
         }
     }
 
@@ -519,6 +541,7 @@ pub enum Expression {
     Boolean(Span, bool),
     Condition(Span, Box<Expression>, Box<Expression>, Box<Expression>),
     String(Span, Vec<StringPart>),
+    DateTime(Span, crate::datetime::Zoned),
     InstantiateStruct(Span, Vec<(String, Expression)>, StructInfo),
     AccessField(
         Span,
@@ -528,7 +551,26 @@ pub enum Expression {
         TypeScheme, // struct type
         TypeScheme, // resulting field type
     ),
+    UpdateStruct(
+        Span,
+        Box<Expression>,
+        Vec<(Vec<String>, Expression)>, // paths of the updated fields, new values
+        TypeScheme,                     // struct type
+    ),
     List(Span, Vec<Expression>, TypeScheme),
+    Dict(
+        Span,
+        Vec<(Expression, Expression)>, // keys, values
+        TypeScheme,                    // value type
+    ),
+    Range(
+        Span,                    // span of the '..' or '..=' operator
+        Box<Expression>,         // start
+        Box<Expression>,         // end
+        Option<Box<Expression>>, // step
+        bool,                    // inclusive
+        TypeScheme,              // element type
+    ),
     TypedHole(Span, TypeScheme),
 }
 
@@ -560,9 +602,15 @@ impl Expression {
                 span_if.extend(&then_expr.full_span())
             }
             Expression::String(span, _) => *span,
+            Expression::DateTime(span, _) => *span,
             Expression::InstantiateStruct(span, _, _) => *span,
             Expression::AccessField(_span, full_span, _, _, _, _) => *full_span,
+            Expression::UpdateStruct(full_span, _, _, _) => *full_span,
             Expression::List(full_span, _, _) => *full_span,
+            Expression::Dict(full_span, _, _) => *full_span,
+            Expression::Range(_, start, end, step, _, _) => start
+                .full_span()
+                .extend(&step.as_ref().unwrap_or(end).full_span()),
             Expression::TypedHole(span, _) => *span,
         }
     }
@@ -609,6 +657,8 @@ pub enum Statement {
         TypeScheme,
         Markup,
     ),
+    DefineUnitAlias(String, AcceptsPrefix, String),
+    SetPreferredUnit(TypeExpression, Expression),
     ProcedureCall(crate::ast::ProcedureKind, Vec<Expression>),
     DefineStruct(StructInfo),
 }
@@ -698,6 +748,8 @@ impl Statement {
             }
             Statement::DefineDimension(_, _) => {}
             Statement::DefineBaseUnit(_, _, _, _) => {}
+            Statement::DefineUnitAlias(_, _, _) => {}
+            Statement::SetPreferredUnit(_, _) => {}
             Statement::DefineDerivedUnit(_, _, _, type_annotation, type_, readable_type) => {
                 *readable_type =
                     Self::create_readable_type(registry, type_, type_annotation, false);
@@ -756,13 +808,21 @@ impl Expression {
             Expression::Boolean(_, _) => Type::Boolean,
             Expression::Condition(_, _, then_, _) => then_.get_type(),
             Expression::String(_, _) => Type::String,
+            Expression::DateTime(_, _) => Type::DateTime,
             Expression::InstantiateStruct(_, _, info_) => Type::Struct(Box::new(info_.clone())),
             Expression::AccessField(_, _, _, _, _struct_type, field_type) => {
                 field_type.unsafe_as_concrete()
             }
+            Expression::UpdateStruct(_, _, _, struct_type) => struct_type.unsafe_as_concrete(),
             Expression::List(_, _, element_type) => {
                 Type::List(Box::new(element_type.unsafe_as_concrete()))
             }
+            Expression::Dict(_, _, value_type) => {
+                Type::Dict(Box::new(value_type.unsafe_as_concrete()))
+            }
+            Expression::Range(_, _, _, _, _, element_type) => {
+                Type::List(Box::new(element_type.unsafe_as_concrete()))
+            }
             Expression::TypedHole(_, type_) => type_.unsafe_as_concrete(),
         }
     }
@@ -780,16 +840,32 @@ impl Expression {
             Expression::Boolean(_, _) => TypeScheme::make_quantified(Type::Boolean),
             Expression::Condition(_, _, then_, _) => then_.get_type_scheme(),
             Expression::String(_, _) => TypeScheme::make_quantified(Type::String),
+            Expression::DateTime(_, _) => TypeScheme::make_quantified(Type::DateTime),
             Expression::InstantiateStruct(_, _, info_) => {
                 TypeScheme::make_quantified(Type::Struct(Box::new(info_.clone())))
             }
             Expression::AccessField(_, _, _, _, _struct_type, field_type) => field_type.clone(),
-            Expression::List(_, _, inner) => match inner {
-                TypeScheme::Concrete(t) => TypeScheme::Concrete(Type::List(Box::new(t.clone()))),
+            Expression::UpdateStruct(_, _, _, struct_type) => struct_type.clone(),
+            Expression::List(_, _, inner) | Expression::Range(_, _, _, _, _, inner) => {
+                match inner {
+                    TypeScheme::Concrete(t) => {
+                        TypeScheme::Concrete(Type::List(Box::new(t.clone())))
+                    }
+                    TypeScheme::Quantified(ngen, qt) => TypeScheme::Quantified(
+                        *ngen,
+                        crate::typechecker::qualified_type::QualifiedType {
+                            inner: Type::List(Box::new(qt.inner.clone())),
+                            bounds: qt.bounds.clone(),
+                        },
+                    ),
+                }
+            }
+            Expression::Dict(_, _, inner) => match inner {
+                TypeScheme::Concrete(t) => TypeScheme::Concrete(Type::Dict(Box::new(t.clone()))),
                 TypeScheme::Quantified(ngen, qt) => TypeScheme::Quantified(
                     *ngen,
                     crate::typechecker::qualified_type::QualifiedType {
-                        inner: Type::List(Box::new(qt.inner.clone())),
+                        inner: Type::Dict(Box::new(qt.inner.clone())),
                         bounds: qt.bounds.clone(),
                     },
                 ),
@@ -833,6 +909,12 @@ fn decorator_markup(decorators: &Vec<Decorator>) -> Markup {
             + match decorator {
                 Decorator::MetricPrefixes => m::decorator("@metric_prefixes"),
                 Decorator::BinaryPrefixes => m::decorator("@binary_prefixes"),
+                Decorator::Private => m::decorator("@private"),
+                Decorator::Relative => m::decorator("@relative"),
+                Decorator::Reciprocal => m::decorator("@reciprocal"),
+                Decorator::Fractional => m::decorator("@fractional"),
+                Decorator::NaturalConversion => m::decorator("@natural_conversion"),
+                Decorator::SimplificationTarget => m::decorator("@simplification_target"),
                 Decorator::Aliases(names) => {
                     m::decorator("@aliases")
                         + m::operator("(")
@@ -857,6 +939,28 @@ fn decorator_markup(decorators: &Vec<Decorator>) -> Markup {
                         + m::string(description)
                         + m::operator(")")
                 }
+                Decorator::Source(source) => {
+                    m::decorator("@source")
+                        + m::operator("(")
+                        + m::string(source)
+                        + m::operator(")")
+                }
+                Decorator::Shadowing(policy) => {
+                    m::decorator("@shadowing")
+                        + m::operator("(")
+                        + m::keyword(match policy {
+                            ShadowingPolicy::Error => "error",
+                            ShadowingPolicy::Warn => "warn",
+                            ShadowingPolicy::Allow => "allow",
+                        })
+                        + m::operator(")")
+                }
+                Decorator::Deprecated(hint) => {
+                    m::decorator("@deprecated")
+                        + m::operator("(")
+                        + m::string(hint)
+                        + m::operator(")")
+                }
             }
             + m::nl();
     }
@@ -1050,12 +1154,46 @@ impl PrettyPrint for Statement {
                     + m::space()
                     + expr.pretty_print()
             }
+            Statement::DefineUnitAlias(alias, accepts_prefix, unit) => {
+                m::keyword("unit")
+                    + m::space()
+                    + m::keyword("alias")
+                    + m::space()
+                    + m::unit(alias)
+                    + accepts_prefix_markup(
+                        &Some(*accepts_prefix).filter(|a| a != &AcceptsPrefix::only_long()),
+                    )
+                    + m::space()
+                    + m::operator("=")
+                    + m::space()
+                    + m::unit(unit)
+            }
+            Statement::SetPreferredUnit(dexpr, expr) => {
+                m::keyword("set")
+                    + m::space()
+                    + m::keyword("preferred_unit")
+                    + m::space()
+                    + dexpr.pretty_print()
+                    + m::space()
+                    + m::operator("=")
+                    + m::space()
+                    + expr.pretty_print()
+            }
+            Statement::ProcedureCall(ProcedureKind::DefineUncertainty, args) => {
+                m::decorator("@uncertainty")
+                    + m::operator("(")
+                    + args[0].pretty_print()
+                    + m::operator(")")
+                    + m::space()
+                    + args[1].pretty_print()
+            }
             Statement::ProcedureCall(kind, args) => {
                 let identifier = match kind {
                     ProcedureKind::Print => "print",
                     ProcedureKind::Assert => "assert",
                     ProcedureKind::AssertEq => "assert_eq",
                     ProcedureKind::Type => "type",
+                    ProcedureKind::DefineUncertainty => unreachable!("handled above"),
                 };
                 m::identifier(identifier)
                     + m::operator("(")
@@ -1066,10 +1204,31 @@ impl PrettyPrint for Statement {
                     .sum()
                     + m::operator(")")
             }
-            Statement::DefineStruct(StructInfo { name, fields, .. }) => {
+            Statement::DefineStruct(StructInfo {
+                name,
+                type_parameters,
+                fields,
+                ..
+            }) => {
                 m::keyword("struct")
                     + m::space()
                     + m::type_identifier(name.clone())
+                    + if type_parameters.is_empty() {
+                        m::empty()
+                    } else {
+                        m::operator("<")
+                            + Itertools::intersperse(
+                                type_parameters.iter().map(|p| {
+                                    m::type_identifier(p)
+                                        + m::operator(":")
+                                        + m::space()
+                                        + m::type_identifier("Dim")
+                                }),
+                                m::operator(",") + m::space(),
+                            )
+                            .sum()
+                            + m::operator(">")
+                    }
                     + m::space()
                     + m::operator("{")
                     + if fields.is_empty() {
@@ -1107,11 +1266,15 @@ fn with_parens(expr: &Expression) -> Markup {
         | Expression::CallableCall(..)
         | Expression::Boolean(..)
         | Expression::String(..)
+        | Expression::DateTime(..)
         | Expression::InstantiateStruct(..)
         | Expression::AccessField(..)
+        | Expression::UpdateStruct(..)
         | Expression::List(..)
+        | Expression::Dict(..)
         | Expression::TypedHole(_, _) => expr.pretty_print(),
         Expression::UnaryOperator { .. }
+        | Expression::Range(..)
         | Expression::BinaryOperator { .. }
         | Expression::BinaryOperatorForDate { .. }
         | Expression::Condition(..) => m::operator("(") + expr.pretty_print() + m::operator(")"),
@@ -1276,6 +1439,7 @@ impl PrettyPrint for Expression {
             }
             Boolean(_, val) => val.pretty_print(),
             String(_, parts) => parts.pretty_print(),
+            DateTime(_, dt) => m::value(format!("@{dt}")),
             Condition(_, condition, then, else_) => {
                 m::keyword("if")
                     + m::space()
@@ -1314,6 +1478,30 @@ impl PrettyPrint for Expression {
             AccessField(_, _, expr, attr, _, _) => {
                 expr.pretty_print() + m::operator(".") + m::identifier(attr)
             }
+            UpdateStruct(_, expr, fields, _) => {
+                m::operator("{")
+                    + m::space()
+                    + expr.pretty_print()
+                    + m::space()
+                    + m::keyword("with")
+                    + m::space()
+                    + itertools::Itertools::intersperse(
+                        fields.iter().map(|(path, e)| {
+                            itertools::Itertools::intersperse(
+                                path.iter().map(m::identifier),
+                                m::operator("."),
+                            )
+                            .sum::<Markup>()
+                                + m::operator(":")
+                                + m::space()
+                                + e.pretty_print()
+                        }),
+                        m::operator(",") + m::space(),
+                    )
+                    .sum()
+                    + m::space()
+                    + m::operator("}")
+            }
             List(_, elements, _) => {
                 m::operator("[")
                     + itertools::Itertools::intersperse(
@@ -1323,6 +1511,28 @@ impl PrettyPrint for Expression {
                     .sum()
                     + m::operator("]")
             }
+            Dict(_, entries, _) => {
+                m::operator("{")
+                    + itertools::Itertools::intersperse(
+                        entries.iter().map(|(k, v)| {
+                            k.pretty_print() + m::operator(":") + m::space() + v.pretty_print()
+                        }),
+                        m::operator(",") + m::space(),
+                    )
+                    .sum()
+                    + m::operator("}")
+            }
+            Range(_, start, end, step, inclusive, _) => {
+                let mut markup = start.pretty_print()
+                    + m::space()
+                    + m::operator(if *inclusive { "..=" } else { ".." })
+                    + m::space()
+                    + end.pretty_print();
+                if let Some(step) = step {
+                    markup += m::space() + m::keyword("step") + m::space() + step.pretty_print();
+                }
+                markup
+            }
             TypedHole(_, _) => m::operator("?"),
         }
     }
@@ -1495,6 +1705,10 @@ mod tests {
         roundtrip_check("(-3)!");
         roundtrip_check("megapoints");
         roundtrip_check("Foo { foo: 1 meter, bar: 1 second }");
+        roundtrip_check("{ Foo { foo: 1 meter, bar: 1 second } with bar: 2 second }");
+        roundtrip_check("{\"x\": 1 meter, \"y\": 2 meter}");
+        roundtrip_check("1 ..= 10");
+        roundtrip_check("0 second .. 1 second step 0.1 second");
         roundtrip_check("\"foo\"");
         roundtrip_check("\"newline: \\n\"");
     }
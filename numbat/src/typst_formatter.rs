@@ -0,0 +1,191 @@
+//! A markup backend that writes Typst math. Units are written as upright
+//! text, and slashes are escaped so that they are not typeset as fractions.
+
+use crate::markup::{FormatType, FormattedString, Formatter, Markup};
+use crate::unit::{unit_string_parts, UnitStringPart};
+
+pub struct TypstFormatter;
+
+fn text(content: &str) -> String {
+    format!("\"{}\"", content.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// Wrap an exponent in parentheses, unless it is a single character
+fn exponent(exponent: &str) -> String {
+    if exponent.chars().count() == 1 {
+        exponent.into()
+    } else {
+        format!("({})", exponent.replace('/', r"\/"))
+    }
+}
+
+fn typst_number(value: &str) -> String {
+    let value = value.replace('_', "");
+    match value.as_str() {
+        "inf" => return "infinity".into(),
+        "-inf" => return "-infinity".into(),
+        _ => {}
+    }
+
+    if value.parse::<f64>().is_ok_and(f64::is_finite) {
+        match value.split_once('e') {
+            Some((mantissa, e)) => {
+                let e = e.trim_start_matches('+');
+                format!("{mantissa} times 10^{}", exponent(e))
+            }
+            None => value,
+        }
+    } else {
+        text(&value)
+    }
+}
+
+/// Convert a unit like `kg·m²/s²` to Typst math: `"kg" dot.c "m"^2 \/ "s"^2`
+pub fn typst_unit(unit: &str) -> String {
+    unit_string_parts(unit)
+        .into_iter()
+        .map(|part| match part {
+            UnitStringPart::Name(name) => text(&name),
+            UnitStringPart::Times => " dot.c ".into(),
+            UnitStringPart::Over => r" \/ ".into(),
+            UnitStringPart::Exponent(e) => format!("^{}", exponent(&e)),
+            UnitStringPart::OpenParen => "(".into(),
+            UnitStringPart::CloseParen => ")".into(),
+        })
+        .collect()
+}
+
+fn typst_operator(operator: &str) -> String {
+    match operator {
+        "*" | "×" => "times".into(),
+        "·" => "dot.c".into(),
+        "÷" => "div".into(),
+        "/" => r"\/".into(),
+        "->" | "→" | "➞" | "to" => "arrow.r".into(),
+        "==" => "=".into(),
+        "!=" | "≠" => "!=".into(),
+        "<=" | "≤" => "<=".into(),
+        ">=" | "≥" => ">=".into(),
+        "&&" => "and".into(),
+        "||" => "or".into(),
+        "!" => "not".into(),
+        "{" | "}" | "[" | "]" | "\"" | "%" => format!(r"\{operator}"),
+        "+" | "-" | "=" | "<" | ">" | "(" | ")" | "," | ":" | "^" => operator.into(),
+        _ => text(operator),
+    }
+}
+
+fn typst_identifier(identifier: &str) -> String {
+    if identifier.chars().count() == 1 {
+        identifier.into()
+    } else {
+        text(identifier)
+    }
+}
+
+impl Formatter for TypstFormatter {
+    fn format_part(&self, FormattedString(_, format_type, s): &FormattedString) -> String {
+        match format_type {
+            FormatType::Whitespace if s.contains('\n') => r" \ ".into(),
+            FormatType::Whitespace => " ".into(),
+            FormatType::Value => typst_number(s),
+            FormatType::Unit => typst_unit(s),
+            FormatType::Identifier | FormatType::TypeIdentifier => typst_identifier(s),
+            FormatType::Operator => typst_operator(s),
+            FormatType::Emphasized
+            | FormatType::Dimmed
+            | FormatType::Text
+            | FormatType::String
+            | FormatType::Keyword
+            | FormatType::Decorator => {
+                if s.trim().is_empty() {
+                    " ".into()
+                } else {
+                    text(s)
+                }
+            }
+        }
+    }
+
+    /// Values and units are separated by a thin space, and exponents are
+    /// wrapped in parentheses.
+    fn format(&self, markup: &Markup, _indent: bool) -> String {
+        let mut output = String::new();
+        let mut exponent_open = false;
+        let mut follows_value = false;
+
+        for part in &markup.0 {
+            let formatted = match part {
+                FormattedString(_, FormatType::Unit, _) if follows_value => {
+                    format!(" thin {}", self.format_part(part))
+                }
+                _ => self.format_part(part),
+            };
+            follows_value =
+                part.1 == FormatType::Value || (follows_value && part.1 == FormatType::Whitespace);
+
+            if exponent_open && part.1 != FormatType::Whitespace {
+                output.push_str(&formatted);
+                output.push(')');
+                exponent_open = false;
+            } else if part.1 == FormatType::Operator && part.2 == "^" {
+                output.push_str("^(");
+                exponent_open = true;
+            } else {
+                output.push_str(&formatted);
+            }
+        }
+        if exponent_open {
+            output.push(')');
+        }
+
+        output.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup as m;
+
+    fn typst(markup: Markup) -> String {
+        TypstFormatter.format(&markup, false)
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(typst_unit("km/h"), r#""km" \/ "h""#);
+        assert_eq!(typst_unit("kg·m²/s²"), r#""kg" dot.c "m"^2 \/ "s"^2"#);
+        assert_eq!(typst_unit("m·s⁻¹"), r#""m" dot.c "s"^(-1)"#);
+        assert_eq!(typst_unit("m^(1/2)"), r#""m"^(1\/2)"#);
+    }
+
+    #[test]
+    fn quantities() {
+        assert_eq!(
+            typst(m::value("3") + m::space() + m::unit("km/h")),
+            r#"3 thin "km" \/ "h""#
+        );
+        assert_eq!(typst(m::value("30") + m::unit("°")), r#"30 thin "°""#);
+        assert_eq!(typst(m::value("1_234_567")), "1234567");
+        assert_eq!(typst(m::value("1.602e-19")), "1.602 times 10^(-19)");
+        assert_eq!(typst(m::value("3.0e+50")), "3.0 times 10^(50)");
+        assert_eq!(typst(m::value("inf")), "infinity");
+    }
+
+    #[test]
+    fn expressions() {
+        let expression = m::identifier("v_0")
+            + m::space()
+            + m::operator("×")
+            + m::space()
+            + m::identifier("t")
+            + m::operator("^")
+            + m::value("2")
+            + m::space()
+            + m::operator("->")
+            + m::space()
+            + m::unit("km");
+        assert_eq!(typst(expression), r#""v_0" times t^(2) arrow.r "km""#);
+    }
+}
@@ -40,7 +40,7 @@ impl CanonicalName {
 pub struct UnitIdentifier {
     pub name: String,
     pub canonical_name: CanonicalName,
-    kind: UnitKind,
+    pub(crate) kind: UnitKind,
 }
 
 #[derive(Clone, Debug)]
@@ -411,6 +411,26 @@ impl Unit {
         )
     }
 
+    #[cfg(test)]
+    pub fn joule() -> Self {
+        Self::new_derived(
+            "joule",
+            CanonicalName::new("J", AcceptsPrefix::only_short()),
+            Number::from_f64(1.0),
+            Unit::newton() * Unit::meter(),
+        )
+    }
+
+    #[cfg(test)]
+    pub fn watt() -> Self {
+        Self::new_derived(
+            "watt",
+            CanonicalName::new("W", AcceptsPrefix::only_short()),
+            Number::from_f64(1.0),
+            Unit::joule() / Unit::second(),
+        )
+    }
+
     #[cfg(test)]
     pub fn minute() -> Self {
         Self::new_derived(
@@ -513,6 +533,102 @@ impl Display for Unit {
     }
 }
 
+/// A part of a unit as written by its `Display` implementation, see
+/// [`unit_string_parts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitStringPart {
+    Name(String),
+    Times,
+    Over,
+    Exponent(String),
+    OpenParen,
+    CloseParen,
+}
+
+fn from_superscript(c: char) -> Option<char> {
+    match c {
+        '⁻' => Some('-'),
+        _ => "⁰¹²³⁴⁵⁶⁷⁸⁹"
+            .chars()
+            .position(|s| s == c)
+            .and_then(|digit| char::from_digit(digit as u32, 10)),
+    }
+}
+
+/// Split a unit as written by its `Display` implementation (like `kg·m²/s²`,
+/// `m·s⁻¹` or `m^(1/2)`) into unit names, operators and exponents. This is
+/// used by markup backends that need to typeset units.
+pub fn unit_string_parts(unit: &str) -> Vec<UnitStringPart> {
+    let mut parts = vec![];
+    let mut chars = unit.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '·' => parts.push(UnitStringPart::Times),
+            '/' => parts.push(UnitStringPart::Over),
+            '(' => parts.push(UnitStringPart::OpenParen),
+            ')' => parts.push(UnitStringPart::CloseParen),
+            '^' if chars.peek() == Some(&'(') => {
+                chars.next();
+                let exponent = chars.by_ref().take_while(|&c| c != ')').collect();
+                parts.push(UnitStringPart::Exponent(exponent));
+            }
+            '^' => {
+                let mut exponent = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '-') {
+                    exponent.push(c);
+                    chars.next();
+                }
+                parts.push(UnitStringPart::Exponent(exponent));
+            }
+            c if from_superscript(c).is_some() => {
+                let mut exponent = String::new();
+                exponent.extend(from_superscript(c));
+                while let Some(digit) = chars.peek().and_then(|&c| from_superscript(c)) {
+                    exponent.push(digit);
+                    chars.next();
+                }
+                parts.push(UnitStringPart::Exponent(exponent));
+            }
+            c => match parts.last_mut() {
+                Some(UnitStringPart::Name(name)) => name.push(c),
+                _ => parts.push(UnitStringPart::Name(c.to_string())),
+            },
+        }
+    }
+
+    parts
+}
+
+/// The units in which quantities of a given dimension are shown, as set by
+/// `set preferred_unit <dimension> = <unit>`. Entries are keyed by the base
+/// unit representation, i.e. by the dimension of the unit.
+#[derive(Debug, Clone, Default)]
+pub struct PreferredUnits(Vec<(Unit, Unit)>);
+
+impl PreferredUnits {
+    /// Set `unit` as the preferred unit for its dimension, replacing any
+    /// previous preference for that dimension.
+    pub fn set(&mut self, unit: Unit) {
+        let (base_unit, _) = unit.to_base_unit_representation();
+        self.0.retain(|(b, _)| b != &base_unit);
+        self.0.push((base_unit, unit));
+    }
+
+    /// The preferred unit for the dimension of `unit`, if there is one.
+    pub fn get(&self, unit: &Unit) -> Option<&Unit> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let (base_unit, _) = unit.to_base_unit_representation();
+        self.0
+            .iter()
+            .find(|(b, _)| b == &base_unit)
+            .map(|(_, preferred)| preferred)
+    }
+}
+
 /// This function attempts to solves the equation a = C · b^alpha, where
 /// C is a constant and alpha is a rational exponent. If there is a solution,
 /// `Some(alpha)` is returned. If not, `None` is returned.
@@ -767,6 +883,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn string_parts() {
+        use UnitStringPart::*;
+
+        let name = |n: &str| Name(n.into());
+        let exponent = |e: &str| Exponent(e.into());
+
+        assert_eq!(unit_string_parts("km/h"), [name("km"), Over, name("h")]);
+        assert_eq!(
+            unit_string_parts("kg·m²/s²"),
+            [
+                name("kg"),
+                Times,
+                name("m"),
+                exponent("2"),
+                Over,
+                name("s"),
+                exponent("2")
+            ]
+        );
+        assert_eq!(
+            unit_string_parts("m·s⁻¹"),
+            [name("m"), Times, name("s"), exponent("-1")]
+        );
+        assert_eq!(unit_string_parts("m^(1/2)"), [name("m"), exponent("1/2")]);
+        assert_eq!(unit_string_parts("B^12"), [name("B"), exponent("12")]);
+        assert_eq!(
+            unit_string_parts("m/(kg·s)"),
+            [
+                name("m"),
+                Over,
+                OpenParen,
+                name("kg"),
+                Times,
+                name("s"),
+                CloseParen
+            ]
+        );
+    }
+
     #[test]
     fn is_multiple_of_basic() {
         assert_eq!(
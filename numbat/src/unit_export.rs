@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use num_traits::ToPrimitive;
+
+use crate::json::json_string;
+use crate::markup::{Formatter, PlainTextFormatter};
+use crate::registry::{BaseRepresentation, BaseRepresentationFactor};
+use crate::unit_registry::UnitMetadata;
+
+/// Everything we know about a single unit, as it appears in the JSON export.
+pub(crate) struct UnitExport<'a> {
+    pub name: &'a str,
+    pub base_representation: &'a BaseRepresentation,
+    pub metadata: &'a UnitMetadata,
+    /// Conversion factor to the base units, if the unit has been defined already
+    pub factor: Option<f64>,
+}
+
+fn json_optional_string(s: Option<&str>) -> String {
+    s.map(json_string).unwrap_or_else(|| "null".into())
+}
+
+pub(crate) fn json_number(n: f64) -> String {
+    if n.is_finite() {
+        n.to_string()
+    } else {
+        "null".into()
+    }
+}
+
+fn unit_to_json(unit: &UnitExport) -> String {
+    let md = unit.metadata;
+
+    let aliases = md
+        .aliases
+        .iter()
+        .map(|(alias, accepts_prefix)| {
+            format!(
+                "{{\"name\":{},\"short_prefixes\":{},\"long_prefixes\":{}}}",
+                json_string(alias),
+                accepts_prefix.short,
+                accepts_prefix.long
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let base_representation = unit
+        .base_representation
+        .iter()
+        .map(|BaseRepresentationFactor(base_unit, exponent)| {
+            format!(
+                "{}:{}",
+                json_string(base_unit),
+                json_number(exponent.to_f64().unwrap_or(f64::NAN))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let dimension = PlainTextFormatter {}.format(&md.readable_type, false);
+
+    format!(
+        "{{\"name\":{},\"long_name\":{},\"aliases\":[{}],\"dimension\":{},\"base_representation\":{{{}}},\"factor\":{},\"metric_prefixes\":{},\"binary_prefixes\":{},\"url\":{},\"description\":{}}}",
+        json_string(unit.name),
+        json_optional_string(md.name.as_deref()),
+        aliases,
+        json_string(dimension.trim()),
+        base_representation,
+        unit.factor.map(json_number).unwrap_or_else(|| "null".into()),
+        md.metric_prefixes,
+        md.binary_prefixes,
+        json_optional_string(md.url.as_deref()),
+        json_optional_string(md.description.as_deref()),
+    )
+}
+
+/// Render a list of units as a JSON array, with one unit per line.
+pub(crate) fn units_to_json<'a>(units: impl Iterator<Item = UnitExport<'a>>) -> String {
+    let mut output = String::from("[");
+    for (i, unit) in units.enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        write!(output, "\n  {}", unit_to_json(&unit)).unwrap();
+    }
+    output.push_str("\n]\n");
+    output
+}
@@ -0,0 +1,460 @@
+//! Resolution of unit names at runtime. This is used by `parse_quantity`,
+//! which turns strings like "3.5 km/h" into quantities, and by the reflection
+//! functions like `dimension_name`, which need to know the physical dimension
+//! of a unit. It also knows the long names of units, which are used to write
+//! results like "3 kilometres per hour" instead of "3 km/h".
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use num_traits::Signed;
+
+use crate::arithmetic::{Exponent, Power};
+use crate::ast::{BinaryOperator, Expression, Statement, UnaryOperator};
+use crate::markup::{FormatType, FormattedString, Markup, OutputType};
+use crate::parser::parse;
+use crate::prefix_parser::{AcceptsPrefix, PrefixParser, PrefixParserResult};
+use crate::quantity::Quantity;
+use crate::registry::BaseRepresentation;
+use crate::symbol::Symbol;
+use crate::typed_ast::Type;
+use crate::unit::{Unit, UnitFactor};
+use crate::unit_registry::UnitMetadata;
+
+#[derive(Debug, Clone)]
+pub(crate) struct UnitLookup {
+    prefix_parser: PrefixParser,
+    units: HashMap<String, Unit>,
+    /// Plural forms of unit names, like "feet" for "foot"
+    plurals: HashMap<String, String>,
+    /// The dimensions of all base units
+    base_unit_dimensions: HashMap<String, BaseRepresentation>,
+    /// All named dimensions, in the order of their definition
+    dimension_names: Vec<(String, BaseRepresentation)>,
+    /// Aliases from `unit alias` definitions for derived units that have not
+    /// been evaluated yet, by the name of the unit
+    pending_aliases: HashMap<String, Vec<(String, AcceptsPrefix)>>,
+}
+
+impl UnitLookup {
+    pub fn new() -> Self {
+        Self {
+            prefix_parser: PrefixParser::new(),
+            units: HashMap::new(),
+            plurals: HashMap::new(),
+            base_unit_dimensions: HashMap::new(),
+            dimension_names: vec![],
+            pending_aliases: HashMap::new(),
+        }
+    }
+
+    pub fn add_dimension(&mut self, name: &str, base_representation: BaseRepresentation) {
+        self.dimension_names
+            .push((name.to_string(), base_representation));
+    }
+
+    /// Make a unit available under all of its aliases (which include the name
+    /// of the unit itself)
+    pub fn add_unit(&mut self, unit_name: &str, unit: &Unit, metadata: &UnitMetadata) {
+        for (alias, accepts_prefix) in &metadata.aliases {
+            self.prefix_parser.add_unit_unchecked(
+                &Symbol::new(alias),
+                *accepts_prefix,
+                metadata.metric_prefixes,
+                metadata.binary_prefixes,
+                &Symbol::new(unit_name),
+            );
+            self.units.insert(alias.clone(), unit.clone());
+
+            for (pending_alias, accepts_prefix) in
+                self.pending_aliases.remove(alias).unwrap_or_default()
+            {
+                self.add_alias(&pending_alias, accepts_prefix, alias);
+            }
+        }
+
+        if let Some(plural) = plural_of(unit_name, &metadata.aliases) {
+            self.plurals.insert(unit_name.to_string(), plural);
+        }
+
+        if let (Some(factor), Type::Dimension(dtype)) = (unit.iter().next(), &metadata.type_) {
+            if factor.unit_id.is_base() {
+                self.base_unit_dimensions
+                    .insert(unit_name.to_string(), dtype.to_base_representation());
+            }
+        }
+    }
+
+    /// Make a unit available under an additional name from a `unit alias`
+    /// definition. If the unit is not known yet, the alias is added as soon
+    /// as the unit is.
+    pub fn add_alias(&mut self, alias: &str, accepts_prefix: AcceptsPrefix, unit_name: &str) {
+        let Some(unit) = self.units.get(unit_name).cloned() else {
+            self.pending_aliases
+                .entry(unit_name.to_string())
+                .or_default()
+                .push((alias.to_string(), accepts_prefix));
+            return;
+        };
+
+        self.prefix_parser.add_alias_unchecked(
+            &Symbol::new(alias),
+            accepts_prefix,
+            &Symbol::new(unit_name),
+        );
+        self.units.insert(alias.to_string(), unit);
+    }
+
+    /// The physical dimension of a unit, in terms of base dimensions
+    pub fn dimension_of(&self, unit: &Unit) -> BaseRepresentation {
+        let (base_unit, _) = unit.to_base_unit_representation();
+        base_unit
+            .iter()
+            .filter_map(|factor| {
+                self.base_unit_dimensions
+                    .get(&factor.unit_id.name)
+                    .map(|dimension| dimension.clone().power(factor.exponent))
+            })
+            .product()
+    }
+
+    /// The name of a dimension, like "Velocity" for `Length / Time`. If there
+    /// are several names for the same dimension, all of them are listed. If
+    /// there is none, the dimension is written in terms of base dimensions.
+    pub fn dimension_name(&self, dimension: &BaseRepresentation) -> String {
+        if dimension == &BaseRepresentation::unity() {
+            return "Scalar".into();
+        }
+
+        let names = self
+            .dimension_names
+            .iter()
+            .filter(|(_, base_representation)| base_representation == dimension)
+            .map(|(name, _)| name)
+            .unique()
+            .sorted()
+            .join(" or ");
+
+        if names.is_empty() {
+            dimension.to_string()
+        } else {
+            names
+        }
+    }
+
+    /// Parse a quantity from a string. Only numbers, units and arithmetic
+    /// operators are allowed; variables and functions can not be referenced.
+    pub fn parse_quantity(&self, input: &str) -> Result<Quantity, String> {
+        let statements = parse(input, 0).map_err(|(_, errors)| {
+            errors
+                .first()
+                .map(|e| e.to_string())
+                .unwrap_or_else(|| "invalid syntax".into())
+        })?;
+
+        match statements.as_slice() {
+            [Statement::Expression(expression)] => self.evaluate(expression),
+            _ => Err("expected a single quantity".into()),
+        }
+    }
+
+    /// The long form of a unit, like "kilometres per hour" for `km/h` or
+    /// "metres per second squared" for `m/s²`. If `plural` is set, the last
+    /// unit before the first "per" is written in plural form.
+    pub fn long_unit_name(&self, unit: &Unit, plural: bool) -> String {
+        let (numerator, denominator): (Vec<_>, Vec<_>) =
+            unit.iter().partition(|f| f.exponent.is_positive());
+
+        let mut words = vec![];
+        for (i, factor) in numerator.iter().enumerate() {
+            let plural = plural && i == numerator.len() - 1;
+            words.push(self.long_factor_name(factor, plural, false));
+        }
+        for factor in denominator {
+            words.push("per".into());
+            words.push(self.long_factor_name(factor, false, true));
+        }
+        words.join(" ")
+    }
+
+    fn long_factor_name(&self, factor: &UnitFactor, plural: bool, in_denominator: bool) -> String {
+        let name = &factor.unit_id.name;
+        let name = match self.plurals.get(name) {
+            Some(plural_name) if plural => plural_name,
+            _ => name,
+        };
+        let name = format!("{}{}", factor.prefix.as_string_long(), name).replace('_', " ");
+
+        let exponent = factor.exponent.abs();
+        if exponent == Exponent::from_integer(1) {
+            name
+        } else if exponent == Exponent::from_integer(2) {
+            if in_denominator {
+                format!("{name} squared")
+            } else {
+                format!("square {name}")
+            }
+        } else if exponent == Exponent::from_integer(3) {
+            if in_denominator {
+                format!("{name} cubed")
+            } else {
+                format!("cubic {name}")
+            }
+        } else {
+            format!("{name} to the power of {exponent}")
+        }
+    }
+
+    /// Rewrite all units in the given markup to their long form, e.g. "3 km/h"
+    /// to "3 kilometres per hour". A unit is written in plural form unless the
+    /// value in front of it is 1 or -1. Units that can not be resolved are
+    /// left as they are.
+    pub fn with_long_unit_names(&self, markup: Markup) -> Markup {
+        let mut result = vec![];
+        let mut plural = true;
+        let mut follows_value = false;
+
+        for FormattedString(output_type, format_type, text) in markup.0 {
+            match format_type {
+                FormatType::Value => {
+                    plural = !matches!(
+                        text.replace('_', "").parse::<f64>(),
+                        Ok(value) if value.abs() == 1.0
+                    );
+                }
+                FormatType::Unit => {
+                    if let Some(long_name) = self
+                        .parse_quantity(&text)
+                        .ok()
+                        .filter(|q| q.unsafe_value().to_f64() == 1.0)
+                        .map(|q| self.long_unit_name(q.unit(), plural))
+                    {
+                        if follows_value {
+                            result.push(FormattedString(
+                                OutputType::Normal,
+                                FormatType::Whitespace,
+                                " ".into(),
+                            ));
+                        }
+                        result.push(FormattedString(output_type, format_type, long_name));
+                        follows_value = false;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            follows_value = format_type == FormatType::Value;
+            result.push(FormattedString(output_type, format_type, text));
+        }
+
+        Markup(result)
+    }
+
+    fn evaluate(&self, expression: &Expression) -> Result<Quantity, String> {
+        match expression {
+            Expression::Scalar(_, number) => Ok(Quantity::from_scalar(number.to_f64())),
+            Expression::Identifier(_, identifier) => match self.prefix_parser.parse(identifier) {
+                PrefixParserResult::UnitIdentifier(_, prefix, unit_name, _) => {
+                    let unit = self.units[unit_name.as_str()].clone();
+                    Ok(Quantity::from_unit(unit.with_prefix(prefix)))
+                }
+                PrefixParserResult::Identifier => Err(format!("unknown unit '{identifier}'")),
+            },
+            Expression::UnaryOperator {
+                op: UnaryOperator::Negate,
+                expr,
+                ..
+            } => Ok(-self.evaluate(expr)?),
+            Expression::BinaryOperator { op, lhs, rhs, .. } => {
+                let lhs = self.evaluate(lhs)?;
+                let rhs = self.evaluate(rhs)?;
+                let result = match op {
+                    BinaryOperator::Add => &lhs + &rhs,
+                    BinaryOperator::Sub => &lhs - &rhs,
+                    BinaryOperator::Mul => Ok(lhs * rhs),
+                    BinaryOperator::Div => Ok(lhs / rhs),
+                    BinaryOperator::Power => lhs.power(rhs),
+                    BinaryOperator::ConvertTo => lhs.convert_to(rhs.unit()),
+                    _ => return Err("only arithmetic operators are allowed".into()),
+                };
+                result.map_err(|e| e.to_string())
+            }
+            _ => Err("only numbers, units and arithmetic operators are allowed".into()),
+        }
+    }
+}
+
+/// Find the plural of a unit name among its aliases, e.g. "metres" for
+/// "metre", "inches" for "inch", "centuries" for "century" or "feet" for
+/// "foot". Units like "hertz" do not have a plural form.
+fn plural_of(name: &str, aliases: &[(String, AcceptsPrefix)]) -> Option<String> {
+    let candidates = [
+        Some(format!("{name}s")),
+        Some(format!("{name}es")),
+        name.strip_suffix('y').map(|stem| format!("{stem}ies")),
+        Some(name.replace("oo", "ee")),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|candidate| candidate != name && aliases.iter().any(|(alias, _)| alias == candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::typed_ast::{DType, DTypeFactor, Type};
+    use crate::unit::CanonicalName;
+
+    fn lookup() -> UnitLookup {
+        let mut lookup = UnitLookup::new();
+        for (name, alias, dimension) in [("meter", "m", "Length"), ("second", "s", "Time")] {
+            let canonical_name = CanonicalName::new(alias, AcceptsPrefix::only_short());
+            let unit = Unit::new_base(name, canonical_name.clone());
+            let metadata = UnitMetadata {
+                type_: Type::Dimension(DType::base_dimension(dimension)),
+                readable_type: Markup::default(),
+                aliases: vec![
+                    (name.into(), AcceptsPrefix::only_long()),
+                    (format!("{name}s"), AcceptsPrefix::only_long()),
+                    (alias.into(), AcceptsPrefix::only_short()),
+                ],
+                name: None,
+                canonical_name,
+                url: None,
+                description: None,
+                binary_prefixes: false,
+                metric_prefixes: true,
+            };
+            lookup.add_unit(name, &unit, &metadata);
+            lookup.add_dimension(
+                dimension,
+                DType::base_dimension(dimension).to_base_representation(),
+            );
+        }
+        lookup.add_dimension(
+            "Velocity",
+            DType::from_factors(&[
+                (
+                    DTypeFactor::BaseDimension("Length".into()),
+                    Exponent::from_integer(1),
+                ),
+                (
+                    DTypeFactor::BaseDimension("Time".into()),
+                    Exponent::from_integer(-1),
+                ),
+            ])
+            .to_base_representation(),
+        );
+        lookup
+    }
+
+    #[test]
+    fn parses_quantities() {
+        let lookup = lookup();
+        let meter = Quantity::from_unit(Unit::new_base(
+            "meter",
+            CanonicalName::new("m", AcceptsPrefix::only_short()),
+        ));
+
+        let km = lookup.parse_quantity("2.5 km").unwrap();
+        assert_eq!(
+            km.convert_to(meter.unit()).unwrap().unsafe_value().to_f64(),
+            2500.0
+        );
+
+        let speed = lookup.parse_quantity("-3 meter / second").unwrap();
+        assert_eq!(speed.unsafe_value().to_f64(), -3.0);
+
+        assert_eq!(
+            lookup.parse_quantity("4").unwrap(),
+            Quantity::from_scalar(4.0)
+        );
+    }
+
+    #[test]
+    fn dimensions() {
+        let lookup = lookup();
+        let dimension_name = |input: &str| {
+            let quantity = lookup.parse_quantity(input).unwrap();
+            lookup.dimension_name(&lookup.dimension_of(quantity.unit()))
+        };
+
+        assert_eq!(dimension_name("3"), "Scalar");
+        assert_eq!(dimension_name("3 km"), "Length");
+        assert_eq!(dimension_name("3 m / s"), "Velocity");
+        assert_eq!(dimension_name("3 m / m"), "Scalar");
+        assert_eq!(dimension_name("3 m * s"), "Length × Time");
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let lookup = lookup();
+
+        assert_eq!(
+            lookup.parse_quantity("3 parsec"),
+            Err("unknown unit 'parsec'".into())
+        );
+        assert!(lookup.parse_quantity("3 m + 2 s").is_err());
+        assert!(lookup.parse_quantity("sqrt(4) m").is_err());
+        assert!(lookup.parse_quantity("3 m 4 (").is_err());
+        assert!(lookup.parse_quantity("").is_err());
+    }
+
+    #[test]
+    fn long_unit_names() {
+        let lookup = lookup();
+        let long_unit_name = |input: &str, plural: bool| {
+            let quantity = lookup.parse_quantity(input).unwrap();
+            lookup.long_unit_name(quantity.unit(), plural)
+        };
+
+        assert_eq!(long_unit_name("km", false), "kilometer");
+        assert_eq!(long_unit_name("km", true), "kilometers");
+        assert_eq!(long_unit_name("m/s", true), "meters per second");
+        assert_eq!(long_unit_name("m/s^2", true), "meters per second squared");
+        assert_eq!(long_unit_name("m^2", true), "square meters");
+        assert_eq!(long_unit_name("m^3", false), "cubic meter");
+        assert_eq!(long_unit_name("m^4", true), "meters to the power of 4");
+        assert_eq!(long_unit_name("m s", true), "meter seconds");
+        assert_eq!(long_unit_name("1/s", true), "per second");
+
+        let markup = crate::markup::value("3")
+            + crate::markup::space()
+            + crate::markup::unit("km/ms")
+            + crate::markup::text(", ")
+            + crate::markup::value("-1")
+            + crate::markup::space()
+            + crate::markup::unit("m");
+        assert_eq!(
+            lookup.with_long_unit_names(markup).to_string(),
+            "3 kilometers per millisecond, -1 meter"
+        );
+    }
+
+    #[test]
+    fn plurals() {
+        let aliases = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| (name.to_string(), AcceptsPrefix::only_long()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            plural_of("metre", &aliases(&["metres", "meter"])),
+            Some("metres".into())
+        );
+        assert_eq!(
+            plural_of("inch", &aliases(&["inches"])),
+            Some("inches".into())
+        );
+        assert_eq!(
+            plural_of("century", &aliases(&["centuries"])),
+            Some("centuries".into())
+        );
+        assert_eq!(plural_of("foot", &aliases(&["feet"])), Some("feet".into()));
+        assert_eq!(plural_of("hertz", &aliases(&["hertz", "Hz"])), None);
+    }
+}
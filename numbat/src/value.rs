@@ -1,10 +1,15 @@
 use std::sync::Arc;
 
+use indexmap::IndexMap;
 use itertools::Itertools;
-use jiff::Zoned;
 
 use crate::{
-    list::NumbatList, pretty_print::PrettyPrint, quantity::Quantity, typed_ast::StructInfo,
+    datetime::Zoned,
+    list::NumbatList,
+    pretty_print::{escape_numbat_string, PrettyPrint},
+    quantity::Quantity,
+    range::NumbatRange,
+    typed_ast::StructInfo,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -27,7 +32,7 @@ impl std::fmt::Display for FunctionReference {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub enum Value {
     Quantity(Quantity),
     Boolean(bool),
@@ -38,6 +43,10 @@ pub enum Value {
     FormatSpecifiers(Option<String>),
     StructInstance(Arc<StructInfo>, Vec<Value>),
     List(NumbatList<Value>),
+    /// A lazily evaluated range, which behaves like a list of quantities
+    Range(NumbatRange),
+    /// A dictionary with string keys, in insertion order
+    Dict(IndexMap<String, Value>),
 }
 
 impl Value {
@@ -69,7 +78,7 @@ impl Value {
     }
 
     #[track_caller]
-    pub fn unsafe_as_datetime(self) -> jiff::Zoned {
+    pub fn unsafe_as_datetime(self) -> Zoned {
         if let Value::DateTime(dt) = self {
             dt
         } else {
@@ -95,18 +104,117 @@ impl Value {
         }
     }
 
+    /// Get the elements of a list. Ranges are materialized.
     #[track_caller]
     pub fn unsafe_as_list(self) -> NumbatList<Value> {
-        if let Value::List(values) = self {
-            values
+        match self {
+            Value::List(values) => values,
+            Value::Range(range) => range.iter().collect(),
+            _ => panic!("Expected value to be a list"),
+        }
+    }
+
+    /// Get the elements of a list without materializing ranges
+    #[track_caller]
+    pub fn unsafe_as_list_elements(self) -> ListElements {
+        match self {
+            Value::List(values) => ListElements::List(values),
+            Value::Range(range) => ListElements::Range(range),
+            _ => panic!("Expected value to be a list"),
+        }
+    }
+
+    #[track_caller]
+    pub fn unsafe_as_dict(self) -> IndexMap<String, Value> {
+        if let Value::Dict(entries) = self {
+            entries
         } else {
-            panic!("Expected value to be a list");
+            panic!("Expected value to be a dictionary");
         }
     }
 
     pub(crate) fn is_quantity(&self) -> bool {
         matches!(self, Value::Quantity(_))
     }
+
+    /// Approximate number of bytes that this value occupies on the heap, not
+    /// counting the heap memory of nested values.
+    pub(crate) fn heap_size(&self) -> usize {
+        let value_size = std::mem::size_of::<Value>();
+        match self {
+            Value::String(s) => s.len(),
+            Value::StructInstance(_, fields) => fields.len() * value_size,
+            Value::List(list) => list.len() * value_size,
+            Value::Dict(entries) => entries
+                .keys()
+                .map(|key| key.len() + std::mem::size_of::<String>() + value_size)
+                .sum(),
+            _ => 0,
+        }
+    }
+}
+
+/// The elements of a value of type `List<A>`, which is either an ordinary
+/// list or a lazily evaluated range.
+#[derive(Debug, Clone)]
+pub enum ListElements {
+    List(NumbatList<Value>),
+    Range(NumbatRange),
+}
+
+impl ListElements {
+    pub fn len(&self) -> usize {
+        match self {
+            ListElements::List(list) => list.len(),
+            ListElements::Range(range) => range.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Value> + '_> {
+        match self {
+            ListElements::List(list) => Box::new(list.iter().cloned()),
+            ListElements::Range(range) => Box::new(range.iter()),
+        }
+    }
+}
+
+impl From<ListElements> for Value {
+    fn from(elements: ListElements) -> Self {
+        match elements {
+            ListElements::List(list) => Value::List(list),
+            ListElements::Range(range) => Value::Range(range),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Quantity(a), Value::Quantity(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::DateTime(a), Value::DateTime(b)) => a == b,
+            (Value::FunctionReference(a), Value::FunctionReference(b)) => a == b,
+            (Value::FormatSpecifiers(a), Value::FormatSpecifiers(b)) => a == b,
+            (Value::StructInstance(info_a, a), Value::StructInstance(info_b, b)) => {
+                info_a == info_b && a == b
+            }
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Dict(a), Value::Dict(b)) => a == b,
+            // Ranges are compared element-wise, also to ordinary lists
+            (Value::Range(a), Value::Range(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x == y)
+            }
+            (Value::Range(range), Value::List(list)) | (Value::List(list), Value::Range(range)) => {
+                range.len() == list.len() && range.iter().zip(list.iter()).all(|(x, y)| &x == y)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -144,6 +252,23 @@ impl std::fmt::Display for Value {
                     .map(|element| element.to_string())
                     .join(", ")
             ),
+            Value::Range(range) => write!(
+                f,
+                "[{}]",
+                range
+                    .displayed_elements()
+                    .into_iter()
+                    .map(|element| element.map_or("…".to_owned(), |e| e.to_string()))
+                    .join(", ")
+            ),
+            Value::Dict(entries) => write!(
+                f,
+                "{{{}}}",
+                entries
+                    .iter()
+                    .map(|(key, value)| format!("\"{}\": {value}", escape_numbat_string(key)))
+                    .join(", ")
+            ),
         }
     }
 }
@@ -189,6 +314,31 @@ impl PrettyPrint for Value {
                     .sum()
                     + crate::markup::operator("]")
             }
+            Value::Range(range) => {
+                crate::markup::operator("[")
+                    + itertools::Itertools::intersperse(
+                        range.displayed_elements().into_iter().map(|element| {
+                            element.map_or(crate::markup::operator("…"), |e| e.pretty_print())
+                        }),
+                        crate::markup::operator(",") + crate::markup::space(),
+                    )
+                    .sum()
+                    + crate::markup::operator("]")
+            }
+            Value::Dict(entries) => {
+                crate::markup::operator("{")
+                    + itertools::Itertools::intersperse(
+                        entries.iter().map(|(key, value)| {
+                            key.pretty_print()
+                                + crate::markup::operator(":")
+                                + crate::markup::space()
+                                + value.pretty_print()
+                        }),
+                        crate::markup::operator(",") + crate::markup::space(),
+                    )
+                    .sum()
+                    + crate::markup::operator("}")
+            }
         }
     }
 }
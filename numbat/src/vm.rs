@@ -1,22 +1,31 @@
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
 use std::{cmp::Ordering, fmt::Display};
 
 use indexmap::IndexMap;
-use num_traits::ToPrimitive;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 use crate::list::NumbatList;
+use crate::range::NumbatRange;
 use crate::span::Span;
 use crate::typed_ast::StructInfo;
 use crate::{
-    ffi::{self, ArityRange, Callable, ForeignFunction},
-    interpreter::{InterpreterResult, PrintFunction, Result, RuntimeError},
+    ffi::{self, ArityRange, Callable, Capability, ForeignFunction},
+    format_specifiers::format_quantity,
+    interpreter::{
+        InterpreterResult, PrintFunction, Result, RuntimeError, TraceEvent, TraceFunction,
+    },
     markup::Markup,
     math,
     number::Number,
     prefix::Prefix,
+    prefix_parser::AcceptsPrefix,
     quantity::{Quantity, QuantityError},
-    unit::Unit,
+    registry::BaseRepresentation,
+    unit::{PreferredUnits, Unit},
+    unit_lookup::UnitLookup,
     unit_registry::{UnitMetadata, UnitRegistry},
     value::{FunctionReference, Value},
 };
@@ -39,6 +48,18 @@ pub enum Op {
     /// `1 <new_unit>` to the constant with the given index.
     SetUnitConstant,
 
+    /// Pop a quantity from the stack and make its unit the preferred unit
+    /// for displaying quantities of the same dimension
+    SetPreferredUnit,
+
+    /// Pop a quantity from the stack and add its unit to the derived units
+    /// that results are simplified to (`@simplification_target`)
+    AddSimplificationTarget,
+
+    /// Pop a quantity from the stack and show results in its unit with
+    /// fractions like `1 ½` (`@fractional`)
+    AddFractionalUnit,
+
     /// Push the value of the specified local variable onto the stack (even
     /// though it is already on the stack, somewhere lower down).
     GetLocal,
@@ -112,9 +133,23 @@ pub enum Op {
     BuildStructInstance,
     /// Access a single field of a struct
     AccessStructField,
+    /// Replace a (possibly nested) field of a struct with the value on top of
+    /// the stack
+    UpdateStructField,
 
     /// Build a list from the elements on the stack
     BuildList,
+    /// Build a dictionary from the key/value pairs on the stack
+    BuildDict,
+    /// Build a range from its start, end and step. The operand is 1 for
+    /// inclusive ranges and 0 otherwise.
+    BuildRange,
+
+    /// Pop the given number of pairs of values of an expression with one of its
+    /// variables shifted up and down by its uncertainty, and the nominal value of
+    /// the expression. Push the combined standard uncertainty, i.e. the square
+    /// root of the sum of the squared (half) differences within the pairs.
+    CombineUncertainties,
 
     /// Return from the current function
     Return,
@@ -135,7 +170,11 @@ impl Op {
             | Op::Jump
             | Op::CallCallable
             | Op::AccessStructField
-            | Op::BuildList => 1,
+            | Op::UpdateStructField
+            | Op::BuildList
+            | Op::BuildDict
+            | Op::BuildRange
+            | Op::CombineUncertainties => 1,
             Op::Negate
             | Op::Factorial
             | Op::Add
@@ -157,6 +196,9 @@ impl Op {
             | Op::LogicalOr
             | Op::LogicalNeg
             | Op::Return
+            | Op::SetPreferredUnit
+            | Op::AddSimplificationTarget
+            | Op::AddFractionalUnit
             | Op::GetLastResult => 0,
         }
     }
@@ -166,6 +208,9 @@ impl Op {
             Op::LoadConstant => "LoadConstant",
             Op::ApplyPrefix => "ApplyPrefix",
             Op::SetUnitConstant => "SetUnitConstant",
+            Op::SetPreferredUnit => "SetPreferredUnit",
+            Op::AddSimplificationTarget => "AddSimplificationTarget",
+            Op::AddFractionalUnit => "AddFractionalUnit",
             Op::GetLocal => "GetLocal",
             Op::GetUpvalue => "GetUpvalue",
             Op::GetLastResult => "GetLastResult",
@@ -200,7 +245,11 @@ impl Op {
             Op::Return => "Return",
             Op::BuildStructInstance => "BuildStructInstance",
             Op::AccessStructField => "AccessStructField",
+            Op::UpdateStructField => "UpdateStructField",
             Op::BuildList => "BuildList",
+            Op::BuildDict => "BuildDict",
+            Op::BuildRange => "BuildRange",
+            Op::CombineUncertainties => "CombineUncertainties",
         }
     }
 }
@@ -211,6 +260,7 @@ pub enum Constant {
     Unit(Unit),
     Boolean(bool),
     String(String),
+    DateTime(crate::datetime::Zoned),
     FunctionReference(FunctionReference),
     FormatSpecifiers(Option<String>),
 }
@@ -222,6 +272,7 @@ impl Constant {
             Constant::Unit(u) => Value::Quantity(Quantity::from_unit(u.clone())),
             Constant::Boolean(b) => Value::Boolean(*b),
             Constant::String(s) => Value::String(s.clone()),
+            Constant::DateTime(dt) => Value::DateTime(dt.clone()),
             Constant::FunctionReference(inner) => Value::FunctionReference(inner.clone()),
             Constant::FormatSpecifiers(s) => Value::FormatSpecifiers(s.clone()),
         }
@@ -235,12 +286,55 @@ impl Display for Constant {
             Constant::Unit(unit) => write!(f, "{unit}"),
             Constant::Boolean(val) => write!(f, "{val}"),
             Constant::String(val) => write!(f, "\"{val}\""),
+            Constant::DateTime(dt) => write!(f, "@{dt}"),
             Constant::FunctionReference(inner) => write!(f, "{inner}"),
             Constant::FormatSpecifiers(_) => write!(f, "<format specfiers>"),
         }
     }
 }
 
+/// A handle that can be used to interrupt a running evaluation from another
+/// thread, see [`crate::Context::interrupt_handle`].
+#[derive(Debug, Clone)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// Abort the current evaluation as soon as possible. The evaluation then
+    /// fails with [`RuntimeError::Interrupted`].
+    pub fn interrupt(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
+    }
+}
+
+/// The flag behind [`InterruptHandle`]s. A clone gets a new flag, such that
+/// interrupting one evaluation does not affect independent clones of a context.
+#[derive(Debug, Default)]
+pub(crate) struct InterruptFlag(Arc<AtomicBool>);
+
+impl InterruptFlag {
+    pub(crate) fn handle(&self) -> InterruptHandle {
+        InterruptHandle(self.0.clone())
+    }
+
+    pub(crate) fn reset(&self) {
+        self.0.store(false, AtomicOrdering::Relaxed);
+    }
+}
+
+impl Clone for InterruptFlag {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// A position in the bytecode, used to disassemble only the code that has been
+/// compiled after a certain point.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BytecodePosition {
+    num_functions: usize,
+    main_offset: usize,
+}
+
 #[derive(Clone)]
 struct CallFrame {
     /// The function being executed, index into [Vm]s `bytecode` vector.
@@ -265,8 +359,60 @@ impl CallFrame {
     }
 }
 
+/// Identifies the heap allocation of a list, along with its capacity
+type ListAllocation = (*const VecDeque<Value>, usize);
+
+fn list_allocations(values: &VecDeque<Value>) -> Vec<ListAllocation> {
+    values
+        .iter()
+        .filter_map(|value| match value {
+            Value::List(list) => Some(list.allocation()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Source of randomness for `random()`. Seeded from the OS unless a fixed seed
+/// has been set.
+struct RandomSource {
+    rng: StdRng,
+    fixed_seed: bool,
+}
+
+impl RandomSource {
+    fn from_entropy() -> Self {
+        RandomSource {
+            rng: StdRng::from_entropy(),
+            fixed_seed: false,
+        }
+    }
+
+    fn from_seed(seed: u64) -> Self {
+        RandomSource {
+            rng: StdRng::seed_from_u64(seed),
+            fixed_seed: true,
+        }
+    }
+}
+
+/// Clones of a VM produce different random numbers, unless a fixed seed has
+/// been set for reproducibility.
+impl Clone for RandomSource {
+    fn clone(&self) -> Self {
+        if self.fixed_seed {
+            RandomSource {
+                rng: self.rng.clone(),
+                fixed_seed: true,
+            }
+        } else {
+            RandomSource::from_entropy()
+        }
+    }
+}
+
 pub struct ExecutionContext<'a> {
     pub print_fn: &'a mut PrintFunction,
+    pub trace_fn: Option<&'a mut TraceFunction>,
 }
 
 #[derive(Clone)]
@@ -279,12 +425,27 @@ pub struct Vm {
     /// currently being compiled.
     current_chunk_index: usize,
 
+    /// Source code spans for the instructions of each function (same indices as
+    /// `bytecode`). An entry `(offset, span)` applies to all instructions from
+    /// that offset up to the offset of the next entry.
+    spans: Vec<Vec<(usize, Option<Span>)>>,
+
+    /// Spans of the expressions that are currently being compiled
+    span_stack: Vec<Span>,
+
+    /// Whether or not to record spans for the instructions that are being
+    /// compiled. They are only needed for tracing and in debug mode.
+    record_spans: bool,
+
     /// Constants are numbers like '1.4' or a [Unit] like 'meter'.
     pub constants: Vec<Constant>,
 
     /// struct metadata, used so we can display struct fields at runtime
     struct_infos: IndexMap<String, Arc<StructInfo>>,
 
+    /// Paths of field indices for nested struct updates
+    field_paths: Vec<Vec<usize>>,
+
     /// Unit prefixes in use
     prefixes: Vec<Prefix>,
 
@@ -316,7 +477,51 @@ pub struct Vm {
     /// Whether or not to run in debug mode.
     debug: bool,
 
+    /// Set from another thread to abort the evaluation
+    interrupt_flag: Arc<AtomicBool>,
+
+    /// Total number of instructions that have been executed by this VM
+    instruction_count: u64,
+
+    /// Approximate number of bytes that have been allocated for values since
+    /// the last call to `reset_allocated_bytes`. This is the total of all
+    /// allocations, memory that has been freed in the meantime is not subtracted.
+    allocated_bytes: usize,
+
+    /// Evaluations are aborted when `allocated_bytes` exceeds this limit
+    memory_limit: Option<usize>,
+
+    /// Source of randomness for `random()`
+    rng: RandomSource,
+
+    /// Whether or not functions like `read_csv` may access the file system
+    file_system_access: bool,
+
+    /// Whether or not functions like `fetch_json` may access the network
+    network_access: bool,
+
+    /// Whether or not `env` may read environment variables
+    environment_access: bool,
+
+    /// Command-line arguments of the script that is being run, returned by `args()`
+    script_arguments: Vec<String>,
+
     pub unit_registry: UnitRegistry,
+
+    /// Units by name, for parsing quantities at runtime
+    unit_lookup: UnitLookup,
+
+    /// Units in which results are shown, by dimension
+    preferred_units: PreferredUnits,
+
+    /// Derived units like `N` or `W` that results are simplified to
+    simplification_targets: Vec<Unit>,
+
+    /// Whether or not results are simplified to `simplification_targets`
+    derived_unit_simplification: bool,
+
+    /// Units like `cup` in which results are shown with fractions
+    fractional_units: Vec<Unit>,
 }
 
 impl Vm {
@@ -324,8 +529,12 @@ impl Vm {
         Self {
             bytecode: vec![("<main>".into(), vec![])],
             current_chunk_index: 0,
+            spans: vec![vec![]],
+            span_stack: vec![],
+            record_spans: false,
             constants: vec![],
             struct_infos: IndexMap::new(),
+            field_paths: vec![],
             prefixes: vec![],
             strings: vec![],
             unit_information: vec![],
@@ -335,13 +544,72 @@ impl Vm {
             frames: vec![CallFrame::root()],
             stack: vec![],
             debug: false,
+            interrupt_flag: Arc::new(AtomicBool::new(false)),
+            instruction_count: 0,
+            allocated_bytes: 0,
+            memory_limit: None,
+            rng: RandomSource::from_entropy(),
+            file_system_access: false,
+            network_access: false,
+            environment_access: false,
+            script_arguments: vec![],
             unit_registry: UnitRegistry::new(),
+            unit_lookup: UnitLookup::new(),
+            preferred_units: PreferredUnits::default(),
+            simplification_targets: vec![],
+            derived_unit_simplification: true,
+            fractional_units: vec![],
         }
     }
     pub fn set_debug(&mut self, activate: bool) {
         self.debug = activate;
     }
 
+    pub(crate) fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    pub(crate) fn set_interrupt_flag(&mut self, flag: &InterruptFlag) {
+        if !Arc::ptr_eq(&self.interrupt_flag, &flag.0) {
+            self.interrupt_flag = flag.0.clone();
+        }
+    }
+
+    pub(crate) fn set_random_seed(&mut self, seed: u64) {
+        self.rng = RandomSource::from_seed(seed);
+    }
+
+    pub(crate) fn set_file_system_access(&mut self, allow: bool) {
+        self.file_system_access = allow;
+    }
+
+    pub(crate) fn set_network_access(&mut self, allow: bool) {
+        self.network_access = allow;
+    }
+
+    pub(crate) fn set_environment_access(&mut self, allow: bool) {
+        self.environment_access = allow;
+    }
+
+    pub(crate) fn set_script_arguments(&mut self, arguments: Vec<String>) {
+        self.script_arguments = arguments;
+    }
+
+    fn ensure_capability(&self, capability: Capability, function_name: &str) -> Result<()> {
+        match capability {
+            Capability::FileSystem if !self.file_system_access => Err(
+                RuntimeError::FileSystemAccessDisabled(function_name.to_string()),
+            ),
+            Capability::Network if !self.network_access => Err(
+                RuntimeError::NetworkAccessDisabled(function_name.to_string()),
+            ),
+            Capability::Environment if !self.environment_access => Err(
+                RuntimeError::EnvironmentAccessDisabled(function_name.to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
     // The following functions are helpers for the compilation process
 
     fn current_chunk_mut(&mut self) -> &mut Vec<u8> {
@@ -354,17 +622,61 @@ impl Vm {
         chunk.push(arg_bytes[1]);
     }
 
+    pub(crate) fn push_span(&mut self, span: Span) {
+        self.span_stack.push(span);
+    }
+
+    pub(crate) fn pop_span(&mut self) {
+        self.span_stack.pop();
+    }
+
+    /// Spans are always recorded in debug mode
+    pub(crate) fn set_record_spans(&mut self, record: bool) {
+        let record = record || self.debug;
+        if self.record_spans && !record {
+            // Make sure that the following instructions are not associated
+            // with the last recorded span
+            let offset = self.bytecode[self.current_chunk_index].1.len();
+            self.spans[self.current_chunk_index].push((offset, None));
+        }
+        self.record_spans = record;
+    }
+
+    fn record_span(&mut self) {
+        let span = self.span_stack.last().copied();
+        let offset = self.bytecode[self.current_chunk_index].1.len();
+        let spans = &mut self.spans[self.current_chunk_index];
+        if spans.last().map(|(_, s)| *s) != Some(span) {
+            spans.push((offset, span));
+        }
+    }
+
+    fn span_at(&self, function_idx: usize, offset: usize) -> Option<Span> {
+        let spans = &self.spans[function_idx];
+        let idx = spans.partition_point(|(o, _)| *o <= offset);
+        spans.get(idx.checked_sub(1)?)?.1
+    }
+
     pub fn add_op(&mut self, op: Op) {
+        if self.record_spans {
+            self.record_span();
+        }
         self.current_chunk_mut().push(op as u8);
     }
 
     pub fn add_op1(&mut self, op: Op, arg: u16) {
+        if self.record_spans {
+            self.record_span();
+        }
         let current_chunk = self.current_chunk_mut();
         current_chunk.push(op as u8);
         Self::push_u16(current_chunk, arg)
     }
 
     pub(crate) fn add_op2(&mut self, op: Op, arg1: u16, arg2: u16) {
+        if self.record_spans {
+            self.record_span();
+        }
         let current_chunk = self.current_chunk_mut();
         current_chunk.push(op as u8);
         Self::push_u16(current_chunk, arg1);
@@ -372,6 +684,9 @@ impl Vm {
     }
 
     pub(crate) fn add_op3(&mut self, op: Op, arg1: u16, arg2: u16, arg3: u16) {
+        if self.record_spans {
+            self.record_span();
+        }
         let current_chunk = self.current_chunk_mut();
         current_chunk.push(op as u8);
         Self::push_u16(current_chunk, arg1);
@@ -408,6 +723,15 @@ impl Vm {
         self.struct_infos.get_index_of(name)
     }
 
+    pub fn add_field_path(&mut self, path: Vec<usize>) -> u16 {
+        if let Some(idx) = self.field_paths.iter().position(|p| p == &path) {
+            idx as u16
+        } else {
+            self.field_paths.push(path);
+            (self.field_paths.len() - 1) as u16
+        }
+    }
+
     pub fn add_prefix(&mut self, prefix: Prefix) -> u16 {
         if let Some(idx) = self.prefixes.iter().position(|p| p == &prefix) {
             idx as u16
@@ -418,6 +742,93 @@ impl Vm {
         }
     }
 
+    /// Make a base unit available for parsing quantities at runtime. Derived
+    /// units are added once their defining expression has been evaluated.
+    pub(crate) fn add_unit_to_lookup(
+        &mut self,
+        unit_name: &str,
+        unit: &Unit,
+        metadata: &UnitMetadata,
+    ) {
+        self.unit_lookup.add_unit(unit_name, unit, metadata);
+    }
+
+    /// Make an alias from a `unit alias` definition available for parsing
+    /// quantities at runtime
+    pub(crate) fn add_unit_alias_to_lookup(
+        &mut self,
+        alias: &str,
+        accepts_prefix: AcceptsPrefix,
+        unit_name: &str,
+    ) {
+        self.unit_lookup.add_alias(alias, accepts_prefix, unit_name);
+    }
+
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.allocated_bytes
+    }
+
+    pub(crate) fn reset_allocated_bytes(&mut self) {
+        self.allocated_bytes = 0;
+    }
+
+    pub(crate) fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    pub(crate) fn set_derived_unit_simplification(&mut self, enable: bool) {
+        self.derived_unit_simplification = enable;
+    }
+
+    pub(crate) fn unit_lookup(&self) -> &UnitLookup {
+        &self.unit_lookup
+    }
+
+    /// The value of the global variable in the given stack slot
+    pub(crate) fn global(&self, slot: usize) -> Option<&Value> {
+        self.stack.get(slot)
+    }
+
+    /// Simplify a quantity for display, taking preferred units and derived
+    /// units into account
+    pub(crate) fn simplify(&self, quantity: &Quantity) -> Quantity {
+        let derived_units: &[Unit] = if self.derived_unit_simplification {
+            &self.simplification_targets
+        } else {
+            &[]
+        };
+        let simplified = quantity.full_simplify_with(&self.preferred_units, derived_units);
+        if self.fractional_units.contains(simplified.unit()) {
+            simplified.with_fractional_display()
+        } else {
+            simplified
+        }
+    }
+
+    /// Convert a value to a string for string interpolation
+    fn to_interpolated_string(&self, value: Value) -> String {
+        match value {
+            Value::Quantity(q) => self.simplify(&q).to_string(),
+            Value::Boolean(b) => b.to_string(),
+            Value::String(s) => s,
+            Value::DateTime(dt) => crate::datetime::to_string(&dt),
+            Value::FunctionReference(r) => r.to_string(),
+            s @ Value::StructInstance(..) => s.to_string(),
+            l @ (Value::List(_) | Value::Range(_)) => l.to_string(),
+            d @ Value::Dict(_) => d.to_string(),
+            Value::FormatSpecifiers(_) => unreachable!(),
+        }
+    }
+
+    /// Make the name of a dimension available at runtime
+    pub(crate) fn add_dimension_to_lookup(
+        &mut self,
+        name: &str,
+        base_representation: BaseRepresentation,
+    ) {
+        self.unit_lookup.add_dimension(name, base_representation);
+    }
+
     pub fn add_unit_information(
         &mut self,
         unit_name: &str,
@@ -439,6 +850,7 @@ impl Vm {
 
     pub(crate) fn begin_function(&mut self, name: &str) {
         self.bytecode.push((name.into(), vec![]));
+        self.spans.push(vec![]);
         self.current_chunk_index = self.bytecode.len() - 1
     }
 
@@ -494,48 +906,124 @@ impl Vm {
         for (idx, identifier) in self.unit_information.iter().enumerate() {
             eprintln!("  {:04} {}", idx, identifier.0);
         }
-        for (idx, (function_name, bytecode)) in self.bytecode.iter().enumerate() {
-            eprintln!(".CODE {idx} ({function_name})");
-            let mut offset = 0;
-            while offset < bytecode.len() {
-                let this_offset = offset;
-                let op = bytecode[offset];
-                offset += 1;
-                let op = unsafe { std::mem::transmute::<u8, Op>(op) };
-
-                let mut operands: Vec<u16> = vec![];
-                for _ in 0..op.num_operands() {
-                    let operand =
-                        u16::from_le_bytes(bytecode[offset..(offset + 2)].try_into().unwrap());
-                    operands.push(operand);
-                    offset += 2;
-                }
+        eprint!("{}", self.disassemble_since(BytecodePosition::default()));
+        eprintln!();
+    }
 
-                let operands_str = operands
-                    .iter()
-                    .map(u16::to_string)
-                    .collect::<Vec<String>>()
-                    .join(" ");
-
-                eprint!(
-                    "  {:04} {:<13} {}",
-                    this_offset,
-                    op.to_string(),
-                    operands_str,
-                );
-
-                if op == Op::LoadConstant {
-                    eprint!("     (value: {})", self.constants[operands[0] as usize]);
-                } else if op == Op::Call {
-                    eprint!(
-                        "   ({}, num_args={})",
-                        self.bytecode[operands[0] as usize].0, operands[1] as usize
-                    );
+    pub(crate) fn position(&self) -> BytecodePosition {
+        BytecodePosition {
+            num_functions: self.bytecode.len(),
+            main_offset: self.bytecode[0].1.len(),
+        }
+    }
+
+    /// Returns a human-readable listing of all code that has been compiled
+    /// after the given position: new functions first, followed by the new
+    /// part of the global code.
+    pub(crate) fn disassemble_since(&self, position: BytecodePosition) -> String {
+        let mut output = String::new();
+        for idx in position.num_functions.max(1)..self.bytecode.len() {
+            self.disassemble_chunk(&mut output, idx, 0);
+        }
+        if position.main_offset < self.bytecode[0].1.len() || position.num_functions == 0 {
+            self.disassemble_chunk(&mut output, 0, position.main_offset);
+        }
+        output
+    }
+
+    /// Returns a human-readable listing of the most recent definition of the
+    /// given function.
+    pub(crate) fn disassemble_function(&self, name: &str) -> Option<String> {
+        let idx = self.bytecode.iter().skip(1).rposition(|(n, _)| n == name)? + 1;
+
+        let mut output = String::new();
+        self.disassemble_chunk(&mut output, idx, 0);
+        Some(output)
+    }
+
+    fn disassemble_chunk(&self, output: &mut String, idx: usize, start_offset: usize) {
+        use std::fmt::Write;
+
+        let (function_name, bytecode) = &self.bytecode[idx];
+        writeln!(output, ".CODE {idx} ({function_name})").unwrap();
+
+        let mut offset = start_offset;
+        while offset < bytecode.len() {
+            let this_offset = offset;
+            let op = bytecode[offset];
+            offset += 1;
+            let op = unsafe { std::mem::transmute::<u8, Op>(op) };
+
+            let mut operands: Vec<u16> = vec![];
+            for _ in 0..op.num_operands() {
+                let operand =
+                    u16::from_le_bytes(bytecode[offset..(offset + 2)].try_into().unwrap());
+                operands.push(operand);
+                offset += 2;
+            }
+
+            let operands_str = operands
+                .iter()
+                .map(u16::to_string)
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            write!(
+                output,
+                "  {:04} {:<13} {:<11}",
+                this_offset,
+                op.to_string(),
+                operands_str,
+            )
+            .unwrap();
+
+            let annotation = match op {
+                Op::LoadConstant => match &self.constants[operands[0] as usize] {
+                    Constant::Unit(unit) => {
+                        let (base_unit, factor) = unit.to_base_unit_representation();
+                        if &base_unit == unit {
+                            Some(format!("value: {unit}"))
+                        } else {
+                            Some(format!("value: {unit} = {factor} {base_unit}"))
+                        }
+                    }
+                    constant => Some(format!("value: {constant}")),
+                },
+                Op::ApplyPrefix => Some(format!(
+                    "factor: {}",
+                    self.prefixes[operands[0] as usize].factor()
+                )),
+                Op::SetUnitConstant => Some(format!(
+                    "unit: {}",
+                    self.unit_information[operands[0] as usize].0
+                )),
+                Op::Call => Some(format!(
+                    "{}, num_args={}",
+                    self.bytecode[operands[0] as usize].0, operands[1]
+                )),
+                Op::FFICallFunction | Op::FFICallProcedure => Some(format!(
+                    "{}, num_args={}",
+                    self.ffi_callables[operands[0] as usize].name, operands[1]
+                )),
+                Op::Jump | Op::JumpIfFalse => {
+                    Some(format!("to {:04}", offset + operands[0] as usize))
                 }
-                eprintln!();
+                Op::BuildStructInstance => self
+                    .struct_infos
+                    .get_index(operands[0] as usize)
+                    .map(|(name, _)| format!("struct: {name}")),
+                Op::UpdateStructField => Some(format!(
+                    "path: {:?}",
+                    self.field_paths[operands[0] as usize]
+                )),
+                _ => None,
+            };
+
+            if let Some(annotation) = annotation {
+                write!(output, " ({annotation})").unwrap();
             }
+            writeln!(output).unwrap();
         }
-        eprintln!();
     }
 
     // The following functions are helpers for the actual execution of the code
@@ -572,6 +1060,42 @@ impl Vm {
         self.stack.push(value);
     }
 
+    /// Fail if allocating another `bytes` would exceed the memory limit.
+    fn check_allocation(&self, bytes: usize) -> Result<()> {
+        match self.memory_limit {
+            Some(limit) if self.allocated_bytes.saturating_add(bytes) > limit => {
+                Err(RuntimeError::MemoryLimitExceeded(limit))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Account for `bytes` of newly allocated memory. Fails if this exceeds the
+    /// memory limit.
+    fn track_allocation(&mut self, bytes: usize) -> Result<()> {
+        self.check_allocation(bytes)?;
+        self.allocated_bytes = self.allocated_bytes.saturating_add(bytes);
+        Ok(())
+    }
+
+    /// Push the result of a foreign function. Lists that re-use the allocation
+    /// of one of the `inputs` are only accounted for with their additional capacity.
+    fn push_ffi_result(&mut self, value: Value, inputs: &[ListAllocation]) -> Result<()> {
+        let bytes = match &value {
+            Value::List(list) => {
+                let (allocation, capacity) = list.allocation();
+                let reused = inputs
+                    .iter()
+                    .find(|(input, _)| *input == allocation)
+                    .map_or(0, |(_, capacity)| *capacity);
+                capacity.saturating_sub(reused) * std::mem::size_of::<Value>()
+            }
+            value => value.heap_size(),
+        };
+        self.push(value);
+        self.track_allocation(bytes)
+    }
+
     #[track_caller]
     fn pop_quantity(&mut self) -> Quantity {
         match self.pop() {
@@ -586,7 +1110,7 @@ impl Vm {
     }
 
     #[track_caller]
-    fn pop_datetime(&mut self) -> jiff::Zoned {
+    fn pop_datetime(&mut self) -> crate::datetime::Zoned {
         match self.pop() {
             Value::DateTime(q) => q,
             _ => panic!("Expected datetime to be on the top of the stack"),
@@ -622,11 +1146,41 @@ impl Vm {
         self.current_frame().ip >= self.bytecode[self.current_frame().function_idx].1.len()
     }
 
+    fn check_interrupt(&self) -> Result<()> {
+        if self.interrupt_flag.load(AtomicOrdering::Relaxed) {
+            return Err(RuntimeError::Interrupted);
+        }
+        Ok(())
+    }
+
     fn run_without_cleanup(&mut self, ctx: &mut ExecutionContext) -> Result<InterpreterResult> {
+        let mut num_instructions = 0;
+        let result = self.execute(ctx, &mut num_instructions);
+        self.instruction_count += num_instructions;
+        result
+    }
+
+    fn execute(
+        &mut self,
+        ctx: &mut ExecutionContext,
+        num_instructions: &mut u64,
+    ) -> Result<InterpreterResult> {
+        // Jumps only ever go forward, so code can only run for a long time if
+        // it calls functions. It is enough to check for interrupts before the
+        // code starts and on every call.
+        self.check_interrupt()?;
+        let instrumented = self.debug || ctx.trace_fn.is_some();
+
         let mut result_last_statement = None;
         while !self.is_at_the_end() {
-            self.debug();
+            if instrumented {
+                self.debug();
+                if let Some(trace_fn) = &mut ctx.trace_fn {
+                    self.trace(&mut **trace_fn);
+                }
+            }
 
+            *num_instructions += 1;
             let op = unsafe { std::mem::transmute::<u8, Op>(self.read_byte()) };
 
             match op {
@@ -663,12 +1217,27 @@ impl Vm {
                         )
                         .map_err(RuntimeError::UnitRegistryError)?;
 
-                    self.constants[constant_idx as usize] = Constant::Unit(Unit::new_derived(
+                    let unit = Unit::new_derived(
                         &unit_information.0,
                         unit_information.2.canonical_name.clone(),
                         *conversion_value.unsafe_value(),
                         defining_unit.clone(),
-                    ));
+                    );
+                    self.unit_lookup
+                        .add_unit(&unit_information.0, &unit, &unit_information.2);
+                    self.constants[constant_idx as usize] = Constant::Unit(unit);
+                }
+                Op::SetPreferredUnit => {
+                    let quantity = self.pop_quantity();
+                    self.preferred_units.set(quantity.unit().clone());
+                }
+                Op::AddSimplificationTarget => {
+                    let quantity = self.pop_quantity();
+                    self.simplification_targets.push(quantity.unit().clone());
+                }
+                Op::AddFractionalUnit => {
+                    let quantity = self.pop_quantity();
+                    self.fractional_units.push(quantity.unit().clone());
                 }
                 Op::GetLocal => {
                     let slot_idx = self.read_u16() as usize;
@@ -713,22 +1282,9 @@ impl Vm {
                     let base = rhs.to_base_unit_representation();
                     let seconds_f64 = base.unsafe_value().to_f64();
 
-                    let seconds_i64 = seconds_f64
-                        .to_i64()
-                        .ok_or(RuntimeError::DurationOutOfRange)?;
-
-                    let span = jiff::Span::new()
-                        .try_seconds(seconds_i64)
-                        .map_err(|_| RuntimeError::DurationOutOfRange)?
-                        .nanoseconds((seconds_f64.fract() * 1_000_000_000f64).round() as i64);
-
                     self.push(Value::DateTime(match op {
-                        Op::AddToDateTime => lhs
-                            .checked_add(span)
-                            .map_err(|_| RuntimeError::DateTimeOutOfRange)?,
-                        Op::SubFromDateTime => lhs
-                            .checked_sub(span)
-                            .map_err(|_| RuntimeError::DateTimeOutOfRange)?,
+                        Op::AddToDateTime => crate::datetime::add_seconds(&lhs, seconds_f64)?,
+                        Op::SubFromDateTime => crate::datetime::sub_seconds(&lhs, seconds_f64)?,
                         _ => unreachable!(),
                     }));
                 }
@@ -737,12 +1293,7 @@ impl Vm {
                     let rhs = self.pop_datetime();
                     let lhs = self.pop_datetime();
 
-                    let duration = lhs
-                        .since(&rhs)
-                        .map_err(|_| RuntimeError::DateTimeOutOfRange)?;
-                    let duration = duration
-                        .total(jiff::Unit::Second)
-                        .map_err(|_| RuntimeError::DurationOutOfRange)?;
+                    let duration = crate::datetime::seconds_between(&lhs, &rhs)?;
 
                     let ret = Value::Quantity(Quantity::new(
                         Number::from_f64(duration),
@@ -815,7 +1366,12 @@ impl Vm {
                         return Err(RuntimeError::FactorialOfNonInteger);
                     }
 
-                    self.push_quantity(Quantity::from_scalar(math::factorial(lhs)));
+                    let result = math::factorial(lhs);
+                    if result.is_infinite() {
+                        return Err(RuntimeError::FloatOverflow);
+                    }
+
+                    self.push_quantity(Quantity::from_scalar(result));
                 }
                 Op::JumpIfFalse => {
                     let offset = self.read_u16() as usize;
@@ -828,6 +1384,7 @@ impl Vm {
                     self.current_frame_mut().ip += offset;
                 }
                 Op::Call => {
+                    self.check_interrupt()?;
                     let function_idx = self.read_u16() as usize;
                     let num_args = self.read_u16() as usize;
                     self.frames.push(CallFrame {
@@ -847,11 +1404,44 @@ impl Vm {
                     for _ in 0..num_args {
                         args.push_front(self.pop());
                     }
+                    let inputs = list_allocations(&args);
+                    self.check_allocation(ffi::expected_allocation(
+                        self.ffi_callables[function_idx].name,
+                        &args,
+                    ))?;
 
                     match &self.ffi_callables[function_idx].callable {
                         Callable::Function(function) => {
                             let result = (function)(args);
-                            self.push(result?);
+                            self.push_ffi_result(result?, &inputs)?;
+                        }
+                        Callable::FunctionWithRng(function) => {
+                            let result = (function)(&mut self.rng.rng, args);
+                            self.push_ffi_result(result?, &inputs)?;
+                        }
+                        Callable::FunctionWithCapability(capability, function) => {
+                            self.ensure_capability(
+                                *capability,
+                                self.ffi_callables[function_idx].name,
+                            )?;
+                            let result = (function)(args);
+                            self.push_ffi_result(result?, &inputs)?;
+                        }
+                        Callable::FunctionWithCapabilityAndUnitLookup(capability, function) => {
+                            self.ensure_capability(
+                                *capability,
+                                self.ffi_callables[function_idx].name,
+                            )?;
+                            let result = (function)(&self.unit_lookup, args);
+                            self.push_ffi_result(result?, &inputs)?;
+                        }
+                        Callable::FunctionWithScriptArguments(function) => {
+                            let result = (function)(&self.script_arguments, args);
+                            self.push_ffi_result(result?, &inputs)?;
+                        }
+                        Callable::FunctionWithUnitLookup(function) => {
+                            let result = (function)(&self.unit_lookup, args);
+                            self.push_ffi_result(result?, &inputs)?;
                         }
                         Callable::Procedure(procedure) => {
                             let span_idx = self.read_u16() as usize;
@@ -869,6 +1459,7 @@ impl Vm {
                     }
                 }
                 Op::CallCallable => {
+                    self.check_interrupt()?;
                     let num_args = self.read_u16() as usize;
 
                     let callable = self.pop();
@@ -893,11 +1484,38 @@ impl Vm {
                             for _ in 0..num_args {
                                 args.push_front(self.pop());
                             }
+                            let inputs = list_allocations(&args);
+                            self.check_allocation(ffi::expected_allocation(name, &args))?;
 
                             match &self.ffi_callables[function_idx].callable {
                                 Callable::Function(function) => {
                                     let result = (function)(args);
-                                    self.push(result?);
+                                    self.push_ffi_result(result?, &inputs)?;
+                                }
+                                Callable::FunctionWithRng(function) => {
+                                    let result = (function)(&mut self.rng.rng, args);
+                                    self.push_ffi_result(result?, &inputs)?;
+                                }
+                                Callable::FunctionWithCapability(capability, function) => {
+                                    self.ensure_capability(*capability, name)?;
+                                    let result = (function)(args);
+                                    self.push_ffi_result(result?, &inputs)?;
+                                }
+                                Callable::FunctionWithCapabilityAndUnitLookup(
+                                    capability,
+                                    function,
+                                ) => {
+                                    self.ensure_capability(*capability, name)?;
+                                    let result = (function)(&self.unit_lookup, args);
+                                    self.push_ffi_result(result?, &inputs)?;
+                                }
+                                Callable::FunctionWithScriptArguments(function) => {
+                                    let result = (function)(&self.script_arguments, args);
+                                    self.push_ffi_result(result?, &inputs)?;
+                                }
+                                Callable::FunctionWithUnitLookup(function) => {
+                                    let result = (function)(&self.unit_lookup, args);
+                                    self.push_ffi_result(result?, &inputs)?;
                                 }
                                 Callable::Procedure(..) => unreachable!("Foreign procedures can not be targeted by a function reference"),
                             }
@@ -906,11 +1524,7 @@ impl Vm {
                             // TODO: implement this using a closure, once we have that in the language
 
                             let dt = self.pop_datetime();
-
-                            let tz = jiff::tz::TimeZone::get(&tz_name)
-                                .map_err(|_| RuntimeError::UnknownTimezone(tz_name))?;
-
-                            let dt = dt.with_time_zone(tz);
+                            let dt = crate::datetime::with_time_zone(&dt, tz_name)?;
 
                             self.push(Value::DateTime(dt));
                         }
@@ -924,16 +1538,6 @@ impl Vm {
                 Op::JoinString => {
                     let num_parts = self.read_u16() as usize;
                     let mut joined = String::new();
-                    let to_str = |value| match value {
-                        Value::Quantity(q) => q.full_simplify().to_string(),
-                        Value::Boolean(b) => b.to_string(),
-                        Value::String(s) => s,
-                        Value::DateTime(dt) => crate::datetime::to_string(&dt),
-                        Value::FunctionReference(r) => r.to_string(),
-                        s @ Value::StructInstance(..) => s.to_string(),
-                        l @ Value::List(_) => l.to_string(),
-                        Value::FormatSpecifiers(_) => unreachable!(),
-                    };
 
                     let map_strfmt_error_to_runtime_error = |err| match err {
                         strfmt::FmtError::Invalid(s) => RuntimeError::InvalidFormatSpecifiers(s),
@@ -947,37 +1551,28 @@ impl Vm {
                         let part = match self.pop() {
                             Value::FormatSpecifiers(Some(specifiers)) => match self.pop() {
                                 Value::Quantity(q) => {
-                                    let q = q.full_simplify();
-
-                                    let mut vars = HashMap::new();
-                                    vars.insert("value".to_string(), q.unsafe_value().to_f64());
-
-                                    let mut str =
-                                        strfmt::strfmt(&format!("{{value{specifiers}}}"), &vars)
-                                            .map_err(map_strfmt_error_to_runtime_error)?;
-
-                                    let unit_str = q.unit().to_string();
-
-                                    if !unit_str.is_empty() {
-                                        str += " ";
-                                        str += &unit_str;
-                                    }
-
-                                    str
+                                    format_quantity(&self.simplify(&q), &specifiers)?
                                 }
                                 value => {
                                     let mut vars = HashMap::new();
-                                    vars.insert("value".to_string(), to_str(value));
+                                    vars.insert(
+                                        "value".to_string(),
+                                        self.to_interpolated_string(value),
+                                    );
 
                                     strfmt::strfmt(&format!("{{value{specifiers}}}"), &vars)
                                         .map_err(map_strfmt_error_to_runtime_error)?
                                 }
                             },
-                            Value::FormatSpecifiers(None) => to_str(self.pop()),
-                            v => to_str(v),
+                            Value::FormatSpecifiers(None) => {
+                                let value = self.pop();
+                                self.to_interpolated_string(value)
+                            }
+                            v => self.to_interpolated_string(v),
                         };
                         joined = part + &joined; // reverse order
                     }
+                    self.track_allocation(joined.len())?;
                     self.push(Value::String(joined))
                 }
                 Op::Return => {
@@ -1017,7 +1612,9 @@ impl Vm {
                         content.push(self.pop());
                     }
 
-                    self.stack.push(Value::StructInstance(struct_info, content));
+                    let value = Value::StructInstance(struct_info, content);
+                    self.track_allocation(value.heap_size())?;
+                    self.stack.push(value);
                 }
                 Op::AccessStructField => {
                     let field_idx = self.read_u16();
@@ -1027,6 +1624,23 @@ impl Vm {
                     let value = fields.swap_remove(field_idx as usize);
                     self.stack.push(value);
                 }
+                Op::UpdateStructField => {
+                    let path_idx = self.read_u16();
+
+                    let value = self.pop();
+                    let mut instance = self.pop();
+
+                    let mut field = &mut instance;
+                    for &field_idx in &self.field_paths[path_idx as usize] {
+                        let Value::StructInstance(_, fields) = field else {
+                            unreachable!("Updated fields are always part of a struct");
+                        };
+                        field = &mut fields[field_idx];
+                    }
+                    *field = value;
+
+                    self.stack.push(instance);
+                }
                 Op::BuildList => {
                     let length = self.read_u16();
                     let mut list = NumbatList::with_capacity(length as usize);
@@ -1035,7 +1649,59 @@ impl Vm {
                         list.push_front(self.pop());
                     }
 
-                    self.stack.push(list.into());
+                    let value = Value::from(list);
+                    self.track_allocation(value.heap_size())?;
+                    self.stack.push(value);
+                }
+                Op::BuildDict => {
+                    let num_entries = self.read_u16() as usize;
+                    let mut entries = Vec::with_capacity(num_entries);
+
+                    for _ in 0..num_entries {
+                        let value = self.pop();
+                        let key = self.pop().unsafe_as_string();
+                        entries.push((key, value));
+                    }
+
+                    // Later entries take precedence for duplicate keys
+                    let value = Value::Dict(entries.into_iter().rev().collect());
+                    self.track_allocation(value.heap_size())?;
+                    self.stack.push(value);
+                }
+                Op::BuildRange => {
+                    let inclusive = self.read_u16() == 1;
+
+                    let step = self.pop_quantity();
+                    let end = self.pop_quantity();
+                    let start = self.pop_quantity();
+
+                    self.stack
+                        .push(Value::Range(NumbatRange::new(start, end, step, inclusive)?));
+                }
+                Op::CombineUncertainties => {
+                    let num_variables = self.read_u16() as usize;
+
+                    let shifted: Vec<Quantity> = (0..2 * num_variables)
+                        .map(|_| self.pop_quantity())
+                        .collect();
+                    let nominal = self.pop_quantity();
+
+                    let mut sum_of_squares = 0.0;
+                    for pair in shifted.chunks(2) {
+                        let value_in_nominal_unit = |q: &Quantity| {
+                            q.value_in(nominal.unit())
+                                .map_err(RuntimeError::QuantityError)
+                        };
+                        let deviation = (value_in_nominal_unit(&pair[0])?
+                            - value_in_nominal_unit(&pair[1])?)
+                            / 2.0;
+                        sum_of_squares += deviation * deviation;
+                    }
+
+                    self.push_quantity(Quantity::new_f64(
+                        sum_of_squares.sqrt(),
+                        nominal.unit().clone(),
+                    ));
                 }
             }
         }
@@ -1067,6 +1733,20 @@ impl Vm {
         );
     }
 
+    fn trace(&self, trace_fn: &mut TraceFunction) {
+        let frame = self.current_frame();
+        let (function_name, bytecode) = &self.bytecode[frame.function_idx];
+        let op = unsafe { std::mem::transmute::<u8, Op>(bytecode[frame.ip]) };
+
+        trace_fn(&TraceEvent {
+            function_name,
+            offset: frame.ip,
+            op: op.to_string(),
+            stack_top: self.stack.last(),
+            span: self.span_at(frame.function_idx, frame.ip),
+        });
+    }
+
     pub fn add_string(&mut self, m: Markup) -> u16 {
         self.strings.push(m);
         assert!(self.strings.len() <= u16::MAX as usize);
@@ -1092,6 +1772,7 @@ fn vm_basic() {
     let mut print_fn = |_: &Markup| {};
     let mut ctx = ExecutionContext {
         print_fn: &mut print_fn,
+        trace_fn: None,
     };
 
     assert_eq!(
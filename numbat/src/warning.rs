@@ -0,0 +1,42 @@
+use thiserror::Error;
+
+use crate::span::Span;
+
+/// Warnings are reported for code that works, but should probably be changed.
+/// They can be retrieved with [`crate::Context::take_warnings`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum Warning {
+    #[error("'{name}' is deprecated: {hint}")]
+    Deprecated {
+        span: Span,
+        name: String,
+        hint: String,
+    },
+
+    #[error("'{name}' shadows an existing unit or identifier")]
+    Shadowing {
+        span: Span,
+        name: String,
+        original_span: Span,
+    },
+
+    #[error("'{original}' is interpreted as '{replacement}'")]
+    CompatibilitySyntax {
+        original: String,
+        replacement: String,
+    },
+
+    /// Reported by [`crate::Context::check`]. `kind` is `"function"`,
+    /// `"variable"` or `"unit"`.
+    #[error("{kind} '{name}' is never used")]
+    Unused {
+        span: Span,
+        kind: &'static str,
+        name: String,
+    },
+
+    /// Reported by [`crate::Context::check`] for `if true then … else …` and
+    /// `if false then … else …`
+    #[error("branch is never evaluated, as the condition is always '{condition}'")]
+    UnreachableBranch { span: Span, condition: bool },
+}
@@ -1,12 +1,15 @@
 mod common;
 
-use common::get_test_context;
+use common::{get_test_context, get_test_context_without_prelude};
 
 use insta::assert_snapshot;
 use numbat::markup::{Formatter, PlainTextFormatter};
 use numbat::resolver::CodeSource;
 use numbat::NumbatError;
-use numbat::{pretty_print::PrettyPrint, Context, InterpreterResult};
+use numbat::{
+    pretty_print::{MathFormat, PrettyPrint},
+    Context, InterpreterResult, ScientificNotation, ShadowingPolicy, UnitOptions, Warning,
+};
 
 #[track_caller]
 fn expect_output_with_context(ctx: &mut Context, code: &str, expected_output: impl AsRef<str>) {
@@ -146,7 +149,10 @@ fn simple_value() {
     expect_failure("0x_", "Expected base-16 digit");
     expect_failure("0x_0", "Expected base-16 digit");
     expect_failure("0x0_", "Expected base-16 digit");
-    expect_failure("0x0.0", "Expected base-16 digit");
+    expect_failure(
+        "0x0.0",
+        "Expected binary exponent (like 'p0') in hexadecimal floating point literal",
+    );
 
     expect_output("NaN", "NaN");
     expect_output("inf", "inf");
@@ -222,6 +228,46 @@ fn test_conversions() {
     expect_output_with_context(&mut ctx, "12 deg -> x", "12°");
 }
 
+#[test]
+fn test_reciprocal_conversions() {
+    expect_output("8 L/100km -> mpg", "29.4018 mpg");
+    expect_output("30 mpg -> L/km", "0.0784049 l/km");
+
+    // Only units with a @reciprocal decorator can be converted like this
+    expect_failure("2 s -> Hz", "right hand side: Time⁻¹");
+    expect_output(
+        "@reciprocal\nunit per_s: Frequency = Hz\n2 s -> per_s",
+        "0.5 per_s",
+    );
+}
+
+#[test]
+fn test_implicit_multiplication_with_parentheses() {
+    expect_output("2(3+4)", "14");
+    expect_output("let a = 2\nlet b = 3\n(a)(b)", "6");
+    expect_output("let x = 1\n3 m (1 + x)", "6 m");
+    expect_output("1 / 2(4)", "0.125");
+    expect_output("sqrt(4)", "2");
+    expect_failure(
+        "sqrt (4)",
+        "'sqrt' is a function and can not be multiplied. To call it, remove the space",
+    );
+    expect_failure(
+        "fn f(x) = 2 x\nf (3 m)",
+        "remove the space before the parentheses: 'f(…)'",
+    );
+
+    let mut ctx = get_test_context();
+    ctx.set_strict_multiplication(true);
+    expect_failure_with_context(
+        &mut ctx,
+        "2(3+4)",
+        "Implicit multiplication with parentheses is disabled",
+    );
+    expect_output_with_context(&mut ctx, "2*(3+4)", "14");
+    expect_output_with_context(&mut ctx, "sqrt(4) m", "2 m");
+}
+
 #[test]
 fn test_implicit_conversion() {
     let mut ctx = get_test_context();
@@ -419,6 +465,16 @@ fn test_temperature_conversions() {
     expect_output("-40 -> from_fahrenheit -> celsius", "-40");
 }
 
+#[test]
+fn test_offset_conversions() {
+    expect_output("use physics::offset_conversion\n3 atm -> gauge", "2 atm");
+    expect_output("use physics::offset_conversion\n1 W -> dBm", "30");
+
+    // not part of the prelude, so these names stay available
+    expect_failure("3 bar -> psig", "Unknown identifier 'psig'");
+    expect_output("let gauge = 12\ngauge", "12");
+}
+
 #[test]
 fn test_other_functions() {
     expect_output("sqrt(4)", "2");
@@ -458,7 +514,7 @@ fn test_misc_examples() {
     expect_output("2min + 30s", "2.5 min");
     expect_output("2min + 30s -> sec", "150 s");
     expect_output("4/3 * pi * (6000km)³", "9.04779e+11 km³");
-    expect_output("40kg * 9.8m/s^2 * 150cm", "588 kg·m²/s²");
+    expect_output("40kg * 9.8m/s^2 * 150cm", "588 J");
     expect_output("sin(30°)", "0.5");
 
     expect_output("60mph -> m/s", "26.8224 m/s");
@@ -649,6 +705,16 @@ fn test_string_interpolation() {
         "\"a    1234    b\"",
     );
 
+    expect_output("\"{1234.5:e}\"", "\"1.2345e3\"");
+    expect_output("\"{1234.5 m:.2e}\"", "\"1.23e3 m\"");
+    expect_output("\"[{2 m:>6}]\"", "\"[   2 m]\"");
+    expect_output("\"[{2 m:<6}]\"", "\"[2 m   ]\"");
+    expect_output("\"[{2 m:*^7}]\"", "\"[**2 m**]\"");
+    expect_output("\"{-2.5 m:09.2}\"", "\"-002.50 m\"");
+    expect_output("\"{3 km/h:value}\"", "\"3\"");
+    expect_output("\"{pi m:.3value}\"", "\"3.142\"");
+    expect_output("\"[{2 m:>4value}]\"", "\"[   2]\"");
+
     // Doesn't work at the moment, as `strfmt` expects `i64`'s for `#x`, but Numbat deals with `f64`'s
     // internally
     //expect_output("\"{31:#x}\"", "0x1f")
@@ -671,6 +737,15 @@ fn test_string_interpolation() {
     );
 }
 
+#[cfg(feature = "regex")]
+#[test]
+fn test_invalid_regex() {
+    expect_failure(
+        "regex_match(\"foo\", \"(unclosed\")",
+        "Invalid regular expression",
+    );
+}
+
 #[test]
 fn test_overwrite_regular_function() {
     expect_output(
@@ -726,6 +801,21 @@ fn test_full_simplify_for_function_calls() {
     expect_output("floor(1.2 hours / hour)", "1");
 }
 
+#[cfg(feature = "datetime")]
+#[test]
+fn test_datetime_literals() {
+    expect_output("@2024-05-01T13:00Z", "2024-05-01 13:00:00 UTC");
+    expect_output("@2024-05-01T13:00Z + 90 min", "2024-05-01 14:30:00 UTC");
+    expect_output("@2024-05-01T13:00+02:00 == @2024-05-01T11:00Z", "true");
+
+    expect_failure("@2024-02-30", "Invalid date literal");
+    expect_failure(
+        "@2024-05-01T13:00[Europe/Nowhere",
+        "Unterminated time zone annotation in date literal",
+    );
+}
+
+#[cfg(feature = "datetime")]
 #[test]
 fn test_datetime_runtime_errors() {
     expect_failure("datetime(\"2000-01-99\")", "Unrecognized datetime format");
@@ -741,7 +831,15 @@ fn test_datetime_runtime_errors() {
     expect_failure(
         "format_datetime(\"%Y-%m-%dT%H%:M\", now())",
         "Error in datetime format",
-    )
+    );
+    expect_failure(
+        "parse_duration(\"1 fortnight\")",
+        "Unrecognized duration format: '1 fortnight'",
+    );
+    expect_failure(
+        "parse_clock(\"1:75:00\")",
+        "Unrecognized clock time format: '1:75:00'",
+    );
 }
 
 #[test]
@@ -961,3 +1059,1829 @@ mod tests {
         }
     }
 }
+
+#[test]
+fn test_aliased_and_selective_imports() {
+    let mut ctx = get_test_context_without_prelude();
+    let _ = ctx
+        .interpret("use math::geometry as geo", CodeSource::Internal)
+        .unwrap();
+    expect_output_with_context(&mut ctx, "geo::hypot2(3, 4)", "5");
+    expect_output_with_context(&mut ctx, "geo::hypot3(2, 3, 6)", "7");
+    expect_failure_with_context(&mut ctx, "hypot2(3, 4)", "Unknown identifier 'hypot2'");
+
+    // Importing the module again makes the names available unqualified
+    let _ = ctx
+        .interpret("use math::geometry", CodeSource::Internal)
+        .unwrap();
+    expect_output_with_context(&mut ctx, "hypot2(3, 4)", "5");
+
+    let mut ctx = get_test_context_without_prelude();
+    let _ = ctx
+        .interpret("use math::geometry (hypot2)", CodeSource::Internal)
+        .unwrap();
+    expect_output_with_context(&mut ctx, "hypot2(3, 4)", "5");
+    expect_output_with_context(&mut ctx, "math::geometry::hypot3(2, 3, 6)", "7");
+    expect_failure_with_context(&mut ctx, "hypot3(2, 3, 6)", "Unknown identifier 'hypot3'");
+    expect_failure_with_context(
+        &mut ctx,
+        "use math::statistics (foo)",
+        "Module 'math::statistics' does not define 'foo'",
+    );
+
+    // Modules that have already been imported (here: by the prelude) can be aliased as well
+    expect_output("use math::geometry as geo\ngeo::hypot2(3, 4)", "5");
+}
+
+#[test]
+fn test_disassembler() {
+    let mut ctx = get_test_context_without_prelude();
+    ctx.set_function_inlining(false);
+    let _ = ctx
+        .interpret(
+            "unit m\nunit ft = 0.3048 m\nfn double(x) = 2 x",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    let _ = ctx.interpret("double(3 ft)", CodeSource::Internal).unwrap();
+    let listing = ctx.disassemble_last();
+    assert!(listing.contains(".CODE 0 (<main>)"));
+    assert!(listing.contains("value: ft = 0.3048 m"));
+    assert!(listing.contains("(double, num_args=1)"));
+    assert!(!listing.contains("SetUnitConstant"));
+
+    let listing = ctx.disassemble_function("double").unwrap();
+    assert!(listing.contains("(double)"));
+    assert!(listing.contains("Multiply"));
+    assert!(listing.contains("Return"));
+
+    assert!(ctx.disassemble_function("foo").is_none());
+
+    // The listing covers the whole input, even if an observer is installed
+    ctx.set_observer(|_, _| {});
+    let _ = ctx
+        .interpret("double(1)\ndouble(2)", CodeSource::Internal)
+        .unwrap();
+    let listing = ctx.disassemble_last();
+    assert_eq!(listing.matches("(double, num_args=1)").count(), 2);
+    ctx.clear_observer();
+
+    // … and it is kept if the evaluation fails
+    assert!(ctx
+        .interpret("double(2) / 0", CodeSource::Internal)
+        .is_err());
+    assert!(ctx.disassemble_last().contains("(double, num_args=1)"));
+}
+
+#[test]
+fn test_function_inlining() {
+    let mut ctx = get_test_context_without_prelude();
+    let _ = ctx
+        .interpret(
+            "unit m\nunit ft = 0.3048 m\nfn double(x) = 2 x\nfn quadruple(x) = double(double(x))",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    // Nested calls are inlined as well
+    expect_output_with_context(&mut ctx, "quadruple(3 ft)", "12 ft");
+    let listing = ctx.disassemble_last();
+    assert!(!listing.contains("(quadruple, num_args=1)"));
+    assert!(!listing.contains("(double, num_args=1)"));
+
+    // Arguments that are used more than once are only evaluated once
+    let _ = ctx
+        .interpret("fn sqr(x) = x * x", CodeSource::Internal)
+        .unwrap();
+    expect_output_with_context(&mut ctx, "let y = 3 m\nsqr(y)", "9 m²");
+    expect_output_with_context(&mut ctx, "sqr(y + 1 m)", "16 m²");
+    assert!(ctx.disassemble_last().contains("(sqr, num_args=1)"));
+
+    // Functions that have been inlined behave like the original function
+    // when the functions they call are redefined
+    let _ = ctx
+        .interpret("fn double(x) = 3 x", CodeSource::Internal)
+        .unwrap();
+    expect_output_with_context(&mut ctx, "quadruple(1)", "4");
+    expect_output_with_context(&mut ctx, "double(1)", "3");
+
+    // Constant accessors are inlined, unless the constant is shadowed at the
+    // call site or has been redefined
+    let _ = ctx
+        .interpret(
+            "let c = 3 m\nfn get_c() = c\nfn scaled_c(x) = x * c",
+            CodeSource::Internal,
+        )
+        .unwrap();
+    expect_output_with_context(&mut ctx, "get_c()", "3 m");
+    assert!(!ctx.disassemble_last().contains("(get_c, num_args=0)"));
+    expect_output_with_context(&mut ctx, "fn f(c) = scaled_c(c)\nf(2)", "6 m");
+    let _ = ctx.interpret("let c = 5 m", CodeSource::Internal).unwrap();
+    expect_output_with_context(&mut ctx, "get_c()", "3 m");
+    assert!(ctx.disassemble_last().contains("(get_c, num_args=0)"));
+
+    ctx.set_function_inlining(false);
+    let _ = ctx.interpret("sqr(y)", CodeSource::Internal).unwrap();
+    assert!(ctx.disassemble_last().contains("(sqr, num_args=1)"));
+}
+
+#[test]
+fn test_trace_function() {
+    use numbat::{InterpreterSettings, TraceEvent};
+    use std::sync::{Arc, Mutex};
+
+    let trace = Arc::new(Mutex::new(vec![]));
+    let trace_c = trace.clone();
+    let mut settings = InterpreterSettings {
+        trace_fn: Some(Box::new(move |event: &TraceEvent| {
+            trace_c.lock().unwrap().push((
+                event.op,
+                event.stack_top.cloned(),
+                event.span.map(|span| span.start.byte),
+            ));
+        })),
+        ..Default::default()
+    };
+
+    let mut ctx = get_test_context_without_prelude();
+    let _ = ctx
+        .interpret_with_settings(&mut settings, "2 + 3", CodeSource::Internal)
+        .unwrap();
+
+    let trace = trace.lock().unwrap();
+    let ops: Vec<_> = trace.iter().map(|(op, _, _)| *op).collect();
+    assert_eq!(ops, ["LoadConstant", "LoadConstant", "Add", "Return"]);
+
+    // The operands are associated with their own spans, the addition with the
+    // span of the whole expression
+    assert_eq!(trace[1].2, Some(4));
+    assert_eq!(trace[2].2, Some(0));
+    assert!(trace[0].1.is_none());
+    assert!(trace[2].1.is_some());
+}
+
+#[test]
+fn test_trace_spans_of_untraced_code() {
+    use numbat::{InterpreterSettings, TraceEvent};
+    use std::sync::{Arc, Mutex};
+
+    let mut ctx = get_test_context_without_prelude();
+
+    // Spans are only recorded for code that is compiled while tracing
+    let _ = ctx
+        .interpret(
+            "fn countdown(n) = if n == 0 then 0 else countdown(n - 1)",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    let spans = Arc::new(Mutex::new(vec![]));
+    let spans_c = spans.clone();
+    let mut settings = InterpreterSettings {
+        trace_fn: Some(Box::new(move |event: &TraceEvent| {
+            spans_c
+                .lock()
+                .unwrap()
+                .push((event.function_name.to_string(), event.span));
+        })),
+        ..Default::default()
+    };
+    let _ = ctx
+        .interpret_with_settings(&mut settings, "countdown(3)", CodeSource::Internal)
+        .unwrap();
+
+    let spans = spans.lock().unwrap();
+    let (function, main): (Vec<_>, Vec<_>) =
+        spans.iter().partition(|(name, _)| name == "countdown");
+    assert!(!function.is_empty());
+    assert!(function.iter().all(|(_, span)| span.is_none()));
+    assert!(main.iter().any(|(_, span)| span.is_some()));
+}
+
+#[test]
+fn test_statement_observer() {
+    use std::sync::{Arc, Mutex};
+
+    let log = Arc::new(Mutex::new(vec![]));
+    let log_c = log.clone();
+
+    let mut ctx = get_test_context();
+    ctx.set_observer(move |statement, result| {
+        let fmt = PlainTextFormatter {};
+        let value = match result {
+            InterpreterResult::Value(value) => fmt.format(&value.pretty_print(), false),
+            InterpreterResult::Continue => String::new(),
+        };
+        log_c
+            .lock()
+            .unwrap()
+            .push((fmt.format(&statement.pretty_print(), false), value));
+    });
+
+    let (_, result) = ctx
+        .interpret("let x = 2 m\nx + 3 m\nlet y = 1", CodeSource::Internal)
+        .unwrap();
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            ("let x: Length = 2 metre".to_owned(), "".to_owned()),
+            ("x + 3 metre".to_owned(), "5 m".to_owned()),
+            ("let y: Scalar = 1".to_owned(), "".to_owned()),
+        ]
+    );
+    assert!(result.is_value());
+
+    ctx.clear_observer();
+    let _ = ctx.interpret("x", CodeSource::Internal).unwrap();
+    assert_eq!(log.lock().unwrap().len(), 3);
+}
+
+#[test]
+fn test_output_sink() {
+    use std::sync::{Arc, Mutex};
+
+    let output = Arc::new(Mutex::new(vec![]));
+    let output_c = output.clone();
+
+    let mut ctx = get_test_context();
+    ctx.set_output_sink(move |m| {
+        let fmt = PlainTextFormatter {};
+        output_c.lock().unwrap().push(fmt.format(m, false));
+    });
+
+    let _ = ctx
+        .interpret("print(\"hello\")\nprint(2 km)", CodeSource::Internal)
+        .unwrap();
+
+    assert_eq!(*output.lock().unwrap(), ["hello", "2 km"]);
+}
+
+#[test]
+fn test_times_ten_notation() {
+    expect_output("1.602×10^-19 C", "1.602e-19 C");
+    expect_output("1.602·10⁻¹⁹ C -> aC", "0.1602 aC");
+    expect_output("1 / 2×10^3", "0.0005");
+
+    use std::sync::{Arc, Mutex};
+
+    let output = Arc::new(Mutex::new(vec![]));
+    let output_c = output.clone();
+
+    let mut ctx = get_test_context();
+    ctx.set_scientific_notation(ScientificNotation::Unicode);
+    ctx.set_output_sink(move |m| {
+        let fmt = PlainTextFormatter {};
+        output_c.lock().unwrap().push(fmt.format(m, false));
+    });
+
+    let _ = ctx
+        .interpret(
+            "print(1.602e-19 C)\nprint(6.02214076e23 / mol)\nprint(1234.5 m)\nprint(\"{1e-9}\")",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    assert_eq!(
+        *output.lock().unwrap(),
+        ["1.602×10⁻¹⁹ C", "6.02214×10²³ mol⁻¹", "1234.5 m", "1.0e-9"]
+    );
+}
+
+#[test]
+fn test_exchange_rates_available() {
+    let _ctx = get_test_context();
+    assert!(Context::exchange_rates_available());
+}
+
+#[cfg(feature = "fetch-exchangerates")]
+#[test]
+fn test_prefetch_exchange_rates_async() {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Poll, Wake, Waker};
+
+    /// Wakes up the thread that polls the future
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let _ctx = get_test_context();
+
+    let mut prefetch = std::pin::pin!(Context::prefetch_exchange_rates_async());
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut task_context = std::task::Context::from_waker(&waker);
+    let available = loop {
+        match prefetch.as_mut().poll(&mut task_context) {
+            Poll::Ready(available) => break available,
+            Poll::Pending => std::thread::park(),
+        }
+    };
+
+    assert!(available);
+    assert!(Context::exchange_rates_available());
+}
+
+#[test]
+fn test_parallel_evaluation_from_shared_context() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    let ctx = get_test_context();
+    assert_send_sync(&ctx);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (1..=4)
+            .map(|i| {
+                let ctx = &ctx;
+                scope.spawn(move || {
+                    let mut ctx = ctx.clone();
+                    let code = format!("let x = {i} km\nx -> m");
+                    match ctx.interpret(&code, CodeSource::Internal).unwrap().1 {
+                        InterpreterResult::Value(value) => {
+                            PlainTextFormatter {}.format(&value.pretty_print(), false)
+                        }
+                        InterpreterResult::Continue => panic!(),
+                    }
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, ["1000 m", "2000 m", "3000 m", "4000 m"]);
+    });
+}
+
+#[test]
+fn test_interpret_many() {
+    fn format_results(
+        results: Vec<Result<(Vec<numbat::Statement>, InterpreterResult), NumbatError>>,
+    ) -> Vec<String> {
+        results
+            .into_iter()
+            .map(|result| match result {
+                Ok((_, InterpreterResult::Value(value))) => {
+                    PlainTextFormatter {}.format(&value.pretty_print(), false)
+                }
+                Ok((_, InterpreterResult::Continue)) => "continue".into(),
+                Err(_) => "error".into(),
+            })
+            .collect()
+    }
+
+    let inputs = [
+        "2 km -> m",
+        "let x = 3\nx + 1",
+        "x",
+        "1 / 0",
+        "3 ft -> in",
+        "fn f(y) = 2 y\nf(4)",
+        "f(4)",
+        "ans",
+    ];
+    let expected = ["2000 m", "4", "error", "error", "36 in", "8", "error", "42"];
+
+    let mut ctx = get_test_context();
+    expect_output_with_context(&mut ctx, "42", "42");
+    assert_eq!(format_results(ctx.interpret_many(&inputs)), expected);
+
+    // Neither definitions nor results leaked into the context
+    assert!(ctx.interpret("x", CodeSource::Internal).is_err());
+    expect_output_with_context(&mut ctx, "ans", "42");
+
+    // Enough inputs to be distributed over several threads
+    let inputs = inputs.repeat(10);
+    let expected = expected.repeat(10);
+    assert_eq!(
+        format_results(ctx.interpret_many_parallel(&inputs)),
+        expected
+    );
+
+    // With a fixed seed, the results do not depend on how the inputs are distributed
+    ctx.set_random_seed(1);
+    let inputs = ["random()"; 20];
+    assert_eq!(
+        format_results(ctx.interpret_many(&inputs)),
+        format_results(ctx.interpret_many_parallel(&inputs))
+    );
+}
+
+#[test]
+fn test_check() {
+    let mut ctx = get_test_context();
+
+    let code = "let used = 2
+                let unused = 3
+                fn f(x) = if true then x else used
+                fn countdown(n: Scalar) -> Scalar = if n > 0 then countdown(n - 1) else 0
+                unit foo = 2 m
+                f(3)";
+    let warnings: Vec<String> = ctx
+        .check(code, CodeSource::Internal)
+        .unwrap()
+        .iter()
+        .map(|warning| warning.to_string())
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "variable 'unused' is never used",
+            "function 'countdown' is never used",
+            "unit 'foo' is never used",
+            "branch is never evaluated, as the condition is always 'true'",
+        ]
+    );
+
+    // The code has not been evaluated, nothing has been defined
+    assert!(ctx.check("1 / 0", CodeSource::Internal).unwrap().is_empty());
+    assert!(ctx.interpret("used", CodeSource::Internal).is_err());
+
+    assert!(matches!(
+        ctx.check("1 m + 1 s", CodeSource::Internal),
+        Err(NumbatError::TypeCheckError(_))
+    ));
+
+    // Modules that have only been checked are imported for real later on
+    assert!(ctx
+        .check("use extra::algebra", CodeSource::Internal)
+        .unwrap()
+        .is_empty());
+    expect_output_with_context(
+        &mut ctx,
+        "use extra::algebra\nlen(quadratic_equation(1, 0, -1))",
+        "2",
+    );
+}
+
+#[test]
+fn test_interrupt_handle() {
+    use numbat::InterpreterSettings;
+
+    let mut ctx = get_test_context();
+    let handle = ctx.interrupt_handle();
+
+    // Interrupt the evaluation after a fixed number of instructions. In a real
+    // application, this would happen from another thread.
+    let mut num_instructions = 0;
+    let mut settings = InterpreterSettings {
+        trace_fn: Some(Box::new(move |_: &numbat::TraceEvent| {
+            num_instructions += 1;
+            if num_instructions == 1000 {
+                handle.interrupt();
+            }
+        })),
+        ..Default::default()
+    };
+
+    let result = ctx.interpret_with_settings(
+        &mut settings,
+        "fn countdown(n) = if n == 0 then 0 else countdown(n - 1)\ncountdown(100000)",
+        CodeSource::Internal,
+    );
+
+    assert!(matches!(
+        result,
+        Err(NumbatError::RuntimeError(numbat::RuntimeError::Interrupted))
+    ));
+
+    // The context can still be used afterwards
+    expect_output_with_context(&mut ctx, "1 + 2", "3");
+
+    // An interrupt that is requested before the evaluation starts is not lost
+    ctx.interrupt_handle().interrupt();
+    assert!(matches!(
+        ctx.interpret("2 * 3", CodeSource::Internal),
+        Err(NumbatError::RuntimeError(numbat::RuntimeError::Interrupted))
+    ));
+    expect_output_with_context(&mut ctx, "1 + 2", "3");
+}
+
+#[test]
+fn test_instruction_count() {
+    let mut ctx = get_test_context();
+
+    let _ = ctx
+        .interpret(
+            "fn countdown(n) = if n == 0 then 0 else countdown(n - 1)",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    let _ = ctx
+        .interpret("countdown(10)", CodeSource::Internal)
+        .unwrap();
+    let short = ctx.last_instruction_count();
+    assert!(short > 0);
+
+    let _ = ctx
+        .interpret("countdown(1000)", CodeSource::Internal)
+        .unwrap();
+    let long = ctx.last_instruction_count();
+    assert!(long > 50 * short);
+
+    // Failed evaluations are counted as well
+    assert!(ctx.interpret("1 / 0 + 1", CodeSource::Internal).is_err());
+    assert!(ctx.last_instruction_count() > 0);
+}
+
+#[test]
+fn test_memory_limit() {
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret("fn square(x: Scalar) -> Scalar = x^2", CodeSource::Internal)
+        .unwrap();
+
+    let _ = ctx.interpret("[1, 2, 3]", CodeSource::Internal).unwrap();
+    let small = ctx.last_allocated_bytes();
+    assert!(small > 0);
+
+    let _ = ctx
+        .interpret("map(square, range(1, 1000))", CodeSource::Internal)
+        .unwrap();
+    assert!(ctx.last_allocated_bytes() > 100 * small);
+
+    // Taking the tail of a list does not allocate
+    let _ = ctx
+        .interpret("let xs = map(square, range(1, 1000))", CodeSource::Internal)
+        .unwrap();
+    let _ = ctx
+        .interpret("drop(900, xs)", CodeSource::Internal)
+        .unwrap();
+    assert!(ctx.last_allocated_bytes() < 10 * small);
+
+    ctx.set_memory_limit(Some(100_000));
+    let result = ctx.interpret("map(square, range(1, 100000))", CodeSource::Internal);
+    assert!(matches!(
+        result,
+        Err(NumbatError::RuntimeError(
+            numbat::RuntimeError::MemoryLimitExceeded(100_000)
+        ))
+    ));
+
+    // Huge results are rejected before they are built
+    for input in [
+        "cons(0, 1 ..= 1e15)",
+        "cons_end(0, range(1, 1e15))",
+        "str_length(str_repeat(\"abc\", 1e12))",
+    ] {
+        assert!(matches!(
+            ctx.interpret(input, CodeSource::Internal),
+            Err(NumbatError::RuntimeError(
+                numbat::RuntimeError::MemoryLimitExceeded(100_000)
+            ))
+        ));
+    }
+
+    // Smaller evaluations still work
+    expect_output_with_context(&mut ctx, "sum(map(square, [1, 2, 3]))", "14");
+    expect_output_with_context(&mut ctx, "str_length(str_repeat(\"abc\", 1000))", "3000");
+
+    ctx.set_memory_limit(None);
+    expect_output_with_context(&mut ctx, "len(map(square, range(1, 10000)))", "10000");
+}
+
+#[test]
+fn test_quantity_api() {
+    let ctx = get_test_context();
+
+    let distance = ctx.quantity(42.195, "km").unwrap();
+    let time = ctx.quantity(2.0, "h").unwrap();
+    let speed = distance / time;
+
+    let km_per_hour = ctx.unit("km/h").unwrap();
+    assert_eq!(
+        speed.convert_to(&km_per_hour).unwrap().to_string(),
+        "21.0975 km/h"
+    );
+    assert!((speed.value_in(&ctx.unit("m/s").unwrap()).unwrap() - 5.86042).abs() < 1e-5);
+
+    let sum = (&ctx.quantity(1.0, "m").unwrap() + &ctx.quantity(20.0, "cm").unwrap()).unwrap();
+    assert_eq!(sum.to_string(), "1.2 m");
+
+    let incompatible = &ctx.quantity(1.0, "m").unwrap() - &ctx.quantity(1.0, "s").unwrap();
+    assert!(matches!(
+        incompatible,
+        Err(numbat::QuantityError::IncompatibleUnits(_, _))
+    ));
+
+    assert!(matches!(
+        ctx.unit("speed_of_light"),
+        Err(NumbatError::RuntimeError(numbat::RuntimeError::NotAUnit(_)))
+    ));
+    assert!(ctx.unit("kilometre_typo").is_err());
+    assert!(ctx.unit("2 km").is_err());
+    assert!(ctx.unit("sqrt(4) m").is_err());
+}
+
+#[test]
+fn test_unit_lookup_does_not_evaluate_code() {
+    let mut ctx = get_test_context();
+    let _ = ctx.interpret("3 m", CodeSource::Internal).unwrap();
+
+    assert!(ctx.unit("km/h").is_ok());
+    assert!(ctx.unit("km)\nlet x = (1").is_err());
+    assert!(ctx.quantity(2.0, "ans").is_err());
+
+    expect_output_with_context(&mut ctx, "ans", "3 m");
+}
+
+#[test]
+fn test_convert() {
+    let ctx = get_test_context();
+
+    approx::assert_relative_eq!(ctx.convert(2.0, "km", "m").unwrap(), 2000.0);
+    approx::assert_relative_eq!(
+        ctx.convert(100.0, "km/h", "mph").unwrap(),
+        62.137119,
+        epsilon = 1e-6
+    );
+
+    let converted = ctx.convert_many(&[1.0, 2.5, 10.0], "kWh", "MJ").unwrap();
+    for (actual, expected) in converted.iter().zip([3.6, 9.0, 36.0]) {
+        approx::assert_relative_eq!(*actual, expected);
+    }
+
+    assert!(matches!(
+        ctx.convert(1.0, "m", "s"),
+        Err(NumbatError::RuntimeError(
+            numbat::RuntimeError::QuantityError(_)
+        ))
+    ));
+    assert!(ctx.convert(1.0, "m", "meterz").is_err());
+}
+
+#[test]
+fn test_unit_aliases() {
+    expect_output("unit alias mtr = meter\n2 mtr", "2 m");
+    expect_output("unit alias mtr = meter\n3 kilomtr -> m", "3000 m");
+    expect_output("unit alias mtr: short = meter\n3 kmtr -> m", "3000 m");
+    expect_output("unit alias Stunde = hour\n90 min -> Stunde", "1.5 h");
+    expect_output(
+        "unit alias mtr = meter\nfn f(x: Length) -> Length = 2 x\nf(1 mtr)",
+        "2 m",
+    );
+
+    expect_failure("unit alias mtr = meter\n1 mtr + 1 s", "Length");
+    expect_failure("unit alias mtr = km", "Not the name of a unit: 'km'");
+    expect_failure(
+        "unit alias c0 = speed_of_light",
+        "Not the name of a unit: 'speed_of_light'",
+    );
+    expect_failure("unit alias m = meter", "Identifier is already in use");
+    expect_failure(
+        "unit alias ms: short = meter",
+        "Identifier is already in use",
+    );
+
+    expect_output("@alias(mtr: short)\nunit meter\n3 kmtr -> m", "3000 m");
+    expect_output("@alias(Stunde) unit hour\n90 min -> Stunde", "1.5 h");
+
+    // Aliases can also be used when parsing quantities at runtime
+    expect_output(
+        "unit alias mtr: short = meter\nparse_quantity(\"3 kmtr\", m) -> m",
+        "3000 m",
+    );
+    expect_output(
+        "@alias(Stunde) unit hour\nparse_quantity(\"2 Stunde\", s) -> min",
+        "120 min",
+    );
+    expect_output(
+        "unit foo = 2 m\nunit alias quux = foo\nparse_quantity(\"3 quux\", m) -> m",
+        "6 m",
+    );
+
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret("unit alias mtr = meter", CodeSource::Internal)
+        .unwrap();
+    approx::assert_relative_eq!(ctx.convert(2.0, "kilomtr", "m").unwrap(), 2000.0);
+}
+
+#[test]
+fn test_preferred_units() {
+    expect_output("set preferred_unit Velocity = km/h\n10 m/s", "36 km/h");
+    expect_output("set preferred_unit Length = cm\n2 m × 3", "600 cm");
+    expect_output("set preferred_unit Length = cm\n2 m / 4 m", "0.5");
+    expect_output("set preferred_unit Length = cm\n2 m²", "2 m²");
+    expect_output(
+        "set preferred_unit Velocity = km/h\nset preferred_unit Velocity = mph\n10 m/s",
+        "22.3694 mph",
+    );
+    expect_output(
+        "set preferred_unit Length = cm\n\"distance: {1.5 m}\"",
+        "\"distance: 150 cm\"",
+    );
+
+    // Explicit conversions take precedence
+    expect_output(
+        "set preferred_unit Velocity = km/h\n10 m/s -> mph",
+        "22.3694 mph",
+    );
+    expect_output("set preferred_unit Length = cm\n2 m -> m", "2 m");
+
+    expect_failure("set preferred_unit Velocity = kg", "specified dimension");
+    expect_failure("set preferred_unit Velocity = true", "preferred unit");
+    expect_failure(
+        "set preferred_unit Scalar = percent",
+        "Preferred units can not be set for dimensionless quantities",
+    );
+}
+
+#[test]
+fn test_derived_unit_simplification() {
+    expect_output("2 kg × 3 m/s²", "6 N");
+    expect_output("2 kg × 3 m²/s³", "6 W");
+    expect_output("10 J / 2 s", "5 W");
+    expect_output("2 A × 3 s", "6 C");
+    expect_output("3 W / 2 A", "1.5 V");
+    expect_output("2 kg × 3 m²/s³ × 1 K", "6 W·K");
+
+    // Products that already contain a derived unit are left alone
+    expect_output("10 N × 0.5 m", "5 N·m");
+    expect_output("3 V × 2 A", "6 A·V");
+    expect_output("3 V × 2 A × 1.5 h", "9 A·V·h");
+
+    // Not simplified, since this would change the numerical value
+    expect_output("10 mA × 2 h", "20 mA·h");
+    expect_output("1 g × 1 m/s²", "1 g·m/s²");
+
+    // Explicit conversions take precedence
+    expect_output("2 kg × 3 m/s² -> kg m/s²", "6 kg·m/s²");
+
+    expect_output(
+        "dimension Foo = Length × Time\n\
+         @simplification_target\n\
+         unit foo: Foo = meter second\n\
+         2 m × 3 s",
+        "6 foo",
+    );
+
+    let mut ctx = get_test_context();
+    ctx.set_derived_unit_simplification(false);
+    expect_output_with_context(&mut ctx, "2 kg × 3 m/s²", "6 kg·m/s²");
+}
+
+#[test]
+fn test_long_unit_names() {
+    let mut ctx = get_test_context();
+    ctx.set_long_unit_names(true);
+
+    let mut expect_long_form = |code: &str, expected_output: &str| {
+        let InterpreterResult::Value(val) = ctx.interpret(code, CodeSource::Internal).unwrap().1
+        else {
+            panic!();
+        };
+        assert_eq!(
+            ctx.with_unit_names(val.pretty_print()).to_string(),
+            expected_output
+        );
+    };
+
+    expect_long_form("3 km/h", "3 kilometres per hour");
+    expect_long_form("1 km/h", "1 kilometre per hour");
+    expect_long_form("2 ft", "2 feet");
+    expect_long_form("12 inches", "12 inches");
+    expect_long_form("5 m^2", "5 square metres");
+    expect_long_form("9.81 m/s^2", "9.81 metres per second squared");
+    expect_long_form("2 kW h", "2 kilowatt hours");
+    expect_long_form("50 Hz", "50 hertz");
+    expect_long_form("30° -> deg", "30 degrees");
+    expect_long_form("42", "42");
+
+    let mut ctx = get_test_context();
+    let InterpreterResult::Value(val) = ctx.interpret("3 km/h", CodeSource::Internal).unwrap().1
+    else {
+        panic!();
+    };
+    assert_eq!(
+        ctx.with_unit_names(val.pretty_print()).to_string(),
+        "3 km/h"
+    );
+}
+
+#[test]
+fn test_latex() {
+    let latex = |code: &str| {
+        let mut ctx = get_test_context();
+        ctx.interpret(code, CodeSource::Internal)
+            .unwrap()
+            .1
+            .value_as_string()
+            .unwrap()
+            .trim_matches('"')
+            .to_string()
+    };
+
+    assert_eq!(latex("9.81 m/s² -> latex"), r"\qty{9.81}{m/s^{2}}");
+    assert_eq!(latex("30° -> deg -> latex"), r"\qty{30}{\degree}");
+    assert_eq!(latex("1.602e-19 C -> latex"), r"\qty{1.602e-19}{C}");
+    assert_eq!(latex("1234567 -> latex"), r"\num{1234567}");
+    assert_eq!(latex("true -> latex"), r"\text{true}");
+
+    let mut ctx = get_test_context();
+    let (statements, _) = ctx
+        .interpret("2 km / 30 min", CodeSource::Internal)
+        .unwrap();
+    let expression = statements[0].to_latex();
+    assert!(expression.contains(r"\qty{2}{\kilo\metre}"));
+    assert!(expression.contains(r"\qty{30}{\minute}"));
+}
+
+#[test]
+fn test_typst_and_mathml() {
+    let convert = |code: &str| {
+        let mut ctx = get_test_context();
+        let output = ctx
+            .interpret(code, CodeSource::Internal)
+            .unwrap()
+            .1
+            .value_as_string()
+            .unwrap();
+        // Only strip the quotes around the string, Typst output can end with a quote
+        output[1..output.len() - 1].to_string()
+    };
+
+    assert_eq!(convert("9.81 m/s² -> typst"), r#"9.81 thin "m" \/ "s"^2"#);
+    assert_eq!(
+        convert("1.602e-19 C -> typst"),
+        r#"1.602 times 10^(-19) thin "C""#
+    );
+    assert_eq!(
+        convert("9.81 m/s² -> mathml"),
+        r#"<math><mn>9.81</mn><mspace width="0.1667em"/><mrow><mi mathvariant="normal">m</mi><mo>/</mo><msup><mi mathvariant="normal">s</mi><mn>2</mn></msup></mrow></math>"#
+    );
+
+    let mut ctx = get_test_context();
+    let (statements, _) = ctx
+        .interpret("2 km / 30 min", CodeSource::Internal)
+        .unwrap();
+    assert!(statements[0]
+        .to_math(MathFormat::Typst)
+        .contains(r#"2 thin "kilometre""#));
+    assert!(statements[0]
+        .to_math(MathFormat::MathMl)
+        .starts_with("<math>"));
+}
+
+#[test]
+fn test_compatibility_syntax() {
+    let mut ctx = get_test_context();
+    ctx.set_compatibility_syntax(true);
+
+    let mut evaluate = |code: &str| {
+        let InterpreterResult::Value(val) = ctx.interpret(code, CodeSource::Text).unwrap().1 else {
+            panic!();
+        };
+        (val.pretty_print().to_string(), ctx.take_warnings())
+    };
+
+    let (output, warnings) = evaluate("5 ft 3 in -> in");
+    assert_eq!(output, "63 in");
+    assert!(matches!(
+        warnings.as_slice(),
+        [Warning::CompatibilitySyntax { replacement, .. }] if replacement == "(5 ft + 3 in)"
+    ));
+
+    assert_eq!(evaluate("|-3 m|").0, "3 m");
+    assert_eq!(evaluate("2 |3 - 5|").0, "4");
+    assert_eq!(evaluate("1|4 ft -> in").0, "3 in");
+    assert_eq!(evaluate("2 m2").0, "2 m²");
+
+    let (output, warnings) = evaluate("in->cm");
+    assert_eq!(output, "2.54 cm");
+    assert!(warnings.is_empty());
+
+    // Variables are not mistaken for exponents
+    let _ = evaluate("let t0 = 2 s\nt0");
+    let (output, warnings) = evaluate("t0 + 1 s");
+    assert_eq!(output, "3 s");
+    assert!(warnings.is_empty());
+    assert_eq!(evaluate("let m2 = 5\nm2 + 1").0, "6");
+
+    // Syntax checks see the translated code
+    assert!(ctx.check_syntax("|-3 m|").is_empty());
+    let errors = ctx.check_syntax("|x| +/ 2");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span.start.byte, 5);
+
+    let mut ctx = get_test_context();
+    assert!(ctx.interpret("|-3 m|", CodeSource::Text).is_err());
+    assert!(!ctx.check_syntax("|-3 m|").is_empty());
+}
+
+#[test]
+fn test_check_syntax() {
+    let ctx = get_test_context();
+
+    assert!(ctx.check_syntax("2 meter + 3 second").is_empty());
+    assert!(ctx.check_syntax("let x = 2\nx^3").is_empty());
+
+    let errors = ctx.check_syntax("2 +/ 3");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span.start.byte, 3);
+
+    let errors = ctx.check_syntax("2 +/ 3\nlet = 4");
+    assert_eq!(errors.len(), 2);
+}
+
+#[test]
+fn test_deprecation_warnings() {
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret(
+            "@deprecated(\"use 'newton' instead\")\n\
+             @aliases(kp: short)\n\
+             unit kilopond = 9.80665 N\n\
+             @deprecated(\"use 'sqr' instead\")\n\
+             fn square(x) = x^2",
+            CodeSource::Internal,
+        )
+        .unwrap();
+    assert!(ctx.take_warnings().is_empty());
+
+    expect_output_with_context(&mut ctx, "2 kp -> N", "19.6133 N");
+    let warnings = ctx.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].to_string(),
+        "'kp' is deprecated: use 'newton' instead"
+    );
+    assert!(ctx.take_warnings().is_empty());
+
+    expect_output_with_context(&mut ctx, "square(3)", "9");
+    assert_eq!(
+        ctx.take_warnings()[0].to_string(),
+        "'square' is deprecated: use 'sqr' instead"
+    );
+
+    // Redefining a name without the decorator removes the deprecation
+    expect_output_with_context(&mut ctx, "fn square(x) = x * x\nsquare(3)", "9");
+    assert!(ctx.take_warnings().is_empty());
+
+    // Warnings that are not taken do not pile up
+    expect_output_with_context(&mut ctx, "1 kp -> N", "9.80665 N");
+    expect_output_with_context(&mut ctx, "1 kp -> N", "9.80665 N");
+    assert_eq!(ctx.take_warnings().len(), 1);
+
+    ctx.set_deprecation_warnings(false);
+    expect_output_with_context(&mut ctx, "1 kilopond -> N", "9.80665 N");
+    assert!(ctx.take_warnings().is_empty());
+}
+
+#[test]
+fn test_shadowing() {
+    expect_failure("let am = 8 h", "Identifier is already in use");
+    expect_failure("fn f(d) = 2 d", "Identifier is already in use");
+
+    expect_output("@shadowing(allow)\nlet am = 8 h\nam -> min", "480 min");
+    expect_output("@shadowing(allow)\nlet am = 8 h\n3 km -> m", "3000 m");
+    expect_output("@shadowing(allow)\nfn f(d) = 2 d\nf(3)", "6");
+    expect_output("@shadowing(allow)\nlet m = 2\n3 meter * m", "6 m");
+    expect_failure(
+        "@shadowing(allow)\nlet m = 2\n3 km",
+        "Unknown identifier 'km'",
+    );
+    expect_output(
+        "let x = 2\n@shadowing(allow)\nunit x = 3 m\n2 x -> m",
+        "6 m",
+    );
+    expect_failure(
+        "@shadowing(error)\nlet am = 8 h",
+        "Identifier is already in use",
+    );
+    expect_failure("@shadowing(maybe)\nlet am = 8 h", "shadowing policy");
+
+    let mut ctx = get_test_context();
+    ctx.set_shadowing_policy(ShadowingPolicy::Warn);
+    expect_output_with_context(&mut ctx, "let am = 2\nam", "2");
+    let warnings = ctx.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].to_string(),
+        "'am' shadows an existing unit or identifier"
+    );
+
+    expect_output_with_context(&mut ctx, "@shadowing(allow)\nlet fm = 3\nfm", "3");
+    assert!(ctx.take_warnings().is_empty());
+}
+
+#[test]
+fn test_subscripts_and_superscripts() {
+    expect_output("let v₀ = 2 m/s\nv₀ × 3 s", "6 m");
+    expect_output("let E_k = 2 J\nlet Eₖ = 3 J\nE_k + Eₖ", "5 J");
+    expect_output("2 m→cm", "200 cm");
+
+    expect_output("2¹⁰", "1024");
+    expect_output("2⁻¹⁰ == 1 / 1024", "true");
+    expect_output("2⁰", "1");
+    expect_output("1 m²s⁻¹ -> cm²/s", "10000 cm²/s");
+    expect_output(
+        "fn f(x: Length¹⁰) -> Length¹⁰ = x\nf(1 m^10) == 1 m^10",
+        "true",
+    );
+
+    expect_failure(
+        "2²³⁴⁵⁶⁷⁸⁹⁰¹²³⁴⁵⁶⁷⁸⁹⁰¹²³⁴⁵⁶⁷⁸⁹⁰¹²³⁴⁵⁶⁷⁸⁹⁰",
+        "Overflow in number literal",
+    );
+}
+
+#[test]
+fn test_unicode_normalization() {
+    // Ångström sign and decomposed 'A' + combining ring, vs. the letter 'Å'
+    expect_output("\u{212B} == \u{00C5}", "true");
+    expect_output("A\u{030A} == \u{00C5}", "true");
+    // Ohm sign vs. Greek capital omega
+    expect_output("2 k\u{2126} -> \u{03A9}", "2000 Ω");
+    // Micro sign vs. Greek small letter mu
+    expect_output("\u{00B5}0 == \u{03BC}0", "true");
+    expect_output("3 \u{00B5}m == 3 \u{03BC}m", "true");
+    expect_output("let \u{00B5}_x = 2\n\u{03BC}_x", "2");
+    // Latin small letter h with stroke vs. Planck constant over two pi
+    expect_output("\u{0127} == \u{210F}", "true");
+
+    // Results use the normalized spelling
+    expect_output("2 k\u{2126}", "2 k\u{03A9}");
+    expect_output("3 \u{212B}", "3 \u{00C5}");
+    expect_output("5 \u{03BC}s", "5 \u{00B5}s");
+
+    expect_failure(
+        "unit \u{00B5}thing = 2 m\nunit \u{03BC}thing = 3 m",
+        "written with different characters ('µ' (U+00B5) and 'μ' (U+03BC))",
+    );
+}
+
+#[test]
+fn test_define_api() {
+    let mut ctx = get_test_context();
+
+    ctx.define_dimension("Currency").unwrap();
+    ctx.define_base_unit(
+        "credit",
+        "Currency",
+        UnitOptions {
+            aliases: vec!["credits".into()],
+            short_aliases: vec!["Cr".into()],
+            metric_prefixes: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.define_unit("bounty", 250.0, "kCr", UnitOptions::default())
+        .unwrap();
+    ctx.define_derived_dimension("CurrencyRate", &[("Currency", 1), ("Time", -1)])
+        .unwrap();
+
+    approx::assert_relative_eq!(ctx.convert(2.0, "bounty", "credits").unwrap(), 500_000.0);
+    approx::assert_relative_eq!(ctx.convert(1.0, "megacredit", "kCr").unwrap(), 1000.0);
+
+    expect_output_with_context(
+        &mut ctx,
+        "let income: CurrencyRate = 3 bounty / day\nincome -> Cr / h",
+        "31250 Cr/h",
+    );
+
+    // Failed definitions leave the context untouched
+    assert!(ctx.define_dimension("Currency").is_err());
+    assert!(ctx
+        .define_unit("credit", 2.0, "Cr", UnitOptions::default())
+        .is_err());
+    assert!(ctx
+        .define_unit("gold", 2.0, "unknown_unit", UnitOptions::default())
+        .is_err());
+    assert!(ctx.unit("gold").is_err());
+    approx::assert_relative_eq!(ctx.convert(1.0, "bounty", "Cr").unwrap(), 250_000.0);
+}
+
+#[test]
+fn test_info_for_structs() {
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret(
+            "use chemistry::elements\nstruct Pair<D: Dim> { first: D, second: List<String> }",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    let info = PlainTextFormatter {}.format(&ctx.print_info_for_keyword("Pair"), false);
+    assert!(info.contains("Struct: Pair<D>"));
+    assert!(info.contains("first: D\n"));
+    assert!(info.contains("second: List<String>\n"));
+
+    // Functions that return a struct also list its fields
+    let info = PlainTextFormatter {}.format(&ctx.print_info_for_keyword("element"), false);
+    assert!(info.contains("Returns:     ChemicalElement"));
+    assert!(info.contains("atomic_mass: Mass\n"));
+    assert!(info.contains("density: MassDensity\n"));
+}
+
+#[cfg(feature = "chemical-elements")]
+#[test]
+fn test_molar_mass() {
+    expect_output("molar_mass(\"H2O\")", "18.015 g/mol");
+    expect_output("molar_mass(\"C6H12O6\")", "180.156 g/mol");
+    expect_output("molar_mass(\"Ca(OH)2\")", "74.092 g/mol");
+    expect_output("molar_mass(\"CuSO4·5H2O\")", "249.677 g/mol");
+    expect_output("molar_mass(\"H2O\") -> kg/mol", "0.018015 kg/mol");
+
+    expect_failure("molar_mass(\"Xy2\")", "unknown element 'Xy'");
+    expect_failure("molar_mass(\"Ca(OH2\")", "missing ')'");
+    expect_failure("molar_mass(\"h2o\")", "unexpected character 'h'");
+    expect_failure(
+        "molar_mass(\"H99999999999\")",
+        "the number of atoms is too large",
+    );
+    expect_failure(
+        "molar_mass(\"(H65536)65536\")",
+        "the number of atoms is too large",
+    );
+}
+
+#[test]
+fn test_uncertainty_of_constants() {
+    expect_output("uncertainty(c) -> m/s", "0 m/s");
+    expect_output("uncertainty(electron_mass) -> kg", "2.8e-40 kg");
+    expect_output("uncertainty(µ0) == uncertainty(magnetic_constant)", "true");
+
+    expect_output("uncertainty(2 m) -> m", "0 m");
+    expect_failure(
+        "@uncertainty(1 m)\nunit foo = 2 m",
+        "only be used on let definitions",
+    );
+    expect_failure(
+        "@uncertainty(1 s)\nlet foo = 2 m",
+        "Incompatible types in uncertainty decorator",
+    );
+
+    // Uncertainties are propagated through calculations
+    expect_output("@uncertainty(3 m)\nlet x = 10 m\nuncertainty(x^2)", "60 m²");
+    expect_output(
+        "@uncertainty(3 m)\nlet x = 10 m\nlet y = 2 x\nuncertainty(y)",
+        "6 m",
+    );
+    expect_output(
+        "@uncertainty(3 m)\nlet x = 10 m\n@uncertainty(4 m)\nlet y = 10 m\nuncertainty(x + y)",
+        "5 m",
+    );
+    expect_output(
+        "@uncertainty(3 m)\nlet x = 10 m\nfn f(x: Length) -> Length = uncertainty(x)\nf(x) -> m",
+        "0 m",
+    );
+    expect_failure("map(uncertainty, [G])", "can only be called directly");
+
+    let mut ctx = get_test_context();
+    let _ = ctx.interpret("42", CodeSource::Internal).unwrap();
+    let info = PlainTextFormatter {}.format(&ctx.print_info_for_keyword("G"), false);
+    assert!(info.contains("Source: CODATA 2018"));
+    assert!(info.contains("Standard uncertainty: 1.5e-15"));
+
+    // Showing the uncertainty does not change the last result
+    expect_output_with_context(&mut ctx, "ans", "42");
+}
+
+#[test]
+fn test_cooking_unknown_ingredient() {
+    expect_failure(
+        "use extra::cooking\n2 cups × density(\"sand\")",
+        "density: Unknown ingredient 'sand'. Known ingredients are: water, butter,",
+    );
+}
+
+#[test]
+fn test_cooking_ingredients() {
+    expect_output("use extra::cooking\n2 cups flour -> gram", "260.247 g");
+    expect_output("use extra::cooking\n200 g / butter -> mL", "219.539 ml");
+}
+
+#[test]
+fn test_fractional_units() {
+    expect_output("1.5 cups", "1 ½ cup");
+    expect_output("cup / 3", "⅓ cup");
+    expect_output("1 pint / 3 -> cup", "⅔ cup");
+    expect_output("-0.5 tbsp", "-½ tablespoon");
+    expect_output("2 cups", "2 cup");
+    expect_output("0.3 cups", "0.3 cup");
+    expect_output("\"{2.25 tsp}\"", "\"2 ¼ teaspoon\"");
+    expect_output("1.5 L", "1.5 l");
+
+    expect_output("@fractional\nunit slice\n1.5 slice", "1 ½ slice");
+}
+
+#[test]
+fn test_weather_formulas_outside_of_valid_range() {
+    expect_failure(
+        "use extra::weather\nwind_chill(from_celsius(20), 20 km/h)",
+        "wind_chill: The air temperature must be at most 10 °C",
+    );
+    expect_failure(
+        "use extra::weather\nwind_chill(from_celsius(-10), 1 km/h)",
+        "wind_chill: The wind speed must be above 4.8 km/h",
+    );
+    expect_failure(
+        "use extra::weather\nheat_index(from_celsius(20), 50%)",
+        "heat_index: The air temperature must be at least 80 °F (26.7 °C)",
+    );
+    expect_failure(
+        "use extra::weather\ndew_point(from_celsius(20), 120%)",
+        "dew_point: The relative humidity must be larger than 0% and at most 100%",
+    );
+}
+
+#[test]
+fn test_statistics() {
+    expect_output("mean([1 m, 2 m])", "1.5 m");
+    expect_output("stdev([1 m, 3 m])", "1 m");
+    expect_output("variance([1 m, 3 m])", "1 m²");
+    expect_output("correlation([1 m, 2 m], [3 s, 5 s])", "1");
+
+    expect_failure("median([])", "Empty list");
+    expect_failure(
+        "quantile([1, 2, 3], 1.5)",
+        "Quantile must be between 0 and 1",
+    );
+    expect_failure(
+        "correlation([1, 2, 3], [1, 2])",
+        "Expected lists of equal length, got lists with 3 and 2 elements",
+    );
+}
+
+#[test]
+fn test_number_theory() {
+    expect_output("prime_factors(84)", "[2, 2, 3, 7]");
+    expect_output("modpow(2, 100, 1e9 + 7)", "976_371_285");
+
+    expect_failure(
+        "gcd(2.5, 5)",
+        "Expected an integer with an absolute value of at most 2^53, got 2.5",
+    );
+    expect_failure(
+        "is_prime(1e20)",
+        "Expected an integer with an absolute value of at most 2^53, got 100000000000000000000",
+    );
+    expect_failure("prime_factors(0)", "Expected a positive integer, got 0");
+    expect_failure(
+        "modpow(2, -1, 5)",
+        "Expected a non-negative integer, got -1",
+    );
+    expect_failure(
+        "lcm(2^30, 3^20)",
+        "Result is too large to be represented as an exact integer",
+    );
+
+    expect_output("binom(60, 30)", "1.18265e+17");
+    expect_failure(
+        "binom(2000, 1000)",
+        "Result is too large to be represented as a floating point number",
+    );
+    expect_failure("perm(-3, 2)", "Expected a non-negative integer, got -3");
+    expect_failure(
+        "factorial_exact(10_001)",
+        "Result is too large to be represented as an exact integer",
+    );
+    expect_failure(
+        "171!",
+        "Result is too large to be represented as a floating point number",
+    );
+}
+
+#[test]
+fn test_special_functions() {
+    expect_output("erf(1)", "0.842701");
+    expect_output("elliptic_k(1)", "inf");
+    expect_output("elliptic_k(2)", "NaN");
+    expect_output("elliptic_e(1.5)", "NaN");
+    expect_output("bessel_i(0, 1e300)", "inf");
+    expect_output("bessel_i(1_000_000_000, 2)", "0");
+
+    expect_failure(
+        "bessel_j(0.5, 1)",
+        "The order of Bessel functions must be an integer",
+    );
+}
+
+#[test]
+fn test_least_squares_fits() {
+    expect_output(
+        "fit_linear_slope([1 s, 2 s, 3 s], [2 m, 4 m, 6 m])",
+        "2 m/s",
+    );
+    expect_output(
+        "fit_polynomial([0, 1, 2], [1 m, 3 m, 5 m], 1, 1)",
+        "[1 m, 2 m]",
+    );
+
+    expect_failure(
+        "fit_polynomial([1, 2, 3], [1, 2, 3], 1.5, 1)",
+        "Polynomial degree must be a non-negative integer",
+    );
+    expect_failure(
+        "fit_polynomial([1, 1, 2], [1, 2, 3], 2, 1)",
+        "Not enough distinct data points to fit a polynomial of degree 2",
+    );
+}
+
+#[test]
+fn test_tables() {
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret("fn inch_to_cm(x) = x inch -> cm", CodeSource::Internal)
+        .unwrap();
+
+    expect_output_with_context(
+        &mut ctx,
+        "table([1, 2], inch_to_cm)",
+        r#"" x │ inch_to_cm(x)\n───┼───────────────\n 1 │       2.54 cm\n 2 │       5.08 cm""#,
+    );
+    expect_output_with_context(
+        &mut ctx,
+        "table_markdown([1, 2], inch_to_cm)",
+        r#""| x | inch_to_cm(x) |\n|---:|---:|\n| 1 | 2.54 cm |\n| 2 | 5.08 cm |""#,
+    );
+    expect_output_with_context(
+        &mut ctx,
+        r#"table_csv(["a", "b,c"], str_length)"#,
+        r#""x,str_length(x)\na,1\n\"b,c\",3""#,
+    );
+}
+
+#[test]
+fn test_table_lookups() {
+    expect_output("lookup(2, [1, 2, 3], [\"a\", \"b\", \"c\"])", "\"b\"");
+    expect_output("lookup_nearest(2.6 m, [1 m, 2 m, 3 m], [10, 20, 30])", "30");
+    expect_output(
+        "interpolate(1.25 m, [1 m, 2 m, 4 m], [10 s, 20 s, 30 s])",
+        "12.5 s",
+    );
+    expect_output(
+        "interpolate(3 m, [1 m, 2 m, 4 m], [10 s, 20 s, 30 s])",
+        "25 s",
+    );
+
+    expect_failure(
+        "lookup(4, [1, 2, 3], [\"a\", \"b\", \"c\"])",
+        "lookup: The key was not found in the table",
+    );
+    expect_failure(
+        "interpolate(5 m, [1 m, 2 m, 4 m], [10 s, 20 s, 30 s])",
+        "interpolate: The value is outside of the range of the table",
+    );
+    expect_failure(
+        "interpolate(0 m, [1 m, 2 m, 4 m], [10 s, 20 s, 30 s])",
+        "interpolate: The value is outside of the range of the table",
+    );
+}
+
+#[test]
+fn test_text_plots() {
+    expect_output("str_contains(plot(sin, 0, 2π), \"┤\")", "true");
+    expect_output(
+        "str_contains(plot_data([0 s, 1 s, 2 s], [0 m, 400 cm, 5 m]), \"[m]\")",
+        "true",
+    );
+    expect_output(
+        "str_contains(text_plot(xlabel(\"time\", line_plot_data([0 s, 1 min], [1, 2]))), \"time [s]\")",
+        "true",
+    );
+    expect_output(
+        "str_slice(svg_plot(line_plot(cos, 0, 1)), 0, 4)",
+        "\"<svg\"",
+    );
+
+    expect_failure(
+        "plot_data([1, 2], [1])",
+        "The lists of x and y values must have the same length",
+    );
+    expect_failure(
+        "plot(sqrt, -2, -1)",
+        "There are no finite data points to plot",
+    );
+}
+
+#[test]
+fn test_read_csv() {
+    let file = std::env::temp_dir().join(format!("numbat-read-csv-{}.csv", std::process::id()));
+    std::fs::write(
+        &file,
+        "name,time [s],power [W]\nfirst,1,100\n\"second, and last\",2.5,\n",
+    )
+    .unwrap();
+    let path = file.to_string_lossy().replace('\\', "\\\\");
+
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret(
+            "struct Measurement { name: String, time: Time, power: Power }",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    let read = |template: &str| format!("read_csv(\"{path}\", {template})");
+    let template = "Measurement { name: \"\", time: s, power: W }";
+
+    expect_failure_with_context(
+        &mut ctx,
+        &read(template),
+        "'read_csv' needs access to the file system",
+    );
+
+    ctx.set_file_system_access(true);
+    expect_failure_with_context(
+        &mut ctx,
+        &read(template),
+        "missing entry in column 'power' (row 3)",
+    );
+
+    let _ = ctx
+        .interpret(
+            &format!("let data = read_csv_with_missing(\"{path}\", {template})"),
+            CodeSource::Internal,
+        )
+        .unwrap();
+    expect_output_with_context(&mut ctx, "len(data)", "2");
+    expect_output_with_context(&mut ctx, "head(data).power", "100 W");
+    expect_output_with_context(&mut ctx, "head(tail(data)).time -> ms", "2500 ms");
+    expect_output_with_context(&mut ctx, "head(tail(data)).name", "\"second, and last\"");
+    expect_output_with_context(&mut ctx, "is_nan(head(tail(data)).power)", "true");
+
+    // The unit in the header takes precedence over the field value
+    let _ = ctx
+        .interpret(
+            &format!(
+                "let data_kw = read_csv_with_missing(\"{path}\", Measurement {{ name: \"\", time: min, power: kW }})"
+            ),
+            CodeSource::Internal,
+        )
+        .unwrap();
+    expect_output_with_context(&mut ctx, "head(data_kw).power", "0.1 kW");
+    expect_output_with_context(&mut ctx, "head(tail(data_kw)).time", "0.0416667 min");
+
+    let _ = ctx
+        .interpret("struct Flow { power: Current }", CodeSource::Internal)
+        .unwrap();
+    expect_failure_with_context(
+        &mut ctx,
+        &read("Flow { power: A }"),
+        "column 'power' is given in 'W', which is not compatible with the unit of the field, 'A'",
+    );
+
+    let _ = ctx
+        .interpret("struct Sample { voltage: Voltage }", CodeSource::Internal)
+        .unwrap();
+    expect_failure_with_context(
+        &mut ctx,
+        &read("Sample { voltage: V }"),
+        "missing column 'voltage'",
+    );
+
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn test_write_csv() {
+    let file = std::env::temp_dir().join(format!("numbat-write-csv-{}.csv", std::process::id()));
+    let path = file.to_string_lossy().replace('\\', "\\\\");
+
+    let mut ctx = get_test_context();
+    ctx.set_file_system_access(true);
+    let _ = ctx
+        .interpret(
+            "struct Measurement { name: String, time: Time, power: Power }
+             let data = [Measurement { name: \"a\", time: 1 min, power: 1 kW },
+                         Measurement { name: \"b, c\", time: 30 s, power: 500 W }]",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    expect_output_with_context(&mut ctx, &format!("write_csv(\"{path}\", data)"), "2");
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "name,time [min],power [kW]\na,1,1\n\"b, c\",0.5,0.5\n"
+    );
+    expect_output_with_context(
+        &mut ctx,
+        &format!(
+            "head(tail(read_csv(\"{path}\", Measurement {{ name: \"\", time: min, power: kW }}))).power -> W"
+        ),
+        "500 W",
+    );
+
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn test_text_files() {
+    let file = std::env::temp_dir().join(format!("numbat-text-files-{}.txt", std::process::id()));
+    let path = file.to_string_lossy().replace('\\', "\\\\");
+
+    let mut ctx = get_test_context();
+    expect_failure_with_context(
+        &mut ctx,
+        &format!("read_text(\"{path}\")"),
+        "'read_text' needs access to the file system",
+    );
+
+    ctx.set_file_system_access(true);
+    expect_output_with_context(
+        &mut ctx,
+        &format!("write_text(\"{path}\", \"E = {{2 kWh -> MJ}}\\n\")"),
+        "11",
+    );
+    expect_output_with_context(&mut ctx, &format!("append_text(\"{path}\", \"ok\")"), "2");
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "E = 7.2 MJ\nok");
+    expect_output_with_context(
+        &mut ctx,
+        &format!("read_text(\"{path}\")"),
+        "\"E = 7.2 MJ\\nok\"",
+    );
+
+    std::fs::remove_file(&file).unwrap();
+    expect_failure_with_context(
+        &mut ctx,
+        &format!("read_text(\"{path}\")"),
+        "Could not read file",
+    );
+}
+
+#[test]
+fn test_parse_quantity() {
+    expect_output("parse_quantity(\"3.5 km/h\", km/h)", "3.5 km/h");
+    expect_output("parse_quantity(\"2 ft + 6 in\", cm) -> cm", "76.2 cm");
+    expect_output("parse_quantity(\"-1.5e3 mW\", W) -> W", "-1.5 W");
+    expect_output("parse_quantity(\"42\", 1)", "42");
+    expect_output("parse_quantity_or(\"n/a\", 0 W) -> W", "0 W");
+    expect_output("parse_quantity_or(\"3 kg\", 0 W) -> W", "0 W");
+
+    expect_failure(
+        "parse_quantity(\"3 kg\", m)",
+        "Could not parse '3 kg' as a quantity: expected a quantity in units of 'm'",
+    );
+    expect_failure(
+        "parse_quantity(\"3 m\", 1)",
+        "expected a number without a unit",
+    );
+    expect_failure("parse_quantity(\"3 foo\", m)", "unknown unit 'foo'");
+    expect_failure("let x = 2 m\nparse_quantity(\"x\", m)", "unknown unit 'x'");
+    expect_failure(
+        "parse_quantity(\"sqrt(4) m\", m)",
+        "only numbers, units and arithmetic operators are allowed",
+    );
+}
+
+#[test]
+fn test_environment() {
+    std::env::set_var("NUMBAT_TEST_VARIABLE", "42 km");
+
+    let mut ctx = get_test_context();
+    expect_failure_with_context(
+        &mut ctx,
+        "env(\"NUMBAT_TEST_VARIABLE\")",
+        "'env' needs access to environment variables",
+    );
+    expect_output_with_context(&mut ctx, "args()", "[]");
+
+    ctx.set_environment_access(true);
+    expect_output_with_context(&mut ctx, "env(\"NUMBAT_TEST_VARIABLE\")", "\"42 km\"");
+    expect_output_with_context(
+        &mut ctx,
+        "env_or(\"NUMBAT_UNSET_TEST_VARIABLE\", \"default\")",
+        "\"default\"",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        "env(\"NUMBAT_UNSET_TEST_VARIABLE\")",
+        "Environment variable 'NUMBAT_UNSET_TEST_VARIABLE' is not set",
+    );
+
+    ctx.set_script_arguments(vec!["5".into(), "km".into()]);
+    expect_output_with_context(&mut ctx, "args()", "[\"5\", \"km\"]");
+}
+
+#[test]
+fn test_dicts() {
+    expect_output("{}", "{}");
+    expect_output("{\"b\": 2 m, \"a\": 30 cm}", "{\"b\": 2 m, \"a\": 30 cm}");
+    expect_output(
+        "dict_insert(\"c\", [3], {\"a\": [1, 2]})",
+        "{\"a\": [1, 2], \"c\": [3]}",
+    );
+
+    expect_failure(
+        "dict_get(\"c\", {\"a\": 1, \"b\": 2})",
+        "Key not found in dictionary: \"c\"",
+    );
+    expect_failure(
+        "{\"a\": 1 m, \"b\": 1 s}",
+        "Incompatible types in dictionary",
+    );
+    expect_failure("{1: 2}", "Dictionary keys need to be strings");
+
+    // `Dict` is only special in type annotations
+    expect_output("let Dict = 3\nDict + 1", "4");
+    expect_output("let lengths: Dict<Length> = {\"a\": 1 m}\nlengths", "{\"a\": 1 m}");
+}
+
+#[test]
+fn test_ranges() {
+    expect_output("1 ..= 3", "[1, 2, 3]");
+    expect_output("1 .. 3", "[1, 2]");
+    expect_output("3 ..= 1", "[]");
+    expect_output("0 m ..= 1 m step 50 cm", "[0 m, 0.5 m, 1 m]");
+    expect_output("0 m ..= 1 km step 500 m", "[0 m, 500 m, 1000 m]");
+    expect_output("len(1 ..= 10^12)", "1_000_000_000_000");
+    expect_output("(1 ..= 3) == [1, 2, 3]", "true");
+
+    // Huge ranges are never materialized
+    expect_output(
+        "1 ..= 10^12",
+        "[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, …, 1_000_000_000_000]",
+    );
+    expect_output("mean(1 ..= 10^12) == 500_000_000_000.5", "true");
+    expect_output(
+        "take(2, drop(10^11, 1 ..= 10^12))",
+        "[100_000_000_001, 100_000_000_002]",
+    );
+    expect_output("len(take(10^11, 1 ..= 10^12))", "100_000_000_000");
+
+    // `step` is not a keyword
+    expect_output(
+        "let step = 2
+1 ..= 5 step step",
+        "[1, 3, 5]",
+    );
+
+    expect_failure("1 ..= 3 step 0", "Invalid range: the step must not be zero");
+    expect_failure(
+        "1 ..= 10^20",
+        "Invalid range: the range has too many elements",
+    );
+    expect_failure("head(1 .. 1)", "Empty list");
+    expect_failure("1 m ..= 2 m", "needs an explicit 'step'");
+}
+
+#[test]
+fn test_relative_units() {
+    expect_output("100 m + 19 %", "119 m");
+    expect_output("2 kg - 5 %", "1.9 kg");
+    expect_output("100 g + 5 ‰", "100.5 g");
+    expect_output("2 + 5 %", "2.05");
+    expect_output("100 + 5 ‰", "100.005");
+    expect_output("2 m + 25 ppm", "2.00005 m");
+    expect_output("100 m + 10 % + 10 %", "121.0 m");
+    expect_output("5 % + 1 % -> %", "6 %");
+    expect_output("1 + 19 % × 2", "1.38");
+
+    expect_failure("19 % + 100 m", "right hand side: Length");
+
+    expect_output("15 % of 80 kg", "12 kg");
+    expect_output("use math::percentages\npercent_change(80 kg, 84 kg)", "5 %");
+    expect_failure("percent_change(80 kg, 84 kg)", "Unknown identifier");
+    expect_failure(
+        "2 m of 80 kg",
+        "The left hand side of 'of' needs to be a dimensionless fraction",
+    );
+}
+
+#[test]
+fn test_natural_units() {
+    expect_output(
+        "use physics::natural_units\n1 fm -> natural",
+        "\"5.06773 GeV⁻¹\"",
+    );
+    expect_output(
+        "use physics::natural_units\nnatural_convert(1 / GeV, fm)",
+        "0.197327 fm",
+    );
+    expect_failure(
+        "use physics::natural_units\nnatural_convert(1 GeV, A)",
+        "and 'Current' in natural units",
+    );
+
+    expect_output("use physics::natural_units\n1 GeV -> kg", "1.78266e-27 kg");
+    expect_output("use physics::natural_units\n1 / GeV -> fm", "0.197327 fm");
+    expect_output("use physics::natural_units\n2 m -> cm", "200 cm");
+    expect_failure(
+        "use physics::natural_units\n1 GeV -> A",
+        "right hand side: Current",
+    );
+    expect_failure("1 GeV -> kg", "right hand side: Mass");
+}
+
+#[test]
+fn test_to_json() {
+    expect_output(
+        "to_json([1.5 m, 2 cm])",
+        r#""[\{\"value\": 1.5, \"unit\": \"m\"\}, \{\"value\": 2, \"unit\": \"cm\"\}]""#,
+    );
+    expect_output(
+        "struct Point { label: String, x: Length }
+         to_json(Point { label: \"A\", x: 2 km })",
+        r#""\{\"label\": \"A\", \"x\": \{\"value\": 2, \"unit\": \"km\"\}\}""#,
+    );
+    expect_output("to_json([true, false])", r#""[true, false]""#);
+    expect_output(
+        "to_json({\"a\": 1 m, \"b\": 2 m})",
+        r#""\{\"a\": \{\"value\": 1, \"unit\": \"m\"\}, \"b\": \{\"value\": 2, \"unit\": \"m\"\}\}""#,
+    );
+}
+
+#[cfg(feature = "datetime")]
+#[test]
+fn test_parse_json() {
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret(
+            "struct Item { name: String, price: Money, in_stock: Bool }
+             struct Inventory { updated: DateTime, items: List<Item> }",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    let parse = |json: &str| {
+        format!(
+            r#"parse_json("{json}", Inventory {{ updated: now(), items: [Item {{ name: "", price: EUR, in_stock: false }}] }})"#
+        )
+    };
+    let json = r#"\{\"updated\": \"2024-06-01T12:00:00Z\", \"items\": [\{\"name\": \"Tea\", \"price\": 3.5, \"in_stock\": true\}, \{\"name\": \"Cake\", \"price\": null, \"in_stock\": false\}]\}"#;
+
+    let _ = ctx
+        .interpret(
+            &format!("let inventory = {}", parse(json)),
+            CodeSource::Internal,
+        )
+        .unwrap();
+    expect_output_with_context(&mut ctx, "len(inventory.items)", "2");
+    expect_output_with_context(&mut ctx, "head(inventory.items).price == 3.5 EUR", "true");
+    expect_output_with_context(&mut ctx, "head(inventory.items).in_stock", "true");
+    expect_output_with_context(
+        &mut ctx,
+        "is_nan(head(tail(inventory.items)).price)",
+        "true",
+    );
+
+    expect_failure_with_context(
+        &mut ctx,
+        &parse(r#"\{\"updated\": \"2024-06-01T12:00:00Z\", \"items\": [\{\"name\": 1\}]\}"#),
+        "expected a string at '$.items[0].name', got a number",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        &parse(r#"\{\"items\": []\}"#),
+        "missing field '$.updated'",
+    );
+    expect_failure("parse_json(\"[1, 2\", [1])", "Invalid JSON data");
+    expect_failure(
+        "parse_json(\"[1, 2]\", [1, 2])",
+        "the schema for '$' needs to be a list with a single element",
+    );
+    expect_output(
+        r#"parse_json("\{\"x\": 2, \"y\": 3\}", {"": 1 m})"#,
+        r#"{"x": 2 m, "y": 3 m}"#,
+    );
+    expect_failure(
+        r#"parse_json("\{\"x\": 2\}", {})"#,
+        "the schema for '$' needs to be a dictionary with a single entry",
+    );
+}
+
+#[test]
+fn test_fetch_json_needs_network_access() {
+    expect_failure(
+        "fetch_json(\"https://numbat.dev/\", 1)",
+        "'fetch_json' needs network access",
+    );
+}
+
+#[test]
+fn test_random_seed() {
+    let sample = |seed| {
+        let mut ctx = get_test_context();
+        ctx.set_random_seed(seed);
+        ctx.interpret(
+            "[random(), rand_int(1, 100), rand_norm(0, 1), rand_expon(2), rand_poisson(3)]",
+            CodeSource::Internal,
+        )
+        .unwrap()
+        .1
+    };
+
+    assert_eq!(sample(42), sample(42));
+    assert_ne!(sample(42), sample(43));
+}
+
+#[test]
+fn test_random_numbers_of_cloned_contexts() {
+    let sample = |ctx: &mut Context| ctx.interpret("random()", CodeSource::Internal).unwrap().1;
+
+    // Clones do not share the sequence of random numbers …
+    let mut ctx = get_test_context();
+    let mut clone = ctx.clone();
+    assert_ne!(sample(&mut ctx), sample(&mut clone));
+
+    // … unless a seed has been set
+    ctx.set_random_seed(42);
+    let mut clone = ctx.clone();
+    assert_eq!(sample(&mut ctx), sample(&mut clone));
+}
@@ -3,7 +3,7 @@ mod common;
 use common::get_test_context;
 
 use numbat::resolver::{CodeSource, ResolverError};
-use numbat::{InterpreterResult, NumbatError};
+use numbat::{InterpreterResult, NumbatError, Warning};
 
 use std::ffi::OsStr;
 use std::fs;
@@ -11,7 +11,10 @@ use std::fs;
 use crate::common::get_test_context_without_prelude;
 
 fn assert_runs(code: &str) {
-    let result = get_test_context().interpret(code, CodeSource::Internal);
+    let mut ctx = get_test_context();
+    // Use a fixed seed, so that examples with random numbers are reproducible
+    ctx.set_random_seed(42);
+    let result = ctx.interpret(code, CodeSource::Internal);
     assert!(result.is_ok(), "Failed with: {result:#?}");
     assert!(matches!(
         result.unwrap().1,
@@ -77,11 +80,31 @@ fn modules_are_self_consistent() {
     run_for_each_file("modules/**/*.nbt", assert_runs_without_prelude);
 }
 
+#[test]
+fn modules_have_no_dead_code() {
+    let warnings = get_test_context_without_prelude()
+        .check("use all", CodeSource::Internal)
+        .unwrap();
+    let dead_code: Vec<_> = warnings
+        .iter()
+        .filter(|w| {
+            matches!(
+                w,
+                Warning::Unused { .. } | Warning::UnreachableBranch { .. }
+            )
+        })
+        .map(|w| w.to_string())
+        .collect();
+    assert!(dead_code.is_empty(), "Found dead code: {dead_code:#?}");
+}
+
 #[test]
 fn examples_can_be_parsed_and_interpreted() {
     run_for_each_file("../examples/*.nbt", assert_runs);
 }
 
+// The tests cover the functionality of all optional features
+#[cfg(all(feature = "datetime", feature = "regex", feature = "chemical-elements"))]
 #[test]
 fn numbat_tests_are_executed_successfully() {
     run_for_each_file("../examples/tests/*.nbt", assert_runs);